@@ -1,12 +1,12 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_faucet::mint;
+use diem_faucet::{mint, rate_limit::RateLimit};
 use diem_logger::prelude::info;
 use diem_sdk::types::chain_id::ChainId;
-use std::fmt;
+use std::{fmt, net::SocketAddr, time::Duration};
 use structopt::StructOpt;
-use warp::Filter;
+use warp::{http::StatusCode, Filter};
 
 #[derive(Debug, StructOpt)]
 #[structopt(
@@ -37,6 +37,20 @@ struct Args {
     /// Note: Chain ID of 0 is not allowed; Use number if chain id is not predefined.
     #[structopt(short = "c", long, default_value = "2")]
     pub chain_id: ChainId,
+    /// Comma separated codes of the currencies minted by the faucet.
+    #[structopt(long, default_value = "XDX,XUS")]
+    pub currencies: mint::Currencies,
+    /// Maximum number of requests from a same IP address per rate limit period.
+    /// Unlimited if not set.
+    #[structopt(long)]
+    pub max_requests_per_ip: Option<u32>,
+    /// Maximum number of requests to fund a same account per rate limit period.
+    /// Unlimited if not set.
+    #[structopt(long)]
+    pub max_requests_per_account: Option<u32>,
+    /// Period of the rate limits, in seconds.
+    #[structopt(long, default_value = "3600")]
+    pub rate_limit_period_secs: u64,
 }
 
 #[tokio::main]
@@ -53,11 +67,16 @@ async fn main() {
         args.chain_id,
         args.server_url.as_str(),
     );
-    let service = std::sync::Arc::new(mint::Service::new(
-        args.server_url,
-        args.chain_id,
-        args.mint_key_file_path,
-    ));
+    let period = Duration::from_secs(args.rate_limit_period_secs);
+    let mut service = mint::Service::new(args.server_url, args.chain_id, args.mint_key_file_path)
+        .with_currencies(args.currencies);
+    if let Some(max_requests) = args.max_requests_per_ip {
+        service = service.with_ip_rate_limit(RateLimit::new(max_requests, period));
+    }
+    if let Some(max_requests) = args.max_requests_per_account {
+        service = service.with_account_rate_limit(RateLimit::new(max_requests, period));
+    }
+    let service = std::sync::Arc::new(service);
 
     info!("[faucet]: running on: {}", address);
     warp::serve(routes(service)).run(address).await;
@@ -66,9 +85,21 @@ async fn main() {
 fn routes(
     service: std::sync::Arc<mint::Service>,
 ) -> warp::filters::BoxedFilter<(impl warp::Reply,)> {
-    let mint = warp::any()
+    // POST /?amount=25&auth_key=xxx&currency_code=XXX
+    let route_root = warp::path::end().map(|| false);
+    // POST /mint?amount=25&auth_key=xxx&currency_code=XXX
+    let route_mint = warp::path!("mint").map(|| false);
+    // POST /mint/dry-run?amount=25&auth_key=xxx&currency_code=XXX
+    let route_dry_run = warp::path!("mint" / "dry-run").map(|| true);
+
+    let mint = route_dry_run
+        .or(route_mint)
+        .unify()
+        .or(route_root)
+        .unify()
         .and(warp::post())
         .and(warp::any().map(move || std::sync::Arc::clone(&service)))
+        .and(warp::addr::remote())
         .and(warp::query().map(move |params: mint::MintParams| params))
         .and_then(handle)
         .with(warp::log::custom(|info| {
@@ -86,20 +117,29 @@ fn routes(
         }))
         .with(warp::cors().allow_any_origin().allow_methods(vec!["POST"]));
 
-    // POST /?amount=25&auth_key=xxx&currency_code=XXX
-    let route_root = warp::path::end().and(mint.clone());
-    // POST /mint?amount=25&auth_key=xxx&currency_code=XXX
-    let route_mint = warp::path::path("mint").and(warp::path::end()).and(mint);
-
     let health = warp::path!("-" / "healthy").map(|| "diem-faucet:ok");
-    health.or(route_mint.or(route_root)).boxed()
+    health.or(mint).boxed()
 }
 
 async fn handle(
+    dry_run: bool,
     service: std::sync::Arc<mint::Service>,
+    remote_addr: Option<SocketAddr>,
     params: mint::MintParams,
 ) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
-    match service.process(params).await {
+    if let Err(rejection) = service.admit(&params, remote_addr.map(|addr| addr.ip())) {
+        let status = match rejection {
+            mint::Rejection::UnsupportedCurrency(_) => StatusCode::BAD_REQUEST,
+            mint::Rejection::IpRateLimited(_) | mint::Rejection::AccountRateLimited(_) => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+        };
+        return Ok(Box::new(warp::reply::with_status(
+            rejection.to_string(),
+            status,
+        )));
+    }
+    match service.process(params, dry_run).await {
         Ok(body) => Ok(Box::new(body.to_string())),
         Err(err) => Err(warp::reject::custom(ServerInternalError(err.to_string()))),
     }
@@ -125,9 +165,10 @@ impl warp::reject::Reject for ServerInternalError {}
 #[cfg(test)]
 mod tests {
     use crate::routes;
-    use diem_faucet::mint;
+    use diem_faucet::{mint, rate_limit::RateLimit};
     use diem_infallible::RwLock;
     use diem_sdk::{
+        move_types::language_storage::TypeTag,
         transaction_builder::stdlib::{ScriptCall, ScriptFunctionCall},
         types::{
             account_address::AccountAddress,
@@ -140,12 +181,18 @@ mod tests {
             },
         },
     };
-    use std::{collections::HashMap, convert::TryFrom, sync::Arc};
-    use warp::Filter;
+    use std::{collections::HashMap, convert::TryFrom, sync::Arc, time::Duration};
+    use warp::{http::StatusCode, Filter};
 
     fn setup(
         accounts: Arc<RwLock<HashMap<AccountAddress, serde_json::Value>>>,
     ) -> Arc<mint::Service> {
+        Arc::new(setup_service(accounts))
+    }
+
+    fn setup_service(
+        accounts: Arc<RwLock<HashMap<AccountAddress, serde_json::Value>>>,
+    ) -> mint::Service {
         let f = tempfile::NamedTempFile::new()
             .unwrap()
             .into_temp_path()
@@ -164,12 +211,11 @@ mod tests {
         let future = warp::serve(stub).bind(([127, 0, 0, 1], port));
         tokio::task::spawn(async move { future.await });
 
-        let service = mint::Service::new(
+        mint::Service::new(
             format!("http://localhost:{}/v1", port),
             chain_id,
             f.to_str().unwrap().to_owned(),
-        );
-        Arc::new(service)
+        )
     }
 
     #[tokio::test]
@@ -288,6 +334,148 @@ mod tests {
         assert_eq!(account["role"]["type"], "designated_dealer");
     }
 
+    #[tokio::test]
+    async fn test_mint_multiple_currencies() {
+        let accounts = genesis_accounts();
+        let service = setup(accounts.clone());
+        let filter = routes(service);
+
+        let auth_key = "459c77a38803bd53f3adee52703810e3a74fd7c46952c497e75afb0a7932586d";
+        let amount = 13345;
+        let resp = warp::test::request()
+            .method("POST")
+            .path(
+                format!(
+                    "/mint?auth_key={}&amount={}&currency_code=XDX,XUS&return_txns=true",
+                    auth_key, amount
+                )
+                .as_str(),
+            )
+            .reply(&filter)
+            .await;
+        let txns: Vec<SignedTransaction> =
+            bcs::from_bytes(&hex::decode(resp.body()).expect("hex encoded response body"))
+                .expect("valid bcs vec");
+        // One transaction to create the account, and one transfer per currency
+        assert_eq!(txns.len(), 3);
+
+        let reader = accounts.read();
+        let addr = AccountAddress::try_from("a74fd7c46952c497e75afb0a7932586d".to_owned()).unwrap();
+        let account = reader.get(&addr).expect("account should be created");
+        assert_eq!(account["balances"][0]["currency"], "XDX");
+        assert_eq!(account["balances"][0]["amount"], amount);
+        assert_eq!(account["balances"][1]["currency"], "XUS");
+        assert_eq!(account["balances"][1]["amount"], amount);
+    }
+
+    #[tokio::test]
+    async fn test_mint_unsupported_currency() {
+        let accounts = genesis_accounts();
+        let service = setup_service(accounts).with_currencies("XUS".parse().unwrap());
+        let filter = routes(Arc::new(service));
+
+        let auth_key = "459c77a38803bd53f3adee52703810e3a74fd7c46952c497e75afb0a7932586d";
+        let resp = warp::test::request()
+            .method("POST")
+            .path(format!("/mint?auth_key={}&amount=1&currency_code=XUS,XDX", auth_key).as_str())
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(resp.body(), "currency XDX is not minted by this faucet");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() {
+        let accounts = genesis_accounts();
+        let service = setup(accounts.clone());
+        let filter = routes(service);
+
+        let auth_key = "459c77a38803bd53f3adee52703810e3a74fd7c46952c497e75afb0a7932586d";
+        let addr = AccountAddress::try_from("a74fd7c46952c497e75afb0a7932586d".to_owned()).unwrap();
+        let resp = warp::test::request()
+            .method("POST")
+            .path(
+                format!(
+                    "/mint/dry-run?auth_key={}&amount=1&currency_code=XDX",
+                    auth_key
+                )
+                .as_str(),
+            )
+            .reply(&filter)
+            .await;
+        let txns: Vec<SignedTransaction> =
+            bcs::from_bytes(&hex::decode(resp.body()).expect("hex encoded response body"))
+                .expect("valid bcs vec");
+        assert_eq!(txns.len(), 2);
+        assert!(accounts.read().get(&addr).is_none());
+
+        // The sequence numbers used by the dry run are still available
+        let resp = warp::test::request()
+            .method("POST")
+            .path(format!("/mint?auth_key={}&amount=1&currency_code=XDX", auth_key).as_str())
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.body(), "1");
+        assert!(accounts.read().get(&addr).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_account_rate_limit() {
+        let accounts = genesis_accounts();
+        let service = setup_service(accounts)
+            .with_account_rate_limit(RateLimit::new(1, Duration::from_secs(3600)));
+        let filter = routes(Arc::new(service));
+
+        let auth_key = "459c77a38803bd53f3adee52703810e3a74fd7c46952c497e75afb0a7932586d";
+        let path = format!("/mint?auth_key={}&amount=1&currency_code=XDX", auth_key);
+        let resp = warp::test::request()
+            .method("POST")
+            .path(path.as_str())
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = warp::test::request()
+            .method("POST")
+            .path(path.as_str())
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_ip_rate_limit() {
+        let accounts = genesis_accounts();
+        let service = setup_service(accounts)
+            .with_ip_rate_limit(RateLimit::new(1, Duration::from_secs(3600)));
+        let filter = routes(Arc::new(service));
+
+        let remote_addr = ([10, 0, 0, 1], 1234).into();
+        let resp = warp::test::request()
+            .method("POST")
+            .remote_addr(remote_addr)
+            .path(
+                "/mint?auth_key=459c77a38803bd53f3adee52703810e3a74fd7c46952c497e75afb0a7932586d\
+                &amount=1&currency_code=XDX",
+            )
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // The limit applies to requests for any account
+        let resp = warp::test::request()
+            .method("POST")
+            .remote_addr(remote_addr)
+            .path(
+                "/mint?auth_key=44b8f03f203ec45dbd7484e433752efe54aa533116e934f8a50c28bece06d3ac\
+                &amount=1&currency_code=XDX",
+            )
+            .reply(&filter)
+            .await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.body(), "too many requests from 10.0.0.1");
+    }
+
     #[tokio::test]
     async fn test_mint_invalid_auth_key() {
         let accounts = genesis_accounts();
@@ -452,12 +640,12 @@ mod tests {
                                 writer.insert(address, create_dd_account(&address.to_string(), 0));
                             assert!(previous.is_none(), "should not create account twice");
                         }
-                        Some(ScriptCall::PeerToPeerWithMetadata { payee, amount, .. }) => {
-                            let mut writer = accounts.write();
-                            let account =
-                                writer.get_mut(&payee).expect("account should be created");
-                            account["balances"][0]["amount"] = serde_json::json!(amount);
-                        }
+                        Some(ScriptCall::PeerToPeerWithMetadata {
+                            currency,
+                            payee,
+                            amount,
+                            ..
+                        }) => set_balance(&accounts, payee, &currency, amount),
                         _ => panic!("unexpected type of script"),
                     }
                 }
@@ -516,12 +704,12 @@ mod tests {
                                 writer.insert(address, create_dd_account(&address.to_string(), 0));
                             assert!(previous.is_none(), "should not create account twice");
                         }
-                        ScriptFunctionCall::PeerToPeerWithMetadata { payee, amount, .. } => {
-                            let mut writer = accounts.write();
-                            let account =
-                                writer.get_mut(&payee).expect("account should be created");
-                            account["balances"][0]["amount"] = serde_json::json!(amount);
-                        }
+                        ScriptFunctionCall::PeerToPeerWithMetadata {
+                            currency,
+                            payee,
+                            amount,
+                            ..
+                        } => set_balance(&accounts, payee, &currency, amount),
                         script => panic!("unexpected type of script: {:?}", script),
                     }
                 }
@@ -539,6 +727,31 @@ mod tests {
         }
     }
 
+    fn set_balance(
+        accounts: &RwLock<HashMap<AccountAddress, serde_json::Value>>,
+        address: AccountAddress,
+        currency: &TypeTag,
+        amount: u64,
+    ) {
+        let currency = match currency {
+            TypeTag::Struct(tag) => tag.name.to_string(),
+            _ => panic!("unexpected currency type: {:?}", currency),
+        };
+        let mut writer = accounts.write();
+        let account = writer.get_mut(&address).expect("account should be created");
+        let balances = account["balances"].as_array_mut().unwrap();
+        match balances
+            .iter_mut()
+            .find(|balance| balance["currency"] == currency.as_str())
+        {
+            Some(balance) => balance["amount"] = serde_json::json!(amount),
+            None => balances.push(serde_json::json!({
+                "amount": amount,
+                "currency": currency,
+            })),
+        }
+    }
+
     fn create_response(
         id: &serde_json::Value,
         chain_id: u8,
@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::rate_limit::{RateLimit, RateLimiter};
 use anyhow::Result;
 use diem_logger::prelude::warn;
 use diem_sdk::{
@@ -14,30 +15,107 @@ use diem_sdk::{
         LocalAccount,
     },
 };
-use serde::Deserialize;
-use std::{fmt, sync::Mutex};
+use serde::{
+    de::{Error as _, IntoDeserializer},
+    Deserialize, Deserializer,
+};
+use std::{fmt, net::IpAddr, str::FromStr, sync::Mutex};
 
 #[derive(Debug)]
 pub enum Response {
     DDAccountNextSeqNum(u64),
     SubmittedTxns(Vec<SignedTransaction>),
+    DryRunTxns(Vec<SignedTransaction>),
 }
 
 impl std::fmt::Display for Response {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Response::DDAccountNextSeqNum(v1) => write!(f, "{}", v1),
-            Response::SubmittedTxns(v2) => {
+            Response::SubmittedTxns(v2) | Response::DryRunTxns(v2) => {
                 write!(f, "{}", hex::encode(bcs::to_bytes(&v2).unwrap()))
             }
         }
     }
 }
 
+/// Why a mint request is refused before any transaction is built.
+#[derive(Debug)]
+pub enum Rejection {
+    /// The faucet is not configured to mint this currency.
+    UnsupportedCurrency(Currency),
+    /// Too many requests were made from the IP address of the client.
+    IpRateLimited(IpAddr),
+    /// Too many requests were made to fund the account.
+    AccountRateLimited(AccountAddress),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::UnsupportedCurrency(currency) => {
+                write!(f, "currency {} is not minted by this faucet", currency)
+            }
+            Rejection::IpRateLimited(ip) => write!(f, "too many requests from {}", ip),
+            Rejection::AccountRateLimited(address) => {
+                write!(f, "too many requests for account {}", address)
+            }
+        }
+    }
+}
+
+/// A non-empty list of distinct currencies, written as comma separated currency codes, e.g.
+/// `XDX,XUS`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Currencies(Vec<Currency>);
+
+impl Currencies {
+    /// The first currency of the list.
+    pub fn primary(&self) -> Currency {
+        self.0[0]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Currency> {
+        self.0.iter()
+    }
+
+    pub fn contains(&self, currency: &Currency) -> bool {
+        self.0.contains(currency)
+    }
+}
+
+impl FromStr for Currencies {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut currencies = vec![];
+        for code in s.split(',') {
+            let currency = Currency::deserialize(code.trim().into_deserializer()).map_err(
+                |e: serde::de::value::Error| {
+                    anyhow::format_err!("invalid currency code {:?}: {}", code, e)
+                },
+            )?;
+            if !currencies.contains(&currency) {
+                currencies.push(currency);
+            }
+        }
+        Ok(Currencies(currencies))
+    }
+}
+
+impl<'de> Deserialize<'de> for Currencies {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
 #[derive(Deserialize)]
 pub struct MintParams {
     pub amount: u64,
-    pub currency_code: Currency,
+    /// `amount` is minted in each of the currencies.
+    pub currency_code: Currencies,
     pub auth_key: AuthenticationKey,
     pub return_txns: Option<bool>,
     pub is_designated_dealer: Option<bool>,
@@ -82,6 +160,9 @@ pub struct Service {
     dd_account: Mutex<LocalAccount>,
     transaction_factory: TransactionFactory,
     client: Client,
+    currencies: Currencies,
+    ip_rate_limiter: Option<RateLimiter<IpAddr>>,
+    account_rate_limiter: Option<RateLimiter<AccountAddress>>,
 }
 
 impl Service {
@@ -103,10 +184,57 @@ impl Service {
             transaction_factory: TransactionFactory::new(chain_id)
                 .with_transaction_expiration_time(30),
             client,
+            currencies: Currencies(vec![Currency::XDX, Currency::XUS]),
+            ip_rate_limiter: None,
+            account_rate_limiter: None,
         }
     }
 
-    pub async fn process(&self, mut params: MintParams) -> Result<Response> {
+    /// Sets the currencies the faucet mints, all of them by default.
+    pub fn with_currencies(mut self, currencies: Currencies) -> Self {
+        self.currencies = currencies;
+        self
+    }
+
+    /// Limits the requests from a same IP address, unlimited by default.
+    pub fn with_ip_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.ip_rate_limiter = Some(RateLimiter::new(rate_limit));
+        self
+    }
+
+    /// Limits the requests to fund a same account, unlimited by default.
+    pub fn with_account_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.account_rate_limiter = Some(RateLimiter::new(rate_limit));
+        self
+    }
+
+    /// Checks that the requested currencies are minted by the faucet, and counts the request
+    /// against the rate limits of the client IP address, if known, and of the receiver account.
+    pub fn admit(&self, params: &MintParams, remote_ip: Option<IpAddr>) -> Result<(), Rejection> {
+        if let Some(currency) = params
+            .currency_code
+            .iter()
+            .find(|currency| !self.currencies.contains(currency))
+        {
+            return Err(Rejection::UnsupportedCurrency(*currency));
+        }
+        if let (Some(limiter), Some(ip)) = (&self.ip_rate_limiter, remote_ip) {
+            if !limiter.try_acquire(ip) {
+                return Err(Rejection::IpRateLimited(ip));
+            }
+        }
+        if let Some(limiter) = &self.account_rate_limiter {
+            if !limiter.try_acquire(params.receiver()) {
+                return Err(Rejection::AccountRateLimited(params.receiver()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds and submits the transactions creating the receiver account if needed, and
+    /// funding it with each requested currency. With `dry_run`, the transactions are returned
+    /// without being submitted.
+    pub async fn process(&self, mut params: MintParams, dry_run: bool) -> Result<Response> {
         let (tc_seq, dd_seq, receiver_seq) = self.sequences(params.receiver()).await?;
         let txns = {
             let mut treasury_account = self.treasury_account.lock().unwrap();
@@ -118,26 +246,31 @@ impl Service {
                 *treasury_account.sequence_number_mut() = tc_seq;
             }
             if dd_seq > dd_account.sequence_number() {
-                *dd_account.sequence_number_mut() = tc_seq;
+                *dd_account.sequence_number_mut() = dd_seq;
             }
+            let treasury_next_seq = treasury_account.sequence_number();
+            let dd_next_seq = dd_account.sequence_number();
+
+            // New accounts get a balance in all currencies when more than one is minted
+            let add_all_currencies = params.currency_code.iter().count() > 1;
 
             let mut txns = vec![];
             if receiver_seq.is_none() {
                 let builder = if params.is_designated_dealer.unwrap_or(false) {
                     self.transaction_factory.create_designated_dealer(
-                        params.currency_code,
+                        params.currency_code.primary(),
                         0, // sliding_nonce
                         params.auth_key,
                         &format!("No. {} DD", treasury_account.sequence_number()),
-                        false, // add all currencies
+                        add_all_currencies,
                     )
                 } else {
                     self.transaction_factory.create_parent_vasp_account(
-                        params.currency_code,
+                        params.currency_code.primary(),
                         0, // sliding_nonce
                         params.auth_key,
                         &format!("No. {} VASP", treasury_account.sequence_number()),
-                        false, // add all currencies
+                        add_all_currencies,
                     )
                 };
 
@@ -161,17 +294,29 @@ impl Service {
                 txns.push(treasury_account.sign_with_transaction_builder(builder));
             }
 
-            txns.push(dd_account.sign_with_transaction_builder(
-                self.transaction_factory.peer_to_peer_with_metadata(
-                    params.currency_code,
-                    params.receiver(),
-                    params.amount,
-                    params.bcs_metadata(),
-                    vec![],
-                ),
-            ));
+            let metadata = params.bcs_metadata();
+            for currency in params.currency_code.iter() {
+                txns.push(dd_account.sign_with_transaction_builder(
+                    self.transaction_factory.peer_to_peer_with_metadata(
+                        *currency,
+                        params.receiver(),
+                        params.amount,
+                        metadata.clone(),
+                        vec![],
+                    ),
+                ));
+            }
+
+            // Nothing is submitted, so the sequence numbers used are still available
+            if dry_run {
+                *treasury_account.sequence_number_mut() = treasury_next_seq;
+                *dd_account.sequence_number_mut() = dd_next_seq;
+            }
             txns
         };
+        if dry_run {
+            return Ok(Response::DryRunTxns(txns));
+        }
 
         let batch = txns
             .iter()
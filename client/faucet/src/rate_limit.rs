@@ -0,0 +1,142 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-memory token buckets limiting how often a same client can be served.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Number of buckets above which the full ones are dropped, as they are equivalent to no bucket.
+const MAX_BUCKETS: usize = 100_000;
+
+/// Allows `max_requests` per `period`, in bursts of at most `max_requests`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    max_requests: u32,
+    period: Duration,
+}
+
+impl RateLimit {
+    pub fn new(max_requests: u32, period: Duration) -> Self {
+        assert!(
+            period > Duration::from_secs(0),
+            "rate limit period must not be zero"
+        );
+        Self {
+            max_requests,
+            period,
+        }
+    }
+
+    pub fn max_requests(&self) -> u32 {
+        self.max_requests
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    fn capacity(&self) -> f64 {
+        self.max_requests as f64
+    }
+
+    fn refill(&self, elapsed: Duration) -> f64 {
+        elapsed.as_secs_f64() * self.capacity() / self.period.as_secs_f64()
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// A token bucket per key, refilled continuously at the rate of the limit.
+pub struct RateLimiter<K> {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<K, Bucket>>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Takes a token from the bucket of `key`. Returns false, without taking any token, if the
+    /// bucket is empty.
+    pub fn try_acquire(&self, key: K) -> bool {
+        self.try_acquire_at(key, Instant::now())
+    }
+
+    fn try_acquire_at(&self, key: K, now: Instant) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_BUCKETS {
+            let limit = self.limit;
+            buckets.retain(|_, bucket| {
+                bucket.tokens + limit.refill(now.saturating_duration_since(bucket.last_refill))
+                    < limit.capacity()
+            });
+        }
+
+        let bucket = buckets.entry(key).or_insert_with(|| Bucket {
+            tokens: self.limit.capacity(),
+            last_refill: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + self.limit.refill(elapsed)).min(self.limit.capacity());
+        bucket.last_refill = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_refill() {
+        let limiter = RateLimiter::new(RateLimit::new(2, Duration::from_secs(60)));
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire_at("a", now));
+        assert!(limiter.try_acquire_at("a", now));
+        assert!(!limiter.try_acquire_at("a", now));
+        // Other keys have their own bucket
+        assert!(limiter.try_acquire_at("b", now));
+
+        // A token is added every 30 seconds
+        assert!(!limiter.try_acquire_at("a", now + Duration::from_secs(29)));
+        assert!(limiter.try_acquire_at("a", now + Duration::from_secs(30)));
+        assert!(!limiter.try_acquire_at("a", now + Duration::from_secs(30)));
+
+        // Buckets never hold more than the limit
+        let later = now + Duration::from_secs(3600);
+        assert!(limiter.try_acquire_at("a", later));
+        assert!(limiter.try_acquire_at("a", later));
+        assert!(!limiter.try_acquire_at("a", later));
+    }
+
+    #[test]
+    fn test_no_requests_allowed() {
+        let limiter = RateLimiter::new(RateLimit::new(0, Duration::from_secs(60)));
+        let now = Instant::now();
+
+        assert!(!limiter.try_acquire_at("a", now));
+        assert!(!limiter.try_acquire_at("a", now + Duration::from_secs(3600)));
+    }
+}
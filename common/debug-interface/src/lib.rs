@@ -87,6 +87,36 @@ impl NodeDebugClient {
 
         Ok(response.json()?)
     }
+
+    /// Retrieves the summary of the transactions in mempool and of the latest evictions, or
+    /// `null` if mempool isn't running.
+    pub fn get_mempool_summary(&self) -> Result<serde_json::Value> {
+        let mut url = self.url.clone();
+        url.set_path("mempool");
+        let response = self.client.get(url).send()?;
+
+        Ok(response.json()?)
+    }
+
+    /// Retrieves the latest consensus rounds that timed out, with the proposer and the voting
+    /// power seen in each, or `null` if consensus isn't running.
+    pub fn get_consensus_timeouts(&self) -> Result<serde_json::Value> {
+        let mut url = self.url.clone();
+        url.set_path("consensus/timeouts");
+        let response = self.client.get(url).send()?;
+
+        Ok(response.json()?)
+    }
+
+    /// Retrieves the round trip time, role and synced version of the connected peers, as
+    /// monitored by the node.
+    pub fn get_peers(&self) -> Result<serde_json::Value> {
+        let mut url = self.url.clone();
+        url.set_path("peers");
+        let response = self.client.get(url).send()?;
+
+        Ok(response.json()?)
+    }
 }
 
 /// Implement default utility client for AsyncNodeDebugInterface
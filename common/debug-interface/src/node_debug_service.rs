@@ -3,14 +3,31 @@
 
 //! Debug interface to access information in a specific node.
 
+use diem_infallible::RwLock;
 use diem_logger::{info, json_log, Filter, Logger};
 use std::{net::SocketAddr, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
 use warp::Filter as _;
 
-#[derive(Debug)]
+/// Produces the JSON served by a route, e.g. `GET /mempool`.
+pub type JsonProvider = Box<dyn Fn() -> serde_json::Value + Send + Sync>;
+
+/// The provider of a route, set once the component serving it is running.
+type JsonProviderSlot = Arc<RwLock<Option<JsonProvider>>>;
+
+fn reply_from(slot: &JsonProviderSlot) -> warp::reply::Json {
+    match &*slot.read() {
+        Some(provider) => warp::reply::json(&provider()),
+        // The component isn't running yet, or at all
+        None => warp::reply::json(&serde_json::Value::Null),
+    }
+}
+
 pub struct NodeDebugService {
     runtime: Runtime,
+    mempool_summary: JsonProviderSlot,
+    consensus_timeouts: JsonProviderSlot,
+    peers: JsonProviderSlot,
 }
 
 impl NodeDebugService {
@@ -28,6 +45,27 @@ impl NodeDebugService {
         // GET /events
         let events = warp::path("events").map(|| warp::reply::json(&json_log::pop_last_entries()));
 
+        // GET /mempool
+        let mempool_summary = JsonProviderSlot::default();
+        let mempool = {
+            let mempool_summary = mempool_summary.clone();
+            warp::path("mempool").map(move || reply_from(&mempool_summary))
+        };
+
+        // GET /consensus/timeouts
+        let consensus_timeouts = JsonProviderSlot::default();
+        let consensus = {
+            let consensus_timeouts = consensus_timeouts.clone();
+            warp::path!("consensus" / "timeouts").map(move || reply_from(&consensus_timeouts))
+        };
+
+        // GET /peers
+        let peers = JsonProviderSlot::default();
+        let peers_route = {
+            let peers = peers.clone();
+            warp::path("peers").map(move || reply_from(&peers))
+        };
+
         // Post /log/filter
         let local_filter = {
             let logger = logger.clone();
@@ -65,13 +103,36 @@ impl NodeDebugService {
             .and(warp::path("log"))
             .and(local_filter.or(remote_filter));
 
-        let routes = log.or(warp::get().and(metrics.or(events)));
+        let routes =
+            log.or(warp::get().and(metrics.or(events).or(mempool).or(consensus).or(peers_route)));
 
         runtime
             .handle()
             .spawn(async move { warp::serve(routes).bind(address).await });
 
-        Self { runtime }
+        Self {
+            runtime,
+            mempool_summary,
+            consensus_timeouts,
+            peers,
+        }
+    }
+
+    /// Serves the summary produced by `provider` on `GET /mempool`, once mempool is running.
+    pub fn set_mempool_summary_provider(&self, provider: JsonProvider) {
+        *self.mempool_summary.write() = Some(provider);
+    }
+
+    /// Serves the rounds produced by `provider` on `GET /consensus/timeouts`, once consensus is
+    /// running.
+    pub fn set_consensus_timeouts_provider(&self, provider: JsonProvider) {
+        *self.consensus_timeouts.write() = Some(provider);
+    }
+
+    /// Serves the monitored peers produced by `provider` on `GET /peers`, once networking is
+    /// running.
+    pub fn set_peers_provider(&self, provider: JsonProvider) {
+        *self.peers.write() = Some(provider);
     }
 
     pub fn runtime(&self) -> &Runtime {
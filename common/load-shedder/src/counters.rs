@@ -0,0 +1,25 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_metrics::{register_int_counter_vec, register_int_gauge_vec, IntCounterVec, IntGaugeVec};
+use once_cell::sync::Lazy;
+
+/// Admission decisions taken by the load shedder, per inbound path
+pub static ADMISSION_DECISIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_load_shedder_admission_decisions",
+        "Number of admission decisions taken by the load shedder",
+        &["path", "decision"]
+    )
+    .unwrap()
+});
+
+/// Smoothed latency (in microseconds) of the monitored latency sources
+pub static SMOOTHED_LATENCY_US: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_load_shedder_smoothed_latency_us",
+        "Exponentially weighted moving average of the latency of a monitored source",
+        &["source"]
+    )
+    .unwrap()
+});
@@ -0,0 +1,346 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! Admission control for low-priority inbound work.
+//!
+//! Latency-critical components (block execution, storage commits) report their latencies
+//! into a process-wide [`LatencyMonitor`]. Components serving low-priority work (JSON-RPC
+//! range scans, backup streaming, mempool broadcasts from non-preferred peers) consult a
+//! [`LoadShedder`] before doing that work; while the smoothed latencies exceed the configured
+//! thresholds, the work is delayed or rejected according to the per-path [`ShedPolicy`].
+//! This keeps consensus live during load spikes at the expense of best-effort services.
+
+mod counters;
+
+use diem_config::config::{LoadSheddingConfig, ShedPolicy};
+use once_cell::sync::Lazy;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Weight (in percent) given to a new latency sample in the moving average.
+const EWMA_NEW_SAMPLE_WEIGHT_PERCENT: u64 = 20;
+
+static GLOBAL_LATENCY_MONITOR: Lazy<Arc<LatencyMonitor>> =
+    Lazy::new(|| Arc::new(LatencyMonitor::new()));
+
+/// Latency-critical operations whose latency determines whether the node is overloaded.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LatencySource {
+    Execution,
+    Storage,
+}
+
+impl LatencySource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LatencySource::Execution => "execution",
+            LatencySource::Storage => "storage",
+        }
+    }
+}
+
+/// Low-priority inbound paths which may be shed under overload.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InboundPath {
+    JsonRpcScan,
+    BackupStreaming,
+    NonPreferredMempoolBroadcast,
+}
+
+impl InboundPath {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InboundPath::JsonRpcScan => "json_rpc_scan",
+            InboundPath::BackupStreaming => "backup_streaming",
+            InboundPath::NonPreferredMempoolBroadcast => "non_preferred_mempool_broadcast",
+        }
+    }
+}
+
+/// The outcome of asking the load shedder whether a piece of work may proceed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Admission {
+    /// Proceed immediately.
+    Accept,
+    /// Proceed after waiting for the given duration.
+    Delay(Duration),
+    /// Do not perform the work.
+    Shed,
+}
+
+impl Admission {
+    fn as_str(self) -> &'static str {
+        match self {
+            Admission::Accept => "accept",
+            Admission::Delay(_) => "delay",
+            Admission::Shed => "shed",
+        }
+    }
+}
+
+/// Smoothed latency of a single source, together with the time of its last sample.
+struct SmoothedLatency {
+    ewma_us: AtomicU64,
+    /// Microseconds since the monitor was created; 0 if no sample was ever taken.
+    last_sample_us: AtomicU64,
+}
+
+impl SmoothedLatency {
+    fn new() -> Self {
+        Self {
+            ewma_us: AtomicU64::new(0),
+            last_sample_us: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Tracks the smoothed latency of every [`LatencySource`].
+pub struct LatencyMonitor {
+    start: Instant,
+    execution: SmoothedLatency,
+    storage: SmoothedLatency,
+}
+
+impl LatencyMonitor {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            execution: SmoothedLatency::new(),
+            storage: SmoothedLatency::new(),
+        }
+    }
+
+    fn source(&self, source: LatencySource) -> &SmoothedLatency {
+        match source {
+            LatencySource::Execution => &self.execution,
+            LatencySource::Storage => &self.storage,
+        }
+    }
+
+    fn now_us(&self) -> u64 {
+        // Never return 0, which is reserved for "no sample yet".
+        (self.start.elapsed().as_micros() as u64).max(1)
+    }
+
+    /// Records a latency sample for `source`.
+    pub fn observe(&self, source: LatencySource, latency: Duration) {
+        let sample = latency.as_micros() as u64;
+        let smoothed = self.source(source);
+        let has_previous_sample = smoothed.last_sample_us.load(Ordering::Acquire) != 0;
+        // Races between concurrent observers only affect the weighting of one sample, so a
+        // plain update loop is good enough here.
+        let _ = smoothed
+            .ewma_us
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                Some(if has_previous_sample {
+                    (current * (100 - EWMA_NEW_SAMPLE_WEIGHT_PERCENT)
+                        + sample * EWMA_NEW_SAMPLE_WEIGHT_PERCENT)
+                        / 100
+                } else {
+                    sample
+                })
+            });
+        smoothed
+            .last_sample_us
+            .store(self.now_us(), Ordering::Release);
+        counters::SMOOTHED_LATENCY_US
+            .with_label_values(&[source.as_str()])
+            .set(smoothed.ewma_us.load(Ordering::Acquire) as i64);
+    }
+
+    /// Returns the smoothed latency of `source`, or `None` if there is no sample newer than
+    /// `window`.
+    pub fn smoothed_latency(&self, source: LatencySource, window: Duration) -> Option<Duration> {
+        let smoothed = self.source(source);
+        let last_sample_us = smoothed.last_sample_us.load(Ordering::Acquire);
+        if last_sample_us == 0
+            || self.now_us().saturating_sub(last_sample_us) > window.as_micros() as u64
+        {
+            return None;
+        }
+        Some(Duration::from_micros(
+            smoothed.ewma_us.load(Ordering::Acquire),
+        ))
+    }
+}
+
+impl Default for LatencyMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records a latency sample for `source` in the process-wide latency monitor.
+pub fn observe_latency(source: LatencySource, latency: Duration) {
+    GLOBAL_LATENCY_MONITOR.observe(source, latency);
+}
+
+/// Starts a timer which records the elapsed time for `source` in the process-wide latency
+/// monitor when dropped.
+pub fn start_latency_timer(source: LatencySource) -> LatencyTimer {
+    LatencyTimer {
+        source,
+        start: Instant::now(),
+    }
+}
+
+/// Guard returned by [`start_latency_timer`].
+pub struct LatencyTimer {
+    source: LatencySource,
+    start: Instant,
+}
+
+impl Drop for LatencyTimer {
+    fn drop(&mut self) {
+        observe_latency(self.source, self.start.elapsed());
+    }
+}
+
+/// Decides whether low-priority inbound work may proceed, based on the latencies reported to
+/// a [`LatencyMonitor`] and the configured per-path policies.
+#[derive(Clone)]
+pub struct LoadShedder {
+    config: LoadSheddingConfig,
+    monitor: Arc<LatencyMonitor>,
+}
+
+impl LoadShedder {
+    /// Creates a load shedder backed by the process-wide latency monitor.
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        Self::new_with_monitor(config, GLOBAL_LATENCY_MONITOR.clone())
+    }
+
+    pub fn new_with_monitor(config: LoadSheddingConfig, monitor: Arc<LatencyMonitor>) -> Self {
+        Self { config, monitor }
+    }
+
+    /// Returns true if any monitored latency currently exceeds its threshold.
+    pub fn is_overloaded(&self) -> bool {
+        if !self.config.enabled {
+            return false;
+        }
+        let window = Duration::from_millis(self.config.sample_window_ms);
+        let exceeds = |source, threshold_ms| {
+            self.monitor
+                .smoothed_latency(source, window)
+                .map_or(false, |latency| {
+                    latency > Duration::from_millis(threshold_ms)
+                })
+        };
+        exceeds(
+            LatencySource::Execution,
+            self.config.execution_latency_threshold_ms,
+        ) || exceeds(
+            LatencySource::Storage,
+            self.config.storage_latency_threshold_ms,
+        )
+    }
+
+    fn policy(&self, path: InboundPath) -> ShedPolicy {
+        match path {
+            InboundPath::JsonRpcScan => self.config.json_rpc_scans,
+            InboundPath::BackupStreaming => self.config.backup_streaming,
+            InboundPath::NonPreferredMempoolBroadcast => {
+                self.config.non_preferred_mempool_broadcasts
+            }
+        }
+    }
+
+    /// Decides whether work arriving on `path` may proceed, and records the decision.
+    pub fn admit(&self, path: InboundPath) -> Admission {
+        let admission = match self.policy(path) {
+            ShedPolicy::Admit => Admission::Accept,
+            _ if !self.is_overloaded() => Admission::Accept,
+            ShedPolicy::Deprioritize => {
+                Admission::Delay(Duration::from_millis(self.config.deprioritize_delay_ms))
+            }
+            ShedPolicy::Shed => Admission::Shed,
+        };
+        counters::ADMISSION_DECISIONS
+            .with_label_values(&[path.as_str(), admission.as_str()])
+            .inc();
+        admission
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_config() -> LoadSheddingConfig {
+        LoadSheddingConfig {
+            enabled: true,
+            ..LoadSheddingConfig::default()
+        }
+    }
+
+    fn shedder(monitor: &Arc<LatencyMonitor>) -> LoadShedder {
+        LoadShedder::new_with_monitor(enabled_config(), monitor.clone())
+    }
+
+    #[test]
+    fn accepts_without_samples() {
+        let monitor = Arc::new(LatencyMonitor::new());
+        let shedder = shedder(&monitor);
+        assert!(!shedder.is_overloaded());
+        assert_eq!(shedder.admit(InboundPath::JsonRpcScan), Admission::Accept);
+    }
+
+    #[test]
+    fn sheds_according_to_policy_under_overload() {
+        let monitor = Arc::new(LatencyMonitor::new());
+        let shedder = shedder(&monitor);
+        monitor.observe(LatencySource::Storage, Duration::from_secs(5));
+        assert!(shedder.is_overloaded());
+        assert_eq!(shedder.admit(InboundPath::JsonRpcScan), Admission::Shed);
+        assert_eq!(
+            shedder.admit(InboundPath::BackupStreaming),
+            Admission::Delay(Duration::from_millis(
+                LoadSheddingConfig::default().deprioritize_delay_ms
+            ))
+        );
+    }
+
+    #[test]
+    fn recovers_once_latency_drops() {
+        let monitor = Arc::new(LatencyMonitor::new());
+        let shedder = shedder(&monitor);
+        monitor.observe(LatencySource::Execution, Duration::from_secs(3));
+        assert!(shedder.is_overloaded());
+        for _ in 0..50 {
+            monitor.observe(LatencySource::Execution, Duration::from_millis(10));
+        }
+        assert!(!shedder.is_overloaded());
+    }
+
+    #[test]
+    fn ignores_stale_samples() {
+        let monitor = Arc::new(LatencyMonitor::new());
+        let config = LoadSheddingConfig {
+            sample_window_ms: 0,
+            ..enabled_config()
+        };
+        let shedder = LoadShedder::new_with_monitor(config, monitor.clone());
+        monitor.observe(LatencySource::Storage, Duration::from_secs(5));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(!shedder.is_overloaded());
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let monitor = Arc::new(LatencyMonitor::new());
+        let shedder = LoadShedder::new_with_monitor(LoadSheddingConfig::default(), monitor.clone());
+        monitor.observe(LatencySource::Storage, Duration::from_secs(5));
+        assert_eq!(
+            shedder.admit(InboundPath::NonPreferredMempoolBroadcast),
+            Admission::Accept
+        );
+    }
+}
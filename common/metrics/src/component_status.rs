@@ -0,0 +1,25 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured status reports published by node components (e.g., the progress of state sync),
+//! served by the metric server at `host:port/status/<component>`.
+
+use diem_infallible::RwLock;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::HashMap;
+
+static COMPONENT_STATUS: Lazy<RwLock<HashMap<String, Value>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Replaces the status report of the given component.
+pub fn set_component_status(component: &str, status: Value) {
+    COMPONENT_STATUS
+        .write()
+        .insert(component.to_string(), status);
+}
+
+/// Returns the latest status report of the given component, if it has published one.
+pub fn get_component_status(component: &str) -> Option<Value> {
+    COMPONENT_STATUS.read().get(component).cloned()
+}
@@ -47,11 +47,14 @@
 #![forbid(unsafe_code)]
 #![recursion_limit = "128"]
 
+mod component_status;
 mod json_encoder;
 mod json_metrics;
 pub mod metric_server;
 mod public_metrics;
 
+pub use component_status::{get_component_status, set_component_status};
+
 mod op_counters;
 pub use op_counters::{DurationHistogram, OpMetrics};
 
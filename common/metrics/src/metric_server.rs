@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    gather_metrics, json_encoder::JsonEncoder, json_metrics::get_json_metrics,
-    public_metrics::PUBLIC_METRICS, NUM_METRICS,
+    component_status::get_component_status, gather_metrics, json_encoder::JsonEncoder,
+    json_metrics::get_json_metrics, public_metrics::PUBLIC_METRICS, NUM_METRICS,
 };
 use futures::future;
 use hyper::{
@@ -18,6 +18,8 @@ use std::{
 };
 use tokio::runtime;
 
+const COMPONENT_STATUS_PATH_PREFIX: &str = "/status/";
+
 fn encode_metrics(encoder: impl Encoder, whitelist: &'static [&'static str]) -> Vec<u8> {
     let mut metric_families = gather_metrics();
     if !whitelist.is_empty() {
@@ -87,6 +89,13 @@ async fn serve_metrics(req: Request<Body>) -> Result<Response<Body>, hyper::Erro
             let buffer = encode_metrics(encoder, &[]);
             *resp.body_mut() = Body::from(buffer);
         }
+        // expose structured component status reports to host:port/status/<component>
+        (&Method::GET, path) if path.starts_with(COMPONENT_STATUS_PATH_PREFIX) => {
+            match get_component_status(&path[COMPONENT_STATUS_PATH_PREFIX.len()..]) {
+                Some(status) => *resp.body_mut() = Body::from(status.to_string()),
+                None => *resp.status_mut() = StatusCode::NOT_FOUND,
+            }
+        }
         _ => {
             *resp.status_mut() = StatusCode::NOT_FOUND;
         }
@@ -70,3 +70,15 @@ fn get_all_metrics_test() {
     }
 }
 }
+
+#[test]
+fn component_status_test() {
+    assert_eq!(get_component_status("test_component"), None);
+
+    set_component_status("test_component", serde_json::json!({ "version": 1 }));
+    set_component_status("test_component", serde_json::json!({ "version": 2 }));
+    assert_eq!(
+        get_component_status("test_component"),
+        Some(serde_json::json!({ "version": 2 }))
+    );
+}
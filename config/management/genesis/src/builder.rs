@@ -222,16 +222,15 @@ impl<S: KVStorage> GenesisBuilder<S> {
         let validators = self.validators()?;
         let move_modules = self.move_modules()?;
 
-        let genesis = vm_genesis::encode_genesis_transaction(
-            diem_root_key,
-            treasury_compliance_key,
-            &validators,
-            &move_modules,
-            publishing_option,
-            consensus_config,
-            chain_id,
-        );
-
-        Ok(genesis)
+        let mut genesis_builder =
+            vm_genesis::GenesisBuilder::new(chain_id, diem_root_key, treasury_compliance_key)
+                .validators(validators)
+                .move_modules(move_modules)
+                .consensus_config(consensus_config);
+        if let Some(publishing_option) = publishing_option {
+            genesis_builder = genesis_builder.publishing_option(publishing_option);
+        }
+
+        genesis_builder.build_transaction()
     }
 }
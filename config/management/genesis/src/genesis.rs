@@ -6,7 +6,9 @@ use diem_management::{config::ConfigPath, error::Error, secure_backend::SharedBa
 use diem_secure_storage::Storage;
 use diem_types::{
     chain_id::ChainId,
-    on_chain_config::{ConsensusConfigV1, OnChainConsensusConfig},
+    on_chain_config::{
+        ConsensusConfigV1, ConsensusConfigV4, OnChainConsensusConfig, RoundTimeoutCurve,
+    },
     transaction::Transaction,
 };
 use std::{fs::File, io::Write, path::PathBuf};
@@ -24,6 +26,48 @@ pub struct Genesis {
     pub backend: SharedBackend,
     #[structopt(long)]
     pub path: Option<PathBuf>,
+    #[structopt(flatten)]
+    pub round_timeout_curve: RoundTimeoutCurveOptions,
+}
+
+/// Sets the round timeout curve on-chain, instead of leaving it to the config of each validator.
+#[derive(Debug, StructOpt)]
+pub struct RoundTimeoutCurveOptions {
+    /// Duration of the first round after a commit, in milliseconds
+    #[structopt(
+        long,
+        requires_all(&["round-timeout-exponent-base-percent", "round-timeout-max-exponent"])
+    )]
+    pub round_base_timeout_ms: Option<u64>,
+    /// By how much the duration of rounds grows every round without a commit, in percent
+    #[structopt(long, requires("round-base-timeout-ms"))]
+    pub round_timeout_exponent_base_percent: Option<u64>,
+    /// Number of rounds without a commit after which the duration of rounds stops growing
+    #[structopt(long, requires("round-base-timeout-ms"))]
+    pub round_timeout_max_exponent: Option<u64>,
+}
+
+impl RoundTimeoutCurveOptions {
+    pub fn consensus_config(&self) -> OnChainConsensusConfig {
+        match (
+            self.round_base_timeout_ms,
+            self.round_timeout_exponent_base_percent,
+            self.round_timeout_max_exponent,
+        ) {
+            (Some(base_timeout_ms), Some(exponent_base_percent), Some(max_exponent)) => {
+                OnChainConsensusConfig::V4(ConsensusConfigV4 {
+                    two_chain: true,
+                    round_timeout_curve: RoundTimeoutCurve {
+                        base_timeout_ms,
+                        exponent_base_percent,
+                        max_exponent,
+                    },
+                    ..ConsensusConfigV4::default()
+                })
+            }
+            _ => OnChainConsensusConfig::V1(ConsensusConfigV1 { two_chain: true }),
+        }
+    }
 }
 
 impl Genesis {
@@ -39,11 +83,7 @@ impl Genesis {
         let chain_id = config.chain_id;
         let storage = Storage::from(&config.shared_backend);
         let genesis = GenesisBuilder::new(storage)
-            .build(
-                chain_id,
-                None,
-                OnChainConsensusConfig::V1(ConsensusConfigV1 { two_chain: true }),
-            )
+            .build(chain_id, None, self.round_timeout_curve.consensus_config())
             .map_err(|e| Error::UnexpectedError(e.to_string()))?;
 
         if let Some(path) = self.path {
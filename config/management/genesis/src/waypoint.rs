@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::genesis::RoundTimeoutCurveOptions;
 use diem_config::config::RocksdbConfig;
 use diem_management::{config::ConfigPath, error::Error, secure_backend::SharedBackend};
 use diem_temppath::TempPath;
@@ -21,6 +22,9 @@ pub struct CreateWaypoint {
     chain_id: Option<ChainId>,
     #[structopt(flatten)]
     shared_backend: SharedBackend,
+    // Must match the options genesis was created with
+    #[structopt(flatten)]
+    round_timeout_curve: RoundTimeoutCurveOptions,
 }
 
 impl CreateWaypoint {
@@ -30,6 +34,7 @@ impl CreateWaypoint {
             chain_id: self.chain_id,
             backend: self.shared_backend,
             path: None,
+            round_timeout_curve: self.round_timeout_curve,
         };
 
         let genesis = genesis_helper.execute()?;
@@ -9,6 +9,7 @@ use diem_config::config::Peer;
 use diem_crypto::{ed25519::Ed25519PublicKey, x25519};
 use diem_management::{error::Error, execute_command};
 use diem_types::{account_address::AccountAddress, waypoint::Waypoint, PeerId};
+use safety_rules::SafetyRulesState;
 use serde::Serialize;
 use std::collections::HashMap;
 use structopt::StructOpt;
@@ -28,6 +29,8 @@ pub enum Command {
     CreateValidator(crate::governance::CreateValidator),
     #[structopt(about = "Create a new validator operator account")]
     CreateValidatorOperator(crate::governance::CreateValidatorOperator),
+    #[structopt(about = "Export the safety rules state from the validator storage to a file")]
+    ExportSafetyRulesState(crate::safety_rules_state::ExportSafetyRulesState),
     #[structopt(about = "Extract a trusted peer identity from an x25519 PrivateKey file")]
     ExtractPeerFromFile(crate::keys::ExtractPeerFromFile),
     #[structopt(about = "Extract a trusted peer identity from storage")]
@@ -40,6 +43,8 @@ pub enum Command {
     ExtractPublicKey(crate::keys::ExtractPublicKey),
     #[structopt(about = "Generate a PrivateKey to a file")]
     GenerateKey(crate::keys::GenerateKey),
+    #[structopt(about = "Import the safety rules state from a file into the validator storage")]
+    ImportSafetyRulesState(crate::safety_rules_state::ImportSafetyRulesState),
     #[structopt(about = "Set the waypoint in the validator storage")]
     InsertWaypoint(diem_management::waypoint::InsertWaypoint),
     #[structopt(about = "Prints an account from the validator storage")]
@@ -82,12 +87,14 @@ pub enum CommandName {
     CheckValidatorSetEndpoints,
     CreateValidator,
     CreateValidatorOperator,
+    ExportSafetyRulesState,
     ExtractPeerFromFile,
     ExtractPeerFromStorage,
     ExtractPeersFromKeys,
     ExtractPrivateKey,
     ExtractPublicKey,
     GenerateKey,
+    ImportSafetyRulesState,
     InsertWaypoint,
     PrintAccount,
     PrintKey,
@@ -114,12 +121,14 @@ impl From<&Command> for CommandName {
             Command::CheckValidatorSetEndpoints(_) => CommandName::CheckValidatorSetEndpoints,
             Command::CreateValidator(_) => CommandName::CreateValidator,
             Command::CreateValidatorOperator(_) => CommandName::CreateValidatorOperator,
+            Command::ExportSafetyRulesState(_) => CommandName::ExportSafetyRulesState,
             Command::ExtractPrivateKey(_) => CommandName::ExtractPrivateKey,
             Command::ExtractPublicKey(_) => CommandName::ExtractPublicKey,
             Command::ExtractPeerFromFile(_) => CommandName::ExtractPeerFromFile,
             Command::ExtractPeerFromStorage(_) => CommandName::ExtractPeerFromStorage,
             Command::ExtractPeersFromKeys(_) => CommandName::ExtractPeersFromKeys,
             Command::GenerateKey(_) => CommandName::GenerateKey,
+            Command::ImportSafetyRulesState(_) => CommandName::ImportSafetyRulesState,
             Command::InsertWaypoint(_) => CommandName::InsertWaypoint,
             Command::PrintAccount(_) => CommandName::PrintAccount,
             Command::PrintKey(_) => CommandName::PrintKey,
@@ -148,12 +157,14 @@ impl std::fmt::Display for CommandName {
             CommandName::CheckValidatorSetEndpoints => "check-validator-set-endpoints",
             CommandName::CreateValidator => "create-validator",
             CommandName::CreateValidatorOperator => "create-validator-operator",
+            CommandName::ExportSafetyRulesState => "export-safety-rules-state",
             CommandName::ExtractPrivateKey => "extract-private-key",
             CommandName::ExtractPublicKey => "extract-public-key",
             CommandName::ExtractPeerFromFile => "extract-peer-from-file",
             CommandName::ExtractPeerFromStorage => "extract-peer-from-storage",
             CommandName::ExtractPeersFromKeys => "extract-peers-from-keys",
             CommandName::GenerateKey => "generate-key",
+            CommandName::ImportSafetyRulesState => "import-safety-rules-state",
             CommandName::InsertWaypoint => "insert-waypoint",
             CommandName::PrintAccount => "print-account",
             CommandName::PrintKey => "print-key",
@@ -187,6 +198,7 @@ impl Command {
             Command::CreateValidatorOperator(cmd) => {
                 Self::print_transaction_context(cmd.execute().map(|(txn_ctx, _)| txn_ctx))
             }
+            Command::ExportSafetyRulesState(cmd) => Self::pretty_print(cmd.execute()),
             Command::InsertWaypoint(cmd) => Self::print_success(cmd.execute()),
             Command::ExtractPeerFromFile(cmd) => Self::pretty_print(cmd.execute()),
             Command::ExtractPeerFromStorage(cmd) => Self::pretty_print(cmd.execute()),
@@ -194,6 +206,7 @@ impl Command {
             Command::ExtractPrivateKey(cmd) => Self::print_success(cmd.execute()),
             Command::ExtractPublicKey(cmd) => Self::print_success(cmd.execute()),
             Command::GenerateKey(cmd) => Self::print_success(cmd.execute().map(|_| ())),
+            Command::ImportSafetyRulesState(cmd) => Self::print_success(cmd.execute()),
             Command::PrintAccount(cmd) => Self::pretty_print(cmd.execute()),
             Command::PrintKey(cmd) => Self::pretty_print(cmd.execute()),
             Command::PrintXKey(cmd) => Self::pretty_print(cmd.execute()),
@@ -285,6 +298,14 @@ impl Command {
         )
     }
 
+    pub fn export_safety_rules_state(self) -> Result<SafetyRulesState, Error> {
+        execute_command!(
+            self,
+            Command::ExportSafetyRulesState,
+            CommandName::ExportSafetyRulesState
+        )
+    }
+
     pub fn extract_private_key(self) -> Result<(), Error> {
         execute_command!(
             self,
@@ -329,6 +350,14 @@ impl Command {
         execute_command!(self, Command::GenerateKey, CommandName::GenerateKey)
     }
 
+    pub fn import_safety_rules_state(self) -> Result<(), Error> {
+        execute_command!(
+            self,
+            Command::ImportSafetyRulesState,
+            CommandName::ImportSafetyRulesState
+        )
+    }
+
     pub fn insert_waypoint(self) -> Result<(), Error> {
         execute_command!(self, Command::InsertWaypoint, CommandName::InsertWaypoint)
     }
@@ -0,0 +1,74 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use diem_management::{config::ConfigPath, error::Error, secure_backend::ValidatorBackend};
+use diem_secure_storage::Storage;
+use safety_rules::{PersistentSafetyStorage, SafetyRulesState};
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// Exports the safety rules state of a validator to a file, to migrate the validator to another
+/// machine. The validator must be stopped before, and never restarted on this machine after, as
+/// running it on both could make it vote twice in a round.
+#[derive(Debug, StructOpt)]
+pub struct ExportSafetyRulesState {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+    /// File the state is written to
+    #[structopt(long)]
+    output_file: PathBuf,
+}
+
+impl ExportSafetyRulesState {
+    pub fn execute(self) -> Result<SafetyRulesState, Error> {
+        let mut safety_storage = safety_storage(&self.config, &self.validator_backend)?;
+        let state = safety_storage
+            .export_state()
+            .map_err(|e| Error::UnexpectedError(e.to_string()))?;
+        let contents =
+            serde_yaml::to_string(&state).map_err(|e| Error::UnexpectedError(e.to_string()))?;
+        fs::write(&self.output_file, contents)
+            .map_err(|e| Error::IO("safety rules state".to_string(), e))?;
+        Ok(state)
+    }
+}
+
+/// Imports the safety rules state exported from another machine, after importing the consensus key
+/// of the validator. The import is rejected if the state is behind the one already in storage.
+#[derive(Debug, StructOpt)]
+pub struct ImportSafetyRulesState {
+    #[structopt(flatten)]
+    config: ConfigPath,
+    #[structopt(flatten)]
+    validator_backend: ValidatorBackend,
+    /// File the state is read from
+    #[structopt(long)]
+    input_file: PathBuf,
+}
+
+impl ImportSafetyRulesState {
+    pub fn execute(self) -> Result<(), Error> {
+        let contents = fs::read(&self.input_file).map_err(|e| {
+            Error::UnableToReadFile(self.input_file.to_string_lossy().to_string(), e.to_string())
+        })?;
+        let state: SafetyRulesState =
+            serde_yaml::from_slice(&contents).map_err(|e| Error::UnexpectedError(e.to_string()))?;
+        let mut safety_storage = safety_storage(&self.config, &self.validator_backend)?;
+        safety_storage
+            .import_state(state)
+            .map_err(|e| Error::UnexpectedError(e.to_string()))
+    }
+}
+
+fn safety_storage(
+    config: &ConfigPath,
+    validator_backend: &ValidatorBackend,
+) -> Result<PersistentSafetyStorage, Error> {
+    let config = config
+        .load()?
+        .override_validator_backend(&validator_backend.validator_backend)?;
+    let storage = Storage::from(&config.validator_backend);
+    Ok(PersistentSafetyStorage::new(storage, true))
+}
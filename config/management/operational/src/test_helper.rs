@@ -17,6 +17,7 @@ use diem_types::{
     waypoint::Waypoint, PeerId,
 };
 use itertools::Itertools;
+use safety_rules::SafetyRulesState;
 use std::{
     collections::{HashMap, HashSet},
     path::Path,
@@ -241,6 +242,25 @@ impl OperationalTool {
         )
     }
 
+    pub fn export_safety_rules_state(
+        &self,
+        output_file: &Path,
+        backend: &config::SecureBackend,
+    ) -> Result<SafetyRulesState, Error> {
+        let args = format!(
+            "
+                {command}
+                --output-file {output_file}
+                --validator-backend {backend_args}
+            ",
+            command = command(TOOL_NAME, CommandName::ExportSafetyRulesState),
+            output_file = output_file.to_str().unwrap(),
+            backend_args = backend_args(backend)?,
+        );
+        let command = Command::from_iter(args.split_whitespace());
+        command.export_safety_rules_state()
+    }
+
     pub fn extract_peer_from_file(
         &self,
         key_file: &Path,
@@ -324,6 +344,25 @@ impl OperationalTool {
         load_key(key_file.to_path_buf(), encoding)
     }
 
+    pub fn import_safety_rules_state(
+        &self,
+        input_file: &Path,
+        backend: &config::SecureBackend,
+    ) -> Result<(), Error> {
+        let args = format!(
+            "
+                {command}
+                --input-file {input_file}
+                --validator-backend {backend_args}
+            ",
+            command = command(TOOL_NAME, CommandName::ImportSafetyRulesState),
+            input_file = input_file.to_str().unwrap(),
+            backend_args = backend_args(backend)?,
+        );
+        let command = Command::from_iter(args.split_whitespace());
+        command.import_safety_rules_state()
+    }
+
     pub fn insert_waypoint(
         &self,
         waypoint: Waypoint,
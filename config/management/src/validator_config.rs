@@ -190,7 +190,8 @@ pub fn validate_address(
                 }
                 has_addr = true
             }
-            Protocol::Tcp(_) => has_port = true,
+            Protocol::Tcp(_) | Protocol::Udp(_) => has_port = true,
+            Protocol::Quic => (),
             Protocol::Dns(_) | Protocol::Ip6(_) | Protocol::Dns6(_) => {
                 return Err(Error::CommandArgumentError(format!(
                     "{}: IPv6 is currently not supported.  Protocol: '{}'",
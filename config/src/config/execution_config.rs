@@ -2,12 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::config::{Error, RootPath, SecureBackend};
-use diem_types::transaction::Transaction;
+use diem_types::transaction::{Transaction, Version};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::File,
     io::{Read, Write},
     net::SocketAddr,
+    ops::RangeInclusive,
     path::PathBuf,
 };
 
@@ -23,6 +24,12 @@ pub struct ExecutionConfig {
     pub service: ExecutionCorrectnessService,
     pub backend: SecureBackend,
     pub network_timeout_ms: u64,
+    /// If set, the executors record a trace of the execution of the committed transactions in a
+    /// version range, to debug nodes diverging.
+    pub trace: Option<ExecutionTraceConfig>,
+    /// The maximum number of transactions in the speculative block outputs the executors keep for
+    /// re-executions of the same blocks, which bounds the memory they take.
+    pub speculation_result_cache_max_txns: usize,
 }
 
 impl std::fmt::Debug for ExecutionConfig {
@@ -40,8 +47,13 @@ impl std::fmt::Debug for ExecutionConfig {
         )?;
         write!(
             f,
-            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?} }}",
-            self.sign_vote_proposal, self.service, self.backend
+            ", sign_vote_proposal: {:?}, service: {:?}, backend: {:?}, trace: {:?} ",
+            self.sign_vote_proposal, self.service, self.backend, self.trace
+        )?;
+        write!(
+            f,
+            ", speculation_result_cache_max_txns: {:?} }}",
+            self.speculation_result_cache_max_txns
         )?;
         self.service.fmt(f)
     }
@@ -57,6 +69,8 @@ impl Default for ExecutionConfig {
             sign_vote_proposal: true,
             // Default value of 30 seconds for the network timeout.
             network_timeout_ms: 30_000,
+            trace: None,
+            speculation_result_cache_max_txns: 10_000,
         }
     }
 }
@@ -118,6 +132,27 @@ pub struct RemoteExecutionService {
     pub server_address: SocketAddr,
 }
 
+/// Where and for which versions the executors record execution traces.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecutionTraceConfig {
+    /// The directory of the trace files, one per executor.
+    pub dir: PathBuf,
+    pub first_version: Version,
+    pub last_version: Version,
+}
+
+impl ExecutionTraceConfig {
+    /// The trace file of the executor named `executor`.
+    pub fn path(&self, executor: &str) -> PathBuf {
+        self.dir.join(format!("{}.trace", executor))
+    }
+
+    pub fn versions(&self) -> RangeInclusive<Version> {
+        self.first_version..=self.last_version
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
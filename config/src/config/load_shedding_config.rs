@@ -0,0 +1,51 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::{Deserialize, Serialize};
+
+/// What to do with low-priority inbound work while the node is overloaded.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShedPolicy {
+    /// Always admit the work, regardless of load.
+    Admit,
+    /// Admit the work, but only after waiting `deprioritize_delay_ms`.
+    Deprioritize,
+    /// Reject the work outright.
+    Shed,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoadSheddingConfig {
+    pub enabled: bool,
+    /// Smoothed block execution latency above which the node is considered overloaded.
+    pub execution_latency_threshold_ms: u64,
+    /// Smoothed storage commit latency above which the node is considered overloaded.
+    pub storage_latency_threshold_ms: u64,
+    /// Latency samples older than this are ignored, so an idle node recovers from a spike.
+    pub sample_window_ms: u64,
+    /// How long deprioritized work is held back before it is admitted.
+    pub deprioritize_delay_ms: u64,
+    /// Policy for range scans served by JSON-RPC (transactions, events, account transactions).
+    pub json_rpc_scans: ShedPolicy,
+    /// Policy for streaming endpoints of the backup service.
+    pub backup_streaming: ShedPolicy,
+    /// Policy for mempool broadcasts received from non-upstream (non-preferred) peers.
+    pub non_preferred_mempool_broadcasts: ShedPolicy,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> LoadSheddingConfig {
+        LoadSheddingConfig {
+            enabled: false,
+            execution_latency_threshold_ms: 2_000,
+            storage_latency_threshold_ms: 1_000,
+            sample_window_ms: 10_000,
+            deprioritize_delay_ms: 500,
+            json_rpc_scans: ShedPolicy::Shed,
+            backup_streaming: ShedPolicy::Deprioritize,
+            non_preferred_mempool_broadcasts: ShedPolicy::Shed,
+        }
+    }
+}
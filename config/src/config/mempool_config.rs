@@ -12,10 +12,22 @@ pub struct MempoolConfig {
     pub default_failovers: usize,
     pub max_broadcasts_per_peer: usize,
     pub mempool_snapshot_interval_secs: u64,
+    // minimum gas price increase, in percent, for a transaction to replace the queued transaction
+    // with the same sender and sequence number
+    pub replacement_gas_price_bump_pct: u64,
     pub shared_mempool_ack_timeout_ms: u64,
+    // ACK latency above which the batch size broadcast to a peer is decreased
+    pub shared_mempool_ack_latency_target_ms: u64,
     pub shared_mempool_backoff_interval_ms: u64,
+    // max number of txns broadcast in a batch to peers on the validator and VFN networks
     pub shared_mempool_batch_size: usize,
+    // max number of txns broadcast in a batch to peers on public networks
+    pub shared_mempool_public_batch_size: usize,
+    // number of txns broadcast in a batch is adapted to each peer, but never below this
+    pub shared_mempool_min_batch_size: usize,
     pub shared_mempool_max_concurrent_inbound_syncs: usize,
+    // number of client submissions (JSON-RPC and API) validated concurrently
+    pub shared_mempool_max_concurrent_client_submissions: usize,
     pub shared_mempool_tick_interval_ms: u64,
     pub system_transaction_timeout_secs: u64,
     pub system_transaction_gc_interval_ms: u64,
@@ -27,10 +39,15 @@ impl Default for MempoolConfig {
             shared_mempool_tick_interval_ms: 50,
             shared_mempool_backoff_interval_ms: 30_000,
             shared_mempool_batch_size: 100,
+            shared_mempool_public_batch_size: 50,
+            shared_mempool_min_batch_size: 10,
             shared_mempool_ack_timeout_ms: 2_000,
+            shared_mempool_ack_latency_target_ms: 500,
             shared_mempool_max_concurrent_inbound_syncs: 2,
+            shared_mempool_max_concurrent_client_submissions: 16,
             max_broadcasts_per_peer: 1,
             mempool_snapshot_interval_secs: 180,
+            replacement_gas_price_bump_pct: 10,
             capacity: 1_000_000,
             capacity_per_user: 100,
             default_failovers: 3,
@@ -24,6 +24,8 @@ mod execution_config;
 pub use execution_config::*;
 mod key_manager_config;
 pub use key_manager_config::*;
+mod load_shedding_config;
+pub use load_shedding_config::*;
 mod logger_config;
 pub use logger_config::*;
 mod mempool_config;
@@ -70,6 +72,8 @@ pub struct NodeConfig {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub full_node_networks: Vec<NetworkConfig>,
     #[serde(default)]
+    pub load_shedding: LoadSheddingConfig,
+    #[serde(default)]
     pub logger: LoggerConfig,
     #[serde(default)]
     pub mempool: MempoolConfig,
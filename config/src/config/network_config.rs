@@ -36,6 +36,8 @@ pub const NETWORK_CHANNEL_SIZE: usize = 1024;
 pub const PING_INTERVAL_MS: u64 = 1000;
 pub const PING_TIMEOUT_MS: u64 = 10_000;
 pub const PING_FAILURES_TOLERATED: u64 = 5;
+pub const PEER_MONITORING_INTERVAL_MS: u64 = 15_000;
+pub const PEER_MONITORING_TIMEOUT_MS: u64 = 10_000;
 pub const CONNECTIVITY_CHECK_INTERVAL_MS: u64 = 5000;
 pub const MAX_CONCURRENT_NETWORK_REQS: usize = 100;
 pub const MAX_CONNECTION_DELAY_MS: u64 = 60_000; /* 1 minute */
@@ -90,6 +92,10 @@ pub struct NetworkConfig {
     pub ping_timeout_ms: u64,
     // Number of failed healthcheck pings until a peer is marked unhealthy
     pub ping_failures_tolerated: u64,
+    // Interval to probe peers for their round trip time, role and synced version
+    pub peer_monitoring_interval_ms: u64,
+    // Timeout until a peer monitoring probe fails
+    pub peer_monitoring_timeout_ms: u64,
     // Maximum number of outbound connections, limited by ConnectivityManager
     pub max_outbound_connections: usize,
     // Maximum number of outbound connections, limited by PeerManager
@@ -98,6 +104,9 @@ pub struct NetworkConfig {
     pub inbound_rate_limit_config: Option<RateLimitConfig>,
     // Outbound rate limiting configuration, if not specified, no rate limiting
     pub outbound_rate_limit_config: Option<RateLimitConfig>,
+    // Rate limits of the messages received from each peer, keyed by protocol name as in metrics,
+    // e.g. `MempoolDirectSend`. Protocols without one are not rate limited
+    pub inbound_protocol_rate_limits: HashMap<String, ProtocolRateLimitConfig>,
 }
 
 impl Default for NetworkConfig {
@@ -128,10 +137,13 @@ impl NetworkConfig {
             ping_interval_ms: PING_INTERVAL_MS,
             ping_timeout_ms: PING_TIMEOUT_MS,
             ping_failures_tolerated: PING_FAILURES_TOLERATED,
+            peer_monitoring_interval_ms: PEER_MONITORING_INTERVAL_MS,
+            peer_monitoring_timeout_ms: PEER_MONITORING_TIMEOUT_MS,
             max_outbound_connections: MAX_FULLNODE_OUTBOUND_CONNECTIONS,
             max_inbound_connections: MAX_INBOUND_CONNECTIONS,
             inbound_rate_limit_config: None,
             outbound_rate_limit_config: None,
+            inbound_protocol_rate_limits: HashMap::new(),
         };
         config.prepare_identity();
         config
@@ -371,6 +383,17 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// Rate limit of the messages of a protocol received from a peer. Messages received once the
+/// bucket is empty are dropped.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProtocolRateLimitConfig {
+    /// Maximum number of messages/s from a peer
+    pub message_bucket_rate: usize,
+    /// Maximum burst of messages from a peer
+    pub message_bucket_size: usize,
+}
+
 pub type PeerSet = HashMap<PeerId, Peer>;
 
 // TODO: Combine with RoleType?
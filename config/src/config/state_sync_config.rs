@@ -10,10 +10,35 @@ pub struct StateSyncConfig {
     pub chunk_limit: u64,
     // The timeout of the state sync client to process a commit notification (in milliseconds)
     pub client_commit_timeout_ms: u64,
+    // Additional on-chain configs to fetch and publish to reconfig subscribers, on top of
+    // the ones registered in the node binary
+    pub custom_on_chain_configs: Vec<CustomOnChainConfig>,
+    // Whether responses to peers which are behind the local epoch carry a proof of the epoch
+    // changes up to the local epoch, so that the peers can verify the local highest ledger info
+    // in a single round trip. Requires all peers to understand these responses.
+    pub enable_epoch_change_proofs: bool,
+    // Whether a node with an empty ledger (i.e., only genesis) bootstraps from a state snapshot
+    // of a recent version downloaded from its peers, instead of executing all the transactions
+    // since genesis. Requires the peers to serve state snapshots.
+    pub enable_fast_sync: bool,
+    // The version at which state sync halts, e.g., to take a backup or to fork a testnet at an
+    // exact version. Chunks crossing the version are truncated and nothing beyond it is
+    // committed. Disables fast sync. None syncs without a limit.
+    pub halt_at_version: Option<u64>,
+    // Rate limits on the chunk requests served to downstream peers (None disables the limits)
+    pub inbound_rate_limit_config: Option<ChunkRateLimitConfig>,
     // default timeout used for long polling to remote peer
     pub long_poll_timeout_ms: u64,
     // valid maximum chunk limit for sanity check
     pub max_chunk_limit: u64,
+    // The maximum size (in bytes) of executed chunks to buffer before committing them to storage
+    // in a single write batch. Chunks ending at a ledger info are always committed immediately.
+    // 0 disables batching, i.e., every chunk is committed once executed.
+    pub max_outstanding_commit_bytes: u64,
+    // The maximum number of chunks to request ahead of the next chunk, while earlier chunks are
+    // executed. The number of prefetched chunks adapts to the observed request and execution
+    // latencies. 0 disables prefetching.
+    pub max_prefetch_chunks: u64,
     // valid maximum timeout limit for sanity check
     pub max_timeout_ms: u64,
     // The timeout of the state sync coordinator to receive a commit ack from mempool (in milliseconds)
@@ -22,6 +47,12 @@ pub struct StateSyncConfig {
     // if no progress is made by sending chunk requests to a number of networks,
     // the next sync request will be multicasted, i.e. sent to more networks
     pub multicast_timeout_ms: u64,
+    // The number of threads verifying the proofs of chunk responses, so that the coordinator
+    // keeps processing messages while large chunks are verified. 0 verifies the proofs on the
+    // coordinator thread.
+    pub num_verification_threads: usize,
+    // Rate limits on the chunk requests sent to upstream peers (None disables the limits)
+    pub outbound_rate_limit_config: Option<ChunkRateLimitConfig>,
     // The timeout for ensuring sync requests are making progress (i.e., the maximum time between
     // commits when processing a sync request).
     pub sync_request_timeout_ms: u64,
@@ -34,13 +65,63 @@ impl Default for StateSyncConfig {
         Self {
             chunk_limit: 1000,
             client_commit_timeout_ms: 5_000,
+            custom_on_chain_configs: vec![],
+            enable_epoch_change_proofs: false,
+            enable_fast_sync: false,
+            halt_at_version: None,
+            inbound_rate_limit_config: None,
             long_poll_timeout_ms: 10_000,
             max_chunk_limit: 1000,
+            max_outstanding_commit_bytes: 0,
+            max_prefetch_chunks: 0,
             max_timeout_ms: 120_000,
             mempool_commit_timeout_ms: 5_000,
             multicast_timeout_ms: 30_000,
+            num_verification_threads: 0,
+            outbound_rate_limit_config: None,
             sync_request_timeout_ms: 60_000,
             tick_interval_ms: 100,
         }
     }
 }
+
+/// Token bucket rate limits on chunk requests, in chunks per second. The validator network and
+/// the other (public or private) networks have separate budgets, each shared by all the peers on
+/// a network, so that syncing public full nodes cannot exhaust the budget of validators.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChunkRateLimitConfig {
+    /// Maximum number of chunks per second on the validator network
+    pub validator_network_rate: usize,
+    /// Maximum burst of chunks on the validator network
+    pub validator_network_burst: usize,
+    /// Maximum number of chunks per second on each public or private network
+    pub public_network_rate: usize,
+    /// Maximum burst of chunks on each public or private network
+    pub public_network_burst: usize,
+    /// Allow for disabling the throttles
+    pub enabled: bool,
+}
+
+impl Default for ChunkRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            validator_network_rate: 100,
+            validator_network_burst: 200,
+            public_network_rate: 10,
+            public_network_burst: 20,
+            enabled: true,
+        }
+    }
+}
+
+/// An on-chain config which is not part of the registry built into the node, identified by the
+/// account it is published under and its name.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomOnChainConfig {
+    // The address of the account holding the config, as a hex literal (e.g., "0xA550C18")
+    pub address: String,
+    // The identifier of the config resource
+    pub identifier: String,
+}
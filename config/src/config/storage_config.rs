@@ -32,6 +32,82 @@ impl Default for RocksdbConfig {
     }
 }
 
+/// Retention windows of the ledger history. Each class of data is pruned independently of the state
+/// history (see `StorageConfig::prune_window`), so that, e.g., events can be kept for much longer
+/// than the state. As for the state, None disables pruning and the windows are in number of
+/// versions.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LedgerPruneWindows {
+    /// Window of the transactions, along with the index of user transactions by account.
+    pub transaction_prune_window: Option<u64>,
+    /// Window of the events, along with their indices and accumulators.
+    pub event_prune_window: Option<u64>,
+}
+
+/// Offloads the transactions and events of old versions to an object store, so that archival nodes
+/// only keep the latest part of the ledger history on the local disk. Offloaded data is read back
+/// through an in-memory cache and verified against the transaction infos, which stay local.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColdStorageConfig {
+    /// Number of latest versions whose transactions and events are kept on the local disk.
+    pub hot_window: u64,
+    /// Number of versions offloaded together, as a single object.
+    pub segment_size: u64,
+    /// Number of offloaded segments cached in memory.
+    pub cache_size: usize,
+    pub backend: ColdStorageBackend,
+}
+
+impl Default for ColdStorageConfig {
+    fn default() -> Self {
+        Self {
+            hot_window: 10_000_000,
+            segment_size: 10_000,
+            cache_size: 16,
+            backend: ColdStorageBackend::LocalDir {
+                path: PathBuf::from("/opt/diem/cold"),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ColdStorageBackend {
+    /// Objects are files in a local directory, e.g., a mounted bucket.
+    LocalDir { path: PathBuf },
+    /// Objects are written and read by command lines, e.g., of the `aws s3` or `gsutil` CLIs. The
+    /// object name is passed in the `$OBJECT_NAME` env var. `put_object` is fed with the object on
+    /// stdin, and `get_object` is expected to stream out the object on stdout.
+    Command {
+        put_object: String,
+        get_object: String,
+    },
+}
+
+/// Configures a node started as a read replica, which serves JSON-RPC from the DB of a node running
+/// in another process on the same host (see `StorageConfig::dir`), isolating the query load from
+/// the node.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReadReplicaConfig {
+    /// Directory the replica keeps its own logs in, relative to the data directory if relative.
+    pub secondary_dir: PathBuf,
+    /// Interval of catching up with the writes of the node, in milliseconds.
+    pub catch_up_interval_ms: u64,
+}
+
+impl Default for ReadReplicaConfig {
+    fn default() -> Self {
+        Self {
+            secondary_dir: PathBuf::from("db_read_replica"),
+            catch_up_interval_ms: 500,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct StorageConfig {
@@ -42,6 +118,18 @@ pub struct StorageConfig {
     /// None disables pruning. The windows is in number of versions, consider system tps
     /// (transaction per second) when calculating proper window.
     pub prune_window: Option<u64>,
+    /// Retention windows of the transactions and events, pruned independently of the state.
+    pub ledger_prune_windows: LedgerPruneWindows,
+    /// None keeps the whole ledger history on the local disk.
+    pub cold_storage: Option<ColdStorageConfig>,
+    /// Directory the backup service creates DB checkpoints in upon request. None disables
+    /// checkpoints. Checkpoints are cheap if on the same filesystem as `dir`.
+    pub checkpoint_dir: Option<PathBuf>,
+    /// Interval in versions of the state checkpoints recorded for light clients to bootstrap
+    /// from. None disables state checkpoints.
+    pub state_checkpoint_interval: Option<u64>,
+    /// Only used when the node is started as a read replica.
+    pub read_replica: ReadReplicaConfig,
     #[serde(skip)]
     data_dir: PathBuf,
     /// Read, Write, Connect timeout for network operations in milliseconds
@@ -64,6 +152,12 @@ impl Default for StorageConfig {
             // conservatively safe minimal prune window. It'll take a few Gigabytes of disk space
             // depending on the size of an average account blob.
             prune_window: Some(1_000_000),
+            // The ledger history is kept in full by default.
+            ledger_prune_windows: LedgerPruneWindows::default(),
+            cold_storage: None,
+            checkpoint_dir: None,
+            state_checkpoint_interval: None,
+            read_replica: ReadReplicaConfig::default(),
             data_dir: PathBuf::from("/opt/diem/data"),
             // Default read/write/connection timeout, in milliseconds
             timeout_ms: 30_000,
@@ -81,6 +175,24 @@ impl StorageConfig {
         }
     }
 
+    pub fn checkpoint_dir(&self) -> Option<PathBuf> {
+        self.checkpoint_dir.as_ref().map(|checkpoint_dir| {
+            if checkpoint_dir.is_relative() {
+                self.data_dir.join(checkpoint_dir)
+            } else {
+                checkpoint_dir.clone()
+            }
+        })
+    }
+
+    pub fn read_replica_dir(&self) -> PathBuf {
+        if self.read_replica.secondary_dir.is_relative() {
+            self.data_dir.join(&self.read_replica.secondary_dir)
+        } else {
+            self.read_replica.secondary_dir.clone()
+        }
+    }
+
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
         self.data_dir = data_dir;
     }
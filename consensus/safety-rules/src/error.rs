@@ -27,6 +27,8 @@ pub enum Error {
     InvalidProposal(String),
     #[error("Invalid QC: {0}")]
     InvalidQuorumCertificate(String),
+    #[error("Invalid safety rules state to import: {0}")]
+    InvalidSafetyRulesState(String),
     #[error("{0} is not set, SafetyRules is not initialized")]
     NotInitialized(String),
     #[error("Data not found in secure storage: {0}")]
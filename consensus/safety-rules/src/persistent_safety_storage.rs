@@ -15,7 +15,29 @@ use diem_global_constants::{CONSENSUS_KEY, EXECUTION_KEY, OWNER_ACCOUNT, SAFETY_
 use diem_logger::prelude::*;
 use diem_secure_storage::{CryptoStorage, KVStorage, Storage};
 use diem_types::waypoint::Waypoint;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The state safety rules persist for a validator, exported from the storage of one machine to be
+/// imported in the storage of another, e.g. when migrating the validator to new hardware.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SafetyRulesState {
+    pub author: Author,
+    /// The consensus key the safety data was signed with, which must be imported along with it.
+    pub consensus_public_key: Ed25519PublicKey,
+    pub safety_data: SafetyData,
+    pub waypoint: Waypoint,
+}
+
+impl fmt::Display for SafetyRulesState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "SafetyRulesState: [author: {}, consensus_public_key: {}, {}, waypoint: {}]",
+            self.author, self.consensus_public_key, self.safety_data, self.waypoint
+        )
+    }
+}
 
 /// SafetyRules needs an abstract storage interface to act as a common utility for storing
 /// persistent data to local disk, cloud, secrets managers, or even memory (for tests)
@@ -177,6 +199,83 @@ impl PersistentSafetyStorage {
         Ok(())
     }
 
+    /// Exports the state of this storage, to be imported on another machine. The validator must
+    /// be stopped first and never restarted on this machine, otherwise it could vote again in
+    /// rounds it already voted in from the other one.
+    pub fn export_state(&mut self) -> Result<SafetyRulesState, Error> {
+        let _timer = counters::start_timer("get", CONSENSUS_KEY);
+        let consensus_public_key = self
+            .internal_store
+            .get_public_key(CONSENSUS_KEY)
+            .map(|r| r.public_key)?;
+        Ok(SafetyRulesState {
+            author: self.author()?,
+            consensus_public_key,
+            safety_data: self.safety_data()?,
+            waypoint: self.waypoint()?,
+        })
+    }
+
+    /// Imports the state exported from another machine. The consensus key must already be in
+    /// this storage. To prevent equivocation, the import is rejected if it's behind the state
+    /// already in this storage, e.g. because it was exported before the validator last voted.
+    pub fn import_state(&mut self, state: SafetyRulesState) -> Result<(), Error> {
+        let consensus_public_key = self
+            .internal_store
+            .get_public_key(CONSENSUS_KEY)
+            .map(|r| r.public_key)?;
+        if consensus_public_key != state.consensus_public_key {
+            return Err(Error::InvalidSafetyRulesState(format!(
+                "exported for consensus key {}, but the one in storage is {}",
+                state.consensus_public_key, consensus_public_key
+            )));
+        }
+        if let Some(author) = Self::if_set(self.author())? {
+            if author != state.author {
+                return Err(Error::InvalidSafetyRulesState(format!(
+                    "exported for author {}, but the one in storage is {}",
+                    state.author, author
+                )));
+            }
+        }
+        if let Some(safety_data) = Self::if_set(self.safety_data())? {
+            let imported = &state.safety_data;
+            let is_behind = imported.epoch < safety_data.epoch
+                || (imported.epoch == safety_data.epoch
+                    && (imported.last_voted_round < safety_data.last_voted_round
+                        || imported.preferred_round < safety_data.preferred_round
+                        || imported.one_chain_round < safety_data.one_chain_round));
+            if is_behind {
+                return Err(Error::InvalidSafetyRulesState(format!(
+                    "{} is behind the one in storage, {}",
+                    imported, safety_data
+                )));
+            }
+        }
+        if let Some(waypoint) = Self::if_set(self.waypoint())? {
+            if state.waypoint.version() < waypoint.version() {
+                return Err(Error::InvalidSafetyRulesState(format!(
+                    "waypoint {} is behind the one in storage, {}",
+                    state.waypoint, waypoint
+                )));
+            }
+        }
+
+        self.internal_store.set(OWNER_ACCOUNT, state.author)?;
+        self.set_waypoint(&state.waypoint)?;
+        self.set_safety_data(state.safety_data)
+    }
+
+    /// Distinguishes values missing from storage, e.g. in one only holding the consensus key, from
+    /// errors reading them.
+    fn if_set<T>(result: Result<T, Error>) -> Result<Option<T>, Error> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(Error::SecureStorageMissingDataError(_)) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
     #[cfg(any(test, feature = "testing"))]
     pub fn internal_store(&mut self) -> &mut Storage {
         &mut self.internal_store
@@ -217,4 +316,50 @@ mod tests {
         assert_eq!(safety_data.last_voted_round, 8);
         assert_eq!(safety_data.preferred_round, 1);
     }
+
+    #[test]
+    fn test_export_import_state() {
+        let consensus_private_key = ValidatorSigner::from_int(0).private_key().clone();
+        let mut source = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            Author::random(),
+            consensus_private_key.clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        source
+            .set_safety_data(SafetyData::new(2, 8, 6, 7, None))
+            .unwrap();
+        let state = source.export_state().unwrap();
+
+        // The consensus key must be imported first.
+        let mut target = PersistentSafetyStorage::new(Storage::from(InMemoryStorage::new()), true);
+        assert!(target.import_state(state.clone()).is_err());
+        target
+            .internal_store()
+            .import_private_key(CONSENSUS_KEY, consensus_private_key)
+            .unwrap();
+        target.import_state(state.clone()).unwrap();
+        assert_eq!(target.export_state().unwrap(), state);
+
+        // Importing state behind the one in storage could lead to equivocation.
+        let mut stale_state = state.clone();
+        stale_state.safety_data = SafetyData::new(2, 7, 6, 7, None);
+        assert!(matches!(
+            target.import_state(stale_state),
+            Err(Error::InvalidSafetyRulesState(_))
+        ));
+        let mut other_author_state = state.clone();
+        other_author_state.author = Author::random();
+        assert!(matches!(
+            target.import_state(other_author_state),
+            Err(Error::InvalidSafetyRulesState(_))
+        ));
+
+        let mut newer_state = state;
+        newer_state.safety_data = SafetyData::new(3, 0, 0, 0, None);
+        target.import_state(newer_state.clone()).unwrap();
+        assert_eq!(target.export_state().unwrap(), newer_state);
+    }
 }
@@ -4,6 +4,7 @@
 use crate::{
     counters,
     epoch_manager::EpochManager,
+    liveness::round_timeouts::RoundTimeouts,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     persistent_liveness_storage::StorageWriteProxy,
@@ -24,7 +25,8 @@ use std::{collections::HashMap, sync::Arc};
 use storage_interface::DbReader;
 use tokio::runtime::{self, Runtime};
 
-/// Helper function to start consensus based on configuration and return the runtime
+/// Helper function to start consensus based on configuration and return the runtime, along with
+/// the rounds that time out
 pub fn start_consensus(
     node_config: &NodeConfig,
     mut network_sender: ConsensusNetworkSender,
@@ -33,7 +35,7 @@ pub fn start_consensus(
     consensus_to_mempool_sender: mpsc::Sender<ConsensusRequest>,
     diem_db: Arc<dyn DbReader>,
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
-) -> Runtime {
+) -> (Runtime, RoundTimeouts) {
     let runtime = runtime::Builder::new_multi_thread()
         .thread_name("consensus")
         .enable_all()
@@ -72,6 +74,8 @@ pub fn start_consensus(
         reconfig_events,
     );
 
+    let round_timeouts = epoch_mgr.round_timeouts();
+
     let (network_task, network_receiver) =
         NetworkTask::new(network_events, self_receiver, shared_connections);
 
@@ -79,5 +83,5 @@ pub fn start_consensus(
     runtime.spawn(epoch_mgr.start(timeout_receiver, network_receiver));
 
     debug!("Consensus started.");
-    runtime
+    (runtime, round_timeouts)
 }
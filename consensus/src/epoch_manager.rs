@@ -12,6 +12,7 @@ use crate::{
         rotating_proposer_election::{choose_leader, RotatingProposer},
         round_proposer_election::RoundProposer,
         round_state::{ExponentialTimeInterval, RoundState, RoundStateLogSchema},
+        round_timeouts::RoundTimeouts,
     },
     logging::{LogEvent, LogSchema},
     metrics_safety_rules::MetricsSafetyRules,
@@ -88,6 +89,7 @@ pub struct EpochManager {
     reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
     commit_msg_tx: Option<Sender<VerifiedEvent>>,
     back_pressure: Arc<AtomicU64>,
+    round_timeouts: RoundTimeouts,
 }
 
 impl EpochManager {
@@ -125,9 +127,15 @@ impl EpochManager {
             reconfig_events,
             commit_msg_tx: None,
             back_pressure,
+            round_timeouts: RoundTimeouts::default(),
         }
     }
 
+    /// The rounds that timed out, in this epoch and the previous ones.
+    pub fn round_timeouts(&self) -> RoundTimeouts {
+        self.round_timeouts.clone()
+    }
+
     fn epoch_state(&self) -> &EpochState {
         match self
             .processor
@@ -147,14 +155,27 @@ impl EpochManager {
         &self,
         time_service: Arc<dyn TimeService>,
         timeout_sender: channel::Sender<Round>,
+        onchain_config: &OnChainConsensusConfig,
     ) -> RoundState {
-        // 1.5^6 ~= 11
-        // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
-        let time_interval = Box::new(ExponentialTimeInterval::new(
-            Duration::from_millis(self.config.round_initial_timeout_ms),
-            1.2,
-            6,
-        ));
+        let onchain_time_interval = onchain_config.round_timeout_curve().and_then(|curve| {
+            let time_interval = ExponentialTimeInterval::from_curve(&curve);
+            if time_interval.is_none() {
+                error!(
+                    curve = ?curve,
+                    "Invalid on-chain round timeout curve, using the local config instead",
+                );
+            }
+            time_interval
+        });
+        // 1.2^6 ~= 3
+        // Timeout goes from initial_timeout to initial_timeout*3 in 6 steps
+        let time_interval = Box::new(onchain_time_interval.unwrap_or_else(|| {
+            ExponentialTimeInterval::new(
+                Duration::from_millis(self.config.round_initial_timeout_ms),
+                1.2,
+                6,
+            )
+        }));
         RoundState::new(time_interval, time_service, timeout_sender)
     }
 
@@ -320,8 +341,11 @@ impl EpochManager {
         }
 
         info!(epoch = epoch, "Create RoundState");
-        let round_state =
-            self.create_round_state(self.time_service.clone(), self.timeout_sender.clone());
+        let round_state = self.create_round_state(
+            self.time_service.clone(),
+            self.timeout_sender.clone(),
+            &onchain_config,
+        );
 
         info!(epoch = epoch, "Create ProposerElection");
         let proposer_election = self.create_proposer_election(&epoch_state);
@@ -354,6 +378,7 @@ impl EpochManager {
                 self.txn_manager.clone(),
                 self.time_service.clone(),
                 self.config.max_block_size,
+                onchain_config.block_lane_caps(),
             );
 
             RoundManager::new(
@@ -368,6 +393,7 @@ impl EpochManager {
                 self.storage.clone(),
                 self.config.sync_only,
                 onchain_config,
+                self.round_timeouts.clone(),
             )
         };
 
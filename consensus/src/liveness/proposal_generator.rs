@@ -13,6 +13,7 @@ use consensus_types::{
 };
 
 use diem_infallible::Mutex;
+use diem_types::on_chain_config::BlockLaneCaps;
 use std::sync::Arc;
 
 #[cfg(test)]
@@ -40,6 +41,8 @@ pub struct ProposalGenerator {
     time_service: Arc<dyn TimeService>,
     // Max number of transactions to be added to a proposed block.
     max_block_size: u64,
+    // Max number of transactions of each lane to be added to a proposed block, set on-chain.
+    block_lane_caps: BlockLaneCaps,
     // Last round that a proposal was generated
     last_round_generated: Mutex<Round>,
 }
@@ -51,6 +54,7 @@ impl ProposalGenerator {
         txn_manager: Arc<dyn TxnManager>,
         time_service: Arc<dyn TimeService>,
         max_block_size: u64,
+        block_lane_caps: BlockLaneCaps,
     ) -> Self {
         Self {
             author,
@@ -58,6 +62,7 @@ impl ProposalGenerator {
             txn_manager,
             time_service,
             max_block_size,
+            block_lane_caps,
             last_round_generated: Mutex::new(0),
         }
     }
@@ -123,7 +128,7 @@ impl ProposalGenerator {
 
             let payload = self
                 .txn_manager
-                .pull_txns(self.max_block_size, exclude_payload)
+                .pull_txns(self.max_block_size, self.block_lane_caps, exclude_payload)
                 .await
                 .context("Fail to retrieve txn")?;
 
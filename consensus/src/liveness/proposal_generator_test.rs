@@ -8,7 +8,7 @@ use crate::{
     util::mock_time_service::SimulatedTimeService,
 };
 use consensus_types::block::{block_test_utils::certificate_for_genesis, Block};
-use diem_types::validator_signer::ValidatorSigner;
+use diem_types::{on_chain_config::BlockLaneCaps, validator_signer::ValidatorSigner};
 use std::sync::Arc;
 
 #[tokio::test]
@@ -21,6 +21,7 @@ async fn test_proposal_generation_empty_tree() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        BlockLaneCaps::default(),
     );
     let genesis = block_store.ordered_root();
 
@@ -46,6 +47,7 @@ async fn test_proposal_generation_parent() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        BlockLaneCaps::default(),
     );
     let genesis = block_store.ordered_root();
     let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
@@ -87,6 +89,7 @@ async fn test_old_proposal_generation() {
         Arc::new(MockTransactionManager::new(None)),
         Arc::new(SimulatedTimeService::new()),
         1,
+        BlockLaneCaps::default(),
     );
     let genesis = block_store.ordered_root();
     let a1 = inserter.insert_block_with_qc(certificate_for_genesis(), &genesis, 1);
@@ -8,7 +8,7 @@ use crate::{
 };
 use consensus_types::{common::Round, sync_info::SyncInfo, vote::Vote};
 use diem_logger::{prelude::*, Schema};
-use diem_types::validator_verifier::ValidatorVerifier;
+use diem_types::{on_chain_config::RoundTimeoutCurve, validator_verifier::ValidatorVerifier};
 use serde::Serialize;
 use std::{fmt, sync::Arc, time::Duration};
 
@@ -37,6 +37,19 @@ pub struct NewRoundEvent {
     pub round: Round,
     pub reason: NewRoundReason,
     pub timeout: Duration,
+    /// What was seen of the previous round, if it timed out while this node was in it.
+    pub timed_out_round: Option<TimedOutRound>,
+}
+
+/// What a node saw of a round that ended with a timeout certificate.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TimedOutRound {
+    pub round: Round,
+    pub proposal_received: bool,
+    /// The highest voting power of the votes for a same block.
+    pub voting_power: u64,
+    /// The voting power of the timeout votes.
+    pub timeout_voting_power: u64,
 }
 
 impl fmt::Display for NewRoundEvent {
@@ -102,6 +115,24 @@ impl ExponentialTimeInterval {
             max_exponent,
         }
     }
+
+    /// Follows the curve set on-chain, or returns None if the curve is out of the bounds checked
+    /// by `new`, has a zero base timeout, or shrinks the timeout from round to round.
+    pub fn from_curve(curve: &RoundTimeoutCurve) -> Option<Self> {
+        let exponent_base = curve.exponent_base_percent as f64 / 100.0;
+        if curve.base_timeout_ms == 0
+            || curve.exponent_base_percent < 100
+            || curve.max_exponent >= 32
+            || exponent_base.powf(curve.max_exponent as f64).ceil() >= f64::from(std::u32::MAX)
+        {
+            return None;
+        }
+        Some(Self::new(
+            Duration::from_millis(curve.base_timeout_ms),
+            exponent_base,
+            curve.max_exponent as usize,
+        ))
+    }
 }
 
 impl RoundTimeInterval for ExponentialTimeInterval {
@@ -149,6 +180,8 @@ pub struct RoundState {
     pending_votes: PendingVotes,
     // Vote sent locally for the current round.
     vote_sent: Option<Vote>,
+    // Whether a valid proposal was received for the current round.
+    proposal_received: bool,
 }
 
 #[derive(Default, Schema)]
@@ -196,6 +229,7 @@ impl RoundState {
             timeout_sender,
             pending_votes: PendingVotes::new(),
             vote_sent: None,
+            proposal_received: false,
         }
     }
 
@@ -229,11 +263,6 @@ impl RoundState {
         }
         let new_round = sync_info.highest_round() + 1;
         if new_round > self.current_round {
-            // Start a new round.
-            self.current_round = new_round;
-            self.pending_votes = PendingVotes::new();
-            self.vote_sent = None;
-            let timeout = self.setup_timeout();
             // The new round reason is QCReady in case both QC.round + 1 == new_round, otherwise
             // it's Timeout and TC.round + 1 == new_round.
             let new_round_reason = if sync_info.highest_certified_round() + 1 == new_round {
@@ -241,10 +270,29 @@ impl RoundState {
             } else {
                 NewRoundReason::Timeout
             };
+            let timed_out_round = if new_round_reason == NewRoundReason::Timeout
+                && self.current_round + 1 == new_round
+            {
+                Some(TimedOutRound {
+                    round: self.current_round,
+                    proposal_received: self.proposal_received,
+                    voting_power: self.pending_votes.highest_voting_power(),
+                    timeout_voting_power: self.pending_votes.timeout_voting_power(),
+                })
+            } else {
+                None
+            };
+            // Start a new round.
+            self.current_round = new_round;
+            self.pending_votes = PendingVotes::new();
+            self.vote_sent = None;
+            self.proposal_received = false;
+            let timeout = self.setup_timeout();
             let new_round_event = NewRoundEvent {
                 round: self.current_round,
                 reason: new_round_reason,
                 timeout,
+                timed_out_round,
             };
             debug!(round = new_round, "Starting new round: {}", new_round_event);
             return Some(new_round_event);
@@ -277,6 +325,12 @@ impl RoundState {
         self.vote_sent.clone()
     }
 
+    pub fn record_proposal(&mut self, round: Round) {
+        if round == self.current_round {
+            self.proposal_received = true;
+        }
+    }
+
     /// Setup the timeout task and return the duration of the current timeout
     fn setup_timeout(&mut self) -> Duration {
         let timeout_sender = self.timeout_sender.clone();
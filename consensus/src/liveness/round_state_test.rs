@@ -4,6 +4,7 @@
 use crate::{
     liveness::round_state::{
         ExponentialTimeInterval, NewRoundEvent, NewRoundReason, RoundState, RoundTimeInterval,
+        TimedOutRound,
     },
     util::mock_time_service::SimulatedTimeService,
 };
@@ -16,6 +17,7 @@ use diem_crypto::HashValue;
 use diem_types::{
     block_info::BlockInfo,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    on_chain_config::RoundTimeoutCurve,
 };
 use futures::StreamExt;
 use std::{collections::BTreeMap, sync::Arc, time::Duration};
@@ -33,6 +35,45 @@ fn test_round_time_interval() {
     assert_eq!(6750, interval.get_round_duration(1000).as_millis());
 }
 
+#[test]
+fn test_round_time_interval_from_curve() {
+    let curve = RoundTimeoutCurve {
+        base_timeout_ms: 3000,
+        exponent_base_percent: 150,
+        max_exponent: 2,
+    };
+    let interval = ExponentialTimeInterval::from_curve(&curve).unwrap();
+    assert_eq!(3000, interval.get_round_duration(0).as_millis());
+    assert_eq!(6750, interval.get_round_duration(1000).as_millis());
+
+    // Curves that would make the node panic or never time out are refused
+    for curve in &[
+        RoundTimeoutCurve {
+            base_timeout_ms: 0,
+            ..curve
+        },
+        RoundTimeoutCurve {
+            exponent_base_percent: 0,
+            ..curve
+        },
+        RoundTimeoutCurve {
+            exponent_base_percent: 99,
+            ..curve
+        },
+        RoundTimeoutCurve {
+            max_exponent: 32,
+            ..curve
+        },
+        RoundTimeoutCurve {
+            exponent_base_percent: 10_000,
+            max_exponent: 6,
+            ..curve
+        },
+    ] {
+        assert!(ExponentialTimeInterval::from_curve(curve).is_none());
+    }
+}
+
 #[tokio::test]
 /// Verify that RoundState properly outputs local timeout events upon timeout
 async fn test_basic_timeout() {
@@ -78,6 +119,47 @@ fn test_round_event_generation() {
     );
 }
 
+#[test]
+fn test_timed_out_round() {
+    let (mut pm, _) = make_round_state();
+    expect_qc(
+        2,
+        pm.process_certificates(generate_sync_info(Some(1), None, None)),
+    );
+    // A proposal for another round is ignored
+    pm.record_proposal(3);
+    let event = pm
+        .process_certificates(generate_sync_info(Some(1), Some(2), None))
+        .unwrap();
+    assert_eq!(
+        event.timed_out_round,
+        Some(TimedOutRound {
+            round: 2,
+            proposal_received: false,
+            voting_power: 0,
+            timeout_voting_power: 0,
+        })
+    );
+
+    pm.record_proposal(3);
+    let event = pm
+        .process_certificates(generate_sync_info(Some(1), Some(3), None))
+        .unwrap();
+    assert!(event.timed_out_round.unwrap().proposal_received);
+
+    // Rounds this node wasn't in aren't reported
+    let event = pm
+        .process_certificates(generate_sync_info(Some(1), Some(6), None))
+        .unwrap();
+    assert_eq!(event.reason, NewRoundReason::Timeout);
+    assert_eq!(event.timed_out_round, None);
+    // Neither are rounds ending with a QC
+    let event = pm
+        .process_certificates(generate_sync_info(Some(7), Some(6), None))
+        .unwrap();
+    assert_eq!(event.timed_out_round, None);
+}
+
 fn make_round_state() -> (RoundState, channel::Receiver<Round>) {
     let time_interval = Box::new(ExponentialTimeInterval::fixed(Duration::from_millis(2)));
     let simulated_time = SimulatedTimeService::auto_advance_until(Duration::from_millis(4));
@@ -0,0 +1,114 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rounds that ended with a timeout certificate, recorded for operators to attribute liveness
+//! issues to the validators involved.
+
+use crate::{
+    counters,
+    liveness::round_state::TimedOutRound,
+    logging::{LogEvent, LogSchema},
+};
+use consensus_types::common::{Author, Round};
+use diem_infallible::Mutex;
+use diem_logger::prelude::*;
+use serde::Serialize;
+use std::{collections::VecDeque, sync::Arc};
+
+/// Number of timed out rounds kept.
+const MAX_ROUND_TIMEOUTS: usize = 1_000;
+
+/// Why a round ended with a timeout certificate, as seen by this node.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundTimeoutReason {
+    /// No valid proposal was received, e.g. because the proposer is down or partitioned.
+    NoProposal,
+    /// A proposal was received, but not voted for by a quorum.
+    NoQuorum,
+}
+
+impl RoundTimeoutReason {
+    /// The label of the reason in metrics and logs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RoundTimeoutReason::NoProposal => "no_proposal",
+            RoundTimeoutReason::NoQuorum => "no_quorum",
+        }
+    }
+}
+
+/// A round that ended with a timeout certificate while this node was in it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct RoundTimeout {
+    /// The epoch of the round.
+    pub epoch: u64,
+    /// The round that timed out.
+    pub round: Round,
+    /// Why the round timed out.
+    pub reason: RoundTimeoutReason,
+    /// The validator expected to propose in the round.
+    pub proposer: Author,
+    /// The highest voting power of the votes received for a same block.
+    pub voting_power: u64,
+    /// The voting power of the timeout votes received.
+    pub timeout_voting_power: u64,
+    /// The voting power of a quorum in the epoch.
+    pub quorum_voting_power: u64,
+}
+
+impl RoundTimeout {
+    pub(crate) fn new(
+        epoch: u64,
+        timed_out_round: &TimedOutRound,
+        proposer: Author,
+        quorum_voting_power: u64,
+    ) -> Self {
+        let reason = if timed_out_round.proposal_received {
+            RoundTimeoutReason::NoQuorum
+        } else {
+            RoundTimeoutReason::NoProposal
+        };
+        Self {
+            epoch,
+            round: timed_out_round.round,
+            reason,
+            proposer,
+            voting_power: timed_out_round.voting_power,
+            timeout_voting_power: timed_out_round.timeout_voting_power,
+            quorum_voting_power,
+        }
+    }
+}
+
+/// The latest rounds that timed out, shared by the round managers of successive epochs.
+#[derive(Clone, Default)]
+pub struct RoundTimeouts(Arc<Mutex<VecDeque<RoundTimeout>>>);
+
+impl RoundTimeouts {
+    pub(crate) fn record(&self, round_timeout: RoundTimeout) {
+        warn!(
+            LogSchema::new(LogEvent::RoundTimeout)
+                .epoch(round_timeout.epoch)
+                .round(round_timeout.round)
+                .remote_peer(round_timeout.proposer),
+            reason = round_timeout.reason.as_str(),
+            voting_power = round_timeout.voting_power,
+            timeout_voting_power = round_timeout.timeout_voting_power,
+            quorum_voting_power = round_timeout.quorum_voting_power,
+        );
+        counters::TIMED_OUT_ROUNDS
+            .with_label_values(&[round_timeout.reason.as_str()])
+            .inc();
+        let mut round_timeouts = self.0.lock();
+        if round_timeouts.len() == MAX_ROUND_TIMEOUTS {
+            round_timeouts.pop_front();
+        }
+        round_timeouts.push_back(round_timeout);
+    }
+
+    /// The latest rounds that timed out, oldest first.
+    pub fn read(&self) -> Vec<RoundTimeout> {
+        self.0.lock().iter().cloned().collect()
+    }
+}
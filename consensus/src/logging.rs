@@ -30,6 +30,7 @@ pub enum LogEvent {
     ReceiveSyncInfo,
     ReceiveVote,
     RetrieveBlock,
+    RoundTimeout,
     StateSync,
     SyncToPeer,
     Timeout,
@@ -59,6 +59,10 @@ pub struct PendingVotes {
     maybe_partial_2chain_tc: Option<TwoChainTimeoutCertificate>,
     /// Map of Author to vote. This is useful to discard multiple votes.
     author_to_vote: HashMap<Author, Vote>,
+    /// The highest voting power of the votes for a same ledger info, short of a quorum.
+    highest_voting_power: u64,
+    /// The voting power of the authors of timeout votes.
+    timeout_voting_power: u64,
 }
 
 impl PendingVotes {
@@ -69,9 +73,21 @@ impl PendingVotes {
             maybe_partial_tc: None,
             maybe_partial_2chain_tc: None,
             author_to_vote: HashMap::new(),
+            highest_voting_power: 0,
+            timeout_voting_power: 0,
         }
     }
 
+    /// The highest voting power of the votes for a same ledger info, if no QC could be formed.
+    pub fn highest_voting_power(&self) -> u64 {
+        self.highest_voting_power
+    }
+
+    /// The voting power of the authors of the timeout votes.
+    pub fn timeout_voting_power(&self) -> u64 {
+        self.timeout_voting_power
+    }
+
     /// Insert a vote and if the vote is valid, return a QuorumCertificate preferentially over a
     /// TimeoutCertificate if either can can be formed
     pub fn insert_vote(
@@ -113,6 +129,11 @@ impl PendingVotes {
         //
 
         self.author_to_vote.insert(vote.author(), vote.clone());
+        if vote.is_timeout() {
+            self.timeout_voting_power += validator_verifier
+                .get_voting_power(&vote.author())
+                .unwrap_or(0);
+        }
 
         //
         // 3. Let's check if we can create a QC
@@ -139,7 +160,10 @@ impl PendingVotes {
                 }
 
                 // not enough votes
-                Err(VerifyError::TooLittleVotingPower { voting_power, .. }) => voting_power,
+                Err(VerifyError::TooLittleVotingPower { voting_power, .. }) => {
+                    self.highest_voting_power = self.highest_voting_power.max(voting_power);
+                    voting_power
+                }
 
                 // error
                 Err(error) => {
@@ -12,6 +12,7 @@ use crate::{
         proposal_generator::ProposalGenerator,
         proposer_election::ProposerElection,
         round_state::{NewRoundEvent, NewRoundReason, RoundState},
+        round_timeouts::{RoundTimeout, RoundTimeouts},
     },
     logging::{LogEvent, LogSchema},
     metrics_safety_rules::MetricsSafetyRules,
@@ -228,6 +229,7 @@ pub struct RoundManager {
     decoupled_execution: bool,
     back_pressure_limit: u64,
     onchain_config: OnChainConsensusConfig,
+    round_timeouts: RoundTimeouts,
 }
 
 impl RoundManager {
@@ -243,6 +245,7 @@ impl RoundManager {
         storage: Arc<dyn PersistentLivenessStorage>,
         sync_only: bool,
         onchain_config: OnChainConsensusConfig,
+        round_timeouts: RoundTimeouts,
     ) -> Self {
         // when decoupled execution is false,
         // the counter is still static.
@@ -267,6 +270,7 @@ impl RoundManager {
             decoupled_execution: false,
             back_pressure_limit: 1, // arbitrary dummy value
             onchain_config,
+            round_timeouts,
         }
     }
 
@@ -284,6 +288,7 @@ impl RoundManager {
         back_pressure: Arc<AtomicU64>,
         back_pressure_limit: u64,
         onchain_config: OnChainConsensusConfig,
+        round_timeouts: RoundTimeouts,
     ) -> Self {
         Self {
             epoch_state,
@@ -300,6 +305,7 @@ impl RoundManager {
             decoupled_execution: true,
             back_pressure_limit,
             onchain_config,
+            round_timeouts,
         }
     }
 
@@ -335,6 +341,15 @@ impl RoundManager {
             }
             NewRoundReason::Timeout => {
                 counters::TIMEOUT_ROUNDS_COUNT.inc();
+                if let Some(timed_out_round) = &new_round_event.timed_out_round {
+                    self.round_timeouts.record(RoundTimeout::new(
+                        self.epoch_state.epoch,
+                        timed_out_round,
+                        self.proposer_election
+                            .get_valid_proposer(timed_out_round.round),
+                        self.epoch_state.verifier.quorum_voting_power(),
+                    ));
+                }
             }
         };
         debug!(
@@ -642,6 +657,7 @@ impl RoundManager {
             author,
             proposal,
         );
+        self.round_state.record_proposal(proposal.round());
 
         let block_time_since_epoch = Duration::from_micros(proposal.timestamp_usecs());
 
@@ -7,6 +7,7 @@ use crate::{
         proposal_generator::ProposalGenerator,
         rotating_proposer_election::RotatingProposer,
         round_state::{ExponentialTimeInterval, NewRoundEvent, NewRoundReason, RoundState},
+        round_timeouts::RoundTimeouts,
     },
     metrics_safety_rules::MetricsSafetyRules,
     network::NetworkSender,
@@ -23,7 +24,7 @@ use diem_types::{
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    on_chain_config::{OnChainConsensusConfig, ValidatorSet},
+    on_chain_config::{BlockLaneCaps, OnChainConsensusConfig, ValidatorSet},
     validator_info::ValidatorInfo,
     validator_signer::ValidatorSigner,
     validator_verifier::ValidatorVerifier,
@@ -47,6 +48,7 @@ pub fn generate_corpus_proposal() -> Vec<u8> {
                 round: 1,
                 reason: NewRoundReason::QCReady,
                 timeout: std::time::Duration::new(5, 0),
+                timed_out_round: None,
             })
             .await;
         // serialize and return proposal
@@ -144,6 +146,7 @@ fn create_node_for_fuzzing() -> RoundManager {
         Arc::new(MockTransactionManager::new(None)),
         time_service,
         1,
+        BlockLaneCaps::default(),
     );
 
     //
@@ -168,6 +171,7 @@ fn create_node_for_fuzzing() -> RoundManager {
         storage,
         false,
         OnChainConsensusConfig::default(),
+        RoundTimeouts::default(),
     )
 }
 
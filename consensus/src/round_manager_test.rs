@@ -8,6 +8,7 @@ use crate::{
         proposer_election::ProposerElection,
         rotating_proposer_election::RotatingProposer,
         round_state::{ExponentialTimeInterval, RoundState},
+        round_timeouts::RoundTimeouts,
     },
     metrics_safety_rules::MetricsSafetyRules,
     network::{IncomingBlockRetrievalRequest, NetworkSender},
@@ -41,7 +42,7 @@ use diem_secure_storage::Storage;
 use diem_types::{
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    on_chain_config::OnChainConsensusConfig,
+    on_chain_config::{BlockLaneCaps, OnChainConsensusConfig},
     validator_signer::ValidatorSigner,
     validator_verifier::random_validator_verifier,
     waypoint::Waypoint,
@@ -202,6 +203,7 @@ impl NodeSetup {
             Arc::new(MockTransactionManager::new(None)),
             time_service.clone(),
             1,
+            BlockLaneCaps::default(),
         );
 
         let round_state = Self::create_round_state(time_service);
@@ -222,6 +224,7 @@ impl NodeSetup {
             storage.clone(),
             false,
             OnChainConsensusConfig::default(),
+            RoundTimeouts::default(),
         );
         block_on(round_manager.start(last_vote_sent));
         Self {
@@ -5,7 +5,7 @@ use crate::error::{MempoolError, StateSyncError};
 use anyhow::Result;
 use consensus_types::{block::Block, common::Payload, executed_block::ExecutedBlock};
 use diem_crypto::HashValue;
-use diem_types::ledger_info::LedgerInfoWithSignatures;
+use diem_types::{ledger_info::LedgerInfoWithSignatures, on_chain_config::BlockLaneCaps};
 use executor_types::{Error as ExecutionError, StateComputeResult};
 use std::sync::Arc;
 
@@ -20,11 +20,13 @@ pub fn empty_state_computer_call_back() -> StateComputerCommitCallBackType {
 #[async_trait::async_trait]
 pub trait TxnManager: Send + Sync {
     /// Brings new transactions to be applied.
+    /// The `lane_caps` limit the number of governance and user transactions brought.
     /// The `exclude_txns` list includes the transactions that are already pending in the
     /// branch of blocks consensus is trying to extend.
     async fn pull_txns(
         &self,
         max_size: u64,
+        lane_caps: BlockLaneCaps,
         exclude: Vec<&Payload>,
     ) -> Result<Payload, MempoolError>;
 
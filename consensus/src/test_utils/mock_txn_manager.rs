@@ -9,6 +9,7 @@ use consensus_types::{
 };
 use diem_mempool::ConsensusRequest;
 use diem_types::{
+    on_chain_config::BlockLaneCaps,
     transaction::TransactionStatus,
     vm_status::{KeptVMStatus, StatusCode},
 };
@@ -53,6 +54,7 @@ impl TxnManager for MockTransactionManager {
     async fn pull_txns(
         &self,
         _max_size: u64,
+        _lane_caps: BlockLaneCaps,
         _exclude_txns: Vec<&Payload>,
     ) -> Result<Payload, MempoolError> {
         // generate 1k txn is too slow with coverage instrumentation
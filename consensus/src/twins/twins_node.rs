@@ -88,7 +88,7 @@ impl SMRNode {
             ValidatorSet::CONFIG_ID,
             bcs::to_bytes(storage.get_validator_set()).unwrap(),
         );
-        let payload = OnChainConfigPayload::new(1, Arc::new(configs));
+        let payload = OnChainConfigPayload::new(1, 0, Arc::new(configs));
         reconfig_sender.push((), payload).unwrap();
 
         let runtime = Builder::new_multi_thread()
@@ -7,7 +7,7 @@ use consensus_types::{block::Block, common::Payload};
 use diem_logger::prelude::*;
 use diem_mempool::{ConsensusRequest, ConsensusResponse, TransactionSummary};
 use diem_metrics::monitor;
-use diem_types::transaction::TransactionStatus;
+use diem_types::{on_chain_config::BlockLaneCaps, transaction::TransactionStatus};
 use executor_types::StateComputeResult;
 use fail::fail_point;
 use futures::channel::{mpsc, oneshot};
@@ -50,10 +50,12 @@ impl MempoolProxy {
     async fn pull_internal(
         &self,
         max_size: u64,
+        lane_caps: BlockLaneCaps,
         exclude_txns: Vec<TransactionSummary>,
     ) -> Result<Payload, MempoolError> {
         let (callback, callback_rcv) = oneshot::channel();
-        let req = ConsensusRequest::GetBlockRequest(max_size, exclude_txns.clone(), callback);
+        let req =
+            ConsensusRequest::GetBlockRequest(max_size, lane_caps, exclude_txns.clone(), callback);
         // send to shared mempool
         self.consensus_to_mempool_sender
             .clone()
@@ -86,6 +88,7 @@ impl TxnManager for MempoolProxy {
     async fn pull_txns(
         &self,
         max_size: u64,
+        lane_caps: BlockLaneCaps,
         exclude_payloads: Vec<&Payload>,
     ) -> Result<Payload, MempoolError> {
         fail_point!("consensus::pull_txns", |_| {
@@ -105,7 +108,9 @@ impl TxnManager for MempoolProxy {
         let mut count = self.poll_count;
         let txns = loop {
             count -= 1;
-            let txns = self.pull_internal(max_size, exclude_txns.clone()).await?;
+            let txns = self
+                .pull_internal(max_size, lane_caps, exclude_txns.clone())
+                .await?;
             if txns.is_empty() && no_pending_txns && count > 0 {
                 sleep(Duration::from_millis(NO_TXN_DELAY)).await;
                 continue;
@@ -638,6 +638,19 @@ impl NoiseSession {
         self.remote_public_key
     }
 
+    /// derives an independent session from this one, e.g. for another stream of the same
+    /// connection. Both peers derive matching sessions from the same `label`, which must not be
+    /// used for more than one stream in each direction as the nonces of the new session start
+    /// over from zero
+    pub fn derive_session(&self, label: &[u8]) -> Result<Self, NoiseError> {
+        if !self.valid {
+            return Err(NoiseError::SessionClosed);
+        }
+        let (write_key, _) = hkdf(&self.write_key, Some(label))?;
+        let (read_key, _) = hkdf(&self.read_key, Some(label))?;
+        Ok(Self::new(write_key, read_key, self.remote_public_key))
+    }
+
     /// encrypts a message for the other peers (post-handshake)
     /// the function encrypts in place, and returns the authentication tag as result
     pub fn write_message_in_place(&mut self, message: &mut [u8]) -> Result<Vec<u8>, NoiseError> {
@@ -105,6 +105,26 @@ fn simple_handshake() {
             };
             assert_eq!(received_message, message_sent.as_slice());
         }
+
+        // derived sessions only match for the same label
+        let mut initiator_derived = initiator_session.derive_session(b"label").unwrap();
+        let mut responder_derived = responder_session.derive_session(b"label").unwrap();
+        let mut message = message_sent.clone();
+        let auth_tag = initiator_derived
+            .write_message_in_place(&mut message)
+            .unwrap();
+        message.extend_from_slice(&auth_tag);
+        let mut other_message = message.clone();
+        assert_eq!(
+            responder_derived
+                .read_message_in_place(&mut message)
+                .unwrap(),
+            message_sent.as_slice()
+        );
+        let mut responder_other = responder_session.derive_session(b"other").unwrap();
+        assert!(responder_other
+            .read_message_in_place(&mut other_message)
+            .is_err());
     }
 }
 
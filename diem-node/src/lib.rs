@@ -15,14 +15,20 @@ use diem_mempool::gen_mempool_reconfig_subscription;
 use diem_metrics::metric_server;
 use diem_time_service::TimeService;
 use diem_types::{
-    account_config::diem_root_address, account_state::AccountState, chain_id::ChainId,
-    move_resource::MoveStorage, on_chain_config::VMPublishingOption,
+    account_config::{diem_root_address, CORE_CODE_ADDRESS},
+    account_state::AccountState,
+    chain_id::ChainId,
+    move_resource::MoveStorage,
+    on_chain_config::VMPublishingOption,
 };
 use diem_vm::DiemVM;
 use diemdb::DiemDB;
-use executor::{db_bootstrapper::maybe_bootstrap, Executor};
+use executor::{
+    db_bootstrapper::maybe_bootstrap, execution_trace::ExecutionTraceRecorder, Executor,
+};
 use executor_types::ChunkExecutor;
 use futures::{channel::mpsc::channel, executor::block_on};
+use network::protocols::peer_monitor::{self, PeerMonitoringStorage, SyncedVersionProvider};
 use network_builder::builder::NetworkBuilder;
 use state_sync_v1::bootstrapper::StateSyncBootstrapper;
 use std::{
@@ -36,7 +42,7 @@ use std::{
         Arc,
     },
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
 use storage_interface::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
@@ -57,9 +63,12 @@ pub struct DiemHandle {
     _backup: Runtime,
 }
 
-pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
-    crash_handler::setup_panic_handler();
+pub struct ReadReplicaHandle {
+    _rpc: Runtime,
+    _debug: NodeDebugService,
+}
 
+fn setup_logger(config: &NodeConfig, log_file: Option<PathBuf>) -> Option<Arc<Logger>> {
     let mut logger = diem_logger::Logger::new();
     logger
         .channel_size(config.logger.chan_size)
@@ -69,7 +78,13 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
     if let Some(log_file) = log_file {
         logger.printer(Box::new(FileWriter::new(log_file)));
     }
-    let logger = Some(logger.build());
+    Some(logger.build())
+}
+
+pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
+    crash_handler::setup_panic_handler();
+
+    let logger = setup_logger(config, log_file);
 
     // Let's now log some important information, since the logger is set up
     info!(config = config, "Loaded DiemNode config");
@@ -93,6 +108,23 @@ pub fn start(config: &NodeConfig, log_file: Option<PathBuf>) {
     }
 }
 
+/// Starts serving JSON-RPC from a read replica of the DB of the node running on the same host,
+/// instead of starting a node. Transactions can't be submitted to the replica, which runs no
+/// mempool.
+pub fn start_read_replica(config: &NodeConfig) {
+    crash_handler::setup_panic_handler();
+
+    let logger = setup_logger(config, None);
+    info!(config = config, "Loaded DiemNode config for read replica");
+
+    let _replica_handle = setup_read_replica_environment(config, logger);
+    let term = Arc::new(AtomicBool::new(false));
+
+    while !term.load(Ordering::Acquire) {
+        std::thread::park();
+    }
+}
+
 pub fn load_test_environment<R>(
     config_path: Option<PathBuf>,
     random_ports: bool,
@@ -191,8 +223,57 @@ fn fetch_chain_id(db: &DbReaderWriter) -> ChainId {
         .chain_id()
 }
 
-fn setup_chunk_executor(db: DbReaderWriter) -> Box<dyn ChunkExecutor> {
-    Box::new(Executor::<DiemVM>::new(db))
+/// Logs which Diem Framework release is published on-chain, to detect nodes running a framework
+/// that drifted from the known releases.
+fn log_diem_framework_release(db: &DbReaderWriter) {
+    let module_blobs = (&*db.reader)
+        .fetch_synced_version()
+        .and_then(|version| {
+            db.reader
+                .get_account_state_with_proof_by_version(CORE_CODE_ADDRESS, version)
+        })
+        .and_then(|(blob, _proof)| match blob {
+            Some(blob) => Ok(AccountState::try_from(&blob)?
+                .get_modules()
+                .cloned()
+                .collect::<Vec<_>>()),
+            None => Ok(vec![]),
+        });
+    let module_blobs = match module_blobs {
+        Ok(module_blobs) => module_blobs,
+        Err(err) => {
+            warn!(error = ?err, "Failed to read the on-chain Diem Framework modules");
+            return;
+        }
+    };
+    match diem_framework_releases::find_release_for_modules(&module_blobs) {
+        Ok(Some(manifest)) => info!(
+            release = manifest.name,
+            version = manifest.version.map(|version| version.to_string()),
+            "On-chain Diem Framework matches a known release"
+        ),
+        Ok(None) => warn!(
+            module_count = module_blobs.len(),
+            "On-chain Diem Framework matches no known release"
+        ),
+        Err(err) => warn!(error = ?err, "Failed to identify the on-chain Diem Framework release"),
+    }
+}
+
+fn setup_chunk_executor(db: DbReaderWriter, config: &NodeConfig) -> Box<dyn ChunkExecutor> {
+    let executor = Executor::<DiemVM>::new(db);
+    match &config.execution.trace {
+        Some(trace_config) => Box::new(
+            executor.with_trace_recorder(
+                ExecutionTraceRecorder::open(
+                    &trace_config.path("chunk_executor"),
+                    trace_config.versions(),
+                )
+                .expect("Unable to open execution trace"),
+            ),
+        ),
+        None => Box::new(executor),
+    }
 }
 
 fn setup_debug_interface(config: &NodeConfig, logger: Option<Arc<Logger>>) -> NodeDebugService {
@@ -252,6 +333,41 @@ async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
     }
 }
 
+pub fn setup_read_replica_environment(
+    node_config: &NodeConfig,
+    logger: Option<Arc<Logger>>,
+) -> ReadReplicaHandle {
+    let debug_if = setup_debug_interface(node_config, logger);
+
+    let metrics_port = node_config.debug_interface.metrics_server_port;
+    let metric_host = node_config.debug_interface.address.clone();
+    thread::spawn(move || metric_server::start_server(metric_host, metrics_port, false));
+
+    let instant = Instant::now();
+    let diem_db = DiemDB::open_as_read_replica(
+        node_config.storage.dir(),
+        node_config.storage.read_replica_dir(),
+        Duration::from_millis(node_config.storage.read_replica.catch_up_interval_ms),
+        node_config.storage.rocksdb_config,
+    )
+    .expect("DB should open as a read replica.");
+    let (diem_db, db_rw) = DbReaderWriter::wrap(diem_db);
+    debug!(
+        "Read replica opened in {} ms",
+        instant.elapsed().as_millis()
+    );
+
+    let chain_id = fetch_chain_id(&db_rw);
+    // Submitted transactions are rejected, as there's no mempool receiving them.
+    let (mp_client_sender, _) = channel(AC_SMP_CHANNEL_BUFFER_SIZE);
+    let rpc_runtime = bootstrap_rpc(node_config, chain_id, diem_db, mp_client_sender);
+
+    ReadReplicaHandle {
+        _rpc: rpc_runtime,
+        _debug: debug_if,
+    }
+}
+
 pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> DiemHandle {
     let debug_if = setup_debug_interface(node_config, logger);
 
@@ -265,19 +381,31 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     });
 
     let mut instant = Instant::now();
-    let (diem_db, db_rw) = DbReaderWriter::wrap(
-        DiemDB::open(
-            &node_config.storage.dir(),
-            false, /* readonly */
-            node_config.storage.prune_window,
-            node_config.storage.rocksdb_config,
-        )
-        .expect("DB should open."),
-    );
+    let mut diem_db = DiemDB::open_with_ledger_prune_windows(
+        &node_config.storage.dir(),
+        false, /* readonly */
+        node_config.storage.prune_window,
+        node_config.storage.ledger_prune_windows,
+        node_config.storage.rocksdb_config,
+    )
+    .expect("DB should open.");
+    if let Some(cold_storage_config) = &node_config.storage.cold_storage {
+        diem_db
+            .enable_cold_storage(cold_storage_config)
+            .expect("Cold storage should be enabled.");
+    }
+    if let Some(interval) = node_config.storage.state_checkpoint_interval {
+        diem_db
+            .enable_state_checkpoints(interval)
+            .expect("State checkpoints should be enabled.");
+    }
+    let (diem_db, db_rw) = DbReaderWriter::wrap(diem_db);
     let _simple_storage_service = start_storage_service_with_db(node_config, Arc::clone(&diem_db));
     let backup_service = start_backup_service(
         node_config.storage.backup_service_address,
         Arc::clone(&diem_db),
+        &node_config.load_shedding,
+        node_config.storage.checkpoint_dir(),
     );
 
     let genesis_waypoint = node_config.base.waypoint.genesis_waypoint();
@@ -295,18 +423,26 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     );
 
     instant = Instant::now();
-    let chunk_executor = setup_chunk_executor(db_rw.clone());
+    let chunk_executor = setup_chunk_executor(db_rw.clone(), node_config);
     debug!(
         "ChunkExecutor setup in {} ms",
         instant.elapsed().as_millis()
     );
     let chain_id = fetch_chain_id(&db_rw);
+    log_diem_framework_release(&db_rw);
     let mut network_runtimes = vec![];
     let mut state_sync_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
     let mut reconfig_subscriptions = vec![];
 
+    // The peer monitors of all networks share their view of the connected peers.
+    let peer_monitoring_storage = Arc::new(PeerMonitoringStorage::new());
+    let synced_version: SyncedVersionProvider = {
+        let reader = Arc::clone(&db_rw.reader);
+        Arc::new(move || reader.get_latest_version().ok())
+    };
+
     let (mempool_reconfig_subscription, mempool_reconfig_events) =
         gen_mempool_reconfig_subscription();
     reconfig_subscriptions.push(mempool_reconfig_subscription);
@@ -343,6 +479,12 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             TimeService::real(),
         );
         let network_id = network_config.network_id.clone();
+        network_builder.add_peer_monitoring(
+            network_config.peer_monitoring_interval_ms,
+            network_config.peer_monitoring_timeout_ms,
+            peer_monitoring_storage.clone(),
+            synced_version.clone(),
+        );
 
         // Create the endpoints to connect the Network to State Sync.
         let (state_sync_sender, state_sync_events) =
@@ -400,6 +542,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     // Create state sync bootstrapper
     let state_sync_bootstrapper = StateSyncBootstrapper::bootstrap(
         state_sync_network_handles,
+        peer_monitoring_storage.clone(),
         mempool_notifier,
         consensus_listener,
         Arc::clone(&db_rw.reader),
@@ -416,7 +559,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
     let (consensus_to_mempool_sender, consensus_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
 
     instant = Instant::now();
-    let mempool = diem_mempool::bootstrap(
+    let (mempool, mempool_summary_reader) = diem_mempool::bootstrap(
         node_config,
         Arc::clone(&db_rw.reader),
         mempool_network_handles,
@@ -426,6 +569,13 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
         mempool_reconfig_events,
     );
     debug!("Mempool started in {} ms", instant.elapsed().as_millis());
+    debug_if.set_mempool_summary_provider(Box::new(move || {
+        serde_json::to_value(mempool_summary_reader.read()).unwrap_or_default()
+    }));
+    debug_if.set_peers_provider(Box::new(move || {
+        serde_json::to_value(peer_monitor::monitored_peers(&peer_monitoring_storage))
+            .unwrap_or_default()
+    }));
 
     // StateSync should be instantiated and started before Consensus to avoid a cyclic dependency:
     // network provider -> consensus -> state synchronizer -> network provider.  This has resulted
@@ -444,7 +594,7 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
 
         // Initialize and start consensus.
         instant = Instant::now();
-        consensus_runtime = Some(start_consensus(
+        let (runtime, round_timeouts) = start_consensus(
             node_config,
             consensus_network_sender,
             consensus_network_events,
@@ -452,8 +602,12 @@ pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>)
             consensus_to_mempool_sender,
             diem_db,
             consensus_reconfig_events,
-        ));
+        );
+        consensus_runtime = Some(runtime);
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
+        debug_if.set_consensus_timeouts_provider(Box::new(move || {
+            serde_json::to_value(round_timeouts.read()).unwrap_or_default()
+        }));
     }
 
     // Spawn a task which will periodically dump some interesting state
@@ -23,6 +23,13 @@ struct Args {
     #[structopt(long, help = "Enable a single validator testnet")]
     test: bool,
 
+    #[structopt(
+        long,
+        help = "Serve JSON-RPC from a read replica of the DB of the node running on the same host",
+        conflicts_with = "test"
+    )]
+    read_replica: bool,
+
     #[structopt(
         long,
         help = "RNG Seed to use when starting single validator testnet",
@@ -63,6 +70,10 @@ fn main() {
     } else {
         let config = NodeConfig::load(args.config.unwrap()).expect("Failed to load node config");
         println!("Using node config {:?}", &config);
-        diem_node::start(&config, None);
+        if args.read_replica {
+            diem_node::start_read_replica(&config);
+        } else {
+            diem_node::start(&config, None);
+        }
     };
 }
@@ -9,12 +9,13 @@ use crate::{
     serializer::{SerializerClient, SerializerService},
     thread::ThreadService,
 };
-use diem_config::config::{ExecutionCorrectnessService, NodeConfig};
+use diem_config::config::{ExecutionConfig, ExecutionCorrectnessService, NodeConfig};
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_global_constants::EXECUTION_KEY;
 use diem_secure_storage::{CryptoStorage, Storage};
 use diem_vm::DiemVM;
-use executor::Executor;
+use executor::{execution_trace::ExecutionTraceRecorder, Executor};
+use executor_types::BlockExecutor;
 use std::{convert::TryInto, net::SocketAddr, sync::Arc};
 use storage_client::StorageClient;
 
@@ -43,6 +44,28 @@ pub fn extract_execution_prikey(config: &NodeConfig) -> Option<Ed25519PrivateKey
     }
 }
 
+/// Creates the block executor behind execution correctness, configured by `execution_config`.
+pub(crate) fn block_executor(
+    storage_address: SocketAddr,
+    timeout: u64,
+    execution_config: &ExecutionConfig,
+) -> Box<dyn BlockExecutor> {
+    let executor = Executor::<DiemVM>::new(StorageClient::new(&storage_address, timeout).into())
+        .with_speculation_result_cache_max_txns(execution_config.speculation_result_cache_max_txns);
+    match &execution_config.trace {
+        Some(trace_config) => Box::new(
+            executor.with_trace_recorder(
+                ExecutionTraceRecorder::open(
+                    &trace_config.path("block_executor"),
+                    trace_config.versions(),
+                )
+                .expect("Unable to open execution trace"),
+            ),
+        ),
+        None => Box::new(executor),
+    }
+}
+
 enum ExecutionCorrectnessWrapper {
     Local(Arc<LocalService>),
     Process(ProcessService),
@@ -66,16 +89,26 @@ impl ExecutionCorrectnessManager {
         let execution_prikey = extract_execution_prikey(config);
         let storage_address = config.storage.address;
         let timeout_ms = config.storage.timeout_ms;
+        let execution_config = &config.execution;
         match &config.execution.service {
-            ExecutionCorrectnessService::Local => {
-                Self::new_local(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Serializer => {
-                Self::new_serializer(storage_address, execution_prikey, timeout_ms)
-            }
-            ExecutionCorrectnessService::Thread => {
-                Self::new_thread(storage_address, execution_prikey, timeout_ms)
-            }
+            ExecutionCorrectnessService::Local => Self::new_local(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                execution_config,
+            ),
+            ExecutionCorrectnessService::Serializer => Self::new_serializer(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                execution_config,
+            ),
+            ExecutionCorrectnessService::Thread => Self::new_thread(
+                storage_address,
+                execution_prikey,
+                timeout_ms,
+                execution_config,
+            ),
             _ => unreachable!(
                 "Unimplemented ExecutionCorrectnessService: {:?}",
                 config.execution.service
@@ -87,10 +120,9 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        execution_config: &ExecutionConfig,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DiemVM>::new(
-            StorageClient::new(&storage_address, timeout).into(),
-        ));
+        let block_executor = block_executor(storage_address, timeout, execution_config);
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Local(Arc::new(
                 LocalService::new(block_executor, execution_prikey),
@@ -109,10 +141,9 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         timeout: u64,
+        execution_config: &ExecutionConfig,
     ) -> Self {
-        let block_executor = Box::new(Executor::<DiemVM>::new(
-            StorageClient::new(&storage_address, timeout).into(),
-        ));
+        let block_executor = block_executor(storage_address, timeout, execution_config);
         let serializer_service = SerializerService::new(block_executor, execution_prikey);
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Serializer(Arc::new(
@@ -125,8 +156,14 @@ impl ExecutionCorrectnessManager {
         storage_address: SocketAddr,
         execution_prikey: Option<Ed25519PrivateKey>,
         network_timeout: u64,
+        execution_config: &ExecutionConfig,
     ) -> Self {
-        let thread = ThreadService::new(storage_address, execution_prikey, network_timeout);
+        let thread = ThreadService::new(
+            storage_address,
+            execution_prikey,
+            network_timeout,
+            execution_config.clone(),
+        );
         Self {
             internal_execution_correctness: ExecutionCorrectnessWrapper::Thread(thread),
         }
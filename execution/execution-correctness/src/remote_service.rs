@@ -1,18 +1,19 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::serializer::{
-    ExecutionCorrectnessInput, SerializerClient, SerializerService, TSerializerClient,
+use crate::{
+    execution_correctness_manager,
+    serializer::{
+        ExecutionCorrectnessInput, SerializerClient, SerializerService, TSerializerClient,
+    },
 };
+use diem_config::config::ExecutionConfig;
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use diem_infallible::Mutex;
 use diem_logger::warn;
 use diem_secure_net::{NetworkClient, NetworkServer};
-use diem_vm::DiemVM;
-use executor::Executor;
 use executor_types::Error;
 use std::net::SocketAddr;
-use storage_client::StorageClient;
 
 pub trait RemoteService {
     fn client(&self) -> SerializerClient {
@@ -31,10 +32,13 @@ pub fn execute(
     listen_addr: SocketAddr,
     prikey: Option<Ed25519PrivateKey>,
     network_timeout: u64,
+    execution_config: &ExecutionConfig,
 ) {
-    let block_executor = Box::new(Executor::<DiemVM>::new(
-        StorageClient::new(&storage_addr, network_timeout).into(),
-    ));
+    let block_executor = execution_correctness_manager::block_executor(
+        storage_addr,
+        network_timeout,
+        execution_config,
+    );
     let serializer_service = SerializerService::new(block_executor, prikey);
     let mut network_server = NetworkServer::new("execution", listen_addr, network_timeout);
 
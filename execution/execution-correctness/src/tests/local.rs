@@ -29,7 +29,11 @@ fn execution_correctness(
     };
     // Timeout value of 5 seconds for network operations.
     let timeout_ms = 5_000;
-    let execution_correctness_manager =
-        ExecutionCorrectnessManager::new_local(config.storage.address, prikey, timeout_ms);
+    let execution_correctness_manager = ExecutionCorrectnessManager::new_local(
+        config.storage.address,
+        prikey,
+        timeout_ms,
+        &config.execution,
+    );
     (execution_correctness_manager.client(), pubkey)
 }
@@ -8,7 +8,7 @@
 //! in testing correctness of the communication layer between ExecutionCorrectness and SafetyRules.
 
 use crate::remote_service::{self, RemoteService};
-use diem_config::utils;
+use diem_config::{config::ExecutionConfig, utils};
 use diem_crypto::ed25519::Ed25519PrivateKey;
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
@@ -28,13 +28,20 @@ impl ThreadService {
         storage_addr: SocketAddr,
         prikey: Option<Ed25519PrivateKey>,
         network_timeout: u64,
+        execution_config: ExecutionConfig,
     ) -> Self {
         let listen_port = utils::get_available_port();
         let listen_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), listen_port);
         let server_addr = listen_addr;
 
         let child = thread::spawn(move || {
-            remote_service::execute(storage_addr, listen_addr, prikey, network_timeout)
+            remote_service::execute(
+                storage_addr,
+                listen_addr,
+                prikey,
+                network_timeout,
+                &execution_config,
+            )
         });
 
         Self {
@@ -0,0 +1,42 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::Result;
+use executor::execution_trace::{find_divergence, read_trace};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(
+    name = "execution-trace-diff",
+    about = "Find the first transaction executed differently in the execution traces of two nodes."
+)]
+struct Opt {
+    /// Trace file, or directory of trace files, recorded by the first node.
+    #[structopt(parse(from_os_str))]
+    left: PathBuf,
+
+    /// Trace file, or directory of trace files, recorded by the second node.
+    #[structopt(parse(from_os_str))]
+    right: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::from_args();
+
+    let left = read_trace(&opt.left)?;
+    let right = read_trace(&opt.right)?;
+    let num_common_versions = left.keys().filter(|v| right.contains_key(v)).count();
+    println!(
+        "Comparing {} versions recorded in both traces ({} on the left, {} on the right).",
+        num_common_versions,
+        left.len(),
+        right.len()
+    );
+
+    match find_divergence(&left, &right) {
+        Some(divergence) => print!("{}", divergence),
+        None => println!("No divergence found."),
+    }
+    Ok(())
+}
@@ -2,6 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_crypto::HashValue;
+use diem_types::{
+    contract_event::ContractEvent,
+    transaction::{TransactionInfo, Version},
+    write_set::WriteSet,
+};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -18,6 +23,21 @@ pub enum Error {
     SerializationError(String),
 }
 
+/// Raised when replaying a transaction doesn't reproduce its recorded transaction info, i.e. its
+/// status, gas used, events or resulting state differ from when it was originally committed.
+#[derive(Debug, Error)]
+#[error(
+    "Transaction at version {version} diverged when replayed.\n\
+     Replayed txn_info: {replayed_txn_info}\nRecorded txn_info: {recorded_txn_info}"
+)]
+pub struct ReplayDivergence {
+    pub version: Version,
+    pub recorded_txn_info: TransactionInfo,
+    pub replayed_txn_info: TransactionInfo,
+    pub replayed_events: Vec<ContractEvent>,
+    pub replayed_write_set: WriteSet,
+}
+
 impl From<anyhow::Error> for Error {
     fn from(error: anyhow::Error) -> Self {
         Self::InternalError {
@@ -4,7 +4,7 @@
 #![forbid(unsafe_code)]
 
 mod error;
-pub use error::Error;
+pub use error::{Error, ReplayDivergence};
 
 use anyhow::Result;
 use diem_crypto::{
@@ -15,6 +15,7 @@ use diem_crypto::{
     HashValue,
 };
 use diem_types::{
+    access_path::AccessPath,
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
     epoch_state::EpochState,
@@ -26,16 +27,29 @@ use diem_types::{
 };
 use scratchpad::ProofRead;
 use serde::{Deserialize, Serialize};
-use std::{cmp::max, collections::HashMap, sync::Arc};
-use storage_interface::TreeState;
+use std::{
+    cmp::max,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use storage_interface::{StateSnapshotReceiver, TreeState};
 
 type SparseMerkleProof = diem_types::proof::SparseMerkleProof<AccountStateBlob>;
 type SparseMerkleTree = scratchpad::SparseMerkleTree<AccountStateBlob>;
 
+/// The output of executing a chunk of transactions.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChunkOutput {
+    /// The events emitted by the transactions of the chunk.
+    pub events: Vec<ContractEvent>,
+    /// The access paths written by the transactions of the chunk.
+    pub written_paths: HashSet<AccessPath>,
+}
+
 pub trait ChunkExecutor: Send + Sync {
     /// Verifies the transactions based on the provided proofs and ledger info. If the transactions
     /// are valid, executes them and commits immediately if execution results match the proofs.
-    /// Returns a vector of reconfiguration events in the chunk
+    /// Returns the events emitted and the access paths written by the chunk
     fn execute_and_commit_chunk(
         &self,
         txn_list_with_proof: TransactionListWithProof,
@@ -44,7 +58,65 @@ pub trait ChunkExecutor: Send + Sync {
         // An optional end of epoch LedgerInfo. We do not allow chunks that end epoch without
         // carrying any epoch change LI.
         epoch_change_li: Option<LedgerInfoWithSignatures>,
-    ) -> Result<Vec<ContractEvent>>;
+    ) -> Result<ChunkOutput>;
+
+    /// Verifies and executes the transactions like `execute_and_commit_chunk`, but keeps the
+    /// results in memory instead of committing them, so that subsequent chunks can be executed
+    /// on top of them and committed in the same storage write batch. The buffered chunks are
+    /// committed as soon as a chunk ends at a ledger info to commit. Returns the events emitted
+    /// and the access paths written by the chunk
+    fn execute_and_buffer_chunk(
+        &self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<ChunkOutput>;
+
+    /// Commits the buffered chunks (if any) to storage in a single write batch.
+    fn commit_buffered_chunks(&self) -> Result<()>;
+
+    /// Returns the trees resulting from the execution of the buffered chunks, or None if there
+    /// are no buffered chunks.
+    fn buffered_trees(&self) -> Option<ExecutedTrees>;
+
+    /// Verifies and executes the transactions like `execute_and_commit_chunk`, but neither
+    /// commits them nor changes the synced state. Each chunk is executed on top of the chunks
+    /// previously executed in dry-run mode (the first one on top of the synced state), so that
+    /// a stream of chunks can be validated against a trusted ledger info (e.g., the ledger info
+    /// of a waypoint) before it is applied. Returns the trees resulting from the execution,
+    /// which carry the resulting state root.
+    fn dry_run_chunk(
+        &self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<ExecutedTrees>;
+
+    /// Discards the chunks executed in dry-run mode, so that the next dry run starts from the
+    /// synced state.
+    fn reset_dry_run(&self);
+
+    /// Gets a receiver which restores the account state tree at `version` from the chunks of a
+    /// state snapshot. Once all the chunks are added and the receiver is finished, the snapshot
+    /// is completed with `finalize_state_snapshot`.
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>>;
+
+    /// Verifies the single transaction in `txn_list_with_proof` at the version of the verified
+    /// target ledger info, and commits it together with the restored state snapshot at that
+    /// version, so that syncing continues from the snapshot. `frozen_subtrees` are the roots of
+    /// the frozen subtrees of the transaction accumulator before the transaction, and
+    /// `epoch_change_lis` are the ledger infos ending the epochs up to the target.
+    fn finalize_state_snapshot(
+        &self,
+        txn_list_with_proof: TransactionListWithProof,
+        frozen_subtrees: Vec<HashValue>,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_lis: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<()>;
 }
 
 pub trait BlockExecutor: Send + Sync {
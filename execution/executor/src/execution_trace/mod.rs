@@ -0,0 +1,288 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Execution traces, to localize non-determinism when the state roots of nodes diverge.
+//!
+//! When enabled, the executor records for each transaction committed in a version range what it
+//! read from storage, what it wrote, the events it emitted and the gas it used. Nodes executing
+//! the same transactions deterministically record the same traces, so the first version at which
+//! the traces of two nodes differ points to the transaction that diverged, and the differences to
+//! the part of its execution that did.
+//!
+//! A trace file is a sequence of BCS serialized `ExecutionTraceEntry`, each prefixed with its
+//! length as a little endian `u32`. Entries are only appended, so a version executed more than
+//! once, e.g. because committing a chunk failed, may appear more than once: the last one wins.
+//!
+//! Read sets include the Move modules loaded by a transaction, which are cached by the VM for the
+//! rest of the block, so they depend on how transactions are grouped into blocks and chunks.
+
+#[cfg(test)]
+mod test;
+
+use crate::types::TransactionData;
+use anyhow::{ensure, format_err, Result};
+use diem_crypto::{hash::CryptoHash, HashValue};
+use diem_infallible::Mutex;
+use diem_types::{
+    access_path::AccessPath,
+    contract_event::ContractEvent,
+    transaction::{Transaction, TransactionStatus, Version},
+    vm_status::KeptVMStatus,
+    write_set::{WriteOp, WriteSet},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufReader, ErrorKind, Read, Write},
+    ops::RangeInclusive,
+    path::Path,
+};
+
+/// What a committed transaction did during execution.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ExecutionTraceEntry {
+    pub version: Version,
+    pub transaction_hash: HashValue,
+    pub status: KeptVMStatus,
+    pub gas_used: u64,
+    /// The keys read from storage, empty if the VM doesn't record reads.
+    pub read_set: Vec<AccessPath>,
+    pub write_set: WriteSet,
+    pub events: Vec<ContractEvent>,
+    /// The root hash of the state tree after the transaction.
+    pub state_root_hash: HashValue,
+}
+
+impl ExecutionTraceEntry {
+    /// The entry of `txn` committed at `version`, or `None` if it isn't kept on chain.
+    pub(crate) fn new(
+        version: Version,
+        txn: &Transaction,
+        txn_data: &TransactionData,
+    ) -> Option<Self> {
+        let status = match txn_data.status() {
+            TransactionStatus::Keep(status) => status.clone(),
+            TransactionStatus::Discard(_) | TransactionStatus::Retry => return None,
+        };
+        Some(Self {
+            version,
+            transaction_hash: txn.hash(),
+            status,
+            gas_used: txn_data.gas_used(),
+            read_set: txn_data.read_set().to_vec(),
+            write_set: txn_data.write_set().clone(),
+            events: txn_data.events().to_vec(),
+            state_root_hash: txn_data.state_root_hash(),
+        })
+    }
+}
+
+/// Appends the entries of the transactions committed in a version range to a trace file.
+pub struct ExecutionTraceRecorder {
+    versions: RangeInclusive<Version>,
+    file: Mutex<File>,
+}
+
+impl ExecutionTraceRecorder {
+    /// Opens the trace file at `path` to record the transactions committed in `versions`. The
+    /// file is created if it doesn't exist, and appended to otherwise.
+    pub fn open(path: &Path, versions: RangeInclusive<Version>) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| format_err!("Failed to open trace file {:?}: {}", path, err))?;
+        Ok(Self {
+            versions,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Whether the transaction at `version` is recorded.
+    pub fn is_recording(&self, version: Version) -> bool {
+        self.versions.contains(&version)
+    }
+
+    /// Appends `entries` in the version range to the trace file.
+    pub(crate) fn record(
+        &self,
+        entries: impl IntoIterator<Item = ExecutionTraceEntry>,
+    ) -> Result<()> {
+        let mut bytes = vec![];
+        for entry in entries {
+            if !self.is_recording(entry.version) {
+                continue;
+            }
+            let entry_bytes = bcs::to_bytes(&entry)?;
+            bytes.extend_from_slice(&u32::try_from(entry_bytes.len())?.to_le_bytes());
+            bytes.extend(entry_bytes);
+        }
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let mut file = self.file.lock();
+        file.write_all(&bytes)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads the trace at `path`, which is either a trace file or a directory of trace files, e.g.
+/// the ones recorded by the block and chunk executors of a node. Returns the last entry recorded
+/// for each version.
+pub fn read_trace(path: &Path) -> Result<BTreeMap<Version, ExecutionTraceEntry>> {
+    let mut trace = BTreeMap::new();
+    if path.is_dir() {
+        let mut files = fs::read_dir(path)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        files.sort();
+        for file in files {
+            read_trace_file(&file, &mut trace)?;
+        }
+    } else {
+        read_trace_file(path, &mut trace)?;
+    }
+    Ok(trace)
+}
+
+fn read_trace_file(path: &Path, trace: &mut BTreeMap<Version, ExecutionTraceEntry>) -> Result<()> {
+    let mut reader = BufReader::new(
+        File::open(path).map_err(|err| format_err!("Failed to open {:?}: {}", path, err))?,
+    );
+    loop {
+        let mut len_bytes = [0u8; 4];
+        match reader.read_exact(&mut len_bytes) {
+            Ok(()) => (),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return Err(err.into()),
+        }
+        let mut entry_bytes = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+        // A truncated entry is what's left of a node stopped while writing it.
+        if let Err(err) = reader.read_exact(&mut entry_bytes) {
+            ensure!(
+                err.kind() == ErrorKind::UnexpectedEof,
+                "Failed to read {:?}: {}",
+                path,
+                err
+            );
+            return Ok(());
+        }
+        let entry: ExecutionTraceEntry = bcs::from_bytes(&entry_bytes)?;
+        trace.insert(entry.version, entry);
+    }
+}
+
+/// The first transaction executed differently in two traces.
+#[derive(Debug, Eq, PartialEq)]
+pub struct TraceDivergence {
+    pub version: Version,
+    /// Human readable descriptions of what differs, from the left trace to the right one.
+    pub differences: Vec<String>,
+}
+
+impl fmt::Display for TraceDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Execution diverged at version {}:", self.version)?;
+        for difference in &self.differences {
+            writeln!(f, "    {}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the versions recorded in both traces, and returns the first one whose entries
+/// differ, if any.
+pub fn find_divergence(
+    left: &BTreeMap<Version, ExecutionTraceEntry>,
+    right: &BTreeMap<Version, ExecutionTraceEntry>,
+) -> Option<TraceDivergence> {
+    left.iter()
+        .filter_map(|(version, left_entry)| Some((left_entry, right.get(version)?)))
+        .find(|(left_entry, right_entry)| left_entry != right_entry)
+        .map(|(left_entry, right_entry)| TraceDivergence {
+            version: left_entry.version,
+            differences: diff_entries(left_entry, right_entry),
+        })
+}
+
+fn diff_entries(left: &ExecutionTraceEntry, right: &ExecutionTraceEntry) -> Vec<String> {
+    let mut differences = vec![];
+    if left.transaction_hash != right.transaction_hash {
+        differences.push(format!(
+            "transaction hash: {} != {}",
+            left.transaction_hash, right.transaction_hash
+        ));
+    }
+    if left.status != right.status {
+        differences.push(format!("status: {:?} != {:?}", left.status, right.status));
+    }
+    if left.gas_used != right.gas_used {
+        differences.push(format!("gas used: {} != {}", left.gas_used, right.gas_used));
+    }
+
+    let left_reads = left.read_set.iter().collect::<BTreeSet<_>>();
+    let right_reads = right.read_set.iter().collect::<BTreeSet<_>>();
+    for access_path in left_reads.difference(&right_reads) {
+        differences.push(format!("read only on the left: {}", access_path));
+    }
+    for access_path in right_reads.difference(&left_reads) {
+        differences.push(format!("read only on the right: {}", access_path));
+    }
+
+    let left_writes = left.write_set.iter().cloned().collect::<BTreeMap<_, _>>();
+    let right_writes = right.write_set.iter().cloned().collect::<BTreeMap<_, _>>();
+    let written_paths = left_writes
+        .keys()
+        .chain(right_writes.keys())
+        .collect::<BTreeSet<_>>();
+    for access_path in written_paths {
+        let left_write = left_writes.get(access_path);
+        let right_write = right_writes.get(access_path);
+        if left_write != right_write {
+            differences.push(format!(
+                "write to {}: {} != {}",
+                access_path,
+                describe_write(left_write),
+                describe_write(right_write),
+            ));
+        }
+    }
+
+    if left.events.len() != right.events.len() {
+        differences.push(format!(
+            "number of events: {} != {}",
+            left.events.len(),
+            right.events.len()
+        ));
+    }
+    for (index, (left_event, right_event)) in left.events.iter().zip(&right.events).enumerate() {
+        if left_event != right_event {
+            differences.push(format!(
+                "event {}: {:?} != {:?}",
+                index, left_event, right_event
+            ));
+        }
+    }
+
+    if left.state_root_hash != right.state_root_hash {
+        differences.push(format!(
+            "state root hash: {} != {}",
+            left.state_root_hash, right.state_root_hash
+        ));
+    }
+    differences
+}
+
+fn describe_write(write: Option<&WriteOp>) -> String {
+    match write {
+        Some(write_op) => format!("{:?}", write_op),
+        None => "no write".to_string(),
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use diem_temppath::TempPath;
+use diem_types::{account_address::AccountAddress, write_set::WriteSetMut};
+
+fn entry(version: Version, value: u8) -> ExecutionTraceEntry {
+    let access_path = AccessPath::new(AccountAddress::random(), vec![1, 2, 3]);
+    ExecutionTraceEntry {
+        version,
+        transaction_hash: HashValue::random(),
+        status: KeptVMStatus::Executed,
+        gas_used: 100,
+        read_set: vec![access_path.clone()],
+        write_set: WriteSetMut::new(vec![(access_path, WriteOp::Value(vec![value]))])
+            .freeze()
+            .unwrap(),
+        events: vec![],
+        state_root_hash: HashValue::random(),
+    }
+}
+
+#[test]
+fn test_record_and_read_trace() {
+    let dir = TempPath::new();
+    let path = dir.path().join("executor.trace");
+    let recorder = ExecutionTraceRecorder::open(&path, 1..=2).unwrap();
+
+    let entries = (0..4).map(|version| entry(version, 0)).collect::<Vec<_>>();
+    recorder.record(entries.clone()).unwrap();
+    // Re-executing a version overrides its entry.
+    let reexecuted = entry(2, 1);
+    recorder.record(vec![reexecuted.clone()]).unwrap();
+
+    let trace = read_trace(&path).unwrap();
+    assert_eq!(
+        trace.into_iter().collect::<Vec<_>>(),
+        vec![(1, entries[1].clone()), (2, reexecuted)]
+    );
+    // A directory of trace files is read as one trace.
+    assert_eq!(read_trace(dir.path()).unwrap().len(), 2);
+}
+
+#[test]
+fn test_find_divergence() {
+    let left = (0..3)
+        .map(|version| (version, entry(version, 0)))
+        .collect::<BTreeMap<_, _>>();
+    let mut right = left.clone();
+    right.remove(&0);
+    assert_eq!(find_divergence(&left, &right), None);
+
+    let mut diverged = left[&1].clone();
+    diverged.gas_used += 1;
+    diverged.write_set = WriteSetMut::new(vec![(
+        diverged.read_set[0].clone(),
+        WriteOp::Value(vec![1]),
+    )])
+    .freeze()
+    .unwrap();
+    right.insert(1, diverged);
+    right.insert(2, entry(2, 1));
+
+    let divergence = find_divergence(&left, &right).unwrap();
+    assert_eq!(divergence.version, 1);
+    assert_eq!(
+        divergence.differences,
+        vec![
+            "gas used: 100 != 101".to_string(),
+            format!("write to {}: Value(00) != Value(01)", left[&1].read_set[0]),
+        ]
+    );
+}
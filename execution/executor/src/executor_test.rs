@@ -369,6 +369,105 @@ fn test_executor_execute_and_commit_chunk() {
     assert_eq!(li, ledger_info);
 }
 
+#[test]
+fn test_executor_execute_and_buffer_chunk() {
+    let (chunks, ledger_info) = create_transaction_chunks(vec![1..31, 31..71, 71..91]);
+
+    let TestExecutor {
+        _path,
+        db,
+        executor,
+    } = TestExecutor::new();
+    let synced_version = || {
+        db.reader
+            .get_latest_transaction_info_option()
+            .unwrap()
+            .unwrap()
+            .0
+    };
+
+    // Buffer the first chunk. It is executed, but not written to storage until committed.
+    let chunk_output = executor
+        .execute_and_buffer_chunk(chunks[0].clone(), ledger_info.clone(), None)
+        .unwrap();
+    assert!(!chunk_output.written_paths.is_empty());
+    assert_eq!(executor.buffered_trees().unwrap().version(), Some(30));
+    assert_eq!(synced_version(), 0);
+    executor.commit_buffered_chunks().unwrap();
+    assert!(executor.buffered_trees().is_none());
+    assert_eq!(synced_version(), 30);
+    let li = db.reader.get_latest_ledger_info().unwrap();
+    assert_eq!(li.ledger_info().version(), 0);
+
+    // Buffer the second chunk, and execute the third chunk on top of it. The third chunk ends at
+    // the target ledger info, so both chunks are committed with it.
+    executor
+        .execute_and_buffer_chunk(chunks[1].clone(), ledger_info.clone(), None)
+        .unwrap();
+    assert_eq!(executor.buffered_trees().unwrap().version(), Some(70));
+    assert_eq!(synced_version(), 30);
+    executor
+        .execute_and_buffer_chunk(chunks[2].clone(), ledger_info.clone(), None)
+        .unwrap();
+    assert!(executor.buffered_trees().is_none());
+    assert_eq!(synced_version(), 90);
+    let li = db.reader.get_latest_ledger_info().unwrap();
+    assert_eq!(li, ledger_info);
+}
+
+#[test]
+fn test_executor_dry_run_chunk() {
+    let (chunks, ledger_info) = create_transaction_chunks(vec![1..31, 31..71, 71..91]);
+
+    let TestExecutor {
+        _path,
+        db,
+        executor,
+    } = TestExecutor::new();
+
+    // Dry-run all chunks. Each chunk is executed on top of the previous one, but storage is left
+    // untouched.
+    let mut executed_trees = None;
+    for chunk in &chunks {
+        executed_trees = Some(
+            executor
+                .dry_run_chunk(chunk.clone(), ledger_info.clone(), None)
+                .unwrap(),
+        );
+    }
+    let executed_trees = executed_trees.unwrap();
+    assert_eq!(executed_trees.version(), Some(90));
+    assert_eq!(
+        executed_trees.txn_accumulator().root_hash(),
+        ledger_info.ledger_info().transaction_accumulator_hash()
+    );
+    let (version, _) = db
+        .reader
+        .get_latest_transaction_info_option()
+        .unwrap()
+        .unwrap();
+    assert_eq!(version, 0);
+
+    // After a reset, the dry run starts from storage again, so a chunk with a gap is rejected.
+    executor.reset_dry_run();
+    assert!(executor
+        .dry_run_chunk(chunks[1].clone(), ledger_info.clone(), None)
+        .is_err());
+
+    // Executing the chunks for real results in the dry-run state.
+    executor.reset_dry_run();
+    for chunk in chunks {
+        executor
+            .execute_and_commit_chunk(chunk, ledger_info.clone(), None)
+            .unwrap();
+    }
+    assert_eq!(db.reader.get_latest_ledger_info().unwrap(), ledger_info);
+    assert_eq!(
+        db.reader.get_latest_state_root().unwrap().1,
+        executed_trees.state_root()
+    );
+}
+
 #[test]
 fn test_executor_execute_and_commit_chunk_restart() {
     let first_batch_size = 30;
@@ -451,6 +550,36 @@ fn test_executor_execute_and_commit_chunk_local_result_mismatch() {
         .is_err());
 }
 
+#[test]
+fn test_executor_replay_chunk_divergence() {
+    let (chunks, _) = create_transaction_chunks(vec![1..11]);
+    let txns = chunks[0].transactions.clone();
+    let mut txn_infos = chunks[0].proof.transaction_infos.clone();
+    let original_txn_info = txn_infos[2].clone();
+    txn_infos[2] = TransactionInfo::new(
+        original_txn_info.transaction_hash(),
+        original_txn_info.state_root_hash(),
+        original_txn_info.event_root_hash(),
+        original_txn_info.gas_used() + 1,
+        original_txn_info.status().clone(),
+    );
+
+    // The first transaction whose recorded txn_info isn't reproduced is reported.
+    let TestExecutor {
+        _path,
+        db: _,
+        executor,
+    } = TestExecutor::new();
+    let error = executor.replay_chunk(1, txns, txn_infos).unwrap_err();
+    let divergence = error.downcast_ref::<ReplayDivergence>().unwrap();
+    assert_eq!(divergence.version, 3);
+    assert_eq!(divergence.replayed_txn_info, original_txn_info);
+    assert_eq!(
+        divergence.recorded_txn_info.gas_used(),
+        original_txn_info.gas_used() + 1
+    );
+}
+
 #[test]
 fn test_noop_block_after_reconfiguration() {
     let executor = TestExecutor::new();
@@ -36,7 +36,7 @@ pub fn fuzz_execute_and_commit_chunk(
     verified_target_li: LedgerInfoWithSignatures,
 ) {
     let executor = create_test_executor();
-    let _events = executor.execute_and_commit_chunk(txn_list_with_proof, verified_target_li, None);
+    let _output = executor.execute_and_commit_chunk(txn_list_with_proof, verified_target_li, None);
 }
 
 pub fn fuzz_execute_and_commit_blocks(
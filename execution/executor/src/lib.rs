@@ -3,6 +3,7 @@
 
 #![forbid(unsafe_code)]
 
+pub mod execution_trace;
 #[cfg(test)]
 mod executor_test;
 #[cfg(any(test, feature = "fuzzing"))]
@@ -17,6 +18,7 @@ mod types;
 pub mod db_bootstrapper;
 
 use crate::{
+    execution_trace::{ExecutionTraceEntry, ExecutionTraceRecorder},
     logging::{LogEntry, LogSchema},
     metrics::{
         DIEM_EXECUTOR_COMMIT_BLOCKS_SECONDS, DIEM_EXECUTOR_ERRORS,
@@ -24,7 +26,7 @@ use crate::{
         DIEM_EXECUTOR_SAVE_TRANSACTIONS_SECONDS, DIEM_EXECUTOR_TRANSACTIONS_SAVED,
         DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS,
     },
-    speculation_cache::SpeculationCache,
+    speculation_cache::{SpeculationCache, SpeculationResultCache},
     types::{ProcessedVMOutput, TransactionData},
 };
 use anyhow::{bail, ensure, format_err, Result};
@@ -32,14 +34,14 @@ use diem_crypto::{
     hash::{CryptoHash, EventAccumulatorHasher, TransactionAccumulatorHasher},
     HashValue,
 };
-use diem_infallible::{RwLock, RwLockReadGuard};
+use diem_infallible::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
+use diem_load_shedder::{start_latency_timer, LatencySource};
 use diem_logger::prelude::*;
 use diem_state_view::StateViewId;
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_state::AccountState,
     account_state_blob::AccountStateBlob,
-    contract_event::ContractEvent,
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
     on_chain_config,
@@ -48,12 +50,13 @@ use diem_types::{
         Transaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
         TransactionPayload, TransactionStatus, TransactionToCommit, Version,
     },
+    vm_status::VMStatus,
     write_set::{WriteOp, WriteSet},
 };
 use diem_vm::VMExecutor;
 use executor_types::{
-    BlockExecutor, ChunkExecutor, Error, ExecutedTrees, ProofReader, StateComputeResult,
-    TransactionReplayer,
+    BlockExecutor, ChunkExecutor, ChunkOutput, Error, ExecutedTrees, ProofReader, ReplayDivergence,
+    StateComputeResult, TransactionReplayer,
 };
 use fail::fail_point;
 use std::{
@@ -62,17 +65,46 @@ use std::{
     marker::PhantomData,
     sync::Arc,
 };
-use storage_interface::{state_view::VerifiedStateView, DbReaderWriter, TreeState};
+use storage_interface::{
+    state_view::VerifiedStateView, DbReaderWriter, StateSnapshotReceiver, TreeState,
+};
 
 type SparseMerkleProof = diem_types::proof::SparseMerkleProof<AccountStateBlob>;
 
+// The maximum number of transactions in the speculative block outputs kept for re-executions of
+// the same blocks, unless configured otherwise.
+const DEFAULT_SPECULATION_RESULT_CACHE_MAX_TXNS: usize = 10_000;
+
 /// `Executor` implements all functionalities the execution module needs to provide.
 pub struct Executor<V> {
     db: DbReaderWriter,
     cache: RwLock<SpeculationCache>,
+    result_cache: Mutex<SpeculationResultCache>,
+    chunk_buffer: Mutex<Option<ChunkBuffer>>,
+    dry_run: Mutex<Option<DryRun>>,
+    trace_recorder: Option<ExecutionTraceRecorder>,
     phantom: PhantomData<V>,
 }
 
+/// Chunks which have been executed but not yet committed to storage. The synced trees in the
+/// cache reflect the execution of the buffered chunks.
+struct ChunkBuffer {
+    // The synced trees in storage, on top of which the buffered chunks were executed. Holding
+    // them keeps the in-memory state of the buffered chunks alive.
+    persisted_trees: ExecutedTrees,
+    // The version of the first buffered transaction
+    first_version: Version,
+    txns_to_commit: Vec<TransactionToCommit>,
+}
+
+/// Chunks executed in dry-run mode, which are never committed.
+struct DryRun {
+    // The synced trees in storage, on top of which the dry run started
+    persisted_trees: ExecutedTrees,
+    // The trees resulting from the execution of the chunks so far
+    synced_trees: ExecutedTrees,
+}
+
 impl<V> Executor<V>
 where
     V: VMExecutor,
@@ -92,10 +124,118 @@ where
         Self {
             db,
             cache: RwLock::new(SpeculationCache::new_with_startup_info(startup_info)),
+            result_cache: Mutex::new(SpeculationResultCache::new(
+                DEFAULT_SPECULATION_RESULT_CACHE_MAX_TXNS,
+            )),
+            chunk_buffer: Mutex::new(None),
+            dry_run: Mutex::new(None),
+            trace_recorder: None,
             phantom: PhantomData,
         }
     }
 
+    /// Records the execution of the committed transactions with `trace_recorder`.
+    pub fn with_trace_recorder(mut self, trace_recorder: ExecutionTraceRecorder) -> Self {
+        self.trace_recorder = Some(trace_recorder);
+        self
+    }
+
+    /// Bounds the total number of transactions in the speculative block outputs kept for
+    /// re-executions of the same blocks.
+    pub fn with_speculation_result_cache_max_txns(mut self, max_txns: usize) -> Self {
+        self.result_cache = Mutex::new(SpeculationResultCache::new(max_txns));
+        self
+    }
+
+    /// Runs the transactions through the VM, recording the keys they read if the execution is
+    /// traced.
+    fn vm_execute_block(
+        &self,
+        transactions: Vec<Transaction>,
+        state_view: &VerifiedStateView<'_>,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        if self.trace_recorder.is_some() {
+            V::execute_block_and_record_reads(transactions, state_view)
+        } else {
+            V::execute_block(transactions, state_view)
+        }
+    }
+
+    /// The trace entries of the transactions kept among `txns`, the first of which is committed
+    /// at `first_version`. Empty if the execution isn't traced.
+    fn trace_entries<'a>(
+        &self,
+        first_version: Version,
+        txns: impl Iterator<Item = (&'a Transaction, &'a TransactionData)>,
+    ) -> Vec<ExecutionTraceEntry> {
+        let trace_recorder = match &self.trace_recorder {
+            Some(trace_recorder) => trace_recorder,
+            None => return vec![],
+        };
+        let mut version = first_version;
+        let mut entries = vec![];
+        for (txn, txn_data) in txns {
+            if let Some(entry) = ExecutionTraceEntry::new(version, txn, txn_data) {
+                if trace_recorder.is_recording(version) {
+                    entries.push(entry);
+                }
+                version += 1;
+            }
+        }
+        entries
+    }
+
+    fn record_trace(&self, entries: Vec<ExecutionTraceEntry>) {
+        if let Some(trace_recorder) = &self.trace_recorder {
+            // Tracing is for debugging: failing to record doesn't fail execution.
+            if let Err(err) = trace_recorder.record(entries) {
+                warn!("Failed to record execution trace: {}", err);
+            }
+        }
+    }
+
+    /// Runs the transactions of a block through the VM on top of `state_view`, which reflects the
+    /// state after the parent block.
+    fn execute_block_on_state_view(
+        &self,
+        transactions: &[Transaction],
+        state_view: VerifiedStateView<'_>,
+        parent_block_executed_trees: &ExecutedTrees,
+    ) -> Result<ProcessedVMOutput, Error> {
+        let vm_outputs = {
+            let _timer = DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS.start_timer();
+            let _latency_timer = start_latency_timer(LatencySource::Execution);
+            fail_point!("executor::vm_execute_block", |_| {
+                Err(Error::from(anyhow::anyhow!(
+                    "Injected error in vm_execute_block"
+                )))
+            });
+            self.vm_execute_block(transactions.to_vec(), &state_view)
+                .map_err(anyhow::Error::from)?
+        };
+
+        let status: Vec<_> = vm_outputs
+            .iter()
+            .map(TransactionOutput::status)
+            .cloned()
+            .collect();
+        if !status.is_empty() {
+            trace!("Execution status: {:?}", status);
+        }
+
+        let (account_to_state, account_to_proof) = state_view.into();
+        let output = Self::process_vm_outputs(
+            account_to_state,
+            account_to_proof,
+            transactions,
+            vm_outputs,
+            parent_block_executed_trees,
+        )
+        .map_err(|err| format_err!("Failed to execute block: {}", err))?;
+
+        Ok(output)
+    }
+
     fn reset_cache(&self) -> Result<(), Error> {
         let startup_info = self
             .db
@@ -110,6 +250,12 @@ where
         Self {
             db,
             cache: RwLock::new(SpeculationCache::new_for_db_bootstrapping(tree_state)),
+            result_cache: Mutex::new(SpeculationResultCache::new(
+                DEFAULT_SPECULATION_RESULT_CACHE_MAX_TXNS,
+            )),
+            chunk_buffer: Mutex::new(None),
+            dry_run: Mutex::new(None),
+            trace_recorder: None,
             phantom: PhantomData,
         }
     }
@@ -171,6 +317,7 @@ where
         &self,
         txn_list_with_proof: TransactionListWithProof,
         verified_target_li: &LedgerInfoWithSignatures,
+        synced_trees: &ExecutedTrees,
     ) -> Result<(Vec<Transaction>, Vec<TransactionInfo>)> {
         // 1. Verify that input transactions belongs to the ledger represented by the ledger info.
         txn_list_with_proof.verify(
@@ -191,9 +338,7 @@ where
                 );
             }
         };
-        let read_lock = self.cache.read();
-
-        let num_committed_txns = read_lock.synced_trees().txn_accumulator().num_leaves();
+        let num_committed_txns = synced_trees.txn_accumulator().num_leaves();
         ensure!(
             first_txn_version <= num_committed_txns,
             "Transaction list too new. Expected version: {}. First transaction version: {}.",
@@ -239,7 +384,7 @@ where
         );
         // The two accumulator root hashes should be identical.
         ensure!(
-            read_lock.synced_trees().state_id() == accu_from_proof.root_hash(),
+            synced_trees.state_id() == accu_from_proof.root_hash(),
             "Fork happens because the current synced_trees doesn't match the txn list provided."
         );
 
@@ -354,12 +499,14 @@ where
             txn_data.push(TransactionData::new(
                 blobs,
                 new_node_hashes,
+                vm_output.write_set().clone(),
                 vm_output.events().to_vec(),
                 vm_output.status().clone(),
                 state_tree_hash,
                 Arc::new(event_tree),
                 vm_output.gas_used(),
                 txn_info_hash,
+                vm_output.read_set().map_or_else(Vec::new, <[_]>::to_vec),
             ));
         }
 
@@ -371,12 +518,14 @@ where
                 TransactionData::new(
                     HashMap::new(),
                     HashMap::new(),
+                    WriteSet::default(),
                     vec![],
                     TransactionStatus::Retry,
                     current_state_tree.root_hash(),
                     Arc::new(InMemoryAccumulator::<EventAccumulatorHasher>::default()),
                     0,
                     None,
+                    vec![],
                 ),
             );
 
@@ -467,29 +616,32 @@ where
     fn replay_transactions_impl(
         &self,
         first_version: u64,
+        // The trees in storage, which may be behind the synced trees if chunks are buffered.
+        persisted_trees: &ExecutedTrees,
+        // The trees on top of which the transactions are executed.
+        synced_trees: &ExecutedTrees,
         transactions: Vec<Transaction>,
         transaction_infos: Vec<TransactionInfo>,
     ) -> Result<(
         ProcessedVMOutput,
         Vec<TransactionToCommit>,
-        Vec<ContractEvent>,
+        ChunkOutput,
         Vec<Transaction>,
         Vec<TransactionInfo>,
     )> {
-        let read_lock = self.cache.read();
         // Construct a StateView and pass the transactions to VM.
         let state_view = VerifiedStateView::new(
             StateViewId::ChunkExecution { first_version },
             Arc::clone(&self.db.reader),
-            read_lock.synced_trees().version(),
-            read_lock.synced_trees().state_root(),
-            read_lock.synced_trees().state_tree(),
+            persisted_trees.version(),
+            persisted_trees.state_root(),
+            synced_trees.state_tree(),
         );
 
         fail_point!("executor::vm_execute_chunk", |_| {
             Err(anyhow::anyhow!("Injected error in execute_chunk"))
         });
-        let vm_outputs = V::execute_block(transactions.clone(), &state_view)?;
+        let vm_outputs = self.vm_execute_block(transactions.clone(), &state_view)?;
 
         // Since other validators have committed these transactions, their status should all be
         // TransactionStatus::Keep.
@@ -498,6 +650,11 @@ where
                 bail!("Syncing transactions that should be discarded.");
             }
         }
+        let written_paths = vm_outputs
+            .iter()
+            .flat_map(|output| output.write_set().iter())
+            .map(|(access_path, _)| access_path.clone())
+            .collect();
 
         let (account_to_state, account_to_proof) = state_view.into();
 
@@ -506,9 +663,16 @@ where
             account_to_proof,
             &transactions,
             vm_outputs,
-            read_lock.synced_trees(),
+            synced_trees,
         )?;
 
+        // Record the trace before verifying the outputs, so that it covers a divergent
+        // transaction. The versions of the chunk are final, as its transactions are verified.
+        self.record_trace(self.trace_entries(
+            first_version,
+            transactions.iter().zip(output.transaction_data()),
+        ));
+
         // Since we have verified the proofs, we just need to verify that each TransactionInfo
         // object matches what we have computed locally.
         let mut txns_to_commit = vec![];
@@ -544,15 +708,23 @@ where
                 txn_data.gas_used(),
                 recorded_status.clone(),
             );
-            ensure!(
-                txn_info == generated_txn_info,
-                "txn_info do not match for {}-th transaction in chunk.\nChunk txn_info: {}\nProof txn_info: {}",
-                i, generated_txn_info, txn_info
-            );
+            if txn_info != generated_txn_info {
+                return Err(ReplayDivergence {
+                    version: first_version
+                        .checked_add(i as u64)
+                        .ok_or_else(|| format_err!("version + i overflows"))?,
+                    recorded_txn_info: txn_info,
+                    replayed_txn_info: generated_txn_info,
+                    replayed_events: txn_data.events().to_vec(),
+                    replayed_write_set: txn_data.write_set().clone(),
+                }
+                .into());
+            }
             txns_to_commit.push(TransactionToCommit::new(
                 txn,
                 txn_data.account_blobs().clone(),
                 Some(txn_data.jf_node_hashes().clone()),
+                txn_data.write_set().clone(),
                 txn_data.events().to_vec(),
                 txn_data.gas_used(),
                 recorded_status,
@@ -563,7 +735,10 @@ where
         Ok((
             output,
             txns_to_commit,
-            events,
+            ChunkOutput {
+                events,
+                written_paths,
+            },
             txns_to_retry,
             txn_infos_to_retry,
         ))
@@ -572,17 +747,21 @@ where
     fn execute_chunk(
         &self,
         first_version: u64,
+        persisted_trees: &ExecutedTrees,
+        synced_trees: &ExecutedTrees,
         transactions: Vec<Transaction>,
         transaction_infos: Vec<TransactionInfo>,
-    ) -> Result<(
-        ProcessedVMOutput,
-        Vec<TransactionToCommit>,
-        Vec<ContractEvent>,
-    )> {
+    ) -> Result<(ProcessedVMOutput, Vec<TransactionToCommit>, ChunkOutput)> {
         let num_txns = transactions.len();
 
-        let (processed_vm_output, txns_to_commit, events, txns_to_retry, _txn_infos_to_retry) =
-            self.replay_transactions_impl(first_version, transactions, transaction_infos)?;
+        let (processed_vm_output, txns_to_commit, chunk_output, txns_to_retry, _) = self
+            .replay_transactions_impl(
+                first_version,
+                persisted_trees,
+                synced_trees,
+                transactions,
+                transaction_infos,
+            )?;
 
         ensure!(
             txns_to_retry.is_empty(),
@@ -594,7 +773,57 @@ where
                 .ok_or_else(|| format_err!("integer overflow occurred"))?,
         );
 
-        Ok((processed_vm_output, txns_to_commit, events))
+        Ok((processed_vm_output, txns_to_commit, chunk_output))
+    }
+
+    /// Commits the buffered chunks (if any) together with the given ledger info in a single
+    /// storage write batch. If the write fails, the buffered chunks are dropped and the cache is
+    /// reset to the state in storage.
+    fn commit_chunk_buffer(
+        &self,
+        chunk_buffer: &mut MutexGuard<'_, Option<ChunkBuffer>>,
+        ledger_info_to_commit: Option<LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        if chunk_buffer.is_none() {
+            return Ok(());
+        }
+        fail_point!("executor::commit_chunk", |_| {
+            Err(anyhow::anyhow!("Injected error in commit_chunk"))
+        });
+        let buffer = chunk_buffer.take().expect("buffer must exist");
+        let save_result = {
+            let _latency_timer = start_latency_timer(LatencySource::Storage);
+            self.db.writer.save_transactions(
+                &buffer.txns_to_commit,
+                buffer.first_version,
+                ledger_info_to_commit.as_ref(),
+            )
+        };
+        if let Err(error) = save_result {
+            self.reset_cache()?;
+            return Err(error);
+        }
+
+        // Cache maintenance.
+        let mut write_lock = self.cache.write();
+        if let Some(ledger_info_with_sigs) = &ledger_info_to_commit {
+            let output_trees = write_lock.synced_trees().clone();
+            write_lock.update_block_tree_root(output_trees, ledger_info_with_sigs.ledger_info());
+        }
+        write_lock.reset();
+
+        info!(
+            LogSchema::new(LogEntry::ChunkExecutor)
+                .synced_to_version(
+                    write_lock
+                        .synced_trees()
+                        .version()
+                        .expect("version must exist")
+                )
+                .committed_with_ledger_info(ledger_info_to_commit.is_some()),
+            "sync_finished",
+        );
+        Ok(())
     }
 }
 
@@ -607,15 +836,34 @@ impl<V: VMExecutor> ChunkExecutor for Executor<V> {
         // An optional end of epoch LedgerInfo. We do not allow chunks that end epoch without
         // carrying any epoch change LI.
         epoch_change_li: Option<LedgerInfoWithSignatures>,
-    ) -> Result<Vec<ContractEvent>> {
+    ) -> Result<ChunkOutput> {
         let _timer = DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS.start_timer();
-        // 1. Update the cache in executor to be consistent with latest synced state.
-        self.reset_cache()?;
-        let read_lock = self.cache.read();
+        let chunk_output = self.execute_and_buffer_chunk(
+            txn_list_with_proof,
+            verified_target_li,
+            epoch_change_li,
+        )?;
+        self.commit_buffered_chunks()?;
+        Ok(chunk_output)
+    }
+
+    fn execute_and_buffer_chunk(
+        &self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<ChunkOutput> {
+        // 1. Update the cache in executor to be consistent with latest synced state, unless the
+        // chunk is executed on top of buffered chunks.
+        let mut chunk_buffer = self.chunk_buffer.lock();
+        if chunk_buffer.is_none() {
+            self.reset_cache()?;
+        }
+        let synced_trees = self.cache.read().synced_trees().clone();
 
         info!(
             LogSchema::new(LogEntry::ChunkExecutor)
-                .local_synced_version(read_lock.synced_trees().txn_accumulator().num_leaves() - 1)
+                .local_synced_version(synced_trees.txn_accumulator().num_leaves() - 1)
                 .first_version_in_request(txn_list_with_proof.first_transaction_version)
                 .num_txns_in_request(txn_list_with_proof.transactions.len()),
             "sync_request_received",
@@ -623,52 +871,171 @@ impl<V: VMExecutor> ChunkExecutor for Executor<V> {
 
         // 2. Verify input transaction list.
         let (transactions, transaction_infos) =
-            self.verify_chunk(txn_list_with_proof, &verified_target_li)?;
+            self.verify_chunk(txn_list_with_proof, &verified_target_li, &synced_trees)?;
 
         // 3. Execute transactions.
-        let first_version = read_lock.synced_trees().txn_accumulator().num_leaves();
-        drop(read_lock);
-        let (output, txns_to_commit, events) =
-            self.execute_chunk(first_version, transactions, transaction_infos)?;
+        let first_version = synced_trees.txn_accumulator().num_leaves();
+        let persisted_trees = match chunk_buffer.as_ref() {
+            Some(buffer) => buffer.persisted_trees.clone(),
+            None => synced_trees.clone(),
+        };
+        let (output, txns_to_commit, chunk_output) = self.execute_chunk(
+            first_version,
+            &persisted_trees,
+            &synced_trees,
+            transactions,
+            transaction_infos,
+        )?;
 
-        // 4. Commit to DB.
+        // 4. Buffer the executed transactions.
         let ledger_info_to_commit =
             Self::find_chunk_li(verified_target_li, epoch_change_li, &output)?;
         if ledger_info_to_commit.is_none() && txns_to_commit.is_empty() {
-            return Ok(events);
+            return Ok(chunk_output);
         }
-        fail_point!("executor::commit_chunk", |_| {
-            Err(anyhow::anyhow!("Injected error in commit_chunk"))
-        });
-        self.db.writer.save_transactions(
-            &txns_to_commit,
+        chunk_buffer
+            .get_or_insert_with(|| ChunkBuffer {
+                persisted_trees,
+                first_version,
+                txns_to_commit: vec![],
+            })
+            .txns_to_commit
+            .extend(txns_to_commit);
+        self.cache
+            .write()
+            .update_synced_trees(output.executed_trees().clone());
+
+        // 5. Commit to DB if the chunk ends at a ledger info.
+        if ledger_info_to_commit.is_some() {
+            self.commit_chunk_buffer(&mut chunk_buffer, ledger_info_to_commit)?;
+        }
+        Ok(chunk_output)
+    }
+
+    fn commit_buffered_chunks(&self) -> Result<()> {
+        let mut chunk_buffer = self.chunk_buffer.lock();
+        self.commit_chunk_buffer(&mut chunk_buffer, None)
+    }
+
+    fn buffered_trees(&self) -> Option<ExecutedTrees> {
+        let chunk_buffer = self.chunk_buffer.lock();
+        chunk_buffer
+            .as_ref()
+            .map(|_| self.cache.read().synced_trees().clone())
+    }
+
+    fn dry_run_chunk(
+        &self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        epoch_change_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<ExecutedTrees> {
+        // 1. Start the dry run from the synced state (including any buffered chunks).
+        let mut dry_run = self.dry_run.lock();
+        if dry_run.is_none() {
+            let chunk_buffer = self.chunk_buffer.lock();
+            if chunk_buffer.is_none() {
+                self.reset_cache()?;
+            }
+            let synced_trees = self.cache.read().synced_trees().clone();
+            let persisted_trees = match chunk_buffer.as_ref() {
+                Some(buffer) => buffer.persisted_trees.clone(),
+                None => synced_trees.clone(),
+            };
+            *dry_run = Some(DryRun {
+                persisted_trees,
+                synced_trees,
+            });
+        }
+        let DryRun {
+            persisted_trees,
+            synced_trees,
+        } = dry_run.as_mut().expect("dry run must exist");
+
+        // 2. Verify input transaction list.
+        let (transactions, transaction_infos) =
+            self.verify_chunk(txn_list_with_proof, &verified_target_li, synced_trees)?;
+
+        // 3. Execute transactions and verify the result against the ledger infos.
+        let first_version = synced_trees.txn_accumulator().num_leaves();
+        let (output, _, _) = self.execute_chunk(
             first_version,
-            ledger_info_to_commit.as_ref(),
+            persisted_trees,
+            synced_trees,
+            transactions,
+            transaction_infos,
         )?;
+        Self::find_chunk_li(verified_target_li, epoch_change_li, &output)?;
 
-        // 5. Cache maintenance.
-        let mut write_lock = self.cache.write();
-        let output_trees = output.executed_trees().clone();
-        if let Some(ledger_info_with_sigs) = &ledger_info_to_commit {
-            write_lock.update_block_tree_root(output_trees, ledger_info_with_sigs.ledger_info());
-        } else {
-            write_lock.update_synced_trees(output_trees);
-        }
-        write_lock.reset();
+        *synced_trees = output.executed_trees().clone();
+        Ok(synced_trees.clone())
+    }
 
-        info!(
-            LogSchema::new(LogEntry::ChunkExecutor)
-                .synced_to_version(
-                    write_lock
-                        .synced_trees()
-                        .version()
-                        .expect("version must exist")
-                )
-                .committed_with_ledger_info(ledger_info_to_commit.is_some()),
-            "sync_finished",
+    fn reset_dry_run(&self) {
+        *self.dry_run.lock() = None;
+    }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        self.db
+            .writer
+            .get_state_snapshot_receiver(version, expected_root_hash)
+    }
+
+    fn finalize_state_snapshot(
+        &self,
+        txn_list_with_proof: TransactionListWithProof,
+        frozen_subtrees: Vec<HashValue>,
+        verified_target_li: LedgerInfoWithSignatures,
+        mut epoch_change_lis: Vec<LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        let chunk_buffer = self.chunk_buffer.lock();
+        ensure!(
+            chunk_buffer.is_none(),
+            "Cannot finalize a state snapshot while chunks are buffered."
+        );
+
+        // 1. Verify the transaction at the snapshot version.
+        let version = verified_target_li.ledger_info().version();
+        ensure!(
+            txn_list_with_proof.transactions.len() == 1
+                && txn_list_with_proof.first_transaction_version == Some(version),
+            "Expected the single transaction at the snapshot version {}, got {} transactions \
+             starting at {:?}.",
+            version,
+            txn_list_with_proof.transactions.len(),
+            txn_list_with_proof.first_transaction_version,
         );
+        txn_list_with_proof.verify(verified_target_li.ledger_info(), Some(version))?;
+        let TransactionListWithProof {
+            transactions,
+            events,
+            proof,
+            ..
+        } = txn_list_with_proof;
+        let events = events
+            .and_then(|mut events| events.pop())
+            .ok_or_else(|| format_err!("The events of the snapshot transaction are missing."))?;
+
+        // 2. Commit the transaction, the accumulator and the ledger infos.
+        if epoch_change_lis.last() != Some(&verified_target_li) {
+            epoch_change_lis.push(verified_target_li);
+        }
+        self.db.writer.finalize_state_snapshot(
+            version,
+            &frozen_subtrees,
+            &transactions[0],
+            &proof.transaction_infos[0],
+            &events,
+            &epoch_change_lis,
+        )?;
 
-        Ok(events)
+        // 3. Continue from the snapshot.
+        self.reset_cache()?;
+        Ok(())
     }
 }
 
@@ -690,8 +1057,15 @@ impl<V: VMExecutor> TransactionReplayer for Executor<V> {
         while !txns.is_empty() {
             let num_txns = txns.len();
 
-            let (output, txns_to_commit, _, txns_to_retry, txn_infos_to_retry) =
-                self.replay_transactions_impl(first_version, txns, txn_infos)?;
+            let synced_trees = self.cache.read().synced_trees().clone();
+            let (output, txns_to_commit, _, txns_to_retry, txn_infos_to_retry) = self
+                .replay_transactions_impl(
+                    first_version,
+                    &synced_trees,
+                    &synced_trees,
+                    txns,
+                    txn_infos,
+                )?;
             assert!(txns_to_retry.len() < num_txns);
 
             self.db
@@ -780,48 +1154,48 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
 
             let parent_block_executed_trees =
                 Self::get_executed_trees_from_lock(&read_lock, parent_block_id)?;
+            let parent_state_id = parent_block_executed_trees.state_id();
 
             // Hold a ref to the current base smt, so that all in-mem state in between the
             // currently committed version and the end version of the parent block won't go away
             // during execution.
             let _base_smt = read_lock.committed_trees().state_tree().clone();
 
-            let state_view = self.get_executed_state_view_from_lock(
-                &read_lock,
-                StateViewId::BlockExecution { block_id },
-                &parent_block_executed_trees,
-            );
-            drop(read_lock);
-
-            let vm_outputs = {
-                let _timer = DIEM_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS.start_timer();
-                fail_point!("executor::vm_execute_block", |_| {
-                    Err(Error::from(anyhow::anyhow!(
-                        "Injected error in vm_execute_block"
-                    )))
-                });
-                V::execute_block(transactions.clone(), &state_view).map_err(anyhow::Error::from)?
+            let cached_output =
+                self.result_cache
+                    .lock()
+                    .get(parent_state_id, block_id, &transactions);
+            let output = match cached_output {
+                Some(output) => {
+                    drop(read_lock);
+                    info!(
+                        LogSchema::new(LogEntry::BlockExecutor).block_id(block_id),
+                        "speculation_result_reused"
+                    );
+                    output
+                }
+                None => {
+                    let state_view = self.get_executed_state_view_from_lock(
+                        &read_lock,
+                        StateViewId::BlockExecution { block_id },
+                        &parent_block_executed_trees,
+                    );
+                    drop(read_lock);
+                    let output = self.execute_block_on_state_view(
+                        &transactions,
+                        state_view,
+                        &parent_block_executed_trees,
+                    )?;
+                    self.result_cache.lock().insert(
+                        parent_state_id,
+                        block_id,
+                        transactions.clone(),
+                        output.clone(),
+                    );
+                    output
+                }
             };
 
-            let status: Vec<_> = vm_outputs
-                .iter()
-                .map(TransactionOutput::status)
-                .cloned()
-                .collect();
-            if !status.is_empty() {
-                trace!("Execution status: {:?}", status);
-            }
-
-            let (account_to_state, account_to_proof) = state_view.into();
-            let output = Self::process_vm_outputs(
-                account_to_state,
-                account_to_proof,
-                &transactions,
-                vm_outputs,
-                &parent_block_executed_trees,
-            )
-            .map_err(|err| format_err!("Failed to execute block: {}", err))?;
-
             let parent_accu = parent_block_executed_trees.txn_accumulator();
 
             let state_compute_result = output.compute_result(
@@ -890,6 +1264,7 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
                     txn.clone(),
                     txn_data.account_blobs().clone(),
                     Some(txn_data.jf_node_hashes().clone()),
+                    txn_data.write_set().clone(),
                     txn_data.events().to_vec(),
                     txn_data.gas_used(),
                     recorded_status.clone(),
@@ -897,6 +1272,13 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
             }
         }
 
+        let trace_entries = self.trace_entries(
+            num_txns_in_li - txns_to_keep.len() as u64,
+            blocks.iter().flat_map(|block| {
+                itertools::zip_eq(block.transactions(), block.output().transaction_data())
+            }),
+        );
+
         let last_block = blocks
             .last()
             .ok_or_else(|| format_err!("CommittableBlockBatch is empty"))?;
@@ -947,6 +1329,7 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
         let num_txns_to_commit = txns_to_commit.len() as u64;
         {
             let _timer = DIEM_EXECUTOR_SAVE_TRANSACTIONS_SECONDS.start_timer();
+            let _latency_timer = start_latency_timer(LatencySource::Storage);
             DIEM_EXECUTOR_TRANSACTIONS_SAVED.observe(num_txns_to_commit as f64);
 
             assert_eq!(first_version_to_commit, num_txns_in_li - num_txns_to_commit);
@@ -962,10 +1345,15 @@ impl<V: VMExecutor> BlockExecutor for Executor<V> {
                 Some(&ledger_info_with_sigs),
             )?;
         }
-
-        self.cache
-            .write()
-            .prune(ledger_info_with_sigs.ledger_info())?;
+        self.record_trace(trace_entries);
+
+        let mut cache = self.cache.write();
+        cache.prune(ledger_info_with_sigs.ledger_info())?;
+        // The committed blocks and the ones conflicting with them are never executed again.
+        self.result_cache
+            .lock()
+            .retain(|block_id| cache.get_block(block_id).is_ok());
+        drop(cache);
 
         // Now that the blocks are persisted successfully, we can reply to consensus
         Ok(())
@@ -1,7 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use diem_metrics::{register_histogram, register_int_counter, Histogram, IntCounter};
+use diem_metrics::{
+    register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Histogram, IntCounter, IntCounterVec, IntGauge,
+};
 use once_cell::sync::Lazy;
 
 pub static DIEM_EXECUTOR_EXECUTE_AND_COMMIT_CHUNK_SECONDS: Lazy<Histogram> = Lazy::new(|| {
@@ -67,3 +70,25 @@ pub static DIEM_EXECUTOR_TRANSACTIONS_SAVED: Lazy<Histogram> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static DIEM_EXECUTOR_SPECULATION_RESULT_CACHE_LOOKUPS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "diem_executor_speculation_result_cache_lookups",
+        // metric description
+        "The number of lookups of speculative block execution results in Diem executor, by result",
+        // metric labels
+        &["result"]
+    )
+    .unwrap()
+});
+
+pub static DIEM_EXECUTOR_SPECULATION_RESULT_CACHE_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "diem_executor_speculation_result_cache_size",
+        // metric description
+        "The number of speculative block execution results cached in Diem executor"
+    )
+    .unwrap()
+});
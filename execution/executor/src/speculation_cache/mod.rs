@@ -19,6 +19,9 @@ mod test;
 
 use crate::{
     logging::{LogEntry, LogSchema},
+    metrics::{
+        DIEM_EXECUTOR_SPECULATION_RESULT_CACHE_LOOKUPS, DIEM_EXECUTOR_SPECULATION_RESULT_CACHE_SIZE,
+    },
     types::ProcessedVMOutput,
 };
 use anyhow::{format_err, Result};
@@ -29,7 +32,7 @@ use diem_logger::prelude::*;
 use diem_types::{ledger_info::LedgerInfo, transaction::Transaction};
 use executor_types::{Error, ExecutedTrees};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     sync::{Arc, Weak},
 };
 use storage_interface::{StartupInfo, TreeState};
@@ -284,3 +287,87 @@ impl SpeculationCache {
             })?)
     }
 }
+
+/// Keeps the outputs of the most recently executed blocks, so that a block executed again on top
+/// of the same parent state, e.g. after a consensus timeout or once the block tree was reset, is
+/// not run through the VM again. Outputs are keyed by the id of the state of the parent
+/// (i.e. the root hash of its transaction accumulator) and the block id, and are only returned
+/// for the same transactions. The total number of transactions of the outputs kept is bounded,
+/// the oldest outputs being evicted first, and the outputs of blocks which were committed or
+/// pruned are dropped.
+pub(crate) struct SpeculationResultCache {
+    max_txns: usize,
+    // The total number of transactions of `outputs`.
+    num_txns: usize,
+    outputs: HashMap<(HashValue, HashValue), (Vec<Transaction>, ProcessedVMOutput)>,
+    // Keys of `outputs` in insertion order.
+    keys: VecDeque<(HashValue, HashValue)>,
+}
+
+impl SpeculationResultCache {
+    pub fn new(max_txns: usize) -> Self {
+        Self {
+            max_txns,
+            num_txns: 0,
+            outputs: HashMap::new(),
+            keys: VecDeque::new(),
+        }
+    }
+
+    pub fn get(
+        &self,
+        parent_state_id: HashValue,
+        block_id: HashValue,
+        transactions: &[Transaction],
+    ) -> Option<ProcessedVMOutput> {
+        let output = self
+            .outputs
+            .get(&(parent_state_id, block_id))
+            .filter(|(txns, _)| txns.as_slice() == transactions)
+            .map(|(_, output)| output.clone());
+        DIEM_EXECUTOR_SPECULATION_RESULT_CACHE_LOOKUPS
+            .with_label_values(&[if output.is_some() { "hit" } else { "miss" }])
+            .inc();
+        output
+    }
+
+    pub fn insert(
+        &mut self,
+        parent_state_id: HashValue,
+        block_id: HashValue,
+        transactions: Vec<Transaction>,
+        output: ProcessedVMOutput,
+    ) {
+        // An output which does not fit is not kept, rather than evicting all the others.
+        if transactions.len() > self.max_txns {
+            return;
+        }
+        let key = (parent_state_id, block_id);
+        self.num_txns += transactions.len();
+        match self.outputs.insert(key, (transactions, output)) {
+            Some((old_txns, _)) => self.num_txns -= old_txns.len(),
+            None => self.keys.push_back(key),
+        }
+        while self.num_txns > self.max_txns {
+            let oldest = self.keys.pop_front().expect("Keys must not be empty.");
+            let (txns, _) = self.outputs.remove(&oldest).expect("Output must exist.");
+            self.num_txns -= txns.len();
+        }
+        DIEM_EXECUTOR_SPECULATION_RESULT_CACHE_SIZE.set(self.outputs.len() as i64);
+    }
+
+    /// Keeps only the outputs of the blocks for which `keep_block` holds.
+    pub fn retain(&mut self, mut keep_block: impl FnMut(&HashValue) -> bool) {
+        let outputs = &mut self.outputs;
+        let num_txns = &mut self.num_txns;
+        self.keys.retain(|key| {
+            let keep = keep_block(&key.1);
+            if !keep {
+                let (txns, _) = outputs.remove(key).expect("Output must exist.");
+                *num_txns -= txns.len();
+            }
+            keep
+        });
+        DIEM_EXECUTOR_SPECULATION_RESULT_CACHE_SIZE.set(self.outputs.len() as i64);
+    }
+}
@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::*;
-use diem_types::{block_info::BlockInfo, epoch_state::EpochState};
+use diem_types::{
+    account_address::AccountAddress, block_info::BlockInfo, block_metadata::BlockMetadata,
+    epoch_state::EpochState,
+};
 
 fn id(index: u64) -> HashValue {
     let bytes = index.to_be_bytes();
@@ -105,3 +108,97 @@ fn test_add_block_missing_parent() {
     let mut cache = create_cache();
     assert!(cache.add_block(id(99), gen_block(id(100))).is_err());
 }
+
+fn gen_output(reconfig: bool) -> ProcessedVMOutput {
+    ProcessedVMOutput::new(
+        vec![],
+        ExecutedTrees::new_empty(),
+        if reconfig {
+            Some(EpochState::empty())
+        } else {
+            None
+        },
+    )
+}
+
+#[test]
+fn test_result_cache() {
+    let txns = vec![Transaction::BlockMetadata(BlockMetadata::new(
+        id(1),
+        0,
+        0,
+        vec![],
+        AccountAddress::ZERO,
+    ))];
+    let mut cache = SpeculationResultCache::new(2);
+    cache.insert(id(0), id(1), txns.clone(), gen_output(true));
+    cache.insert(id(0), id(2), txns.clone(), gen_output(false));
+
+    // Outputs are only reused for the same parent state, block and transactions.
+    assert!(cache
+        .get(id(0), id(1), &txns)
+        .unwrap()
+        .epoch_state()
+        .is_some());
+    assert!(cache.get(id(3), id(1), &txns).is_none());
+    assert!(cache.get(id(0), id(1), &[]).is_none());
+
+    // Re-inserting an output replaces it.
+    cache.insert(id(0), id(1), txns.clone(), gen_output(false));
+    assert!(cache
+        .get(id(0), id(1), &txns)
+        .unwrap()
+        .epoch_state()
+        .is_none());
+
+    // The oldest output is evicted first, once the outputs exceed the number of transactions.
+    cache.insert(id(3), id(1), txns.clone(), gen_output(false));
+    assert!(cache.get(id(0), id(1), &txns).is_none());
+    assert!(cache.get(id(0), id(2), &txns).is_some());
+    assert!(cache.get(id(3), id(1), &txns).is_some());
+
+    // Outputs without transactions do not count.
+    cache.insert(id(0), id(3), vec![], gen_output(false));
+    assert!(cache.get(id(0), id(2), &txns).is_some());
+    assert!(cache.get(id(0), id(3), &[]).is_some());
+
+    // An output with more transactions than allowed is not kept, and does not evict the others.
+    let many_txns = vec![txns[0].clone(); 3];
+    cache.insert(id(0), id(4), many_txns.clone(), gen_output(false));
+    assert!(cache.get(id(0), id(4), &many_txns).is_none());
+    assert!(cache.get(id(0), id(2), &txns).is_some());
+    assert!(cache.get(id(3), id(1), &txns).is_some());
+
+    // Nothing but empty outputs is kept without capacity.
+    let mut cache = SpeculationResultCache::new(0);
+    cache.insert(id(0), id(1), txns.clone(), gen_output(false));
+    assert!(cache.get(id(0), id(1), &txns).is_none());
+}
+
+#[test]
+fn test_result_cache_retain() {
+    let txns = vec![Transaction::BlockMetadata(BlockMetadata::new(
+        id(1),
+        0,
+        0,
+        vec![],
+        AccountAddress::ZERO,
+    ))];
+    let mut cache = SpeculationResultCache::new(2);
+    cache.insert(id(0), id(1), txns.clone(), gen_output(false));
+    cache.insert(id(3), id(1), txns.clone(), gen_output(false));
+    cache.insert(id(0), id(2), vec![], gen_output(false));
+
+    // Dropping a block drops its outputs on top of all parent states.
+    cache.retain(|block_id| *block_id != id(1));
+    assert!(cache.get(id(0), id(1), &txns).is_none());
+    assert!(cache.get(id(3), id(1), &txns).is_none());
+    assert!(cache.get(id(0), id(2), &[]).is_some());
+
+    // The transactions of the dropped outputs no longer count.
+    cache.insert(id(0), id(5), txns.clone(), gen_output(false));
+    cache.insert(id(0), id(6), txns.clone(), gen_output(false));
+    assert!(cache.get(id(0), id(2), &[]).is_some());
+    assert!(cache.get(id(0), id(5), &txns).is_some());
+    assert!(cache.get(id(0), id(6), &txns).is_some());
+}
@@ -5,6 +5,7 @@
 
 use diem_crypto::{hash::EventAccumulatorHasher, HashValue};
 use diem_types::{
+    access_path::AccessPath,
     account_address::AccountAddress,
     account_state_blob::AccountStateBlob,
     contract_event::ContractEvent,
@@ -13,6 +14,7 @@ use diem_types::{
     on_chain_config,
     proof::accumulator::InMemoryAccumulator,
     transaction::{TransactionStatus, Version},
+    write_set::WriteSet,
 };
 use executor_types::{ExecutedTrees, StateComputeResult};
 use std::{collections::HashMap, sync::Arc};
@@ -31,6 +33,9 @@ pub struct TransactionData {
     /// and its corresponding nibble path.
     jf_node_hashes: HashMap<NibblePath, HashValue>,
 
+    /// The write set generated from this transaction.
+    write_set: WriteSet,
+
     /// The list of events emitted during this transaction.
     events: Vec<ContractEvent>,
 
@@ -48,28 +53,35 @@ pub struct TransactionData {
 
     /// The transaction info hash if the VM status output was keep, None otherwise
     txn_info_hash: Option<HashValue>,
+
+    /// The keys read from storage, if recorded by the VM for tracing.
+    read_set: Vec<AccessPath>,
 }
 
 impl TransactionData {
     pub fn new(
         account_blobs: HashMap<AccountAddress, AccountStateBlob>,
         jf_node_hashes: HashMap<NibblePath, HashValue>,
+        write_set: WriteSet,
         events: Vec<ContractEvent>,
         status: TransactionStatus,
         state_root_hash: HashValue,
         event_tree: Arc<InMemoryAccumulator<EventAccumulatorHasher>>,
         gas_used: u64,
         txn_info_hash: Option<HashValue>,
+        read_set: Vec<AccessPath>,
     ) -> Self {
         TransactionData {
             account_blobs,
             jf_node_hashes,
+            write_set,
             events,
             status,
             state_root_hash,
             event_tree,
             gas_used,
             txn_info_hash,
+            read_set,
         }
     }
 
@@ -81,6 +93,10 @@ impl TransactionData {
         &self.jf_node_hashes
     }
 
+    pub fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
     pub fn events(&self) -> &[ContractEvent] {
         &self.events
     }
@@ -104,6 +120,10 @@ impl TransactionData {
     pub fn txn_info_hash(&self) -> Option<HashValue> {
         self.txn_info_hash
     }
+
+    pub fn read_set(&self) -> &[AccessPath] {
+        &self.read_set
+    }
 }
 
 /// The output of Processing the vm output of a series of transactions to the parent
@@ -788,7 +788,8 @@ impl PublicUsageTest for MempoolValidationError {
             let resp = env.submit(&txn2);
             assert_eq!(
                 resp.error.expect("error").message,
-                "Server error: Mempool submission error: \"Failed to update gas price to 0\""
+                "Server error: Mempool submission error: \"Failed to replace transaction with gas \
+                 price 0: gas price must be at least 1 (10% above 0)\""
                     .to_string(),
             );
         });
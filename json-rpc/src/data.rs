@@ -4,18 +4,27 @@
 use crate::{
     errors::JsonRpcError,
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionListView, TransactionView,
-        TransactionsWithProofsView,
+        AccountResourcesWithProofsView, AccountStateWithProofView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView, BytesView,
+        CurrencyInfoView, EventByVersionWithProofView, EventView, EventWithProofView, MetadataView,
+        StateCheckpointView, StateProofView, TransactionListView, TransactionSimulationView,
+        TransactionView, TransactionsWithProofsView,
     },
 };
 use anyhow::Result;
+use diem_crypto::HashValue;
+use diem_state_view::StateView;
 use diem_types::{
-    account_address::AccountAddress, account_config::diem_root_address,
-    account_state::AccountState, chain_id::ChainId, event::EventKey,
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_config::diem_root_address,
+    account_state::AccountState,
+    chain_id::ChainId,
+    event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
+    transaction::{SignedTransaction, TransactionStatus},
 };
+use diem_vm::DiemVM;
 use resource_viewer::{AnnotatedMoveStruct, MoveValueAnnotator};
 use std::{
     collections::BTreeMap,
@@ -129,6 +138,27 @@ pub fn get_account_transaction(
     Ok(tx)
 }
 
+/// Returns the transaction with the given hash, if committed at or before `ledger_version`
+pub fn get_transaction_by_hash(
+    db: &dyn MoveDbReader,
+    hash: HashValue,
+    include_events: bool,
+    ledger_version: u64,
+) -> Result<Option<TransactionView>, JsonRpcError> {
+    let tx = db
+        .get_transaction_by_hash(hash, include_events, ledger_version)?
+        .map(|tx| {
+            TransactionView::try_from_tx_and_events(
+                tx.version,
+                tx.transaction,
+                tx.proof.transaction_info,
+                tx.events.unwrap_or_default(),
+            )
+        })
+        .transpose()?;
+    Ok(tx)
+}
+
 /// Returns all account transactions
 pub fn get_account_transactions(
     db: &dyn MoveDbReader,
@@ -274,6 +304,62 @@ pub fn get_accumulator_consistency_proof(
     AccumulatorConsistencyProofView::try_from(&proof).map_err(Into::into)
 }
 
+/// Returns the latest state checkpoint at or before `version`, from which a light client can
+/// bootstrap its trusted state.
+///
+/// See [`MoveDbReader::get_state_checkpoint`]
+pub fn get_state_checkpoint(
+    db: &dyn MoveDbReader,
+    version: u64,
+) -> Result<StateCheckpointView, JsonRpcError> {
+    let checkpoint = db.get_state_checkpoint(Some(version))?;
+    StateCheckpointView::try_from(&checkpoint).map_err(Into::into)
+}
+
+/// Executes `txn` on the state at `version` without committing anything, and returns the write
+/// set, events and gas it would produce if committed at the next version. Transactions that would
+/// be discarded return the VM status they would be discarded with.
+///
+/// The secondary signers of a multi-agent transaction don't need to have signed it yet: see
+/// [`DiemVM::simulate_signed_transaction`].
+pub fn simulate(
+    db: &dyn MoveDbReader,
+    version: u64,
+    txn: SignedTransaction,
+) -> Result<TransactionSimulationView, JsonRpcError> {
+    let state_view = DbStateView { db, version };
+    let output = DiemVM::simulate_signed_transaction(txn, &state_view);
+    match output.status().clone() {
+        TransactionStatus::Keep(status) => {
+            TransactionSimulationView::try_from_output(version + 1, &status, output)
+                .map_err(Into::into)
+        }
+        TransactionStatus::Discard(status_code) => Err(JsonRpcError::vm_status(status_code)),
+        TransactionStatus::Retry => Err(JsonRpcError::internal_error(
+            "Simulated transaction was not executed".to_string(),
+        )),
+    }
+}
+
+/// The state at a version, as read by the VM when simulating transactions.
+struct DbStateView<'a> {
+    db: &'a dyn MoveDbReader,
+    version: u64,
+}
+
+impl<'a> StateView for DbStateView<'a> {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        Ok(
+            get_account_state(self.db, access_path.address, self.version)?
+                .and_then(|account_state| account_state.get(&access_path.path).cloned()),
+        )
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
+}
+
 /// Returns the account state to the client, alongside a proof relative to the version and
 /// ledger_version specified by the client. If version or ledger_version are not specified,
 /// the latest known versions will be used.
@@ -296,6 +382,50 @@ pub fn get_account_state_with_proof(
     )?)
 }
 
+/// Returns the resources of the types in `resource_types` stored under `account_address` at
+/// `version`, along with the account state they are read from, its proof relative to the ledger
+/// info, and the proof of the ledger info relative to `client_known_version`, so that a light
+/// client can verify them in one round trip.
+pub fn get_account_resources_with_proofs(
+    db: &dyn MoveDbReader,
+    ledger_info: LedgerInfoWithSignatures,
+    account_address: AccountAddress,
+    resource_types: Vec<String>,
+    client_known_version: u64,
+    version: u64,
+) -> Result<AccountResourcesWithProofsView, JsonRpcError> {
+    let paths = resource_types
+        .iter()
+        .map(|resource_type| AccountResourcesWithProofsView::resource_path(resource_type))
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| JsonRpcError::invalid_param(&format!("resources: {}", e)))?;
+    let ledger_version = ledger_info.ledger_info().version();
+    let account_state_with_proof =
+        db.get_account_state_with_proof(account_address, version, ledger_version)?;
+    let account_state = account_state_with_proof
+        .blob
+        .as_ref()
+        .map(AccountState::try_from)
+        .transpose()?;
+    let resources = resource_types
+        .into_iter()
+        .zip(paths)
+        .map(|(resource_type, path)| {
+            let resource = account_state
+                .as_ref()
+                .and_then(|state| state.get(&path))
+                .map(|resource| BytesView::new(resource.clone()));
+            (resource_type, resource)
+        })
+        .collect();
+    let state_proof = db.get_state_proof_with_ledger_info(client_known_version, ledger_info)?;
+    Ok(AccountResourcesWithProofsView {
+        resources,
+        account_state_with_proof: AccountStateWithProofView::try_from(account_state_with_proof)?,
+        state_proof: StateProofView::try_from(&state_proof)?,
+    })
+}
+
 /// Get all resources stored under `account_address` at `version`
 pub fn get_resources(
     db: &dyn MoveDbReader,
@@ -79,6 +79,22 @@ fn test_method_fuzzer() {
         &gen_request_params!(["00000000000000000000000000000000000000000a550c18", 0]),
         "get_event_by_version_with_proof",
     );
+    method_fuzzer(&gen_request_params!([0]), "get_state_checkpoint");
+    method_fuzzer(
+        &gen_request_params!([
+            "00000000000000000000000000000000000000000a550c18",
+            ["0x1::AccountFreezing::FreezingBit"],
+            0
+        ]),
+        "get_account_resources_with_proofs",
+    );
+    method_fuzzer(
+        &gen_request_params!([
+            "0ea1bd7e7d69d6e9a7e0b5d4fe0e7c6eb3a2a2e1b0c7e0e5a5e0ec0e9c6cf5d1",
+            true
+        ]),
+        "get_transaction_by_hash",
+    );
 }
 
 pub fn method_fuzzer(params_data: &[u8], method: &str) {
@@ -146,6 +162,7 @@ pub fn request_fuzzer(json_request: serde_json::Value) {
         diem_types::chain_id::ChainId::test(),
         config::DEFAULT_BATCH_SIZE_LIMIT,
         config::DEFAULT_PAGE_SIZE_LIMIT,
+        diem_load_shedder::LoadShedder::new(config::LoadSheddingConfig::default()),
     );
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
@@ -6,22 +6,25 @@ use crate::{
     data,
     errors::JsonRpcError,
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionListView, TransactionView,
-        TransactionsWithProofsView,
+        AccountResourcesWithProofsView, AccountStateWithProofView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView,
+        CurrencyInfoView, EventByVersionWithProofView, EventView, EventWithProofView, MetadataView,
+        StateCheckpointView, StateProofView, TransactionListView, TransactionSimulationView,
+        TransactionView, TransactionsWithProofsView,
     },
 };
 use anyhow::Result;
 use diem_config::config::RoleType;
 use diem_json_rpc_types::request::{
-    GetAccountParams, GetAccountStateWithProofParams, GetAccountTransactionParams,
-    GetAccountTransactionsParams, GetAccountTransactionsWithProofsParams,
-    GetAccumulatorConsistencyProofParams, GetCurrenciesParams, GetEventByVersionWithProof,
-    GetEventsParams, GetEventsWithProofsParams, GetMetadataParams, GetNetworkStatusParams,
-    GetResourcesParams, GetStateProofParams, GetTransactionsParams,
-    GetTransactionsWithProofsParams, MethodRequest, SubmitParams,
+    GetAccountParams, GetAccountResourcesWithProofsParams, GetAccountStateWithProofParams,
+    GetAccountTransactionParams, GetAccountTransactionsParams,
+    GetAccountTransactionsWithProofsParams, GetAccumulatorConsistencyProofParams,
+    GetCurrenciesParams, GetEventByVersionWithProof, GetEventsParams, GetEventsWithProofsParams,
+    GetMetadataParams, GetNetworkStatusParams, GetResourcesParams, GetStateCheckpointParams,
+    GetStateProofParams, GetTransactionByHashParams, GetTransactionsParams,
+    GetTransactionsWithProofsParams, MethodRequest, SimulateParams, SubmitParams,
 };
+use diem_load_shedder::{Admission, InboundPath, LoadShedder};
 use diem_mempool::{MempoolClientSender, SubmissionStatus};
 use diem_types::{
     chain_id::ChainId, ledger_info::LedgerInfoWithSignatures, mempool_status::MempoolStatusCode,
@@ -42,6 +45,7 @@ pub(crate) struct JsonRpcService {
     chain_id: ChainId,
     batch_size_limit: u16,
     page_size_limit: u16,
+    load_shedder: LoadShedder,
 }
 
 impl JsonRpcService {
@@ -52,6 +56,7 @@ impl JsonRpcService {
         chain_id: ChainId,
         batch_size_limit: u16,
         page_size_limit: u16,
+        load_shedder: LoadShedder,
     ) -> Self {
         Self {
             db,
@@ -60,6 +65,7 @@ impl JsonRpcService {
             chain_id,
             batch_size_limit,
             page_size_limit,
+            load_shedder,
         }
     }
 
@@ -99,6 +105,19 @@ impl JsonRpcService {
         self.validate_size_limit("page size", self.page_size_limit, size)
     }
 
+    /// Applies the load shedding policy for range scans: waits if the scan is deprioritized,
+    /// fails if it is shed.
+    pub async fn admit_scan(&self) -> Result<(), JsonRpcError> {
+        match self.load_shedder.admit(InboundPath::JsonRpcScan) {
+            Admission::Accept => Ok(()),
+            Admission::Delay(delay) => {
+                tokio::time::sleep(delay).await;
+                Ok(())
+            }
+            Admission::Shed => Err(JsonRpcError::server_overloaded()),
+        }
+    }
+
     fn validate_size_limit(&self, name: &str, limit: u16, size: usize) -> Result<(), JsonRpcError> {
         if size > limit as usize {
             Err(JsonRpcError::invalid_request_with_msg(format!(
@@ -143,6 +162,9 @@ impl<'a> Handler<'a> {
     }
 
     pub async fn handle(&self, method_request: MethodRequest) -> Result<Value, JsonRpcError> {
+        if is_scan(&method_request) {
+            self.service.admit_scan().await?;
+        }
         let response: Value = match method_request {
             MethodRequest::Submit(params) => self.submit(params).await?.into(),
             MethodRequest::GetMetadata(params) => {
@@ -193,6 +215,16 @@ impl<'a> Handler<'a> {
             MethodRequest::GetEventByVersionWithProof(params) => {
                 serde_json::to_value(self.get_event_by_version_with_proof(params).await?)?
             }
+            MethodRequest::GetStateCheckpoint(params) => {
+                serde_json::to_value(self.get_state_checkpoint(params).await?)?
+            }
+            MethodRequest::Simulate(params) => serde_json::to_value(self.simulate(params).await?)?,
+            MethodRequest::GetAccountResourcesWithProofs(params) => {
+                serde_json::to_value(self.get_account_resources_with_proofs(params).await?)?
+            }
+            MethodRequest::GetTransactionByHash(params) => {
+                serde_json::to_value(self.get_transaction_by_hash(params).await?)?
+            }
         };
         Ok(response)
     }
@@ -290,6 +322,23 @@ impl<'a> Handler<'a> {
         )
     }
 
+    /// Returns the committed transaction with the hash specified by `params`
+    async fn get_transaction_by_hash(
+        &self,
+        params: GetTransactionByHashParams,
+    ) -> Result<Option<TransactionView>, JsonRpcError> {
+        let GetTransactionByHashParams {
+            hash,
+            include_events,
+        } = params;
+        data::get_transaction_by_hash(
+            self.service.db.borrow(),
+            hash,
+            include_events,
+            self.version(),
+        )
+    }
+
     /// Returns all account transactions
     async fn get_account_transactions(
         &self,
@@ -406,6 +455,25 @@ impl<'a> Handler<'a> {
         )
     }
 
+    /// Returns the resources selected by the client in the account specified by `params`, along
+    /// with the proofs relative to the version known to the client needed to verify them.
+    async fn get_account_resources_with_proofs(
+        &self,
+        params: GetAccountResourcesWithProofsParams,
+    ) -> Result<AccountResourcesWithProofsView, JsonRpcError> {
+        let client_known_version =
+            self.version_param(Some(params.client_known_version), "client_known_version")?;
+        let version = self.version_param(params.version, "version")?;
+        data::get_account_resources_with_proofs(
+            self.service.db.borrow(),
+            self.ledger_info.clone(),
+            params.account,
+            params.resources,
+            client_known_version,
+            version,
+        )
+    }
+
     /// Returns proof of new state relative to version known to client
     async fn get_state_proof(
         &self,
@@ -427,6 +495,25 @@ impl<'a> Handler<'a> {
         )
     }
 
+    /// Returns the latest state checkpoint at or before the version specified by the client, or
+    /// the latest known version if not specified.
+    async fn get_state_checkpoint(
+        &self,
+        params: GetStateCheckpointParams,
+    ) -> Result<StateCheckpointView, JsonRpcError> {
+        let version = self.version_param(params.version, "version")?;
+        data::get_state_checkpoint(self.service.db.borrow(), version)
+    }
+
+    /// Executes a signed transaction on the latest known version without submitting it, and
+    /// returns what it would change.
+    async fn simulate(
+        &self,
+        params: SimulateParams,
+    ) -> Result<TransactionSimulationView, JsonRpcError> {
+        data::simulate(self.service.db.borrow(), self.version(), params.data)
+    }
+
     /// Returns the account state to the client, alongside a proof relative to the version and
     /// ledger_version specified by the client. If version or ledger_version are not specified,
     /// the latest known versions will be used.
@@ -446,3 +533,17 @@ impl<'a> Handler<'a> {
         )
     }
 }
+
+/// Returns true for methods which scan a range of the ledger and are therefore subject to load
+/// shedding.
+fn is_scan(method_request: &MethodRequest) -> bool {
+    matches!(
+        method_request,
+        MethodRequest::GetTransactions(_)
+            | MethodRequest::GetTransactionsWithProofs(_)
+            | MethodRequest::GetAccountTransactions(_)
+            | MethodRequest::GetAccountTransactionsWithProofs(_)
+            | MethodRequest::GetEvents(_)
+            | MethodRequest::GetEventsWithProofs(_)
+    )
+}
@@ -10,8 +10,9 @@ use crate::{
     util::{sdk_info_from_user_agent, SdkInfo},
 };
 use anyhow::{ensure, Result};
-use diem_config::config::{NodeConfig, RoleType, StreamConfig};
+use diem_config::config::{LoadSheddingConfig, NodeConfig, RoleType, StreamConfig};
 use diem_json_rpc_types::Method;
+use diem_load_shedder::LoadShedder;
 use diem_logger::{debug, Schema};
 use diem_mempool::MempoolClientSender;
 use diem_types::{chain_id::ChainId, ledger_info::LedgerInfoWithSignatures};
@@ -107,6 +108,7 @@ pub fn bootstrap(
     role: RoleType,
     chain_id: ChainId,
     stream_config: &StreamConfig,
+    load_shedding_config: &LoadSheddingConfig,
 ) -> Runtime {
     let runtime = Builder::new_multi_thread()
         .thread_name("json-rpc")
@@ -121,6 +123,7 @@ pub fn bootstrap(
         chain_id,
         batch_size_limit,
         page_size_limit,
+        LoadShedder::new(load_shedding_config.clone()),
     );
 
     let base_route = warp::any()
@@ -224,6 +227,7 @@ pub fn bootstrap_from_config(
         config.base.role,
         chain_id,
         &config.json_rpc.stream_rpc,
+        &config.load_shedding,
     )
 }
 
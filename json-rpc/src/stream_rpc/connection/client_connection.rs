@@ -239,7 +239,7 @@ impl ClientConnection {
             return Err(err);
         }
 
-        match CallableStreamMethod(request.method_request).call_method(
+        match CallableStreamMethod(request.method_request.clone()).call_method(
             db.clone(),
             self.clone(),
             request.id.clone(),
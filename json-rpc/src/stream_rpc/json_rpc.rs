@@ -9,7 +9,7 @@ use tokio::task::JoinHandle;
 use crate::stream_rpc::{
     connection::ClientConnection,
     subscription_types::{Subscription, SubscriptionHelper},
-    subscriptions::{EventsSubscription, TransactionsSubscription},
+    subscriptions::{EventsSubscription, MatchingEventsSubscription, TransactionsSubscription},
 };
 use diem_json_rpc_types::{stream::request::StreamMethodRequest, Id};
 
@@ -31,6 +31,9 @@ impl CallableStreamMethod {
             StreamMethodRequest::SubscribeToEvents(params) => {
                 EventsSubscription::default().run(helper, params)
             }
+            StreamMethodRequest::SubscribeToMatchingEvents(params) => {
+                MatchingEventsSubscription::default().run(helper, params)
+            }
             // This is handled in the `handle_rpc_request` function, as we don't spawn a task
             StreamMethodRequest::Unsubscribe => unreachable!(),
         }
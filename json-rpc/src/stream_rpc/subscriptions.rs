@@ -7,15 +7,135 @@ use crate::{
     stream_rpc::subscription_types::{Subscription, SubscriptionHelper},
     views::{EventView, TransactionView},
 };
+use anyhow::{ensure, Result};
 use diem_json_rpc_types::stream::request::{
-    SubscribeToEventsParams, SubscribeToTransactionsParams,
+    EventFilter, SubscribeToEventsParams, SubscribeToMatchingEventsParams,
+    SubscribeToTransactionsParams,
 };
 use diem_logger::warn;
-use std::borrow::Borrow;
+use diem_types::{
+    account_address::AccountAddress,
+    contract_event::ContractEvent,
+    event::EventKey,
+    transaction::{Transaction, TransactionInfo, Version},
+};
+use move_core_types::{language_storage::TypeTag, parser::parse_type_tag};
+use std::{borrow::Borrow, convert::TryFrom};
 
-#[derive(Clone, Copy, Debug, Default)]
+/// The number of batches of transactions a filtered subscription reads without finding a match
+/// before it yields, so that a subscription far behind does not hold a runtime thread.
+const MAX_FILTERED_BATCHES: u64 = 10;
+
+/// An [`EventFilter`] with its type tag parsed.
+#[derive(Clone, Debug, Default)]
+pub struct ParsedEventFilter {
+    event_key: Option<EventKey>,
+    type_tag: Option<TypeTag>,
+    account: Option<AccountAddress>,
+}
+
+impl ParsedEventFilter {
+    fn parse(filter: &EventFilter) -> Result<Self, JsonRpcError> {
+        let type_tag = filter
+            .type_tag
+            .as_deref()
+            .map(parse_type_tag)
+            .transpose()
+            .map_err(|_| JsonRpcError::invalid_param("filter.type_tag"))?;
+        Ok(Self {
+            event_key: filter.event_key,
+            type_tag,
+            account: filter.account,
+        })
+    }
+
+    fn matches(&self, event: &ContractEvent) -> bool {
+        self.event_key.map_or(true, |key| event.key() == &key)
+            && self
+                .type_tag
+                .as_ref()
+                .map_or(true, |type_tag| event.type_tag() == type_tag)
+            && self
+                .account
+                .map_or(true, |account| event.key().get_creator_address() == account)
+    }
+}
+
+type TransactionWithEvents = (Version, Transaction, TransactionInfo, Vec<ContractEvent>);
+
+/// Reads the transactions from `start_version` along with their events, until some emit an
+/// event matching `filter` or the latest version is reached. Returns the transactions emitting a
+/// matching event, and the version following the last transaction read.
+fn next_matching_transactions(
+    helper: &SubscriptionHelper,
+    start_version: Version,
+    filter: &ParsedEventFilter,
+) -> Result<(Vec<TransactionWithEvents>, Version)> {
+    let ledger_version = helper.db.get_latest_version()?;
+    let limit = helper.client.config.fetch_size;
+    let mut next_version = start_version;
+    for _ in 0..MAX_FILTERED_BATCHES {
+        if next_version > ledger_version || limit == 0 {
+            break;
+        }
+        let txs = helper
+            .db
+            .get_transactions(next_version, limit, ledger_version, true)?;
+        let num_txs = txs.transactions.len();
+        if num_txs == 0 {
+            break;
+        }
+        let event_lists = txs.events.unwrap_or_default();
+        ensure!(
+            event_lists.len() == num_txs && txs.proof.transaction_infos.len() == num_txs,
+            "expected as many event lists and transaction infos as transactions",
+        );
+        let matching = txs
+            .transactions
+            .into_iter()
+            .zip(txs.proof.transaction_infos)
+            .zip(event_lists)
+            .enumerate()
+            .filter(|(_, (_, events))| events.iter().any(|event| filter.matches(event)))
+            .map(|(index, ((tx, tx_info), events))| {
+                (next_version + index as u64, tx, tx_info, events)
+            })
+            .collect::<Vec<_>>();
+        next_version += num_txs as u64;
+        if !matching.is_empty() {
+            return Ok((matching, next_version));
+        }
+    }
+    Ok((vec![], next_version))
+}
+
+#[derive(Clone, Debug, Default)]
 pub struct TransactionsSubscription {
     pub(crate) latest_version: u64,
+    pub(crate) filter: Option<ParsedEventFilter>,
+}
+
+impl TransactionsSubscription {
+    fn next_matching(
+        &mut self,
+        helper: &SubscriptionHelper,
+        filter: ParsedEventFilter,
+        include_events: bool,
+    ) -> Result<Vec<TransactionView>> {
+        let (transactions, next_version) =
+            next_matching_transactions(helper, self.latest_version, &filter)?;
+        // Skip the versions read without a match; `on_send` moves past the matching ones.
+        if transactions.is_empty() {
+            self.latest_version = next_version;
+        }
+        transactions
+            .into_iter()
+            .map(|(version, tx, tx_info, events)| {
+                let events = if include_events { events } else { vec![] };
+                TransactionView::try_from_tx_and_events(version, tx, tx_info, events)
+            })
+            .collect()
+    }
 }
 
 impl Subscription<SubscribeToTransactionsParams, TransactionView> for TransactionsSubscription {
@@ -25,14 +145,30 @@ impl Subscription<SubscribeToTransactionsParams, TransactionView> for Transactio
         params: &SubscribeToTransactionsParams,
     ) -> Result<(), JsonRpcError> {
         self.latest_version = params.starting_version;
+        self.filter = params
+            .filter
+            .as_ref()
+            .map(ParsedEventFilter::parse)
+            .transpose()?;
         Ok(())
     }
 
     fn next(
-        &self,
+        &mut self,
         helper: &SubscriptionHelper,
         params: &SubscribeToTransactionsParams,
     ) -> Vec<TransactionView> {
+        if let Some(filter) = self.filter.clone() {
+            return self
+                .next_matching(helper, filter, params.include_events.unwrap_or(false))
+                .unwrap_or_else(|e| {
+                    warn!(
+                        "Client#{} Could not fetch transactions: {}",
+                        helper.client.id, e
+                    );
+                    vec![]
+                });
+        }
         match get_transactions(
             helper.db.borrow(),
             helper.db.get_latest_version().unwrap_or(0),
@@ -52,8 +188,8 @@ impl Subscription<SubscribeToTransactionsParams, TransactionView> for Transactio
     }
 
     fn on_send(&mut self, tx: Option<&TransactionView>) {
-        if tx.is_some() {
-            self.latest_version += 1;
+        if let Some(tx) = tx {
+            self.latest_version = tx.version + 1;
         }
     }
 }
@@ -74,7 +210,7 @@ impl Subscription<SubscribeToEventsParams, EventView> for EventsSubscription {
     }
 
     fn next(
-        &self,
+        &mut self,
         helper: &SubscriptionHelper,
         params: &SubscribeToEventsParams,
     ) -> Vec<EventView> {
@@ -99,3 +235,59 @@ impl Subscription<SubscribeToEventsParams, EventView> for EventsSubscription {
         }
     }
 }
+
+#[derive(Clone, Debug, Default)]
+pub struct MatchingEventsSubscription {
+    pub(crate) latest_version: u64,
+    pub(crate) filter: ParsedEventFilter,
+}
+
+impl MatchingEventsSubscription {
+    fn next_matching(&mut self, helper: &SubscriptionHelper) -> Result<Vec<EventView>> {
+        let (transactions, next_version) =
+            next_matching_transactions(helper, self.latest_version, &self.filter)?;
+        // Skip the versions read without a match; `on_send` moves past the matching ones.
+        if transactions.is_empty() {
+            self.latest_version = next_version;
+        }
+        let mut events = vec![];
+        for (version, _, _, tx_events) in transactions {
+            for event in tx_events {
+                if self.filter.matches(&event) {
+                    events.push(EventView::try_from((version, event))?);
+                }
+            }
+        }
+        Ok(events)
+    }
+}
+
+impl Subscription<SubscribeToMatchingEventsParams, EventView> for MatchingEventsSubscription {
+    fn init(
+        &mut self,
+        _helper: &SubscriptionHelper,
+        params: &SubscribeToMatchingEventsParams,
+    ) -> Result<(), JsonRpcError> {
+        self.latest_version = params.starting_version;
+        self.filter = ParsedEventFilter::parse(&params.filter)?;
+        Ok(())
+    }
+
+    fn next(
+        &mut self,
+        helper: &SubscriptionHelper,
+        _params: &SubscribeToMatchingEventsParams,
+    ) -> Vec<EventView> {
+        self.next_matching(helper).unwrap_or_else(|e| {
+            warn!("Client#{} Could not fetch events: {}", helper.client.id, e);
+            vec![]
+        })
+    }
+
+    fn on_send(&mut self, event: Option<&EventView>) {
+        // The events of a transaction are all sent in the same batch.
+        if let Some(event) = event {
+            self.latest_version = event.transaction_version + 1;
+        }
+    }
+}
@@ -8,11 +8,12 @@ use crate::{
     },
     tests::utils::create_db_and_runtime,
 };
-use diem_json_rpc_types::stream::response::StreamJsonRpcResponse;
+use diem_json_rpc_types::{stream::response::StreamJsonRpcResponse, views::EventView};
 use futures::{SinkExt, StreamExt};
+use move_core_types::parser::parse_type_tag;
 use reqwest::Url;
 use serde_json::json;
-use std::convert::TryFrom;
+use std::{collections::BTreeSet, convert::TryFrom};
 use tokio_tungstenite::{
     connect_async,
     tungstenite::{http, Message},
@@ -33,6 +34,11 @@ async fn test_websocket_call_and_response() {
             json!({"id": "client-generated-id", "method": "subscribe_to_events", "params": {"event_key": 1337}, "jsonrpc": "2.0"}).to_string(),
             json!({"jsonrpc":"2.0","id":"client-generated-id","error":{"code":-32602,"message":"Invalid params for method 'subscribe_to_events'","data":null}}).to_string(),
         ),
+        (
+            "subscribe_to_matching_events: invalid type tag",
+            json!({"id": "client-generated-id", "method": "subscribe_to_matching_events", "params": {"starting_version": 0, "filter": {"type_tag": "0x1::"}}, "jsonrpc": "2.0"}).to_string(),
+            json!({"jsonrpc":"2.0","id":"client-generated-id","error":{"code":-32602,"message":"Invalid param filter.type_tag","data":null}}).to_string(),
+        ),
     ];
 
     for (name, request, expected) in calls {
@@ -49,7 +55,11 @@ async fn test_websocket_call_and_response() {
 async fn test_invalid_params() {
     let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
 
-    let endpoint_names = vec!["subscribe_to_transactions", "subscribe_to_events"];
+    let endpoint_names = vec![
+        "subscribe_to_transactions",
+        "subscribe_to_events",
+        "subscribe_to_matching_events",
+    ];
 
     for endpoint_name in endpoint_names {
         let name = format!("{}: invalid param", &endpoint_name);
@@ -120,6 +130,89 @@ async fn test_websocket_fetching_data() {
     }
 }
 
+#[tokio::test]
+async fn test_websocket_fetching_matching_data() {
+    let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
+
+    // Only the events of the transactions in the mock DB can be matched
+    let num_txns = mock_db.all_txns.len() as u64;
+    let events = mock_db
+        .events
+        .iter()
+        .filter(|(version, _)| *version < num_txns)
+        .collect::<Vec<_>>();
+    let (_, first_event) = events.first().expect("no events in mock DB");
+    let account = first_event.key().get_creator_address();
+    let account_events = events
+        .iter()
+        .filter(|(_, ev)| ev.key().get_creator_address() == account)
+        .collect::<Vec<_>>();
+    let num_account_txns = account_events
+        .iter()
+        .map(|(version, _)| version)
+        .collect::<BTreeSet<_>>()
+        .len();
+    // Not every generated type tag can be parsed back, e.g. if an identifier is a keyword
+    let (_, typed_event) = events
+        .iter()
+        .find(|(_, ev)| {
+            parse_type_tag(&ev.type_tag().to_string()).ok().as_ref() == Some(ev.type_tag())
+        })
+        .expect("no event with a parsable type tag in mock DB");
+    let type_tag = typed_event.type_tag();
+    let num_type_tag_events = events
+        .iter()
+        .filter(|(_, ev)| ev.type_tag() == type_tag)
+        .count();
+
+    let calls = vec![
+        (
+            "subscribe_to_transactions: get matching transaction data",
+            json!({"id": "client-generated-id", "method": "subscribe_to_transactions", "params": {"starting_version": 0, "filter": {"account": account}}, "jsonrpc": "2.0"}),
+            num_account_txns,
+        ),
+        (
+            "subscribe_to_matching_events: get account events data",
+            json!({"id": "client-generated-id", "method": "subscribe_to_matching_events", "params": {"starting_version": 0, "filter": {"account": account}}, "jsonrpc": "2.0"}),
+            account_events.len(),
+        ),
+        (
+            "subscribe_to_matching_events: get type tag events data",
+            json!({"id": "client-generated-id", "method": "subscribe_to_matching_events", "params": {"starting_version": 0, "filter": {"type_tag": type_tag.to_string()}}, "jsonrpc": "2.0"}),
+            num_type_tag_events,
+        ),
+    ];
+
+    for (name, request, expected_number) in calls {
+        let (mut ws_client, cm) = connect_to_ws(mock_db.clone(), &config, None).await;
+
+        ws_client.send_text(request.to_string()).await;
+        let sub_result = next_message(&mut ws_client, name).await;
+        verify_ok(sub_result, name);
+
+        for i in 0..expected_number {
+            // Transactions without a match are skipped in batches, with a backoff in between
+            let msg = timeout(
+                2_000,
+                ws_client.recv(),
+                &format!("{} get message {}", &name, i),
+            )
+            .await
+            .unwrap_or_else(|e| panic!("{}: message not ok. {:?}", name, e));
+            let resp: StreamJsonRpcResponse =
+                serde_json::from_str(msg.to_str().expect("response")).unwrap();
+            assert!(resp.error.is_none());
+            if name.starts_with("subscribe_to_matching_events") {
+                let event: EventView = serde_json::from_value(resp.result.unwrap()).unwrap();
+                assert!(event.transaction_version < num_txns);
+            }
+        }
+
+        close_ws(ws_client, name).await;
+        assert_eq!(num_clients(&cm), 0);
+    }
+}
+
 #[tokio::test]
 async fn test_multiple_subscriptions_and_response() {
     let (mock_db, config) = ws_test_setup(5, 10, 100, 1000).await;
@@ -2,22 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    errors::ServerCode,
+    errors::{InvalidRequestCode, ServerCode},
     runtime::check_latest_ledger_info_timestamp,
     tests::utils::{
         create_database_client_and_runtime, create_db_and_runtime, mock_db, test_bootstrap,
         MockDiemDB,
     },
     util::{sdk_info_from_user_agent, SdkInfo, SdkLang, SdkVersion},
-    views::VMStatusView,
+    views::{AccountResourcesWithProofsView, VMStatusView},
 };
 use diem_client::{views::TransactionDataView, BlockingClient, MethodRequest};
 use diem_config::{config::DEFAULT_CONTENT_LENGTH_LIMIT, utils};
 use diem_crypto::{ed25519::Ed25519PrivateKey, hash::CryptoHash, HashValue, PrivateKey, Uniform};
 use diem_metrics::get_all_metrics;
+use diem_transaction_builder::stdlib::encode_create_parent_vasp_account_script_function;
 use diem_types::{
     account_address::AccountAddress,
-    account_config::AccountResource,
+    account_config::{treasury_compliance_account_address, xus_tag, AccountResource},
     account_state::AccountState,
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
     chain_id::ChainId,
@@ -25,8 +26,11 @@ use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
     mempool_status::{MempoolStatus, MempoolStatusCode},
     proof::{SparseMerkleProof, TransactionAccumulatorProof, TransactionInfoWithProof},
+    state_checkpoint::StateCheckpoint,
     test_helpers::transaction_test_helpers::get_test_signed_txn,
-    transaction::{Transaction, TransactionInfo, TransactionPayload},
+    transaction::{
+        authenticator::AuthenticationKey, Transaction, TransactionInfo, TransactionPayload,
+    },
     vm_status::StatusCode,
 };
 use futures::{channel::mpsc::channel, StreamExt};
@@ -1417,6 +1421,43 @@ fn test_get_account_transaction() {
     }
 }
 
+#[test]
+fn test_get_transaction_by_hash() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    for (version, (tx, status)) in mock_db.all_txns.iter().enumerate() {
+        let tx_view = client
+            .get_transaction_by_hash(tx.hash(), true)
+            .unwrap()
+            .into_inner()
+            .expect("Couldn't find tx");
+        assert_eq!(tx_view.version, version as u64);
+        assert_eq!(tx_view.hash, tx.hash());
+        assert_eq!(tx_view.vm_status, VMStatusView::from(status));
+
+        let expected_events = mock_db
+            .events
+            .iter()
+            .filter(|(ev, _)| *ev == version as u64)
+            .count();
+        assert_eq!(tx_view.events.len(), expected_events);
+
+        let tx_view = client
+            .get_transaction_by_hash(tx.hash(), false)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        assert!(tx_view.events.is_empty());
+    }
+
+    // Unknown transactions are not found
+    let tx_view = client
+        .get_transaction_by_hash(HashValue::random(), true)
+        .unwrap()
+        .into_inner();
+    assert_eq!(tx_view, None);
+}
+
 #[test]
 fn test_get_account_transactions() {
     let (mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -1492,6 +1533,135 @@ fn test_get_state_proof() {
     assert_eq!(li.ledger_info().version(), version);
 }
 
+#[test]
+fn test_get_state_checkpoint() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let version = mock_db.version;
+    let view = client.get_state_checkpoint(None).unwrap().into_inner();
+    let checkpoint = StateCheckpoint::try_from(&view).unwrap();
+    assert_eq!(checkpoint.version(), version);
+    assert_eq!(view.waypoint, checkpoint.waypoint());
+
+    let mut view = client
+        .get_state_checkpoint(Some(version - 1))
+        .unwrap()
+        .into_inner();
+    assert_eq!(view.version, version - 1);
+
+    // Fields not matching the serialized checkpoint are rejected.
+    view.state_root_hash = HashValue::random();
+    assert!(StateCheckpoint::try_from(&view).is_err());
+}
+
+#[test]
+fn test_get_account_resources_with_proofs() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let account = get_first_account_from_mock_db(&mock_db);
+    let expected_proof = get_first_state_proof_from_mock_db(&mock_db);
+    let expected_state = AccountState::try_from(expected_proof.blob.as_ref().unwrap()).unwrap();
+
+    let resource_types = vec![
+        "0x1::DiemAccount::DiemAccount".to_string(),
+        "0x1::AccountFreezing::FreezingBit".to_string(),
+        "0x1::Foo::Bar".to_string(),
+    ];
+    let view = client
+        .get_account_resources_with_proofs(account, resource_types.clone(), 0, None)
+        .unwrap()
+        .into_inner();
+    assert_eq!(view.resources.len(), resource_types.len());
+    for resource_type in &resource_types {
+        let path = AccountResourcesWithProofsView::resource_path(resource_type).unwrap();
+        let expected = expected_state
+            .get(&path)
+            .map(|resource| resource.as_slice());
+        assert_eq!(view.resources[resource_type].as_deref(), expected);
+    }
+    assert_eq!(view.resources["0x1::Foo::Bar"], None);
+
+    // The resources are read from the proven account state
+    assert_eq!(
+        view.account_state_with_proof.version,
+        expected_proof.version
+    );
+    let blob: AccountStateBlob =
+        bcs::from_bytes(view.account_state_with_proof.blob.as_ref().unwrap()).unwrap();
+    assert_eq!(blob, *expected_proof.blob.as_ref().unwrap());
+    let li: LedgerInfoWithSignatures =
+        bcs::from_bytes(&view.state_proof.ledger_info_with_signatures).unwrap();
+    assert_eq!(li.ledger_info().version(), mock_db.version);
+
+    // Non resource types are rejected
+    let error = client
+        .get_account_resources_with_proofs(account, vec!["u8".to_string()], 0, None)
+        .unwrap_err();
+    let error = error.json_rpc_error().unwrap();
+    assert_eq!(error.code, InvalidRequestCode::InvalidParams as i16);
+}
+
+#[test]
+fn test_simulate() {
+    let (mock_db, client, _runtime) = create_database_client_and_runtime();
+
+    let sender = treasury_compliance_account_address();
+    let sequence_number = client
+        .get_account(sender)
+        .unwrap()
+        .into_inner()
+        .unwrap()
+        .sequence_number;
+    let new_account = AccountAddress::random();
+    let new_account_key = Ed25519PrivateKey::generate_for_testing();
+    let simulate = |sequence_number| {
+        let txn = get_test_signed_txn(
+            sender,
+            sequence_number,
+            &vm_genesis::GENESIS_KEYPAIR.0,
+            vm_genesis::GENESIS_KEYPAIR.1.clone(),
+            Some(encode_create_parent_vasp_account_script_function(
+                xus_tag(),
+                0,
+                new_account,
+                AuthenticationKey::ed25519(&new_account_key.public_key())
+                    .prefix()
+                    .to_vec(),
+                b"vasp".to_vec(),
+                false,
+            )),
+        );
+        client.simulate(&txn)
+    };
+
+    let view = simulate(sequence_number).unwrap().into_inner();
+    assert_eq!(view.vm_status, VMStatusView::Executed);
+    assert!(view
+        .write_set
+        .iter()
+        .any(|change| change.address == new_account && change.value.is_some()));
+    assert!(!view.events.is_empty());
+    assert!(view
+        .events
+        .iter()
+        .all(|event| event.transaction_version == mock_db.version + 1));
+    // Nothing is committed.
+    assert!(client
+        .get_account(new_account)
+        .unwrap()
+        .into_inner()
+        .is_none());
+
+    // Transactions that would be discarded return the reason.
+    let error = simulate(sequence_number + 1).unwrap_err();
+    let error = error.json_rpc_error().unwrap();
+    assert_eq!(error.code, ServerCode::VmValidationError as i16);
+    assert_eq!(
+        error.as_status_code().unwrap(),
+        StatusCode::SEQUENCE_NUMBER_TOO_NEW
+    );
+}
+
 #[test]
 fn test_get_network_status() {
     let (_mock_db, client, _runtime) = create_database_client_and_runtime();
@@ -4,14 +4,14 @@
 use anyhow::{format_err, Error, Result};
 use diem_config::{
     config::{
-        RoleType, StreamConfig, DEFAULT_BATCH_SIZE_LIMIT, DEFAULT_CONTENT_LENGTH_LIMIT,
-        DEFAULT_PAGE_SIZE_LIMIT, DEFAULT_STREAM_RPC_MAX_POLL_INTERVAL_MS,
-        DEFAULT_STREAM_RPC_POLL_INTERVAL_MS, DEFAULT_STREAM_RPC_SEND_QUEUE_SIZE,
-        DEFAULT_STREAM_RPC_SUBSCRIPTION_FETCH_SIZE,
+        LoadSheddingConfig, RoleType, StreamConfig, DEFAULT_BATCH_SIZE_LIMIT,
+        DEFAULT_CONTENT_LENGTH_LIMIT, DEFAULT_PAGE_SIZE_LIMIT,
+        DEFAULT_STREAM_RPC_MAX_POLL_INTERVAL_MS, DEFAULT_STREAM_RPC_POLL_INTERVAL_MS,
+        DEFAULT_STREAM_RPC_SEND_QUEUE_SIZE, DEFAULT_STREAM_RPC_SUBSCRIPTION_FETCH_SIZE,
     },
     utils,
 };
-use diem_crypto::HashValue;
+use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_mempool::{MempoolClientSender, SubmissionStatus};
 use diem_types::{
     account_address::AccountAddress,
@@ -27,6 +27,7 @@ use diem_types::{
         AccumulatorConsistencyProof, AccumulatorRangeProof, SparseMerkleProof,
         TransactionAccumulatorProof, TransactionInfoWithProof, TransactionListProof,
     },
+    state_checkpoint::StateCheckpoint,
     state_proof::StateProof,
     transaction::{
         AccountTransactionsWithProof, SignedTransaction, Transaction, TransactionInfo,
@@ -88,6 +89,7 @@ pub fn test_bootstrap(
         RoleType::Validator,
         ChainId::test(),
         &stream_config,
+        &LoadSheddingConfig::default(),
     )
 }
 
@@ -205,6 +207,38 @@ impl DbReader for MockDiemDB {
         Ok(AccountTransactionsWithProof::new(txns_with_proofs))
     }
 
+    fn get_transaction_by_hash(
+        &self,
+        hash: HashValue,
+        include_events: bool,
+        ledger_version: u64,
+    ) -> Result<Option<TransactionWithProof>> {
+        let version = self
+            .all_txns
+            .iter()
+            .take(ledger_version as usize + 1)
+            .position(|(tx, _)| tx.hash() == hash);
+        let version = match version {
+            Some(version) => version as u64,
+            None => return Ok(None),
+        };
+        let TransactionListWithProof {
+            transactions,
+            events,
+            proof,
+            ..
+        } = self.get_transactions(version, 1, ledger_version, include_events)?;
+        Ok(Some(TransactionWithProof {
+            version,
+            transaction: transactions[0].clone(),
+            events: events.map(|mut events| events.remove(0)),
+            proof: TransactionInfoWithProof::new(
+                TransactionAccumulatorProof::new(vec![]),
+                proof.transaction_infos[0].clone(),
+            ),
+        }))
+    }
+
     fn get_transactions(
         &self,
         start_version: u64,
@@ -312,6 +346,30 @@ impl DbReader for MockDiemDB {
         Ok(AccumulatorConsistencyProof::new(Vec::new()))
     }
 
+    fn get_state_checkpoint(&self, version: Option<Version>) -> Result<StateCheckpoint> {
+        let li = self.get_latest_ledger_info()?;
+        let version = version.unwrap_or(self.version);
+        let status = self
+            .all_txns
+            .get(version as usize)
+            .map(|(_, status)| status.clone())
+            .unwrap_or(KeptVMStatus::Executed);
+        Ok(StateCheckpoint::new(
+            version,
+            LedgerInfoWithSignatures::new(li.ledger_info().clone(), BTreeMap::new()),
+            TransactionInfoWithProof::new(
+                TransactionAccumulatorProof::new(vec![]),
+                TransactionInfo::new(
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                    0,
+                    status,
+                ),
+            ),
+        ))
+    }
+
     fn get_state_proof(&self, known_version: u64) -> Result<StateProof> {
         let li = self.get_latest_ledger_info()?;
         self.get_state_proof_with_ledger_info(known_version, li)
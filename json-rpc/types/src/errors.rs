@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 /// list of server internal errors
-pub static INTERNAL_ERRORS: &[i16; 7] = &[
+pub static INTERNAL_ERRORS: &[i16; 8] = &[
     ServerCode::DefaultServerError as i16,
     ServerCode::VmInvariantViolationError as i16,
     ServerCode::VmExecutionError as i16,
@@ -20,6 +20,7 @@ pub static INTERNAL_ERRORS: &[i16; 7] = &[
     ServerCode::MempoolIsFull as i16,
     ServerCode::MempoolTooManyTransactions as i16,
     ServerCode::MempoolUnknownError as i16,
+    ServerCode::ServerOverloaded as i16,
 ];
 
 pub fn is_internal_error(err_code: &i16) -> bool {
@@ -51,6 +52,9 @@ pub enum ServerCode {
     MempoolInvalidUpdate = -32010,
     MempoolVmError = -32011,
     MempoolUnknownError = -32012,
+
+    // Request rejected by load shedding while the node is overloaded
+    ServerOverloaded = -32013,
 }
 
 /// JSON RPC server error codes for invalid request
@@ -188,6 +192,14 @@ impl JsonRpcError {
         }
     }
 
+    pub fn server_overloaded() -> Self {
+        Self {
+            code: ServerCode::ServerOverloaded as i16,
+            message: "Server error: node is overloaded, retry later".to_string(),
+            data: None,
+        }
+    }
+
     pub fn code_as_str(&self) -> &'static str {
         match InvalidRequestCode::from_i16(self.code) {
             Some(code) => code.as_str(),
@@ -59,6 +59,10 @@ pub enum Method {
     GetAccountTransactionsWithProofs,
     GetEventsWithProofs,
     GetEventByVersionWithProof,
+    GetStateCheckpoint,
+    Simulate,
+    GetAccountResourcesWithProofs,
+    GetTransactionByHash,
 }
 
 impl Method {
@@ -81,6 +85,10 @@ impl Method {
             Method::GetAccountTransactionsWithProofs => "get_account_transactions_with_proofs",
             Method::GetEventsWithProofs => "get_events_with_proofs",
             Method::GetEventByVersionWithProof => "get_event_by_version_with_proof",
+            Method::GetStateCheckpoint => "get_state_checkpoint",
+            Method::Simulate => "simulate",
+            Method::GetAccountResourcesWithProofs => "get_account_resources_with_proofs",
+            Method::GetTransactionByHash => "get_transaction_by_hash",
         }
     }
 }
@@ -3,6 +3,7 @@
 
 use super::{Id, JsonRpcVersion, Method};
 use crate::{errors::JsonRpcError, views::BytesView};
+use diem_crypto::HashValue;
 use diem_types::{
     account_address::AccountAddress, event::EventKey, transaction::SignedTransaction,
 };
@@ -84,6 +85,10 @@ pub enum MethodRequest {
     GetAccountTransactionsWithProofs(GetAccountTransactionsWithProofsParams),
     GetEventsWithProofs(GetEventsWithProofsParams),
     GetEventByVersionWithProof(GetEventByVersionWithProof),
+    GetStateCheckpoint(GetStateCheckpointParams),
+    Simulate(SimulateParams),
+    GetAccountResourcesWithProofs(GetAccountResourcesWithProofsParams),
+    GetTransactionByHash(GetTransactionByHashParams),
 }
 
 impl MethodRequest {
@@ -126,6 +131,16 @@ impl MethodRequest {
             Method::GetEventByVersionWithProof => {
                 MethodRequest::GetEventByVersionWithProof(serde_json::from_value(value)?)
             }
+            Method::GetStateCheckpoint => {
+                MethodRequest::GetStateCheckpoint(serde_json::from_value(value)?)
+            }
+            Method::Simulate => MethodRequest::Simulate(serde_json::from_value(value)?),
+            Method::GetAccountResourcesWithProofs => {
+                MethodRequest::GetAccountResourcesWithProofs(serde_json::from_value(value)?)
+            }
+            Method::GetTransactionByHash => {
+                MethodRequest::GetTransactionByHash(serde_json::from_value(value)?)
+            }
         };
 
         Ok(method_request)
@@ -154,6 +169,12 @@ impl MethodRequest {
             }
             MethodRequest::GetEventsWithProofs(_) => Method::GetEventsWithProofs,
             MethodRequest::GetEventByVersionWithProof(_) => Method::GetEventByVersionWithProof,
+            MethodRequest::GetStateCheckpoint(_) => Method::GetStateCheckpoint,
+            MethodRequest::Simulate(_) => Method::Simulate,
+            MethodRequest::GetAccountResourcesWithProofs(_) => {
+                Method::GetAccountResourcesWithProofs
+            }
+            MethodRequest::GetTransactionByHash(_) => Method::GetTransactionByHash,
         }
     }
 }
@@ -383,6 +404,36 @@ pub struct GetEventByVersionWithProof {
     pub version: Option<u64>,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetStateCheckpointParams {
+    #[serde(default)]
+    pub version: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetAccountResourcesWithProofsParams {
+    pub account: AccountAddress,
+    /// The types of the resources to get, e.g. `0x1::DiemAccount::DiemAccount`.
+    pub resources: Vec<String>,
+    /// The latest version trusted by the client, from which the state proof starts.
+    pub client_known_version: u64,
+    #[serde(default)]
+    pub version: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetTransactionByHashParams {
+    pub hash: HashValue,
+    pub include_events: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SimulateParams {
+    #[serde(serialize_with = "serialize_signed_transaction")]
+    #[serde(deserialize_with = "deserialize_signed_transaction")]
+    pub data: SignedTransaction,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -996,4 +1047,106 @@ mod test {
         // Object with more params
         parse_ok(json!({ "key": key, "version": 10, "foo": 99 }));
     }
+
+    #[test]
+    fn get_state_checkpoint() {
+        let parse = |value| serde_json::from_value::<GetStateCheckpointParams>(value);
+        let parse_ok = |value| parse(value).unwrap();
+        let parse_err = |value| parse(value).unwrap_err();
+
+        // Correct arguments
+        parse_ok(json!([10]));
+        parse_ok(json!([]));
+
+        // Incorrect arguments
+        parse_err(json!([10, 11]));
+        parse_err(json!(["foo"]));
+
+        // Object params
+        parse_ok(json!({ "version": 10 }));
+        parse_ok(json!({}));
+
+        // Object with more params
+        parse_ok(json!({ "version": 10, "foo": 99 }));
+    }
+
+    #[test]
+    fn get_account_resources_with_proofs() {
+        let parse = |value| serde_json::from_value::<GetAccountResourcesWithProofsParams>(value);
+        let parse_ok = |value| parse(value).unwrap();
+        let parse_err = |value| parse(value).unwrap_err();
+
+        let account = "1668f6be25668c1a17cd8caf6b8d2f25";
+        let resources = json!(["0x1::DiemAccount::DiemAccount"]);
+
+        // Correct arguments
+        parse_ok(json!([account, resources, 5, 10]));
+        parse_ok(json!([account, resources, 5]));
+        parse_ok(json!([account, [], 5]));
+
+        // Incorrect arguments
+        parse_err(json!([account, resources, 5, 10, 11]));
+        parse_err(json!([account, "0x1::DiemAccount::DiemAccount", 5]));
+        parse_err(json!([account, resources]));
+        parse_err(json!(["foo", resources, 5]));
+        parse_err(json!([]));
+        parse_err(json!({}));
+
+        // Object params
+        parse_ok(json!({
+            "account": account,
+            "resources": resources,
+            "client_known_version": 5,
+            "version": 10,
+        }));
+        parse_ok(json!({ "account": account, "resources": resources, "client_known_version": 5 }));
+
+        // Object without all required params
+        parse_err(json!({ "account": account, "resources": resources }));
+
+        // Object with more params
+        parse_ok(json!({
+            "account": account,
+            "resources": resources,
+            "client_known_version": 5,
+            "foo": 99,
+        }));
+    }
+
+    #[test]
+    fn get_transaction_by_hash() {
+        let parse_ok = |value| serde_json::from_value::<GetTransactionByHashParams>(value).unwrap();
+        let parse_err =
+            |value| serde_json::from_value::<GetTransactionByHashParams>(value).unwrap_err();
+
+        let hash = "0ea1bd7e7d69d6e9a7e0b5d4fe0e7c6eb3a2a2e1b0c7e0e5a5e0ec0e9c6cf5d1";
+
+        // Array with all params
+        parse_ok(json!([hash, false]));
+
+        // Array with too many params
+        parse_err(json!([hash, false, "foo"]));
+
+        // Array with wrong param
+        parse_err(json!(["foo", false]));
+
+        // Array with too few params
+        parse_err(json!([hash]));
+
+        // Object params
+        parse_ok(json!({
+            "hash": hash,
+            "include_events": true,
+        }));
+
+        // Object without all params
+        parse_err(json!({ "include_events": true }));
+
+        // Object with more params
+        parse_ok(json!({
+            "hash": hash,
+            "include_events": true,
+            "foo": 11,
+        }));
+    }
 }
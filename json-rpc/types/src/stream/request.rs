@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{errors::JsonRpcError, request::RawJsonRpcRequest, Id, JsonRpcVersion};
-use diem_types::event::EventKey;
+use diem_types::{account_address::AccountAddress, event::EventKey};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
@@ -68,12 +68,13 @@ impl FromStr for StreamJsonRpcRequest {
     }
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "method", content = "params")]
 pub enum StreamMethodRequest {
     SubscribeToTransactions(SubscribeToTransactionsParams),
     SubscribeToEvents(SubscribeToEventsParams),
+    SubscribeToMatchingEvents(SubscribeToMatchingEventsParams),
     Unsubscribe,
 }
 
@@ -93,6 +94,9 @@ impl StreamMethodRequest {
             StreamMethod::SubscribeToEvents => {
                 StreamMethodRequest::SubscribeToEvents(serde_json::from_value(value)?)
             }
+            StreamMethod::SubscribeToMatchingEvents => {
+                StreamMethodRequest::SubscribeToMatchingEvents(serde_json::from_value(value)?)
+            }
             StreamMethod::Unsubscribe => StreamMethodRequest::Unsubscribe,
         };
 
@@ -105,6 +109,9 @@ impl StreamMethodRequest {
                 StreamMethod::SubscribeToTransactions
             }
             StreamMethodRequest::SubscribeToEvents(_) => StreamMethod::SubscribeToEvents,
+            StreamMethodRequest::SubscribeToMatchingEvents(_) => {
+                StreamMethod::SubscribeToMatchingEvents
+            }
             StreamMethodRequest::Unsubscribe => StreamMethod::Unsubscribe,
         }
     }
@@ -115,6 +122,7 @@ impl StreamMethodRequest {
 pub enum StreamMethod {
     SubscribeToTransactions,
     SubscribeToEvents,
+    SubscribeToMatchingEvents,
     Unsubscribe,
 }
 
@@ -123,6 +131,7 @@ impl StreamMethod {
         match self {
             StreamMethod::SubscribeToTransactions => "subscribe_to_transactions",
             StreamMethod::SubscribeToEvents => "subscribe_to_events",
+            StreamMethod::SubscribeToMatchingEvents => "subscribe_to_matching_events",
             StreamMethod::Unsubscribe => "unsubscribe",
         }
     }
@@ -134,8 +143,28 @@ pub struct SubscribeToEventsParams {
     pub event_seq_num: u64,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SubscribeToTransactionsParams {
     pub starting_version: u64,
     pub include_events: Option<bool>,
+    /// Only the transactions emitting an event matching the filter are sent, if set.
+    pub filter: Option<EventFilter>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SubscribeToMatchingEventsParams {
+    /// The version of the first transaction whose events are sent.
+    pub starting_version: u64,
+    pub filter: EventFilter,
+}
+
+/// Matches the events having all the fields set in the filter: an empty filter matches all
+/// events.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EventFilter {
+    pub event_key: Option<EventKey>,
+    /// The type of the event, e.g. `0x1::DiemAccount::ReceivedPaymentEvent`.
+    pub type_tag: Option<String>,
+    /// The account whose event handle emitted the event.
+    pub account: Option<AccountAddress>,
 }
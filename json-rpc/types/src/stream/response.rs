@@ -35,7 +35,9 @@ impl StreamJsonRpcResponseView {
             StreamMethod::SubscribeToTransactions => {
                 Self::Transaction(serde_json::from_value(value)?)
             }
-            StreamMethod::SubscribeToEvents => Self::Event(serde_json::from_value(value)?),
+            StreamMethod::SubscribeToEvents | StreamMethod::SubscribeToMatchingEvents => {
+                Self::Event(serde_json::from_value(value)?)
+            }
             StreamMethod::Unsubscribe => Self::UnsubscribeResult(serde_json::from_value(value)?),
         })
     }
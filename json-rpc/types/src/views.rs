@@ -5,6 +5,7 @@ use anyhow::{ensure, format_err, Error, Result};
 use diem_crypto::hash::{CryptoHash, HashValue};
 use diem_transaction_builder::{error_explain, stdlib::ScriptCall};
 use diem_types::{
+    access_path::AccessPath,
     account_config::{
         AccountResource, AccountRole, AdminTransactionEvent, BalanceResource, BaseUrlRotationEvent,
         BurnEvent, CancelBurnEvent, ComplianceKeyRotationEvent, CreateAccountEvent,
@@ -17,16 +18,20 @@ use diem_types::{
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     diem_id_identifier::DiemIdVaspDomainIdentifier,
     event::EventKey,
+    ledger_info::LedgerInfo,
     proof::{
         AccountStateProof, AccumulatorConsistencyProof, SparseMerkleProof,
         TransactionAccumulatorProof, TransactionInfoWithProof, TransactionListProof,
     },
+    state_checkpoint::StateCheckpoint,
     state_proof::StateProof,
     transaction::{
         AccountTransactionsWithProof, Script, ScriptFunction, Transaction, TransactionArgument,
-        TransactionInfo, TransactionListWithProof, TransactionPayload,
+        TransactionInfo, TransactionListWithProof, TransactionOutput, TransactionPayload,
     },
     vm_status::KeptVMStatus,
+    waypoint::Waypoint,
+    write_set::WriteOp,
 };
 use hex::FromHex;
 use move_core_types::{
@@ -34,6 +39,7 @@ use move_core_types::{
     identifier::Identifier,
     language_storage::{StructTag, TypeTag},
     move_resource::MoveStructType,
+    parser::parse_type_tag,
     vm_status::AbortLocation,
 };
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
@@ -850,6 +856,59 @@ impl TransactionView {
     }
 }
 
+/// What a transaction would do if it were committed right after the version it is simulated on.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TransactionSimulationView {
+    pub vm_status: VMStatusView,
+    pub gas_used: u64,
+    pub write_set: Vec<WriteSetChangeView>,
+    /// The events emitted, whose `transaction_version` is the version the transaction would be
+    /// committed at.
+    pub events: Vec<EventView>,
+}
+
+impl TransactionSimulationView {
+    /// The view of the `output` of a transaction kept with `status` when committed at `version`.
+    pub fn try_from_output(
+        version: u64,
+        status: &KeptVMStatus,
+        output: TransactionOutput,
+    ) -> Result<Self> {
+        let gas_used = output.gas_used();
+        let (write_set, events) = output.into();
+        let events = events
+            .into_iter()
+            .map(|event| EventView::try_from((version, event)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            vm_status: VMStatusView::from(status),
+            gas_used,
+            write_set: write_set
+                .iter()
+                .map(|(access_path, write_op)| WriteSetChangeView {
+                    address: access_path.address,
+                    path: BytesView::from(access_path.path.as_slice()),
+                    value: match write_op {
+                        WriteOp::Value(value) => Some(BytesView::from(value.as_slice())),
+                        WriteOp::Deletion => None,
+                    },
+                })
+                .collect(),
+            events,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct WriteSetChangeView {
+    pub address: AccountAddress,
+    /// The path of the changed value under `address`, e.g. a resource tag.
+    pub path: BytesView,
+    /// The new value, or `None` if the value is deleted.
+    pub value: Option<BytesView>,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct TransactionListView(pub Vec<TransactionView>);
 
@@ -1440,6 +1499,42 @@ impl TryFrom<&AccumulatorConsistencyProofView> for AccumulatorConsistencyProof {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct StateCheckpointView {
+    pub version: u64,
+    pub epoch: u64,
+    pub state_root_hash: HashValue,
+    pub waypoint: Waypoint,
+    pub state_checkpoint: BytesView,
+}
+
+impl TryFrom<&StateCheckpoint> for StateCheckpointView {
+    type Error = Error;
+
+    fn try_from(checkpoint: &StateCheckpoint) -> Result<Self, Self::Error> {
+        Ok(Self {
+            version: checkpoint.version(),
+            epoch: checkpoint.epoch(),
+            state_root_hash: checkpoint.state_root_hash(),
+            waypoint: checkpoint.waypoint(),
+            state_checkpoint: BytesView::new(bcs::to_bytes(checkpoint)?),
+        })
+    }
+}
+
+impl TryFrom<&StateCheckpointView> for StateCheckpoint {
+    type Error = Error;
+
+    fn try_from(view: &StateCheckpointView) -> Result<Self, Self::Error> {
+        let checkpoint: StateCheckpoint = bcs::from_bytes(view.state_checkpoint.as_ref())?;
+        ensure!(
+            view == &StateCheckpointView::try_from(&checkpoint)?,
+            "State checkpoint view fields don't match the serialized checkpoint.",
+        );
+        Ok(checkpoint)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct AccountStateWithProofView {
     pub version: u64,
@@ -1484,6 +1579,60 @@ impl TryFrom<&AccountStateWithProofView> for AccountStateWithProof {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AccountResourcesWithProofsView {
+    /// The selected resources keyed by their types, null for the ones the account doesn't have.
+    pub resources: BTreeMap<String, Option<BytesView>>,
+    /// The state of the account the resources are read from, proven by the ledger info of
+    /// `state_proof`.
+    pub account_state_with_proof: AccountStateWithProofView,
+    pub state_proof: StateProofView,
+}
+
+impl AccountResourcesWithProofsView {
+    /// Returns the path of the resource of type `resource_type`, e.g.
+    /// `0x1::DiemAccount::DiemAccount`, in an account state.
+    pub fn resource_path(resource_type: &str) -> Result<Vec<u8>> {
+        match parse_type_tag(resource_type)? {
+            TypeTag::Struct(struct_tag) => Ok(AccessPath::resource_access_vec(struct_tag)),
+            type_tag => Err(format_err!("{} is not a resource type", type_tag)),
+        }
+    }
+
+    /// Verifies the selected resources are the ones of the account at `address` at `version`, in
+    /// the ledger `ledger_info`, and returns them. The ledger info must itself be verified with
+    /// `state_proof` first.
+    pub fn verify(
+        &self,
+        address: AccountAddress,
+        version: u64,
+        ledger_info: &LedgerInfo,
+    ) -> Result<BTreeMap<String, Option<Vec<u8>>>> {
+        let account_state_with_proof =
+            AccountStateWithProof::try_from(&self.account_state_with_proof)?;
+        account_state_with_proof.verify(ledger_info, version, address)?;
+        let account_state = account_state_with_proof
+            .blob
+            .as_ref()
+            .map(AccountState::try_from)
+            .transpose()?;
+
+        self.resources
+            .iter()
+            .map(|(resource_type, resource)| {
+                let path = Self::resource_path(resource_type)?;
+                let expected = account_state.as_ref().and_then(|state| state.get(&path));
+                ensure!(
+                    resource.as_deref() == expected.map(Vec::as_slice),
+                    "Resource {} doesn't match the account state.",
+                    resource_type,
+                );
+                Ok((resource_type.clone(), expected.cloned()))
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct AccountStateProofView {
     pub ledger_info_to_transaction_info_proof: BytesView,
@@ -12,6 +12,9 @@ use once_cell::sync::Lazy;
 use std::{convert::TryFrom, path::PathBuf};
 
 pub mod legacy;
+mod manifest;
+
+pub use manifest::{ModuleDrift, ReleaseManifest, ReleaseVersion, MANIFEST_FILE_NAME};
 
 #[cfg(test)]
 mod tests;
@@ -69,6 +72,80 @@ pub fn load_error_descriptions_from_release(release_name: &str) -> Result<Vec<u8
     }
 }
 
+/// Load the script ABIs from the specified release, by `<module name>/<function name>`.
+pub fn load_script_abis_from_release(release_name: &str) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut abis_path = PathBuf::from(release_name);
+    abis_path.push("script_abis");
+
+    match RELEASES_DIR.get_dir(&abis_path) {
+        Some(abis_dir) => {
+            let mut abis = vec![];
+            for module_dir in abis_dir.dirs() {
+                let module_name = module_dir.path().file_name().unwrap().to_string_lossy();
+                for file in module_dir.files() {
+                    let function_name = file.path().file_stem().unwrap().to_string_lossy();
+                    abis.push((
+                        format!("{}/{}", module_name, function_name),
+                        file.contents().to_vec(),
+                    ));
+                }
+            }
+            abis.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
+            Ok(abis)
+        }
+        None => bail!("release {} not found", release_name),
+    }
+}
+
+/// Load the manifest shipped with the specified release.
+pub fn load_manifest_from_release(release_name: &str) -> Result<ReleaseManifest> {
+    let mut manifest_path = PathBuf::from(release_name);
+    manifest_path.push(MANIFEST_FILE_NAME);
+
+    match RELEASES_DIR.get_file(manifest_path) {
+        Some(file) => Ok(serde_json::from_slice(file.contents())?),
+        None => bail!("manifest of release {} not found", release_name),
+    }
+}
+
+/// Build the manifest of the specified release from its content. It is expected to be equal to
+/// the manifest shipped with the release.
+pub fn build_manifest_for_release(release_name: &str) -> Result<ReleaseManifest> {
+    let modules = load_modules_from_release(release_name)?;
+    let script_abis = load_script_abis_from_release(release_name)?;
+    let error_descriptions = load_error_descriptions_from_release(release_name)?;
+    ReleaseManifest::new(
+        release_name,
+        modules.iter().map(Vec::as_slice),
+        script_abis
+            .iter()
+            .map(|(name, abi)| (name.clone(), abi.as_slice())),
+        &error_descriptions,
+    )
+}
+
+/// Find the release whose modules are exactly `module_blobs`, e.g. the modules published under
+/// the Diem Framework code address. When several releases match, the latest versioned one is
+/// returned.
+pub fn find_release_for_modules(module_blobs: &[Vec<u8>]) -> Result<Option<ReleaseManifest>> {
+    let mut matching = None;
+    for release_name in list_all_releases()? {
+        let manifest = load_manifest_from_release(&release_name)?;
+        if !manifest
+            .module_drift(module_blobs.iter().map(Vec::as_slice))?
+            .is_empty()
+        {
+            continue;
+        }
+        // Unversioned releases, like `current`, have the lowest version
+        matching = match matching {
+            Some(found) if found.version >= manifest.version => Some(found),
+            _ => Some(manifest),
+        };
+    }
+    Ok(matching)
+}
+
 static CURRENT_MODULE_BLOBS: Lazy<Vec<Vec<u8>>> =
     Lazy::new(|| load_modules_from_release("current").unwrap());
 
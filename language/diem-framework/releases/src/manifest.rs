@@ -0,0 +1,217 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Manifests describing the content of Diem Framework releases.
+//!
+//! A manifest records the version of a release along with the hashes of its compiled modules,
+//! script ABIs and error descriptions. It is generated together with the release, so that a
+//! rebuilt release can be checked against the published one, and so that the modules published
+//! on-chain can be traced back to the release they come from.
+
+use anyhow::{bail, format_err, Error, Result};
+use diem_crypto::HashValue;
+use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use move_binary_format::file_format::CompiledModule;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+};
+
+/// Name of the manifest file at the root of a release.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Prefix of the names of the versioned releases, e.g. `release-1.4.0-rc0`.
+const RELEASE_NAME_PREFIX: &str = "release-";
+
+/// The Genesis module is only run by the genesis transaction and never published on-chain.
+const GENESIS_MODULE_NAME: &str = "Genesis";
+
+/// Semantic version of a release, e.g. `1.4.0-rc0`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ReleaseVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre_release: Option<String>,
+}
+
+impl ReleaseVersion {
+    /// Returns the version of a release named `release-<version>`, or `None` for the releases
+    /// which are not versioned, like `current`.
+    pub fn from_release_name(release_name: &str) -> Option<Self> {
+        release_name
+            .strip_prefix(RELEASE_NAME_PREFIX)
+            .and_then(|version| version.parse().ok())
+    }
+}
+
+impl FromStr for ReleaseVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (version, pre_release) = match s.split_once('-') {
+            Some((version, pre_release)) if !pre_release.is_empty() => {
+                (version, Some(pre_release.to_string()))
+            }
+            Some(_) => bail!("empty pre-release in version {}", s),
+            None => (s, None),
+        };
+        let numbers = version
+            .split('.')
+            .map(u64::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format_err!("invalid version {}: {}", s, err))?;
+        match numbers.as_slice() {
+            [major, minor, patch] => Ok(Self {
+                major: *major,
+                minor: *minor,
+                patch: *patch,
+                pre_release,
+            }),
+            _ => bail!("invalid version {}: expected <major>.<minor>.<patch>", s),
+        }
+    }
+}
+
+impl fmt::Display for ReleaseVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre_release) = &self.pre_release {
+            write!(f, "-{}", pre_release)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for ReleaseVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            // A pre-release precedes the release of the same version
+            .then_with(|| match (&self.pre_release, &other.pre_release) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(pre), Some(other_pre)) => pre.cmp(other_pre),
+            })
+    }
+}
+
+impl PartialOrd for ReleaseVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Serialize for ReleaseVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ReleaseVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// The content of a Diem Framework release, identified by hashes.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, CryptoHasher, BCSCryptoHash)]
+pub struct ReleaseManifest {
+    pub name: String,
+    pub version: Option<ReleaseVersion>,
+    /// Hashes of the compiled modules, by module name.
+    pub modules: BTreeMap<String, HashValue>,
+    /// Hashes of the script ABIs, by `<module name>/<function name>`.
+    pub script_abis: BTreeMap<String, HashValue>,
+    /// Hash of the error descriptions.
+    pub error_descriptions: HashValue,
+}
+
+impl ReleaseManifest {
+    pub fn new<'a>(
+        release_name: &str,
+        module_blobs: impl IntoIterator<Item = &'a [u8]>,
+        script_abis: impl IntoIterator<Item = (String, &'a [u8])>,
+        error_descriptions: &[u8],
+    ) -> Result<Self> {
+        let modules = module_blobs
+            .into_iter()
+            .map(|blob| Ok((module_name(blob)?, HashValue::sha3_256_of(blob))))
+            .collect::<Result<_>>()?;
+        let script_abis = script_abis
+            .into_iter()
+            .map(|(name, abi)| (name, HashValue::sha3_256_of(abi)))
+            .collect();
+        Ok(Self {
+            name: release_name.to_string(),
+            version: ReleaseVersion::from_release_name(release_name),
+            modules,
+            script_abis,
+            error_descriptions: HashValue::sha3_256_of(error_descriptions),
+        })
+    }
+
+    /// Compares the modules of the release with the modules published on-chain.
+    pub fn module_drift<'a>(
+        &self,
+        on_chain_module_blobs: impl IntoIterator<Item = &'a [u8]>,
+    ) -> Result<ModuleDrift> {
+        let mut drift = ModuleDrift::default();
+        let mut on_chain_names = BTreeSet::new();
+        for blob in on_chain_module_blobs {
+            let name = module_name(blob)?;
+            match self.modules.get(&name) {
+                Some(hash) if *hash == HashValue::sha3_256_of(blob) => (),
+                Some(_) => drift.changed.push(name.clone()),
+                None => drift.unexpected.push(name.clone()),
+            }
+            on_chain_names.insert(name);
+        }
+        drift.missing = self
+            .modules
+            .keys()
+            .filter(|name| name.as_str() != GENESIS_MODULE_NAME && !on_chain_names.contains(*name))
+            .cloned()
+            .collect();
+        Ok(drift)
+    }
+}
+
+/// Differences between the modules of a release and the modules published on-chain.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ModuleDrift {
+    /// Modules published on-chain with a content different from the release.
+    pub changed: Vec<String>,
+    /// Modules of the release which are not published on-chain.
+    pub missing: Vec<String>,
+    /// Modules published on-chain which are not part of the release.
+    pub unexpected: Vec<String>,
+}
+
+impl ModuleDrift {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for ModuleDrift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "changed: {:?}, missing: {:?}, unexpected: {:?}",
+            self.changed, self.missing, self.unexpected
+        )
+    }
+}
+
+fn module_name(blob: &[u8]) -> Result<String> {
+    let module = CompiledModule::deserialize(blob)
+        .map_err(|err| format_err!("failed to deserialize module: {:?}", err))?;
+    Ok(module.self_id().name().to_string())
+}
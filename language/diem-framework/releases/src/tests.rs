@@ -1,6 +1,7 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::{ReleaseManifest, ReleaseVersion};
 use move_binary_format::CompiledModule;
 
 #[test]
@@ -41,4 +42,63 @@ fn can_deserialize_and_verify_modules_from_all_versions() {
     }
 }
 
-// TODO: tests to ensure script abis and error_descriptions can be correctly read
+#[test]
+fn manifests_match_content_of_all_versions() {
+    for ver in crate::list_all_releases().unwrap() {
+        let manifest = crate::load_manifest_from_release(&ver).unwrap();
+        assert_eq!(
+            manifest,
+            crate::build_manifest_for_release(&ver).unwrap(),
+            "manifest of version {} is out of date",
+            ver
+        );
+        assert!(!manifest.script_abis.is_empty());
+        assert_eq!(manifest.version, ReleaseVersion::from_release_name(&ver));
+    }
+}
+
+#[test]
+fn release_versions() {
+    let version: ReleaseVersion = "1.4.0-rc0".parse().unwrap();
+    assert_eq!(
+        ReleaseVersion::from_release_name("release-1.4.0-rc0"),
+        Some(version.clone())
+    );
+    assert_eq!(version.to_string(), "1.4.0-rc0");
+    assert_eq!(ReleaseVersion::from_release_name("current"), None);
+    assert!("1.4".parse::<ReleaseVersion>().is_err());
+    assert!("1.4.0-".parse::<ReleaseVersion>().is_err());
+
+    let older: ReleaseVersion = "1.2.0".parse().unwrap();
+    let newer: ReleaseVersion = "1.4.0".parse().unwrap();
+    assert!(older < version && version < newer);
+}
+
+#[test]
+fn find_release_of_published_modules() {
+    let current = crate::load_manifest_from_release("current").unwrap();
+    let published: Vec<_> = crate::current_modules_with_blobs()
+        .filter(|(_, module)| module.self_id().name().as_str() != "Genesis")
+        .map(|(blob, _)| blob.clone())
+        .collect();
+    let found = crate::find_release_for_modules(&published)
+        .unwrap()
+        .expect("current modules should match a release");
+    assert_eq!(found.modules, current.modules);
+
+    // Dropping a module, or publishing one of another release, is a drift
+    let missing = &published[1..];
+    let drift = current
+        .module_drift(missing.iter().map(Vec::as_slice))
+        .unwrap();
+    assert_eq!(drift.missing.len(), 1);
+    assert!(drift.changed.is_empty() && drift.unexpected.is_empty());
+    assert!(crate::find_release_for_modules(missing).unwrap().is_none());
+
+    let old_release: ReleaseManifest =
+        crate::load_manifest_from_release("release-1.2.0-rc0").unwrap();
+    let drift = old_release
+        .module_drift(published.iter().map(Vec::as_slice))
+        .unwrap();
+    assert!(!drift.is_empty());
+}
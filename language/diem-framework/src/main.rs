@@ -42,10 +42,16 @@ fn main() {
                 .help("do not print information about linking and layout compatibility between the old and new standard library"),
         )
         .arg(Arg::with_name("no-errmap").long("no-errmap").help("do not generate error explanations"))
+        .arg(Arg::with_name("no-manifest").long("no-manifest").help("do not generate the release manifest"))
         .arg(
             Arg::with_name("with-diagram")
                 .long("with-diagram")
                 .help("include diagrams in the stdlib documentation")
+        )
+        .arg(
+            Arg::with_name("with-checked-modules")
+                .long("with-checked-modules")
+                .help("also build the modules with runtime checks of their specification, for test networks")
         );
     let matches = cli.get_matches();
     let options = release::ReleaseOptions {
@@ -57,6 +63,8 @@ fn main() {
         script_abis: !matches.is_present("no-script-abi"),
         script_builder: !matches.is_present("no-script-builder"),
         errmap: !matches.is_present("no-errmap"),
+        manifest: !matches.is_present("no-manifest"),
+        checked_modules: matches.is_present("with-checked-modules"),
         time_it: true,
     };
 
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{diem_framework_named_addresses, path_in_crate, save_binary};
+use bytecode_verifier::verify_module;
+use diem_framework_releases::{ReleaseManifest, MANIFEST_FILE_NAME};
 use log::LevelFilter;
 use move_binary_format::{compatibility::Compatibility, normalized::Module, CompiledModule};
 use move_command_line_common::files::{
@@ -11,7 +13,7 @@ use move_core_types::language_storage::ModuleId;
 use move_symbol_pool::Symbol;
 use std::{
     collections::BTreeMap,
-    fs::{create_dir_all, remove_dir_all, File},
+    fs::{self, create_dir_all, remove_dir_all, File},
     io::Read,
     path::Path,
 };
@@ -77,11 +79,15 @@ where
         let new_module_id = module.self_id();
         if let Some(old_api) = old.get(&new_module_id) {
             let new_api = Module::new(module);
-            let compatibility = Compatibility::check(old_api, &new_api);
+            let changes = Compatibility::changes(old_api, &new_api);
+            let compatibility = Compatibility::from_changes(&changes);
             if is_linking_layout_compatible && !compatibility.is_fully_compatible() {
                 println!("Found linking/layout-incompatible change:");
                 is_linking_layout_compatible = false
             }
+            for change in changes.iter().filter(|change| change.is_breaking()) {
+                println!("{}: {}", new_module_id.name(), change);
+            }
             if !compatibility.struct_and_function_linking {
                 println!("Linking API for structs/functions of module {} has changed. Need to redeploy all dependent modules.", new_module_id.name())
             }
@@ -274,6 +280,95 @@ fn build_error_code_map(output_path: impl AsRef<Path>) {
     move_prover::run_move_prover_errors_to_stderr(options).unwrap();
 }
 
+fn build_checked_modules(output_path: impl AsRef<Path>) {
+    let output_path = output_path.as_ref();
+    recreate_dir(output_path);
+
+    let options = move_prover::cli::Options {
+        move_sources: crate::diem_stdlib_files(),
+        move_deps: vec![],
+        move_named_address_values: move_prover::cli::named_addresses_for_options(
+            &diem_framework_named_addresses(),
+        ),
+        verbosity_level: LevelFilter::Warn,
+        run_checked_build: true,
+        checked_build: move_prover::checked_build::CheckedBuildOptions {
+            output_directory: output_path.to_string_lossy().to_string(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    options.setup_logging_for_test();
+    move_prover::run_move_prover_errors_to_stderr(options).unwrap();
+
+    // The runtime checks must not make the modules unsafe to publish
+    let files = find_filenames(&[output_path], |p| {
+        extension_equals(p, MOVE_COMPILED_EXTENSION)
+    })
+    .unwrap();
+    for f in files {
+        let bytes = fs::read(&f).expect("Failed to read checked module");
+        let module =
+            CompiledModule::deserialize(&bytes).expect("Failed to deserialize checked module");
+        verify_module(&module)
+            .unwrap_or_else(|err| panic!("Checked module {} does not verify: {:?}", f, err));
+    }
+}
+
+fn generate_manifest(release_path: &Path) {
+    let release_name = release_path
+        .file_name()
+        .expect("Release path should name a directory")
+        .to_string_lossy();
+    let read = |path: &Path| {
+        fs::read(path).unwrap_or_else(|_| panic!("Failed to read {}", path.to_string_lossy()))
+    };
+
+    let modules: Vec<_> = find_filenames(&[release_path.join("modules")], |p| {
+        extension_equals(p, MOVE_COMPILED_EXTENSION)
+    })
+    .unwrap()
+    .into_iter()
+    .map(|p| read(Path::new(&p)))
+    .collect();
+
+    let script_abis: Vec<_> = find_filenames(&[release_path.join("script_abis")], |p| {
+        extension_equals(p, "abi")
+    })
+    .unwrap()
+    .into_iter()
+    .map(|p| {
+        let path = Path::new(&p);
+        let module_name = path
+            .parent()
+            .unwrap()
+            .file_name()
+            .unwrap()
+            .to_string_lossy();
+        let function_name = path.file_stem().unwrap().to_string_lossy();
+        (format!("{}/{}", module_name, function_name), read(path))
+    })
+    .collect();
+
+    let mut errmap_path = release_path
+        .join("error_description")
+        .join("error_description");
+    errmap_path.set_extension(MOVE_ERROR_DESC_EXTENSION);
+
+    let manifest = ReleaseManifest::new(
+        &release_name,
+        modules.iter().map(Vec::as_slice),
+        script_abis
+            .iter()
+            .map(|(name, abi)| (name.clone(), abi.as_slice())),
+        &read(&errmap_path),
+    )
+    .expect("Failed to build the release manifest");
+    let json = serde_json::to_string_pretty(&manifest).expect("Failed to serialize the manifest");
+    fs::write(release_path.join(MANIFEST_FILE_NAME), format!("{}\n", json))
+        .expect("Failed to write the release manifest");
+}
+
 /// Options to configure the generation of a release.
 pub struct ReleaseOptions {
     pub check_layout_compatibility: bool,
@@ -284,6 +379,10 @@ pub struct ReleaseOptions {
     pub script_abis: bool,
     pub script_builder: bool,
     pub errmap: bool,
+    pub manifest: bool,
+    /// Whether to build the modules with runtime checks of their specification, for test
+    /// networks. The checked modules are not part of the manifest.
+    pub checked_modules: bool,
     pub time_it: bool,
 }
 
@@ -298,6 +397,8 @@ impl Default for ReleaseOptions {
             script_abis: true,
             script_builder: true,
             errmap: true,
+            manifest: true,
+            checked_modules: false,
             time_it: false,
         }
     }
@@ -322,6 +423,7 @@ where
 ///   - Script ABIs
 ///   - Script Builder
 ///   - Error Descriptions
+///   - Manifest, versioning the release and hashing the artifacts above
 pub fn create_release(output_path: impl AsRef<Path>, options: &ReleaseOptions) {
     let output_path = output_path.as_ref();
 
@@ -390,6 +492,18 @@ pub fn create_release(output_path: impl AsRef<Path>, options: &ReleaseOptions) {
             build_error_code_map(&err_exp_path)
         });
     }
+
+    if options.manifest {
+        run_step(msg("Generating release manifest"), || {
+            generate_manifest(output_path)
+        });
+    }
+
+    if options.checked_modules {
+        run_step(msg("Building modules with runtime checks"), || {
+            build_checked_modules(output_path.join("checked_modules"))
+        });
+    }
 }
 
 /// Sync generated documentation from the current release to the previous locations of script and
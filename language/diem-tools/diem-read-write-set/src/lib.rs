@@ -4,9 +4,12 @@
 use anyhow::{bail, Result};
 use diem_types::{
     account_config,
+    block_metadata::BlockMetadata,
     transaction::{SignedTransaction, TransactionPayload},
 };
-use diem_vm::system_module_names::{SCRIPT_PROLOGUE_NAME, USER_EPILOGUE_NAME};
+use diem_vm::system_module_names::{
+    BLOCK_PROLOGUE, DIEM_BLOCK_MODULE, SCRIPT_PROLOGUE_NAME, USER_EPILOGUE_NAME,
+};
 use move_core_types::{
     ident_str,
     identifier::IdentStr,
@@ -16,6 +19,10 @@ use move_core_types::{
 };
 use std::ops::Deref;
 
+mod parallel_execution;
+
+pub use parallel_execution::{execute_block, BlockReadWriteSets};
+
 pub struct ReadWriteSetAnalysis(read_write_set::ReadWriteSetAnalysis);
 
 const TRANSACTION_FEES_NAME: &IdentStr = ident_str!("TransactionFee");
@@ -128,6 +135,31 @@ impl ReadWriteSetAnalysis {
             }
         }
     }
+
+    /// Returns an overapproximation of the `ResourceKey`'s in global storage accessed by the block
+    /// prologue run for `block_metadata` in state `blockchain_view`.
+    fn get_concretized_keys_block_metadata(
+        &self,
+        block_metadata: &BlockMetadata,
+        blockchain_view: &impl MoveResolver,
+        is_write: bool,
+    ) -> Result<Vec<ResourceKey>> {
+        let (round, timestamp, previous_votes, proposer) = block_metadata.clone().into_inner();
+        self.get_concretized_keys(
+            &DIEM_BLOCK_MODULE,
+            BLOCK_PROLOGUE,
+            &[account_config::reserved_vm_address()],
+            &serialize_values(&vec![
+                MoveValue::U64(round),
+                MoveValue::U64(timestamp),
+                MoveValue::Vector(previous_votes.into_iter().map(MoveValue::Address).collect()),
+                MoveValue::Address(proposer),
+            ]),
+            &[],
+            blockchain_view,
+            is_write,
+        )
+    }
 }
 
 impl Deref for ReadWriteSetAnalysis {
@@ -0,0 +1,135 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::ReadWriteSetAnalysis;
+use anyhow::{format_err, Result};
+use diem_crypto::HashValue;
+use diem_parallel_executor::task::ReadWriteSetInferencer;
+use diem_state_view::StateView;
+use diem_types::{
+    access_path::AccessPath,
+    transaction::{SignedTransaction, Transaction, TransactionOutput},
+    vm_status::VMStatus,
+};
+use diem_vm::{
+    data_cache::RemoteStorage, parallel_executor::ParallelDiemVM, DiemVM, PreprocessedTransaction,
+    VMExecutor,
+};
+use move_core_types::{language_storage::ResourceKey, resolver::MoveResolver};
+use std::collections::HashMap;
+
+/// The keys read and written by a transaction, in that order.
+type ReadWriteSet = (Vec<AccessPath>, Vec<AccessPath>);
+
+/// The read and write sets of the transactions of a block, inferred before executing the block in
+/// parallel since the analysis can't be shared between threads. Transactions whose read and write
+/// sets can't be inferred, e.g. those running a script, make the whole block execute sequentially.
+pub struct BlockReadWriteSets {
+    user_transactions: HashMap<SignedTransaction, ReadWriteSet>,
+    block_metadata: HashMap<HashValue, ReadWriteSet>,
+}
+
+impl BlockReadWriteSets {
+    /// Infers the read and write sets of `transactions` when executed in state `blockchain_view`.
+    pub fn new(
+        analysis: &ReadWriteSetAnalysis,
+        transactions: &[Transaction],
+        blockchain_view: &impl MoveResolver,
+    ) -> Self {
+        let mut user_transactions = HashMap::new();
+        let mut block_metadata = HashMap::new();
+        for txn in transactions {
+            match txn {
+                Transaction::UserTransaction(txn) => {
+                    if let Ok(read_write_set) = infer_read_write_set(|is_write| {
+                        analysis.get_concretized_keys_tx(txn, blockchain_view, is_write)
+                    }) {
+                        user_transactions.insert(txn.clone(), read_write_set);
+                    }
+                }
+                Transaction::BlockMetadata(metadata) => {
+                    if let Ok(read_write_set) = infer_read_write_set(|is_write| {
+                        analysis.get_concretized_keys_block_metadata(
+                            metadata,
+                            blockchain_view,
+                            is_write,
+                        )
+                    }) {
+                        block_metadata.insert(metadata.id(), read_write_set);
+                    }
+                }
+                Transaction::GenesisTransaction(_) => (),
+            }
+        }
+        Self {
+            user_transactions,
+            block_metadata,
+        }
+    }
+
+    fn get_keys(&self, txn: &PreprocessedTransaction, is_write: bool) -> Result<Vec<AccessPath>> {
+        let read_write_set = match txn {
+            PreprocessedTransaction::UserTransaction(txn) => {
+                let txn: &SignedTransaction = txn;
+                self.user_transactions.get(txn)
+            }
+            PreprocessedTransaction::BlockMetadata(metadata) => {
+                self.block_metadata.get(&metadata.id())
+            }
+            // Transactions with an invalid signature are discarded without being executed.
+            PreprocessedTransaction::InvalidSignature => return Ok(vec![]),
+            PreprocessedTransaction::WaypointWriteSet(_) | PreprocessedTransaction::WriteSet(_) => {
+                None
+            }
+        };
+        let (reads, writes) = read_write_set
+            .ok_or_else(|| format_err!("No read/write set inferred for {:?}", txn))?;
+        Ok(if is_write {
+            writes.clone()
+        } else {
+            reads.clone()
+        })
+    }
+}
+
+impl ReadWriteSetInferencer for BlockReadWriteSets {
+    type T = PreprocessedTransaction;
+
+    fn infer_reads(&self, txn: &PreprocessedTransaction) -> Result<Vec<AccessPath>> {
+        self.get_keys(txn, false)
+    }
+
+    fn infer_writes(&self, txn: &PreprocessedTransaction) -> Result<Vec<AccessPath>> {
+        self.get_keys(txn, true)
+    }
+}
+
+fn infer_read_write_set(
+    get_keys: impl Fn(bool) -> Result<Vec<ResourceKey>>,
+) -> Result<ReadWriteSet> {
+    let to_access_paths = |keys: Vec<ResourceKey>| {
+        keys.into_iter()
+            .map(AccessPath::resource_access_path)
+            .collect()
+    };
+    Ok((
+        to_access_paths(get_keys(false)?),
+        to_access_paths(get_keys(true)?),
+    ))
+}
+
+/// Executes a block of `transactions` on `state_view`, in parallel if enabled by the on-chain
+/// consensus config, using `analysis` to infer the read and write sets of the transactions.
+pub fn execute_block<S: StateView>(
+    analysis: &ReadWriteSetAnalysis,
+    transactions: Vec<Transaction>,
+    state_view: &S,
+) -> Result<Vec<TransactionOutput>, VMStatus> {
+    if !ParallelDiemVM::is_enabled(state_view) {
+        return DiemVM::execute_block(transactions, state_view);
+    }
+
+    let read_write_sets =
+        BlockReadWriteSets::new(analysis, &transactions, &RemoteStorage::new(state_view));
+    ParallelDiemVM::execute_block(read_write_sets, transactions, state_view)
+}
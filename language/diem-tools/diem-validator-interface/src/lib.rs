@@ -8,6 +8,7 @@ pub use crate::storage_interface::DBDebuggerInterface;
 pub use json_rpc_interface::JsonRpcDebuggerInterface;
 
 use anyhow::{anyhow, Result};
+use diem_framework_releases::ReleaseManifest;
 use diem_state_view::StateView;
 use diem_types::{
     access_path::AccessPath,
@@ -37,24 +38,40 @@ pub trait DiemValidatorInterface: Sync {
         seq: u64,
     ) -> Result<Option<Version>>;
 
+    fn get_diem_framework_module_blobs_by_version(&self, version: Version) -> Result<Vec<Vec<u8>>> {
+        Ok(self
+            .get_account_state_by_version(account_config::CORE_CODE_ADDRESS, version)?
+            .ok_or_else(|| anyhow!("Failure reading diem root address state"))?
+            .get_modules()
+            .cloned()
+            .collect())
+    }
+
     fn get_diem_framework_modules_by_version(
         &self,
         version: Version,
     ) -> Result<Vec<CompiledModule>> {
         let mut acc = vec![];
-        for module_bytes in self
-            .get_account_state_by_version(account_config::CORE_CODE_ADDRESS, version)?
-            .ok_or_else(|| anyhow!("Failure reading diem root address state"))?
-            .get_modules()
-        {
+        for module_bytes in self.get_diem_framework_module_blobs_by_version(version)? {
             acc.push(
-                CompiledModule::deserialize(module_bytes)
+                CompiledModule::deserialize(&module_bytes)
                     .map_err(|e| anyhow!("Failure deserializing module: {:?}", e))?,
             )
         }
         Ok(acc)
     }
 
+    /// Get the manifest of the Diem Framework release published on-chain at `version`, if the
+    /// published modules match a known release.
+    fn get_diem_framework_release_by_version(
+        &self,
+        version: Version,
+    ) -> Result<Option<ReleaseManifest>> {
+        diem_framework_releases::find_release_for_modules(
+            &self.get_diem_framework_module_blobs_by_version(version)?,
+        )
+    }
+
     /// Get the account states of the most critical accounts, including:
     /// 1. Diem Framework code address
     /// 2. Diem Root address
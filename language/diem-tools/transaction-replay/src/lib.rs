@@ -23,7 +23,9 @@ use diem_vm::{
 };
 use move_binary_format::{errors::VMResult, file_format::CompiledModule};
 use move_cli::sandbox::utils::on_disk_state_view::OnDiskStateView;
-use move_core_types::{effects::ChangeSet as MoveChanges, language_storage::TypeTag};
+use move_core_types::{
+    effects::ChangeSet as MoveChanges, gas_schedule::GasProfile, language_storage::TypeTag,
+};
 use move_lang::{compiled_unit::AnnotatedCompiledUnit, Compiler, Flags};
 use move_vm_runtime::{move_vm::MoveVM, session::Session};
 use move_vm_test_utils::DeltaStorage;
@@ -91,6 +93,36 @@ impl DiemDebugger {
         Ok(ret)
     }
 
+    /// Replays the transactions from `begin` to `begin + limit` and profiles the gas charged to
+    /// each of the user transactions kept on chain.
+    pub fn profile_gas_of_past_transactions(
+        &self,
+        mut begin: Version,
+        mut limit: u64,
+    ) -> Result<Vec<(Version, GasProfile)>> {
+        let mut txns = self.debugger.get_committed_transactions(begin, limit)?;
+        let mut ret = vec![];
+        while limit != 0 {
+            let state_view = DebuggerStateView::new(&*self.debugger, begin);
+            let outputs = DiemVM::execute_block_with_gas_profiling(txns.clone(), &state_view)
+                .map_err(|err| format_err!("Unexpected VM Error: {:?}", err))?;
+            // Transactions after a reconfiguration are executed in the next epoch.
+            let epoch_len = outputs
+                .iter()
+                .position(is_reconfiguration)
+                .map_or(outputs.len(), |idx| idx + 1);
+            for (version, output) in (begin..).zip(outputs.iter().take(epoch_len)) {
+                if let Some(profile) = output.gas_profile() {
+                    ret.push((version, profile.clone()));
+                }
+            }
+            begin += epoch_len as u64;
+            limit -= epoch_len as u64;
+            txns = txns.split_off(epoch_len);
+        }
+        Ok(ret)
+    }
+
     pub fn execute_transactions_by_epoch(
         &self,
         begin: Version,
@@ -72,6 +72,9 @@ enum Command {
         base_version: Version,
         revision: Version,
     },
+    /// Profile the gas charged to the user transactions from version `start` to `start + limit`.
+    #[structopt(name = "profile-gas")]
+    ProfileGas { start: Version, limit: u64 },
     /// Get the bytecode for all Diem Framework modules at `version`
     #[structopt(name = "get-modules")]
     GetModules { version: Version },
@@ -201,6 +204,11 @@ fn main() -> Result<()> {
                 Changeset::new(&base_annotation, &revision_annotation, "\n")
             );
         }
+        Command::ProfileGas { start, limit } => {
+            for (version, profile) in debugger.profile_gas_of_past_transactions(start, limit)? {
+                println!("Version {}:\n{}", version, profile);
+            }
+        }
         Command::GetModules { version } => {
             let modules =
                 debugger.get_diem_framework_modules_at_version(version, opt.save_write_sets)?;
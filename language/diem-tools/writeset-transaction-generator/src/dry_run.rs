@@ -0,0 +1,272 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Dry-runs of admin write sets against a fork of a DB, re-checking the global invariants of the
+//! Diem Framework modules they affect before the write sets get released.
+
+use anyhow::{bail, format_err, Result};
+use diem_framework::{diem_framework_named_addresses, diem_stdlib_files};
+use diem_framework_releases::current_modules;
+use diem_transaction_replay::DiemDebugger;
+use diem_types::{
+    access_path::Path as AccessPathKind,
+    account_config::CORE_CODE_ADDRESS,
+    transaction::{TransactionOutput, TransactionStatus, Version, WriteSetPayload},
+    write_set::{WriteOp, WriteSet},
+};
+use diem_validator_interface::{DBDebuggerInterface, DiemValidatorInterface};
+use move_binary_format::CompiledModule;
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag},
+    vm_status::KeptVMStatus,
+};
+use move_model::model::VerificationScope;
+use move_prover::cli::{named_addresses_for_options, Options};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// Changes made by a write set to the modules and resources.
+#[derive(Debug, Default)]
+pub struct WriteSetChanges {
+    /// Modules published by the write set.
+    pub published_modules: BTreeMap<ModuleId, Vec<u8>>,
+    /// Modules deleted by the write set.
+    pub deleted_modules: BTreeSet<ModuleId>,
+    /// Resources written or deleted by the write set.
+    pub affected_resources: BTreeSet<StructTag>,
+}
+
+impl WriteSetChanges {
+    pub fn new(write_set: &WriteSet) -> Self {
+        let mut changes = Self::default();
+        for (access_path, write_op) in write_set {
+            match (access_path.get_path(), write_op) {
+                (AccessPathKind::Code(module_id), WriteOp::Value(bytes)) => {
+                    changes.published_modules.insert(module_id, bytes.clone());
+                }
+                (AccessPathKind::Code(module_id), WriteOp::Deletion) => {
+                    changes.deleted_modules.insert(module_id);
+                }
+                (AccessPathKind::Resource(struct_tag), _) => {
+                    changes.affected_resources.insert(struct_tag);
+                }
+            }
+        }
+        changes
+    }
+
+    /// Names of the Diem Framework modules whose global invariants may no longer hold after the
+    /// write set: the modules it publishes, and the modules declaring the resources it changes.
+    pub fn affected_framework_modules(&self) -> BTreeSet<Identifier> {
+        self.published_modules
+            .keys()
+            .map(|id| (id.address(), id.name()))
+            .chain(
+                self.affected_resources
+                    .iter()
+                    .map(|tag| (&tag.address, tag.module.as_ident_str())),
+            )
+            .filter(|(address, _)| **address == CORE_CODE_ADDRESS)
+            .map(|(_, name)| name.to_owned())
+            .collect()
+    }
+}
+
+/// Outcome of a write set applied on top of the state of a DB.
+pub struct DryRunOutput {
+    /// Version of the state the write set was applied to.
+    pub version: Version,
+    pub output: TransactionOutput,
+    pub changes: WriteSetChanges,
+}
+
+/// Forks the DB under `db_root_path` into `fork_root_path`, which must not exist yet, so that
+/// the DB is left untouched by the dry-runs. The DB must not be written while being forked, e.g.
+/// it should be a backup restored from the network rather than the DB of a running node.
+///
+/// As with the checkpoints of the DB, the immutable table files are hard-linked when possible.
+pub fn fork_db(db_root_path: &Path, fork_root_path: &Path) -> Result<DBDebuggerInterface> {
+    if fork_root_path.exists() {
+        bail!("Fork path {:?} already exists", fork_root_path);
+    }
+    copy_dir(db_root_path, fork_root_path)?;
+    DBDebuggerInterface::open(fork_root_path)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let (from, to) = (entry.path(), to.join(entry.file_name()));
+        if entry.file_type()?.is_dir() {
+            copy_dir(&from, &to)?;
+        } else if from.extension().map_or(false, |ext| ext == "sst") {
+            if fs::hard_link(&from, &to).is_err() {
+                fs::copy(&from, &to)?;
+            }
+        } else {
+            fs::copy(&from, &to)?;
+        }
+    }
+    Ok(())
+}
+
+/// Applies `payload` on top of the state of `validator` at `version`, or at the latest version
+/// if not set. Fails unless the write set is kept and triggers a reconfiguration.
+pub fn dry_run_writeset(
+    validator: Box<dyn DiemValidatorInterface>,
+    version: Option<Version>,
+    payload: &WriteSetPayload,
+) -> Result<DryRunOutput> {
+    let version = match version {
+        Some(version) => version,
+        None => validator.get_latest_version()?,
+    };
+    let output =
+        DiemDebugger::new(validator).execute_writeset_at_version(version, payload, false)?;
+
+    if output.status() != &TransactionStatus::Keep(KeptVMStatus::Executed) {
+        bail!(
+            "Unexpected transaction status from running WriteSetPayload: {:?}",
+            output.status()
+        )
+    }
+    let new_epoch_event_key = diem_types::on_chain_config::new_epoch_event_key();
+    if !output
+        .events()
+        .iter()
+        .any(|e| *e.key() == new_epoch_event_key)
+    {
+        bail!("Output WriteSet won't trigger a reconfiguration")
+    }
+
+    Ok(DryRunOutput {
+        version,
+        changes: WriteSetChanges::new(output.write_set()),
+        output,
+    })
+}
+
+/// Runs the Move Prover on the local Diem Framework sources, to verify that the global
+/// invariants hold in each of the modules affected by the dry-run.
+///
+/// The invariants are verified against the local sources, so the modules published by the write
+/// set are expected to be built from them, as in the `current` release. Modules built from other
+/// sources are reported.
+pub fn check_invariants(changes: &WriteSetChanges) -> Result<()> {
+    let local_modules = current_modules()
+        .iter()
+        .map(|module| (module.self_id(), module))
+        .collect::<BTreeMap<_, _>>();
+    for (module_id, bytes) in &changes.published_modules {
+        let module = CompiledModule::deserialize(bytes)
+            .map_err(|e| format_err!("Failure deserializing module {}: {:?}", module_id, e))?;
+        if local_modules.get(module_id).copied() != Some(&module) {
+            println!(
+                "Warning: module {} is not built from the local Diem Framework sources",
+                module_id
+            );
+        }
+    }
+
+    let output_dir = tempfile::tempdir()?;
+    for module_name in changes.affected_framework_modules() {
+        println!("Checking global invariants of module {}", module_name);
+        let mut options = Options {
+            move_sources: diem_stdlib_files(),
+            move_named_address_values: named_addresses_for_options(
+                &diem_framework_named_addresses(),
+            ),
+            output_path: output_dir
+                .path()
+                .join("output.bpl")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+        options.prover.verify_scope = VerificationScope::OnlyModule(module_name.to_string());
+        move_prover::run_move_prover_errors_to_stderr(options).map_err(|err| {
+            format_err!(
+                "Global invariants of module {} do not verify: {}",
+                module_name,
+                err
+            )
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_types::{access_path::AccessPath, account_config, write_set::WriteSetMut};
+    use move_core_types::{account_address::AccountAddress, language_storage::ResourceKey};
+
+    #[test]
+    fn affected_framework_modules() {
+        let module_id = |address, name| ModuleId::new(address, Identifier::new(name).unwrap());
+        let resource_path = |address, module| {
+            let struct_tag = StructTag {
+                address,
+                module: Identifier::new(module).unwrap(),
+                name: Identifier::new("T").unwrap(),
+                type_params: vec![],
+            };
+            AccessPath::resource_access_path(ResourceKey::new(
+                account_config::diem_root_address(),
+                struct_tag,
+            ))
+        };
+        let user_address = AccountAddress::random();
+        let write_set = WriteSetMut::new(vec![
+            (
+                AccessPath::code_access_path(module_id(CORE_CODE_ADDRESS, "Published")),
+                WriteOp::Value(vec![]),
+            ),
+            (
+                AccessPath::code_access_path(module_id(CORE_CODE_ADDRESS, "Deleted")),
+                WriteOp::Deletion,
+            ),
+            (
+                AccessPath::code_access_path(module_id(user_address, "UserModule")),
+                WriteOp::Value(vec![]),
+            ),
+            (
+                resource_path(CORE_CODE_ADDRESS, "Written"),
+                WriteOp::Value(vec![]),
+            ),
+            (
+                resource_path(CORE_CODE_ADDRESS, "Removed"),
+                WriteOp::Deletion,
+            ),
+            (
+                resource_path(user_address, "UserResource"),
+                WriteOp::Value(vec![]),
+            ),
+        ])
+        .freeze()
+        .unwrap();
+
+        let changes = WriteSetChanges::new(&write_set);
+        assert_eq!(changes.published_modules.len(), 2);
+        assert_eq!(
+            changes.deleted_modules,
+            vec![module_id(CORE_CODE_ADDRESS, "Deleted")]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(changes.affected_resources.len(), 3);
+        // Only the modules of the Diem Framework are checked
+        assert_eq!(
+            changes.affected_framework_modules(),
+            vec!["Published", "Removed", "Written"]
+                .into_iter()
+                .map(|name| Identifier::new(name).unwrap())
+                .collect()
+        );
+    }
+}
@@ -5,16 +5,19 @@ use anyhow::{bail, format_err, Result};
 use diem_types::{
     account_address::AccountAddress,
     chain_id::ChainId,
-    transaction::{Transaction, TransactionPayload},
+    transaction::{Transaction, TransactionPayload, WriteSetPayload},
 };
+use diem_validator_interface::{DiemValidatorInterface, JsonRpcDebuggerInterface};
 
 use diem_writeset_generator::{
-    create_release, encode_custom_script, encode_halt_network_payload,
-    encode_remove_validators_payload, release_flow::artifacts::load_latest_artifact,
+    create_release,
+    dry_run::{check_invariants, dry_run_writeset, fork_db},
+    encode_custom_script, encode_halt_network_payload, encode_remove_validators_payload,
+    release_flow::artifacts::load_latest_artifact,
     verify_release,
 };
 use move_binary_format::CompiledModule;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 
 const GENESIS_MODULE_NAME: &str = "Genesis";
@@ -77,6 +80,35 @@ enum Command {
         #[structopt(long)]
         use_latest_version: bool,
     },
+    /// Apply a WriteSet to a fork of a DB, and check the global invariants of the affected Diem
+    /// Framework modules with the Move Prover.
+    #[structopt(name = "dry-run")]
+    DryRunWriteSet {
+        /// Path to the DB to fork, e.g. a backup restored from the network. It must not be in use.
+        #[structopt(parse(from_os_str))]
+        db: PathBuf,
+        /// Path to the serialized bytes of WriteSet.
+        #[structopt(parse(from_os_str))]
+        writeset_path: PathBuf,
+        /// Path where the DB is forked. A temporary directory is used when not set.
+        #[structopt(long, parse(from_os_str))]
+        fork_path: Option<PathBuf>,
+        /// Blockchain height. The latest height of the DB is used when not set.
+        #[structopt(long)]
+        version: Option<u64>,
+        /// Skip checking the global invariants with the Move Prover.
+        #[structopt(long)]
+        skip_invariants: bool,
+    },
+    /// Report which Diem Framework release is published on a remote blockchain.
+    #[structopt(name = "show-release")]
+    ShowDiemFrameworkRelease {
+        /// Public JSON-rpc endpoint URL.
+        url: String,
+        /// Blockchain height. The latest height is used when not set.
+        #[structopt(long)]
+        version: Option<u64>,
+    },
 }
 
 fn save_bytes(bytes: Vec<u8>, path: PathBuf) -> Result<()> {
@@ -84,6 +116,23 @@ fn save_bytes(bytes: Vec<u8>, path: PathBuf) -> Result<()> {
         .map_err(|err| format_err!("Unable to write to path: {:?}", err))
 }
 
+fn read_writeset_payload(path: &Path) -> Result<WriteSetPayload> {
+    let raw_bytes =
+        std::fs::read(path).map_err(|err| format_err!("Unable to read from path: {:?}", err))?;
+    if let Ok(txn_payload) = bcs::from_bytes::<TransactionPayload>(raw_bytes.as_slice()) {
+        match txn_payload {
+            TransactionPayload::WriteSet(payload) => Ok(payload),
+            _ => bail!("Unexpected transacton type"),
+        }
+    } else {
+        let txn: Transaction = bcs::from_bytes(raw_bytes.as_slice())?;
+        match txn {
+            Transaction::GenesisTransaction(ws) => Ok(ws),
+            _ => bail!("Unexpected transacton type"),
+        }
+    }
+}
+
 fn diem_framework_modules(release_name: &str) -> Vec<(Vec<u8>, CompiledModule)> {
     // Need to filter out Genesis module similiar to what is done in vmgenesis to make sure Genesis
     // module isn't published on-chain.
@@ -154,22 +203,7 @@ fn main() -> Result<()> {
             use_latest_version,
         } => {
             let release_name = load_latest_artifact(&chain_id)?.release_name;
-            let writeset_payload = {
-                let raw_bytes = std::fs::read(writeset_path.as_path()).unwrap();
-                if let Ok(txn_payload) = bcs::from_bytes::<TransactionPayload>(raw_bytes.as_slice())
-                {
-                    match txn_payload {
-                        TransactionPayload::WriteSet(payload) => payload,
-                        _ => bail!("Unexpected transacton type"),
-                    }
-                } else {
-                    let txn: Transaction = bcs::from_bytes(raw_bytes.as_slice())?;
-                    match txn {
-                        Transaction::GenesisTransaction(ws) => ws,
-                        _ => bail!("Unexpected transacton type"),
-                    }
-                }
-            };
+            let writeset_payload = read_writeset_payload(&writeset_path)?;
             let release_modules = diem_framework_modules(release_name.as_str());
             verify_release(
                 chain_id,
@@ -180,6 +214,63 @@ fn main() -> Result<()> {
             )?;
             return Ok(());
         }
+        Command::DryRunWriteSet {
+            db,
+            writeset_path,
+            fork_path,
+            version,
+            skip_invariants,
+        } => {
+            let writeset_payload = read_writeset_payload(&writeset_path)?;
+            let temp_dir = tempfile::tempdir()?;
+            let fork_path = fork_path.unwrap_or_else(|| temp_dir.path().join("db"));
+            println!("Forking DB into {:?}", fork_path);
+            let fork = fork_db(&db, &fork_path)?;
+
+            let dry_run = dry_run_writeset(Box::new(fork), version, &writeset_payload)?;
+            println!("WriteSet applied at version {}", dry_run.version);
+            for module_id in dry_run.changes.published_modules.keys() {
+                println!("Publishing module: {}", module_id);
+            }
+            for module_id in &dry_run.changes.deleted_modules {
+                println!("Deleting module: {}", module_id);
+            }
+            for struct_tag in &dry_run.changes.affected_resources {
+                println!("Changing resource: {}", struct_tag);
+            }
+
+            if !skip_invariants {
+                check_invariants(&dry_run.changes)?;
+            }
+            return Ok(());
+        }
+        Command::ShowDiemFrameworkRelease { url, version } => {
+            let remote = JsonRpcDebuggerInterface::new(url.as_str())?;
+            let version = match version {
+                Some(version) => version,
+                None => remote.get_latest_version()?,
+            };
+            match remote.get_diem_framework_release_by_version(version)? {
+                Some(manifest) => println!(
+                    "Diem Framework release at version {}: {}",
+                    version, manifest.name
+                ),
+                None => {
+                    let current = diem_framework_releases::load_manifest_from_release("current")?;
+                    let drift = current.module_drift(
+                        remote
+                            .get_diem_framework_module_blobs_by_version(version)?
+                            .iter()
+                            .map(Vec::as_slice),
+                    )?;
+                    println!(
+                        "Diem Framework at version {} matches no known release, drift from current: {}",
+                        version, drift
+                    );
+                }
+            }
+            return Ok(());
+        }
     };
     let output_path = if let Some(p) = opt.output {
         p
@@ -210,6 +210,9 @@ pub(crate) fn execute_block_impl<A: VMAdapter>(
         });
     });
 
+    // Reads so far were made to set up the block rather than by one of its transactions.
+    data_cache.take_read_set();
+
     for (idx, txn) in signature_verified_block.into_iter().enumerate() {
         let log_context = AdapterLogSchema::new(data_cache.id(), idx);
         if should_restart {
@@ -219,8 +222,11 @@ pub(crate) fn execute_block_impl<A: VMAdapter>(
             debug!(log_context, "Retry after reconfiguration");
             continue;
         };
-        let (vm_status, output, sender) =
+        let (vm_status, mut output, sender) =
             adapter.execute_single_transaction(&txn, data_cache, &log_context)?;
+        if let Some(read_set) = data_cache.take_read_set() {
+            output = output.with_read_set(read_set);
+        }
         if !output.status().is_discarded() {
             data_cache.push_write_set(output.write_set());
         } else {
@@ -254,7 +260,7 @@ pub(crate) fn execute_block_impl<A: VMAdapter>(
 /// Transactions after signature checking:
 /// Waypoints and BlockPrologues are not signed and are unaffected by signature checking,
 /// but a user transaction or writeset transaction is transformed to a SignatureCheckedTransaction.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum PreprocessedTransaction {
     UserTransaction(Box<SignatureCheckedTransaction>),
     WaypointWriteSet(WriteSetPayload),
@@ -46,6 +46,16 @@ pub static BLOCK_TRANSACTION_COUNT: Lazy<Histogram> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count the number of blocks that were executed sequentially after failing to execute in
+/// parallel.
+pub static PARALLEL_EXECUTION_FALLBACKS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_vm_parallel_execution_fallbacks",
+        "Number of blocks executed sequentially after failing to execute in parallel"
+    )
+    .unwrap()
+});
+
 pub static TXN_TOTAL_SECONDS: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         "diem_vm_txn_total_seconds",
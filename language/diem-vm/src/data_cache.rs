@@ -5,6 +5,7 @@
 use crate::{counters::CRITICAL_ERRORS, create_access_path, logging::AdapterLogSchema};
 #[allow(unused_imports)]
 use anyhow::format_err;
+use diem_infallible::Mutex;
 use diem_logger::prelude::*;
 use diem_state_view::{StateView, StateViewId};
 use diem_types::{
@@ -20,7 +21,7 @@ use move_core_types::{
     language_storage::{ModuleId, StructTag},
     resolver::{ModuleResolver, ResourceResolver},
 };
-use std::collections::btree_map::BTreeMap;
+use std::collections::{btree_map::BTreeMap, BTreeSet};
 
 /// A local cache for a given a `StateView`. The cache is private to the Diem layer
 /// but can be used as a one shot cache for systems that need a simple `RemoteCache`
@@ -38,6 +39,8 @@ use std::collections::btree_map::BTreeMap;
 pub struct StateViewCache<'a> {
     data_view: &'a dyn StateView,
     data_map: BTreeMap<AccessPath, Option<Vec<u8>>>,
+    // The keys read since the read set was last taken, if reads are recorded.
+    read_set: Option<Mutex<BTreeSet<AccessPath>>>,
 }
 
 impl<'a> StateViewCache<'a> {
@@ -47,9 +50,27 @@ impl<'a> StateViewCache<'a> {
         StateViewCache {
             data_view,
             data_map: BTreeMap::new(),
+            read_set: None,
         }
     }
 
+    /// Create a `StateViewCache` which also records the keys read through it, including the ones
+    /// served from the local cache.
+    pub fn new_recording_reads(data_view: &'a dyn StateView) -> Self {
+        StateViewCache {
+            read_set: Some(Mutex::new(BTreeSet::new())),
+            ..Self::new(data_view)
+        }
+    }
+
+    // Returns the keys read since the last call, if reads are recorded. Called at the end of
+    // each transaction to get the reads of that transaction.
+    pub(crate) fn take_read_set(&self) -> Option<Vec<AccessPath>> {
+        self.read_set
+            .as_ref()
+            .map(|read_set| std::mem::take(&mut *read_set.lock()).into_iter().collect())
+    }
+
     // Publishes a `WriteSet` computed at the end of a transaction.
     // The effect is to build a layer in front of the `StateView` which keeps
     // track of the data as if the changes were applied immediately.
@@ -75,6 +96,9 @@ impl<'block> StateView for StateViewCache<'block> {
             "Injected failure in data_cache::get"
         )));
 
+        if let Some(read_set) = &self.read_set {
+            read_set.lock().insert(access_path.clone());
+        }
         match self.data_map.get(access_path) {
             Some(opt_data) => Ok(opt_data.clone()),
             None => match self.data_view.get(access_path) {
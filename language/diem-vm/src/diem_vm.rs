@@ -8,7 +8,7 @@ use crate::{
         validate_signed_transaction, PreprocessedTransaction, VMAdapter,
     },
     counters::*,
-    data_cache::StateViewCache,
+    data_cache::{RemoteStorage, StateViewCache},
     diem_vm_impl::{
         charge_global_write_gas_usage, convert_changeset_and_events, get_currency_info,
         get_gas_currency_code, get_transaction_output, DiemVMImpl, DiemVMInternals,
@@ -59,6 +59,14 @@ impl DiemVM {
         Self(DiemVMImpl::new(state))
     }
 
+    /// Creates a VM which profiles the gas charged to user transactions, and attaches the profile
+    /// to their outputs. This is meant for debugging.
+    pub fn new_with_gas_profiling<S: StateView>(state: &S) -> Self {
+        let mut vm = Self::new(state);
+        vm.0.enable_gas_profiling();
+        vm
+    }
+
     pub fn new_for_validation<S: StateView>(state: &S) -> Self {
         info!(
             AdapterLogSchema::new(state.id(), 0),
@@ -348,6 +356,9 @@ impl DiemVM {
         let gas_schedule = unwrap_or_discard!(self.0.get_gas_schedule(log_context));
         let txn_data = TransactionMetadata::new(txn);
         let mut gas_status = GasStatus::new(gas_schedule, txn_data.max_gas_amount());
+        if self.0.gas_profiling() {
+            gas_status.enable_profiling();
+        }
 
         let result = match txn.payload() {
             payload @ TransactionPayload::Script(_)
@@ -379,7 +390,7 @@ impl DiemVM {
             .get();
         TXN_GAS_USAGE.observe(gas_usage as f64);
 
-        match result {
+        let (vm_status, output) = match result {
             Ok(output) => output,
             Err(err) => {
                 let txn_status = TransactionStatus::from(err.clone());
@@ -396,6 +407,13 @@ impl DiemVM {
                     )
                 }
             }
+        };
+        match gas_status.profile() {
+            // No gas is charged to discarded transactions.
+            Some(profile) if !output.status().is_discarded() => {
+                (vm_status, output.with_gas_profile(profile.clone()))
+            }
+            _ => (vm_status, output),
         }
     }
 
@@ -698,6 +716,71 @@ impl DiemVM {
         BLOCK_TRANSACTION_COUNT.observe(count as f64);
         Ok(res)
     }
+
+    /// Checks whether `sender` could publish the `modules` bundle on `state_view`, without
+    /// executing a transaction: the sender must be allowed to publish modules, and the modules
+    /// must deserialize, pass the bytecode verifier, link against the modules on chain and be
+    /// compatible with the versions they replace. Returns the error publishing the bundle would
+    /// fail with, if any.
+    pub fn simulate_module_publish<S: StateView>(
+        &self,
+        modules: Vec<Vec<u8>>,
+        sender: AccountAddress,
+        state_view: &S,
+    ) -> Result<(), VMStatus> {
+        let log_context = AdapterLogSchema::new(state_view.id(), 0);
+        // Same rules as the module prologue and `execute_module`.
+        let module_address = if self.0.publishing_option(&log_context)?.is_open_module() {
+            sender
+        } else if sender == account_config::diem_root_address() {
+            account_config::CORE_CODE_ADDRESS
+        } else {
+            return Err(VMStatus::Error(StatusCode::INVALID_MODULE_PUBLISHER));
+        };
+
+        let storage = RemoteStorage::new(state_view);
+        let mut session = self.0.new_session(&storage);
+        session
+            .publish_module_bundle(modules, module_address, &mut GasStatus::new_unmetered())
+            .map_err(|e| e.into_vm_status())
+    }
+
+    /// Executes `txn` on `state_view` to preview its output, which must not be committed. Only the
+    /// signature of the sender is checked: the secondary signers of a multi-agent transaction may
+    /// sign with placeholder authenticators, as they are authenticated with the authentication
+    /// keys stored on chain. This lets clients simulate multi-agent transactions before gathering
+    /// all their signatures.
+    pub fn simulate_signed_transaction(
+        txn: SignedTransaction,
+        state_view: &dyn StateView,
+    ) -> TransactionOutput {
+        let txn = match txn.check_sender_signature() {
+            Ok(txn) => txn,
+            Err(_) => return discard_error_output(StatusCode::INVALID_SIGNATURE),
+        };
+        let state_view_cache = StateViewCache::new(state_view);
+        let mut vm = DiemVM::new(&state_view_cache);
+        vm.0.enable_simulation();
+        let log_context = AdapterLogSchema::new(state_view.id(), 0);
+        let (_vm_status, output) =
+            vm.execute_user_transaction(&state_view_cache, &txn, &log_context);
+        output
+    }
+
+    /// Alternate form of 'execute_block' that profiles the gas charged to user transactions, and
+    /// attaches the profile to their `TransactionOutput`.
+    pub fn execute_block_with_gas_profiling(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let mut state_view_cache = StateViewCache::new(state_view);
+        let vm = DiemVM::new_with_gas_profiling(&state_view_cache);
+        let res = adapter_common::execute_block_impl(&vm, transactions, &mut state_view_cache)?;
+        Ok(res
+            .into_iter()
+            .map(|(_vm_status, txn_output)| txn_output)
+            .collect())
+    }
 }
 
 // Executor external API
@@ -723,6 +806,22 @@ impl VMExecutor for DiemVM {
             .map(|(_vm_status, txn_output)| txn_output)
             .collect())
     }
+
+    fn execute_block_and_record_reads(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        let mut state_view_cache = StateViewCache::new_recording_reads(state_view);
+        let count = transactions.len();
+        let vm = DiemVM::new(&state_view_cache);
+        let res = adapter_common::execute_block_impl(&vm, transactions, &mut state_view_cache)?;
+        // Record the histogram count for transactions per block.
+        BLOCK_TRANSACTION_COUNT.observe(count as f64);
+        Ok(res
+            .into_iter()
+            .map(|(_vm_status, txn_output)| txn_output)
+            .collect())
+    }
 }
 
 // VMValidator external API
@@ -48,6 +48,8 @@ pub struct DiemVMImpl {
     on_chain_config: Option<VMConfig>,
     version: Option<DiemVersion>,
     publishing_option: Option<VMPublishingOption>,
+    gas_profiling: bool,
+    simulation: bool,
 }
 
 impl DiemVMImpl {
@@ -60,6 +62,8 @@ impl DiemVMImpl {
             on_chain_config: None,
             version: None,
             publishing_option: None,
+            gas_profiling: false,
+            simulation: false,
         };
         vm.load_configs_impl(&RemoteStorage::new(state));
         vm
@@ -77,9 +81,27 @@ impl DiemVMImpl {
             on_chain_config: Some(on_chain_config),
             version: Some(version),
             publishing_option: Some(publishing_option),
+            gas_profiling: false,
+            simulation: false,
         }
     }
 
+    /// Whether the gas charged to user transactions is profiled and attached to their outputs.
+    pub(crate) fn gas_profiling(&self) -> bool {
+        self.gas_profiling
+    }
+
+    pub(crate) fn enable_gas_profiling(&mut self) {
+        self.gas_profiling = true;
+    }
+
+    /// Makes the prologue authenticate the secondary signers of multi-agent transactions with the
+    /// authentication keys stored on chain rather than the keys they signed with, so that
+    /// transactions can be simulated before their secondary signers sign them.
+    pub(crate) fn enable_simulation(&mut self) {
+        self.simulation = true;
+    }
+
     /// Provides access to some internal APIs of the Diem VM.
     pub fn internals(&self) -> DiemVMInternals {
         DiemVMInternals(self)
@@ -228,8 +250,18 @@ impl DiemVMImpl {
         let secondary_public_key_hashes: Vec<MoveValue> = txn_data
             .secondary_authentication_key_preimages
             .iter()
-            .map(|preimage| {
-                MoveValue::vector_u8(HashValue::sha3_256_of(&preimage.to_vec()).to_vec())
+            .enumerate()
+            .map(|(i, preimage)| {
+                let on_chain_key = match txn_data.secondary_signers.get(i) {
+                    Some(address) if self.simulation => {
+                        on_chain_authentication_key(session, *address)
+                    }
+                    _ => None,
+                };
+                MoveValue::vector_u8(
+                    on_chain_key
+                        .unwrap_or_else(|| HashValue::sha3_256_of(&preimage.to_vec()).to_vec()),
+                )
             })
             .collect();
         let args = if self.get_diem_version()? >= DIEM_VERSION_3 && txn_data.is_multi_agent() {
@@ -565,7 +597,7 @@ pub(crate) fn charge_global_write_gas_usage<R: MoveResolver>(
             .mul(gas_status.cost_table().gas_constants.default_account_size)
             .get();
     gas_status
-        .deduct_gas(InternalGasUnits::new(total_cost))
+        .charge_storage("write_set", InternalGasUnits::new(total_cost))
         .map_err(|p_err| p_err.finish(Location::Undefined).into_vm_status())
 }
 
@@ -597,6 +629,23 @@ pub(crate) fn get_gas_currency_code(txn: &SignedTransaction) -> Result<Identifie
     }
 }
 
+/// The authentication key stored on chain for the account at `address`, if there is one.
+fn on_chain_authentication_key<S: MoveResolver>(
+    session: &mut Session<S>,
+    address: AccountAddress,
+) -> Option<Vec<u8>> {
+    let return_vals = session
+        .execute_function(
+            &account_config::ACCOUNT_MODULE,
+            AUTHENTICATION_KEY_NAME,
+            vec![],
+            serialize_values(&vec![MoveValue::Address(address)]),
+            &mut GasStatus::new_unmetered(),
+        )
+        .ok()?;
+    bcs::from_bytes(return_vals.first()?).ok()
+}
+
 pub(crate) fn get_currency_info<S: MoveResolver>(
     currency_code: &IdentStr,
     remote_cache: &S,
@@ -120,13 +120,17 @@ pub mod transaction_metadata;
 // pub mod diem_transaction_validator;
 pub mod diem_vm;
 pub mod logging;
+pub mod parallel_executor;
 pub mod script_to_script_function;
 pub mod system_module_names;
 
 #[cfg(test)]
 mod unit_tests;
 
-pub use crate::{diem_vm::DiemVM, diem_vm_impl::convert_changeset_and_events};
+pub use crate::{
+    adapter_common::PreprocessedTransaction, diem_vm::DiemVM,
+    diem_vm_impl::convert_changeset_and_events,
+};
 
 use diem_state_view::StateView;
 use diem_types::{
@@ -161,6 +165,16 @@ pub trait VMExecutor: Send + Sync {
         transactions: Vec<Transaction>,
         state_view: &dyn StateView,
     ) -> Result<Vec<TransactionOutput>, VMStatus>;
+
+    /// Executes a block of transactions like `execute_block`, and also records in the output of
+    /// each transaction the keys it read from `state_view`, for debugging. VMs which can't record
+    /// reads return the same outputs as `execute_block`.
+    fn execute_block_and_record_reads(
+        transactions: Vec<Transaction>,
+        state_view: &dyn StateView,
+    ) -> Result<Vec<TransactionOutput>, VMStatus> {
+        Self::execute_block(transactions, state_view)
+    }
 }
 
 /// Get the AccessPath to a resource stored under `address` with type name `tag`
@@ -0,0 +1,136 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Parallel execution of the transactions of a block.
+//!
+//! Transactions are executed optimistically on several threads, each reading the writes of the
+//! transactions before it in the block through a multi-version view. The read and write sets
+//! estimated by a `ReadWriteSetInferencer` tell which transactions may conflict, and a
+//! transaction reading a value not written yet is re-executed once the writer is done. Outputs are
+//! committed in block order, so they are the same as when executing the block sequentially.
+
+pub(crate) mod storage_wrapper;
+mod vm_wrapper;
+
+use crate::{
+    adapter_common::{preprocess_transaction, PreprocessedTransaction},
+    counters::*,
+    data_cache::RemoteStorage,
+    diem_vm::DiemVM,
+    parallel_executor::vm_wrapper::DiemVMWrapper,
+    VMExecutor,
+};
+use diem_logger::prelude::*;
+use diem_parallel_executor::{
+    errors::Error,
+    executor::ParallelTransactionExecutor,
+    task::{
+        ReadWriteSetInferencer, Transaction as PTransaction,
+        TransactionOutput as PTransactionOutput,
+    },
+};
+use diem_state_view::StateView;
+use diem_types::{
+    access_path::AccessPath,
+    on_chain_config::{OnChainConfig, OnChainConsensusConfig},
+    transaction::{Transaction, TransactionOutput, TransactionStatus},
+    vm_status::VMStatus,
+    write_set::{WriteOp, WriteSet},
+};
+use rayon::prelude::*;
+
+impl PTransaction for PreprocessedTransaction {
+    type Key = AccessPath;
+    type Value = WriteOp;
+}
+
+/// The output of a transaction executed in parallel.
+pub(crate) struct DiemTransactionOutput(TransactionOutput);
+
+impl DiemTransactionOutput {
+    pub fn new(output: TransactionOutput) -> Self {
+        Self(output)
+    }
+
+    pub fn into_inner(self) -> TransactionOutput {
+        self.0
+    }
+}
+
+impl PTransactionOutput for DiemTransactionOutput {
+    type T = PreprocessedTransaction;
+
+    fn get_writes(&self) -> Vec<(AccessPath, WriteOp)> {
+        self.0.write_set().iter().cloned().collect()
+    }
+
+    /// Transactions after a reconfiguration are retried in the next block, as in sequential
+    /// execution.
+    fn skip_output() -> Self {
+        Self(TransactionOutput::new(
+            WriteSet::default(),
+            vec![],
+            0,
+            TransactionStatus::Retry,
+        ))
+    }
+}
+
+pub struct ParallelDiemVM;
+
+impl ParallelDiemVM {
+    /// Whether blocks executed on `state_view` may be executed in parallel, as set by the on-chain
+    /// consensus config.
+    pub fn is_enabled<S: StateView>(state_view: &S) -> bool {
+        OnChainConsensusConfig::fetch_config(&RemoteStorage::new(state_view))
+            .map_or(false, |config| config.parallel_execution())
+    }
+
+    /// Executes a block of `transactions` in parallel, using `inferencer` to estimate their read
+    /// and write sets. Callers should check the block may be executed in parallel with
+    /// `is_enabled` first.
+    ///
+    /// The block is executed sequentially instead when the read and write sets of a transaction
+    /// can't be inferred, or when a transaction writes outside of its inferred write set. The
+    /// outputs are the same as `DiemVM::execute_block` in any case.
+    pub fn execute_block<S, I>(
+        inferencer: I,
+        transactions: Vec<Transaction>,
+        state_view: &S,
+    ) -> Result<Vec<TransactionOutput>, VMStatus>
+    where
+        S: StateView,
+        I: ReadWriteSetInferencer<T = PreprocessedTransaction>,
+    {
+        let count = transactions.len();
+        let signature_verified_block: Vec<PreprocessedTransaction> = transactions
+            .par_iter()
+            .cloned()
+            .map(preprocess_transaction::<DiemVM>)
+            .collect();
+        let executor =
+            ParallelTransactionExecutor::<PreprocessedTransaction, DiemVMWrapper<S>, I>::new(
+                inferencer,
+            );
+
+        match executor.execute_transactions_parallel(state_view, signature_verified_block) {
+            Ok(outputs) => {
+                // Record the histogram count for transactions per block.
+                BLOCK_TRANSACTION_COUNT.observe(count as f64);
+                Ok(outputs
+                    .into_iter()
+                    .map(DiemTransactionOutput::into_inner)
+                    .collect())
+            }
+            Err(Error::UserError(err)) => Err(err),
+            Err(err) => {
+                info!(
+                    "Falling back to sequential execution of the block: {:?}",
+                    err
+                );
+                PARALLEL_EXECUTION_FALLBACKS.inc();
+                DiemVM::execute_block(transactions, state_view)
+            }
+        }
+    }
+}
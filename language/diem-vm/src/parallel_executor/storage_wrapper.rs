@@ -0,0 +1,89 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::data_cache::RemoteStorage;
+use anyhow::{format_err, Result};
+use diem_state_view::{StateView, StateViewId};
+use diem_types::{access_path::AccessPath, write_set::WriteOp};
+use move_binary_format::errors::VMError;
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{ModuleId, StructTag},
+    resolver::{ModuleResolver, ResourceResolver},
+};
+use mvhashmap::{MVHashMapView, Version};
+use once_cell::sync::OnceCell;
+
+/// The state seen by a transaction executed in parallel: the writes of the transactions before it
+/// in the block, on top of the state the block is executed on.
+///
+/// Reading a value that an earlier transaction is expected to write but hasn't written yet fails,
+/// and the earlier transaction is recorded so that the reader can be re-executed after it. This
+/// only happens for reads missing from the inferred read set, as the executor waits for those.
+pub(crate) struct VersionedView<'a, S> {
+    base_view: &'a S,
+    hashmap_view: &'a MVHashMapView<'a, AccessPath, WriteOp>,
+    blocked_by: OnceCell<Version>,
+}
+
+impl<'a, S: StateView> VersionedView<'a, S> {
+    pub fn new(base_view: &'a S, hashmap_view: &'a MVHashMapView<'a, AccessPath, WriteOp>) -> Self {
+        Self {
+            base_view,
+            hashmap_view,
+            blocked_by: OnceCell::new(),
+        }
+    }
+
+    /// The first transaction whose write was read before it was executed, if any.
+    pub fn blocked_by(&self) -> Option<Version> {
+        self.blocked_by.get().copied()
+    }
+}
+
+impl<'a, S: StateView> StateView for VersionedView<'a, S> {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        match self.hashmap_view.read(access_path) {
+            Ok(WriteOp::Value(blob)) => Ok(Some(blob.clone())),
+            Ok(WriteOp::Deletion) => Ok(None),
+            Err(Some(version)) => {
+                // Keep the first dependency, which is the one the transaction stopped at.
+                let _ = self.blocked_by.set(version);
+                Err(format_err!(
+                    "Read of {:?} depends on transaction {} of the block",
+                    access_path,
+                    version,
+                ))
+            }
+            Err(None) => self.base_view.get(access_path),
+        }
+    }
+
+    fn is_genesis(&self) -> bool {
+        self.base_view.is_genesis()
+    }
+
+    fn id(&self) -> StateViewId {
+        self.base_view.id()
+    }
+}
+
+impl<'a, S: StateView> ModuleResolver for VersionedView<'a, S> {
+    type Error = VMError;
+
+    fn get_module(&self, module_id: &ModuleId) -> Result<Option<Vec<u8>>, Self::Error> {
+        RemoteStorage::new(self).get_module(module_id)
+    }
+}
+
+impl<'a, S: StateView> ResourceResolver for VersionedView<'a, S> {
+    type Error = VMError;
+
+    fn get_resource(
+        &self,
+        address: &AccountAddress,
+        tag: &StructTag,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        RemoteStorage::new(self).get_resource(address, tag)
+    }
+}
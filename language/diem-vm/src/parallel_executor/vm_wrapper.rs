@@ -0,0 +1,69 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    adapter_common::{PreprocessedTransaction, VMAdapter},
+    diem_vm::DiemVM,
+    logging::AdapterLogSchema,
+    parallel_executor::{storage_wrapper::VersionedView, DiemTransactionOutput},
+};
+use diem_logger::prelude::*;
+use diem_parallel_executor::task::{ExecutionStatus, ExecutorTask};
+use diem_state_view::StateView;
+use diem_types::{access_path::AccessPath, vm_status::VMStatus, write_set::WriteOp};
+use mvhashmap::MVHashMapView;
+
+/// Executes the transactions of a block on one of the threads of the parallel executor, with its
+/// own instance of the VM.
+pub(crate) struct DiemVMWrapper<'a, S> {
+    vm: DiemVM,
+    base_view: &'a S,
+}
+
+impl<'a, S: 'a + StateView> ExecutorTask for DiemVMWrapper<'a, S> {
+    type T = PreprocessedTransaction;
+    type Output = DiemTransactionOutput;
+    type Error = VMStatus;
+    type Argument = &'a S;
+
+    fn init(base_view: &'a S) -> Self {
+        Self {
+            vm: DiemVM::new(base_view),
+            base_view,
+        }
+    }
+
+    fn execute_transaction(
+        &self,
+        view: MVHashMapView<AccessPath, WriteOp>,
+        txn: &PreprocessedTransaction,
+    ) -> ExecutionStatus<DiemTransactionOutput, VMStatus> {
+        let log_context = AdapterLogSchema::new(self.base_view.id(), view.version());
+        let versioned_view = VersionedView::new(self.base_view, &view);
+
+        let result = self
+            .vm
+            .execute_single_transaction(txn, &versioned_view, &log_context);
+        // Whatever the VM made of it, a read of a value not written yet invalidates the execution.
+        if let Some(version) = versioned_view.blocked_by() {
+            trace!(
+                log_context,
+                "Transaction blocked by transaction {} of the block, retrying",
+                version
+            );
+            return ExecutionStatus::Retry(version);
+        }
+
+        match result {
+            Ok((_vm_status, output, _sender)) => {
+                if DiemVM::should_restart_execution(&output) {
+                    info!(log_context, "Reconfiguration occurred: restart required");
+                    ExecutionStatus::SkipRest(DiemTransactionOutput::new(output))
+                } else {
+                    ExecutionStatus::Success(DiemTransactionOutput::new(output))
+                }
+            }
+            Err(err) => ExecutionStatus::Abort(err),
+        }
+    }
+}
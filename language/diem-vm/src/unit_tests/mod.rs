@@ -1,4 +1,5 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+mod parallel_executor_tests;
 mod script_to_script_function_tests;
@@ -0,0 +1,58 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::parallel_executor::storage_wrapper::VersionedView;
+use anyhow::Result;
+use diem_state_view::StateView;
+use diem_types::{access_path::AccessPath, account_address::AccountAddress, write_set::WriteOp};
+use mvhashmap::MVHashMap;
+use std::collections::HashMap;
+
+struct BaseView(HashMap<AccessPath, Vec<u8>>);
+
+impl StateView for BaseView {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.get(access_path).cloned())
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
+}
+
+fn access_path(path: u8) -> AccessPath {
+    AccessPath::new(AccountAddress::ZERO, vec![path])
+}
+
+#[test]
+fn test_versioned_view() {
+    let base_view = BaseView((0..4).map(|path| (access_path(path), vec![path])).collect());
+    // Transaction 0 writes paths 0 and 1, transaction 1 may write path 2, and transaction 3 path 3.
+    let (map, _) = MVHashMap::new_from(vec![
+        (access_path(0), 0),
+        (access_path(1), 0),
+        (access_path(2), 1),
+        (access_path(3), 3),
+    ]);
+    map.write(&access_path(0), 0, WriteOp::Value(vec![10]))
+        .unwrap();
+    map.write(&access_path(1), 0, WriteOp::Deletion).unwrap();
+
+    let hashmap_view = map.view(2);
+    let view = VersionedView::new(&base_view, &hashmap_view);
+    // Writes of earlier transactions are read, and other values from the base view.
+    assert_eq!(view.get(&access_path(0)).unwrap(), Some(vec![10]));
+    assert_eq!(view.get(&access_path(1)).unwrap(), None);
+    assert_eq!(view.get(&access_path(3)).unwrap(), Some(vec![3]));
+    assert_eq!(view.blocked_by(), None);
+
+    // Values not written yet by earlier transactions block the read.
+    assert!(view.get(&access_path(2)).is_err());
+    assert_eq!(view.blocked_by(), Some(1));
+
+    // Once skipped, the base value is read.
+    map.skip(&access_path(2), 1).unwrap();
+    let view = VersionedView::new(&base_view, &hashmap_view);
+    assert_eq!(view.get(&access_path(2)).unwrap(), Some(vec![2]));
+    assert_eq!(view.blocked_by(), None);
+}
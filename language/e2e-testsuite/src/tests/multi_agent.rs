@@ -5,9 +5,12 @@
 
 use diem_transaction_builder::stdlib::*;
 use diem_types::{
-    account_config, test_helpers::transaction_test_helpers, transaction::TransactionStatus,
-    vm_status::KeptVMStatus,
+    account_config,
+    test_helpers::transaction_test_helpers,
+    transaction::TransactionStatus,
+    vm_status::{KeptVMStatus, StatusCode},
 };
+use diem_vm::DiemVM;
 use language_e2e_tests::{
     account::{self, xdx_currency_code, xus_currency_code, Account},
     common_transactions::{
@@ -247,3 +250,47 @@ fn multi_agent_wrong_number_of_signers() {
         &TransactionStatus::Keep(KeptVMStatus::MiscellaneousError)
     );
 }
+
+#[test]
+fn multi_agent_simulate_with_placeholder_signature() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let mut sender = executor.create_raw_account_data(1_000_010, 10);
+    let mut secondary_signer = executor.create_xdx_raw_account_data(100_100, 100);
+    sender.add_balance_currency(xdx_currency_code());
+    secondary_signer.add_balance_currency(xus_currency_code());
+
+    executor.add_account_data(&sender);
+    executor.add_account_data(&secondary_signer);
+
+    // The secondary signer hasn't signed yet: sign with a placeholder key instead.
+    let mut unsigned_secondary_signer = secondary_signer.account().clone();
+    let placeholder = Account::new();
+    unsigned_secondary_signer.rotate_key(placeholder.privkey, placeholder.pubkey);
+    let txn = multi_agent_swap_txn(sender.account(), &unsigned_secondary_signer, 10, 10, 100);
+
+    // The transaction can't be executed...
+    let output = executor.execute_transaction(txn.clone());
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(StatusCode::INVALID_SIGNATURE)
+    );
+
+    // ...but it can be simulated.
+    let output = DiemVM::simulate_signed_transaction(txn, executor.get_state_view());
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+    assert!(!output.write_set().is_empty());
+
+    // The signature of the sender is still checked.
+    let mut unsigned_sender = sender.account().clone();
+    let placeholder = Account::new();
+    unsigned_sender.rotate_key(placeholder.privkey, placeholder.pubkey);
+    let txn = multi_agent_swap_txn(&unsigned_sender, secondary_signer.account(), 10, 10, 100);
+    let output = DiemVM::simulate_signed_transaction(txn, executor.get_state_view());
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Discard(StatusCode::INVALID_SIGNATURE)
+    );
+}
@@ -3,9 +3,10 @@
 
 use diem_types::{
     account_config::{ReceivedPaymentEvent, SentPaymentEvent},
-    transaction::{SignedTransaction, TransactionOutput, TransactionStatus},
+    transaction::{SignedTransaction, Transaction, TransactionOutput, TransactionStatus},
     vm_status::{known_locations, KeptVMStatus},
 };
+use diem_vm::DiemVM;
 use language_e2e_tests::{
     account::{self, Account},
     common_transactions::peer_to_peer_txn,
@@ -262,6 +263,42 @@ fn zero_amount_peer_to_peer() {
     }
 }
 
+#[test]
+fn peer_to_peer_gas_profile() {
+    test_with_different_versions! {CURRENT_RELEASE_VERSIONS, |test_env| {
+        let mut executor = test_env.executor;
+        let sender = executor.create_raw_account_data(1_000_000, 10);
+        let receiver = executor.create_raw_account_data(100_000, 10);
+        executor.add_account_data(&sender);
+        executor.add_account_data(&receiver);
+
+        let txn = peer_to_peer_txn(sender.account(), receiver.account(), 10, 1_000);
+        let output = executor.execute_transaction(txn.clone());
+        assert!(output.gas_profile().is_none());
+
+        let profiled_output = DiemVM::execute_block_with_gas_profiling(
+            vec![Transaction::UserTransaction(txn)],
+            executor.get_state_view(),
+        )
+        .expect("The VM should not fail to startup")
+        .pop()
+        .expect("A block with one transaction should have one output");
+        // Profiling doesn't change the outcome of the transaction.
+        assert_eq!(profiled_output.status(), output.status());
+        assert_eq!(profiled_output.gas_used(), output.gas_used());
+        assert_eq!(profiled_output.write_set(), output.write_set());
+
+        let profile = profiled_output
+            .gas_profile()
+            .expect("User transactions should be profiled");
+        assert!(profile.intrinsic > 0);
+        assert!(profile.instructions.contains_key("call"));
+        assert!(profile.storage.contains_key("write_set"));
+        assert!(!profile.natives.is_empty());
+    }
+    }
+}
+
 // Holder for transaction data; arguments to transactions.
 struct TxnInfo {
     pub sender: Account,
@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    file_format::{AbilitySet, StructTypeParameter, Visibility},
+    file_format::{Ability, AbilitySet, StructTypeParameter, Visibility},
     normalized::Module,
 };
-use std::collections::BTreeSet;
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+use std::{collections::BTreeSet, fmt};
 
 /// The result of a linking and layout compatibility check. Here is what the different combinations
 /// mean:
@@ -20,6 +21,160 @@ pub struct Compatibility {
     pub struct_layout: bool,
 }
 
+/// How a change to a module affects the modules depending on it and the values published under
+/// its structs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ChangeClass {
+    /// Dependent modules keep linking and published values can still be read.
+    Compatible,
+    /// Dependent modules that reference the changed struct or function may not link.
+    BreaksLinking,
+    /// Previously published values of the changed struct can no longer be read.
+    BreaksLayout,
+}
+
+/// A change from an old to a new version of a module, which is relevant to its dependent
+/// modules or to the values published under its structs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ModuleChange {
+    /// The address or the name of the module changed.
+    ModuleIdChanged {
+        old: ModuleId,
+        new: ModuleId,
+    },
+    StructAdded(Identifier),
+    StructRemoved(Identifier),
+    StructAbilitiesChanged {
+        name: Identifier,
+        old: AbilitySet,
+        new: AbilitySet,
+    },
+    StructTypeParametersChanged {
+        name: Identifier,
+        old: Vec<StructTypeParameter>,
+        new: Vec<StructTypeParameter>,
+    },
+    StructFieldsChanged(Identifier),
+    /// A public, script or friend function was added, or a private function was exposed.
+    FunctionAdded(Identifier),
+    /// A public, script or friend function was removed, or made private.
+    FunctionRemoved(Identifier),
+    FunctionVisibilityChanged {
+        name: Identifier,
+        old: Visibility,
+        new: Visibility,
+    },
+    /// The parameter or return types of the function changed.
+    FunctionSignatureChanged(Identifier),
+    FunctionTypeParametersChanged {
+        name: Identifier,
+        old: Vec<AbilitySet>,
+        new: Vec<AbilitySet>,
+    },
+    FriendAdded(ModuleId),
+    FriendRemoved(ModuleId),
+}
+
+impl ModuleChange {
+    /// Classifies the change.
+    pub fn class(&self) -> ChangeClass {
+        use ModuleChange::*;
+        let compatible_if = |compatible: bool| {
+            if compatible {
+                ChangeClass::Compatible
+            } else {
+                ChangeClass::BreaksLinking
+            }
+        };
+        match self {
+            StructAdded(_) | FunctionAdded(_) | FriendAdded(_) => ChangeClass::Compatible,
+            // Note: we intentionally do *not* label the removal of a struct a layout compatibility
+            // violation. Existing modules can still successfully read previously published values
+            // of this struct `Parent::T`. That is, code like the function `foo` in
+            // ```
+            // struct S { t: Parent::T }
+            // public fun foo(a: addr): S { move_from<S>(addr) }
+            // ```
+            // in module `Child` will continue to run without error. But values of type
+            // `Parent::T` in `Child` are now "orphaned" in the sense that `Parent` no
+            // longer exposes any API for reading/writing them.
+            ModuleIdChanged { .. }
+            | StructRemoved(_)
+            | FunctionRemoved(_)
+            | FunctionSignatureChanged(_)
+            | FriendRemoved(_) => ChangeClass::BreaksLinking,
+            StructAbilitiesChanged { old, new, .. } => {
+                compatible_if(struct_abilities_compatibile(*old, *new))
+            }
+            StructTypeParametersChanged { old, new, .. } => {
+                compatible_if(struct_type_parameters_compatibile(old, new))
+            }
+            // TODO: this is a stricter definition than required. We could in principle
+            // choose to label the following as compatible
+            // (1) changing the name (but not position or type) of a field. The VM does
+            //     not care about the name of a field (it's purely informational), but
+            //     clients presumably do.
+            // (2) changing the type of a field to a different, but layout and kind
+            //     compatible type. E.g. `struct S { b: bool }` to `struct S { b: B }`
+            // where
+            //     B is struct B { some_name: bool }. TODO: does this affect clients? I
+            //     think not--the serialization of the same data with these two types
+            //     will be the same.
+            StructFieldsChanged(_) => ChangeClass::BreaksLayout,
+            FunctionVisibilityChanged { old, new, .. } => {
+                compatible_if(visibility_compatible(*old, *new))
+            }
+            FunctionTypeParametersChanged { old, new, .. } => {
+                compatible_if(fun_type_parameters_compatibile(old, new))
+            }
+        }
+    }
+
+    /// Returns true if the change breaks dependent modules or published values.
+    pub fn is_breaking(&self) -> bool {
+        self.class() != ChangeClass::Compatible
+    }
+}
+
+impl fmt::Display for ModuleChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ModuleChange::*;
+        match self {
+            ModuleIdChanged { old, new } => write!(f, "module {} was replaced by {}", old, new),
+            StructAdded(name) => write!(f, "struct {} was added", name),
+            StructRemoved(name) => write!(f, "struct {} was removed", name),
+            StructAbilitiesChanged { name, old, new } => write!(
+                f,
+                "abilities of struct {} changed from {} to {}",
+                name,
+                format_abilities(*old),
+                format_abilities(*new)
+            ),
+            StructTypeParametersChanged { name, .. } => {
+                write!(f, "type parameters of struct {} changed", name)
+            }
+            StructFieldsChanged(name) => write!(f, "fields of struct {} changed", name),
+            FunctionAdded(name) => write!(f, "function {} was added", name),
+            FunctionRemoved(name) => write!(f, "function {} was removed", name),
+            FunctionVisibilityChanged { name, old, new } => write!(
+                f,
+                "visibility of function {} changed from {} to {}",
+                name,
+                format_visibility(*old),
+                format_visibility(*new)
+            ),
+            FunctionSignatureChanged(name) => {
+                write!(f, "parameter or return types of function {} changed", name)
+            }
+            FunctionTypeParametersChanged { name, .. } => {
+                write!(f, "type parameters of function {} changed", name)
+            }
+            FriendAdded(module_id) => write!(f, "friend {} was added", module_id),
+            FriendRemoved(module_id) => write!(f, "friend {} was removed", module_id),
+        }
+    }
+}
+
 impl Compatibility {
     /// Return true if the two module s compared in the compatiblity check are both linking and
     /// layout compatible.
@@ -29,123 +184,143 @@ impl Compatibility {
 
     /// Return compatibility assessment for `new_module` relative to old module `old_module`.
     pub fn check(old_module: &Module, new_module: &Module) -> Compatibility {
-        let mut struct_and_function_linking = true;
-        let mut struct_layout = true;
+        Self::from_changes(&Self::changes(old_module, new_module))
+    }
+
+    /// Return the compatibility assessment of the given changes.
+    pub fn from_changes(changes: &[ModuleChange]) -> Compatibility {
+        let breaks = |class| changes.iter().any(|change| change.class() == class);
+        Compatibility {
+            struct_and_function_linking: !breaks(ChangeClass::BreaksLinking),
+            struct_layout: !breaks(ChangeClass::BreaksLayout),
+        }
+    }
+
+    /// Return the changes from `old_module` to `new_module`, both compatible and breaking ones.
+    ///
+    /// The modules are considered as compatible function-wise when all the conditions are met:
+    ///
+    /// - old module's public functions are a subset of the new module's public functions
+    ///   (i.e. we cannot remove or change public functions)
+    /// - old module's script functions are a subset of the new module's script functions
+    ///   (i.e. we cannot remove or change script functions)
+    /// - for any friend function that is removed or changed in the old module
+    ///   - if the function visibility is upgraded to public, it is OK
+    ///   - otherwise, it is considered as incompatible.
+    ///
+    /// NOTE: it is possible to relax the compatibility checking for a friend function, i.e.,
+    /// we can remove/change a friend function if the function is not used by any module in the
+    /// friend list. But for simplicity, we decided to go to the more restrictive form now and
+    /// we may revisit this in the future.
+    ///
+    /// For friend declarations, additions to the list are allowed but removals are not.
+    ///
+    /// NOTE: we may also relax this checking a bit in the future: we may allow the removal of
+    /// a module removed from the friend list if the module does not call any friend function
+    /// in this module.
+    pub fn changes(old_module: &Module, new_module: &Module) -> Vec<ModuleChange> {
+        let mut changes = vec![];
 
-        // module's name and address are unchanged
         if old_module.address != new_module.address || old_module.name != new_module.name {
-            struct_and_function_linking = false;
+            changes.push(ModuleChange::ModuleIdChanged {
+                old: ModuleId::new(old_module.address, old_module.name.clone()),
+                new: ModuleId::new(new_module.address, new_module.name.clone()),
+            });
         }
 
-        // old module's structs are a subset of the new module's structs
         for (name, old_struct) in &old_module.structs {
             let new_struct = match new_module.structs.get(name) {
                 Some(new_struct) => new_struct,
                 None => {
-                    // Struct not present in new . Existing modules that depend on this struct will fail to link with the new version of the module.
-                    struct_and_function_linking = false;
-                    // Note: we intentionally do *not* label this a layout compatibility violation.
-                    // Existing modules can still successfully read previously published values of
-                    // this struct `Parent::T`. That is, code like the function `foo` in
-                    // ```
-                    // struct S { t: Parent::T }
-                    // public fun foo(a: addr): S { move_from<S>(addr) }
-                    // ```
-                    // in module `Child` will continue to run without error. But values of type
-                    // `Parent::T` in `Child` are now "orphaned" in the sense that `Parent` no
-                    // longer exposes any API for reading/writing them.
+                    changes.push(ModuleChange::StructRemoved(name.clone()));
                     continue;
                 }
             };
-
-            if !struct_abilities_compatibile(old_struct.abilities, new_struct.abilities)
-                || !struct_type_parameters_compatibile(
-                    &old_struct.type_parameters,
-                    &new_struct.type_parameters,
-                )
-            {
-                struct_and_function_linking = false;
-            }
-            if new_struct.fields != old_struct.fields {
-                // Fields changed. Code in this module will fail at runtime if it tries to
-                // read a previously published struct value
-                // TODO: this is a stricter definition than required. We could in principle
-                // choose to label the following as compatible
-                // (1) changing the name (but not position or type) of a field. The VM does
-                //     not care about the name of a field (it's purely informational), but
-                //     clients presumably do.
-                // (2) changing the type of a field to a different, but layout and kind
-                //     compatible type. E.g. `struct S { b: bool }` to `struct S { b: B }`
-                // where
-                //     B is struct B { some_name: bool }. TODO: does this affect clients? I
-                //     think not--the serialization of the same data with these two types
-                //     will be the same.
-                struct_layout = false
+            if old_struct.abilities != new_struct.abilities {
+                changes.push(ModuleChange::StructAbilitiesChanged {
+                    name: name.clone(),
+                    old: old_struct.abilities,
+                    new: new_struct.abilities,
+                });
+            }
+            if old_struct.type_parameters != new_struct.type_parameters {
+                changes.push(ModuleChange::StructTypeParametersChanged {
+                    name: name.clone(),
+                    old: old_struct.type_parameters.clone(),
+                    new: new_struct.type_parameters.clone(),
+                });
+            }
+            if old_struct.fields != new_struct.fields {
+                changes.push(ModuleChange::StructFieldsChanged(name.clone()));
+            }
+        }
+        for name in new_module.structs.keys() {
+            if !old_module.structs.contains_key(name) {
+                changes.push(ModuleChange::StructAdded(name.clone()));
             }
         }
 
-        // The modules are considered as compatible function-wise when all the conditions are met:
-        //
-        // - old module's public functions are a subset of the new module's public functions
-        //   (i.e. we cannot remove or change public functions)
-        // - old module's script functions are a subset of the new module's script functions
-        //   (i.e. we cannot remove or change script functions)
-        // - for any friend function that is removed or changed in the old module
-        //   - if the function visibility is upgraded to public, it is OK
-        //   - otherwise, it is considered as incompatible.
-        //
-        // NOTE: it is possible to relax the compatibility checking for a friend function, i.e.,
-        // we can remove/change a friend function if the function is not used by any module in the
-        // friend list. But for simplicity, we decided to go to the more restrictive form now and
-        // we may revisit this in the future.
         for (name, old_func) in &old_module.exposed_functions {
             let new_func = match new_module.exposed_functions.get(name) {
                 Some(new_func) => new_func,
                 None => {
-                    struct_and_function_linking = false;
+                    changes.push(ModuleChange::FunctionRemoved(name.clone()));
                     continue;
                 }
             };
-            let is_vis_compatible = match (old_func.visibility, new_func.visibility) {
-                (Visibility::Public, Visibility::Public) => true,
-                (Visibility::Public, _) => false,
-                (Visibility::Script, Visibility::Script) => true,
-                (Visibility::Script, _) => false,
-                (Visibility::Friend, Visibility::Public)
-                | (Visibility::Friend, Visibility::Friend) => true,
-                (Visibility::Friend, _) => false,
-                (Visibility::Private, _) => unreachable!("A private function can never be exposed"),
-            };
-            if !is_vis_compatible
-                || old_func.parameters != new_func.parameters
-                || old_func.return_ != new_func.return_
-                || !fun_type_parameters_compatibile(
-                    &old_func.type_parameters,
-                    &new_func.type_parameters,
-                )
-            {
-                struct_and_function_linking = false;
+            if old_func.visibility != new_func.visibility {
+                changes.push(ModuleChange::FunctionVisibilityChanged {
+                    name: name.clone(),
+                    old: old_func.visibility,
+                    new: new_func.visibility,
+                });
+            }
+            if old_func.parameters != new_func.parameters || old_func.return_ != new_func.return_ {
+                changes.push(ModuleChange::FunctionSignatureChanged(name.clone()));
+            }
+            if old_func.type_parameters != new_func.type_parameters {
+                changes.push(ModuleChange::FunctionTypeParametersChanged {
+                    name: name.clone(),
+                    old: old_func.type_parameters.clone(),
+                    new: new_func.type_parameters.clone(),
+                });
+            }
+        }
+        for name in new_module.exposed_functions.keys() {
+            if !old_module.exposed_functions.contains_key(name) {
+                changes.push(ModuleChange::FunctionAdded(name.clone()));
             }
         }
 
-        // check friend declarations compatibility
-        //
-        // - additions to the list are allowed
-        // - removals are not allowed
-        //
-        // NOTE: we may also relax this checking a bit in the future: we may allow the removal of
-        // a module removed from the friend list if the module does not call any friend function
-        // in this module.
         let old_friend_module_ids: BTreeSet<_> = old_module.friends.iter().cloned().collect();
         let new_friend_module_ids: BTreeSet<_> = new_module.friends.iter().cloned().collect();
-        if !old_friend_module_ids.is_subset(&new_friend_module_ids) {
-            struct_and_function_linking = false;
-        }
+        changes.extend(
+            old_friend_module_ids
+                .difference(&new_friend_module_ids)
+                .cloned()
+                .map(ModuleChange::FriendRemoved),
+        );
+        changes.extend(
+            new_friend_module_ids
+                .difference(&old_friend_module_ids)
+                .cloned()
+                .map(ModuleChange::FriendAdded),
+        );
 
-        Compatibility {
-            struct_and_function_linking,
-            struct_layout,
-        }
+        changes
+    }
+}
+
+// A public or script function must keep its visibility, while a friend function may become public.
+fn visibility_compatible(old_visibility: Visibility, new_visibility: Visibility) -> bool {
+    match (old_visibility, new_visibility) {
+        (Visibility::Public, Visibility::Public) => true,
+        (Visibility::Public, _) => false,
+        (Visibility::Script, Visibility::Script) => true,
+        (Visibility::Script, _) => false,
+        (Visibility::Friend, Visibility::Public) | (Visibility::Friend, Visibility::Friend) => true,
+        (Visibility::Friend, _) => false,
+        (Visibility::Private, _) => unreachable!("A private function can never be exposed"),
     }
 }
 
@@ -207,3 +382,25 @@ fn type_parameter_phantom_decl_compatibile(
     // old_type_paramter.is_phantom => new_type_parameter.is_phantom
     !old_type_parameter.is_phantom || new_type_parameter.is_phantom
 }
+
+fn format_visibility(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Private => "private",
+        Visibility::Public => "public",
+        Visibility::Script => "public(script)",
+        Visibility::Friend => "public(friend)",
+    }
+}
+
+fn format_abilities(abilities: AbilitySet) -> String {
+    let abilities: Vec<_> = abilities
+        .into_iter()
+        .map(|ability| match ability {
+            Ability::Copy => "copy",
+            Ability::Drop => "drop",
+            Ability::Store => "store",
+            Ability::Key => "key",
+        })
+        .collect();
+    format!("{{{}}}", abilities.join(", "))
+}
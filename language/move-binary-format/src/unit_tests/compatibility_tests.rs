@@ -0,0 +1,169 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    compatibility::{ChangeClass, Compatibility, ModuleChange},
+    file_format::{AbilitySet, Visibility},
+    normalized::{Field, Function, Module, Struct, Type},
+};
+use move_core_types::{
+    account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId,
+};
+
+fn ident(name: &str) -> Identifier {
+    Identifier::new(name).unwrap()
+}
+
+fn module_id(name: &str) -> ModuleId {
+    ModuleId::new(AccountAddress::ZERO, ident(name))
+}
+
+fn module() -> Module {
+    Module {
+        address: AccountAddress::ZERO,
+        name: ident("M"),
+        friends: vec![module_id("F")],
+        structs: vec![(
+            ident("S"),
+            Struct {
+                abilities: AbilitySet::PRIMITIVES,
+                type_parameters: vec![],
+                fields: vec![Field {
+                    name: ident("f"),
+                    type_: Type::U64,
+                }],
+            },
+        )]
+        .into_iter()
+        .collect(),
+        exposed_functions: vec![
+            (
+                ident("pub_fun"),
+                Function {
+                    visibility: Visibility::Public,
+                    type_parameters: vec![],
+                    parameters: vec![Type::U64],
+                    return_: vec![],
+                },
+            ),
+            (
+                ident("friend_fun"),
+                Function {
+                    visibility: Visibility::Friend,
+                    type_parameters: vec![],
+                    parameters: vec![],
+                    return_: vec![Type::Bool],
+                },
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    }
+}
+
+#[test]
+fn unchanged_module() {
+    let changes = Compatibility::changes(&module(), &module());
+    assert!(changes.is_empty());
+    assert!(Compatibility::from_changes(&changes).is_fully_compatible());
+}
+
+#[test]
+fn compatible_changes() {
+    let old = module();
+    let mut new = module();
+    new.friends.push(module_id("G"));
+    new.structs.get_mut(&ident("S")).unwrap().abilities = AbilitySet::ALL;
+    new.exposed_functions
+        .get_mut(&ident("friend_fun"))
+        .unwrap()
+        .visibility = Visibility::Public;
+    new.exposed_functions.insert(
+        ident("new_fun"),
+        Function {
+            visibility: Visibility::Script,
+            type_parameters: vec![],
+            parameters: vec![],
+            return_: vec![],
+        },
+    );
+
+    let changes = Compatibility::changes(&old, &new);
+    assert_eq!(
+        changes,
+        vec![
+            ModuleChange::StructAbilitiesChanged {
+                name: ident("S"),
+                old: AbilitySet::PRIMITIVES,
+                new: AbilitySet::ALL,
+            },
+            ModuleChange::FunctionVisibilityChanged {
+                name: ident("friend_fun"),
+                old: Visibility::Friend,
+                new: Visibility::Public,
+            },
+            ModuleChange::FunctionAdded(ident("new_fun")),
+            ModuleChange::FriendAdded(module_id("G")),
+        ]
+    );
+    assert!(changes.iter().all(|change| !change.is_breaking()));
+    assert!(Compatibility::check(&old, &new).is_fully_compatible());
+}
+
+#[test]
+fn linking_breaking_changes() {
+    let old = module();
+    let mut new = module();
+    new.friends.clear();
+    new.exposed_functions.remove(&ident("friend_fun"));
+    new.exposed_functions
+        .get_mut(&ident("pub_fun"))
+        .unwrap()
+        .parameters = vec![Type::U128];
+
+    let changes = Compatibility::changes(&old, &new);
+    assert_eq!(
+        changes,
+        vec![
+            ModuleChange::FunctionRemoved(ident("friend_fun")),
+            ModuleChange::FunctionSignatureChanged(ident("pub_fun")),
+            ModuleChange::FriendRemoved(module_id("F")),
+        ]
+    );
+    assert!(changes
+        .iter()
+        .all(|change| change.class() == ChangeClass::BreaksLinking));
+    let compatibility = Compatibility::check(&old, &new);
+    assert!(!compatibility.struct_and_function_linking);
+    assert!(compatibility.struct_layout);
+}
+
+#[test]
+fn layout_breaking_changes() {
+    let old = module();
+    let mut new = module();
+    new.structs.get_mut(&ident("S")).unwrap().fields[0].type_ = Type::Bool;
+
+    let changes = Compatibility::changes(&old, &new);
+    assert_eq!(changes, vec![ModuleChange::StructFieldsChanged(ident("S"))]);
+    assert_eq!(changes[0].class(), ChangeClass::BreaksLayout);
+    assert_eq!(changes[0].to_string(), "fields of struct S changed");
+    let compatibility = Compatibility::check(&old, &new);
+    assert!(compatibility.struct_and_function_linking);
+    assert!(!compatibility.struct_layout);
+}
+
+#[test]
+fn removed_abilities_break_linking() {
+    let old = module();
+    let mut new = module();
+    new.structs.get_mut(&ident("S")).unwrap().abilities = AbilitySet::EMPTY;
+
+    let changes = Compatibility::changes(&old, &new);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].class(), ChangeClass::BreaksLinking);
+    assert_eq!(
+        changes[0].to_string(),
+        "abilities of struct S changed from {copy, drop, store} to {}"
+    );
+}
@@ -9,6 +9,8 @@
 use mirai_annotations::*;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
+    fmt,
     ops::{Add, Div, Mul, Sub},
     u64,
 };
@@ -275,3 +277,48 @@ impl GasCost {
         self.instruction_gas.add(self.memory_gas)
     }
 }
+
+/// The gas charged to a transaction, broken down by what it was charged for. Amounts are in
+/// internal gas units.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GasProfile {
+    /// Gas charged for the size of the transaction.
+    pub intrinsic: GasCarrier,
+    /// Gas charged for bytecode instructions, by class of instruction.
+    pub instructions: BTreeMap<String, GasCarrier>,
+    /// Gas charged by native functions, by fully qualified function name.
+    pub natives: BTreeMap<String, GasCarrier>,
+    /// Gas charged for accesses to global storage, by operation.
+    pub storage: BTreeMap<String, GasCarrier>,
+}
+
+impl GasProfile {
+    /// The total gas charged, in internal gas units.
+    pub fn total(&self) -> GasCarrier {
+        self.intrinsic
+            + self.instructions.values().sum::<GasCarrier>()
+            + self.natives.values().sum::<GasCarrier>()
+            + self.storage.values().sum::<GasCarrier>()
+    }
+}
+
+impl fmt::Display for GasProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "total: {}", self.total())?;
+        writeln!(f, "intrinsic: {}", self.intrinsic)?;
+        for (title, costs) in &[
+            ("instructions", &self.instructions),
+            ("natives", &self.natives),
+            ("storage", &self.storage),
+        ] {
+            writeln!(f, "{}:", title)?;
+            // Most expensive first.
+            let mut costs = costs.iter().collect::<Vec<_>>();
+            costs.sort_by(|(_, cost1), (_, cost2)| cost2.cmp(cost1));
+            for (name, cost) in costs {
+                writeln!(f, "    {}: {}", name, cost)?;
+            }
+        }
+        Ok(())
+    }
+}
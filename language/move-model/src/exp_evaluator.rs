@@ -0,0 +1,779 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Evaluator of specification expressions over concrete values.
+//!
+//! Given the values of the temporaries (e.g. the parameters of a function), of the results of a
+//! function, and snapshots of the global memory before and after its execution, the evaluator
+//! computes the value of the conditions of the function's specification. This allows checking
+//! specifications at runtime, as done by unit tests and fuzzers, without going through the
+//! prover.
+//!
+//! Quantifiers are evaluated by enumerating their domain, which must be finite: number ranges,
+//! vectors, and the resources of a given type stored in global memory. The domain of the
+//! `address` type is approximated by the addresses holding resources in the memory snapshots,
+//! and the domain of `bool` by its two values; quantifiers over other types cannot be evaluated.
+
+use itertools::Itertools;
+use num::{BigInt, BigUint, One, Signed, ToPrimitive, Zero};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use move_core_types::value::MoveValue;
+
+use crate::{
+    ast::{Condition, Exp, ExpData, LocalVarDecl, Operation, QuantKind, TempIndex, Value},
+    model::{FieldId, GlobalEnv, Loc, ModuleId, NodeId, QualifiedInstId, SpecFunId, StructId},
+    symbol::Symbol,
+    ty::{PrimitiveType, Type},
+};
+
+/// Maximal depth of nested spec function calls, which bounds the evaluation of recursive spec
+/// functions.
+const MAX_CALL_DEPTH: usize = 1000;
+
+// =================================================================================================
+/// # Values and State
+
+/// A concrete value of a specification expression.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EvalValue {
+    Bool(bool),
+    /// An integer, of any of the Move integer types or of the unbounded `num` type.
+    Num(BigInt),
+    Address(BigUint),
+    Vector(Vec<EvalValue>),
+    /// The values of the fields of a struct, in declaration order.
+    Struct(Vec<EvalValue>),
+    /// The numbers from the lower bound (included) to the upper bound (excluded).
+    Range(BigInt, BigInt),
+}
+
+impl EvalValue {
+    /// Converts a Move value. Signers are represented by their address.
+    pub fn from_move_value(val: &MoveValue) -> Self {
+        match val {
+            MoveValue::Bool(b) => EvalValue::Bool(*b),
+            MoveValue::U8(n) => EvalValue::Num(BigInt::from(*n)),
+            MoveValue::U64(n) => EvalValue::Num(BigInt::from(*n)),
+            MoveValue::U128(n) => EvalValue::Num(BigInt::from(*n)),
+            MoveValue::Address(addr) | MoveValue::Signer(addr) => {
+                EvalValue::Address(BigUint::from_bytes_be(&addr.to_vec()))
+            }
+            MoveValue::Vector(elems) => {
+                EvalValue::Vector(elems.iter().map(Self::from_move_value).collect())
+            }
+            MoveValue::Struct(s) => {
+                EvalValue::Struct(s.fields().iter().map(Self::from_move_value).collect())
+            }
+        }
+    }
+}
+
+impl fmt::Display for EvalValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalValue::Bool(b) => write!(f, "{}", b),
+            EvalValue::Num(n) => write!(f, "{}", n),
+            EvalValue::Address(addr) => write!(f, "0x{:x}", addr),
+            EvalValue::Vector(elems) => write!(f, "[{}]", elems.iter().join(", ")),
+            EvalValue::Struct(fields) => write!(f, "{{{}}}", fields.iter().join(", ")),
+            EvalValue::Range(lower, upper) => write!(f, "{}..{}", lower, upper),
+        }
+    }
+}
+
+/// A snapshot of the global memory, holding the resources stored at each address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GlobalSnapshot {
+    resources: BTreeMap<QualifiedInstId<StructId>, BTreeMap<BigUint, EvalValue>>,
+}
+
+impl GlobalSnapshot {
+    /// Stores the resource `value` of type `mem` at `addr`, replacing any existing one.
+    pub fn put_resource(
+        &mut self,
+        mem: QualifiedInstId<StructId>,
+        addr: BigUint,
+        value: EvalValue,
+    ) {
+        self.resources.entry(mem).or_default().insert(addr, value);
+    }
+
+    pub fn get_resource(
+        &self,
+        mem: &QualifiedInstId<StructId>,
+        addr: &BigUint,
+    ) -> Option<&EvalValue> {
+        self.resources.get(mem).and_then(|values| values.get(addr))
+    }
+
+    /// Returns the addresses at which resources are stored.
+    pub fn addresses(&self) -> BTreeSet<BigUint> {
+        self.resources
+            .values()
+            .flat_map(|values| values.keys().cloned())
+            .collect()
+    }
+
+    /// Returns the resources of the struct `module_id::struct_id`, for any instantiation of the
+    /// struct if `inst` is not set.
+    fn resources_of(
+        &self,
+        module_id: ModuleId,
+        struct_id: StructId,
+        inst: Option<&[Type]>,
+    ) -> Vec<EvalValue> {
+        self.resources
+            .iter()
+            .filter(|(mem, _)| {
+                mem.module_id == module_id
+                    && mem.id == struct_id
+                    && inst.map_or(true, |inst| mem.inst == inst)
+            })
+            .flat_map(|(_, values)| values.values().cloned())
+            .collect()
+    }
+}
+
+/// The state an expression is evaluated in.
+#[derive(Debug, Clone, Default)]
+pub struct EvalState {
+    /// The values of the temporaries, by index.
+    pub temps: Vec<EvalValue>,
+    pub memory: GlobalSnapshot,
+}
+
+/// An error raised by an expression which cannot be evaluated, e.g. a division by zero or a
+/// quantifier over an unbounded domain.
+#[derive(Debug, Clone)]
+pub struct EvalError {
+    /// The location of the expression.
+    pub loc: Loc,
+    pub message: String,
+}
+
+pub type EvalResult<T> = Result<T, EvalError>;
+
+// =================================================================================================
+/// # Evaluator
+
+/// The bindings of an evaluation context: the local variables introduced by quantifiers, blocks,
+/// and spec function parameters, along with the type arguments of the spec function being
+/// evaluated.
+#[derive(Clone, Default)]
+struct Frame {
+    locals: BTreeMap<Symbol, EvalValue>,
+    type_args: Vec<Type>,
+    /// Whether the expression is evaluated in the pre-state, i.e., under `old(..)`.
+    in_old: bool,
+    depth: usize,
+}
+
+impl Frame {
+    fn bind(&self, name: Symbol, val: EvalValue) -> Frame {
+        let mut frame = self.clone();
+        frame.locals.insert(name, val);
+        frame
+    }
+}
+
+/// Evaluator of specification expressions over concrete values.
+pub struct ExpEvaluator<'env> {
+    env: &'env GlobalEnv,
+    state: EvalState,
+    pre_state: Option<EvalState>,
+    results: Vec<EvalValue>,
+    type_args: Vec<Type>,
+}
+
+impl<'env> ExpEvaluator<'env> {
+    /// Creates an evaluator of expressions in the given state.
+    pub fn new(env: &'env GlobalEnv, state: EvalState) -> Self {
+        ExpEvaluator {
+            env,
+            state,
+            pre_state: None,
+            results: vec![],
+            type_args: vec![],
+        }
+    }
+
+    /// Sets the state `old(..)` expressions are evaluated in. If not set, they are evaluated in
+    /// the current state.
+    pub fn set_pre_state(mut self, pre_state: EvalState) -> Self {
+        self.pre_state = Some(pre_state);
+        self
+    }
+
+    /// Sets the values of the results of the function, i.e. of `result` or `result_<n>`.
+    pub fn set_results(mut self, results: Vec<EvalValue>) -> Self {
+        self.results = results;
+        self
+    }
+
+    /// Sets the type arguments which generic type parameters are instantiated with.
+    pub fn set_type_args(mut self, type_args: Vec<Type>) -> Self {
+        self.type_args = type_args;
+        self
+    }
+
+    /// Evaluates an expression.
+    pub fn evaluate(&self, exp: &Exp) -> EvalResult<EvalValue> {
+        let frame = Frame {
+            type_args: self.type_args.clone(),
+            ..Frame::default()
+        };
+        self.eval(exp, &frame)
+    }
+
+    /// Evaluates a condition, returning whether it holds.
+    pub fn check_condition(&self, cond: &Condition) -> EvalResult<bool> {
+        let val = self.evaluate(&cond.exp)?;
+        self.expect_bool(cond.exp.node_id(), val)
+    }
+
+    /// Calls a spec function with the given arguments.
+    pub fn call_spec_fun(
+        &self,
+        module_id: ModuleId,
+        fun_id: SpecFunId,
+        type_args: Vec<Type>,
+        args: Vec<EvalValue>,
+    ) -> EvalResult<EvalValue> {
+        let module_env = self.env.get_module(module_id);
+        let decl = module_env.get_spec_fun(fun_id);
+        let frame = Frame {
+            type_args: self.type_args.clone(),
+            ..Frame::default()
+        };
+        self.call(&decl.loc, module_id, fun_id, type_args, args, &frame)
+    }
+
+    fn eval(&self, exp: &Exp, frame: &Frame) -> EvalResult<EvalValue> {
+        match exp.as_ref() {
+            ExpData::Value(_, val) => Ok(match val {
+                Value::Address(addr) => EvalValue::Address(addr.clone()),
+                Value::Number(n) => EvalValue::Num(n.clone()),
+                Value::Bool(b) => EvalValue::Bool(*b),
+                Value::ByteArray(bytes) => EvalValue::Vector(
+                    bytes
+                        .iter()
+                        .map(|b| EvalValue::Num(BigInt::from(*b)))
+                        .collect(),
+                ),
+            }),
+            ExpData::LocalVar(id, name) => match frame.locals.get(name) {
+                Some(val) => Ok(val.clone()),
+                None => self.error(
+                    *id,
+                    format!(
+                        "unbound variable `{}`",
+                        name.display(self.env.symbol_pool())
+                    ),
+                ),
+            },
+            ExpData::Temporary(id, idx) => self.eval_temporary(*id, *idx, frame),
+            ExpData::Call(id, oper, args) => self.eval_call(*id, oper, args, frame),
+            ExpData::Quant(id, kind, ranges, _, condition, body) => {
+                self.eval_quant(*id, *kind, ranges, condition.as_ref(), body, frame)
+            }
+            ExpData::Block(_, decls, body) => {
+                let mut frame = frame.clone();
+                for decl in decls {
+                    let val = match &decl.binding {
+                        Some(binding) => self.eval(binding, &frame)?,
+                        None => return self.error(decl.id, "unbound variable declaration"),
+                    };
+                    frame.locals.insert(decl.name, val);
+                }
+                self.eval(body, &frame)
+            }
+            ExpData::IfElse(_, cond, if_true, if_false) => {
+                if self.eval_bool(cond, frame)? {
+                    self.eval(if_true, frame)
+                } else {
+                    self.eval(if_false, frame)
+                }
+            }
+            ExpData::Invoke(id, ..) | ExpData::Lambda(id, ..) => {
+                self.error(*id, "function values cannot be evaluated")
+            }
+            ExpData::Invalid(id) => self.error(*id, "invalid expression"),
+        }
+    }
+
+    fn eval_temporary(&self, id: NodeId, idx: TempIndex, frame: &Frame) -> EvalResult<EvalValue> {
+        match self.current_state(frame).temps.get(idx) {
+            Some(val) => Ok(val.clone()),
+            None => self.error(id, format!("no value for temporary `$t{}`", idx)),
+        }
+    }
+
+    fn eval_call(
+        &self,
+        id: NodeId,
+        oper: &Operation,
+        args: &[Exp],
+        frame: &Frame,
+    ) -> EvalResult<EvalValue> {
+        use EvalValue::*;
+        // Operations which do not evaluate all of their arguments, or not in the current frame
+        match oper {
+            Operation::And => {
+                return Ok(Bool(
+                    self.eval_bool(&args[0], frame)? && self.eval_bool(&args[1], frame)?,
+                ))
+            }
+            Operation::Or => {
+                return Ok(Bool(
+                    self.eval_bool(&args[0], frame)? || self.eval_bool(&args[1], frame)?,
+                ))
+            }
+            Operation::Implies => {
+                return Ok(Bool(
+                    !self.eval_bool(&args[0], frame)? || self.eval_bool(&args[1], frame)?,
+                ))
+            }
+            Operation::Old => {
+                let frame = Frame {
+                    in_old: true,
+                    ..frame.clone()
+                };
+                return self.eval(&args[0], &frame);
+            }
+            Operation::TypeDomain | Operation::ResourceDomain => {
+                return Ok(Vector(self.domain_values(id, frame)?));
+            }
+            _ => (),
+        }
+
+        let mut vals = args
+            .iter()
+            .map(|arg| self.eval(arg, frame))
+            .collect::<EvalResult<Vec<_>>>()?;
+        let val = match oper {
+            Operation::Function(module_id, fun_id, labels) => {
+                if labels.is_some() {
+                    return self.error(id, "labelled memory cannot be evaluated");
+                }
+                let type_args = self
+                    .env
+                    .get_node_instantiation(id)
+                    .iter()
+                    .map(|ty| ty.instantiate(&frame.type_args))
+                    .collect();
+                let loc = self.env.get_node_loc(id);
+                return self.call(&loc, *module_id, *fun_id, type_args, vals, frame);
+            }
+            Operation::Pack(..) => Struct(vals),
+            Operation::Select(module_id, struct_id, field_id) => {
+                let offset = self.field_offset(*module_id, *struct_id, *field_id);
+                match vals.remove(0) {
+                    Struct(mut fields) if offset < fields.len() => fields.swap_remove(offset),
+                    _ => return self.error(id, "expected a struct"),
+                }
+            }
+            Operation::UpdateField(module_id, struct_id, field_id) => {
+                let offset = self.field_offset(*module_id, *struct_id, *field_id);
+                let field_val = vals.remove(1);
+                match vals.remove(0) {
+                    Struct(mut fields) if offset < fields.len() => {
+                        fields[offset] = field_val;
+                        Struct(fields)
+                    }
+                    _ => return self.error(id, "expected a struct"),
+                }
+            }
+            Operation::Result(idx) => match self.results.get(*idx) {
+                Some(val) => val.clone(),
+                None => return self.error(id, format!("no value for result {}", idx)),
+            },
+            Operation::Index => {
+                let idx = self.expect_num(id, vals.remove(1))?;
+                let mut elems = self.expect_vector(id, vals.remove(0))?;
+                match idx.to_usize().filter(|idx| *idx < elems.len()) {
+                    Some(idx) => elems.swap_remove(idx),
+                    None => return self.error(id, "index out of bounds"),
+                }
+            }
+            Operation::Slice => {
+                let (lower, upper) = self.expect_range(id, vals.remove(1))?;
+                let elems = self.expect_vector(id, vals.remove(0))?;
+                match (lower.to_usize(), upper.to_usize()) {
+                    (Some(lower), Some(upper)) if lower <= upper && upper <= elems.len() => {
+                        Vector(elems[lower..upper].to_vec())
+                    }
+                    _ => return self.error(id, "slice out of bounds"),
+                }
+            }
+            Operation::Range => {
+                let upper = self.expect_num(id, vals.remove(1))?;
+                let lower = self.expect_num(id, vals.remove(0))?;
+                Range(lower, upper)
+            }
+            Operation::RangeVec => {
+                let elems = self.expect_vector(id, vals.remove(0))?;
+                Range(BigInt::zero(), BigInt::from(elems.len()))
+            }
+
+            // Arithmetic and bitwise operators, over unbounded integers
+            Operation::Add
+            | Operation::Sub
+            | Operation::Mul
+            | Operation::Div
+            | Operation::Mod
+            | Operation::BitOr
+            | Operation::BitAnd
+            | Operation::Xor
+            | Operation::Shl
+            | Operation::Shr => {
+                let rhs = self.expect_num(id, vals.remove(1))?;
+                let lhs = self.expect_num(id, vals.remove(0))?;
+                Num(self.eval_arithmetic(id, oper, lhs, rhs)?)
+            }
+            Operation::Lt | Operation::Gt | Operation::Le | Operation::Ge => {
+                let rhs = self.expect_num(id, vals.remove(1))?;
+                let lhs = self.expect_num(id, vals.remove(0))?;
+                Bool(match oper {
+                    Operation::Lt => lhs < rhs,
+                    Operation::Gt => lhs > rhs,
+                    Operation::Le => lhs <= rhs,
+                    _ => lhs >= rhs,
+                })
+            }
+            Operation::Eq | Operation::Identical => Bool(vals[0] == vals[1]),
+            Operation::Neq => Bool(vals[0] != vals[1]),
+            Operation::Iff => {
+                let rhs = self.expect_bool(id, vals.remove(1))?;
+                let lhs = self.expect_bool(id, vals.remove(0))?;
+                Bool(lhs == rhs)
+            }
+            Operation::Not => Bool(!self.expect_bool(id, vals.remove(0))?),
+
+            // Vectors
+            Operation::Len => Num(BigInt::from(self.expect_vector(id, vals.remove(0))?.len())),
+            Operation::EmptyVec => Vector(vec![]),
+            Operation::SingleVec => Vector(vec![vals.remove(0)]),
+            Operation::UpdateVec => {
+                let elem = vals.remove(2);
+                let idx = self.expect_num(id, vals.remove(1))?;
+                let mut elems = self.expect_vector(id, vals.remove(0))?;
+                match idx.to_usize().filter(|idx| *idx < elems.len()) {
+                    Some(idx) => elems[idx] = elem,
+                    None => return self.error(id, "index out of bounds"),
+                }
+                Vector(elems)
+            }
+            Operation::ConcatVec => {
+                let rhs = self.expect_vector(id, vals.remove(1))?;
+                let mut lhs = self.expect_vector(id, vals.remove(0))?;
+                lhs.extend(rhs);
+                Vector(lhs)
+            }
+            Operation::IndexOfVec => {
+                let elem = vals.remove(1);
+                let elems = self.expect_vector(id, vals.remove(0))?;
+                Num(match elems.iter().position(|e| *e == elem) {
+                    Some(idx) => BigInt::from(idx),
+                    None => -BigInt::one(),
+                })
+            }
+            Operation::ContainsVec => {
+                let elem = vals.remove(1);
+                Bool(self.expect_vector(id, vals.remove(0))?.contains(&elem))
+            }
+            Operation::InRangeVec => {
+                let idx = self.expect_num(id, vals.remove(1))?;
+                let elems = self.expect_vector(id, vals.remove(0))?;
+                Bool(!idx.is_negative() && idx < BigInt::from(elems.len()))
+            }
+            Operation::InRangeRange => {
+                let n = self.expect_num(id, vals.remove(1))?;
+                let (lower, upper) = self.expect_range(id, vals.remove(0))?;
+                Bool(lower <= n && n < upper)
+            }
+
+            // Global memory
+            Operation::Exists(label) | Operation::Global(label) => {
+                if label.is_some() {
+                    return self.error(id, "labelled memory cannot be evaluated");
+                }
+                let mem = self.memory_of(id, frame)?;
+                let addr = match vals.remove(0) {
+                    Address(addr) => addr,
+                    _ => return self.error(id, "expected an address"),
+                };
+                let resource = self.current_state(frame).memory.get_resource(&mem, &addr);
+                match (oper, resource) {
+                    (Operation::Exists(_), resource) => Bool(resource.is_some()),
+                    (_, Some(resource)) => resource.clone(),
+                    (_, None) => {
+                        return self.error(id, format!("no resource stored at 0x{:x}", addr))
+                    }
+                }
+            }
+
+            // Constants
+            Operation::MaxU8 => Num(BigInt::from(u8::MAX)),
+            Operation::MaxU64 => Num(BigInt::from(u64::MAX)),
+            Operation::MaxU128 => Num(BigInt::from(u128::MAX)),
+
+            // Operations which have no effect on concrete values
+            Operation::Trace | Operation::BoxValue | Operation::UnboxValue => vals.remove(0),
+            Operation::WellFormed => Bool(true),
+
+            Operation::And
+            | Operation::Or
+            | Operation::Implies
+            | Operation::Old
+            | Operation::TypeDomain
+            | Operation::ResourceDomain => unreachable!(),
+            Operation::Tuple
+            | Operation::TypeValue
+            | Operation::CanModify
+            | Operation::AbortFlag
+            | Operation::AbortCode
+            | Operation::EmptyEventStore
+            | Operation::ExtendEventStore
+            | Operation::EventStoreIncludes
+            | Operation::EventStoreIncludedIn
+            | Operation::NoOp => {
+                return self.error(id, format!("operation {:?} cannot be evaluated", oper))
+            }
+        };
+        Ok(val)
+    }
+
+    fn eval_arithmetic(
+        &self,
+        id: NodeId,
+        oper: &Operation,
+        lhs: BigInt,
+        rhs: BigInt,
+    ) -> EvalResult<BigInt> {
+        let shift_amount = |rhs: &BigInt| match rhs.to_usize() {
+            Some(amount) => Ok(amount),
+            None => self.error(id, "invalid shift amount"),
+        };
+        let result = match oper {
+            Operation::Add => lhs + rhs,
+            Operation::Sub => lhs - rhs,
+            Operation::Mul => lhs * rhs,
+            Operation::Div | Operation::Mod if rhs.is_zero() => {
+                return self.error(id, "division by zero")
+            }
+            Operation::Div => lhs / rhs,
+            Operation::Mod => lhs % rhs,
+            Operation::BitOr => lhs | rhs,
+            Operation::BitAnd => lhs & rhs,
+            Operation::Xor => lhs ^ rhs,
+            Operation::Shl => lhs << shift_amount(&rhs)?,
+            Operation::Shr => lhs >> shift_amount(&rhs)?,
+            _ => unreachable!(),
+        };
+        Ok(result)
+    }
+
+    fn eval_quant(
+        &self,
+        id: NodeId,
+        kind: QuantKind,
+        ranges: &[(LocalVarDecl, Exp)],
+        condition: Option<&Exp>,
+        body: &Exp,
+        frame: &Frame,
+    ) -> EvalResult<EvalValue> {
+        let mut domains = vec![];
+        for (_, range) in ranges {
+            domains.push(self.range_values(range, frame)?);
+        }
+        for vals in domains.into_iter().multi_cartesian_product() {
+            let mut inner = frame.clone();
+            for ((decl, _), val) in ranges.iter().zip(vals.iter()) {
+                inner = inner.bind(decl.name, val.clone());
+            }
+            if let Some(condition) = condition {
+                if !self.eval_bool(condition, &inner)? {
+                    continue;
+                }
+            }
+            let holds = self.eval_bool(body, &inner)?;
+            match kind {
+                QuantKind::Forall if !holds => return Ok(EvalValue::Bool(false)),
+                QuantKind::Exists if holds => return Ok(EvalValue::Bool(true)),
+                // The values of a range are enumerated in increasing order, so the first value
+                // satisfying the predicate is also the minimal one.
+                QuantKind::Choose | QuantKind::ChooseMin if holds => {
+                    return Ok(vals.into_iter().next().unwrap())
+                }
+                _ => (),
+            }
+        }
+        match kind {
+            QuantKind::Forall => Ok(EvalValue::Bool(true)),
+            QuantKind::Exists => Ok(EvalValue::Bool(false)),
+            QuantKind::Choose | QuantKind::ChooseMin => {
+                self.error(id, "no value satisfies the choice")
+            }
+        }
+    }
+
+    /// Returns the values of the domain of a quantified variable.
+    fn range_values(&self, range: &Exp, frame: &Frame) -> EvalResult<Vec<EvalValue>> {
+        match self.eval(range, frame)? {
+            EvalValue::Vector(elems) => Ok(elems),
+            EvalValue::Range(lower, upper) => {
+                let mut vals = vec![];
+                let mut n = lower;
+                while n < upper {
+                    vals.push(EvalValue::Num(n.clone()));
+                    n += 1;
+                }
+                Ok(vals)
+            }
+            _ => self.error(range.node_id(), "expected a vector or a range"),
+        }
+    }
+
+    /// Returns the values of a type or resource domain.
+    fn domain_values(&self, id: NodeId, frame: &Frame) -> EvalResult<Vec<EvalValue>> {
+        let state = self.current_state(frame);
+        match self.env.get_node_type(id).instantiate(&frame.type_args) {
+            Type::TypeDomain(ty) => match *ty {
+                Type::Primitive(PrimitiveType::Bool) => {
+                    Ok(vec![EvalValue::Bool(false), EvalValue::Bool(true)])
+                }
+                Type::Primitive(PrimitiveType::Address) => {
+                    let mut addresses = self.state.memory.addresses();
+                    if let Some(pre_state) = &self.pre_state {
+                        addresses.extend(pre_state.memory.addresses());
+                    }
+                    Ok(addresses.into_iter().map(EvalValue::Address).collect())
+                }
+                _ => self.error(id, "quantifiers over unbounded domains cannot be evaluated"),
+            },
+            Type::ResourceDomain(module_id, struct_id, inst) => {
+                Ok(state
+                    .memory
+                    .resources_of(module_id, struct_id, inst.as_deref()))
+            }
+            _ => self.error(id, "expected a domain"),
+        }
+    }
+
+    fn call(
+        &self,
+        loc: &Loc,
+        module_id: ModuleId,
+        fun_id: SpecFunId,
+        type_args: Vec<Type>,
+        args: Vec<EvalValue>,
+        frame: &Frame,
+    ) -> EvalResult<EvalValue> {
+        let module_env = self.env.get_module(module_id);
+        let decl = module_env.get_spec_fun(fun_id);
+        let body = match &decl.body {
+            Some(body) if !decl.uninterpreted => body,
+            _ => {
+                return Err(EvalError {
+                    loc: loc.clone(),
+                    message: format!(
+                        "spec function `{}` has no definition",
+                        decl.name.display(self.env.symbol_pool())
+                    ),
+                })
+            }
+        };
+        if frame.depth >= MAX_CALL_DEPTH {
+            return Err(EvalError {
+                loc: loc.clone(),
+                message: "maximal depth of spec function calls exceeded".to_string(),
+            });
+        }
+        let frame = Frame {
+            locals: decl
+                .params
+                .iter()
+                .map(|(name, _)| *name)
+                .zip(args)
+                .collect(),
+            type_args,
+            in_old: frame.in_old,
+            depth: frame.depth + 1,
+        };
+        self.eval(body, &frame)
+    }
+
+    // Helpers
+    // -------
+
+    fn current_state(&self, frame: &Frame) -> &EvalState {
+        match &self.pre_state {
+            Some(pre_state) if frame.in_old => pre_state,
+            _ => &self.state,
+        }
+    }
+
+    fn field_offset(&self, module_id: ModuleId, struct_id: StructId, field_id: FieldId) -> usize {
+        let struct_env = self.env.get_struct(module_id.qualified(struct_id));
+        struct_env.get_field(field_id).get_offset()
+    }
+
+    /// Returns the memory accessed by a `global` or `exists` expression.
+    fn memory_of(&self, id: NodeId, frame: &Frame) -> EvalResult<QualifiedInstId<StructId>> {
+        match self
+            .env
+            .get_node_instantiation(id)
+            .first()
+            .map(|ty| ty.instantiate(&frame.type_args))
+        {
+            Some(Type::Struct(module_id, struct_id, inst)) => {
+                Ok(module_id.qualified_inst(struct_id, inst))
+            }
+            _ => self.error(id, "expected a resource type"),
+        }
+    }
+
+    fn eval_bool(&self, exp: &Exp, frame: &Frame) -> EvalResult<bool> {
+        let val = self.eval(exp, frame)?;
+        self.expect_bool(exp.node_id(), val)
+    }
+
+    fn expect_bool(&self, id: NodeId, val: EvalValue) -> EvalResult<bool> {
+        match val {
+            EvalValue::Bool(b) => Ok(b),
+            _ => self.error(id, "expected a boolean"),
+        }
+    }
+
+    fn expect_num(&self, id: NodeId, val: EvalValue) -> EvalResult<BigInt> {
+        match val {
+            EvalValue::Num(n) => Ok(n),
+            _ => self.error(id, "expected a number"),
+        }
+    }
+
+    fn expect_vector(&self, id: NodeId, val: EvalValue) -> EvalResult<Vec<EvalValue>> {
+        match val {
+            EvalValue::Vector(elems) => Ok(elems),
+            _ => self.error(id, "expected a vector"),
+        }
+    }
+
+    fn expect_range(&self, id: NodeId, val: EvalValue) -> EvalResult<(BigInt, BigInt)> {
+        match val {
+            EvalValue::Range(lower, upper) => Ok((lower, upper)),
+            _ => self.error(id, "expected a range"),
+        }
+    }
+
+    fn error<T>(&self, id: NodeId, message: impl Into<String>) -> EvalResult<T> {
+        Err(EvalError {
+            loc: self.env.get_node_loc(id),
+            message: message.into(),
+        })
+    }
+}
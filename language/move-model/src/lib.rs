@@ -38,13 +38,14 @@ use num::{BigUint, Num};
 use crate::{
     ast::{ModuleName, Spec},
     builder::model_builder::ModelBuilder,
-    model::{FunId, FunctionData, GlobalEnv, Loc, ModuleData, ModuleId, StructId},
+    model::{FunId, FunctionData, GlobalEnv, Loc, ModuleData, ModuleId, PackageInfo, StructId},
     options::ModelBuilderOptions,
 };
 
 pub mod ast;
 mod builder;
 pub mod code_writer;
+pub mod exp_evaluator;
 pub mod exp_generator;
 pub mod exp_rewriter;
 pub mod model;
@@ -90,6 +91,58 @@ pub fn run_model_builder_with_options(
     )
 }
 
+/// Build the move model from Move packages, with default compilation flags and custom options.
+/// The sources of the `targets` packages are verified, and the ones of the `deps` packages are
+/// dependencies. The named addresses of all packages are in scope, and must be assigned the same
+/// value by the packages declaring them. The packages are recorded in the environment.
+pub fn run_model_builder_for_packages(
+    targets: Vec<PackageInfo>,
+    deps: Vec<PackageInfo>,
+    options: ModelBuilderOptions,
+) -> anyhow::Result<GlobalEnv> {
+    let mut named_addresses: BTreeMap<&str, (AccountAddress, &str)> = BTreeMap::new();
+    for package in targets.iter().chain(deps.iter()) {
+        for (name, addr) in &package.named_addresses {
+            match named_addresses.get(name.as_str()) {
+                Some((other_addr, other_package)) if other_addr != addr => anyhow::bail!(
+                    "named address `{}` is assigned `0x{}` by package `{}` but `0x{}` by \
+                     package `{}`",
+                    name,
+                    other_addr.short_str_lossless(),
+                    other_package,
+                    addr.short_str_lossless(),
+                    package.name,
+                ),
+                Some(_) => (),
+                None => {
+                    named_addresses.insert(name.as_str(), (*addr, &package.name));
+                }
+            }
+        }
+    }
+    let named_address_mapping = named_addresses
+        .into_iter()
+        .map(|(name, (addr, _))| (name.to_string(), AddressBytes::new(addr.to_u8())))
+        .collect();
+
+    let sources_of = |packages: &[PackageInfo]| {
+        packages
+            .iter()
+            .flat_map(|package| package.source_files.iter().cloned())
+            .collect::<Vec<_>>()
+    };
+    let mut env = run_model_builder_with_options(
+        &sources_of(&targets),
+        &sources_of(&deps),
+        options,
+        named_address_mapping,
+    )?;
+    for package in targets.into_iter().chain(deps) {
+        env.add_package(package);
+    }
+    Ok(env)
+}
+
 /// Build the move model with custom compilation flags and custom options
 /// This collects transitive dependencies for move sources from the provided directory list.
 pub fn run_model_builder_with_options_and_compilation_flags(
@@ -20,6 +20,7 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     ffi::OsStr,
     fmt::{self, Formatter},
+    path::PathBuf,
     rc::Rc,
 };
 
@@ -488,6 +489,37 @@ pub struct GlobalEnv {
     pub used_spec_funs: BTreeSet<QualifiedId<SpecFunId>>,
     /// A type-indexed container for storing extension data in the environment.
     extensions: RefCell<BTreeMap<TypeId, Box<dyn Any>>>,
+    /// The Move packages the sources of the environment come from, if it was built from packages.
+    packages: Vec<PackageInfo>,
+}
+
+/// A Move package the sources of an environment come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackageInfo {
+    pub name: String,
+    /// The `(major, minor, patch)` version of the package.
+    pub version: (u64, u64, u64),
+    /// The root directory of the package, holding its manifest.
+    pub root_path: PathBuf,
+    /// The Move source files of the package.
+    pub source_files: Vec<String>,
+    /// The values of the named addresses in scope in the package.
+    pub named_addresses: BTreeMap<String, AccountAddress>,
+    /// The names of the packages this package directly depends on.
+    pub dependencies: Vec<String>,
+    /// The default values of pragmas for the modules of the package, which apply to the
+    /// functions and structs of a module unless the module or the item sets the pragma itself.
+    pub pragmas: BTreeMap<String, PackagePragmaValue>,
+    /// The functions of the package which are excluded from verification, as
+    /// `<module>::<function>`.
+    pub excluded_functions: BTreeSet<String>,
+}
+
+/// The value of a pragma set by a package.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackagePragmaValue {
+    Bool(bool),
+    Number(usize),
 }
 
 /// Struct a helper type for implementing fmt::Display depending on GlobalEnv
@@ -537,6 +569,7 @@ impl GlobalEnv {
             global_invariants_for_memory: Default::default(),
             used_spec_funs: BTreeSet::new(),
             extensions: Default::default(),
+            packages: vec![],
         }
     }
 
@@ -629,6 +662,22 @@ impl GlobalEnv {
         file_id
     }
 
+    /// Adds a Move package the sources of the environment come from.
+    pub fn add_package(&mut self, package: PackageInfo) {
+        self.packages.push(package);
+    }
+
+    /// Returns the Move packages the sources of the environment come from, in the order they have
+    /// been added. This is empty if the environment was not built from packages.
+    pub fn get_packages(&self) -> &[PackageInfo] {
+        &self.packages
+    }
+
+    /// Returns the Move package with the given name, if any.
+    pub fn get_package(&self, name: &str) -> Option<&PackageInfo> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+
     /// Find all target modules and return in a vector
     pub fn get_target_modules(&self) -> Vec<ModuleEnv> {
         let mut target_modules: Vec<ModuleEnv> = vec![];
@@ -787,6 +836,18 @@ impl GlobalEnv {
         self.source_files.source_slice(loc.file_id, loc.span)
     }
 
+    /// Return the text of the source line on which the given location starts, without the line
+    /// terminator.
+    pub fn get_source_line(&self, loc: &Loc) -> Option<&str> {
+        let location = self.get_location(loc)?;
+        let span = self
+            .source_files
+            .line_span(loc.file_id, location.line)
+            .ok()?;
+        let line = self.source_files.source_slice(loc.file_id, span).ok()?;
+        Some(line.trim_end_matches(|c| c == '\n' || c == '\r'))
+    }
+
     /// Return the source file names.
     pub fn get_source_file_names(&self) -> Vec<String> {
         self.file_name_map
@@ -1578,6 +1639,34 @@ impl<'env> ModuleEnv<'env> {
         self.env.source_files.name(file_id)
     }
 
+    /// Returns the Move package this module is declared in, if the environment was built from
+    /// packages.
+    pub fn get_package(&self) -> Option<&'env PackageInfo> {
+        let path = self.get_source_path();
+        self.env.packages.iter().find(|package| {
+            package
+                .source_files
+                .iter()
+                .any(|file| OsStr::new(file) == path)
+        })
+    }
+
+    /// Returns the default value of a boolean pragma set by the package of this module.
+    pub fn get_package_bool_pragma(&self, name: &str) -> Option<bool> {
+        match self.get_package()?.pragmas.get(name) {
+            Some(PackagePragmaValue::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the default value of a numeric pragma set by the package of this module.
+    pub fn get_package_num_pragma(&self, name: &str) -> Option<usize> {
+        match self.get_package()?.pragmas.get(name) {
+            Some(PackagePragmaValue::Number(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
     /// Return the set of language storage ModuleId's that this module's bytecode depends on
     /// (including itself), friend modules are excluded from the return result.
     pub fn get_dependencies(&self) -> Vec<language_storage::ModuleId> {
@@ -2066,6 +2155,7 @@ impl<'env> ModuleEnv<'env> {
                 print_code: true,
                 print_basic_blocks: true,
                 print_locals: true,
+                print_source: false,
             },
         );
         disas
@@ -2375,7 +2465,8 @@ impl<'env> StructEnv<'env> {
     }
 
     /// Returns the value of a boolean pragma for this struct. This first looks up a
-    /// pragma in this struct, then the enclosing module, and finally uses the provided default.
+    /// pragma in this struct, then the enclosing module, then the package of the module, and
+    /// finally uses the provided default.
     /// value
     pub fn is_pragma_true(&self, name: &str, default: impl FnOnce() -> bool) -> bool {
         let env = self.module_env.env;
@@ -2385,6 +2476,9 @@ impl<'env> StructEnv<'env> {
         if let Some(b) = env.is_property_true(&self.module_env.get_spec().properties, name) {
             return b;
         }
+        if let Some(b) = self.module_env.get_package_bool_pragma(name) {
+            return b;
+        }
         default()
     }
 
@@ -2732,7 +2826,8 @@ impl<'env> FunctionEnv<'env> {
     }
 
     /// Returns the value of a boolean pragma for this function. This first looks up a
-    /// pragma in this function, then the enclosing module, and finally uses the provided default.
+    /// pragma in this function, then the enclosing module, then the package of the module, and
+    /// finally uses the provided default.
     /// value
     pub fn is_pragma_true(&self, name: &str, default: impl FnOnce() -> bool) -> bool {
         let env = self.module_env.env;
@@ -2742,6 +2837,9 @@ impl<'env> FunctionEnv<'env> {
         if let Some(b) = env.is_property_true(&self.module_env.get_spec().properties, name) {
             return b;
         }
+        if let Some(b) = self.module_env.get_package_bool_pragma(name) {
+            return b;
+        }
         default()
     }
 
@@ -2754,6 +2852,9 @@ impl<'env> FunctionEnv<'env> {
         if let Some(b) = env.is_property_true(&self.module_env.get_spec().properties, name) {
             return !b;
         }
+        if let Some(b) = self.module_env.get_package_bool_pragma(name) {
+            return !b;
+        }
         false
     }
 
@@ -2765,10 +2866,12 @@ impl<'env> FunctionEnv<'env> {
             || env
                 .get_num_property(&self.module_env.get_spec().properties, name)
                 .is_some()
+            || self.module_env.get_package_num_pragma(name).is_some()
     }
 
     /// Returns the value of a numeric pragma for this function. This first looks up a
-    /// pragma in this function, then the enclosing module, and finally uses the provided default.
+    /// pragma in this function, then the enclosing module, then the package of the module, and
+    /// finally uses the provided default.
     /// value
     pub fn get_num_pragma(&self, name: &str, default: impl FnOnce() -> usize) -> usize {
         let env = self.module_env.env;
@@ -2778,6 +2881,9 @@ impl<'env> FunctionEnv<'env> {
         if let Some(n) = env.get_num_property(&self.module_env.get_spec().properties, name) {
             return n;
         }
+        if let Some(n) = self.module_env.get_package_num_pragma(name) {
+            return n;
+        }
         default()
     }
 
@@ -3141,9 +3247,14 @@ impl<'env> FunctionEnv<'env> {
             // Don't generate verify method for functions from dependencies.
             return false;
         }
+        if self.is_excluded_by_package() {
+            // Functions excluded by their package are not verified, whatever the pragmas say.
+            return false;
+        }
 
         // We look up the `verify` pragma property first in this function, then in
-        // the module, and finally fall back to the value specified by default_scope.
+        // the module, then in the package, and finally fall back to the value specified by
+        // default_scope.
         let default = || match default_scope {
             // By using `is_exposed`, we essentially mark all of Public, Script, Friend to be
             // in the verification scope because they are "exposed" functions in this module.
@@ -3163,9 +3274,19 @@ impl<'env> FunctionEnv<'env> {
         name.eq(&*self.get_simple_name_string()) || name.eq(&*self.get_name_string())
     }
 
+    /// Returns true if this function is excluded from verification by its package.
+    pub fn is_excluded_by_package(&self) -> bool {
+        self.module_env.get_package().map_or(false, |package| {
+            package
+                .excluded_functions
+                .contains(&self.get_full_name_str())
+        })
+    }
+
     /// Determine whether this function is explicitly deactivated for verification.
     pub fn is_explicitly_not_verified(&self, scope: &VerificationScope) -> bool {
-        !matches!(scope, VerificationScope::Only(..)) && self.is_pragma_false(VERIFY_PRAGMA)
+        !matches!(scope, VerificationScope::Only(..))
+            && (self.is_pragma_false(VERIFY_PRAGMA) || self.is_excluded_by_package())
     }
 
     /// Get the functions that call this one
@@ -69,6 +69,11 @@ pub const DISABLE_INVARIANTS_IN_BODY_PRAGMA: &str = "disable_invariants_in_body"
 /// to this function
 pub const DELEGATE_INVARIANTS_TO_CALLER_PRAGMA: &str = "delegate_invariants_to_caller";
 
+/// Pragma indicating whether calls to this function are inlined at the bytecode level before
+/// verification. If true, calls are inlined regardless of the size of the function; if false,
+/// they are never inlined.
+pub const INLINE_PRAGMA: &str = "inline";
+
 /// Checks whether a pragma is valid in a specific spec block.
 pub fn is_pragma_valid_for_block(target: &SpecBlockContext<'_>, pragma: &str) -> bool {
     use crate::builder::module_builder::SpecBlockContext::*;
@@ -102,6 +107,7 @@ pub fn is_pragma_valid_for_block(target: &SpecBlockContext<'_>, pragma: &str) ->
                 | FRIEND_PRAGMA
                 | DISABLE_INVARIANTS_IN_BODY_PRAGMA
                 | DELEGATE_INVARIANTS_TO_CALLER_PRAGMA
+                | INLINE_PRAGMA
         ),
         _ => false,
     }
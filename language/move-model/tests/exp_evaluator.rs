@@ -0,0 +1,197 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_model::{
+    ast::ConditionKind,
+    exp_evaluator::{EvalState, EvalValue, ExpEvaluator, GlobalSnapshot},
+    model::{GlobalEnv, ModuleEnv},
+    run_model_builder,
+};
+use num::{BigInt, BigUint};
+
+const FIXTURE: &str = "tests/exp_evaluator/Evaluator.move";
+
+fn build_env() -> GlobalEnv {
+    let env = run_model_builder(&[FIXTURE.to_string()], &[]).unwrap();
+    assert!(!env.has_errors());
+    env
+}
+
+fn get_module(env: &GlobalEnv) -> ModuleEnv<'_> {
+    env.get_modules()
+        .find(|module_env| module_env.matches_name("Evaluator"))
+        .unwrap()
+}
+
+fn num(n: u64) -> EvalValue {
+    EvalValue::Num(BigInt::from(n))
+}
+
+fn vector(elems: &[u64]) -> EvalValue {
+    EvalValue::Vector(elems.iter().map(|n| num(*n)).collect())
+}
+
+fn call(env: &GlobalEnv, state: EvalState, name: &str, args: Vec<EvalValue>) -> EvalValue {
+    let module_env = get_module(env);
+    let (fun_id, _) = module_env
+        .get_spec_funs_of_name(env.symbol_pool().make(name))
+        .next()
+        .unwrap();
+    ExpEvaluator::new(env, state)
+        .call_spec_fun(module_env.get_id(), *fun_id, vec![], args)
+        .unwrap_or_else(|err| panic!("evaluation of `{}` failed: {}", name, err.message))
+}
+
+/// Evaluates the conditions of `kind` of the spec of the function `name`.
+fn check_conditions(
+    env: &GlobalEnv,
+    evaluator: &ExpEvaluator,
+    name: &str,
+    kind: ConditionKind,
+) -> Vec<bool> {
+    let module_env = get_module(env);
+    let fun_env = module_env
+        .find_function(env.symbol_pool().make(name))
+        .unwrap();
+    fun_env
+        .get_spec()
+        .filter_kind(kind)
+        .map(|cond| evaluator.check_condition(cond).unwrap())
+        .collect()
+}
+
+#[test]
+fn spec_functions() {
+    let env = build_env();
+    let state = EvalState::default;
+    let v = vector(&[1, 2, 3]);
+    assert_eq!(call(&env, state(), "sum", vec![v.clone(), num(3)]), num(6));
+    assert_eq!(
+        call(&env, state(), "is_sorted", vec![v.clone()]),
+        EvalValue::Bool(true)
+    );
+    assert_eq!(
+        call(&env, state(), "is_sorted", vec![vector(&[2, 1])]),
+        EvalValue::Bool(false)
+    );
+    assert_eq!(
+        call(&env, state(), "contains_zero", vec![v]),
+        EvalValue::Bool(false)
+    );
+    assert_eq!(
+        call(&env, state(), "contains_zero", vec![vector(&[4, 0])]),
+        EvalValue::Bool(true)
+    );
+    assert_eq!(call(&env, state(), "min_divisor", vec![num(91)]), num(7));
+}
+
+#[test]
+fn global_memory() {
+    let env = build_env();
+    let module_env = get_module(&env);
+    let counter = module_env
+        .find_struct(env.symbol_pool().make("Counter"))
+        .unwrap();
+    let mem = module_env.get_id().qualified_inst(counter.get_id(), vec![]);
+    let addr = BigUint::from(0x7u8);
+    let mut memory = GlobalSnapshot::default();
+    memory.put_resource(mem, addr.clone(), EvalValue::Struct(vec![num(5)]));
+    let state = EvalState {
+        temps: vec![],
+        memory,
+    };
+
+    let present = EvalValue::Address(addr);
+    let absent = EvalValue::Address(BigUint::from(0x8u8));
+    assert_eq!(
+        call(&env, state.clone(), "counter_value", vec![present]),
+        num(5)
+    );
+    assert_eq!(call(&env, state, "counter_value", vec![absent]), num(0));
+}
+
+#[test]
+fn function_conditions() {
+    let env = build_env();
+    let state = EvalState {
+        temps: vec![num(u64::MAX), num(1)],
+        memory: GlobalSnapshot::default(),
+    };
+    let evaluator = ExpEvaluator::new(&env, state);
+    assert_eq!(
+        check_conditions(&env, &evaluator, "add", ConditionKind::AbortsIf),
+        vec![true]
+    );
+
+    let state = EvalState {
+        temps: vec![num(2), num(3)],
+        memory: GlobalSnapshot::default(),
+    };
+    let evaluator = ExpEvaluator::new(&env, state.clone()).set_results(vec![num(5)]);
+    assert_eq!(
+        check_conditions(&env, &evaluator, "add", ConditionKind::AbortsIf),
+        vec![false]
+    );
+    assert_eq!(
+        check_conditions(&env, &evaluator, "add", ConditionKind::Ensures),
+        vec![true]
+    );
+    let evaluator = ExpEvaluator::new(&env, state).set_results(vec![num(6)]);
+    assert_eq!(
+        check_conditions(&env, &evaluator, "add", ConditionKind::Ensures),
+        vec![false]
+    );
+}
+
+#[test]
+fn old_state() {
+    let env = build_env();
+    let module_env = get_module(&env);
+    let counter = module_env
+        .find_struct(env.symbol_pool().make("Counter"))
+        .unwrap();
+    let mem = module_env.get_id().qualified_inst(counter.get_id(), vec![]);
+    let addr = BigUint::from(0x7u8);
+    let state_with_counter = |value| {
+        let mut memory = GlobalSnapshot::default();
+        memory.put_resource(
+            mem.clone(),
+            addr.clone(),
+            EvalValue::Struct(vec![num(value)]),
+        );
+        EvalState {
+            temps: vec![EvalValue::Address(addr.clone())],
+            memory,
+        }
+    };
+
+    let evaluator =
+        ExpEvaluator::new(&env, state_with_counter(4)).set_pre_state(state_with_counter(3));
+    assert_eq!(
+        check_conditions(&env, &evaluator, "increment", ConditionKind::Ensures),
+        vec![true]
+    );
+    let evaluator =
+        ExpEvaluator::new(&env, state_with_counter(3)).set_pre_state(state_with_counter(3));
+    assert_eq!(
+        check_conditions(&env, &evaluator, "increment", ConditionKind::Ensures),
+        vec![false]
+    );
+}
+
+#[test]
+fn unbounded_quantifier() {
+    let env = build_env();
+    let module_env = get_module(&env);
+    let (fun_id, _) = module_env
+        .get_spec_funs_of_name(env.symbol_pool().make("unbounded"))
+        .next()
+        .unwrap();
+    let result = ExpEvaluator::new(&env, EvalState::default()).call_spec_fun(
+        module_env.get_id(),
+        *fun_id,
+        vec![],
+        vec![],
+    );
+    assert!(result.is_err());
+}
@@ -0,0 +1,46 @@
+module 0x42::Evaluator {
+    struct Counter has key { value: u64 }
+
+    fun add(x: u64, y: u64): u64 {
+        x + y
+    }
+    spec add {
+        aborts_if x + y > MAX_U64;
+        ensures result == x + y;
+    }
+
+    fun increment(addr: address) acquires Counter {
+        let counter = borrow_global_mut<Counter>(addr);
+        counter.value = counter.value + 1;
+    }
+    spec increment {
+        aborts_if !exists<Counter>(addr);
+        ensures global<Counter>(addr).value == old(global<Counter>(addr).value) + 1;
+    }
+
+    spec module {
+        fun sum(v: vector<u64>, n: num): num {
+            if (n == 0) 0 else v[n - 1] + sum(v, n - 1)
+        }
+
+        fun is_sorted(v: vector<u64>): bool {
+            forall i in 0..len(v), j in 0..len(v) where i < j: v[i] <= v[j]
+        }
+
+        fun contains_zero(v: vector<u64>): bool {
+            exists x in v: x == 0
+        }
+
+        fun min_divisor(n: num): num {
+            choose min d in 2..n + 1 where n % d == 0
+        }
+
+        fun counter_value(addr: address): u64 {
+            if (exists<Counter>(addr)) global<Counter>(addr).value else 0
+        }
+
+        fun unbounded(): bool {
+            forall x: u64: x >= 0
+        }
+    }
+}
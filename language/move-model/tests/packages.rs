@@ -0,0 +1,129 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_core_types::account_address::AccountAddress;
+use move_model::{
+    model::{PackageInfo, PackagePragmaValue, VerificationScope},
+    options::ModelBuilderOptions,
+    run_model_builder_for_packages,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+fn address(literal: &str) -> AccountAddress {
+    AccountAddress::from_hex_literal(literal).unwrap()
+}
+
+/// Returns the fixture package `name`, with the given named addresses and dependencies.
+fn package(name: &str, named_addresses: &[(&str, &str)], dependencies: &[&str]) -> PackageInfo {
+    let root_path = PathBuf::from("tests/packages").join(name);
+    let source_file = match name {
+        "A" => "M.move",
+        _ => "N.move",
+    };
+    PackageInfo {
+        name: name.to_string(),
+        version: (0, 1, 0),
+        source_files: vec![root_path
+            .join("sources")
+            .join(source_file)
+            .to_string_lossy()
+            .to_string()],
+        root_path,
+        named_addresses: named_addresses
+            .iter()
+            .map(|(name, literal)| (name.to_string(), address(literal)))
+            .collect::<BTreeMap<_, _>>(),
+        dependencies: dependencies.iter().map(|dep| dep.to_string()).collect(),
+        pragmas: BTreeMap::new(),
+        excluded_functions: BTreeSet::new(),
+    }
+}
+
+#[test]
+fn test_named_addresses_of_packages() {
+    let a = package("A", &[("A", "0x2"), ("B", "0x3")], &["B"]);
+    let b = package("B", &[("B", "0x3")], &[]);
+    let env = run_model_builder_for_packages(
+        vec![a.clone()],
+        vec![b.clone()],
+        ModelBuilderOptions::default(),
+    )
+    .unwrap();
+    assert!(!env.has_errors());
+
+    // The packages are recorded in the order of the targets, then the dependencies.
+    assert_eq!(env.get_packages(), &[a.clone(), b.clone()]);
+    assert_eq!(env.get_package("B"), Some(&b));
+    assert_eq!(env.get_package("C"), None);
+
+    // Modules are declared at the named addresses of their packages.
+    let modules = env
+        .get_modules()
+        .map(|module_env| {
+            (
+                module_env.get_full_name_str(),
+                module_env.is_target(),
+                module_env.get_package().map(|package| package.name.clone()),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert!(modules.contains(&("0x2::M".to_string(), true, Some("A".to_string()))));
+    assert!(modules.contains(&("0x3::N".to_string(), false, Some("B".to_string()))));
+}
+
+#[test]
+fn test_package_prover_settings() {
+    let mut a = package("A", &[("A", "0x2"), ("B", "0x3")], &["B"]);
+    a.pragmas = vec![
+        ("timeout".to_string(), PackagePragmaValue::Number(40)),
+        (
+            "aborts_if_is_strict".to_string(),
+            PackagePragmaValue::Bool(true),
+        ),
+    ]
+    .into_iter()
+    .collect();
+    a.excluded_functions = vec!["M::f".to_string()].into_iter().collect();
+    let b = package("B", &[("B", "0x3")], &[]);
+    let env =
+        run_model_builder_for_packages(vec![a], vec![b], ModelBuilderOptions::default()).unwrap();
+    assert!(!env.has_errors());
+    let find_function = |module_name: &str, name: &str| {
+        env.get_modules()
+            .find(|module_env| module_env.matches_name(module_name))
+            .unwrap()
+            .into_functions()
+            .find(|func_env| func_env.matches_name(name))
+            .unwrap()
+    };
+
+    // The pragmas of a package are the defaults of its functions, which take precedence.
+    let f = find_function("M", "f");
+    assert!(f.is_pragma_true("aborts_if_is_strict", || false));
+    assert_eq!(f.get_num_pragma("timeout", || 10), 20);
+    let g = find_function("N", "g");
+    assert!(!g.is_pragma_true("aborts_if_is_strict", || false));
+    assert_eq!(g.get_num_pragma("timeout", || 10), 10);
+
+    // Excluded functions are not verified, unless verified explicitly.
+    assert!(f.is_excluded_by_package());
+    assert!(!f.should_verify(&VerificationScope::All));
+    assert!(f.is_explicitly_not_verified(&VerificationScope::All));
+    assert!(!f.is_explicitly_not_verified(&VerificationScope::Only("M::f".to_string())));
+    assert!(!g.is_excluded_by_package());
+}
+
+#[test]
+fn test_conflicting_named_addresses() {
+    let a = package("A", &[("A", "0x2"), ("B", "0x3")], &["B"]);
+    let b = package("B", &[("B", "0x4")], &[]);
+    let error = run_model_builder_for_packages(vec![a], vec![b], ModelBuilderOptions::default())
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "named address `B` is assigned `0x3` by package `A` but `0x4` by package `B`"
+    );
+}
@@ -0,0 +1,10 @@
+module A::M {
+    use B::N;
+
+    public fun f(): u64 {
+        N::g()
+    }
+    spec f {
+        pragma timeout = 20;
+    }
+}
@@ -0,0 +1,5 @@
+module B::N {
+    public fun g(): u64 {
+        1
+    }
+}
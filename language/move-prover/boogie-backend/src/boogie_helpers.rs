@@ -4,7 +4,7 @@
 //! Helpers for emitting Boogie code.
 
 use crate::options::BoogieOptions;
-use bytecode::function_target::FunctionTarget;
+use bytecode::{function_target::FunctionTarget, function_target_pipeline::VerificationFlavor};
 use itertools::Itertools;
 use move_model::{
     ast::{MemoryLabel, TempIndex},
@@ -72,6 +72,14 @@ pub fn boogie_function_name(fun_env: &FunctionEnv<'_>, inst: &[Type]) -> String
     )
 }
 
+/// Return the suffix of the boogie procedure verifying a function in the given flavor.
+pub fn boogie_verification_suffix(flavor: &VerificationFlavor) -> String {
+    match flavor {
+        VerificationFlavor::Regular => "$verify".to_string(),
+        _ => format!("$verify_{}", flavor),
+    }
+}
+
 /// Return boogie name of given spec var.
 pub fn boogie_spec_var_name(
     module_env: &ModuleEnv<'_>,
@@ -3,7 +3,13 @@
 
 //! Wrapper around the boogie program. Allows to call boogie and analyze the output.
 
-use std::{collections::BTreeMap, fs, num::ParseIntError, option::Option::None};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    num::ParseIntError,
+    option::Option::None,
+    process::Output,
+};
 
 use anyhow::anyhow;
 use codespan::{ByteIndex, ColumnIndex, LineIndex, Location, Span};
@@ -15,21 +21,26 @@ use once_cell::sync::Lazy;
 use pretty::RcDoc;
 use regex::Regex;
 
-use bytecode::function_target_pipeline::{FunctionTargetsHolder, FunctionVariant};
+use bytecode::{
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant},
+    usage_analysis::UsageState,
+};
 use move_binary_format::file_format::FunctionDefinitionIndex;
 use move_model::{
     ast::TempIndex,
     code_writer::CodeWriter,
-    model::{FunId, GlobalEnv, Loc, ModuleId, NodeId, QualifiedId, StructId},
+    model::{FunId, GlobalEnv, Loc, ModuleId, NodeId, QualifiedId, QualifiedInstId, StructId},
     ty::{PrimitiveType, Type},
 };
 
 // DEBUG
 // use backtrace::Backtrace;
 use crate::{
-    boogie_helpers::boogie_struct_name,
+    boogie_helpers::{boogie_resource_memory_name, boogie_struct_name},
     options::{BoogieOptions, VectorTheory},
     prover_task_runner::{ProverTaskRunner, RunBoogieWithSeeds},
+    vc_profiler::{strip_trace_output, VcProfile},
+    verification_units,
 };
 
 /// A type alias for the way how we use crate `pretty`'s document type. `pretty` is a
@@ -54,6 +65,9 @@ pub struct BoogieOutput {
 
     /// Full output as a string.
     pub all_output: String,
+
+    /// The solver time per verification condition, if profiling is enabled.
+    pub profile: Option<VcProfile>,
 }
 
 /// Kind of boogie error.
@@ -140,6 +154,7 @@ impl<'env> BoogieWrapper<'env> {
                     return Ok(BoogieOutput {
                         errors: vec![err],
                         all_output: "".to_string(),
+                        profile: None,
                     });
                 } else {
                     panic!("cannot execute boogie `{:?}`: {}", args, err)
@@ -150,7 +165,11 @@ impl<'env> BoogieWrapper<'env> {
         if self.options.num_instances > 1 {
             debug!("Boogie instance with seed {} finished first", seed);
         }
+        self.analyze_output(&output)
+    }
 
+    /// Analyzes the output of a boogie run.
+    fn analyze_output(&self, output: &Output) -> anyhow::Result<BoogieOutput> {
         debug!("analyzing boogie output");
         let out = String::from_utf8_lossy(&output.stdout).to_string();
         let err = String::from_utf8_lossy(&output.stderr).to_string();
@@ -200,18 +219,41 @@ impl<'env> BoogieWrapper<'env> {
                 out
             ));
         }
-        let mut errors = self.extract_verification_errors(&out);
-        errors.extend(self.extract_inconclusive_errors(&out));
-        errors.extend(self.extract_inconsistency_errors(&out));
+        let (diag_out, profile) = if self.options.profile_vcs {
+            (
+                strip_trace_output(&out),
+                Some(VcProfile::from_trace(self.env, self.targets, &out)),
+            )
+        } else {
+            (out.clone(), None)
+        };
+        let mut errors = self.extract_verification_errors(&diag_out);
+        errors.extend(self.extract_inconclusive_errors(&diag_out));
+        errors.extend(self.extract_inconsistency_errors(&diag_out));
         Ok(BoogieOutput {
             errors,
             all_output: out,
+            profile,
         })
     }
 
-    /// Calls boogie and analyzes output.
-    pub fn call_boogie_and_verify_output(&self, boogie_file: &str) -> anyhow::Result<()> {
-        let BoogieOutput { errors, all_output } = self.call_boogie(boogie_file)?;
+    /// Calls boogie and analyzes output. Returns the functions for which errors were reported,
+    /// or None if some error could not be attributed to a function.
+    pub fn call_boogie_and_verify_output(
+        &self,
+        boogie_file: &str,
+    ) -> anyhow::Result<Option<BTreeSet<QualifiedId<FunId>>>> {
+        if self.options.modular_verification {
+            if !self.options.profile_vcs {
+                return self.call_boogie_modular_and_verify_output(boogie_file);
+            }
+            debug!("profiling verification conditions in a single boogie run");
+        }
+        let BoogieOutput {
+            errors,
+            all_output,
+            profile,
+        } = self.call_boogie(boogie_file)?;
         let boogie_log_file = self.options.get_boogie_log_file(boogie_file);
         let log_file_existed = std::path::Path::new(&boogie_log_file).exists();
         debug!("writing boogie log to {}", boogie_log_file);
@@ -220,12 +262,113 @@ impl<'env> BoogieWrapper<'env> {
         for error in &errors {
             self.add_error(error);
         }
+        let failed = errors
+            .iter()
+            .map(|error| self.get_failed_function(error))
+            .collect::<Option<BTreeSet<_>>>();
+
+        if let Some(profile) = &profile {
+            let profile_file = self.options.get_vc_profile_file(boogie_file);
+            info!("writing verification condition profile to {}", profile_file);
+            fs::write(&profile_file, profile.report(self.env))?;
+        }
 
         if !log_file_existed && !self.options.keep_artifacts {
             std::fs::remove_file(boogie_log_file).unwrap_or_default();
         }
 
-        Ok(())
+        Ok(failed)
+    }
+
+    /// Verifies each verified function variant in a boogie run of its own, dispatching the runs
+    /// to a pool of `proc_cores` workers (see `verification_units`). The result of each unit is
+    /// reported as soon as its run finishes. Returns the functions for which errors were
+    /// reported.
+    fn call_boogie_modular_and_verify_output(
+        &self,
+        boogie_file: &str,
+    ) -> anyhow::Result<Option<BTreeSet<QualifiedId<FunId>>>> {
+        let units = verification_units::collect_units(self.env, self.targets, self.options);
+        // Units run side by side, so each run uses a single core.
+        let file_command = BoogieOptions {
+            proc_cores: 1,
+            ..self.options.clone()
+        }
+        .get_boogie_command(boogie_file)?;
+        let commands = units
+            .iter()
+            .map(|unit| (unit.boogie_command(&file_command), unit.timeout))
+            .collect();
+        // With stable test output, errors must be reported in a deterministic order.
+        let num_workers = if self.options.stable_test_output {
+            1
+        } else {
+            self.options.proc_cores
+        };
+        info!(
+            "running solver on {} verification units with {} workers",
+            units.len(),
+            num_workers
+        );
+        let results = verification_units::dispatch_units(commands, num_workers);
+
+        let mut failed = BTreeSet::new();
+        let mut all_output = String::new();
+        for (count, (unit_idx, run)) in results.iter().enumerate() {
+            let unit = &units[unit_idx];
+            let fun_env = self.env.get_function(unit.fun_id);
+            let progress = format!(
+                "[{}/{}] `{}` ({})",
+                count + 1,
+                units.len(),
+                fun_env.get_full_name_str(),
+                unit.variant
+            );
+            let output = match run.output {
+                Some(output) => output.map_err(|err| {
+                    anyhow!("cannot execute boogie on `{}`: {}", unit.procedure, err)
+                })?,
+                None => {
+                    info!("{} timed out", progress);
+                    self.env.error(
+                        &fun_env.get_loc(),
+                        &format!(
+                            "Boogie execution exceeded timeout of {}s",
+                            unit.timeout.as_secs()
+                        ),
+                    );
+                    failed.insert(unit.fun_id);
+                    continue;
+                }
+            };
+            let BoogieOutput {
+                errors,
+                all_output: unit_output,
+                ..
+            } = self.analyze_output(&output)?;
+            all_output.push_str(&unit_output);
+            for error in &errors {
+                self.add_error(error);
+            }
+            if errors.is_empty() {
+                info!("{} verified in {:.3}s", progress, run.elapsed.as_secs_f64());
+            } else {
+                info!(
+                    "{} has {} error(s) after {:.3}s",
+                    progress,
+                    errors.len(),
+                    run.elapsed.as_secs_f64()
+                );
+                failed.insert(unit.fun_id);
+            }
+        }
+
+        let boogie_log_file = self.options.get_boogie_log_file(boogie_file);
+        if self.options.keep_artifacts || std::path::Path::new(&boogie_log_file).exists() {
+            debug!("writing boogie log to {}", boogie_log_file);
+            fs::write(&boogie_log_file, &all_output)?;
+        }
+        Ok(Some(failed))
     }
 
     /// Helper to add a boogie error as a codespan Diagnostic.
@@ -343,11 +486,82 @@ impl<'env> BoogieWrapper<'env> {
                 )
                 .with_message(&format!("abort happened here{}", code))]);
             }
+            if let (Some(model), Some(fun_id)) = (&error.model, self.get_failed_function(error)) {
+                display.extend(self.make_memory_trace(fun_id, model));
+            }
             diag = diag.with_notes(display);
         }
         self.env.add_diag(diag);
     }
 
+    /// Determines the function whose verification failed from the execution trace, or
+    /// otherwise from the location of the error.
+    fn get_failed_function(&self, error: &BoogieError) -> Option<QualifiedId<FunId>> {
+        error
+            .execution_trace
+            .iter()
+            .find_map(|entry| match entry {
+                TraceEntry::Temporary(fun, ..)
+                | TraceEntry::Result(fun, ..)
+                | TraceEntry::Abort(fun, ..) => Some(*fun),
+                _ => None,
+            })
+            .or_else(|| {
+                self.env
+                    .get_enclosing_function(&error.loc)
+                    .map(|fun_env| fun_env.get_qualified_id())
+            })
+    }
+
+    /// Renders the global memory used by the given function, as found in the model at the
+    /// point of failure. The memory is determined by the usage summary of the function, and
+    /// includes the ghost memory backing spec vars.
+    fn make_memory_trace(&self, fun_id: QualifiedId<FunId>, model: &Model) -> Vec<String> {
+        if self.options.stable_test_output {
+            // Values would be redacted, and which memory is present in the model is not
+            // stable across solver versions.
+            return vec![];
+        }
+        let fun_env = self.env.get_function(fun_id);
+        let target = self
+            .targets
+            .get_target(&fun_env, &FunctionVariant::Baseline);
+        let usage = match target.get_annotations().get::<UsageState>() {
+            Some(usage) => usage,
+            None => return vec![],
+        };
+        let mut display = vec![];
+        for mem in &usage.accessed.all {
+            let name = boogie_resource_memory_name(self.env, mem, &None);
+            let value = match model.get_latest_incarnation(&name) {
+                Some(value) => value,
+                None => continue,
+            };
+            let struct_env = self.env.get_struct_qid(mem.to_qualified_id());
+            if let Some(spec_var) = struct_env.get_ghost_memory_spec_var() {
+                let spec_var_name = self
+                    .env
+                    .get_module(spec_var.module_id)
+                    .get_spec_var(spec_var.id)
+                    .name;
+                if let Some(doc) = value.pretty_ghost_memory(self, model, mem) {
+                    display.extend(self.make_trace_entry(
+                        format!("global {}", spec_var_name.display(self.env.symbol_pool())),
+                        doc,
+                    ));
+                }
+            } else if let Some(doc) = value.pretty_memory(self, model, mem) {
+                display.extend(
+                    self.make_trace_entry(format!("global<{}>", self.env.display(mem)), doc),
+                );
+            }
+        }
+        if !display.is_empty() {
+            display.insert(0, "    at failure:".to_string());
+        }
+        display
+    }
+
     fn get_abbreviated_source(&self, node_id: NodeId) -> String {
         let loc = self.env.get_node_loc(node_id);
         let res = if let Ok(src) = self.env.get_source(&loc) {
@@ -695,6 +909,26 @@ impl Model {
         self.vars.is_empty()
     }
 
+    /// Returns the value of the latest incarnation of the given Boogie variable. The model
+    /// contains the initial value under the plain name, and the values of the incarnations
+    /// assigned along the failing path under `<name>@<n>`.
+    fn get_latest_incarnation(&self, name: &str) -> Option<&ModelValue> {
+        let prefix = format!("{}@", name);
+        self.vars
+            .iter()
+            .filter_map(|(key, value)| {
+                let key = key.extract_literal()?.trim_matches('|');
+                if key == name {
+                    Some((0, value))
+                } else {
+                    let n = key.strip_prefix(&prefix)?.parse::<usize>().ok()?;
+                    Some((usize::saturating_add(n, 1), value))
+                }
+            })
+            .max_by_key(|(n, _)| *n)
+            .map(|(_, value)| value)
+    }
+
     /// Parses the given string into a model. The string is expected to end with MODULE_END_MARKER.
     fn parse(&mut self, _wrapper: &BoogieWrapper<'_>, input: &str) -> Result<(), ModelParseError> {
         let mut model_parser = ModelParser { input, at: 0 };
@@ -733,6 +967,15 @@ pub struct ModelValueVector {
     default: ModelValue,
 }
 
+/// Represents a sparse representation of a model value memory.
+#[derive(Debug)]
+pub struct ModelValueMemory {
+    /// The resources which exist, by address.
+    resources: BTreeMap<BigInt, ModelValue>,
+    /// Whether resources exist at all addresses not in `resources`.
+    default_exists: bool,
+}
+
 impl ModelValue {
     /// Makes a literal from a str.
     fn literal(s: &str) -> ModelValue {
@@ -819,6 +1062,70 @@ impl ModelValue {
         }
     }
 
+    /// Extracts a memory from its representation.
+    ///
+    /// Memory is represented by the datatype `$Memory(domain, contents)`, where both components
+    /// are arrays indexed by address. As for vectors, the arrays are found via indirections in
+    /// the model. This has e.g. the form
+    /// ```model
+    ///   $1_M_R_$memory@1 -> (|$Memory'$1_M_R'| |T@[Int]Bool!val!0| |T@[Int]$1_M_R!val!0|)
+    ///   Select__T@[Int]Bool_ -> {
+    ///      |T@[Int]Bool!val!0| 18 -> true
+    ///      else -> false
+    ///    }
+    ///   Select__T@[Int]$1_M_R_ -> {
+    ///      |T@[Int]$1_M_R!val!0| 18 -> ($1_M_R 2)
+    ///      else -> ($1_M_R 0)
+    ///    }
+    /// ```
+    fn extract_memory(&self, model: &Model) -> Option<ModelValueMemory> {
+        let args = self
+            .extract_list_ctor_prefix("$Memory")
+            .or_else(|| self.extract_list_ctor_prefix("|$Memory"))?;
+        if args.len() != 2 {
+            return None;
+        }
+        let select = |map_key: &ModelValue| {
+            let table = model
+                .vars
+                .get(&ModelValue::literal(
+                    &self.deduct_table_name(map_key.extract_literal()?)?,
+                ))?
+                .extract_map()?;
+            let mut values = BTreeMap::new();
+            let mut default = None;
+            for (key, value) in table {
+                if let ModelValue::List(elems) = key {
+                    if elems.len() == 2 && &elems[0] == map_key {
+                        if let Some(addr) = elems[1]
+                            .extract_literal()
+                            .and_then(|s| BigInt::parse_bytes(s.as_bytes(), 10))
+                        {
+                            values.insert(addr, value.clone());
+                        }
+                    }
+                } else if key == &ModelValue::literal("else") {
+                    default = Some(value.clone());
+                }
+            }
+            Some((values, default))
+        };
+        let (domain, domain_default) = select(&args[0])?;
+        let (contents, contents_default) = select(&args[1]).unwrap_or_default();
+        let resources = domain
+            .into_iter()
+            .filter(|(_, exists)| exists == &ModelValue::literal("true"))
+            .filter_map(|(addr, _)| {
+                let value = contents.get(&addr).or_else(|| contents_default.as_ref())?;
+                Some((addr, value.clone()))
+            })
+            .collect();
+        Some(ModelValueMemory {
+            resources,
+            default_exists: domain_default == Some(ModelValue::literal("true")),
+        })
+    }
+
     fn deduct_table_name(&self, map_key: &str) -> Option<String> {
         // The generic representation of map keys is `|T@[Int]<X>!val!0` where `<X>` is the
         // vector element type.
@@ -984,6 +1291,52 @@ impl ModelValue {
         }
     }
 
+    /// Pretty prints a memory, as the map from addresses to the resources stored there.
+    pub fn pretty_memory(
+        &self,
+        wrapper: &BoogieWrapper,
+        model: &Model,
+        mem: &QualifiedInstId<StructId>,
+    ) -> Option<PrettyDoc> {
+        let memory = self.extract_memory(model)?;
+        let ty = mem.to_type();
+        let mut entries = memory
+            .resources
+            .iter()
+            .map(|(addr, value)| {
+                PrettyDoc::text(format!("0x{}: ", addr.to_str_radix(16)))
+                    .append(value.pretty_or_raw(wrapper, model, &ty))
+            })
+            .collect_vec();
+        if memory.default_exists {
+            entries.push(PrettyDoc::text("default: <exists>"));
+        }
+        Some(PrettyDoc::text("memory").append(Self::pretty_vec_or_struct_body(entries)))
+    }
+
+    /// Pretty prints the value of a spec var from the ghost memory backing it. Ghost memory
+    /// stores a struct with the value as its single field at address zero.
+    pub fn pretty_ghost_memory(
+        &self,
+        wrapper: &BoogieWrapper,
+        model: &Model,
+        mem: &QualifiedInstId<StructId>,
+    ) -> Option<PrettyDoc> {
+        let memory = self.extract_memory(model)?;
+        let resource = memory.resources.get(&BigInt::from(0))?;
+        let struct_env = wrapper.env.get_struct_qid(mem.to_qualified_id());
+        let struct_name = &boogie_struct_name(&struct_env, &mem.inst);
+        let fields = resource
+            .extract_list(struct_name)
+            .or_else(|| resource.extract_list(&format!("|{}|", struct_name)))?;
+        let ty = struct_env
+            .get_fields()
+            .next()?
+            .get_type()
+            .instantiate(&mem.inst);
+        Some(fields.get(0)?.pretty_or_raw(wrapper, model, &ty))
+    }
+
     /// Pretty prints the body of a struct or vector, enclosed in braces.
     pub fn pretty_vec_or_struct_body(entries: Vec<PrettyDoc>) -> PrettyDoc {
         PrettyDoc::text("{")
@@ -1244,3 +1597,100 @@ fn index_range_check(max: usize) -> impl FnOnce(usize) -> Result<usize, ModelPar
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses a model in the format produced by the solver.
+    fn parse_model(input: &str) -> Model {
+        let input = format!("{}\n{}", input, MODEL_END_MARKER);
+        let mut model_parser = ModelParser {
+            input: &input,
+            at: 0,
+        };
+        match model_parser.parse_map().unwrap() {
+            ModelValue::Map(vars) => Model {
+                vars,
+                vector_theory: VectorTheory::BoogieArray,
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_latest_incarnation() {
+        let model = parse_model(
+            "$1_M_R_$memory -> 0\n\
+             $1_M_R_$memory@0 -> 1\n\
+             |$1_M_R_$memory@10| -> 11\n\
+             $1_M_R_$memory@2 -> 3\n\
+             $1_M_S_$memory@20 -> 21\n",
+        );
+        assert_eq!(
+            model.get_latest_incarnation("$1_M_R_$memory"),
+            Some(&ModelValue::literal("11"))
+        );
+        assert_eq!(
+            model.get_latest_incarnation("$1_M_S_$memory"),
+            Some(&ModelValue::literal("21"))
+        );
+        assert_eq!(model.get_latest_incarnation("$1_M_T_$memory"), None);
+    }
+
+    #[test]
+    fn test_extract_memory() {
+        let model = parse_model(
+            "$1_M_R_$memory@1 -> (|$Memory'$1_M_R'| |T@[Int]Bool!val!0| |T@[Int]$1_M_R!val!0|)\n\
+             Select__T@[Int]Bool_ -> {\n\
+               |T@[Int]Bool!val!0| 18 -> true\n\
+               |T@[Int]Bool!val!0| 20 -> false\n\
+               |T@[Int]Bool!val!0| 22 -> true\n\
+               else -> false\n\
+             }\n\
+             Select__T@[Int]$1_M_R_ -> {\n\
+               |T@[Int]$1_M_R!val!0| 18 -> ($1_M_R 2)\n\
+               else -> ($1_M_R 0)\n\
+             }\n",
+        );
+        let memory = model
+            .get_latest_incarnation("$1_M_R_$memory")
+            .and_then(|value| value.extract_memory(&model))
+            .unwrap();
+        assert!(!memory.default_exists);
+        assert_eq!(
+            memory.resources.into_iter().collect_vec(),
+            vec![
+                (
+                    BigInt::from(18),
+                    ModelValue::List(vec![
+                        ModelValue::literal("$1_M_R"),
+                        ModelValue::literal("2")
+                    ])
+                ),
+                (
+                    BigInt::from(22),
+                    ModelValue::List(vec![
+                        ModelValue::literal("$1_M_R"),
+                        ModelValue::literal("0")
+                    ])
+                ),
+            ]
+        );
+
+        // Resources exist everywhere unless the domain says otherwise.
+        let model = parse_model(
+            "$1_M_R_$memory -> ($Memory_1 |T@[Int]Bool!val!1| |T@[Int]$1_M_R!val!1|)\n\
+             Select__T@[Int]Bool_ -> {\n\
+               |T@[Int]Bool!val!1| 18 -> false\n\
+               else -> true\n\
+             }\n",
+        );
+        let memory = model
+            .get_latest_incarnation("$1_M_R_$memory")
+            .and_then(|value| value.extract_memory(&model))
+            .unwrap();
+        assert!(memory.default_exists);
+        assert!(memory.resources.is_empty());
+    }
+}
@@ -30,10 +30,11 @@ use crate::{
         boogie_function_name, boogie_make_vec_from_strings, boogie_modifies_memory_name,
         boogie_resource_memory_name, boogie_struct_name, boogie_temp, boogie_type,
         boogie_type_param, boogie_type_suffix, boogie_type_suffix_for_struct,
-        boogie_well_formed_check, boogie_well_formed_expr,
+        boogie_verification_suffix, boogie_well_formed_check, boogie_well_formed_expr,
     },
     options::BoogieOptions,
     spec_translator::SpecTranslator,
+    vc_profiler::{VcEvent, VcSiteTable},
 };
 use bytecode::{
     function_target_pipeline::FunctionVariant,
@@ -90,6 +91,10 @@ impl<'env> BoogieTranslator<'env> {
         let mono_info = mono_analysis::get_info(self.env);
         let empty = &BTreeSet::new();
 
+        if self.options.profile_vcs {
+            env.set_extension(VcSiteTable::default());
+        }
+
         emitln!(
             writer,
             "\n\n//==================================\n// Begin Translation\n"
@@ -391,11 +396,44 @@ impl<'env> FunctionTranslator<'env> {
             fun_target.data.variant,
             fun_target.get_loc().display(env)
         );
+        if self.parent.options.profile_vcs {
+            env.update_extension(|table: &mut VcSiteTable| {
+                table.add_procedure(
+                    self.procedure_name(),
+                    fun_target.func_env.get_qualified_id(),
+                    fun_target.data.variant.clone(),
+                )
+            });
+        }
         self.generate_function_sig();
         self.generate_function_body();
         emitln!(self.parent.writer);
     }
 
+    /// Returns the name of the Boogie procedure of the function.
+    fn procedure_name(&self) -> String {
+        let suffix = match &self.fun_target.data.variant {
+            FunctionVariant::Baseline => "".to_string(),
+            FunctionVariant::Verification(flavor) => boogie_verification_suffix(flavor),
+        };
+        format!(
+            "{}{}",
+            boogie_function_name(self.fun_target.func_env, self.type_inst),
+            suffix
+        )
+    }
+
+    /// Records an element contributing verification conditions to the procedure of the
+    /// function, if verification conditions are profiled.
+    fn record_vc_event(&self, event: VcEvent) {
+        if self.parent.options.profile_vcs {
+            let name = self.procedure_name();
+            self.fun_target
+                .global_env()
+                .update_extension(|table: &mut VcSiteTable| table.add_event(&name, event));
+        }
+    }
+
     /// Return a string for a boogie procedure header. Use inline attribute and name
     /// suffix as indicated by `entry_point`.
     fn generate_function_sig(&self) {
@@ -424,20 +462,13 @@ impl<'env> FunctionTranslator<'env> {
                     attribs.push(format!("{{:random_seed {}}} ", seed));
                 };
 
-                let suffix = match flavor {
-                    VerificationFlavor::Regular => "$verify".to_string(),
-                    VerificationFlavor::Instantiated(_) => {
-                        format!("$verify_{}", flavor)
-                    }
-                    VerificationFlavor::Inconsistency(_) => {
-                        attribs.push(format!(
-                            "{{:msg_if_verifies \"inconsistency_detected{}\"}} ",
-                            self.loc_str(&fun_target.get_loc())
-                        ));
-                        format!("$verify_{}", flavor)
-                    }
-                };
-                (suffix, attribs.join(""))
+                if let VerificationFlavor::Inconsistency(_) = flavor {
+                    attribs.push(format!(
+                        "{{:msg_if_verifies \"inconsistency_detected{}\"}} ",
+                        self.loc_str(&fun_target.get_loc())
+                    ));
+                }
+                (boogie_verification_suffix(flavor), attribs.join(""))
             }
         };
         writer.set_location(&fun_target.get_loc());
@@ -734,6 +765,7 @@ impl<'env> FunctionTranslator<'env> {
             }
             Prop(id, kind, exp) => match kind {
                 PropKind::Assert => {
+                    self.record_vc_event(VcEvent::Assert(*id));
                     emit!(writer, "assert ");
                     let info = fun_target
                         .get_vc_info(*id)
@@ -914,6 +946,10 @@ impl<'env> FunctionTranslator<'env> {
                         let inst = &self.inst_slice(inst);
                         let callee_env = env.get_module(*mid).into_function(*fid);
 
+                        self.record_vc_event(VcEvent::Inline(boogie_function_name(
+                            &callee_env,
+                            inst,
+                        )));
                         let args_str = srcs.iter().cloned().map(str_local).join(", ");
                         let dest_str = dests
                             .iter()
@@ -1282,6 +1318,8 @@ impl<'env> FunctionTranslator<'env> {
                         );
                     }
                     Shl => {
+                        // The inlined `$Shl` asserts that the shift is defined.
+                        self.record_vc_event(VcEvent::Assert(attr_id));
                         let dest = dests[0];
                         let op1 = srcs[0];
                         let op2 = srcs[1];
@@ -1294,6 +1332,7 @@ impl<'env> FunctionTranslator<'env> {
                         );
                     }
                     Shr => {
+                        self.record_vc_event(VcEvent::Assert(attr_id));
                         let dest = dests[0];
                         let op1 = srcs[0];
                         let op2 = srcs[1];
@@ -1393,6 +1432,7 @@ impl<'env> FunctionTranslator<'env> {
                         );
                     }
                     BitOr | BitAnd | Xor => {
+                        self.record_vc_event(VcEvent::Assert(attr_id));
                         emitln!(
                             writer,
                             "// bit operation not supported: {:?}\nassert false;",
@@ -43,7 +43,11 @@ pub mod boogie_wrapper;
 pub mod bytecode_translator;
 pub mod options;
 mod prover_task_runner;
+pub mod smtlib_backend;
+pub mod solver_pool;
 mod spec_translator;
+pub mod vc_profiler;
+pub mod verification_units;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 struct TypeInfo {
@@ -104,6 +104,24 @@ pub struct BoogieOptions {
     pub vector_theory: VectorTheory,
     /// Whether to generate a z3 trace file and where to put it.
     pub z3_trace_file: Option<String>,
+    /// Whether to measure the solver time of each verification condition and report the
+    /// hot spots.
+    pub profile_vcs: bool,
+    /// Whether to verify functions in the subset supported by the SMT-LIB backend by handing
+    /// SMT-LIB directly to the solvers, instead of going through Boogie.
+    pub smtlib_direct: bool,
+    /// Path to the cvc5 executable, used by the SMT-LIB backend.
+    pub cvc5_exe: String,
+    /// Number of warm processes per solver to which the SMT-LIB backend streams verification
+    /// units. Zero to start new solver processes for each unit.
+    pub solver_pool_size: usize,
+    /// Memory limit for pooled solver processes in megabytes; a process exceeding it is
+    /// restarted. Zero for no limit.
+    pub solver_memory_limit_mb: usize,
+    /// Whether to verify each function variant in a Boogie run of its own, running `proc_cores`
+    /// runs in parallel, instead of verifying all of them in a single run. The hard timeout then
+    /// applies to each run.
+    pub modular_verification: bool,
 }
 
 impl Default for BoogieOptions {
@@ -138,6 +156,12 @@ impl Default for BoogieOptions {
             hard_timeout_secs: 0,
             vector_theory: VectorTheory::BoogieArray,
             z3_trace_file: None,
+            profile_vcs: false,
+            smtlib_direct: false,
+            cvc5_exe: read_env_var("CVC5_EXE"),
+            solver_pool_size: 0,
+            solver_memory_limit_mb: 0,
+            modular_verification: false,
         }
     }
 }
@@ -193,9 +217,10 @@ impl BoogieOptions {
         }
         add(&[&format!(
             "-vcsCores:{}",
-            if self.stable_test_output {
+            if self.stable_test_output || self.profile_vcs {
                 // Do not use multiple cores if stable test output is requested.
                 // Error messages may appear in non-deterministic order otherwise.
+                // Similarly, the trace output used for profiling must not be interleaved.
                 1
             } else {
                 self.proc_cores
@@ -217,6 +242,10 @@ impl BoogieOptions {
         if self.generate_smt {
             add(&["-proverLog:@PROC@.smt"]);
         }
+        if self.profile_vcs {
+            // Check each assertion in a separate solver query, and report the time of each.
+            add(&["-trace", "-vcsSplitOnEveryAssert"]);
+        }
         for f in &self.boogie_flags {
             add(&[f.as_str()]);
         }
@@ -229,6 +258,11 @@ impl BoogieOptions {
         format!("{}.log", boogie_file)
     }
 
+    /// Returns the name of the file the verification condition profile is written to.
+    pub fn get_vc_profile_file(&self, boogie_file: &str) -> String {
+        format!("{}.profile", boogie_file)
+    }
+
     /// Adjust a timeout value, given in seconds, for the runtime environment.
     pub fn adjust_timeout(&self, time: usize) -> usize {
         // If env var MVP_TEST_ON_CI is set, add 100% to the timeout for added
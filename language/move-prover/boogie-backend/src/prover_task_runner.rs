@@ -8,7 +8,6 @@ use crate::options::BoogieOptions;
 use async_trait::async_trait;
 use futures::{future::FutureExt, pin_mut, select};
 use log::debug;
-use rand::Rng;
 use regex::Regex;
 use std::{
     process::Output,
@@ -111,7 +110,7 @@ impl ProverTaskRunner {
                         return (task_id, result);
                     }
                     debug!("previous instance failed, waiting for another worker to report...");
-                    num_working_instances = usize::saturating_add(num_working_instances, 1);
+                    num_working_instances = usize::saturating_sub(num_working_instances, 1);
                 }
                 Err(RecvTimeoutError::Timeout) => {
                     // recv timeout, i.e. boogie/underlying solver is hanging
@@ -170,14 +169,10 @@ impl ProverTask for RunBoogieWithSeeds {
     type TaskId = usize;
 
     fn init(&mut self, num_instances: usize) -> Vec<Self::TaskId> {
-        // If we are running only one Boogie instance, use the default random seed.
-        if num_instances == 1 {
-            return vec![self.options.random_seed];
-        }
-        let mut rng = rand::thread_rng();
-        // Otherwise generate a list of random numbers to use as seeds.
+        // Derive the seeds from the configured random seed, so that runs with the same options
+        // are reproducible, and a single instance uses the configured seed itself.
         (0..num_instances)
-            .map(|_| rng.gen::<u8>() as usize)
+            .map(|idx| self.options.random_seed.wrapping_add(idx))
             .collect()
     }
 
@@ -0,0 +1,856 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Direct translation of verification conditions to SMT-LIB2.
+//!
+//! Verification variants which are in a supported subset are translated into an SMT-LIB2 script
+//! which is handed to SMT solvers like Z3 or CVC5 without going through Boogie. The subset
+//! covers loop-free functions which only operate on booleans and integers, i.e. which do not use
+//! references, global memory, structs, vectors, or calls to other functions. Functions outside
+//! of this subset are left to Boogie.
+//!
+//! The encoding is in passive form: each definition of a temporary introduces a new SMT
+//! constant, and each program point has a guard which holds iff the point is reached. Assumptions
+//! strengthen the guard of the points following them, so they never constrain earlier points.
+//! Each assertion is checked by a separate `(check-sat)`, where `unsat` means it holds.
+//!
+//! The available solvers are run as a portfolio: for each verification unit, all solvers are
+//! raced, and the result of the fastest one to reach a conclusive result is reported. Solvers
+//! are either started for each unit, or kept running in a pool of warm processes (see
+//! `solver_pool`).
+
+use std::{
+    collections::BTreeMap, fmt::Write as _, fs, path::Path, process::Output, sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use num::{BigInt, Signed};
+use tokio::{process::Command, sync::Semaphore};
+
+use bytecode::{
+    function_target::FunctionTarget,
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant, VerificationFlavor},
+    stackless_bytecode::{
+        AbortAction, AttrId, Bytecode, Constant, HavocKind, Label, Operation, PropKind,
+    },
+};
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::{Exp, ExpData, Operation as SpecOperation, TempIndex, Value},
+    model::{FunId, GlobalEnv, QualifiedId},
+    pragmas::ADDITION_OVERFLOW_UNCHECKED_PRAGMA,
+    ty::{PrimitiveType, Type},
+};
+
+use crate::{
+    boogie_helpers::{boogie_function_name, boogie_verification_suffix},
+    options::BoogieOptions,
+    prover_task_runner::{ProverTask, ProverTaskRunner},
+    solver_pool::{PoolUnit, SolverPool, UnitOutcome},
+};
+
+/// A verification unit translated to SMT-LIB.
+pub struct SmtLibUnit {
+    /// The SMT-LIB2 script.
+    pub script: String,
+    /// The assertions checked by the script, in the order of its `(check-sat)` commands.
+    pub checks: Vec<AttrId>,
+}
+
+/// Marker for code outside of the supported subset.
+struct Unsupported;
+
+/// The symbolic state at a program point.
+#[derive(Clone)]
+struct SymState {
+    /// The SMT expression for the condition under which the point is reached.
+    guard: String,
+    /// The SMT expressions for the current values of the defined temporaries.
+    temps: BTreeMap<TempIndex, String>,
+}
+
+/// Translates the given function variant to SMT-LIB, if it is in the supported subset.
+pub fn translate_unit(target: &FunctionTarget<'_>) -> Option<SmtLibUnit> {
+    let translator = SmtLibTranslator {
+        target,
+        out: String::new(),
+        counter: 0,
+        checks: vec![],
+    };
+    translator.translate().ok()
+}
+
+struct SmtLibTranslator<'a, 'env> {
+    target: &'a FunctionTarget<'env>,
+    out: String,
+    counter: usize,
+    checks: Vec<AttrId>,
+}
+
+impl<'a, 'env> SmtLibTranslator<'a, 'env> {
+    fn translate(mut self) -> Result<SmtLibUnit, Unsupported> {
+        let target = self.target;
+        let code = target.get_bytecode();
+        let label_offsets = Bytecode::label_offsets(code);
+
+        // Only loop-free code is supported. In stackless bytecode, loops are formed by
+        // backward jumps, so it suffices to check that all jumps go forward.
+        for (offset, bc) in code.iter().enumerate() {
+            let labels = match bc {
+                Bytecode::Jump(_, label) => vec![*label],
+                Bytecode::Branch(_, then_label, else_label, _) => vec![*then_label, *else_label],
+                Bytecode::Call(_, _, _, _, Some(AbortAction(label, _))) => vec![*label],
+                _ => vec![],
+            };
+            if labels
+                .iter()
+                .any(|label| label_offsets[label] as usize <= offset)
+            {
+                return Err(Unsupported);
+            }
+        }
+        for idx in 0..self.target.get_local_count() {
+            smt_sort(self.target.get_local_type(idx))?;
+        }
+
+        writeln!(self.out, "(set-logic ALL)").unwrap();
+        let mut temps = BTreeMap::new();
+        for idx in 0..self.target.get_parameter_count() {
+            let sort = smt_sort(self.target.get_local_type(idx))?;
+            temps.insert(idx, self.declare(&format!("t{}", idx), sort));
+        }
+
+        let mut incoming: BTreeMap<CodeOffset, Vec<SymState>> = BTreeMap::new();
+        let mut current = Some(SymState {
+            guard: "true".to_string(),
+            temps,
+        });
+        for (offset, bc) in code.iter().enumerate() {
+            if let Bytecode::Label(..) = bc {
+                let mut states = incoming.remove(&(offset as CodeOffset)).unwrap_or_default();
+                states.extend(current.take());
+                current = self.merge(states);
+                continue;
+            }
+            if let Some(state) = current.take() {
+                current = self.step(state, bc, &label_offsets, &mut incoming)?;
+            }
+        }
+        Ok(SmtLibUnit {
+            script: self.out,
+            checks: self.checks,
+        })
+    }
+
+    /// Executes one instruction symbolically, returning the state after it, or None if the
+    /// instruction does not fall through to the next one.
+    fn step(
+        &mut self,
+        mut state: SymState,
+        bc: &Bytecode,
+        label_offsets: &BTreeMap<Label, CodeOffset>,
+        incoming: &mut BTreeMap<CodeOffset, Vec<SymState>>,
+    ) -> Result<Option<SymState>, Unsupported> {
+        use Bytecode::*;
+        match bc {
+            Assign(_, dest, src, _) => {
+                let value = get_temp(&state, *src)?;
+                state.temps.insert(*dest, value);
+            }
+            Load(_, dest, constant) => {
+                let value = match constant {
+                    Constant::Bool(b) => b.to_string(),
+                    Constant::U8(n) => n.to_string(),
+                    Constant::U64(n) => n.to_string(),
+                    Constant::U128(n) => n.to_string(),
+                    Constant::Address(_) | Constant::ByteArray(_) => return Err(Unsupported),
+                };
+                state.temps.insert(*dest, value);
+            }
+            Ret(..) | Abort(..) => return Ok(None),
+            Jump(_, label) => {
+                incoming
+                    .entry(label_offsets[label])
+                    .or_default()
+                    .push(state);
+                return Ok(None);
+            }
+            Branch(_, then_label, else_label, cond) => {
+                let cond = get_temp(&state, *cond)?;
+                let mut else_state = state.clone();
+                else_state.guard = format!("(and {} (not {}))", state.guard, cond);
+                state.guard = format!("(and {} {})", state.guard, cond);
+                incoming
+                    .entry(label_offsets[then_label])
+                    .or_default()
+                    .push(state);
+                incoming
+                    .entry(label_offsets[else_label])
+                    .or_default()
+                    .push(else_state);
+                return Ok(None);
+            }
+            Nop(..) => {}
+            Prop(id, kind, exp) => {
+                let cond = self.translate_exp(&state, exp)?;
+                match kind {
+                    PropKind::Assume => {}
+                    PropKind::Assert => {
+                        writeln!(
+                            self.out,
+                            "(push 1)\n(assert (and {} (not {})))\n(check-sat)\n(pop 1)",
+                            state.guard, cond
+                        )
+                        .unwrap();
+                        self.checks.push(*id);
+                    }
+                    PropKind::Modifies => return Err(Unsupported),
+                }
+                // Execution only continues if the condition holds.
+                state.guard = self.define("g", "Bool", format!("(and {} {})", state.guard, cond));
+            }
+            Call(_, dests, oper, srcs, on_abort) => {
+                return self.step_call(state, dests, oper, srcs, on_abort, label_offsets, incoming)
+            }
+            Label(..) | SaveMem(..) | SaveSpecVar(..) => return Err(Unsupported),
+        }
+        Ok(Some(state))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn step_call(
+        &mut self,
+        mut state: SymState,
+        dests: &[TempIndex],
+        oper: &Operation,
+        srcs: &[TempIndex],
+        on_abort: &Option<AbortAction>,
+        label_offsets: &BTreeMap<Label, CodeOffset>,
+        incoming: &mut BTreeMap<CodeOffset, Vec<SymState>>,
+    ) -> Result<Option<SymState>, Unsupported> {
+        use Operation::*;
+        match oper {
+            TraceLocal(..) | TraceReturn(..) | TraceAbort | TraceExp(..) => return Ok(Some(state)),
+            Havoc(HavocKind::Value) => {
+                for src in srcs {
+                    let sort = smt_sort(self.target.get_local_type(*src))?;
+                    let value = self.declare(&format!("t{}", src), sort);
+                    state.temps.insert(*src, value);
+                }
+                return Ok(Some(state));
+            }
+            _ => {}
+        }
+        let args = srcs
+            .iter()
+            .map(|src| get_temp(&state, *src))
+            .collect::<Result<Vec<_>, _>>()?;
+        let dest = match dests {
+            [dest] => *dest,
+            _ => return Err(Unsupported),
+        };
+        let max = || max_value(self.target.get_local_type(dest));
+        let (value, abort_cond) = match (oper, args.as_slice()) {
+            (Not, [a]) => (format!("(not {})", a), None),
+            (And, [a, b]) => (format!("(and {} {})", a, b), None),
+            (Or, [a, b]) => (format!("(or {} {})", a, b), None),
+            (Eq, [a, b]) => (format!("(= {} {})", a, b), None),
+            (Neq, [a, b]) => (format!("(distinct {} {})", a, b), None),
+            (Lt, [a, b]) => (format!("(< {} {})", a, b), None),
+            (Gt, [a, b]) => (format!("(> {} {})", a, b), None),
+            (Le, [a, b]) => (format!("(<= {} {})", a, b), None),
+            (Ge, [a, b]) => (format!("(>= {} {})", a, b), None),
+            (Add, [a, b]) => {
+                if self
+                    .target
+                    .is_pragma_true(ADDITION_OVERFLOW_UNCHECKED_PRAGMA, || false)
+                {
+                    return Err(Unsupported);
+                }
+                let value = format!("(+ {} {})", a, b);
+                let abort_cond = format!("(> {} {})", value, max()?);
+                (value, Some(abort_cond))
+            }
+            (Mul, [a, b]) => {
+                let value = format!("(* {} {})", a, b);
+                let abort_cond = format!("(> {} {})", value, max()?);
+                (value, Some(abort_cond))
+            }
+            (Sub, [a, b]) => (format!("(- {} {})", a, b), Some(format!("(< {} {})", a, b))),
+            (Div, [a, b]) => (format!("(div {} {})", a, b), Some(format!("(= {} 0)", b))),
+            (Mod, [a, b]) => (format!("(mod {} {})", a, b), Some(format!("(= {} 0)", b))),
+            (CastU8, [a]) | (CastU64, [a]) | (CastU128, [a]) => {
+                (a.clone(), Some(format!("(> {} {})", a, max()?)))
+            }
+            _ => return Err(Unsupported),
+        };
+        if let Some(abort_cond) = abort_cond {
+            match on_abort {
+                Some(AbortAction(label, code)) => {
+                    // An arithmetic error aborts with the execution failure code.
+                    let mut abort_state = state.clone();
+                    abort_state.guard = format!("(and {} {})", state.guard, abort_cond);
+                    abort_state.temps.insert(*code, "(- 1)".to_string());
+                    incoming
+                        .entry(label_offsets[label])
+                        .or_default()
+                        .push(abort_state);
+                    state.guard = self.define(
+                        "g",
+                        "Bool",
+                        format!("(and {} (not {}))", state.guard, abort_cond),
+                    );
+                }
+                None => return Err(Unsupported),
+            }
+        }
+        let sort = smt_sort(self.target.get_local_type(dest))?;
+        let value = self.define(&format!("t{}", dest), sort, value);
+        state.temps.insert(dest, value);
+        Ok(Some(state))
+    }
+
+    /// Merges the states flowing into a label.
+    fn merge(&mut self, mut states: Vec<SymState>) -> Option<SymState> {
+        if states.len() <= 1 {
+            return states.pop();
+        }
+        let guard = self.define(
+            "g",
+            "Bool",
+            format!(
+                "(or {})",
+                states
+                    .iter()
+                    .map(|s| s.guard.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        );
+        let mut temps = BTreeMap::new();
+        let all_temps = states
+            .iter()
+            .flat_map(|s| s.temps.keys().cloned())
+            .collect::<Vec<_>>();
+        for temp in all_temps {
+            if temps.contains_key(&temp) {
+                continue;
+            }
+            let values = states
+                .iter()
+                .filter_map(|s| s.temps.get(&temp).map(|v| (s.guard.as_str(), v.as_str())))
+                .collect::<Vec<_>>();
+            let (_, last) = values[values.len() - 1];
+            let value = if values.iter().all(|(_, v)| *v == last) {
+                last.to_string()
+            } else {
+                let exp = values[..values.len() - 1]
+                    .iter()
+                    .rev()
+                    .fold(last.to_string(), |acc, (guard, value)| {
+                        format!("(ite {} {} {})", guard, value, acc)
+                    });
+                // Types have been checked before, so the sort is known to exist.
+                let sort = smt_sort(self.target.get_local_type(temp)).unwrap_or("Int");
+                self.define(&format!("t{}", temp), sort, exp)
+            };
+            temps.insert(temp, value);
+        }
+        Some(SymState { guard, temps })
+    }
+
+    fn translate_exp(&self, state: &SymState, exp: &Exp) -> Result<String, Unsupported> {
+        match exp.as_ref() {
+            ExpData::Temporary(_, idx) => get_temp(state, *idx),
+            ExpData::Value(_, Value::Bool(b)) => Ok(b.to_string()),
+            ExpData::Value(_, Value::Number(n)) => Ok(smt_int(n)),
+            ExpData::IfElse(_, cond, then_exp, else_exp) => Ok(format!(
+                "(ite {} {} {})",
+                self.translate_exp(state, cond)?,
+                self.translate_exp(state, then_exp)?,
+                self.translate_exp(state, else_exp)?
+            )),
+            ExpData::Call(_, SpecOperation::WellFormed, args) if args.len() == 1 => {
+                let value = self.translate_exp(state, &args[0])?;
+                let ty = self.target.global_env().get_node_type(args[0].node_id());
+                match &ty {
+                    Type::Primitive(PrimitiveType::Bool) | Type::Primitive(PrimitiveType::Num) => {
+                        Ok("true".to_string())
+                    }
+                    _ => Ok(format!(
+                        "(and (<= 0 {}) (<= {} {}))",
+                        value,
+                        value,
+                        max_value(&ty)?
+                    )),
+                }
+            }
+            ExpData::Call(_, oper, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.translate_exp(state, arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let smt_oper = match (oper, args.len()) {
+                    (SpecOperation::MaxU8, 0) => {
+                        return max_value(&Type::Primitive(PrimitiveType::U8))
+                    }
+                    (SpecOperation::MaxU64, 0) => {
+                        return max_value(&Type::Primitive(PrimitiveType::U64))
+                    }
+                    (SpecOperation::MaxU128, 0) => {
+                        return max_value(&Type::Primitive(PrimitiveType::U128))
+                    }
+                    (SpecOperation::Not, 1) => "not",
+                    (SpecOperation::Add, 2) => "+",
+                    (SpecOperation::Sub, 2) => "-",
+                    (SpecOperation::Mul, 2) => "*",
+                    (SpecOperation::Div, 2) => "div",
+                    (SpecOperation::Mod, 2) => "mod",
+                    (SpecOperation::Lt, 2) => "<",
+                    (SpecOperation::Gt, 2) => ">",
+                    (SpecOperation::Le, 2) => "<=",
+                    (SpecOperation::Ge, 2) => ">=",
+                    (SpecOperation::Eq, 2)
+                    | (SpecOperation::Identical, 2)
+                    | (SpecOperation::Iff, 2) => "=",
+                    (SpecOperation::Neq, 2) => "distinct",
+                    (SpecOperation::Implies, 2) => "=>",
+                    (SpecOperation::And, _) if !args.is_empty() => "and",
+                    (SpecOperation::Or, _) if !args.is_empty() => "or",
+                    _ => return Err(Unsupported),
+                };
+                Ok(format!("({} {})", smt_oper, args.join(" ")))
+            }
+            _ => Err(Unsupported),
+        }
+    }
+
+    fn declare(&mut self, prefix: &str, sort: &str) -> String {
+        let name = self.fresh_name(prefix);
+        writeln!(self.out, "(declare-const {} {})", name, sort).unwrap();
+        name
+    }
+
+    fn define(&mut self, prefix: &str, sort: &str, exp: String) -> String {
+        let name = self.fresh_name(prefix);
+        writeln!(self.out, "(define-fun {} () {} {})", name, sort, exp).unwrap();
+        name
+    }
+
+    fn fresh_name(&mut self, prefix: &str) -> String {
+        self.counter += 1;
+        format!("{}_{}", prefix, self.counter)
+    }
+}
+
+fn get_temp(state: &SymState, temp: TempIndex) -> Result<String, Unsupported> {
+    state.temps.get(&temp).cloned().ok_or(Unsupported)
+}
+
+fn smt_sort(ty: &Type) -> Result<&'static str, Unsupported> {
+    match ty {
+        Type::Primitive(PrimitiveType::Bool) => Ok("Bool"),
+        Type::Primitive(PrimitiveType::U8)
+        | Type::Primitive(PrimitiveType::U64)
+        | Type::Primitive(PrimitiveType::U128)
+        | Type::Primitive(PrimitiveType::Num) => Ok("Int"),
+        _ => Err(Unsupported),
+    }
+}
+
+fn max_value(ty: &Type) -> Result<String, Unsupported> {
+    match ty {
+        Type::Primitive(PrimitiveType::U8) => Ok(u8::MAX.to_string()),
+        Type::Primitive(PrimitiveType::U64) => Ok(u64::MAX.to_string()),
+        Type::Primitive(PrimitiveType::U128) => Ok(u128::MAX.to_string()),
+        _ => Err(Unsupported),
+    }
+}
+
+fn smt_int(n: &BigInt) -> String {
+    if n.is_negative() {
+        format!("(- {})", -n)
+    } else {
+        n.to_string()
+    }
+}
+
+// =================================================================================================
+// Portfolio execution
+
+/// Runs the available solvers on the verification units in the supported subset.
+pub struct SmtLibWrapper<'env> {
+    pub env: &'env GlobalEnv,
+    pub targets: &'env FunctionTargetsHolder,
+    pub options: &'env BoogieOptions,
+}
+
+impl<'env> SmtLibWrapper<'env> {
+    /// Verifies the verification variants in the supported subset, reporting failed assertions
+    /// to the environment. Returns the variants for which a conclusive result was obtained,
+    /// together with whether all their assertions hold; the remaining ones need to be verified
+    /// via Boogie.
+    pub fn verify(
+        &self,
+        output_dir: &Path,
+    ) -> anyhow::Result<Vec<(QualifiedId<FunId>, FunctionVariant, bool)>> {
+        let solvers = self.get_solvers();
+        if solvers.is_empty() {
+            warn!("no SMT solver configured for direct SMT-LIB verification");
+            return Ok(vec![]);
+        }
+        let mut units = vec![];
+        for (fun_id, variant) in self.targets.get_funs_and_variants() {
+            // Inconsistency checks expect the final `assert false` to fail, which the reporting
+            // below does not account for, so they are left to Boogie.
+            if !variant.is_verified()
+                || matches!(
+                    variant,
+                    FunctionVariant::Verification(VerificationFlavor::Inconsistency(..))
+                )
+            {
+                continue;
+            }
+            let fun_env = self.env.get_function(fun_id);
+            let target = self.targets.get_target(&fun_env, &variant);
+            if let Some(unit) = translate_unit(&target) {
+                units.push((fun_id, variant, unit));
+            }
+        }
+        let results = if self.options.solver_pool_size > 0 {
+            self.run_pooled(&solvers, &units)
+        } else {
+            let mut results = vec![];
+            for (fun_id, variant, unit) in &units {
+                results.push(self.run_portfolio(&solvers, output_dir, *fun_id, variant, unit)?);
+            }
+            results
+        };
+
+        let mut done = vec![];
+        for ((fun_id, variant, unit), result) in units.into_iter().zip(results) {
+            let fun_env = self.env.get_function(fun_id);
+            let (solver_name, results) = match result {
+                Some(result) => result,
+                None => {
+                    debug!(
+                        "SMT solvers are inconclusive on `{}`, deferring to Boogie",
+                        fun_env.get_full_name_str()
+                    );
+                    continue;
+                }
+            };
+            info!(
+                "verified `{}` via SMT-LIB, fastest solver: {}",
+                fun_env.get_full_name_str(),
+                solver_name
+            );
+            let target = self.targets.get_target(&fun_env, &variant);
+            let mut verified = true;
+            for (attr_id, holds) in unit.checks.iter().zip(results) {
+                if holds == Some(false) {
+                    verified = false;
+                    let message = target
+                        .get_vc_info(*attr_id)
+                        .cloned()
+                        .unwrap_or_else(|| "unknown assertion failed".to_string());
+                    self.env.error(&target.get_bytecode_loc(*attr_id), &message);
+                }
+            }
+            done.push((fun_id, variant, verified));
+        }
+        Ok(done)
+    }
+
+    /// Races the solvers on the unit, each in a new process. Returns the name of the fastest
+    /// solver with a conclusive result together with the results of the checks, or None if
+    /// none was conclusive.
+    fn run_portfolio(
+        &self,
+        solvers: &[(String, Vec<String>)],
+        output_dir: &Path,
+        fun_id: QualifiedId<FunId>,
+        variant: &FunctionVariant,
+        unit: &SmtLibUnit,
+    ) -> anyhow::Result<Option<(String, Vec<Option<bool>>)>> {
+        let fun_env = self.env.get_function(fun_id);
+        let flavor = match variant {
+            FunctionVariant::Verification(flavor) => flavor,
+            FunctionVariant::Baseline => unreachable!("baseline is not verified"),
+        };
+        let file = output_dir.join(format!(
+            "{}{}.smt2",
+            boogie_function_name(&fun_env, &[]),
+            boogie_verification_suffix(flavor)
+        ));
+        debug!("writing SMT-LIB to `{}`", file.display());
+        fs::write(&file, &unit.script)?;
+        let task = RunSmtPortfolio {
+            solvers: solvers.to_vec(),
+            smt_file: file.to_string_lossy().to_string(),
+            num_checks: unit.checks.len(),
+        };
+        let (solver_idx, result) = ProverTaskRunner::run_tasks(
+            task,
+            solvers.len(),
+            self.options.sequential_task,
+            self.options.hard_timeout_secs,
+        );
+        if !self.options.keep_artifacts {
+            let _ = fs::remove_file(&file);
+        }
+        let results = match &result {
+            Ok(output) => get_check_results(&output.stdout),
+            Err(err) => {
+                debug!("SMT solvers failed on `{}`: {}", file.display(), err);
+                return Ok(None);
+            }
+        };
+        if results.len() != unit.checks.len() || results.iter().any(|r| r.is_none()) {
+            return Ok(None);
+        }
+        Ok(Some((solvers[solver_idx].0.clone(), results)))
+    }
+
+    /// Streams the units to a pool of warm solver processes. Returns, per unit, the same as
+    /// `run_portfolio`.
+    fn run_pooled(
+        &self,
+        solvers: &[(String, Vec<String>)],
+        units: &[(QualifiedId<FunId>, FunctionVariant, SmtLibUnit)],
+    ) -> Vec<Option<(String, Vec<Option<bool>>)>> {
+        // Interactive solvers read from standard input.
+        let solvers = solvers
+            .iter()
+            .map(|(name, args)| {
+                let mut args = args.clone();
+                if name == "z3" {
+                    args.push("-in".to_string());
+                    if self.options.solver_memory_limit_mb > 0 {
+                        args.push(format!(
+                            "memory_max_size={}",
+                            self.options.solver_memory_limit_mb
+                        ));
+                    }
+                }
+                (name.clone(), args)
+            })
+            .collect::<Vec<_>>();
+        let mut pool = SolverPool::new(
+            solvers,
+            self.options.solver_pool_size,
+            self.options.solver_memory_limit_mb,
+        );
+        let pool_units = units
+            .iter()
+            .map(|(_, _, unit)| PoolUnit {
+                script: unit.script.clone(),
+                num_checks: unit.checks.len(),
+                timeout: self.get_unit_timeout(unit.checks.len()),
+            })
+            .collect();
+        pool.run(pool_units)
+            .into_iter()
+            .zip(units)
+            .map(|((solver_idx, outcome), (_, _, unit))| {
+                if !outcome.is_conclusive(unit.checks.len()) {
+                    return None;
+                }
+                match outcome {
+                    UnitOutcome::Results(results) => {
+                        Some((pool.solver_name(solver_idx).to_string(), results))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the time after which a unit with the given number of checks is abandoned: the
+    /// hard timeout if set, otherwise the solver timeout for each check, with some slack.
+    fn get_unit_timeout(&self, num_checks: usize) -> Duration {
+        if self.options.hard_timeout_secs > 0 {
+            Duration::from_secs(self.options.hard_timeout_secs)
+        } else {
+            Duration::from_secs((self.options.vc_timeout * num_checks.max(1) + 1) as u64)
+        }
+    }
+
+    /// Returns the configured solvers, as names and command lines without the input file.
+    fn get_solvers(&self) -> Vec<(String, Vec<String>)> {
+        let timeout_ms = self.options.vc_timeout * 1000;
+        let mut solvers = vec![];
+        if !self.options.z3_exe.is_empty() {
+            solvers.push((
+                "z3".to_string(),
+                vec![
+                    self.options.z3_exe.clone(),
+                    "-smt2".to_string(),
+                    format!("-t:{}", timeout_ms),
+                    format!("smt.random_seed={}", self.options.random_seed),
+                ],
+            ));
+        }
+        if !self.options.cvc5_exe.is_empty() {
+            solvers.push((
+                "cvc5".to_string(),
+                vec![
+                    self.options.cvc5_exe.clone(),
+                    "--lang=smt2".to_string(),
+                    "--incremental".to_string(),
+                    format!("--tlimit-per={}", timeout_ms),
+                    format!("--seed={}", self.options.random_seed),
+                ],
+            ));
+        }
+        solvers
+    }
+}
+
+/// Returns the results of the `(check-sat)` commands in the output of a solver: whether the
+/// checked assertion holds, or None if the solver was inconclusive.
+fn get_check_results(stdout: &[u8]) -> Vec<Option<bool>> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line {
+            "unsat" => Some(true),
+            "sat" => Some(false),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A portfolio of solvers, one task per solver.
+#[derive(Debug, Clone)]
+struct RunSmtPortfolio {
+    solvers: Vec<(String, Vec<String>)>,
+    smt_file: String,
+    num_checks: usize,
+}
+
+#[async_trait]
+impl ProverTask for RunSmtPortfolio {
+    type TaskResult = std::io::Result<Output>;
+    type TaskId = usize;
+
+    fn init(&mut self, num_instances: usize) -> Vec<Self::TaskId> {
+        (0..num_instances.min(self.solvers.len())).collect()
+    }
+
+    async fn run(&mut self, task_id: Self::TaskId, sem: Arc<Semaphore>) -> Self::TaskResult {
+        let _guard = sem.acquire().await;
+        let (name, args) = &self.solvers[task_id];
+        debug!("running {} on `{}`", name, self.smt_file);
+        Command::new(&args[0])
+            .args(&args[1..])
+            .arg(&self.smt_file)
+            .kill_on_drop(true)
+            .output()
+            .await
+    }
+
+    fn is_success(&self, task_result: &Self::TaskResult) -> bool {
+        match task_result {
+            Ok(output) => {
+                let results = get_check_results(&output.stdout);
+                results.len() == self.num_checks && results.iter().all(|r| r.is_some())
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn make_timeout(&self) -> (Self::TaskId, Self::TaskResult) {
+        (0, Err(std::io::Error::from(std::io::ErrorKind::TimedOut)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_model::run_model_builder;
+
+    const SOURCE: &str = r#"
+module 0x42::M {
+    struct S has drop { x: u64 }
+
+    fun lt(x: u64, y: u64): bool {
+        spec {
+            assert x < y ==> x <= y;
+        };
+        x < y
+    }
+
+    fun branch(b: bool): u64 {
+        if (b) return 1;
+        2
+    }
+
+    fun looping(b: bool): bool {
+        while (b) b = false;
+        b
+    }
+
+    fun get_x(s: &S): u64 {
+        s.x
+    }
+}
+"#;
+
+    /// Translates the baseline of the function with the given name in `SOURCE`.
+    fn translate(fun_name: &str) -> Option<SmtLibUnit> {
+        let path = std::env::temp_dir().join(format!("smtlib_backend_{}.move", std::process::id()));
+        fs::write(&path, SOURCE).unwrap();
+        let env = run_model_builder(&[path.to_string_lossy().to_string()], &[]).unwrap();
+        assert!(!env.has_errors());
+        let mut targets = FunctionTargetsHolder::default();
+        let mut fun_env = None;
+        for module_env in env.get_modules() {
+            for func_env in module_env.into_functions() {
+                targets.add_target(&func_env);
+                if *func_env.get_simple_name_string() == fun_name {
+                    fun_env = Some(func_env);
+                }
+            }
+        }
+        let fun_env = fun_env.expect("function");
+        translate_unit(&targets.get_target(&fun_env, &FunctionVariant::Baseline))
+    }
+
+    #[test]
+    fn test_translate() {
+        let unit = translate("lt").expect("supported");
+        assert_eq!(
+            unit.script,
+            "(set-logic ALL)\n\
+             (declare-const t0_1 Int)\n\
+             (declare-const t1_2 Int)\n\
+             (push 1)\n\
+             (assert (and true (not (=> (< t0_1 t1_2) (<= t0_1 t1_2)))))\n\
+             (check-sat)\n\
+             (pop 1)\n\
+             (define-fun g_3 () Bool (and true (=> (< t0_1 t1_2) (<= t0_1 t1_2))))\n\
+             (define-fun t4_4 () Bool (< t0_1 t1_2))\n"
+        );
+        assert_eq!(unit.checks.len(), 1);
+
+        let unit = translate("branch").expect("supported");
+        assert!(unit.checks.is_empty());
+
+        // Loops and references are outside of the supported subset.
+        assert!(translate("looping").is_none());
+        assert!(translate("get_x").is_none());
+    }
+
+    #[test]
+    fn test_get_check_results() {
+        assert_eq!(
+            get_check_results(b"unsat\nsat\n\nunknown\ntimeout\n"),
+            vec![Some(true), Some(false), None, None]
+        );
+        assert!(get_check_results(b"").is_empty());
+    }
+}
@@ -0,0 +1,483 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A pool of warm solver processes for the SMT-LIB backend.
+//!
+//! Starting a solver process often takes longer than solving the verification conditions of a
+//! small function. The pool therefore keeps solver processes running in interactive mode and
+//! streams verification units to them through their standard input. After each unit, the
+//! solver prints a marker and is reset, so the next unit starts from a clean state.
+//!
+//! Each solver of the portfolio gets the same number of workers. A unit is offered to one
+//! worker of each solver, and the first conclusive result wins; workers skip units which have
+//! already been decided when they get to them, and abandon units which are decided while they
+//! are still running them. A process which is abandoned, exceeds the timeout of a unit, or
+//! exceeds the memory limit is killed and replaced by a fresh one for the next unit.
+
+use log::{debug, warn};
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// The line printed by the solver after each unit.
+const UNIT_DONE_MARKER: &str = "move-prover-unit-done";
+
+/// The interval in which the memory usage of a busy solver process is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The outcome of running a unit on one solver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitOutcome {
+    /// For each `(check-sat)` of the unit, whether the checked assertion holds, or None if the
+    /// solver was inconclusive.
+    Results(Vec<Option<bool>>),
+    /// The unit exceeded its timeout.
+    Timeout,
+    /// The solver process exceeded the memory limit.
+    OutOfMemory,
+    /// The unit was decided by another solver while this one was still running.
+    Cancelled,
+    /// The solver process could not be started or failed.
+    Failed(String),
+}
+
+impl UnitOutcome {
+    /// Returns whether the outcome has a conclusive result for each of the given number of
+    /// checks.
+    pub fn is_conclusive(&self, num_checks: usize) -> bool {
+        match self {
+            UnitOutcome::Results(results) => {
+                results.len() == num_checks && results.iter().all(|r| r.is_some())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A verification unit submitted to the pool.
+pub struct PoolUnit {
+    /// The SMT-LIB2 script of the unit.
+    pub script: String,
+    /// The number of `(check-sat)` commands in the script.
+    pub num_checks: usize,
+    /// The time after which the unit is abandoned.
+    pub timeout: Duration,
+}
+
+struct Job {
+    unit_idx: usize,
+    unit: Arc<PoolUnit>,
+}
+
+/// The result of a unit on a solver, reported by a worker.
+struct JobResult {
+    unit_idx: usize,
+    solver_idx: usize,
+    outcome: UnitOutcome,
+}
+
+/// A pool of warm solver processes.
+pub struct SolverPool {
+    /// The names of the solvers.
+    solver_names: Vec<String>,
+    /// The job queues of the workers, per solver.
+    queues: Vec<Vec<Sender<Job>>>,
+    workers: Vec<JoinHandle<()>>,
+    results: Receiver<JobResult>,
+    /// The units which have been decided, shared with the workers.
+    decided: Arc<Mutex<BTreeSet<usize>>>,
+    /// The index of the first unit of the next run. Units are numbered across runs, so that
+    /// results of earlier runs are never mistaken for results of later ones.
+    next_unit_idx: usize,
+}
+
+impl SolverPool {
+    /// Starts a pool with `workers_per_solver` processes for each of the given solvers, given
+    /// as names and command lines which read SMT-LIB from standard input. A memory limit of 0
+    /// means unlimited.
+    pub fn new(
+        solvers: Vec<(String, Vec<String>)>,
+        workers_per_solver: usize,
+        memory_limit_mb: usize,
+    ) -> Self {
+        let (result_tx, results) = channel();
+        let decided = Arc::new(Mutex::new(BTreeSet::new()));
+        let mut solver_names = vec![];
+        let mut queues = vec![];
+        let mut workers = vec![];
+        for (solver_idx, (name, args)) in solvers.into_iter().enumerate() {
+            let mut solver_queues = vec![];
+            for _ in 0..workers_per_solver.max(1) {
+                let (job_tx, job_rx) = channel();
+                let mut worker = Worker {
+                    solver_idx,
+                    name: name.clone(),
+                    args: args.clone(),
+                    memory_limit_mb,
+                    process: None,
+                    decided: decided.clone(),
+                    results: result_tx.clone(),
+                };
+                workers.push(thread::spawn(move || worker.run(job_rx)));
+                solver_queues.push(job_tx);
+            }
+            solver_names.push(name);
+            queues.push(solver_queues);
+        }
+        Self {
+            solver_names,
+            queues,
+            workers,
+            results,
+            decided,
+            next_unit_idx: 0,
+        }
+    }
+
+    /// Returns the name of the solver with the given index.
+    pub fn solver_name(&self, solver_idx: usize) -> &str {
+        &self.solver_names[solver_idx]
+    }
+
+    /// Runs the units on the pool. Returns, for each unit, the index of the solver which
+    /// decided it together with its outcome. If no solver was conclusive, the outcome of the
+    /// last one to report is returned.
+    pub fn run(&mut self, units: Vec<PoolUnit>) -> Vec<(usize, UnitOutcome)> {
+        let num_units = units.len();
+        let num_checks = units.iter().map(|unit| unit.num_checks).collect::<Vec<_>>();
+        let first_unit_idx = self.next_unit_idx;
+        self.next_unit_idx += num_units;
+        // Distribute the units round robin over the workers of each solver.
+        for (offset, unit) in units.into_iter().enumerate() {
+            let unit_idx = first_unit_idx + offset;
+            let unit = Arc::new(unit);
+            for solver_queues in &self.queues {
+                let job = Job {
+                    unit_idx,
+                    unit: unit.clone(),
+                };
+                let _ = solver_queues[unit_idx % solver_queues.len()].send(job);
+            }
+        }
+        let mut outcomes: Vec<Option<(usize, UnitOutcome)>> = vec![None; num_units];
+        let mut pending = vec![self.queues.len(); num_units];
+        let mut undecided = num_units;
+        while undecided > 0 {
+            let JobResult {
+                unit_idx,
+                solver_idx,
+                outcome,
+            } = match self.results.recv() {
+                Ok(result) => result,
+                // All workers are gone, which only happens if they panicked.
+                Err(_) => break,
+            };
+            let offset = match unit_idx.checked_sub(first_unit_idx) {
+                Some(offset) if pending[offset] > 0 => offset,
+                // From an earlier run, or already decided by another solver.
+                _ => continue,
+            };
+            let conclusive = outcome.is_conclusive(num_checks[offset]);
+            outcomes[offset] = Some((solver_idx, outcome));
+            pending[offset] -= 1;
+            if conclusive || pending[offset] == 0 {
+                pending[offset] = 0;
+                self.decided.lock().unwrap().insert(unit_idx);
+                undecided -= 1;
+            }
+        }
+        outcomes
+            .into_iter()
+            .map(|outcome| {
+                outcome.unwrap_or_else(|| (0, UnitOutcome::Failed("no solver result".to_string())))
+            })
+            .collect()
+    }
+}
+
+impl Drop for SolverPool {
+    fn drop(&mut self) {
+        // Closing the queues terminates the workers, which kill their processes.
+        self.queues.clear();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// A worker owning one solver process.
+struct Worker {
+    solver_idx: usize,
+    name: String,
+    args: Vec<String>,
+    memory_limit_mb: usize,
+    process: Option<SolverProcess>,
+    decided: Arc<Mutex<BTreeSet<usize>>>,
+    results: Sender<JobResult>,
+}
+
+/// A running solver process, with a thread forwarding its output lines.
+struct SolverProcess {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Worker {
+    fn run(&mut self, jobs: Receiver<Job>) {
+        while let Ok(Job { unit_idx, unit }) = jobs.recv() {
+            if self.decided.lock().unwrap().contains(&unit_idx) {
+                continue;
+            }
+            let outcome = self.run_unit(unit_idx, &unit);
+            debug!("{} finished unit {}: {:?}", self.name, unit_idx, outcome);
+            let _ = self.results.send(JobResult {
+                unit_idx,
+                solver_idx: self.solver_idx,
+                outcome,
+            });
+        }
+        if let Some(process) = self.process.take() {
+            self.kill(process);
+        }
+    }
+
+    /// Runs the unit on the solver process, starting one if there is none. The process is kept
+    /// for the next unit unless it failed or had to be killed.
+    fn run_unit(&mut self, unit_idx: usize, unit: &PoolUnit) -> UnitOutcome {
+        let mut process = match self.process.take() {
+            Some(process) => process,
+            None => match self.spawn() {
+                Ok(process) => process,
+                Err(err) => return UnitOutcome::Failed(err),
+            },
+        };
+        let outcome = self.stream_unit(&mut process, unit_idx, unit);
+        if matches!(outcome, UnitOutcome::Results(_)) {
+            self.process = Some(process);
+        } else {
+            self.kill(process);
+        }
+        outcome
+    }
+
+    fn stream_unit(
+        &self,
+        process: &mut SolverProcess,
+        unit_idx: usize,
+        unit: &PoolUnit,
+    ) -> UnitOutcome {
+        let input = format!(
+            "{}\n(echo \"{}\")\n(reset)\n",
+            unit.script, UNIT_DONE_MARKER
+        );
+        if let Err(err) = process
+            .stdin
+            .write_all(input.as_bytes())
+            .and_then(|_| process.stdin.flush())
+        {
+            return UnitOutcome::Failed(format!("cannot write to {}: {}", self.name, err));
+        }
+        let deadline = Instant::now() + unit.timeout;
+        let mut output = vec![];
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return UnitOutcome::Timeout;
+            }
+            match process
+                .lines
+                .recv_timeout(POLL_INTERVAL.min(deadline - now))
+            {
+                Ok(line) => {
+                    let line = line.trim();
+                    if line.trim_matches('"') == UNIT_DONE_MARKER {
+                        return UnitOutcome::Results(
+                            output
+                                .into_iter()
+                                .map(|line: String| match line.as_str() {
+                                    "unsat" => Some(true),
+                                    "sat" => Some(false),
+                                    _ => None,
+                                })
+                                .collect(),
+                        );
+                    }
+                    if !line.is_empty() {
+                        output.push(line.to_string());
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if self.decided.lock().unwrap().contains(&unit_idx) {
+                        return UnitOutcome::Cancelled;
+                    }
+                    if self.memory_limit_mb > 0
+                        && resident_memory_mb(process.child.id())
+                            .map_or(false, |mb| mb > self.memory_limit_mb)
+                    {
+                        return UnitOutcome::OutOfMemory;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return UnitOutcome::Failed(format!("{} terminated unexpectedly", self.name));
+                }
+            }
+        }
+    }
+
+    fn spawn(&self) -> Result<SolverProcess, String> {
+        debug!("starting {}: {}", self.name, self.args.join(" "));
+        let mut child = Command::new(&self.args[0])
+            .args(&self.args[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| format!("cannot start {}: {}", self.name, err))?;
+        let stdin = child.stdin.take().expect("piped stdin");
+        let stdout = child.stdout.take().expect("piped stdout");
+        let (line_tx, lines) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let sent = line.map(|line| line_tx.send(line).is_ok());
+                if !matches!(sent, Ok(true)) {
+                    break;
+                }
+            }
+        });
+        Ok(SolverProcess {
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn kill(&self, mut process: SolverProcess) {
+        if let Err(err) = process.child.kill() {
+            warn!("cannot kill {}: {}", self.name, err);
+        }
+        let _ = process.child.wait();
+    }
+}
+
+/// Returns the resident memory of the process in megabytes, if it can be determined. This is
+/// only supported on systems with a `/proc` file system.
+fn resident_memory_mb(pid: u32) -> Option<usize> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let kb = status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse::<usize>()
+        .ok()?;
+    Some(kb / 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A solver which answers each `(check-sat)` with its first argument.
+    const FAKE_SOLVER: &str = r#"
+while read -r line; do
+    case "$line" in
+        "(check-sat)") echo "$0" ;;
+        *move-prover-unit-done*) echo '"move-prover-unit-done"' ;;
+    esac
+done
+"#;
+
+    fn fake_solver(name: &str, answer: &str) -> (String, Vec<String>) {
+        let args = vec!["sh", "-c", FAKE_SOLVER, answer];
+        (
+            name.to_string(),
+            args.into_iter().map(String::from).collect(),
+        )
+    }
+
+    fn unit(num_checks: usize, timeout: Duration) -> PoolUnit {
+        PoolUnit {
+            script: vec!["(assert true)\n(check-sat)"; num_checks].join("\n"),
+            num_checks,
+            timeout,
+        }
+    }
+
+    #[test]
+    fn test_warm_processes() {
+        let mut pool = SolverPool::new(vec![fake_solver("z3", "unsat")], 2, 0);
+        // The processes are reused for all units, across runs.
+        for _ in 0..2 {
+            let units = (1..=3)
+                .map(|num_checks| unit(num_checks, Duration::from_secs(10)))
+                .collect();
+            assert_eq!(
+                pool.run(units),
+                (1..=3)
+                    .map(|num_checks| (0, UnitOutcome::Results(vec![Some(true); num_checks])))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_first_conclusive_result_wins() {
+        let mut pool = SolverPool::new(
+            vec![fake_solver("z3", "unknown"), fake_solver("cvc4", "sat")],
+            1,
+            0,
+        );
+        assert_eq!(pool.solver_name(1), "cvc4");
+        assert_eq!(
+            pool.run(vec![unit(1, Duration::from_secs(10))]),
+            vec![(1, UnitOutcome::Results(vec![Some(false)]))]
+        );
+
+        // If no solver is conclusive, the outcome of one of them is returned.
+        let mut pool = SolverPool::new(
+            vec![fake_solver("z3", "unknown"), fake_solver("cvc4", "unknown")],
+            1,
+            0,
+        );
+        let outcomes = pool.run(vec![unit(1, Duration::from_secs(10))]);
+        assert_eq!(outcomes[0].1, UnitOutcome::Results(vec![None]));
+    }
+
+    #[test]
+    fn test_timeout_and_failure() {
+        let hanging = (
+            "hanging".to_string(),
+            vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "cat > /dev/null".to_string(),
+            ],
+        );
+        let mut pool = SolverPool::new(vec![hanging], 1, 0);
+        assert_eq!(
+            pool.run(vec![unit(1, Duration::from_millis(200))]),
+            vec![(0, UnitOutcome::Timeout)]
+        );
+
+        let missing = (
+            "missing".to_string(),
+            vec!["/nonexistent/solver".to_string()],
+        );
+        let mut pool = SolverPool::new(vec![missing], 1, 0);
+        let outcomes = pool.run(vec![unit(1, Duration::from_secs(10))]);
+        match &outcomes[0].1 {
+            UnitOutcome::Failed(err) => assert!(err.starts_with("cannot start missing")),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+    }
+}
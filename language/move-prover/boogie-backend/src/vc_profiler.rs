@@ -0,0 +1,483 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Profiling of the solver time spent on verification conditions.
+//!
+//! When profiling is enabled, Boogie is asked to check each assertion of a verification
+//! procedure in a separate solver query (`-vcsSplitOnEveryAssert`), and to trace the time
+//! spent on each of these splits (`-trace`). The splits of a procedure are numbered in the
+//! order of its assertions after Boogie inlined the procedures it calls. While translating, the
+//! translator records the assertions it emits into each procedure, identified by the attribute
+//! ids of the bytecodes they stem from, as well as the calls to inlined procedures. From this
+//! table, the expected sequence of splits of a verification procedure is reconstructed, which
+//! allows attributing solver time back to the spec conditions the assertions were generated
+//! from, and to the pipeline passes which instrumented them. If the number of splits reported by
+//! Boogie does not match the table, the splits of the procedure are not attributed.
+
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+use log::warn;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use bytecode::{
+    function_target::FunctionTarget,
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant, VerificationFlavor},
+    stackless_bytecode::AttrId,
+};
+use move_model::model::{FunId, GlobalEnv, Loc, QualifiedId};
+
+/// The maximal number of conditions reported per function.
+const MAX_CONDITIONS_PER_FUNCTION: usize = 10;
+
+static VERIFYING_PROC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Verifying (?P<proc>\S+) \.\.\.$").unwrap());
+
+static SPLIT_DONE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*--> split #(?P<idx>\d+) done,\s*\[(?P<time>[0-9.]+) s\]\s*(?P<outcome>.*)$")
+        .unwrap()
+});
+
+static PROC_DONE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*\[(?P<time>[0-9.]+) s[,\]].*$").unwrap());
+
+static TRACE_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(Parsing |Coalescing blocks|Inlining|Running abstract interpretation|Verifying |\[TRACE\]|\s*checking split|\s*--> split|\s*\[[0-9.]+ s[,\]])",
+    )
+    .unwrap()
+});
+
+/// An element of a Boogie procedure which contributes verification conditions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VcEvent {
+    /// An assertion generated for the bytecode with the given attribute id.
+    Assert(AttrId),
+    /// A call to the named procedure, which Boogie inlines together with its assertions.
+    Inline(String),
+}
+
+/// The elements contributing verification conditions to one Boogie procedure, in the order
+/// they are emitted.
+#[derive(Clone)]
+struct ProcedureVcs {
+    fun_id: QualifiedId<FunId>,
+    variant: FunctionVariant,
+    events: Vec<VcEvent>,
+}
+
+/// The verification conditions of the emitted Boogie procedures, keyed by procedure name. This
+/// is recorded by the translator as an extension of the global environment when profiling is
+/// enabled.
+#[derive(Clone, Default)]
+pub struct VcSiteTable {
+    procedures: BTreeMap<String, ProcedureVcs>,
+}
+
+/// An assertion in a verification procedure, identified by the bytecode it was generated for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VcSite {
+    pub fun_id: QualifiedId<FunId>,
+    pub variant: FunctionVariant,
+    pub attr_id: AttrId,
+}
+
+impl VcSiteTable {
+    /// Records that the procedure with the given name is emitted for the function variant.
+    pub fn add_procedure(
+        &mut self,
+        name: String,
+        fun_id: QualifiedId<FunId>,
+        variant: FunctionVariant,
+    ) {
+        self.procedures.insert(
+            name,
+            ProcedureVcs {
+                fun_id,
+                variant,
+                events: vec![],
+            },
+        );
+    }
+
+    /// Records an element of the named procedure, which must have been added before.
+    pub fn add_event(&mut self, name: &str, event: VcEvent) {
+        self.procedures
+            .get_mut(name)
+            .expect("procedure added")
+            .events
+            .push(event);
+    }
+
+    /// Returns the function variant the named procedure is emitted for.
+    pub fn get_variant(&self, name: &str) -> Option<(QualifiedId<FunId>, &FunctionVariant)> {
+        self.procedures
+            .get(name)
+            .map(|proc_vcs| (proc_vcs.fun_id, &proc_vcs.variant))
+    }
+
+    /// Returns the assertions checked in the named procedure, including those of the procedures
+    /// inlined into it, in the order Boogie numbers the splits of the procedure.
+    pub fn get_sites(&self, name: &str) -> Vec<VcSite> {
+        let mut sites = vec![];
+        self.collect_sites(name, &mut vec![], &mut sites);
+        sites
+    }
+
+    fn collect_sites(&self, name: &str, inlining: &mut Vec<String>, sites: &mut Vec<VcSite>) {
+        // Procedures not in the table, e.g. those of the prelude, have no assertions.
+        let proc_vcs = match self.procedures.get(name) {
+            Some(proc_vcs) => proc_vcs,
+            None => return,
+        };
+        inlining.push(name.to_string());
+        for event in &proc_vcs.events {
+            match event {
+                VcEvent::Assert(attr_id) => sites.push(VcSite {
+                    fun_id: proc_vcs.fun_id,
+                    variant: proc_vcs.variant.clone(),
+                    attr_id: *attr_id,
+                }),
+                // Boogie inlines a procedure only once along a chain of calls (`{:inline 1}`).
+                VcEvent::Inline(callee) if !inlining.contains(callee) => {
+                    self.collect_sites(callee, inlining, sites)
+                }
+                VcEvent::Inline(_) => {}
+            }
+        }
+        inlining.pop();
+    }
+}
+
+/// The solver time spent on one verification condition.
+pub struct ConditionProfile {
+    /// The location of the spec condition the assertion was generated from.
+    pub loc: Loc,
+    /// The message reported if the condition does not hold.
+    pub message: String,
+    /// The pipeline pass which instrumented the assertion.
+    pub pass: String,
+    /// The solver time in seconds.
+    pub time: f64,
+    /// The outcome reported by Boogie, e.g. `Valid` or `TimeOut`.
+    pub outcome: String,
+}
+
+/// The solver time spent on one verified function variant.
+pub struct FunctionProfile {
+    pub fun_id: QualifiedId<FunId>,
+    pub variant: FunctionVariant,
+    /// The total time in seconds, as reported by Boogie for the procedure.
+    pub time: f64,
+    pub conditions: Vec<ConditionProfile>,
+}
+
+/// The profile of a Boogie run.
+#[derive(Default)]
+pub struct VcProfile {
+    pub functions: Vec<FunctionProfile>,
+}
+
+/// The splits of a procedure, as reported in the trace output of Boogie.
+#[derive(Debug, PartialEq)]
+struct ProcedureTrace {
+    procedure: String,
+    splits: Vec<SplitTrace>,
+    /// The total time in seconds, if Boogie reported it.
+    time: Option<f64>,
+}
+
+#[derive(Debug, PartialEq)]
+struct SplitTrace {
+    idx: usize,
+    time: f64,
+    outcome: String,
+}
+
+impl VcProfile {
+    /// Builds the profile from the trace output of Boogie.
+    pub fn from_trace(env: &GlobalEnv, targets: &FunctionTargetsHolder, out: &str) -> Self {
+        let recorded = env.get_extension::<VcSiteTable>();
+        let empty = VcSiteTable::default();
+        let table = recorded.as_deref().unwrap_or(&empty);
+
+        let mut profile = VcProfile::default();
+        for trace in parse_trace(out) {
+            let (fun_id, variant) = match table.get_variant(&trace.procedure) {
+                Some((fun_id, variant)) if matches!(variant, FunctionVariant::Verification(_)) => {
+                    (fun_id, variant.clone())
+                }
+                _ => continue,
+            };
+            let sites = table.get_sites(&trace.procedure);
+            let attributed = sites.len() == trace.splits.len();
+            if !attributed {
+                warn!(
+                    "cannot attribute the {} splits of `{}` to its {} verification conditions",
+                    trace.splits.len(),
+                    trace.procedure,
+                    sites.len()
+                );
+            }
+            let conditions = trace
+                .splits
+                .into_iter()
+                .map(|split| {
+                    let site = split
+                        .idx
+                        .checked_sub(1)
+                        .and_then(|idx| sites.get(idx))
+                        .filter(|_| attributed);
+                    Self::get_condition(env, targets, site, split)
+                })
+                .collect::<Vec<_>>();
+            // If Boogie did not report the total, e.g. because it was killed, fall back to the
+            // sum of the splits.
+            let time = trace
+                .time
+                .unwrap_or_else(|| conditions.iter().map(|c| c.time).sum());
+            profile.functions.push(FunctionProfile {
+                fun_id,
+                variant,
+                time,
+                conditions,
+            });
+        }
+        profile
+    }
+
+    /// Returns the profile of the condition checked by the split, which is unknown if the
+    /// split could not be attributed to an assertion.
+    fn get_condition(
+        env: &GlobalEnv,
+        targets: &FunctionTargetsHolder,
+        site: Option<&VcSite>,
+        split: SplitTrace,
+    ) -> ConditionProfile {
+        let data = site.and_then(|site| targets.get_data(&site.fun_id, &site.variant));
+        match (site, data) {
+            (Some(site), Some(data)) => {
+                let fun_env = env.get_function(site.fun_id);
+                let target = FunctionTarget::new(&fun_env, data);
+                ConditionProfile {
+                    loc: target.get_bytecode_loc(site.attr_id),
+                    message: target
+                        .get_vc_info(site.attr_id)
+                        .cloned()
+                        .unwrap_or_else(|| "unknown assertion failed".to_string()),
+                    pass: target
+                        .get_vc_pass(site.attr_id)
+                        .cloned()
+                        .unwrap_or_else(|| "unknown".to_string()),
+                    time: split.time,
+                    outcome: split.outcome,
+                }
+            }
+            _ => ConditionProfile {
+                loc: env.unknown_loc(),
+                message: format!("split #{}", split.idx),
+                pass: "unknown".to_string(),
+                time: split.time,
+                outcome: split.outcome,
+            },
+        }
+    }
+
+    /// Returns the accumulated solver time per pipeline pass.
+    pub fn get_pass_times(&self) -> BTreeMap<&str, f64> {
+        let mut result = BTreeMap::new();
+        for cond in self.functions.iter().flat_map(|f| f.conditions.iter()) {
+            *result.entry(cond.pass.as_str()).or_insert(0.0) += cond.time;
+        }
+        result
+    }
+
+    /// Renders a report of the hot spots: the functions ordered by the time spent on them,
+    /// each with its most expensive conditions, followed by the time per pipeline pass.
+    pub fn report(&self, env: &GlobalEnv) -> String {
+        let mut lines = vec![];
+        let total: f64 = self.functions.iter().map(|f| f.time).sum();
+        lines.push(format!(
+            "verification condition profile ({} functions, {:.3}s):",
+            self.functions.len(),
+            total
+        ));
+        for fun_profile in self
+            .functions
+            .iter()
+            .sorted_by(|f1, f2| f2.time.partial_cmp(&f1.time).unwrap())
+        {
+            let fun_env = env.get_function(fun_profile.fun_id);
+            let variant = match &fun_profile.variant {
+                FunctionVariant::Baseline
+                | FunctionVariant::Verification(VerificationFlavor::Regular) => "".to_string(),
+                FunctionVariant::Verification(flavor) => format!(" [{}]", flavor),
+            };
+            lines.push(format!(
+                "  {}{}: {:.3}s",
+                fun_env.get_full_name_str(),
+                variant,
+                fun_profile.time
+            ));
+            for cond in fun_profile
+                .conditions
+                .iter()
+                .sorted_by(|c1, c2| c2.time.partial_cmp(&c1.time).unwrap())
+                .take(MAX_CONDITIONS_PER_FUNCTION)
+            {
+                let share = if fun_profile.time > 0.0 {
+                    100.0 * cond.time / fun_profile.time
+                } else {
+                    0.0
+                };
+                lines.push(format!(
+                    "    {:>8.3}s {:>5.1}%  {} ({}) at {} [{}]",
+                    cond.time,
+                    share,
+                    cond.message,
+                    cond.pass,
+                    cond.loc.display(env),
+                    cond.outcome
+                ));
+            }
+        }
+        lines.push("solver time per pipeline pass:".to_string());
+        for (pass, time) in self
+            .get_pass_times()
+            .into_iter()
+            .sorted_by(|(_, t1), (_, t2)| t2.partial_cmp(t1).unwrap())
+        {
+            lines.push(format!("  {:>8.3}s  {}", time, pass));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Removes the lines produced by Boogie's `-trace` from the output, so the remaining output can
+/// be analyzed as usual.
+pub fn strip_trace_output(out: &str) -> String {
+    out.lines()
+        .filter(|line| !TRACE_LINE.is_match(line))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Extracts the splits of each verified procedure from the trace output of Boogie.
+fn parse_trace(out: &str) -> Vec<ProcedureTrace> {
+    let mut traces: Vec<ProcedureTrace> = vec![];
+    let mut in_procedure = false;
+    for line in out.lines() {
+        if let Some(cap) = VERIFYING_PROC.captures(line) {
+            traces.push(ProcedureTrace {
+                procedure: cap["proc"].to_string(),
+                splits: vec![],
+                time: None,
+            });
+            in_procedure = true;
+        } else if let Some(cap) = SPLIT_DONE.captures(line) {
+            if let (true, Some(trace)) = (in_procedure, traces.last_mut()) {
+                trace.splits.push(SplitTrace {
+                    idx: cap["idx"].parse::<usize>().unwrap_or_default(),
+                    time: cap["time"].parse::<f64>().unwrap_or_default(),
+                    outcome: cap["outcome"].trim().to_string(),
+                });
+            }
+        } else if let Some(cap) = PROC_DONE.captures(line) {
+            if let (true, Some(trace)) = (in_procedure, traces.last_mut()) {
+                trace.time = cap["time"].parse::<f64>().ok();
+                in_procedure = false;
+            }
+        }
+    }
+    traces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_model::model::ModuleId;
+
+    #[test]
+    fn test_parse_trace() {
+        let out = "Parsing output.bpl\n\
+                   Verifying $42_M_f$verify ...\n\
+                   \x20 checking split 1/2, 0.00%, (cost:12/12 last) ...\n\
+                   \x20 --> split #1 done,  [0.02 s] Valid\n\
+                   \x20 checking split 2/2, 50.00%, (cost:30/30 last) ...\n\
+                   \x20 --> split #2 done,  [1.50 s] TimeOut\n\
+                   \x20 [1.60 s, solver resource count: 123]  verified\n\
+                   Verifying $42_M_g$verify ...\n\
+                   \x20 --> split #1 done,  [0.10 s] Valid\n";
+        let traces = parse_trace(out);
+        assert_eq!(
+            traces,
+            vec![
+                ProcedureTrace {
+                    procedure: "$42_M_f$verify".to_string(),
+                    splits: vec![
+                        SplitTrace {
+                            idx: 1,
+                            time: 0.02,
+                            outcome: "Valid".to_string()
+                        },
+                        SplitTrace {
+                            idx: 2,
+                            time: 1.5,
+                            outcome: "TimeOut".to_string()
+                        },
+                    ],
+                    time: Some(1.6),
+                },
+                ProcedureTrace {
+                    procedure: "$42_M_g$verify".to_string(),
+                    splits: vec![SplitTrace {
+                        idx: 1,
+                        time: 0.1,
+                        outcome: "Valid".to_string()
+                    }],
+                    time: None,
+                },
+            ]
+        );
+        assert_eq!(strip_trace_output(out), "");
+    }
+
+    #[test]
+    fn test_sites_follow_inlining() {
+        let env = GlobalEnv::new();
+        let fun_id =
+            |name: &str| ModuleId::new(0).qualified(FunId::new(env.symbol_pool().make(name)));
+        let verify = FunctionVariant::Verification(VerificationFlavor::Regular);
+        let mut table = VcSiteTable::default();
+        table.add_procedure("f$verify".to_string(), fun_id("f"), verify.clone());
+        table.add_event("f$verify", VcEvent::Assert(AttrId::new(1)));
+        table.add_event("f$verify", VcEvent::Inline("g".to_string()));
+        table.add_event(
+            "f$verify",
+            VcEvent::Inline("$1_Vector_length'u8'".to_string()),
+        );
+        table.add_event("f$verify", VcEvent::Assert(AttrId::new(5)));
+        table.add_procedure("g".to_string(), fun_id("g"), FunctionVariant::Baseline);
+        table.add_event("g", VcEvent::Assert(AttrId::new(2)));
+        // A recursive call is not inlined again.
+        table.add_event("g", VcEvent::Inline("g".to_string()));
+        table.add_event("g", VcEvent::Assert(AttrId::new(3)));
+
+        let site = |name: &str, variant: &FunctionVariant, idx: usize| VcSite {
+            fun_id: fun_id(name),
+            variant: variant.clone(),
+            attr_id: AttrId::new(idx),
+        };
+        assert_eq!(
+            table.get_sites("f$verify"),
+            vec![
+                site("f", &verify, 1),
+                site("g", &FunctionVariant::Baseline, 2),
+                site("g", &FunctionVariant::Baseline, 3),
+                site("f", &verify, 5),
+            ]
+        );
+        assert!(table.get_sites("unknown").is_empty());
+    }
+}
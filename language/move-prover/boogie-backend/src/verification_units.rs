@@ -0,0 +1,265 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Modular verification of the generated Boogie code.
+//!
+//! Instead of verifying all procedures in a single Boogie run, each verified function variant
+//! becomes an independent verification unit: a Boogie run on the same file which is restricted
+//! to the procedure of the variant. The units are dispatched to a pool of workers, each running
+//! one Boogie process at a time. Every worker has its own queue of units; a worker whose queue is
+//! empty steals units from the queues of the others, so that a few expensive units do not leave
+//! the remaining workers idle. Results are delivered in the order the units finish.
+
+use std::{
+    collections::VecDeque,
+    io::Read,
+    process::{Command, Output, Stdio},
+    sync::{
+        mpsc::{channel, Receiver},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{debug, warn};
+
+use bytecode::function_target_pipeline::{FunctionTargetsHolder, FunctionVariant};
+use move_model::{
+    model::{FunId, GlobalEnv, QualifiedId},
+    pragmas::TIMEOUT_PRAGMA,
+};
+
+use crate::{
+    boogie_helpers::{boogie_function_name, boogie_verification_suffix},
+    options::BoogieOptions,
+};
+
+/// The interval in which a worker checks whether the Boogie process of its unit has finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Without a hard timeout, a unit is abandoned after this multiple of the solver timeout of its
+/// function, as Boogie may run several solver queries for a procedure.
+const UNIT_TIMEOUT_FACTOR: u64 = 4;
+
+/// Time added to the timeout of a unit for Boogie to parse and type check the file.
+const UNIT_STARTUP_SECS: u64 = 10;
+
+/// A verified function variant, verified in a Boogie run of its own.
+pub struct VerificationUnit {
+    pub fun_id: QualifiedId<FunId>,
+    pub variant: FunctionVariant,
+    /// The name of the Boogie procedure of the variant.
+    pub procedure: String,
+    /// The time after which the Boogie process of the unit is killed.
+    pub timeout: Duration,
+}
+
+/// The outcome of running the Boogie process of a unit.
+pub struct UnitRun {
+    /// The output of the process, or None if it exceeded the timeout of the unit.
+    pub output: Option<std::io::Result<Output>>,
+    /// The wall clock time the unit took.
+    pub elapsed: Duration,
+}
+
+/// Returns the verification units for the verified variants in the targets, the most expensive
+/// ones first, estimating the cost of a unit by the size of its code.
+pub fn collect_units(
+    env: &GlobalEnv,
+    targets: &FunctionTargetsHolder,
+    options: &BoogieOptions,
+) -> Vec<VerificationUnit> {
+    let mut units = vec![];
+    for (fun_id, variant) in targets.get_funs_and_variants() {
+        let fun_env = env.get_function(fun_id);
+        let procedure = match &variant {
+            FunctionVariant::Verification(flavor) => format!(
+                "{}{}",
+                boogie_function_name(&fun_env, &[]),
+                boogie_verification_suffix(flavor)
+            ),
+            FunctionVariant::Baseline => continue,
+        };
+        if fun_env.is_native_or_intrinsic() {
+            continue;
+        }
+        let cost = targets.get_target(&fun_env, &variant).get_bytecode().len();
+        let timeout = if options.hard_timeout_secs > 0 {
+            options.hard_timeout_secs
+        } else {
+            let vc_timeout = fun_env.get_num_pragma(TIMEOUT_PRAGMA, || options.vc_timeout);
+            options.adjust_timeout(vc_timeout) as u64 * UNIT_TIMEOUT_FACTOR + UNIT_STARTUP_SECS
+        };
+        let unit = VerificationUnit {
+            fun_id,
+            variant,
+            procedure,
+            timeout: Duration::from_secs(timeout),
+        };
+        units.push((cost, unit));
+    }
+    units.sort_by(|(cost1, _), (cost2, _)| cost2.cmp(cost1));
+    units.into_iter().map(|(_, unit)| unit).collect()
+}
+
+impl VerificationUnit {
+    /// Returns the command line verifying the unit, given the command line verifying the whole
+    /// file, which ends with the file.
+    pub fn boogie_command(&self, file_command: &[String]) -> Vec<String> {
+        let (file, flags) = file_command.split_last().expect("boogie command with file");
+        let mut args = flags.to_vec();
+        args.push(format!("-proc:{}", self.procedure));
+        args.push(file.clone());
+        args
+    }
+}
+
+/// A command to run as part of a verification unit.
+struct Job {
+    unit_idx: usize,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+/// Runs the given commands, each with its timeout, on a pool of `num_workers` workers. Returns a
+/// receiver which delivers the index of each command together with its outcome, in the order the
+/// commands finish. Dropping the receiver stops the workers after their current command.
+pub fn dispatch_units(
+    commands: Vec<(Vec<String>, Duration)>,
+    num_workers: usize,
+) -> Receiver<(usize, UnitRun)> {
+    let num_workers = num_workers.max(1).min(commands.len().max(1));
+    // Deal the commands round robin, so that each worker starts with its share of the most
+    // expensive units.
+    let mut queues = (0..num_workers)
+        .map(|_| VecDeque::new())
+        .collect::<Vec<_>>();
+    for (unit_idx, (args, timeout)) in commands.into_iter().enumerate() {
+        queues[unit_idx % num_workers].push_back(Job {
+            unit_idx,
+            args,
+            timeout,
+        });
+    }
+    let queues = Arc::new(queues.into_iter().map(Mutex::new).collect::<Vec<_>>());
+    let (result_tx, results) = channel();
+    for worker_idx in 0..num_workers {
+        let queues = queues.clone();
+        let result_tx = result_tx.clone();
+        thread::spawn(move || {
+            while let Some(job) = next_job(&queues, worker_idx) {
+                debug!("worker {} running `{}`", worker_idx, job.args.join(" "));
+                let run = run_with_timeout(&job.args, job.timeout);
+                if result_tx.send((job.unit_idx, run)).is_err() {
+                    // The receiver is gone, nobody is interested in the remaining units.
+                    break;
+                }
+            }
+        });
+    }
+    results
+}
+
+/// Takes the next job of the worker: the front of its own queue, or, if that is empty, the back
+/// of the queue of another worker.
+fn next_job(queues: &[Mutex<VecDeque<Job>>], worker_idx: usize) -> Option<Job> {
+    if let Some(job) = queues[worker_idx].lock().unwrap().pop_front() {
+        return Some(job);
+    }
+    (1..queues.len())
+        .map(|offset| (worker_idx + offset) % queues.len())
+        .find_map(|victim| queues[victim].lock().unwrap().pop_back())
+}
+
+/// Runs the command, killing it if it does not terminate within the timeout. Solver processes
+/// started by the killed process are bounded by their own timeouts.
+fn run_with_timeout(args: &[String], timeout: Duration) -> UnitRun {
+    let start = Instant::now();
+    let child = Command::new(&args[0])
+        .args(&args[1..])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => {
+            return UnitRun {
+                output: Some(Err(err)),
+                elapsed: start.elapsed(),
+            }
+        }
+    };
+    // Drain the output while the process runs, so it does not block on a full pipe.
+    let read_all = |mut source: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut bytes = vec![];
+            let _ = source.read_to_end(&mut bytes);
+            bytes
+        })
+    };
+    let stdout = read_all(Box::new(child.stdout.take().expect("piped stdout")));
+    let stderr = read_all(Box::new(child.stderr.take().expect("piped stderr")));
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(Ok(status)),
+            Ok(None) if start.elapsed() >= timeout => {
+                if let Err(err) = child.kill() {
+                    warn!("cannot kill `{}`: {}", args[0], err);
+                }
+                let _ = child.wait();
+                break None;
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(err) => break Some(Err(err)),
+        }
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    UnitRun {
+        output: status.map(|status| {
+            status.map(|status| Output {
+                status,
+                stdout,
+                stderr,
+            })
+        }),
+        elapsed: start.elapsed(),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn command(script: &str, timeout_ms: u64) -> (Vec<String>, Duration) {
+        let args = vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        (args, Duration::from_millis(timeout_ms))
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let commands = vec![
+            command("exec sleep 10", 200),
+            command("echo first", 10_000),
+            command("echo second; exit 3", 10_000),
+            command("echo third", 10_000),
+        ];
+        let mut runs = dispatch_units(commands, 2).iter().collect::<Vec<_>>();
+        // The units stolen from the worker stuck on the first one finish before it.
+        assert_eq!(runs.last().unwrap().0, 0);
+        runs.sort_by_key(|(unit_idx, _)| *unit_idx);
+        assert_eq!(runs.len(), 4);
+        assert!(runs[0].1.output.is_none());
+        let stdout = |run: &UnitRun| {
+            let output = run.output.as_ref().unwrap().as_ref().unwrap();
+            (
+                String::from_utf8_lossy(&output.stdout).to_string(),
+                output.status.code(),
+            )
+        };
+        assert_eq!(stdout(&runs[1].1), ("first\n".to_string(), Some(0)));
+        assert_eq!(stdout(&runs[2].1), ("second\n".to_string(), Some(3)));
+        assert_eq!(stdout(&runs[3].1), ("third\n".to_string(), Some(0)));
+    }
+}
@@ -3,16 +3,31 @@
 
 use itertools::Itertools;
 use std::{
-    any::{Any, TypeId},
-    collections::BTreeMap,
+    any::{type_name, Any, TypeId},
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
     fmt::{Debug, Formatter, Result},
     rc::Rc,
 };
 
 /// A container for an extensible, dynamically typed set of annotations.
+///
+/// Annotations are either summaries, which describe the behavior of a function and remain valid
+/// under semantics-preserving transformations, or code-dependent, which refer to the code of the
+/// function (e.g. by code offset) and become stale once the code is transformed. Reading a stale
+/// annotation is a bug, which is caught by a debug assertion.
 #[derive(Default, Clone)]
 pub struct Annotations {
     map: BTreeMap<TypeId, Data>,
+    /// The current generation. Each pipeline stage processing the function starts a new one.
+    generation: usize,
+    /// Whether reads and writes of annotations are recorded. This is only needed to render the
+    /// pipeline graph, and off by default.
+    track_accesses: bool,
+    /// The names of the annotation types read since the accesses were last taken.
+    reads: RefCell<BTreeSet<&'static str>>,
+    /// The names of the annotation types written since the accesses were last taken.
+    writes: BTreeSet<&'static str>,
 }
 
 /// An internal struct to represent annotation data. This carries in addition to the
@@ -22,18 +37,37 @@ pub struct Annotations {
 struct Data {
     value: Box<dyn Any>,
     clone_fun: Rc<dyn Fn(&Box<dyn Any>) -> Box<dyn Any>>,
+    name: &'static str,
+    /// The generation in which this annotation was set.
+    generation: usize,
+    /// Whether this annotation refers to the code of the function.
+    code_dependent: bool,
+    /// Whether the code has been transformed since this annotation was set.
+    stale: bool,
 }
 
 impl Data {
-    fn new<T: Any + Clone>(x: T) -> Self {
+    fn new<T: Any + Clone>(x: T, generation: usize, code_dependent: bool) -> Self {
         let clone_fun = Rc::new(|x: &Box<dyn Any>| -> Box<dyn Any> {
             Box::new(x.downcast_ref::<T>().unwrap().clone())
         });
         Self {
             value: Box::new(x),
             clone_fun,
+            name: type_name::<T>(),
+            generation,
+            code_dependent,
+            stale: false,
         }
     }
+
+    fn check_not_stale(&self) {
+        debug_assert!(
+            !self.stale,
+            "annotation `{}` is stale: the code has been transformed since it was computed",
+            self.name
+        );
+    }
 }
 
 impl Clone for Data {
@@ -41,6 +75,10 @@ impl Clone for Data {
         Self {
             value: (self.clone_fun)(&self.value),
             clone_fun: self.clone_fun.clone(),
+            name: self.name,
+            generation: self.generation,
+            code_dependent: self.code_dependent,
+            stale: self.stale,
         }
     }
 }
@@ -62,27 +100,74 @@ impl Annotations {
         self.map.contains_key(&id)
     }
 
+    /// Tests whether annotation of type T is present but stale.
+    pub fn is_stale<T: Any>(&self) -> bool {
+        let id = TypeId::of::<T>();
+        self.map.get(&id).map_or(false, |d| d.stale)
+    }
+
     /// Gets annotation of type T.
     pub fn get<T: Any>(&self) -> Option<&T> {
         let id = TypeId::of::<T>();
-        self.map.get(&id).and_then(|d| d.value.downcast_ref::<T>())
+        self.map.get(&id).and_then(|d| {
+            d.check_not_stale();
+            if self.track_accesses {
+                self.reads.borrow_mut().insert(d.name);
+            }
+            d.value.downcast_ref::<T>()
+        })
     }
 
     /// Gets annotation of type T or creates one from default.
     pub fn get_or_default_mut<T: Any + Default + Clone>(&mut self) -> &mut T {
         let id = TypeId::of::<T>();
-        self.map
+        let generation = self.generation;
+        let data = self
+            .map
             .entry(id)
-            .or_insert_with(|| Data::new(T::default()))
-            .value
-            .downcast_mut::<T>()
-            .expect("cast successful")
+            .or_insert_with(|| Data::new(T::default(), generation, false));
+        data.check_not_stale();
+        if self.track_accesses {
+            self.writes.insert(data.name);
+        }
+        data.value.downcast_mut::<T>().expect("cast successful")
     }
 
-    /// Sets annotation of type T.
+    /// Sets annotation of type T, as a summary which stays valid when the code is transformed.
     pub fn set<T: Any + Clone>(&mut self, x: T) {
         let id = TypeId::of::<T>();
-        self.map.insert(id, Data::new(x));
+        self.record_write(type_name::<T>());
+        self.map.insert(id, Data::new(x, self.generation, false));
+    }
+
+    /// Sets annotation of type T, as one which refers to the current code and becomes stale
+    /// when the code is transformed, unless the transformation declares to preserve it.
+    pub fn set_code_dependent<T: Any + Clone>(&mut self, x: T) {
+        let id = TypeId::of::<T>();
+        self.record_write(type_name::<T>());
+        self.map.insert(id, Data::new(x, self.generation, true));
+    }
+
+    /// Tests whether any code-dependent annotation is present.
+    pub fn has_code_dependent(&self) -> bool {
+        self.map.values().any(|d| d.code_dependent && !d.stale)
+    }
+
+    /// Starts a new generation. Annotations set from now on are not affected by invalidations
+    /// of the previous generations.
+    pub fn new_generation(&mut self) {
+        self.generation += 1;
+    }
+
+    /// Marks code-dependent annotations set before the current generation as stale, except
+    /// those of the given types. To be called after the code has been transformed.
+    pub fn invalidate_code_dependent(&mut self, preserved: &BTreeSet<TypeId>) {
+        let generation = self.generation;
+        for (id, data) in self.map.iter_mut() {
+            if data.code_dependent && data.generation < generation && !preserved.contains(id) {
+                data.stale = true;
+            }
+        }
     }
 
     /// Removes annotation of type T.
@@ -92,4 +177,111 @@ impl Annotations {
             .remove(&id)
             .and_then(|d| d.value.downcast::<T>().ok())
     }
+
+    /// Sets whether reads and writes of annotations are recorded, to be retrieved with
+    /// `take_accesses`.
+    pub fn set_track_accesses(&mut self, track: bool) {
+        self.track_accesses = track;
+    }
+
+    fn record_write(&mut self, name: &'static str) {
+        if self.track_accesses {
+            self.writes.insert(name);
+        }
+    }
+
+    /// Returns the names of the annotation types read and written, respectively, since the last
+    /// call, and resets them.
+    pub fn take_accesses(&mut self) -> (BTreeSet<&'static str>, BTreeSet<&'static str>) {
+        (
+            std::mem::take(self.reads.get_mut()),
+            std::mem::take(&mut self.writes),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Summary(u64);
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct ByOffset(Vec<u16>);
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct AlsoByOffset(Vec<u16>);
+
+    #[test]
+    fn test_invalidate_code_dependent() {
+        let mut annotations = Annotations::default();
+        annotations.new_generation();
+        annotations.set(Summary(1));
+        annotations.set_code_dependent(ByOffset(vec![0, 2]));
+        annotations.set_code_dependent(AlsoByOffset(vec![1]));
+        assert!(annotations.has_code_dependent());
+
+        // A later stage transforms the code, declaring one of the annotations as preserved.
+        annotations.new_generation();
+        let preserved = vec![TypeId::of::<AlsoByOffset>()].into_iter().collect();
+        annotations.invalidate_code_dependent(&preserved);
+        assert!(!annotations.is_stale::<Summary>());
+        assert_eq!(annotations.get::<Summary>(), Some(&Summary(1)));
+        assert!(annotations.is_stale::<ByOffset>());
+        assert!(!annotations.is_stale::<AlsoByOffset>());
+        assert!(annotations.has_code_dependent());
+    }
+
+    #[test]
+    fn test_invalidate_spares_current_generation() {
+        let mut annotations = Annotations::default();
+        annotations.new_generation();
+        annotations.set_code_dependent(ByOffset(vec![0]));
+
+        // The stage setting the annotation transformed the code before computing it.
+        annotations.invalidate_code_dependent(&BTreeSet::new());
+        assert!(!annotations.is_stale::<ByOffset>());
+
+        // Setting a stale annotation again makes it valid.
+        annotations.new_generation();
+        annotations.invalidate_code_dependent(&BTreeSet::new());
+        assert!(annotations.is_stale::<ByOffset>());
+        assert!(!annotations.has_code_dependent());
+        annotations.set_code_dependent(ByOffset(vec![3]));
+        assert_eq!(annotations.get::<ByOffset>(), Some(&ByOffset(vec![3])));
+    }
+
+    #[test]
+    fn test_track_accesses() {
+        let mut annotations = Annotations::default();
+        annotations.set(Summary(1));
+        annotations.get::<Summary>();
+        assert_eq!(
+            annotations.take_accesses(),
+            (BTreeSet::new(), BTreeSet::new())
+        );
+
+        annotations.set_track_accesses(true);
+        annotations.set_code_dependent(ByOffset(vec![0]));
+        annotations.get::<Summary>();
+        annotations.get::<AlsoByOffset>();
+        let (reads, writes) = annotations.take_accesses();
+        assert_eq!(reads, vec![type_name::<Summary>()].into_iter().collect());
+        assert_eq!(writes, vec![type_name::<ByOffset>()].into_iter().collect());
+        assert_eq!(
+            annotations.take_accesses(),
+            (BTreeSet::new(), BTreeSet::new())
+        );
+    }
+
+    #[test]
+    fn test_clone_keeps_staleness() {
+        let mut annotations = Annotations::default();
+        annotations.set_code_dependent(ByOffset(vec![0]));
+        annotations.new_generation();
+        annotations.invalidate_code_dependent(&BTreeSet::new());
+        let cloned = annotations.clone();
+        assert!(cloned.is_stale::<ByOffset>());
+    }
 }
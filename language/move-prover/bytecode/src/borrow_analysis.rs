@@ -371,7 +371,7 @@ impl FunctionTargetProcessor for BorrowAnalysisProcessor {
         // Annotate function target with computed borrow data.
         data.annotations
             .borrow_mut()
-            .set::<BorrowAnnotation>(borrow_annotation);
+            .set_code_dependent::<BorrowAnnotation>(borrow_annotation);
         data.annotations.borrow_mut().remove::<LiveVarAnnotation>();
         data
     }
@@ -599,7 +599,7 @@ impl<'a> TransferFunctions for BorrowAnalysis<'a> {
                                 callee_target.get_return_type(idx).is_mutable_reference()
                             });
                             if has_muts {
-                                callee_target.global_env().error(&self.func_target.get_bytecode_loc(*id),
+                                self.targets.diagnostics().error(&self.func_target.get_bytecode_loc(*id),
                                     "restriction: recursive functions which return `&mut` values not supported");
                             }
                         }
@@ -55,6 +55,10 @@ impl FunctionTargetProcessor for CleanAndOptimizeProcessor {
     fn name(&self) -> String {
         "clean_and_optimize".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 // Analysis
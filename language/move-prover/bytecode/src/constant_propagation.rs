@@ -0,0 +1,495 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interprocedural constant propagation.
+//!
+//! For each point in a function, this computes which temporaries hold a constant, or the value a
+//! parameter had on entry. The analysis is compositional: the summary of a function records
+//! which of its return values are constants or passed-through parameters, so that the results of
+//! a call can be derived from its arguments. Callees without a summary (natives, or functions in
+//! a recursive cycle) are treated as returning unknown values.
+//!
+//! The summary also records the arguments each function passes to its callees. Joined over all
+//! callers, these give the parameters which are passed the same constant at every call of a
+//! function (see `get_constant_params`). This is only sound for functions which cannot be called
+//! from outside of the environment, and only for the baseline variant, which is reached through
+//! these calls; the verification variant is checked for all values of the parameters.
+//!
+//! Summaries are computed on the baseline code. Later passes can rerun the intraprocedural part
+//! of the analysis on instrumented code, using the summaries, to find the temporaries holding a
+//! constant at each instruction (see `get_constant_temps`), and simplify the spec conditions
+//! referring to them (see `simplify_exp`).
+
+use crate::{
+    compositional_analysis::{CompositionalAnalysis, SummaryCache},
+    dataflow_analysis::{DataflowAnalysis, TransferFunctions},
+    dataflow_domains::{AbstractDomain, JoinResult, MapDomain},
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    stackless_bytecode::{AbortAction, BorrowNode, Bytecode, Constant, HavocKind, Operation},
+    stackless_control_flow_graph::StacklessControlFlowGraph,
+};
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::{self, Exp, ExpData, TempIndex, Value},
+    exp_rewriter::ExpRewriterFunctions,
+    model::{FunId, FunctionEnv, GlobalEnv, NodeId, QualifiedId},
+};
+use num::BigInt;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Formatter},
+};
+
+/// The abstract value of a temporary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstValue {
+    /// The temporary holds the given constant.
+    Constant(Constant),
+    /// The temporary holds the value the parameter with the given index had on entry.
+    Param(TempIndex),
+    /// The value of the temporary is not known.
+    Unknown,
+}
+
+impl AbstractDomain for ConstValue {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        if self == other || *self == ConstValue::Unknown {
+            JoinResult::Unchanged
+        } else {
+            *self = ConstValue::Unknown;
+            JoinResult::Changed
+        }
+    }
+}
+
+/// The constant propagation summary of a function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConstantSummary {
+    /// For the return values which are known on all paths that return, their value.
+    pub returns: MapDomain<usize, ConstValue>,
+    /// For each function called, the values of its arguments, joined over all calls.
+    pub call_args: MapDomain<QualifiedId<FunId>, MapDomain<usize, ConstValue>>,
+}
+
+impl AbstractDomain for ConstantSummary {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        self.returns
+            .join(&other.returns)
+            .combine(self.call_args.join(&other.call_args))
+    }
+}
+
+impl ConstantSummary {
+    /// Returns the value of the return value with the given index, if it is known.
+    pub fn get_return(&self, idx: usize) -> Option<&ConstValue> {
+        self.returns
+            .get(&idx)
+            .filter(|value| **value != ConstValue::Unknown)
+    }
+}
+
+/// Gets the constant propagation summary of the function, if it has been computed.
+pub fn get_constant_summary<'env>(target: &FunctionTarget<'env>) -> Option<&'env ConstantSummary> {
+    target.get_annotations().get::<ConstantSummary>()
+}
+
+/// Returns the parameters of the function which are passed the same constant at all calls of
+/// the function. This is only known for functions which are not exposed outside of their module,
+/// once all their callers have been analyzed.
+pub fn get_constant_params(
+    targets: &FunctionTargetsHolder,
+    func_env: &FunctionEnv<'_>,
+) -> BTreeMap<TempIndex, Constant> {
+    if func_env.is_exposed() {
+        return BTreeMap::new();
+    }
+    let fun_id = func_env.get_qualified_id();
+    let mut params: Option<MapDomain<usize, ConstValue>> = None;
+    for caller_id in func_env.get_calling_functions() {
+        let summary = match targets
+            .get_data(&caller_id, &FunctionVariant::Baseline)
+            .and_then(|data| data.annotations.get::<ConstantSummary>())
+        {
+            Some(summary) => summary,
+            None => return BTreeMap::new(),
+        };
+        // Calls which have been inlined into the caller do not reach the function.
+        if let Some(args) = summary.call_args.get(&fun_id) {
+            match &mut params {
+                Some(joined) => {
+                    joined.join(args);
+                }
+                None => params = Some(args.clone()),
+            }
+        }
+    }
+    params
+        .into_iter()
+        .flatten()
+        .filter_map(|(idx, value)| match value {
+            ConstValue::Constant(c) => Some((idx, c)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns, for each instruction of the function, the temporaries which hold a constant before
+/// it. In the baseline variant, this includes the parameters which are passed the same constant
+/// at all calls of the function.
+pub fn get_constant_temps(
+    targets: &FunctionTargetsHolder,
+    target: &FunctionTarget<'_>,
+) -> BTreeMap<CodeOffset, BTreeMap<TempIndex, Constant>> {
+    let mut state = initial_state(target);
+    if target.data.variant == FunctionVariant::Baseline {
+        for (idx, c) in get_constant_params(targets, target.func_env) {
+            state.set(idx, ConstValue::Constant(c));
+        }
+    }
+    let analysis = ConstantPropagation {
+        cache: SummaryCache::new(targets, target.global_env()),
+    };
+    analysis
+        .states_per_instruction(target, state)
+        .into_iter()
+        .map(|(offset, before)| {
+            let constants = before
+                .values
+                .iter()
+                .filter_map(|(temp, value)| match value {
+                    ConstValue::Constant(c) => Some((*temp, c.clone())),
+                    _ => None,
+                })
+                .collect();
+            (offset, constants)
+        })
+        .collect()
+}
+
+/// Simplifies a spec expression, given the temporaries which hold a constant where it is
+/// evaluated: the temporaries are replaced by their values, and boolean operations and
+/// comparisons on values are folded.
+pub fn simplify_exp(exp: Exp, constants: &BTreeMap<TempIndex, Constant>) -> Exp {
+    ConstantFolder { constants }.rewrite_exp(exp)
+}
+
+/// The state on entry: each parameter holds its own value.
+fn initial_state(target: &FunctionTarget<'_>) -> ConstState {
+    let mut state = ConstState::default();
+    for idx in 0..target.get_parameter_count() {
+        if !target.get_local_type(idx).is_reference() {
+            state.set(idx, ConstValue::Param(idx));
+        }
+    }
+    state
+}
+
+// =================================================================================================
+// Processor
+
+pub struct ConstantPropagationProcessor();
+
+impl ConstantPropagationProcessor {
+    pub fn new() -> Box<Self> {
+        Box::new(Self())
+    }
+}
+
+impl FunctionTargetProcessor for ConstantPropagationProcessor {
+    fn process(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        func_env: &FunctionEnv<'_>,
+        mut data: FunctionData,
+    ) -> FunctionData {
+        let summary = {
+            let target = FunctionTarget::new(func_env, &data);
+            let analysis = ConstantPropagation {
+                cache: SummaryCache::new(targets, func_env.module_env.env),
+            };
+            let mut summary = analysis.summarize(&target, initial_state(&target));
+            summary.call_args = analysis.collect_call_args(&target);
+            summary
+        };
+        data.annotations.set(summary);
+        data
+    }
+
+    fn name(&self) -> String {
+        "constant_propagation".to_string()
+    }
+
+    fn dump_result(
+        &self,
+        f: &mut Formatter<'_>,
+        env: &GlobalEnv,
+        targets: &FunctionTargetsHolder,
+    ) -> fmt::Result {
+        writeln!(
+            f,
+            "\n\n********* Result of constant propagation *********\n\n"
+        )?;
+        for module in env.get_modules() {
+            if !module.is_target() {
+                continue;
+            }
+            for fun in module.get_functions() {
+                let target = targets.get_target(&fun, &FunctionVariant::Baseline);
+                let summary = match get_constant_summary(&target) {
+                    Some(summary) => summary,
+                    None => continue,
+                };
+                writeln!(f, "function {} {{", fun.get_full_name_str())?;
+                for (idx, c) in get_constant_params(targets, &fun) {
+                    writeln!(
+                        f,
+                        "  {} = {}",
+                        target.get_local_name(idx).display(target.symbol_pool()),
+                        c
+                    )?;
+                }
+                for idx in 0..target.get_return_count() {
+                    match summary.get_return(idx) {
+                        Some(ConstValue::Constant(c)) => writeln!(f, "  result_{} = {}", idx, c)?,
+                        Some(ConstValue::Param(param)) => writeln!(
+                            f,
+                            "  result_{} = {}",
+                            idx,
+                            target.get_local_name(*param).display(target.symbol_pool())
+                        )?,
+                        _ => {}
+                    }
+                }
+                writeln!(f, "}}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// =================================================================================================
+// Analysis
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ConstState {
+    /// The values of the temporaries which have been defined.
+    values: MapDomain<TempIndex, ConstValue>,
+    /// The values returned on the paths reaching this point.
+    returns: MapDomain<usize, ConstValue>,
+}
+
+impl AbstractDomain for ConstState {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        self.values
+            .join(&other.values)
+            .combine(self.returns.join(&other.returns))
+    }
+}
+
+impl ConstState {
+    fn get(&self, temp: TempIndex) -> ConstValue {
+        self.values
+            .get(&temp)
+            .cloned()
+            .unwrap_or(ConstValue::Unknown)
+    }
+
+    fn set(&mut self, temp: TempIndex, value: ConstValue) {
+        self.values.insert(temp, value);
+    }
+
+    fn forget(&mut self, temp: TempIndex) {
+        self.values.insert(temp, ConstValue::Unknown);
+    }
+}
+
+struct ConstantPropagation<'a> {
+    cache: SummaryCache<'a>,
+}
+
+impl<'a> ConstantPropagation<'a> {
+    /// Folds an operation on constant arguments, if supported.
+    fn fold(oper: &Operation, args: &[ConstValue]) -> ConstValue {
+        use ConstValue::Constant as C;
+        use Constant::Bool;
+        use Operation::*;
+        match (oper, args) {
+            (Not, [C(Bool(b))]) => C(Bool(!b)),
+            (And, [C(Bool(b1)), C(Bool(b2))]) => C(Bool(*b1 && *b2)),
+            (Or, [C(Bool(b1)), C(Bool(b2))]) => C(Bool(*b1 || *b2)),
+            (Eq, [C(c1), C(c2)]) => C(Bool(c1 == c2)),
+            (Neq, [C(c1), C(c2)]) => C(Bool(c1 != c2)),
+            _ => ConstValue::Unknown,
+        }
+    }
+
+    /// Returns the state before each reachable instruction of the function.
+    fn states_per_instruction(
+        &self,
+        target: &FunctionTarget<'_>,
+        initial_state: ConstState,
+    ) -> BTreeMap<CodeOffset, ConstState> {
+        let code = target.get_bytecode();
+        if code.is_empty() {
+            return BTreeMap::new();
+        }
+        let cfg = StacklessControlFlowGraph::new_forward(code);
+        let state_map = self.analyze_function(initial_state, code, &cfg);
+        self.state_per_instruction(state_map, code, &cfg, |before, _| before.clone())
+    }
+
+    /// Returns the values of the arguments of the calls in the function, joined per callee.
+    fn collect_call_args(
+        &self,
+        target: &FunctionTarget<'_>,
+    ) -> MapDomain<QualifiedId<FunId>, MapDomain<usize, ConstValue>> {
+        let code = target.get_bytecode();
+        let mut call_args = MapDomain::default();
+        for (offset, before) in self.states_per_instruction(target, initial_state(target)) {
+            if let Bytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _) =
+                &code[offset as usize]
+            {
+                let args = srcs
+                    .iter()
+                    .map(|src| before.get(*src))
+                    .enumerate()
+                    .collect();
+                call_args.insert_join(mid.qualified(*fid), args);
+            }
+        }
+        call_args
+    }
+}
+
+impl<'a> TransferFunctions for ConstantPropagation<'a> {
+    type State = ConstState;
+    const BACKWARD: bool = false;
+
+    fn execute(&self, state: &mut ConstState, instr: &Bytecode, _offset: CodeOffset) {
+        use Bytecode::*;
+        use Operation::*;
+        match instr {
+            Assign(_, dest, src, _) => {
+                let value = state.get(*src);
+                state.set(*dest, value);
+            }
+            Load(_, dest, c) => state.set(*dest, ConstValue::Constant(c.clone())),
+            Ret(_, srcs) => {
+                for (idx, src) in srcs.iter().enumerate() {
+                    let value = state.get(*src);
+                    state.returns.insert(idx, value);
+                }
+            }
+            Call(_, dests, oper, srcs, on_abort) => {
+                match oper {
+                    Function(mid, fid, _) => {
+                        let summary = self.cache.get::<ConstantSummary>(
+                            mid.qualified(*fid),
+                            &FunctionVariant::Baseline,
+                        );
+                        for (idx, dest) in dests.iter().enumerate() {
+                            let value = match summary.and_then(|s| s.get_return(idx)) {
+                                Some(ConstValue::Param(param)) => srcs
+                                    .get(*param)
+                                    .map(|src| state.get(*src))
+                                    .unwrap_or(ConstValue::Unknown),
+                                Some(value) => value.clone(),
+                                None => ConstValue::Unknown,
+                            };
+                            state.set(*dest, value);
+                        }
+                    }
+                    // The borrowed local may be modified through the reference.
+                    BorrowLoc | Havoc(HavocKind::Value) => {
+                        for temp in srcs.iter().chain(dests.iter()) {
+                            state.forget(*temp);
+                        }
+                    }
+                    WriteBack(BorrowNode::LocalRoot(idx), _) => state.forget(*idx),
+                    _ => {
+                        let value = if dests.len() == 1 {
+                            let args = srcs.iter().map(|src| state.get(*src)).collect::<Vec<_>>();
+                            Self::fold(oper, &args)
+                        } else {
+                            ConstValue::Unknown
+                        };
+                        for dest in dests {
+                            state.set(*dest, value.clone());
+                        }
+                    }
+                }
+                if let Some(AbortAction(_, dest)) = on_abort {
+                    state.forget(*dest);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a> DataflowAnalysis for ConstantPropagation<'a> {}
+
+impl<'a> CompositionalAnalysis<ConstantSummary> for ConstantPropagation<'a> {
+    fn to_summary(&self, state: ConstState, _fun_target: &FunctionTarget) -> ConstantSummary {
+        ConstantSummary {
+            returns: state.returns,
+        }
+    }
+}
+
+// =================================================================================================
+// Simplification of spec expressions
+
+struct ConstantFolder<'a> {
+    constants: &'a BTreeMap<TempIndex, Constant>,
+}
+
+impl<'a> ConstantFolder<'a> {
+    /// Converts a constant into a value of a spec expression. Byte arrays are not converted, as
+    /// spec expressions represent them as vectors.
+    fn to_value(c: &Constant) -> Option<Value> {
+        match c {
+            Constant::Bool(b) => Some(Value::Bool(*b)),
+            Constant::U8(n) => Some(Value::Number(BigInt::from(*n))),
+            Constant::U64(n) => Some(Value::Number(BigInt::from(*n))),
+            Constant::U128(n) => Some(Value::Number(BigInt::from(*n))),
+            Constant::Address(addr) => Some(Value::Address(addr.clone())),
+            Constant::ByteArray(_) => None,
+        }
+    }
+}
+
+impl<'a> ExpRewriterFunctions for ConstantFolder<'a> {
+    fn rewrite_temporary(&mut self, id: NodeId, idx: TempIndex) -> Option<Exp> {
+        let value = Self::to_value(self.constants.get(&idx)?)?;
+        Some(ExpData::Value(id, value).into_exp())
+    }
+
+    fn rewrite_call(&mut self, id: NodeId, oper: &ast::Operation, args: &[Exp]) -> Option<Exp> {
+        use ast::Operation::*;
+        use Value::{Bool, Number};
+        let values = args
+            .iter()
+            .map(|arg| match arg.as_ref() {
+                ExpData::Value(_, value) => Some(value),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let result = match (oper, values.as_slice()) {
+            (Not, [Bool(b)]) => !b,
+            (And, [Bool(b1), Bool(b2)]) => *b1 && *b2,
+            (Or, [Bool(b1), Bool(b2)]) => *b1 || *b2,
+            (Implies, [Bool(b1), Bool(b2)]) => !b1 || *b2,
+            (Iff, [Bool(b1), Bool(b2)]) => b1 == b2,
+            (Eq, [v1, v2]) => v1 == v2,
+            (Neq, [v1, v2]) => v1 != v2,
+            (Lt, [Number(n1), Number(n2)]) => n1 < n2,
+            (Le, [Number(n1), Number(n2)]) => n1 <= n2,
+            (Gt, [Number(n1), Number(n2)]) => n1 > n2,
+            (Ge, [Number(n1), Number(n2)]) => n1 >= n2,
+            _ => return None,
+        };
+        Some(ExpData::Value(id, Bool(result)).into_exp())
+    }
+}
@@ -54,6 +54,10 @@ impl FunctionTargetProcessor for DataInvariantInstrumentationProcessor {
     fn name(&self) -> String {
         "data_invariant_instrumenter".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 struct Instrumenter<'a> {
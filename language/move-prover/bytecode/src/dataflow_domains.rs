@@ -42,6 +42,20 @@ pub trait AbstractDomain {
     fn join(&mut self, other: &Self) -> JoinResult;
 }
 
+/// A trait to be implemented by domains which support a meet, i.e. the greatest lower bound.
+pub trait MeetDomain: AbstractDomain {
+    fn meet(&mut self, other: &Self) -> JoinResult;
+}
+
+/// A trait to be implemented by domains which support widening, to enforce termination of
+/// fixpoint iteration over domains with infinite ascending chains. For domains of finite height,
+/// widening is just join, which is the default.
+pub trait WideningDomain: AbstractDomain {
+    fn widen(&mut self, other: &Self) -> JoinResult {
+        self.join(other)
+    }
+}
+
 // ================================================================================================
 // Predefined Domain Types
 
@@ -132,6 +146,22 @@ impl<E: Ord + Clone> AbstractDomain for SetDomain<E> {
     }
 }
 
+impl<E: Ord + Clone> MeetDomain for SetDomain<E> {
+    fn meet(&mut self, other: &Self) -> JoinResult {
+        let removed = self.difference(other).cloned().collect_vec();
+        for e in &removed {
+            self.remove(e);
+        }
+        if removed.is_empty() {
+            JoinResult::Unchanged
+        } else {
+            JoinResult::Changed
+        }
+    }
+}
+
+impl<E: Ord + Clone> WideningDomain for SetDomain<E> {}
+
 impl<E: Ord + Clone> From<BTreeSet<E>> for SetDomain<E> {
     fn from(s: BTreeSet<E>) -> Self {
         s.into_iter().collect()
@@ -233,6 +263,42 @@ impl<K: Ord + Clone, V: AbstractDomain + Clone> AbstractDomain for MapDomain<K,
     }
 }
 
+impl<K: Ord + Clone, V: MeetDomain + Clone> MeetDomain for MapDomain<K, V> {
+    fn meet(&mut self, other: &Self) -> JoinResult {
+        let mut change = JoinResult::Unchanged;
+        let keys = self.keys().cloned().collect_vec();
+        for k in keys {
+            match other.get(&k) {
+                Some(other_v) => {
+                    let v = self.get_mut(&k).expect("key bound");
+                    change = change.combine(v.meet(other_v));
+                }
+                None => {
+                    self.remove(&k);
+                    change = JoinResult::Changed;
+                }
+            }
+        }
+        change
+    }
+}
+
+impl<K: Ord + Clone, V: WideningDomain + Clone> WideningDomain for MapDomain<K, V> {
+    fn widen(&mut self, other: &Self) -> JoinResult {
+        let mut change = JoinResult::Unchanged;
+        for (k, v) in other.iter() {
+            match self.get_mut(k) {
+                Some(old_v) => change = change.combine(old_v.widen(v)),
+                None => {
+                    self.insert(k.clone(), v.clone());
+                    change = JoinResult::Changed;
+                }
+            }
+        }
+        change
+    }
+}
+
 impl<K: Ord + Clone, V: AbstractDomain + Clone> From<BTreeMap<K, V>> for MapDomain<K, V> {
     fn from(m: BTreeMap<K, V>) -> Self {
         m.into_iter().collect()
@@ -284,3 +350,216 @@ impl<K: Ord + Clone, V: AbstractDomain + Clone + PartialEq> MapDomain<K, V> {
         self.extend(new_values.into_iter());
     }
 }
+
+// ------------------------------------------------------------------------------------------------
+// Reversed Lattice
+
+/// Implements the reversal of a lattice, in which join is the meet of the underlying domain.
+/// For example, `ReversedDomain<SetDomain<E>>` is a "must" set domain, where the join of two
+/// sets is their intersection.
+///
+/// Notice that the top element of the underlying domain, which is the bottom element of the
+/// reversed one, may not be representable (like the universe for sets). Analyses must therefore
+/// start from a proper initial state instead of the default.
+#[derive(Clone, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct ReversedDomain<D>(pub D);
+
+impl<D> Deref for ReversedDomain<D> {
+    type Target = D;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<D> DerefMut for ReversedDomain<D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<D: Debug> Debug for ReversedDomain<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl<D: MeetDomain> AbstractDomain for ReversedDomain<D> {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        self.0.meet(&other.0)
+    }
+}
+
+impl<D: MeetDomain> MeetDomain for ReversedDomain<D> {
+    fn meet(&mut self, other: &Self) -> JoinResult {
+        self.0.join(&other.0)
+    }
+}
+
+/// Meets only shrink the underlying domain, so a reversed domain has no infinite ascending
+/// chains as long as the underlying domain has no infinite descending chains, which holds for
+/// the finite sets and maps used in analyses.
+impl<D: MeetDomain> WideningDomain for ReversedDomain<D> {}
+
+// ------------------------------------------------------------------------------------------------
+// Product Type
+
+/// Implements the product of two domains, where join is component-wise.
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct ProductDomain<A, B>(pub A, pub B);
+
+impl<A: AbstractDomain, B: AbstractDomain> AbstractDomain for ProductDomain<A, B> {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        self.0.join(&other.0).combine(self.1.join(&other.1))
+    }
+}
+
+impl<A: MeetDomain, B: MeetDomain> MeetDomain for ProductDomain<A, B> {
+    fn meet(&mut self, other: &Self) -> JoinResult {
+        self.0.meet(&other.0).combine(self.1.meet(&other.1))
+    }
+}
+
+impl<A: WideningDomain, B: WideningDomain> WideningDomain for ProductDomain<A, B> {
+    fn widen(&mut self, other: &Self) -> JoinResult {
+        self.0.widen(&other.0).combine(self.1.widen(&other.1))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// Stack Type
+
+/// Implements a stack domain, which abstracts the top of a stack: the elements are known from
+/// the top of the stack downwards, while anything below is unknown. The last element of the
+/// underlying sequence is the top of the stack.
+///
+/// The join of two stacks aligns them at the top, joins the elements pointwise, and drops the
+/// elements below the height of the lower stack. Hence, a join never increases the height, and
+/// the domain has finite height if the element domain has. Widening widens pointwise instead.
+#[derive(Clone, Eq, Ord, PartialEq, PartialOrd)]
+pub struct StackDomain<E>(Vec<E>);
+
+impl<E> Default for StackDomain<E> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<E: Debug> Debug for StackDomain<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl<E> From<Vec<E>> for StackDomain<E> {
+    fn from(elems: Vec<E>) -> Self {
+        Self(elems)
+    }
+}
+
+impl<E> StackDomain<E> {
+    /// Pushes an element on top of the stack.
+    pub fn push(&mut self, e: E) {
+        self.0.push(e)
+    }
+
+    /// Pops the top element, returning None if it is unknown.
+    pub fn pop(&mut self) -> Option<E> {
+        self.0.pop()
+    }
+
+    /// Returns the top element, if it is known.
+    pub fn top(&self) -> Option<&E> {
+        self.0.last()
+    }
+
+    /// Returns the number of known elements.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if no element is known.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the known elements, from the bottom to the top.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &E> {
+        self.0.iter()
+    }
+
+    /// Combines two stacks, aligned at the top, with the given pointwise operation.
+    fn combine_aligned(
+        &mut self,
+        other: &Self,
+        mut op: impl FnMut(&mut E, &E) -> JoinResult,
+    ) -> JoinResult {
+        let mut change = JoinResult::Unchanged;
+        if other.0.len() < self.0.len() {
+            self.0.drain(..self.0.len() - other.0.len());
+            change = JoinResult::Changed;
+        }
+        let offset = other.0.len() - self.0.len();
+        for (e, other_e) in self.0.iter_mut().zip(other.0[offset..].iter()) {
+            change = change.combine(op(e, other_e));
+        }
+        change
+    }
+}
+
+impl<E: AbstractDomain> AbstractDomain for StackDomain<E> {
+    fn join(&mut self, other: &Self) -> JoinResult {
+        self.combine_aligned(other, |e, other_e| e.join(other_e))
+    }
+}
+
+impl<E: WideningDomain> WideningDomain for StackDomain<E> {
+    fn widen(&mut self, other: &Self) -> JoinResult {
+        self.combine_aligned(other, |e, other_e| e.widen(other_e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(elems: &[u8]) -> SetDomain<u8> {
+        elems.iter().copied().collect()
+    }
+
+    #[test]
+    fn reversed_set_joins_by_intersection() {
+        let mut s = ReversedDomain(set(&[1, 2, 3]));
+        assert_eq!(
+            s.join(&ReversedDomain(set(&[2, 3, 4]))),
+            JoinResult::Changed
+        );
+        assert_eq!(s.0, set(&[2, 3]));
+        assert_eq!(
+            s.join(&ReversedDomain(set(&[1, 2, 3]))),
+            JoinResult::Unchanged
+        );
+    }
+
+    #[test]
+    fn product_joins_componentwise() {
+        let mut p = ProductDomain(set(&[1]), ReversedDomain(set(&[1, 2])));
+        let change = p.join(&ProductDomain(set(&[2]), ReversedDomain(set(&[2]))));
+        assert_eq!(change, JoinResult::Changed);
+        assert_eq!(p.0, set(&[1, 2]));
+        assert_eq!((p.1).0, set(&[2]));
+    }
+
+    #[test]
+    fn stack_joins_aligned_at_top() {
+        let mut s = StackDomain::from(vec![set(&[0]), set(&[1]), set(&[2])]);
+        let other = StackDomain::from(vec![set(&[3]), set(&[4])]);
+        assert_eq!(s.join(&other), JoinResult::Changed);
+        assert_eq!(s, StackDomain::from(vec![set(&[1, 3]), set(&[2, 4])]));
+        assert_eq!(s.join(&other), JoinResult::Unchanged);
+
+        // joining with a higher stack keeps the height
+        let higher = StackDomain::from(vec![set(&[5]), set(&[1]), set(&[2])]);
+        assert_eq!(s.join(&higher), JoinResult::Unchanged);
+        assert_eq!(s.len(), 2);
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Eliminates spec conditions which cannot contribute to verification, shrinking the program
+//! handed to the backend. Branches whose condition is a constant according to constant
+//! propagation (as generated for `if (false)`, or for calls to functions returning constants)
+//! are first replaced by unconditional jumps, so the prover does not case-split on them, and spec
+//! conditions are simplified by substituting the temporaries holding constants. Then three kinds
+//! of conditions are removed:
+//!
+//! - Any `assume`/`assert` which simplifies to `true`.
+//! - Any `assume`/`assert` in code which is unreachable from the function entry.
+//! - `assume`s which refer only to memory that is neither touched by the code, nor mentioned by
+//!   any `assert`, nor connected to either via other relevant `assume`s. Dropping assumptions
+//!   can never make verification unsound. This is skipped when checking for inconsistencies.
+//!
+//! Replaced branches and removed conditions are reported as notes of this stage, which are shown
+//! when the reported severity is lowered to notes (`--severity note`).
+
+use crate::{
+    constant_propagation,
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    options::ProverOptions,
+    stackless_bytecode::{BorrowNode, Bytecode, Constant, Operation, PropKind},
+    usage_analysis::UsageState,
+};
+use codespan_reporting::diagnostic::Severity;
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::{ExpData, Value},
+    model::{FunctionEnv, QualifiedInstId, StructId},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct DeadSpecEliminationProcessor();
+
+impl DeadSpecEliminationProcessor {
+    pub fn new() -> Box<Self> {
+        Box::new(Self())
+    }
+}
+
+impl FunctionTargetProcessor for DeadSpecEliminationProcessor {
+    fn process(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        func_env: &FunctionEnv<'_>,
+        mut data: FunctionData,
+    ) -> FunctionData {
+        if func_env.is_native() {
+            return data;
+        }
+        let constants = {
+            let target = FunctionTarget::new(func_env, &data);
+            constant_propagation::get_constant_temps(targets, &target)
+        };
+        let mut trivial = BTreeSet::new();
+        for (offset, temps) in constants {
+            match data.code[offset as usize].clone() {
+                Bytecode::Branch(attr_id, then_label, else_label, cond) => {
+                    if let Some(Constant::Bool(b)) = temps.get(&cond) {
+                        let loc = FunctionTarget::new(func_env, &data).get_bytecode_loc(attr_id);
+                        targets.diagnostics().diag(
+                            Severity::Note,
+                            &loc,
+                            &format!("replaced branch on constant `{}` by a jump", b),
+                        );
+                        let label = if *b { then_label } else { else_label };
+                        data.code[offset as usize] = Bytecode::Jump(attr_id, label);
+                    }
+                }
+                Bytecode::Prop(attr_id, kind, exp) if !temps.is_empty() => {
+                    let exp = constant_propagation::simplify_exp(exp, &temps);
+                    if matches!(exp.as_ref(), ExpData::Value(_, Value::Bool(true))) {
+                        trivial.insert(offset);
+                    }
+                    data.code[offset as usize] = Bytecode::Prop(attr_id, kind, exp);
+                }
+                _ => {}
+            }
+        }
+        let dead = {
+            let target = FunctionTarget::new(func_env, &data);
+            let mut dead = unreachable_props(&target);
+            dead.extend(trivial);
+            if !ProverOptions::get(func_env.module_env.env).check_inconsistency {
+                // Irrelevant assumes may still be inconsistent, which the inconsistency check
+                // is supposed to find, so keep them in this case.
+                dead.extend(irrelevant_assumes(targets, &target, &dead));
+            }
+            for offset in &dead {
+                let bc = &target.get_bytecode()[*offset as usize];
+                targets.diagnostics().diag(
+                    Severity::Note,
+                    &target.get_bytecode_loc(bc.get_attr_id()),
+                    &format!(
+                        "removed dead spec condition `{}`",
+                        bc.display(&target, &BTreeMap::new())
+                    ),
+                );
+            }
+            dead
+        };
+        if !dead.is_empty() {
+            data.code = std::mem::take(&mut data.code)
+                .into_iter()
+                .enumerate()
+                .filter(|(offset, _)| !dead.contains(&(*offset as CodeOffset)))
+                .map(|(_, bc)| bc)
+                .collect();
+        }
+        data
+    }
+
+    fn name(&self) -> String {
+        "dead_spec_elimination".to_string()
+    }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
+}
+
+/// Returns the offsets of the spec conditions which are not reachable from the entry.
+fn unreachable_props(target: &FunctionTarget<'_>) -> BTreeSet<CodeOffset> {
+    let code = target.get_bytecode();
+    if code.is_empty() {
+        return BTreeSet::new();
+    }
+    let label_offsets = Bytecode::label_offsets(code);
+    let mut reachable = BTreeSet::new();
+    let mut todo = vec![0 as CodeOffset];
+    while let Some(offset) = todo.pop() {
+        if offset as usize >= code.len() || !reachable.insert(offset) {
+            continue;
+        }
+        todo.extend(Bytecode::get_successors(offset, code, &label_offsets));
+    }
+    code.iter()
+        .enumerate()
+        .filter(|(offset, bc)| {
+            matches!(bc, Bytecode::Prop(..)) && !reachable.contains(&(*offset as CodeOffset))
+        })
+        .map(|(offset, _)| offset as CodeOffset)
+        .collect()
+}
+
+/// Returns true if two memories may denote the same location for some instantiation.
+fn may_overlap(m1: &QualifiedInstId<StructId>, m2: &QualifiedInstId<StructId>) -> bool {
+    m1.module_id == m2.module_id
+        && m1.id == m2.id
+        && (m1.inst == m2.inst
+            || m1.inst.iter().any(|ty| ty.is_open())
+            || m2.inst.iter().any(|ty| ty.is_open()))
+}
+
+/// Returns the offsets of the assumes which refer only to irrelevant memory, ignoring the
+/// conditions at the offsets in `dead`.
+fn irrelevant_assumes(
+    targets: &FunctionTargetsHolder,
+    target: &FunctionTarget<'_>,
+    dead: &BTreeSet<CodeOffset>,
+) -> BTreeSet<CodeOffset> {
+    use Bytecode::*;
+    use Operation::*;
+    let env = target.global_env();
+
+    // Memory touched by the code itself or asserted about, and the assumes which are candidates
+    // for removal together with the memory they refer to.
+    let mut relevant: Vec<QualifiedInstId<StructId>> = vec![];
+    let mut candidates: BTreeMap<CodeOffset, Vec<QualifiedInstId<StructId>>> = BTreeMap::new();
+    for (offset, bc) in target.get_bytecode().iter().enumerate() {
+        let offset = offset as CodeOffset;
+        if dead.contains(&offset) {
+            continue;
+        }
+        match bc {
+            Prop(_, PropKind::Assume, exp) => {
+                let used: Vec<_> = exp.used_memory(env).into_iter().map(|(m, _)| m).collect();
+                if used.is_empty() || !exp.temporaries(env).is_empty() {
+                    relevant.extend(used);
+                } else {
+                    candidates.insert(offset, used);
+                }
+            }
+            Prop(_, _, exp) => {
+                relevant.extend(exp.used_memory(env).into_iter().map(|(m, _)| m));
+            }
+            SaveMem(_, _, mem) => relevant.push(mem.clone()),
+            Call(_, _, oper, _, _) => match oper {
+                Function(mid, fid, inst)
+                | OpaqueCallBegin(mid, fid, inst)
+                | OpaqueCallEnd(mid, fid, inst) => {
+                    let callee_env = env.get_function(mid.qualified(*fid));
+                    match targets
+                        .get_target(&callee_env, &FunctionVariant::Baseline)
+                        .get_annotations()
+                        .get::<UsageState>()
+                    {
+                        Some(usage) => relevant.extend(usage.accessed.get_all_inst(inst)),
+                        // Without a usage summary, we cannot tell which memory is irrelevant
+                        None => return BTreeSet::new(),
+                    }
+                }
+                MoveTo(mid, sid, inst)
+                | MoveFrom(mid, sid, inst)
+                | Exists(mid, sid, inst)
+                | BorrowGlobal(mid, sid, inst)
+                | GetGlobal(mid, sid, inst) => {
+                    relevant.push(mid.qualified_inst(*sid, inst.clone()))
+                }
+                WriteBack(BorrowNode::GlobalRoot(mem), _) => relevant.push(mem.clone()),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    // Assumes which overlap with relevant memory make all their memory relevant as well.
+    loop {
+        let newly_relevant = candidates
+            .iter()
+            .filter(|(_, used)| {
+                used.iter()
+                    .any(|m1| relevant.iter().any(|m2| may_overlap(m1, m2)))
+            })
+            .map(|(offset, _)| *offset)
+            .collect::<Vec<_>>();
+        if newly_relevant.is_empty() {
+            break;
+        }
+        for offset in newly_relevant {
+            relevant.extend(candidates.remove(&offset).unwrap());
+        }
+    }
+    candidates.into_iter().map(|(offset, _)| offset).collect()
+}
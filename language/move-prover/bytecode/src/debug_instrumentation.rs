@@ -108,4 +108,8 @@ impl FunctionTargetProcessor for DebugInstrumenter {
     fn name(&self) -> String {
         "debug_instrumenter".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
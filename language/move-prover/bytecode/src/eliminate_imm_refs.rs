@@ -32,6 +32,10 @@ impl FunctionTargetProcessor for EliminateImmRefsProcessor {
     fn name(&self) -> String {
         "eliminate_imm_refs".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 pub struct EliminateImmRefs<'a> {
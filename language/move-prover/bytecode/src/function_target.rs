@@ -80,6 +80,9 @@ pub struct FunctionData {
     /// A map from byte code attribute to a message to be printed out if verification
     /// fails at this bytecode.
     pub vc_infos: BTreeMap<AttrId, String>,
+    /// A map from byte code attribute with a verification condition message to the name of
+    /// the pipeline pass which introduced it.
+    pub vc_passes: BTreeMap<AttrId, String>,
     /// Annotations associated with this function. This is shared between multiple function
     /// variants.
     pub annotations: Annotations,
@@ -150,6 +153,12 @@ impl<'env> FunctionTarget<'env> {
         self.data.vc_infos.get(&attr_id)
     }
 
+    /// Returns the name of the pipeline pass which introduced the verification condition
+    /// associated with the given attribute, if known.
+    pub fn get_vc_pass(&self, attr_id: AttrId) -> Option<&String> {
+        self.data.vc_passes.get(&attr_id)
+    }
+
     /// Returns true if this function is native.
     pub fn is_native(&self) -> bool {
         self.func_env.is_native()
@@ -401,6 +410,35 @@ impl<'env> FunctionTarget<'env> {
 
         texts.join("\n")
     }
+
+    /// Pretty print the bytecode of the function, interleaved with the source lines it stems
+    /// from. A source line is printed before the first of a sequence of instructions stemming
+    /// from it. Instructions injected by the prover, like `Prop`, stem from the spec conditions
+    /// they check or assume, so those are interleaved as well.
+    pub fn pretty_print_with_source(&self) -> String {
+        let env = self.global_env();
+        let label_offsets = Bytecode::label_offsets(self.get_bytecode());
+        let mut texts = vec![];
+        let mut last_line = None;
+        for (offset, code) in self.get_bytecode().iter().enumerate() {
+            let loc = self.get_bytecode_loc(code.get_attr_id());
+            let line = env
+                .get_location(&loc)
+                .map(|location| (loc.file_id(), location.line));
+            if line.is_some() && line != last_line {
+                if let Some(source) = env.get_source_line(&loc) {
+                    texts.push(format!(
+                        "     // {}: {}",
+                        loc.display_line_only(env),
+                        source.trim()
+                    ));
+                }
+                last_line = line;
+            }
+            texts.push(self.pretty_print_bytecode(&label_offsets, offset, code));
+        }
+        texts.join("\n")
+    }
 }
 
 impl FunctionData {
@@ -429,6 +467,7 @@ impl FunctionData {
             loop_invariants,
             debug_comments: Default::default(),
             vc_infos: Default::default(),
+            vc_passes: Default::default(),
             annotations: Default::default(),
             name_to_index,
             modify_targets,
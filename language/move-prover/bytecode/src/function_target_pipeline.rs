@@ -2,22 +2,39 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    annotations::Annotations,
     function_target::{FunctionData, FunctionTarget},
-    print_targets_for_test,
+    options::ProverOptions,
+    pipeline_graph::PipelineGraph,
+    print_targets_for_test, print_targets_with_source,
+    prover_diagnostics::ProverDiagnostics,
     stackless_bytecode_generator::StacklessBytecodeGenerator,
     stackless_control_flow_graph::generate_cfg_in_dot_format,
+    text_diff,
 };
 use core::fmt;
 use itertools::Itertools;
 use log::debug;
 use move_model::model::{FunId, FunctionEnv, GlobalEnv, QualifiedId};
-use std::{collections::BTreeMap, fmt::Formatter, fs};
+use std::{
+    any::TypeId,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Formatter,
+    fs,
+};
 
 /// A data structure which holds data for multiple function targets, and allows to
 /// manipulate them as part of a transformation pipeline.
 #[derive(Debug, Default)]
 pub struct FunctionTargetsHolder {
     targets: BTreeMap<QualifiedId<FunId>, BTreeMap<FunctionVariant, FunctionData>>,
+    diagnostics: ProverDiagnostics,
+    pipeline_graph: PipelineGraph,
+    /// Whether annotation accesses are recorded, for the pipeline graph.
+    track_annotation_accesses: bool,
+    /// Annotations which concern all functions in the holder, rather than one of them.
+    annotations: Annotations,
 }
 
 /// Describes a function verification flavor.
@@ -75,6 +92,9 @@ pub trait FunctionTargetProcessor {
     /// env of the function being processed, and the target data. During the time the processor is
     /// called, the target data is removed from the holder, and added back once transformation
     /// has finished. This allows the processor to take ownership on the target data.
+    ///
+    /// Diagnostics should be reported to `targets.diagnostics()` rather than to the env, so
+    /// that they are attributed to the stage reporting them.
     fn process(
         &self,
         _targets: &mut FunctionTargetsHolder,
@@ -112,6 +132,21 @@ pub trait FunctionTargetProcessor {
         false
     }
 
+    /// Returns true if this processor may transform the code of the functions it processes.
+    /// Only after such processors the code is compared with the code before, to detect
+    /// code-dependent annotations which became stale. Pure analyses keep the default.
+    fn rewrites_code(&self) -> bool {
+        false
+    }
+
+    /// Returns the types of the code-dependent annotations which remain valid even if this
+    /// processor transforms the code. All other code-dependent annotations computed before this
+    /// processor ran are marked as stale once it changes the code. Processors which set an
+    /// annotation themselves do not need to declare it here.
+    fn preserved_annotations(&self) -> BTreeSet<TypeId> {
+        BTreeSet::new()
+    }
+
     /// To be implemented if `is_single_run()` is true.
     fn run(&self, _env: &GlobalEnv, _targets: &mut FunctionTargetsHolder) {
         unimplemented!()
@@ -244,13 +279,74 @@ impl FunctionTargetsHolder {
             .expect("variant exists")
     }
 
+    /// Sets whether annotation accesses are recorded, in the holder and in all function variants,
+    /// including those added later.
+    pub fn set_track_annotation_accesses(&mut self, track: bool) {
+        self.track_annotation_accesses = track;
+        for data in self.targets.values_mut().flat_map(|vs| vs.values_mut()) {
+            data.annotations.set_track_accesses(track);
+        }
+        self.annotations.set_track_accesses(track);
+    }
+
+    /// Returns the names of the annotation types read and written, respectively, in any function
+    /// variant since the last call, and resets them. Accesses are only recorded once enabled with
+    /// `set_track_annotation_accesses`.
+    pub fn take_annotation_accesses(&mut self) -> (BTreeSet<&'static str>, BTreeSet<&'static str>) {
+        let mut reads = BTreeSet::new();
+        let mut writes = BTreeSet::new();
+        for data in self.targets.values_mut().flat_map(|vs| vs.values_mut()) {
+            let (r, w) = data.annotations.take_accesses();
+            reads.extend(r);
+            writes.extend(w);
+        }
+        let (r, w) = self.annotations.take_accesses();
+        reads.extend(r);
+        writes.extend(w);
+        (reads, writes)
+    }
+
+    /// Attributes all verification conditions which are not yet attributed to a pipeline pass
+    /// to the given pass.
+    pub fn record_vc_passes(&mut self, pass: &str) {
+        for data in self.targets.values_mut().flat_map(|vs| vs.values_mut()) {
+            for attr_id in data.vc_infos.keys() {
+                data.vc_passes
+                    .entry(*attr_id)
+                    .or_insert_with(|| pass.to_string());
+            }
+        }
+    }
+
+    /// Gets the holder-level annotations.
+    pub fn get_annotations(&self) -> &Annotations {
+        &self.annotations
+    }
+
+    /// Gets the holder-level annotations for modification.
+    pub fn get_annotations_mut(&mut self) -> &mut Annotations {
+        &mut self.annotations
+    }
+
+    /// Returns the graph of the pipeline stages which have been run on this holder.
+    pub fn pipeline_graph(&self) -> &PipelineGraph {
+        &self.pipeline_graph
+    }
+
+    /// Returns the collector for the diagnostics reported by pipeline stages.
+    pub fn diagnostics(&self) -> &ProverDiagnostics {
+        &self.diagnostics
+    }
+
     /// Sets function data for a function's variant.
     pub fn insert_target_data(
         &mut self,
         id: &QualifiedId<FunId>,
         variant: FunctionVariant,
-        data: FunctionData,
+        mut data: FunctionData,
     ) {
+        data.annotations
+            .set_track_accesses(self.track_annotation_accesses);
         self.targets.entry(*id).or_default().insert(variant, data);
     }
 
@@ -259,8 +355,24 @@ impl FunctionTargetsHolder {
         let id = func_env.get_qualified_id();
         for variant in self.get_target_variants(func_env) {
             // Remove data so we can own it.
-            let data = self.remove_target_data(&id, &variant);
-            if let Some(processed_data) = processor.process_and_maybe_remove(self, func_env, data) {
+            let mut data = self.remove_target_data(&id, &variant);
+            data.annotations.new_generation();
+            // Only remember the code if the processor may change it and there is an annotation
+            // which might become stale.
+            let code_before = if processor.rewrites_code() && data.annotations.has_code_dependent()
+            {
+                Some(data.code.clone())
+            } else {
+                None
+            };
+            if let Some(mut processed_data) =
+                processor.process_and_maybe_remove(self, func_env, data)
+            {
+                if matches!(&code_before, Some(code) if *code != processed_data.code) {
+                    processed_data
+                        .annotations
+                        .invalidate_code_dependent(&processor.preserved_annotations());
+                }
                 // Put back processed data.
                 self.insert_target_data(&id, variant, processed_data);
             }
@@ -359,9 +471,15 @@ impl FunctionTargetPipeline {
         H2: Fn(usize, &dyn FunctionTargetProcessor, &FunctionTargetsHolder),
     {
         let topological_order = Self::sort_targets_in_topological_order(env, targets);
+        // Recording which annotations the processors access is only needed for the graph.
+        let record_graph = ProverOptions::get(env).dump_pipeline_graph;
+        targets.set_track_annotation_accesses(record_graph);
 
         hook_before_pipeline(targets);
         for (step_count, processor) in self.processors.iter().enumerate() {
+            targets.diagnostics().set_stage(&processor.name());
+            // Discard accesses from outside of the pipeline, e.g. by the hooks.
+            targets.take_annotation_accesses();
             if processor.is_single_run() {
                 processor.run(env, targets);
             } else {
@@ -371,8 +489,20 @@ impl FunctionTargetPipeline {
                 }
                 processor.finalize(env, targets);
             }
+            targets.record_vc_passes(&processor.name());
+            if record_graph {
+                let (reads, writes) = targets.take_annotation_accesses();
+                let variants = targets
+                    .get_funs_and_variants()
+                    .map(|(_, variant)| variant.to_string())
+                    .collect();
+                targets
+                    .pipeline_graph
+                    .add_stage(processor.name(), variants, reads, writes);
+            }
             hook_after_each_processor(step_count + 1, processor.as_ref(), targets);
         }
+        targets.diagnostics().report_to_env(env);
     }
 
     /// Run the pipeline on all functions in the targets holder, with no hooks in effect
@@ -416,6 +546,125 @@ impl FunctionTargetPipeline {
         );
     }
 
+    /// Runs the pipeline on all functions in the targets holder, and dumps unified diffs of the
+    /// bytecode of each function variant between consecutive processors, instead of dumping
+    /// the full bytecode after each of them. Processors which do not change the bytecode produce
+    /// no file.
+    pub fn run_with_diff_dump(
+        &self,
+        env: &GlobalEnv,
+        targets: &mut FunctionTargetsHolder,
+        dump_base_name: &str,
+    ) {
+        let last = RefCell::new(("stackless".to_string(), BTreeMap::new()));
+        self.run_with_hook(
+            env,
+            targets,
+            |holders| last.borrow_mut().1 = Self::get_bytecode_snapshot(env, holders),
+            |step_count, processor, holders| {
+                let name = processor.name();
+                let snapshot = Self::get_bytecode_snapshot(env, holders);
+                let diff = {
+                    let (last_name, last_snapshot) = &*last.borrow();
+                    Self::get_snapshot_diff(env, last_name, last_snapshot, &name, &snapshot)
+                };
+                if !diff.is_empty() {
+                    let file_name = format!("{}_{}_{}.diff", dump_base_name, step_count, name);
+                    debug!("dumping bytecode diff to `{}`", file_name);
+                    fs::write(&file_name, &diff).expect("dumping bytecode diff");
+                }
+                *last.borrow_mut() = (name, snapshot);
+            },
+        );
+    }
+
+    /// Runs the pipeline on all functions in the targets holder, and dumps the bytecode after the
+    /// processor named `stage`, interleaved with the source lines and the spec conditions it
+    /// stems from. The stage `stackless` denotes the bytecode before the pipeline.
+    pub fn run_with_source_dump(
+        &self,
+        env: &GlobalEnv,
+        targets: &mut FunctionTargetsHolder,
+        dump_base_name: &str,
+        stage: &str,
+    ) {
+        if stage != "stackless" && !self.processors.iter().any(|p| p.name() == stage) {
+            env.error(
+                &env.unknown_loc(),
+                &format!("unknown pipeline stage `{}`", stage),
+            );
+            return;
+        }
+        let dump = |step_count: usize, holders: &FunctionTargetsHolder| {
+            let file_name = format!("{}_{}_{}.listing", dump_base_name, step_count, stage);
+            debug!("dumping bytecode with source to `{}`", file_name);
+            let header = format!("after processor `{}`", stage);
+            fs::write(&file_name, print_targets_with_source(env, &header, holders))
+                .expect("dumping bytecode with source");
+        };
+        self.run_with_hook(
+            env,
+            targets,
+            |holders| {
+                if stage == "stackless" {
+                    dump(0, holders)
+                }
+            },
+            |step_count, processor, holders| {
+                if processor.name() == stage {
+                    dump(step_count, holders)
+                }
+            },
+        );
+    }
+
+    /// Renders the bytecode of all function variants which have code.
+    fn get_bytecode_snapshot(
+        env: &GlobalEnv,
+        targets: &FunctionTargetsHolder,
+    ) -> BTreeMap<(QualifiedId<FunId>, FunctionVariant), String> {
+        targets
+            .get_funs_and_variants()
+            .filter_map(|(fun_id, variant)| {
+                let func_env = env.get_function(fun_id);
+                let target = targets.get_target(&func_env, &variant);
+                if target.data.code.is_empty() {
+                    None
+                } else {
+                    let text = target.to_string();
+                    Some(((fun_id, variant), text))
+                }
+            })
+            .collect()
+    }
+
+    /// Computes the unified diffs between two snapshots, one per changed function variant.
+    fn get_snapshot_diff(
+        env: &GlobalEnv,
+        old_name: &str,
+        old: &BTreeMap<(QualifiedId<FunId>, FunctionVariant), String>,
+        new_name: &str,
+        new: &BTreeMap<(QualifiedId<FunId>, FunctionVariant), String>,
+    ) -> String {
+        let keys: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+        let mut result = String::new();
+        for key in keys {
+            let old_text = old.get(key).map(String::as_str).unwrap_or_default();
+            let new_text = new.get(key).map(String::as_str).unwrap_or_default();
+            let diff = text_diff::unified_diff(old_text, new_text, 3);
+            if diff.is_empty() {
+                continue;
+            }
+            let (fun_id, variant) = key;
+            let fun_name = env.get_function(*fun_id).get_full_name_str();
+            result.push_str(&format!(
+                "--- {} [variant {}] after `{}`\n+++ {} [variant {}] after `{}`\n{}",
+                fun_name, variant, old_name, fun_name, variant, new_name, diff
+            ));
+        }
+        result
+    }
+
     fn print_targets(env: &GlobalEnv, name: &str, targets: &FunctionTargetsHolder) -> String {
         print_targets_for_test(env, &format!("after processor `{}`", name), targets)
     }
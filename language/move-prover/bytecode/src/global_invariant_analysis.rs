@@ -56,6 +56,60 @@ pub fn get_info<'env>(target: &FunctionTarget<'env>) -> &'env PerFunctionRelevan
         .expect("Global invariant analysis not performed")
 }
 
+/// Returns the memory modified by the bytecode after which global invariants referring to this
+/// memory need to be asserted, or `None` if no invariant needs to be asserted after it. Return
+/// instructions are not covered: the memory relevant to them depends on the code before them.
+pub fn modified_memory(
+    env: &GlobalEnv,
+    targets: &FunctionTargetsHolder,
+    inv_analysis: &InvariantAnalysisData,
+    bc: &Bytecode,
+) -> Option<BTreeSet<QualifiedInstId<StructId>>> {
+    use BorrowNode::*;
+    use Bytecode::*;
+    use Operation::*;
+
+    match bc {
+        Call(_, _, oper, _, _) => match oper {
+            Function(mid, fid, inst) | OpaqueCallEnd(mid, fid, inst) => {
+                let callee_fid = mid.qualified(*fid);
+
+                // shortcut the call if the callee does not delegate invariant checking.
+                //
+                // NOTE: in this case, memories modified by the callee are NOT back
+                // propagated to the caller in the `verification_analysis.rs`, which means,
+                // the `InvariantRelevance::direct_modified` set for the caller does NOT
+                // necessarily cover invariants that are related to the callee.
+                if !inv_analysis.fun_set_with_no_inv_check.contains(&callee_fid) {
+                    return None;
+                }
+
+                let callee_env = env.get_function(callee_fid);
+                let callee_target = targets.get_target(&callee_env, &FunctionVariant::Baseline);
+                let callee_usage = usage_analysis::get_memory_usage(&callee_target);
+
+                // NOTE: it is important to include *ALL* memories modified by the callee
+                // instead of just the direct ones --- if a function `F` delegates
+                // suspendable invariant checking to its caller, all the functions that `F`
+                // calls will not check suspendable invariants anymore.
+                Some(callee_usage.modified.get_all_inst(inst))
+            }
+
+            MoveTo(mid, sid, inst) | MoveFrom(mid, sid, inst) => {
+                let mem = mid.qualified_inst(*sid, inst.to_owned());
+                Some(std::iter::once(mem).collect())
+            }
+            WriteBack(GlobalRoot(mem), _) => Some(std::iter::once(mem.clone()).collect()),
+
+            // shortcut other operations
+            _ => None,
+        },
+
+        // shortcut other bytecodes
+        _ => None,
+    }
+}
+
 // The function target processor
 pub struct GlobalInvariantAnalysisProcessor {}
 
@@ -220,9 +274,7 @@ impl FunctionTargetProcessor for GlobalInvariantAnalysisProcessor {
 impl PerFunctionRelevance {
     /// Collect and build the relevance analysis information for this function target.
     fn analyze(target: &FunctionTarget, targets: &FunctionTargetsHolder) -> Self {
-        use BorrowNode::*;
         use Bytecode::*;
-        use Operation::*;
 
         // collect information
         let fid = target.func_env.get_qualified_id();
@@ -320,48 +372,14 @@ impl PerFunctionRelevance {
 
             // collect memory modified in operations
             let mem_related = match bc {
-                Call(_, _, oper, _, _) => match oper {
-                    Function(mid, fid, inst) | OpaqueCallEnd(mid, fid, inst) => {
-                        let callee_fid = mid.qualified(*fid);
-
-                        // shortcut the call if the callee does not delegate invariant checking.
-                        //
-                        // NOTE: in this case, memories modified by the callee are NOT back
-                        // propagated to the caller in the `verification_analysis.rs`, which means,
-                        // the `InvariantRelevance::direct_modified` set for the caller does NOT
-                        // necessarily cover invariants that are related to the callee.
-                        if !inv_analysis.fun_set_with_no_inv_check.contains(&callee_fid) {
-                            continue;
-                        }
-
-                        let callee_env = env.get_function(callee_fid);
-                        let callee_target =
-                            targets.get_target(&callee_env, &FunctionVariant::Baseline);
-                        let callee_usage = usage_analysis::get_memory_usage(&callee_target);
-
-                        // NOTE: it is important to include *ALL* memories modified by the callee
-                        // instead of just the direct ones --- if a function `F` delegates
-                        // suspendable invariant checking to its caller, all the functions that `F`
-                        // calls will not check suspendable invariants anymore.
-                        callee_usage.modified.get_all_inst(inst)
-                    }
-
-                    MoveTo(mid, sid, inst) | MoveFrom(mid, sid, inst) => {
-                        let mem = mid.qualified_inst(*sid, inst.to_owned());
-                        std::iter::once(mem).collect()
-                    }
-                    WriteBack(GlobalRoot(mem), _) => std::iter::once(mem.clone()).collect(),
-
-                    // shortcut other operations
-                    _ => continue,
-                },
-
                 Ret(..) if check_suspendable_inv_on_return => {
                     std::mem::take(&mut mem_related_on_return)
                 }
-
-                // shortcut other bytecodes
-                _ => continue,
+                _ => match modified_memory(env, targets, inv_analysis, bc) {
+                    Some(mem) => mem,
+                    // shortcut other bytecodes
+                    None => continue,
+                },
             };
 
             // mark whether we are processing a return instruction
@@ -409,7 +427,7 @@ impl PerFunctionRelevance {
                 }
             }
             if !deferred_invs.is_empty() {
-                env.error(
+                targets.diagnostics().error(
                     &target.get_loc(),
                     &format!(
                         "Function `{}` defers the checking of {} suspendable invariants to the \
@@ -330,6 +330,10 @@ impl FunctionTargetProcessor for GlobalInvariantInstrumentationProcessor {
     fn name(&self) -> String {
         "global_invariant_instrumentation".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 /// A contextualized instrumenter to handle the global invariant instrumentation process.
@@ -96,6 +96,10 @@ impl FunctionTargetProcessor for GlobalInvariantInstrumentationProcessorV2 {
     fn name(&self) -> String {
         "global_invariant_instrumenter_v2".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 struct Analyzer {
@@ -101,4 +101,8 @@ impl FunctionTargetProcessor for InconsistencyCheckInstrumenter {
     fn name(&self) -> String {
         "inconsistency_check_instrumenter".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
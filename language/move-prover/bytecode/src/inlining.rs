@@ -0,0 +1,250 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inlining of small non-opaque callees at the bytecode level.
+//!
+//! Calls to functions with at most `ProverOptions::inline_threshold` instructions, or with
+//! `pragma inline = true`, are replaced by a copy of the callee's code. Verification of the
+//! caller then reasons about the callee's code instead of its spec, which removes imprecision
+//! from weak callee specs. This processor runs first in the pipeline, so all later analyses,
+//! in particular the borrow and usage analyses, compute their summaries over the inlined code.
+//!
+//! A callee is only inlined if this does not change what is checked for the caller: it must be
+//! non-opaque, non-native, non-generic, and not recursive, must not have `requires` conditions,
+//! which are checked at the call site, must not have spec blocks in its code, and must not
+//! change how invariants are checked in its body. `pragma inline = false` prevents inlining.
+//!
+//! Nothing is inlined into callers which are opaque, or which disable or delegate invariant
+//! checking. Callers of an opaque function only see its spec, so the proof of that spec should
+//! rest on the specs of its callees, like the proofs of its callers do, rather than on their code.
+//! Which invariants are checked where is derived from the calls in the model, which inlining does
+//! not remove, so code inlined into a caller with invariant checking disabled or delegated would
+//! be checked under the rules of the caller instead of the ones the callee is verified with.
+
+use crate::{
+    function_data_builder::FunctionDataBuilder,
+    function_target::{FunctionData, FunctionTarget},
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    options::ProverOptions,
+    stackless_bytecode::{AbortAction, AssignKind, AttrId, Bytecode, Label, Operation},
+};
+use move_model::{
+    ast::{ConditionKind, TempIndex},
+    exp_generator::ExpGenerator,
+    model::{FunId, FunctionEnv, QualifiedId},
+    pragmas::{
+        DELEGATE_INVARIANTS_TO_CALLER_PRAGMA, DISABLE_INVARIANTS_IN_BODY_PRAGMA, INLINE_PRAGMA,
+    },
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct InliningProcessor();
+
+impl InliningProcessor {
+    pub fn new() -> Box<Self> {
+        Box::new(Self())
+    }
+}
+
+impl FunctionTargetProcessor for InliningProcessor {
+    fn process(
+        &self,
+        targets: &mut FunctionTargetsHolder,
+        func_env: &FunctionEnv<'_>,
+        data: FunctionData,
+    ) -> FunctionData {
+        if func_env.is_native_or_intrinsic()
+            || func_env.is_opaque()
+            || func_env.is_pragma_true(DISABLE_INVARIANTS_IN_BODY_PRAGMA, || false)
+            || func_env.is_pragma_true(DELEGATE_INVARIANTS_TO_CALLER_PRAGMA, || false)
+        {
+            return data;
+        }
+        let threshold = ProverOptions::get(func_env.module_env.env).inline_threshold;
+        let inlined = data
+            .code
+            .iter()
+            .filter_map(|bc| match bc {
+                Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, None) => {
+                    Some(mid.qualified(*fid))
+                }
+                _ => None,
+            })
+            .filter(|callee_id| is_inlinable(targets, func_env, *callee_id, threshold))
+            .collect::<BTreeSet<_>>();
+        if inlined.is_empty() {
+            return data;
+        }
+
+        let env = func_env.module_env.env;
+        let mut builder = FunctionDataBuilder::new(func_env, data);
+        for bc in std::mem::take(&mut builder.data.code) {
+            match bc {
+                Bytecode::Call(attr_id, dests, Operation::Function(mid, fid, _), srcs, None)
+                    if inlined.contains(&mid.qualified(fid)) =>
+                {
+                    let callee_env = env.get_function(mid.qualified(fid));
+                    let callee_target = targets.get_target(&callee_env, &FunctionVariant::Baseline);
+                    inline_call(&mut builder, attr_id, &callee_target, dests, srcs);
+                }
+                _ => builder.emit(bc),
+            }
+        }
+        for callee_id in inlined {
+            let callee_env = env.get_function(callee_id);
+            let callee_data = targets
+                .get_data(&callee_id, &FunctionVariant::Baseline)
+                .expect("inlined callee has code");
+            for struct_id in &callee_data.acquires_global_resources {
+                if callee_env.module_env.get_id() == func_env.module_env.get_id()
+                    && !builder.data.acquires_global_resources.contains(struct_id)
+                {
+                    builder.data.acquires_global_resources.push(*struct_id);
+                }
+            }
+        }
+        builder.data
+    }
+
+    fn name(&self) -> String {
+        "inlining".to_string()
+    }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
+}
+
+/// Determines whether calls to the callee are inlined into the caller.
+fn is_inlinable(
+    targets: &FunctionTargetsHolder,
+    caller_env: &FunctionEnv<'_>,
+    callee_id: QualifiedId<FunId>,
+    threshold: usize,
+) -> bool {
+    let env = caller_env.module_env.env;
+    let callee_env = env.get_function(callee_id);
+    if callee_id == caller_env.get_qualified_id()
+        || callee_env.is_native_or_intrinsic()
+        || callee_env.is_opaque()
+        || callee_env.get_type_parameter_count() > 0
+        || callee_env.is_pragma_false(INLINE_PRAGMA)
+        || callee_env.is_pragma_true(DISABLE_INVARIANTS_IN_BODY_PRAGMA, || false)
+        || callee_env.is_pragma_true(DELEGATE_INVARIANTS_TO_CALLER_PRAGMA, || false)
+        || callee_env
+            .get_spec()
+            .conditions
+            .iter()
+            .any(|cond| matches!(cond.kind, ConditionKind::Requires))
+    {
+        return false;
+    }
+    let data = match targets.get_data(&callee_id, &FunctionVariant::Baseline) {
+        Some(data) if !data.code.is_empty() => data,
+        _ => return false,
+    };
+    if data.code.iter().any(|bc| {
+        matches!(
+            bc,
+            Bytecode::Prop(..) | Bytecode::SaveMem(..) | Bytecode::SaveSpecVar(..)
+        )
+    }) {
+        return false;
+    }
+    if !(callee_env.is_pragma_true(INLINE_PRAGMA, || false)
+        || (threshold > 0 && data.code.len() <= threshold))
+    {
+        return false;
+    }
+    !is_recursive(&callee_env)
+}
+
+/// Determines whether the function (transitively) calls itself.
+fn is_recursive(fun_env: &FunctionEnv<'_>) -> bool {
+    let env = fun_env.module_env.env;
+    let fun_id = fun_env.get_qualified_id();
+    let mut visited = BTreeSet::new();
+    let mut todo = fun_env
+        .get_called_functions()
+        .into_iter()
+        .collect::<Vec<_>>();
+    while let Some(id) = todo.pop() {
+        if id == fun_id {
+            return true;
+        }
+        if visited.insert(id) {
+            todo.extend(env.get_function(id).get_called_functions());
+        }
+    }
+    false
+}
+
+/// Emits the code of the callee in place of a call to it. The callee's locals become fresh
+/// temporaries of the caller, its labels fresh labels, and its returns jumps to the end of the
+/// inlined code.
+fn inline_call(
+    builder: &mut FunctionDataBuilder<'_>,
+    attr_id: AttrId,
+    callee_target: &FunctionTarget<'_>,
+    dests: Vec<TempIndex>,
+    srcs: Vec<TempIndex>,
+) {
+    let temps = (0..callee_target.get_local_count())
+        .map(|idx| builder.new_temp(callee_target.get_local_type(idx).clone()))
+        .collect::<Vec<_>>();
+    let mut labels = BTreeMap::new();
+    for bc in callee_target.get_bytecode() {
+        if let Bytecode::Label(_, label) = bc {
+            labels.insert(*label, builder.new_label());
+        }
+    }
+    let exit_label = builder.new_label();
+
+    builder.set_loc_from_attr(attr_id);
+    for (param, src) in srcs.into_iter().enumerate() {
+        builder.emit_with(|id| Bytecode::Assign(id, temps[param], src, AssignKind::Move));
+    }
+    for bc in callee_target.get_bytecode() {
+        builder.set_loc(callee_target.get_bytecode_loc(bc.get_attr_id()));
+        let bc = bc
+            .clone()
+            .remap_all_vars(callee_target, &mut |idx| temps[idx]);
+        if let Bytecode::Ret(_, rets) = bc {
+            for (dest, ret) in dests.iter().zip(rets) {
+                builder.emit_with(|id| Bytecode::Assign(id, *dest, ret, AssignKind::Move));
+            }
+            builder.emit_with(|id| Bytecode::Jump(id, exit_label));
+        } else {
+            builder.emit_with(|id| relabel(bc, id, &labels));
+        }
+    }
+    builder.set_loc_from_attr(attr_id);
+    builder.emit_with(|id| Bytecode::Label(id, exit_label));
+}
+
+/// Gives the instruction the new attribute id, and replaces its labels.
+fn relabel(bc: Bytecode, id: AttrId, labels: &BTreeMap<Label, Label>) -> Bytecode {
+    use Bytecode::*;
+    match bc {
+        Assign(_, dest, src, kind) => Assign(id, dest, src, kind),
+        Call(_, dests, oper, srcs, aa) => Call(
+            id,
+            dests,
+            oper,
+            srcs,
+            aa.map(|AbortAction(label, temp)| AbortAction(labels[&label], temp)),
+        ),
+        Ret(_, rets) => Ret(id, rets),
+        Load(_, dest, cons) => Load(id, dest, cons),
+        Branch(_, then_label, else_label, cond) => {
+            Branch(id, labels[&then_label], labels[&else_label], cond)
+        }
+        Jump(_, label) => Jump(id, labels[&label]),
+        Label(_, label) => Label(id, labels[&label]),
+        Abort(_, code) => Abort(id, code),
+        Nop(_) => Nop(id),
+        SaveMem(_, label, mem) => SaveMem(id, label, mem),
+        SaveSpecVar(_, label, var) => SaveSpecVar(id, label, var),
+        Prop(_, kind, exp) => Prop(id, kind, exp),
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Explanation of why a global invariant is asserted in a function. Based on the memory usage of
+//! the function and the result of the global invariant analysis, this determines the instructions
+//! of the function which modify memory the invariant refers to, and therefore cause the invariant
+//! to be asserted after them.
+
+use crate::{
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant, VerificationFlavor},
+    global_invariant_analysis,
+    invariant_relevance::find_relevant_invariants,
+    stackless_bytecode::{Bytecode, Operation},
+    verification_analysis::InvariantAnalysisData,
+};
+use move_binary_format::file_format::CodeOffset;
+use move_model::{
+    ast::GlobalInvariant,
+    model::{FunId, GlobalEnv, GlobalId, QualifiedId, QualifiedInstId, StructId},
+    ty::{TypeUnificationAdapter, Variance},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// A query for why a global invariant is asserted in a function.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InvariantQuery {
+    /// The id of the global invariant, as shown in bytecode dumps.
+    pub invariant: usize,
+    /// The name of the function, either `Module::function` or `0x1::Module::function`.
+    pub function: String,
+}
+
+/// An instruction after which the invariant is asserted.
+#[derive(Debug, Clone)]
+pub struct InvariantAssertion {
+    /// The offset of the instruction in the verification variant of the function.
+    pub offset: CodeOffset,
+    /// The instruction, as displayed in bytecode dumps.
+    pub bytecode: String,
+    /// The source location of the instruction.
+    pub loc: String,
+    /// The memory modified by the instruction which the invariant refers to.
+    pub memory: Vec<String>,
+    /// The callee which modifies the memory, if the instruction is a call.
+    pub callee: Option<String>,
+    /// Whether this is a return, at which the invariant is asserted because its checking is
+    /// deferred to the end of the function. The memory is then modified in the function body.
+    pub deferred: bool,
+}
+
+/// The explanation of why a global invariant is, or is not, asserted in a function.
+#[derive(Debug, Clone)]
+pub struct InvariantExplanation {
+    pub invariant: GlobalId,
+    /// The source location of the invariant.
+    pub invariant_loc: String,
+    pub function: String,
+    /// Whether the function accesses, directly or via callees, memory the invariant refers to.
+    pub accessed: bool,
+    /// Whether the function modifies, directly or via callees, memory the invariant refers to.
+    pub modified: bool,
+    /// Whether the function modifies memory the invariant refers to itself, or via callees which
+    /// delegate the checking of invariants to it.
+    pub directly_modified: bool,
+    /// Whether the invariant is assumed at the entry of the function.
+    pub assumed_at_entry: bool,
+    /// The instructions after which the invariant is asserted.
+    pub assertions: Vec<InvariantAssertion>,
+    /// Remarks on why the invariant is asserted at fewer places than one would expect.
+    pub notes: Vec<String>,
+}
+
+/// Explains why the invariant is asserted in the function. This must be called after the global
+/// invariant analysis, and before the verification variant of the function is further
+/// transformed, as the analysis result refers to the code offsets at that point.
+pub fn explain_invariant(
+    env: &GlobalEnv,
+    targets: &FunctionTargetsHolder,
+    inv_id: GlobalId,
+    fun_id: QualifiedId<FunId>,
+) -> InvariantExplanation {
+    let inv = env
+        .get_global_invariant(inv_id)
+        .expect("global invariant defined");
+    let fun_env = env.get_function(fun_id);
+    let baseline = targets.get_target(&fun_env, &FunctionVariant::Baseline);
+    let relevance = find_relevant_invariants(&baseline, std::iter::once(inv));
+    let mut explanation = InvariantExplanation {
+        invariant: inv_id,
+        invariant_loc: inv.loc.display(env).to_string(),
+        function: fun_env.get_full_name_str(),
+        accessed: relevance.accessed.contains(&inv_id),
+        modified: relevance.modified.contains(&inv_id),
+        directly_modified: relevance.direct_modified.contains(&inv_id),
+        assumed_at_entry: false,
+        assertions: vec![],
+        notes: vec![],
+    };
+
+    let inv_analysis = env
+        .get_extension::<InvariantAnalysisData>()
+        .expect("Verification analysis not performed");
+    if explanation.modified && !explanation.directly_modified {
+        explanation.notes.push(
+            "the memory is only modified in callees, which check the invariant themselves"
+                .to_string(),
+        );
+    }
+    if inv_analysis.fun_set_with_no_inv_check.contains(&fun_id) {
+        explanation
+            .notes
+            .push("the function delegates the checking of invariants to its callers".to_string());
+    }
+    let variant = FunctionVariant::Verification(VerificationFlavor::Regular);
+    if !targets.get_target_variants(&fun_env).contains(&variant) {
+        explanation
+            .notes
+            .push("the function is not verified".to_string());
+        return explanation;
+    }
+
+    let target = targets.get_target(&fun_env, &variant);
+    let info = global_invariant_analysis::get_info(&target);
+    explanation.assumed_at_entry = info.entrypoint_assumptions.contains_key(&inv_id);
+
+    // Follow the analysis: memory modified while invariants are suspended is only related to
+    // the invariants at the return of the function.
+    let check_on_return = inv_analysis
+        .fun_set_with_inv_check_on_exit
+        .contains(&fun_id);
+    let mut modified_in_body = BTreeSet::new();
+    for (offset, bc) in target.data.code.iter().enumerate() {
+        let offset = offset as CodeOffset;
+        let is_return = matches!(bc, Bytecode::Ret(..));
+        let memory = if is_return && check_on_return {
+            std::mem::take(&mut modified_in_body)
+        } else {
+            match global_invariant_analysis::modified_memory(env, targets, &inv_analysis, bc) {
+                Some(memory) => memory,
+                None => continue,
+            }
+        };
+        if check_on_return && !is_return {
+            modified_in_body.extend(memory.iter().cloned());
+        }
+        let asserted = info
+            .per_bytecode_assertions
+            .get(&offset)
+            .map_or(false, |invs| invs.contains_key(&inv_id));
+        if !asserted {
+            continue;
+        }
+        let callee = match bc {
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _)
+            | Bytecode::Call(_, _, Operation::OpaqueCallEnd(mid, fid, _), _, _) => {
+                Some(env.get_function(mid.qualified(*fid)).get_full_name_str())
+            }
+            _ => None,
+        };
+        explanation.assertions.push(InvariantAssertion {
+            offset,
+            bytecode: bc.display(&target, &BTreeMap::new()).to_string(),
+            loc: target
+                .get_bytecode_loc(bc.get_attr_id())
+                .display(env)
+                .to_string(),
+            memory: memory
+                .iter()
+                .filter(|mem| refers_to(inv, mem))
+                .map(|mem| env.display(mem).to_string())
+                .collect(),
+            callee,
+            deferred: is_return,
+        });
+    }
+    if explanation.modified && explanation.assertions.is_empty() && explanation.notes.is_empty() {
+        explanation.notes.push(
+            "the invariant is not checked in the function due to pragmas or options".to_string(),
+        );
+    }
+    explanation
+}
+
+/// Returns true if the invariant refers to some instance of the memory.
+fn refers_to(inv: &GlobalInvariant, mem: &QualifiedInstId<StructId>) -> bool {
+    let mem_ty = mem.to_type();
+    inv.mem_usage.iter().any(|inv_mem| {
+        TypeUnificationAdapter::new_pair(&mem_ty, &inv_mem.to_type(), true, true)
+            .unify(Variance::Allow, false)
+            .is_some()
+    })
+}
+
+impl fmt::Display for InvariantExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "invariant {} {} in function `{}`:",
+            self.invariant.as_usize(),
+            self.invariant_loc,
+            self.function
+        )?;
+        let relevance = [
+            (self.accessed, "accesses"),
+            (self.modified, "modifies"),
+            (self.directly_modified, "directly modifies"),
+        ]
+        .iter()
+        .filter_map(|(holds, what)| holds.then(|| *what))
+        .collect::<Vec<_>>();
+        if relevance.is_empty() {
+            writeln!(f, "  the function does not access memory of the invariant")?;
+        } else {
+            writeln!(
+                f,
+                "  the function {} memory of the invariant",
+                relevance.join(", ")
+            )?;
+        }
+        for note in &self.notes {
+            writeln!(f, "  note: {}", note)?;
+        }
+        if self.assumed_at_entry {
+            writeln!(f, "  assumed at function entry")?;
+        }
+        for assertion in &self.assertions {
+            let kind = if assertion.deferred {
+                "at return"
+            } else {
+                "after"
+            };
+            writeln!(
+                f,
+                "  asserted {} {}: {}",
+                kind, assertion.offset, assertion.bytecode
+            )?;
+            writeln!(f, "    {}", assertion.loc)?;
+            let memory = assertion.memory.join(", ");
+            match (&assertion.callee, assertion.deferred) {
+                (_, true) => writeln!(f, "    because the function body modifies {}", memory)?,
+                (Some(callee), _) => writeln!(
+                    f,
+                    "    because the call to `{}` modifies {}",
+                    callee, memory
+                )?,
+                (None, _) => writeln!(f, "    because the instruction modifies {}", memory)?,
+            }
+        }
+        if self.assertions.is_empty() {
+            writeln!(f, "  never asserted")?;
+        }
+        Ok(())
+    }
+}
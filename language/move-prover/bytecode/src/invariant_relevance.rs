@@ -0,0 +1,194 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Analysis which precomputes, for all functions and all global invariants, which invariants are
+//! relevant to which functions, based on the memory usage summaries of the functions and the
+//! memory used by the invariants. The result is a bidirectional map attached to the function
+//! targets holder, so that later passes do not need to recompute it.
+
+use crate::{
+    function_target::FunctionTarget,
+    function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    usage_analysis,
+    verification_analysis::InvariantRelevance,
+};
+use itertools::Itertools;
+use move_model::{
+    ast::GlobalInvariant,
+    model::{FunId, GlobalEnv, GlobalId, QualifiedId},
+    ty::{TypeUnificationAdapter, Variance},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Formatter},
+};
+
+/// The relevance of global invariants to functions, in both directions. This ignores the
+/// invariant-related pragmas of functions and invariants.
+#[derive(Clone, Default)]
+pub struct InvariantRelevanceMap {
+    /// For each function, the invariants relevant to it.
+    pub fun_to_invs: BTreeMap<QualifiedId<FunId>, InvariantRelevance>,
+    /// For each invariant, the functions which access memory it refers to.
+    pub inv_to_accessing_funs: BTreeMap<GlobalId, BTreeSet<QualifiedId<FunId>>>,
+    /// For each invariant, the functions which modify memory it refers to.
+    pub inv_to_modifying_funs: BTreeMap<GlobalId, BTreeSet<QualifiedId<FunId>>>,
+}
+
+impl InvariantRelevanceMap {
+    /// Compute the map for all functions in the targets holder.
+    pub fn compute(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> Self {
+        // collect all global invariants
+        let mut global_invariants = vec![];
+        for menv in env.get_modules() {
+            for inv_id in env.get_global_invariants_by_module(menv.get_id()) {
+                global_invariants.push(env.get_global_invariant(inv_id).unwrap());
+            }
+        }
+
+        // go over each function target and check global invariant applicability
+        let mut result = Self::default();
+        for (fun_id, fun_variant) in targets.get_funs_and_variants() {
+            debug_assert!(matches!(fun_variant, FunctionVariant::Baseline));
+            let fenv = env.get_function(fun_id);
+            let target = targets.get_target(&fenv, &fun_variant);
+            let related = find_relevant_invariants(&target, global_invariants.iter().copied());
+            for inv_id in &related.accessed {
+                result
+                    .inv_to_accessing_funs
+                    .entry(*inv_id)
+                    .or_default()
+                    .insert(fun_id);
+            }
+            for inv_id in &related.modified {
+                result
+                    .inv_to_modifying_funs
+                    .entry(*inv_id)
+                    .or_default()
+                    .insert(fun_id);
+            }
+            result.fun_to_invs.insert(fun_id, related);
+        }
+        result
+    }
+}
+
+/// Get the invariant relevance map attached to the holder, if it has been computed.
+pub fn get_invariant_relevance(targets: &FunctionTargetsHolder) -> Option<&InvariantRelevanceMap> {
+    targets.get_annotations().get::<InvariantRelevanceMap>()
+}
+
+/// From the iterator of global invariants, find the ones that are relevant to the function as
+/// well as how/why the invariant is relevant.
+pub fn find_relevant_invariants<'a>(
+    target: &FunctionTarget,
+    invariants: impl Iterator<Item = &'a GlobalInvariant>,
+) -> InvariantRelevance {
+    let mem_usage = usage_analysis::get_memory_usage(target);
+    let mem_accessed = &mem_usage.accessed.all;
+    let mem_modified = &mem_usage.modified.all;
+    let mem_direct_accessed = &mem_usage.accessed.direct;
+    let mem_direct_modified = &mem_usage.modified.direct;
+
+    let mut inv_accessed = BTreeSet::new();
+    let mut inv_modified = BTreeSet::new();
+    let mut inv_direct_accessed = BTreeSet::new();
+    let mut inv_direct_modified = BTreeSet::new();
+    for inv in invariants {
+        for fun_mem in mem_accessed.iter() {
+            for inv_mem in &inv.mem_usage {
+                if inv_mem.module_id != fun_mem.module_id || inv_mem.id != fun_mem.id {
+                    continue;
+                }
+                let adapter =
+                    TypeUnificationAdapter::new_vec(&fun_mem.inst, &inv_mem.inst, true, true);
+                let rel = adapter.unify(Variance::Allow, /* shallow_subst */ false);
+                if rel.is_some() {
+                    inv_accessed.insert(inv.id);
+
+                    // the rest exploits the fact that the `used_memory` set (a read-write set)
+                    // is always a superset of the others.
+                    if mem_modified.contains(fun_mem) {
+                        inv_modified.insert(inv.id);
+                    }
+                    if mem_direct_accessed.contains(fun_mem) {
+                        inv_direct_accessed.insert(inv.id);
+                    }
+                    if mem_direct_modified.contains(fun_mem) {
+                        inv_direct_modified.insert(inv.id);
+                    }
+                }
+            }
+        }
+    }
+    InvariantRelevance {
+        accessed: inv_accessed,
+        modified: inv_modified,
+        direct_accessed: inv_direct_accessed,
+        direct_modified: inv_direct_modified,
+    }
+}
+
+// The function target processor
+pub struct InvariantRelevanceProcessor();
+
+impl InvariantRelevanceProcessor {
+    pub fn new() -> Box<Self> {
+        Box::new(Self())
+    }
+}
+
+impl FunctionTargetProcessor for InvariantRelevanceProcessor {
+    fn name(&self) -> String {
+        "invariant_relevance".to_string()
+    }
+
+    fn is_single_run(&self) -> bool {
+        true
+    }
+
+    fn run(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        let relevance = InvariantRelevanceMap::compute(env, targets);
+        targets.get_annotations_mut().set(relevance);
+    }
+
+    fn dump_result(
+        &self,
+        f: &mut Formatter<'_>,
+        env: &GlobalEnv,
+        targets: &FunctionTargetsHolder,
+    ) -> fmt::Result {
+        writeln!(f, "\n********* Result of invariant relevance *********\n")?;
+        let relevance = match get_invariant_relevance(targets) {
+            Some(relevance) => relevance,
+            None => return Ok(()),
+        };
+        let fmt_funs = |funs: Option<&BTreeSet<QualifiedId<FunId>>>| -> String {
+            funs.into_iter()
+                .flatten()
+                .map(|fun_id| env.get_function(*fun_id).get_full_name_str())
+                .join(", ")
+        };
+        writeln!(f, "invariant to functions: [")?;
+        let inv_ids: BTreeSet<_> = relevance
+            .inv_to_accessing_funs
+            .keys()
+            .chain(relevance.inv_to_modifying_funs.keys())
+            .collect();
+        for inv_id in inv_ids {
+            writeln!(f, "  {}: {{", inv_id)?;
+            writeln!(
+                f,
+                "    accessed by: [{}]",
+                fmt_funs(relevance.inv_to_accessing_funs.get(inv_id))
+            )?;
+            writeln!(
+                f,
+                "    modified by: [{}]",
+                fmt_funs(relevance.inv_to_modifying_funs.get(inv_id))
+            )?;
+            writeln!(f, "  }}")?;
+        }
+        writeln!(f, "]")
+    }
+}
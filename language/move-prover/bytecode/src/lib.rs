@@ -12,9 +12,11 @@ pub mod annotations;
 pub mod borrow_analysis;
 pub mod clean_and_optimize;
 pub mod compositional_analysis;
+pub mod constant_propagation;
 pub mod data_invariant_instrumentation;
 pub mod dataflow_analysis;
 pub mod dataflow_domains;
+pub mod dead_spec_elimination;
 pub mod debug_instrumentation;
 pub mod eliminate_imm_refs;
 pub mod function_data_builder;
@@ -25,6 +27,9 @@ pub mod global_invariant_instrumentation;
 pub mod global_invariant_instrumentation_v2;
 pub mod graph;
 pub mod inconsistency_check;
+pub mod inlining;
+pub mod invariant_explanation;
+pub mod invariant_relevance;
 pub mod livevar_analysis;
 pub mod loop_analysis;
 pub mod memory_instrumentation;
@@ -34,12 +39,16 @@ pub mod mutation_tester;
 pub mod options;
 pub mod packed_types_analysis;
 pub mod pipeline_factory;
+pub mod pipeline_graph;
+pub mod prover_diagnostics;
 pub mod reaching_def_analysis;
 pub mod read_write_set_analysis;
 pub mod spec_instrumentation;
 pub mod stackless_bytecode;
 pub mod stackless_bytecode_generator;
 pub mod stackless_control_flow_graph;
+pub mod summary_store;
+pub mod text_diff;
 pub mod usage_analysis;
 pub mod verification_analysis;
 pub mod verification_analysis_v2;
@@ -64,3 +73,28 @@ pub fn print_targets_for_test(
     }
     text
 }
+
+/// Print the bytecode of function targets interleaved with their source, for debugging.
+pub fn print_targets_with_source(
+    env: &GlobalEnv,
+    header: &str,
+    targets: &FunctionTargetsHolder,
+) -> String {
+    let mut text = String::new();
+    text.push_str(&format!("============ {} ================\n", header));
+    for module_env in env.get_modules() {
+        for func_env in module_env.get_functions() {
+            for (variant, target) in targets.get_targets(&func_env) {
+                if !target.data.code.is_empty() {
+                    text += &format!(
+                        "\n[variant {}]\nfun {} {{\n{}\n}}\n",
+                        variant,
+                        func_env.get_full_name_str(),
+                        target.pretty_print_with_source()
+                    );
+                }
+            }
+        }
+    }
+    text
+}
@@ -3,12 +3,20 @@
 
 // Live variable analysis with subsequent dead assignment elimination and
 // computation of new Destroy instructions.
+//
+// Temporaries used by `Prop` instructions are live at the instruction, so stores feeding spec
+// expressions survive the elimination. In aggressive mode, which is enabled by
+// `ProverOptions::aggressive_dead_store_elimination`, dead loads of constants and dead results
+// of pure operations which cannot abort are eliminated as well. As the conditions of the
+// function spec are only injected at the exits of the function later, the temporaries they use
+// are then also live at each exit, so that no value they refer to is eliminated.
 
 use crate::{
     dataflow_analysis::{DataflowAnalysis, TransferFunctions},
     dataflow_domains::{AbstractDomain, JoinResult},
     function_target::{FunctionData, FunctionTarget},
     function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder},
+    options::ProverOptions,
     stackless_bytecode::{AbortAction, AttrId, Bytecode, Label, Operation},
     stackless_control_flow_graph::StacklessControlFlowGraph,
 };
@@ -92,7 +100,7 @@ impl FunctionTargetProcessor for LiveVarAnalysisProcessor {
             let offset_to_live_refs = LiveVarAnnotation(Self::analyze(&func_target, &data.code));
             // Annotate function target with computed life variable data.
             data.annotations
-                .set::<LiveVarAnnotation>(offset_to_live_refs);
+                .set_code_dependent::<LiveVarAnnotation>(offset_to_live_refs);
         }
         data
     }
@@ -100,6 +108,10 @@ impl FunctionTargetProcessor for LiveVarAnalysisProcessor {
     fn name(&self) -> String {
         "livevar_analysis".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 impl LiveVarAnalysisProcessor {
@@ -173,6 +185,11 @@ struct LiveVarAnalysis<'a> {
     func_target: &'a FunctionTarget<'a>,
     next_label_id: usize,
     next_attr_id: usize,
+    /// The temporaries used by the conditions of the function spec, which are live at exits in
+    /// aggressive mode.
+    spec_temps: Vec<TempIndex>,
+    /// Whether to also eliminate dead loads and dead results of pure operations.
+    aggressive: bool,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd)]
@@ -200,13 +217,52 @@ impl LiveVarState {
 
 impl<'a> LiveVarAnalysis<'a> {
     fn new(func_target: &'a FunctionTarget, next_label_id: usize, next_attr_id: usize) -> Self {
+        let aggressive =
+            ProverOptions::get(func_target.global_env()).aggressive_dead_store_elimination;
         Self {
             func_target,
             next_label_id,
             next_attr_id,
+            spec_temps: if aggressive {
+                Self::get_spec_temps(func_target)
+            } else {
+                vec![]
+            },
+            aggressive,
         }
     }
 
+    /// Returns the temporaries of non-reference type used by the conditions of the function
+    /// spec. References are excluded since their lifetime is determined by the borrow
+    /// semantics, and the instrumentation of specs treats them separately.
+    fn get_spec_temps(func_target: &FunctionTarget) -> Vec<TempIndex> {
+        let env = func_target.global_env();
+        func_target
+            .func_env
+            .get_spec()
+            .conditions
+            .iter()
+            .flat_map(|cond| cond.all_exps())
+            .flat_map(|exp| exp.temporaries(env))
+            .map(|(idx, _)| idx)
+            .filter(|idx| {
+                *idx < func_target.get_local_count()
+                    && !func_target.get_local_type(*idx).is_reference()
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Returns whether the operation has no effect besides defining its destinations, and
+    /// cannot abort.
+    fn is_pure(oper: &Operation) -> bool {
+        use Operation::*;
+        matches!(
+            oper,
+            Not | And | Or | Eq | Neq | Lt | Le | Gt | Ge | BitOr | BitAnd | Xor
+        )
+    }
+
     fn transform_code(
         &mut self,
         annotations: &BTreeMap<CodeOffset, LiveVarInfoAtCodeOffset>,
@@ -248,6 +304,19 @@ impl<'a> LiveVarAnalysis<'a> {
                 Bytecode::Assign(_, dest, _, _) if !annotation_at.after.contains(&dest) => {
                     // Drop this assign as it is not used.
                 }
+                Bytecode::Load(_, dest, _)
+                    if self.aggressive && !annotation_at.after.contains(&dest) =>
+                {
+                    // Drop this load as it is not used.
+                }
+                Bytecode::Call(_, dests, oper, _, None)
+                    if self.aggressive
+                        && Self::is_pure(&oper)
+                        && !dests.is_empty()
+                        && dests.iter().all(|dest| !annotation_at.after.contains(dest)) =>
+                {
+                    // Drop this operation as its results are not used.
+                }
                 Bytecode::Call(attr_id, dests, oper, srcs, aa)
                     if code_offset + 1 < code.len() && dests.len() == 1 =>
                 {
@@ -366,8 +435,13 @@ impl<'a> TransferFunctions for LiveVarAnalysis<'a> {
             }
             Ret(_, srcs) => {
                 state.insert(srcs);
+                state.insert(&self.spec_temps);
+            }
+            Abort(_, src) => {
+                state.insert(&[*src]);
+                state.insert(&self.spec_temps);
             }
-            Abort(_, src) | Branch(_, _, _, src) => {
+            Branch(_, _, _, src) => {
                 state.insert(&[*src]);
             }
             Prop(_, _, exp) => {
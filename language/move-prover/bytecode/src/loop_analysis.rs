@@ -9,12 +9,13 @@ use crate::{
     options::ProverOptions,
     stackless_bytecode::{AttrId, Bytecode, HavocKind, Label, Operation, PropKind},
     stackless_control_flow_graph::{BlockContent, BlockId, StacklessControlFlowGraph},
+    usage_analysis::LoopUsageAnnotation,
 };
 use move_binary_format::file_format::CodeOffset;
 use move_model::{
     ast::{self, TempIndex},
     exp_generator::ExpGenerator,
-    model::FunctionEnv,
+    model::{FunctionEnv, QualifiedId, StructId},
 };
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -36,6 +37,9 @@ pub struct FatLoop {
     pub val_targets: BTreeSet<TempIndex>,
     pub mut_targets: BTreeMap<TempIndex, bool>,
     pub back_edges: BTreeSet<CodeOffset>,
+    /// Invariants which refer neither to loop targets nor to memory modified in the loop body,
+    /// and hence are trivially preserved by an iteration of the loop.
+    pub unaffected_invariants: BTreeSet<CodeOffset>,
 }
 
 #[derive(Debug, Clone)]
@@ -74,20 +78,24 @@ impl LoopAnalysisProcessor {
 impl FunctionTargetProcessor for LoopAnalysisProcessor {
     fn process(
         &self,
-        _targets: &mut FunctionTargetsHolder,
+        targets: &mut FunctionTargetsHolder,
         func_env: &FunctionEnv<'_>,
         data: FunctionData,
     ) -> FunctionData {
         if func_env.is_native() {
             return data;
         }
-        let loop_annotation = Self::build_loop_annotation(func_env, &data);
+        let loop_annotation = Self::build_loop_annotation(targets, func_env, &data);
         Self::transform(func_env, data, &loop_annotation)
     }
 
     fn name(&self) -> String {
         "loop_analysis".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 impl LoopAnalysisProcessor {
@@ -215,7 +223,12 @@ impl LoopAnalysisProcessor {
             builder.clear_next_debug_comment();
 
             // add instrumentations to assert loop invariants -> this is the induction case
-            for (attr_id, exp) in loop_info.invariants.values() {
+            for (code_offset, (attr_id, exp)) in &loop_info.invariants {
+                if loop_info.unaffected_invariants.contains(code_offset) {
+                    // the invariant is assumed at the loop header and cannot be changed by the
+                    // loop body, so there is nothing to check
+                    continue;
+                }
                 builder.set_loc_and_vc_info(
                     builder.get_loc(*attr_id),
                     LOOP_INVARIANT_INDUCTION_FAILED,
@@ -343,6 +356,43 @@ impl LoopAnalysisProcessor {
         (val_targets, mut_targets)
     }
 
+    /// Collect the invariants which cannot be affected by an iteration of the loop, i.e., which do
+    /// not refer to any loop target and only refer to memory not modified in the loop body.
+    ///
+    /// This relies on the per-loop memory usage computed by the usage analysis. If it is not
+    /// available, all invariants are considered affected.
+    fn collect_unaffected_invariants(
+        func_target: &FunctionTarget<'_>,
+        header_label: Label,
+        invariants: &BTreeMap<CodeOffset, (AttrId, ast::Exp)>,
+        val_targets: &BTreeSet<TempIndex>,
+        mut_targets: &BTreeMap<TempIndex, bool>,
+    ) -> BTreeSet<CodeOffset> {
+        let env = func_target.global_env();
+        let loop_usage = match func_target
+            .get_annotations()
+            .get::<LoopUsageAnnotation>()
+            .and_then(|an| an.get_loop_usage(&header_label))
+        {
+            Some(usage) => usage,
+            None => return BTreeSet::new(),
+        };
+        let modified: BTreeSet<QualifiedId<StructId>> = loop_usage.modified.get_all_uninst();
+        invariants
+            .iter()
+            .filter(|(_, (_, exp))| {
+                exp.temporaries(env)
+                    .iter()
+                    .all(|(idx, _)| !val_targets.contains(idx) && !mut_targets.contains_key(idx))
+                    && exp
+                        .used_memory(env)
+                        .iter()
+                        .all(|(mem, _)| !modified.contains(&mem.module_id.qualified(mem.id)))
+            })
+            .map(|(code_offset, _)| *code_offset)
+            .collect()
+    }
+
     /// Collect code offsets that are branch instructions forming loop back-edges
     ///
     /// The input to this function should include all the sub loops that constitute a fat-loop.
@@ -375,53 +425,34 @@ impl LoopAnalysisProcessor {
 
     /// Find all loops in the function and collect information needed for invariant instrumentation
     /// and loop-to-DAG transformation.
-    fn build_loop_annotation(func_env: &FunctionEnv<'_>, data: &FunctionData) -> LoopAnnotation {
+    fn build_loop_annotation(
+        targets: &FunctionTargetsHolder,
+        func_env: &FunctionEnv<'_>,
+        data: &FunctionData,
+    ) -> LoopAnnotation {
         // build for natural loops
         let func_target = FunctionTarget::new(func_env, data);
         let code = func_target.get_bytecode();
         let cfg = StacklessControlFlowGraph::new_forward(code);
-        let entry = cfg.entry_block();
-        let nodes = cfg.blocks();
-        let edges: Vec<(BlockId, BlockId)> = nodes
-            .iter()
-            .map(|x| {
-                cfg.successors(*x)
-                    .iter()
-                    .map(|y| (*x, *y))
-                    .collect::<Vec<(BlockId, BlockId)>>()
-            })
-            .flatten()
-            .collect();
-        let graph = Graph::new(entry, nodes, edges);
-        let natural_loops = graph.compute_reducible().expect(
+        let fat_headers = find_fat_loops(&cfg).expect(
             "A well-formed Move function is expected to have a reducible control-flow graph",
         );
 
-        // collect shared headers from loops
-        let mut fat_headers = BTreeMap::new();
-        for single_loop in natural_loops {
-            fat_headers
-                .entry(single_loop.loop_header)
-                .or_insert_with(Vec::new)
-                .push(single_loop);
-        }
-
         // build fat loops by label
         let mut fat_loops = BTreeMap::new();
         for (fat_root, sub_loops) in fat_headers {
-            // get the label of the scc root
-            let label = match cfg.content(fat_root) {
-                BlockContent::Dummy => panic!("A loop header should never be a dummy block"),
-                BlockContent::Basic { lower, upper: _ } => match code[*lower as usize] {
-                    Bytecode::Label(_, label) => label,
-                    _ => panic!("A loop header block is expected to start with a Label bytecode"),
-                },
-            };
-
+            let label = get_loop_header_label(code, &cfg, fat_root);
             let invariants = Self::collect_loop_invariants(&cfg, &func_target, fat_root);
             let (val_targets, mut_targets) =
                 Self::collect_loop_targets(&cfg, &func_target, &sub_loops);
             let back_edges = Self::collect_loop_back_edges(code, &cfg, label, &sub_loops);
+            let unaffected_invariants = Self::collect_unaffected_invariants(
+                &func_target,
+                label,
+                &invariants,
+                &val_targets,
+                &mut_targets,
+            );
 
             // done with all information collection
             fat_loops.insert(
@@ -431,6 +462,7 @@ impl LoopAnalysisProcessor {
                     val_targets,
                     mut_targets,
                     back_edges,
+                    unaffected_invariants,
                 },
             );
         }
@@ -442,9 +474,8 @@ impl LoopAnalysisProcessor {
             .flatten()
             .collect();
 
-        let env = func_target.global_env();
         for attr_id in data.loop_invariants.difference(&all_invariants) {
-            env.error(
+            targets.diagnostics().error(
                 &func_target.get_bytecode_loc(*attr_id),
                 "Loop invariants must be declared at the beginning of the loop header in a \
                 consecutive sequence",
@@ -454,3 +485,49 @@ impl LoopAnalysisProcessor {
         LoopAnnotation { fat_loops }
     }
 }
+
+/// Find the natural loops in the control-flow graph, grouped by their shared loop header. Returns
+/// None if the graph is irreducible.
+pub fn find_fat_loops(
+    cfg: &StacklessControlFlowGraph,
+) -> Option<BTreeMap<BlockId, Vec<NaturalLoop<BlockId>>>> {
+    let entry = cfg.entry_block();
+    let nodes = cfg.blocks();
+    let edges: Vec<(BlockId, BlockId)> = nodes
+        .iter()
+        .map(|x| {
+            cfg.successors(*x)
+                .iter()
+                .map(|y| (*x, *y))
+                .collect::<Vec<(BlockId, BlockId)>>()
+        })
+        .flatten()
+        .collect();
+    let graph = Graph::new(entry, nodes, edges);
+    let natural_loops = graph.compute_reducible()?;
+
+    // collect shared headers from loops
+    let mut fat_headers = BTreeMap::new();
+    for single_loop in natural_loops {
+        fat_headers
+            .entry(single_loop.loop_header)
+            .or_insert_with(Vec::new)
+            .push(single_loop);
+    }
+    Some(fat_headers)
+}
+
+/// Get the label which starts the given loop header block.
+pub fn get_loop_header_label(
+    code: &[Bytecode],
+    cfg: &StacklessControlFlowGraph,
+    header: BlockId,
+) -> Label {
+    match cfg.content(header) {
+        BlockContent::Dummy => panic!("A loop header should never be a dummy block"),
+        BlockContent::Basic { lower, upper: _ } => match code[*lower as usize] {
+            Bytecode::Label(_, label) => label,
+            _ => panic!("A loop header block is expected to start with a Label bytecode"),
+        },
+    }
+}
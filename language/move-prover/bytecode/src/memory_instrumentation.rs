@@ -54,6 +54,10 @@ impl FunctionTargetProcessor for MemoryInstrumentationProcessor {
     fn name(&self) -> String {
         "memory_instr".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 struct Instrumenter<'a> {
@@ -77,7 +77,7 @@ impl FunctionTargetProcessor for MonoAnalysisProcessor {
         self.analyze(env, None, targets);
     }
 
-    fn finalize(&self, env: &GlobalEnv, _targets: &mut FunctionTargetsHolder) {
+    fn finalize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
         // TODO: specialize axioms based on functions they are using. For now,
         //   we can't deal with generic axioms.
         let mut axioms = vec![];
@@ -87,7 +87,9 @@ impl FunctionTargetProcessor for MonoAnalysisProcessor {
                     if params.is_empty() {
                         axioms.push(cond.clone());
                     } else {
-                        env.error(&cond.loc, "generic axioms not yet supported")
+                        targets
+                            .diagnostics()
+                            .error(&cond.loc, "generic axioms not yet supported")
                     }
                 }
             }
@@ -289,7 +291,7 @@ impl<'a> Analyzer<'a> {
                         .entry(callee.module_env.get_id())
                         .or_default()
                         .insert(actuals);
-                } else if !callee.is_opaque() {
+                } else if !verification_analysis::is_opaque_call(self.targets, callee) {
                     // This call needs to be inlined, with targs instantiated by self.inst_opt.
                     // Schedule for later processing if this instance has not been processed yet.
                     let entry = (mid.qualified(*fid), actuals);
@@ -77,6 +77,10 @@ impl FunctionTargetProcessor for MutRefInstrumenter {
     fn name(&self) -> String {
         "mut_ref_instrumentation".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 fn is_mut_ref(builder: &FunctionDataBuilder<'_>, idx: TempIndex) -> bool {
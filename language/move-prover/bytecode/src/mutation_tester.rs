@@ -206,4 +206,8 @@ impl FunctionTargetProcessor for MutationTester {
     fn name(&self) -> String {
         "mutation_tester".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
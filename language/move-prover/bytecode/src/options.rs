@@ -4,7 +4,7 @@
 use codespan_reporting::diagnostic::Severity;
 use move_model::model::{GlobalEnv, VerificationScope};
 use serde::{Deserialize, Serialize};
-use std::rc::Rc;
+use std::{collections::BTreeMap, rc::Rc};
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum AutoTraceLevel {
@@ -44,6 +44,9 @@ pub struct ProverOptions {
     pub stable_test_output: bool,
     /// Scope of what functions to verify.
     pub verify_scope: VerificationScope,
+    /// If non-empty, only verify the functions which modify the given memory (structs named as
+    /// `0xADDR::Module::Struct`), together with the invariants over this memory.
+    pub verify_memory: Vec<String>,
     /// [deprecated] Whether to emit global axiom that resources are well-formed.
     pub resource_wellformed_axiom: bool,
     /// Whether to assume wellformedness when elements are read from memory, instead of on
@@ -69,8 +72,15 @@ pub struct ProverOptions {
     pub report_severity: Severity,
     /// Whether to dump the transformed stackless bytecode to a file
     pub dump_bytecode: bool,
+    /// Whether to dump unified diffs of the bytecode between consecutive pipeline stages to files
+    pub dump_bytecode_diff: bool,
+    /// Pipeline stage (processor name) after which to dump the bytecode interleaved with the
+    /// source and spec conditions it stems from, if any
+    pub dump_bytecode_with_source: Option<String>,
     /// Whether to dump the control-flow graphs (in dot format) to files, one per each function
     pub dump_cfg: bool,
+    /// Whether to dump a graph (in dot format) of the pipeline stages and their dependencies
+    pub dump_pipeline_graph: bool,
     /// Number of Boogie instances to be run concurrently.
     pub num_instances: usize,
     /// Whether to run Boogie instances sequentially.
@@ -81,6 +91,30 @@ pub struct ProverOptions {
     pub unconditional_abort_as_inconsistency: bool,
     /// Whether to run the transformation passes for concrete interpretation (instead of proving)
     pub for_interpretation: bool,
+    /// Directory in which summaries of compositional analyses are persisted across runs, if any.
+    pub summary_cache_dir: Option<String>,
+    /// Minimal severity per pipeline stage (processor name) for diagnostics to be reported.
+    /// Stages without an entry report all diagnostics.
+    pub stage_severity: BTreeMap<String, Severity>,
+    /// File to which the diagnostics of the pipeline stages are written in JSON, if any.
+    pub diagnostics_json: Option<String>,
+    /// Whether functions which are not verified are trusted to satisfy their contracts. Calls
+    /// to such functions are treated like calls to opaque functions instead of being inlined.
+    pub assume_unverified: bool,
+    /// File to which the ledger of unverified contracts assumed by the verified functions is
+    /// written in JSON, if any.
+    pub assumptions_ledger: Option<String>,
+    /// Directory in which verification results are cached across runs, if any.
+    pub verification_cache_dir: Option<String>,
+    /// File to which the resources that scripts may access and modify are written in JSON,
+    /// if any.
+    pub script_usage: Option<String>,
+    /// Maximal number of instructions of a callee which is inlined into its callers before
+    /// verification. 0 means only callees with `pragma inline = true` are inlined.
+    pub inline_threshold: usize,
+    /// Whether dead store elimination also removes dead loads of constants and dead results of
+    /// pure operations, which shrinks the verification conditions.
+    pub aggressive_dead_store_elimination: bool,
 }
 
 // add custom struct for mutation options
@@ -94,6 +128,7 @@ impl Default for ProverOptions {
             omit_model_debug: false,
             stable_test_output: false,
             verify_scope: VerificationScope::All,
+            verify_memory: vec![],
             resource_wellformed_axiom: false,
             assume_wellformed_on_access: false,
             mutation: false,
@@ -106,12 +141,24 @@ impl Default for ProverOptions {
             auto_trace_level: AutoTraceLevel::Off,
             report_severity: Severity::Warning,
             dump_bytecode: false,
+            dump_bytecode_diff: false,
+            dump_bytecode_with_source: None,
             dump_cfg: false,
+            dump_pipeline_graph: false,
             num_instances: 1,
             sequential_task: false,
             check_inconsistency: false,
             unconditional_abort_as_inconsistency: false,
             for_interpretation: false,
+            summary_cache_dir: None,
+            stage_severity: BTreeMap::new(),
+            diagnostics_json: None,
+            assume_unverified: false,
+            assumptions_ledger: None,
+            verification_cache_dir: None,
+            script_usage: None,
+            inline_threshold: 0,
+            aggressive_dead_store_elimination: false,
         }
     }
 }
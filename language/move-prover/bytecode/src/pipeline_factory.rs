@@ -4,13 +4,17 @@
 use crate::{
     borrow_analysis::BorrowAnalysisProcessor,
     clean_and_optimize::CleanAndOptimizeProcessor,
+    constant_propagation::ConstantPropagationProcessor,
     data_invariant_instrumentation::DataInvariantInstrumentationProcessor,
+    dead_spec_elimination::DeadSpecEliminationProcessor,
     debug_instrumentation::DebugInstrumenter,
     eliminate_imm_refs::EliminateImmRefsProcessor,
     function_target_pipeline::{FunctionTargetPipeline, FunctionTargetProcessor},
     global_invariant_analysis::GlobalInvariantAnalysisProcessor,
     global_invariant_instrumentation::GlobalInvariantInstrumentationProcessor,
     inconsistency_check::InconsistencyCheckInstrumenter,
+    inlining::InliningProcessor,
+    invariant_relevance::InvariantRelevanceProcessor,
     livevar_analysis::LiveVarAnalysisProcessor,
     loop_analysis::LoopAnalysisProcessor,
     memory_instrumentation::MemoryInstrumentationProcessor,
@@ -27,6 +31,7 @@ use crate::{
 pub fn default_pipeline_with_options(options: &ProverOptions) -> FunctionTargetPipeline {
     // NOTE: the order of these processors is import!
     let mut processors: Vec<Box<dyn FunctionTargetProcessor>> = vec![
+        InliningProcessor::new(),
         DebugInstrumenter::new(),
         // transformation and analysis
         EliminateImmRefsProcessor::new(),
@@ -36,7 +41,9 @@ pub fn default_pipeline_with_options(options: &ProverOptions) -> FunctionTargetP
         BorrowAnalysisProcessor::new(),
         MemoryInstrumentationProcessor::new(),
         CleanAndOptimizeProcessor::new(),
+        ConstantPropagationProcessor::new(),
         UsageProcessor::new(),
+        InvariantRelevanceProcessor::new(),
         VerificationAnalysisProcessor::new(),
         LoopAnalysisProcessor::new(),
         // spec instrumentation
@@ -44,6 +51,8 @@ pub fn default_pipeline_with_options(options: &ProverOptions) -> FunctionTargetP
         DataInvariantInstrumentationProcessor::new(),
         GlobalInvariantAnalysisProcessor::new(),
         GlobalInvariantInstrumentationProcessor::new(),
+        // optimization
+        DeadSpecEliminationProcessor::new(),
     ];
 
     if options.mutation {
@@ -80,6 +89,7 @@ pub fn default_pipeline() -> FunctionTargetPipeline {
 pub fn experimental_pipeline() -> FunctionTargetPipeline {
     // Enter your pipeline here
     let processors: Vec<Box<dyn FunctionTargetProcessor>> = vec![
+        InliningProcessor::new(),
         DebugInstrumenter::new(),
         // transformation and analysis
         EliminateImmRefsProcessor::new(),
@@ -89,7 +99,9 @@ pub fn experimental_pipeline() -> FunctionTargetPipeline {
         BorrowAnalysisProcessor::new(),
         MemoryInstrumentationProcessor::new(),
         CleanAndOptimizeProcessor::new(),
+        ConstantPropagationProcessor::new(),
         UsageProcessor::new(),
+        InvariantRelevanceProcessor::new(),
         VerificationAnalysisProcessor::new(),
         LoopAnalysisProcessor::new(),
         // spec instrumentation
@@ -98,6 +110,7 @@ pub fn experimental_pipeline() -> FunctionTargetPipeline {
         GlobalInvariantAnalysisProcessor::new(),
         GlobalInvariantInstrumentationProcessor::new(),
         // optimization
+        DeadSpecEliminationProcessor::new(),
         MonoAnalysisProcessor::new(),
     ];
 
@@ -0,0 +1,171 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A record of the stages run by a function target pipeline, for visualization.
+//!
+//! For each stage, the graph records the function variants present after the stage, and which
+//! annotations the stage produced and read. Rendered in DOT format, this shows the order of the
+//! processors together with the dependencies between them via annotations, e.g. that global
+//! invariant instrumentation depends on the usage analysis.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The information recorded for one stage of the pipeline.
+#[derive(Debug, Clone)]
+pub struct PipelineStage {
+    /// The name of the processor.
+    pub name: String,
+    /// The number of function variants, by variant name, after this stage.
+    pub variants: BTreeMap<String, usize>,
+    /// The annotation types read by this stage.
+    pub reads: BTreeSet<String>,
+    /// The annotation types written by this stage.
+    pub writes: BTreeSet<String>,
+}
+
+/// The stages of a pipeline run, in order.
+#[derive(Debug, Clone, Default)]
+pub struct PipelineGraph {
+    pub stages: Vec<PipelineStage>,
+}
+
+/// Strips the module paths from a type name, including those of its type arguments, e.g.
+/// `alloc::vec::Vec<bytecode::annotations::Data>` becomes `Vec<Data>`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+        } else {
+            short.push_str(path.rsplit("::").next().unwrap_or_default());
+            path.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(path.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+impl PipelineGraph {
+    /// Records a stage, given the variant names of all function variants after the stage and
+    /// the full type names of the annotations it read and wrote.
+    pub fn add_stage(
+        &mut self,
+        name: String,
+        variants: Vec<String>,
+        reads: BTreeSet<&'static str>,
+        writes: BTreeSet<&'static str>,
+    ) {
+        let mut variant_counts = BTreeMap::new();
+        for variant in variants {
+            *variant_counts.entry(variant).or_insert(0) += 1;
+        }
+        self.stages.push(PipelineStage {
+            name,
+            variants: variant_counts,
+            reads: reads.into_iter().map(short_type_name).collect(),
+            writes: writes.into_iter().map(short_type_name).collect(),
+        })
+    }
+
+    /// Renders the graph in DOT format. Stages are connected in pipeline order. In addition, a
+    /// stage which reads an annotation is connected to the latest earlier stage producing it.
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![
+            "digraph pipeline {".to_string(),
+            "  rankdir=TB;".to_string(),
+            "  node [shape=box];".to_string(),
+        ];
+        for (idx, stage) in self.stages.iter().enumerate() {
+            let variants = stage
+                .variants
+                .iter()
+                .map(|(variant, count)| format!("{} x{}", variant, count))
+                .collect::<Vec<_>>()
+                .join(", ");
+            lines.push(format!(
+                "  stage{} [label=\"{}: {}\\n{}\"];",
+                idx,
+                idx + 1,
+                stage.name,
+                variants
+            ));
+            if idx > 0 {
+                lines.push(format!("  stage{} -> stage{};", idx - 1, idx));
+            }
+        }
+
+        // The latest stage which produced each annotation so far.
+        let mut producers: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut edges = BTreeSet::new();
+        for (idx, stage) in self.stages.iter().enumerate() {
+            for annotation in &stage.reads {
+                // Reading an annotation written by the same stage is not a dependency.
+                if stage.writes.contains(annotation) {
+                    continue;
+                }
+                if let Some(producer) = producers.get(annotation.as_str()) {
+                    edges.insert(format!(
+                        "  stage{} -> stage{} [style=dashed, color=blue, label=\"{}\"];",
+                        producer, idx, annotation
+                    ));
+                }
+            }
+            for annotation in &stage.writes {
+                producers.insert(annotation, idx);
+            }
+        }
+        lines.extend(edges);
+        lines.push("}".to_string());
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(short_type_name("u64"), "u64");
+        assert_eq!(
+            short_type_name("bytecode::usage_analysis::UsageState"),
+            "UsageState"
+        );
+        assert_eq!(
+            short_type_name("alloc::vec::Vec<bytecode::annotations::Data>"),
+            "Vec<Data>"
+        );
+        assert_eq!(
+            short_type_name("std::collections::BTreeMap<u16, (u64, alloc::string::String)>"),
+            "BTreeMap<u16, (u64, String)>"
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let names = |names: &[&'static str]| names.iter().copied().collect::<BTreeSet<_>>();
+        let mut graph = PipelineGraph::default();
+        graph.add_stage(
+            "borrow".to_string(),
+            vec!["baseline".to_string(), "baseline".to_string()],
+            names(&[]),
+            names(&["a::BorrowAnnotation"]),
+        );
+        graph.add_stage(
+            "usage".to_string(),
+            vec!["baseline".to_string(), "verification".to_string()],
+            names(&["a::BorrowAnnotation", "b::UsageState"]),
+            names(&["b::UsageState"]),
+        );
+        assert_eq!(
+            graph.to_dot(),
+            "digraph pipeline {\n  rankdir=TB;\n  node [shape=box];\n  \
+             stage0 [label=\"1: borrow\\nbaseline x2\"];\n  \
+             stage1 [label=\"2: usage\\nbaseline x1, verification x1\"];\n  \
+             stage0 -> stage1;\n  \
+             stage0 -> stage1 [style=dashed, color=blue, label=\"BorrowAnnotation\"];\n}\n"
+        );
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A collector for the diagnostics reported by the stages of the function target pipeline.
+//!
+//! Processors report diagnostics into the collector owned by the `FunctionTargetsHolder` they
+//! are given, instead of directly into the `GlobalEnv`. Every diagnostic is tagged with the
+//! stage (processor name) which reported it, which allows filtering by stage before the
+//! diagnostics are handed over to the environment, and producing a structured, deterministically
+//! ordered JSON report.
+
+use crate::options::ProverOptions;
+use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
+use move_model::model::{GlobalEnv, Loc};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+
+/// A diagnostic reported by a pipeline stage.
+#[derive(Debug, Clone)]
+pub struct ProverDiagnostic {
+    pub stage: String,
+    pub severity: Severity,
+    pub loc: Loc,
+    pub message: String,
+    pub notes: Vec<String>,
+}
+
+/// The JSON representation of a diagnostic.
+#[derive(Debug, Serialize)]
+pub struct ProverDiagnosticJson {
+    pub stage: String,
+    pub severity: String,
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    pub notes: Vec<String>,
+}
+
+/// Collects diagnostics from pipeline stages. Uses interior mutability so stages can report
+/// while only holding a shared reference to the function targets.
+#[derive(Debug, Default)]
+pub struct ProverDiagnostics {
+    stage: RefCell<String>,
+    diags: RefCell<Vec<ProverDiagnostic>>,
+    /// Number of diagnostics already handed over to the environment.
+    reported: Cell<usize>,
+}
+
+impl ProverDiagnostics {
+    /// Sets the stage to which subsequently reported diagnostics are attributed.
+    pub fn set_stage(&self, stage: &str) {
+        *self.stage.borrow_mut() = stage.to_string();
+    }
+
+    /// Adds an error, without notes.
+    pub fn error(&self, loc: &Loc, msg: &str) {
+        self.diag_with_notes(Severity::Error, loc, msg, vec![])
+    }
+
+    /// Adds an error, with notes.
+    pub fn error_with_notes(&self, loc: &Loc, msg: &str, notes: Vec<String>) {
+        self.diag_with_notes(Severity::Error, loc, msg, notes)
+    }
+
+    /// Adds a diagnostic of given severity.
+    pub fn diag(&self, severity: Severity, loc: &Loc, msg: &str) {
+        self.diag_with_notes(severity, loc, msg, vec![])
+    }
+
+    /// Adds a diagnostic of given severity, with notes.
+    pub fn diag_with_notes(&self, severity: Severity, loc: &Loc, msg: &str, notes: Vec<String>) {
+        self.diags.borrow_mut().push(ProverDiagnostic {
+            stage: self.stage.borrow().clone(),
+            severity,
+            loc: loc.clone(),
+            message: msg.to_string(),
+            notes,
+        })
+    }
+
+    /// Returns true if an error has been reported by any stage.
+    pub fn has_errors(&self) -> bool {
+        self.diags
+            .borrow()
+            .iter()
+            .any(|d| d.severity >= Severity::Error)
+    }
+
+    /// Returns true if the diagnostic passes the severity filter configured for its stage.
+    fn is_enabled(options: &ProverOptions, diag: &ProverDiagnostic) -> bool {
+        options
+            .stage_severity
+            .get(&diag.stage)
+            .map_or(true, |min_severity| diag.severity >= *min_severity)
+    }
+
+    /// Returns the diagnostics which pass the stage filters, in a deterministic order: by file,
+    /// position, stage, and message.
+    pub fn get_sorted(&self, env: &GlobalEnv) -> Vec<ProverDiagnostic> {
+        let options = ProverOptions::get(env);
+        let mut result = self
+            .diags
+            .borrow()
+            .iter()
+            .filter(|d| Self::is_enabled(&options, d))
+            .cloned()
+            .collect::<Vec<_>>();
+        result.sort_by(|d1, d2| {
+            let key = |d: &ProverDiagnostic| {
+                (
+                    env.get_file_and_location(&d.loc).map(|(file, _)| file),
+                    d.loc.span().start(),
+                    d.loc.span().end(),
+                )
+            };
+            key(d1)
+                .cmp(&key(d2))
+                .then_with(|| d1.stage.cmp(&d2.stage))
+                .then_with(|| d1.message.cmp(&d2.message))
+        });
+        result
+    }
+
+    /// Hands over the diagnostics not yet reported which pass the stage filters to the
+    /// environment, in the order they were reported.
+    pub fn report_to_env(&self, env: &GlobalEnv) {
+        let options = ProverOptions::get(env);
+        let diags = self.diags.borrow();
+        for diag in diags[self.reported.get()..]
+            .iter()
+            .filter(|d| Self::is_enabled(&options, d))
+        {
+            env.add_diag(
+                Diagnostic::new(diag.severity)
+                    .with_message(&diag.message)
+                    .with_labels(vec![Label::primary(diag.loc.file_id(), diag.loc.span())])
+                    .with_notes(diag.notes.clone()),
+            );
+        }
+        self.reported.set(diags.len());
+    }
+
+    /// Returns the diagnostics which pass the stage filters as JSON, in deterministic order.
+    pub fn to_json(&self, env: &GlobalEnv) -> serde_json::Value {
+        let entries = self
+            .get_sorted(env)
+            .into_iter()
+            .map(|d| {
+                let (file, line, column) = env
+                    .get_file_and_location(&d.loc)
+                    .map(|(file, l)| (file, l.line.0 as usize + 1, l.column.0 as usize + 1))
+                    .unwrap_or_default();
+                ProverDiagnosticJson {
+                    stage: d.stage,
+                    severity: format!("{:?}", d.severity).to_lowercase(),
+                    file,
+                    line,
+                    column,
+                    message: d.message,
+                    notes: d.notes,
+                }
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_value(entries).expect("diagnostics are serializable")
+    }
+}
@@ -147,6 +147,10 @@ impl FunctionTargetProcessor for ReachingDefProcessor {
     fn name(&self) -> String {
         "reaching_def_analysis".to_string()
     }
+
+    fn rewrites_code(&self) -> bool {
+        true
+    }
 }
 
 struct ReachingDefAnalysis<'a> {
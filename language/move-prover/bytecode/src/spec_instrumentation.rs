@@ -148,6 +148,10 @@ impl FunctionTargetProcessor for SpecInstrumentationProcessor {
         "spec_instrumenter".to_string()
     }
 
+    fn rewrites_code(&self) -> bool {
+        true
+    }
+
     fn dump_result(
         &self,
         f: &mut fmt::Formatter,
@@ -185,6 +189,9 @@ struct Instrumenter<'a> {
     ret_locals: Vec<TempIndex>,
     ret_label: Label,
     can_return: bool,
+    /// The callees which are trusted to satisfy their contract, see
+    /// `verification_analysis::is_trusted`.
+    trusted_callees: BTreeSet<QualifiedId<FunId>>,
     abort_local: TempIndex,
     abort_label: Label,
     can_abort: bool,
@@ -254,12 +261,21 @@ impl<'a> Instrumenter<'a> {
             .collect();
 
         // Create and run the instrumenter.
+        // Determine the callees which are called like opaque functions although they are not.
+        let env = fun_env.module_env.env;
+        let trusted_callees = fun_env
+            .get_called_functions()
+            .into_iter()
+            .filter(|callee| verification_analysis::is_trusted(targets, &env.get_function(*callee)))
+            .collect();
+
         let mut instrumenter = Instrumenter {
             options,
             builder,
             ret_locals,
             ret_label,
             can_return: false,
+            trusted_callees,
             abort_local,
             abort_label,
             can_abort: false,
@@ -529,7 +545,10 @@ impl<'a> Instrumenter<'a> {
         let env = self.builder.global_env();
 
         let callee_env = env.get_module(mid).into_function(fid);
-        let callee_opaque = callee_env.is_opaque();
+        let callee_opaque = callee_env.is_opaque()
+            || self
+                .trusted_callees
+                .contains(&callee_env.get_qualified_id());
         let mut callee_spec = SpecTranslator::translate_fun_spec(
             self.options.auto_trace_level.functions(),
             true,
@@ -590,7 +609,7 @@ impl<'a> Instrumenter<'a> {
         }
 
         // From here on code differs depending on whether the callee is opaque or not.
-        if !callee_env.is_opaque() || self.options.for_interpretation {
+        if !callee_opaque || self.options.for_interpretation {
             self.builder.emit(Call(
                 id,
                 dests,
@@ -1139,7 +1158,7 @@ fn check_caller_callee_modifies_relation(
                     .is_none()
             {
                 let loc = caller_func_target.get_loc();
-                env.error(
+                targets.diagnostics().error(
                     &loc,
                     &format!(
                         "caller `{}` specifies modify targets for `{}` but callee `{}` does not",
@@ -1168,22 +1187,15 @@ fn check_opaque_modifies_completeness(
     // a modifies clause. Otherwise we could introduce unsoundness.
     // TODO: we currently except Event::EventHandle from this, because this is treated as
     //   an immutable reference. We should find a better way how to deal with event handles.
-    for mem in usage_analysis::get_memory_usage(&target)
-        .modified
-        .all
-        .iter()
-    {
+    for mem in usage_analysis::get_memory_usage(&target).get_undeclared_modified() {
         if env.is_wellknown_event_handle_type(&Type::Struct(mem.module_id, mem.id, vec![])) {
             continue;
         }
-        let found = target.get_modify_ids().iter().any(|id| mem == id);
-        if !found {
-            let loc = fun_env.get_spec_loc();
-            env.error(&loc,
-            &format!("function `{}` is opaque but its specification does not have a modifies clause for `{}`",
-                fun_env.get_full_name_str(),
-                env.display(mem))
-            )
-        }
+        let loc = fun_env.get_spec_loc();
+        targets.diagnostics().error(&loc,
+        &format!("function `{}` is opaque but its specification does not have a modifies clause for `{}`",
+            fun_env.get_full_name_str(),
+            env.display(mem))
+        )
     }
 }
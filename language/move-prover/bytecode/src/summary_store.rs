@@ -0,0 +1,317 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A persistent store for the summaries computed by compositional analyses.
+//!
+//! Summaries are keyed by a digest of the function's bytecode and spec, combined with the
+//! digests of all the functions it calls. A stored summary is therefore only reused if neither
+//! the function nor anything it (transitively) depends on has changed since the summary was
+//! computed. This allows re-verifying a package after editing one module without re-analyzing
+//! its unchanged dependencies.
+
+use crate::{
+    function_target::FunctionTarget,
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant},
+    options::ProverOptions,
+    stackless_bytecode::{Bytecode, Operation},
+};
+use log::{debug, warn};
+use move_model::{
+    ast::{ModuleName, Spec},
+    model::{GlobalEnv, ModuleId, QualifiedInstId, StructId},
+    ty::{PrimitiveType, Type},
+};
+use num::{BigUint, Num};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fs, path::PathBuf};
+
+/// Version of the on-disk format. Bump this whenever the digest computation or the encoding of
+/// any persisted summary changes, so that stale entries are never picked up.
+const STORE_FORMAT_VERSION: u32 = 1;
+
+/// The digest of a function variant, attached as an annotation so that callers can incorporate
+/// it into their own digest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionDigest(pub String);
+
+/// A summary which can be persisted in a [`SummaryStore`].
+pub trait PersistentSummary: Sized {
+    /// The on-disk representation of the summary.
+    type Persisted: Serialize + DeserializeOwned;
+
+    /// A stable name of the analysis which produces the summary.
+    const KIND: &'static str;
+
+    /// Converts the summary into its on-disk representation. Returns None if the summary
+    /// cannot be persisted.
+    fn persist(&self, env: &GlobalEnv) -> Option<Self::Persisted>;
+
+    /// Restores the summary from its on-disk representation. Returns None if it refers to
+    /// entities which do not exist in `env`.
+    fn restore(env: &GlobalEnv, persisted: Self::Persisted) -> Option<Self>;
+}
+
+/// A directory of persisted summaries, keyed by function digest.
+pub struct SummaryStore {
+    dir: PathBuf,
+}
+
+impl SummaryStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the summary store configured in the prover options, if any.
+    pub fn from_options(env: &GlobalEnv) -> Option<Self> {
+        ProverOptions::get(env)
+            .summary_cache_dir
+            .as_ref()
+            .map(SummaryStore::new)
+    }
+
+    fn path_for<S: PersistentSummary>(&self, digest: &FunctionDigest) -> PathBuf {
+        self.dir
+            .join(format!("v{}", STORE_FORMAT_VERSION))
+            .join(S::KIND)
+            .join(format!("{}.json", digest.0))
+    }
+
+    /// Loads the summary stored for `digest`, if there is a usable one.
+    pub fn load<S: PersistentSummary>(
+        &self,
+        env: &GlobalEnv,
+        digest: &FunctionDigest,
+    ) -> Option<S> {
+        let path = self.path_for::<S>(digest);
+        let content = fs::read_to_string(&path).ok()?;
+        let persisted = match serde_json::from_str(&content) {
+            Ok(persisted) => persisted,
+            Err(err) => {
+                warn!("ignoring corrupted summary `{}`: {}", path.display(), err);
+                return None;
+            }
+        };
+        let summary = S::restore(env, persisted);
+        if summary.is_some() {
+            debug!("reusing {} summary `{}`", S::KIND, path.display());
+        }
+        summary
+    }
+
+    /// Stores the summary for `digest`. Failures are reported as warnings only, since the store
+    /// is merely an optimization.
+    pub fn save<S: PersistentSummary>(
+        &self,
+        env: &GlobalEnv,
+        digest: &FunctionDigest,
+        summary: &S,
+    ) {
+        let persisted = match summary.persist(env) {
+            Some(persisted) => persisted,
+            None => return,
+        };
+        let path = self.path_for::<S>(digest);
+        let result = serde_json::to_string(&persisted)
+            .map_err(|err| err.to_string())
+            .and_then(|content| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+                }
+                fs::write(&path, content).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("failed to store summary `{}`: {}", path.display(), err);
+        }
+    }
+}
+
+/// Computes the digest of the given function variant from its bytecode, its spec, and the
+/// digests of its callees. Returns None if the digest of a callee is not yet known, which is
+/// the case for recursive functions.
+pub fn compute_function_digest(
+    targets: &FunctionTargetsHolder,
+    target: &FunctionTarget<'_>,
+) -> Option<FunctionDigest> {
+    let env = target.global_env();
+    let mut hasher = Sha256::new();
+    let mut add = |s: String| {
+        hasher.update(s.as_bytes());
+        hasher.update(b"\n");
+    };
+
+    add(format!(
+        "{} [{}]",
+        target.func_env.get_full_name_str(),
+        target.data.variant
+    ));
+    let tctx = target.func_env.get_type_display_ctxt();
+    for idx in 0..target.get_local_count() {
+        add(format!(
+            "local {}: {}",
+            idx,
+            target.get_local_type(idx).display(&tctx)
+        ));
+    }
+    let label_offsets = Bytecode::label_offsets(target.get_bytecode());
+    for code in target.get_bytecode() {
+        add(code.display(target, &label_offsets).to_string());
+        if let Bytecode::Call(_, _, oper, _, _) = code {
+            match oper {
+                Operation::Function(mid, fid, _)
+                | Operation::OpaqueCallBegin(mid, fid, _)
+                | Operation::OpaqueCallEnd(mid, fid, _) => {
+                    let callee_id = mid.qualified(*fid);
+                    let callee_env = env.get_function(callee_id);
+                    if callee_env.is_native_or_intrinsic() {
+                        add(format!("native {}", callee_env.get_full_name_str()));
+                    } else {
+                        let callee_digest = targets
+                            .get_data(&callee_id, &FunctionVariant::Baseline)?
+                            .annotations
+                            .get::<FunctionDigest>()?;
+                        add(format!("callee {}", callee_digest.0));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    add_spec_to_digest(env, target.func_env.get_spec(), &mut add);
+    Some(FunctionDigest(format!("{:x}", hasher.finalize())))
+}
+
+fn add_spec_to_digest(env: &GlobalEnv, spec: &Spec, add: &mut impl FnMut(String)) {
+    for cond in &spec.conditions {
+        add(cond.kind.to_string());
+        for exp in cond.all_exps() {
+            add(exp.display(env).to_string());
+            // The memory used by an expression includes the memory used by the spec functions
+            // it calls, whose definitions are not part of the displayed expression.
+            for (mem, _) in exp.used_memory(env) {
+                add(env.display(&mem).to_string());
+            }
+        }
+    }
+    for (offset, spec) in &spec.on_impl {
+        add(format!("on_impl {}", offset));
+        add_spec_to_digest(env, spec, add);
+    }
+}
+
+// =================================================================================================
+// Persisted memory references
+
+/// A type, referring to structs by name rather than by the ids of the current session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PersistedType {
+    Primitive(String),
+    Tuple(Vec<PersistedType>),
+    Vector(Box<PersistedType>),
+    Struct(PersistedStruct, Vec<PersistedType>),
+    TypeParameter(u16),
+}
+
+/// A struct, identified by module address, module name, and struct name.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PersistedStruct {
+    pub module_address: String,
+    pub module_name: String,
+    pub struct_name: String,
+}
+
+/// A memory location, i.e. a struct together with its instantiation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PersistedMemory {
+    pub struct_id: PersistedStruct,
+    pub inst: Vec<PersistedType>,
+}
+
+const PRIMITIVE_TYPES: &[(PrimitiveType, &str)] = &[
+    (PrimitiveType::Bool, "bool"),
+    (PrimitiveType::U8, "u8"),
+    (PrimitiveType::U64, "u64"),
+    (PrimitiveType::U128, "u128"),
+    (PrimitiveType::Address, "address"),
+    (PrimitiveType::Signer, "signer"),
+    (PrimitiveType::Num, "num"),
+    (PrimitiveType::Range, "range"),
+    (PrimitiveType::EventStore, "event_store"),
+];
+
+fn persist_struct(env: &GlobalEnv, mid: ModuleId, sid: StructId) -> PersistedStruct {
+    let struct_env = env.get_module(mid).into_struct(sid);
+    let module_name = struct_env.module_env.get_name();
+    PersistedStruct {
+        module_address: module_name.addr().to_str_radix(16),
+        module_name: module_name.name().display(env.symbol_pool()).to_string(),
+        struct_name: struct_env.get_name().display(env.symbol_pool()).to_string(),
+    }
+}
+
+fn restore_struct(env: &GlobalEnv, persisted: &PersistedStruct) -> Option<(ModuleId, StructId)> {
+    let addr = BigUint::from_str_radix(&persisted.module_address, 16).ok()?;
+    let pool = env.symbol_pool();
+    let module_env = env.find_module(&ModuleName::new(addr, pool.make(&persisted.module_name)))?;
+    let struct_env = module_env.find_struct(pool.make(&persisted.struct_name))?;
+    Some((module_env.get_id(), struct_env.get_id()))
+}
+
+pub fn persist_type(env: &GlobalEnv, ty: &Type) -> Option<PersistedType> {
+    Some(match ty {
+        Type::Primitive(prim) => PersistedType::Primitive(
+            PRIMITIVE_TYPES
+                .iter()
+                .find(|(p, _)| p == prim)
+                .map(|(_, name)| name.to_string())?,
+        ),
+        Type::Tuple(tys) => PersistedType::Tuple(persist_types(env, tys)?),
+        Type::Vector(elem) => PersistedType::Vector(Box::new(persist_type(env, elem)?)),
+        Type::Struct(mid, sid, inst) => {
+            PersistedType::Struct(persist_struct(env, *mid, *sid), persist_types(env, inst)?)
+        }
+        Type::TypeParameter(idx) => PersistedType::TypeParameter(*idx),
+        _ => return None,
+    })
+}
+
+fn persist_types(env: &GlobalEnv, tys: &[Type]) -> Option<Vec<PersistedType>> {
+    tys.iter().map(|ty| persist_type(env, ty)).collect()
+}
+
+pub fn restore_type(env: &GlobalEnv, persisted: &PersistedType) -> Option<Type> {
+    Some(match persisted {
+        PersistedType::Primitive(name) => Type::Primitive(
+            PRIMITIVE_TYPES
+                .iter()
+                .find(|(_, n)| n == name)
+                .map(|(p, _)| *p)?,
+        ),
+        PersistedType::Tuple(tys) => Type::Tuple(restore_types(env, tys)?),
+        PersistedType::Vector(elem) => Type::Vector(Box::new(restore_type(env, elem)?)),
+        PersistedType::Struct(struct_id, inst) => {
+            let (mid, sid) = restore_struct(env, struct_id)?;
+            Type::Struct(mid, sid, restore_types(env, inst)?)
+        }
+        PersistedType::TypeParameter(idx) => Type::TypeParameter(*idx),
+    })
+}
+
+fn restore_types(env: &GlobalEnv, tys: &[PersistedType]) -> Option<Vec<Type>> {
+    tys.iter().map(|ty| restore_type(env, ty)).collect()
+}
+
+pub fn persist_memory(env: &GlobalEnv, mem: &QualifiedInstId<StructId>) -> Option<PersistedMemory> {
+    Some(PersistedMemory {
+        struct_id: persist_struct(env, mem.module_id, mem.id),
+        inst: persist_types(env, &mem.inst)?,
+    })
+}
+
+pub fn restore_memory(
+    env: &GlobalEnv,
+    persisted: &PersistedMemory,
+) -> Option<QualifiedInstId<StructId>> {
+    let (mid, sid) = restore_struct(env, &persisted.struct_id)?;
+    Some(mid.qualified_inst(sid, restore_types(env, &persisted.inst)?))
+}
@@ -0,0 +1,179 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Line-based unified diffs, used to show how pipeline stages change the bytecode.
+//!
+//! The edit script is computed with Myers' algorithm, which finds a shortest sequence of line
+//! insertions and deletions transforming one text into the other.
+
+use std::cmp::max;
+
+/// An edit of a line, referring to line indices in the old and new text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Same(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Computes a unified diff between the old and the new text, with the given number of context
+/// lines around each change. The result consists of hunks only, without file headers, and is
+/// empty if the texts have the same lines.
+pub fn unified_diff(old: &str, new: &str, context: usize) -> String {
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+    let edits = compute_edits(&old_lines, &new_lines);
+
+    // Group the changes, with their context, into hunks of edit indices.
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for (idx, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Same(..)) {
+            continue;
+        }
+        let start = idx.saturating_sub(context);
+        let end = (idx + context + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = max(last.1, end),
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut result = String::new();
+    for (start, end) in hunks {
+        let (old_pos, new_pos) = positions_before(&edits[..start]);
+        let hunk = &edits[start..end];
+        let old_len = hunk
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Insert(_)))
+            .count();
+        let new_len = hunk
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Delete(_)))
+            .count();
+        result.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            format_range(old_pos, old_len),
+            format_range(new_pos, new_len)
+        ));
+        for edit in hunk {
+            let (marker, line) = match edit {
+                Edit::Same(i, _) => (' ', old_lines[*i]),
+                Edit::Delete(i) => ('-', old_lines[*i]),
+                Edit::Insert(j) => ('+', new_lines[*j]),
+            };
+            result.push(marker);
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+    result
+}
+
+/// Returns the number of old and new lines consumed by the given edits.
+fn positions_before(edits: &[Edit]) -> (usize, usize) {
+    edits.iter().fold((0, 0), |(old, new), edit| match edit {
+        Edit::Same(..) => (old + 1, new + 1),
+        Edit::Delete(_) => (old + 1, new),
+        Edit::Insert(_) => (old, new + 1),
+    })
+}
+
+/// Formats a hunk range. Lines are numbered from 1; an empty range refers to the line before
+/// it.
+fn format_range(pos: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{},0", pos)
+    } else if len == 1 {
+        format!("{}", pos + 1)
+    } else {
+        format!("{},{}", pos + 1, len)
+    }
+}
+
+/// Computes a shortest edit script using Myers' algorithm.
+fn compute_edits(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let offset = n + m;
+    let index = |k: isize| (k + offset) as usize;
+    let takes_insert =
+        |v: &[isize], k: isize, d: isize| k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]);
+
+    // For each number of edits d, the furthest reaching x per diagonal k = x - y, as it was
+    // before the iteration for d.
+    let mut v = vec![0isize; 2 * offset as usize + 2];
+    let mut trace = vec![];
+    'search: for d in 0..=offset {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if takes_insert(&v, k, d) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk back from the end to the start, following the recorded furthest reaching paths.
+    let mut edits = vec![];
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if takes_insert(v, k, d) { k + 1 } else { k - 1 };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Same(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(Edit::Insert(prev_y as usize));
+            } else {
+                edits.push(Edit::Delete(prev_x as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    edits.reverse();
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", 3), "");
+        assert_eq!(unified_diff("", "", 3), "");
+    }
+
+    #[test]
+    fn test_change() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n";
+        let new = "1\n2\n3\nfour\n5\n6\n7\n8\n9\n";
+        assert_eq!(
+            unified_diff(old, new, 1),
+            "@@ -3,3 +3,3 @@\n 3\n-4\n+four\n 5\n@@ -8 +8,2 @@\n 8\n+9\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_sides() {
+        assert_eq!(unified_diff("", "a\nb\n", 3), "@@ -0,0 +1,2 @@\n+a\n+b\n");
+        assert_eq!(unified_diff("a\n", "", 3), "@@ -1 +0,0 @@\n-a\n");
+    }
+}
@@ -7,7 +7,10 @@ use crate::{
     dataflow_domains::{AbstractDomain, JoinResult, SetDomain},
     function_target::{FunctionData, FunctionTarget},
     function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
-    stackless_bytecode::{BorrowNode, Bytecode, Operation, PropKind},
+    loop_analysis,
+    stackless_bytecode::{BorrowNode, Bytecode, Label, Operation, PropKind},
+    stackless_control_flow_graph::StacklessControlFlowGraph,
+    summary_store::{self, FunctionDigest, PersistedMemory, PersistentSummary, SummaryStore},
     verification_analysis,
 };
 
@@ -18,9 +21,14 @@ use move_model::{
 };
 
 use itertools::Itertools;
-use move_model::ast::{ConditionKind, Spec};
+use move_model::ast::{ConditionKind, ExpData, Spec, TempIndex};
 use paste::paste;
-use std::{collections::BTreeSet, fmt, fmt::Formatter};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fmt::Formatter,
+};
 
 pub fn get_memory_usage<'env>(target: &FunctionTarget<'env>) -> &'env UsageState {
     target
@@ -51,6 +59,58 @@ pub struct UsageState {
     pub assumed: MemoryUsage,
     /// The memory mentioned by the assert expressions in this function.
     pub asserted: MemoryUsage,
+    /// The memory declared as modify targets by the `modifies` conditions of this function's
+    /// spec. This is only about the function's own spec, so it has no transitive part.
+    pub declared_modified: MemoryUsage,
+}
+
+/// The JSON representation of the memory a script may touch.
+#[derive(Debug, Serialize)]
+pub struct ScriptUsageJson {
+    pub script: String,
+    /// `transaction script` for the main function of a script, `script function` otherwise.
+    pub kind: String,
+    /// The resources the script may access, directly or through the functions it calls.
+    pub accessed: Vec<String>,
+    /// The resources the script may modify, directly or through the functions it calls.
+    pub modified: Vec<String>,
+}
+
+/// Returns the resources which the transaction scripts and script functions in the environment
+/// may access and modify. Memory backing spec vars is not included.
+pub fn get_script_usage(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> Vec<ScriptUsageJson> {
+    let display_resources = |usage: &MemoryUsage| {
+        usage
+            .all
+            .iter()
+            .filter(|mem| !env.get_struct(mem.to_qualified_id()).is_ghost_memory())
+            .map(|mem| env.display(mem).to_string())
+            .collect::<Vec<_>>()
+    };
+    env.get_modules()
+        .flat_map(|module_env| module_env.into_functions())
+        .filter(|fun_env| fun_env.is_script())
+        .map(|fun_env| {
+            let target = targets.get_target(&fun_env, &FunctionVariant::Baseline);
+            let usage = get_memory_usage(&target);
+            ScriptUsageJson {
+                script: fun_env.get_full_name_str(),
+                kind: if fun_env.module_env.is_script_module() {
+                    "transaction script"
+                } else {
+                    "script function"
+                }
+                .to_string(),
+                accessed: display_resources(&usage.accessed),
+                modified: display_resources(&usage.modified),
+            }
+        })
+        .collect()
+}
+
+/// Returns the result of `get_script_usage` as JSON.
+pub fn script_usage_to_json(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> serde_json::Value {
+    serde_json::to_value(get_script_usage(env, targets)).expect("script usage is serializable")
 }
 
 impl MemoryUsage {
@@ -169,6 +229,20 @@ impl UsageState {
 
     generate_inserter!(asserted, add_direct);
     generate_inserter!(asserted, add_transitive);
+
+    generate_inserter!(declared_modified, add_direct);
+}
+
+impl UsageState {
+    /// Returns the memory which is modified by this function, either directly or via one of its
+    /// callees, but which is not declared as a modify target in the function's spec.
+    pub fn get_undeclared_modified(&self) -> BTreeSet<&QualifiedInstId<StructId>> {
+        self.modified
+            .all
+            .iter()
+            .filter(|mem| !self.declared_modified.all.contains(mem))
+            .collect()
+    }
 }
 
 /// Helpers for the abstract interpretation process
@@ -195,20 +269,124 @@ impl AbstractDomain for UsageState {
             self.modified.join(&other.modified),
             self.assumed.join(&other.assumed),
             self.asserted.join(&other.asserted),
+            self.declared_modified.join(&other.declared_modified),
         ) {
             (
                 JoinResult::Unchanged,
                 JoinResult::Unchanged,
                 JoinResult::Unchanged,
                 JoinResult::Unchanged,
+                JoinResult::Unchanged,
             ) => JoinResult::Unchanged,
             _ => JoinResult::Changed,
         }
     }
 }
 
+// =================================================================================================
+// Persistence
+
+/// The on-disk representation of a `MemoryUsage`.
+#[derive(Serialize, Deserialize)]
+pub struct PersistedMemoryUsage {
+    direct: Vec<PersistedMemory>,
+    transitive: Vec<PersistedMemory>,
+}
+
+/// The on-disk representation of a `UsageState`.
+#[derive(Serialize, Deserialize)]
+pub struct PersistedUsageState {
+    accessed: PersistedMemoryUsage,
+    modified: PersistedMemoryUsage,
+    assumed: PersistedMemoryUsage,
+    asserted: PersistedMemoryUsage,
+    declared_modified: PersistedMemoryUsage,
+}
+
+impl MemoryUsage {
+    fn persist(&self, env: &GlobalEnv) -> Option<PersistedMemoryUsage> {
+        let persist_set = |set: &SetDomain<QualifiedInstId<StructId>>| {
+            set.iter()
+                .map(|mem| summary_store::persist_memory(env, mem))
+                .collect::<Option<Vec<_>>>()
+        };
+        Some(PersistedMemoryUsage {
+            direct: persist_set(&self.direct)?,
+            transitive: persist_set(&self.transitive)?,
+        })
+    }
+
+    fn restore(env: &GlobalEnv, persisted: &PersistedMemoryUsage) -> Option<Self> {
+        let mut usage = MemoryUsage::default();
+        for mem in &persisted.direct {
+            usage.add_direct(summary_store::restore_memory(env, mem)?);
+        }
+        for mem in &persisted.transitive {
+            usage.add_transitive(summary_store::restore_memory(env, mem)?);
+        }
+        Some(usage)
+    }
+}
+
+impl PersistentSummary for UsageState {
+    type Persisted = PersistedUsageState;
+    const KIND: &'static str = "usage_analysis";
+
+    fn persist(&self, env: &GlobalEnv) -> Option<PersistedUsageState> {
+        Some(PersistedUsageState {
+            accessed: self.accessed.persist(env)?,
+            modified: self.modified.persist(env)?,
+            assumed: self.assumed.persist(env)?,
+            asserted: self.asserted.persist(env)?,
+            declared_modified: self.declared_modified.persist(env)?,
+        })
+    }
+
+    fn restore(env: &GlobalEnv, persisted: PersistedUsageState) -> Option<Self> {
+        Some(UsageState {
+            accessed: MemoryUsage::restore(env, &persisted.accessed)?,
+            modified: MemoryUsage::restore(env, &persisted.modified)?,
+            assumed: MemoryUsage::restore(env, &persisted.assumed)?,
+            asserted: MemoryUsage::restore(env, &persisted.asserted)?,
+            declared_modified: MemoryUsage::restore(env, &persisted.declared_modified)?,
+        })
+    }
+}
+
+/// The memory usage of each loop in a function, keyed by the label of the loop header. Loops
+/// which share a header are summarized together, and the summary of a loop includes the usage
+/// of all loops nested in it.
+#[derive(Default, Clone)]
+pub struct LoopUsageAnnotation {
+    pub loops: BTreeMap<Label, UsageState>,
+}
+
+impl LoopUsageAnnotation {
+    /// Get the memory usage of the loop with the given header label.
+    pub fn get_loop_usage(&self, header: &Label) -> Option<&UsageState> {
+        self.loops.get(header)
+    }
+}
+
+/// The memory accessed by each individual instruction of a function, keyed by code offset.
+/// Memory touched by a callee is attributed to the offset of the call. The offsets refer to the
+/// code the usage analysis was run on.
+#[derive(Default, Clone)]
+pub struct OffsetUsageAnnotation {
+    pub accessed_at_offset: BTreeMap<CodeOffset, MemoryUsage>,
+}
+
+impl OffsetUsageAnnotation {
+    /// Returns the memory accessed by the instruction at `offset`, if it accesses any.
+    pub fn get_accessed_at(&self, offset: CodeOffset) -> Option<&MemoryUsage> {
+        self.accessed_at_offset.get(&offset)
+    }
+}
+
 struct MemoryUsageAnalysis<'a> {
     cache: SummaryCache<'a>,
+    /// The global memory each reference of the analyzed function may borrow from.
+    ref_roots: BTreeMap<TempIndex, BTreeSet<QualifiedInstId<StructId>>>,
 }
 
 impl<'a> DataflowAnalysis for MemoryUsageAnalysis<'a> {}
@@ -230,7 +408,7 @@ impl<'a> TransferFunctions for MemoryUsageAnalysis<'a> {
 
         match code {
             // memory accesses in operations
-            Call(_, _, oper, _, _) => match oper {
+            Call(_, _, oper, srcs, _) => match oper {
                 Function(mid, fid, inst)
                 | OpaqueCallBegin(mid, fid, inst)
                 | OpaqueCallEnd(mid, fid, inst) => {
@@ -259,6 +437,8 @@ impl<'a> TransferFunctions for MemoryUsageAnalysis<'a> {
                             state.subsume_callee_as_transitive(summary, inst);
                         }
                     }
+                    // references handed over to the callee may be written through
+                    self.add_modified_through_refs(state, srcs);
                 }
                 MoveTo(mid, sid, inst)
                 | MoveFrom(mid, sid, inst)
@@ -273,6 +453,9 @@ impl<'a> TransferFunctions for MemoryUsageAnalysis<'a> {
                     let mem = mid.qualified_inst(*sid, inst.to_owned());
                     state.add_direct_accessed(mem);
                 }
+                WriteRef => {
+                    self.add_modified_through_refs(state, &srcs[0..1]);
+                }
                 _ => {}
             },
             // memory accesses in expressions
@@ -297,12 +480,148 @@ impl<'a> TransferFunctions for MemoryUsageAnalysis<'a> {
 }
 
 impl<'a> MemoryUsageAnalysis<'a> {
+    /// Marks the global memory which the given references may borrow from as modified. This
+    /// attributes writes through `&mut` references, including those escaping into callees, to
+    /// the instruction writing, rather than only to the one borrowing the global memory.
+    fn add_modified_through_refs(&self, state: &mut UsageState, refs: &[TempIndex]) {
+        for idx in refs {
+            if let Some(roots) = self.ref_roots.get(idx) {
+                state.add_direct_modified_iter(roots.iter().cloned());
+            }
+        }
+    }
+
+    /// Computes the global memory each reference of the function may borrow from. This is
+    /// derived from the code, as the borrow graph is consumed by memory instrumentation before
+    /// this analysis runs. References are followed through assignments, borrows of fields and
+    /// of global memory, the write-backs inserted by memory instrumentation, and calls returning
+    /// mutable references, which are assumed to borrow from all mutable references passed in.
+    /// The result is flow-insensitive, and thus an over-approximation.
+    fn compute_ref_roots(
+        func_target: &FunctionTarget,
+    ) -> BTreeMap<TempIndex, BTreeSet<QualifiedInstId<StructId>>> {
+        use Bytecode::*;
+        use Operation::*;
+        let is_mut_ref = |idx: &TempIndex| func_target.get_local_type(*idx).is_mutable_reference();
+        let mut roots: BTreeMap<TempIndex, BTreeSet<QualifiedInstId<StructId>>> = BTreeMap::new();
+        let mut parents: BTreeMap<TempIndex, BTreeSet<TempIndex>> = BTreeMap::new();
+        for code in func_target.get_bytecode() {
+            match code {
+                Assign(_, dest, src, _) if is_mut_ref(src) => {
+                    parents.entry(*dest).or_default().insert(*src);
+                }
+                Call(_, dests, BorrowGlobal(mid, sid, inst), _, _) => {
+                    roots
+                        .entry(dests[0])
+                        .or_default()
+                        .insert(mid.qualified_inst(*sid, inst.to_owned()));
+                }
+                Call(_, dests, BorrowField(..), srcs, _) => {
+                    parents.entry(dests[0]).or_default().insert(srcs[0]);
+                }
+                Call(_, _, WriteBack(BorrowNode::GlobalRoot(mem), _), srcs, _) => {
+                    roots.entry(srcs[0]).or_default().insert(mem.clone());
+                }
+                Call(_, _, WriteBack(BorrowNode::Reference(parent), _), srcs, _) => {
+                    parents.entry(srcs[0]).or_default().insert(*parent);
+                }
+                Call(_, dests, Function(..), srcs, _) => {
+                    for dest in dests.iter().filter(|idx| is_mut_ref(idx)) {
+                        parents
+                            .entry(*dest)
+                            .or_default()
+                            .extend(srcs.iter().filter(|idx| is_mut_ref(idx)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (child, child_parents) in &parents {
+                let inherited = child_parents
+                    .iter()
+                    .filter_map(|parent| roots.get(parent))
+                    .flatten()
+                    .cloned()
+                    .collect::<BTreeSet<_>>();
+                let child_roots = roots.entry(*child).or_default();
+                let size = child_roots.len();
+                child_roots.extend(inherited);
+                changed |= child_roots.len() != size;
+            }
+        }
+        roots
+    }
+
+    /// Compute the memory accessed by each instruction in isolation. The transfer function
+    /// does not depend on the incoming state, so each instruction is simply executed on an
+    /// empty state.
+    fn compute_offset_usage(&self, func_target: &FunctionTarget) -> OffsetUsageAnnotation {
+        let mut accessed_at_offset = BTreeMap::new();
+        for (offset, code) in func_target.get_bytecode().iter().enumerate() {
+            let offset = offset as CodeOffset;
+            let mut local = UsageState::default();
+            self.execute(&mut local, code, offset);
+            if !local.accessed.all.is_empty() {
+                accessed_at_offset.insert(offset, local.accessed);
+            }
+        }
+        OffsetUsageAnnotation { accessed_at_offset }
+    }
+
+    /// Summarize the memory usage of each loop by joining the usage of all instructions in the
+    /// loop body. Unlike the fixpoint over the whole function, this separates what happens inside
+    /// a loop from what happens before or after it.
+    fn compute_loop_usage(&self, func_target: &FunctionTarget) -> LoopUsageAnnotation {
+        let code = func_target.get_bytecode();
+        let cfg = StacklessControlFlowGraph::new_forward(code);
+        let fat_loops = match loop_analysis::find_fat_loops(&cfg) {
+            Some(fat_loops) => fat_loops,
+            // irreducible control flow; no loop summaries
+            None => return LoopUsageAnnotation::default(),
+        };
+        let mut loops = BTreeMap::new();
+        for (header, sub_loops) in fat_loops {
+            let label = loop_analysis::get_loop_header_label(code, &cfg, header);
+            let body: BTreeSet<_> = sub_loops
+                .iter()
+                .flat_map(|l| l.loop_body.iter().copied())
+                .collect();
+            let mut state = UsageState::default();
+            for block_id in body {
+                for offset in cfg.instr_indexes(block_id).into_iter().flatten() {
+                    self.execute(&mut state, &code[offset as usize], offset);
+                }
+            }
+            loops.insert(label, state);
+        }
+        LoopUsageAnnotation { loops }
+    }
+
     /// Compute usage information for the given spec. This spec is injected in later
     /// phases into the code, but we need to account for it's memory usage already here
     /// as spec injection itself depends on this information.
     fn compute_spec_usage(&self, spec: &Spec, state: &mut UsageState) {
         use ConditionKind::*;
+        let env = self.cache.global_env();
         for cond in &spec.conditions {
+            if matches!(cond.kind, Modifies) {
+                // A modifies condition has the form `global<R>(addr)`: the memory `R` is a
+                // declared write, while memory used in the address expression is only read.
+                let rty = &env.get_node_instantiation(cond.exp.node_id())[0];
+                let (mid, sid, inst) = rty.require_struct();
+                state.add_direct_declared_modified(mid.qualified_inst(sid, inst.to_owned()));
+                if let ExpData::Call(_, _, args) = cond.exp.as_ref() {
+                    for arg in args {
+                        state.add_direct_assumed_iter(
+                            arg.used_memory(env).into_iter().map(|(usage, _)| usage),
+                        );
+                    }
+                }
+                continue;
+            }
             let mut used_memory = cond.exp.used_memory(self.cache.global_env());
             for exp in &cond.additional_exps {
                 used_memory.extend(exp.used_memory(self.cache.global_env()));
@@ -342,12 +661,39 @@ impl FunctionTargetProcessor for UsageProcessor {
         func_env: &FunctionEnv<'_>,
         mut data: FunctionData,
     ) -> FunctionData {
+        let env = func_env.module_env.env;
         let func_target = FunctionTarget::new(func_env, &data);
-        let cache = SummaryCache::new(targets, func_env.module_env.env);
-        let analysis = MemoryUsageAnalysis { cache };
-        let mut summary = analysis.summarize(&func_target, UsageState::default());
-        analysis.compute_spec_usage(func_env.get_spec(), &mut summary);
+        let store = SummaryStore::from_options(env);
+        let digest = store
+            .as_ref()
+            .and_then(|_| summary_store::compute_function_digest(targets, &func_target));
+        let persisted = match (&store, &digest) {
+            (Some(store), Some(digest)) => store.load::<UsageState>(env, digest),
+            _ => None,
+        };
+        let analysis = MemoryUsageAnalysis {
+            cache: SummaryCache::new(targets, env),
+            ref_roots: MemoryUsageAnalysis::compute_ref_roots(&func_target),
+        };
+        let summary = match persisted {
+            Some(summary) => summary,
+            None => {
+                let mut summary = analysis.summarize(&func_target, UsageState::default());
+                analysis.compute_spec_usage(func_env.get_spec(), &mut summary);
+                if let (Some(store), Some(digest)) = (&store, &digest) {
+                    store.save(env, digest, &summary);
+                }
+                summary
+            }
+        };
+        let offset_usage = analysis.compute_offset_usage(&func_target);
+        let loop_usage = analysis.compute_loop_usage(&func_target);
         data.annotations.set(summary);
+        data.annotations.set_code_dependent(offset_usage);
+        data.annotations.set_code_dependent(loop_usage);
+        if let Some(digest) = digest {
+            data.annotations.set::<FunctionDigest>(digest);
+        }
         data
     }
 
@@ -363,10 +709,13 @@ impl FunctionTargetProcessor for UsageProcessor {
     ) -> fmt::Result {
         writeln!(f, "\n\n********* Result of usage analysis *********\n\n")?;
         for module in env.get_modules() {
-            if !module.is_target() {
-                continue;
-            }
+            let is_target = module.is_target();
             for fun in module.get_functions() {
+                // Script functions of dependencies are included, so that what a script can touch
+                // can be reviewed without making the framework a target.
+                if !is_target && !fun.is_script() {
+                    continue;
+                }
                 for (_, ref target) in targets.get_targets(&fun) {
                     let usage = get_memory_usage(target);
                     writeln!(
@@ -401,6 +750,48 @@ impl FunctionTargetProcessor for UsageProcessor {
                     print_usage(&usage.modified, "modified")?;
                     print_usage(&usage.assumed, "assumed")?;
                     print_usage(&usage.asserted, "asserted")?;
+                    writeln!(
+                        f,
+                        "  declared modified = {{{}}}",
+                        usage
+                            .declared_modified
+                            .all
+                            .iter()
+                            .map(|qid| env.display(qid).to_string())
+                            .join(", ")
+                    )?;
+                    if let Some(loop_usage) = target.get_annotations().get::<LoopUsageAnnotation>()
+                    {
+                        for (label, usage) in &loop_usage.loops {
+                            writeln!(
+                                f,
+                                "  loop L{} modified = {{{}}}",
+                                label.as_usize(),
+                                usage
+                                    .modified
+                                    .all
+                                    .iter()
+                                    .map(|qid| env.display(qid).to_string())
+                                    .join(", ")
+                            )?;
+                        }
+                    }
+                    let offset_usage = target
+                        .get_annotations()
+                        .get::<OffsetUsageAnnotation>()
+                        .expect("offset usage");
+                    for (offset, usage) in &offset_usage.accessed_at_offset {
+                        writeln!(
+                            f,
+                            "  accessed at {} = {{{}}}",
+                            offset,
+                            usage
+                                .all
+                                .iter()
+                                .map(|qid| env.display(qid).to_string())
+                                .join(", ")
+                        )?;
+                    }
 
                     writeln!(f, "}}")?;
                 }
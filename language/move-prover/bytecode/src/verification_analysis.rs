@@ -9,21 +9,25 @@
 use crate::{
     function_target::{FunctionData, FunctionTarget},
     function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
+    invariant_relevance::{self, InvariantRelevanceMap},
     options::ProverOptions,
-    usage_analysis,
+    usage_analysis::UsageState,
 };
 
 use move_model::{
-    ast::GlobalInvariant,
-    model::{FunId, FunctionEnv, GlobalEnv, GlobalId, QualifiedId, VerificationScope},
+    ast::ConditionKind,
+    model::{
+        FunId, FunctionEnv, GlobalEnv, GlobalId, QualifiedId, StructEnv, StructId,
+        VerificationScope,
+    },
     pragmas::{
         CONDITION_SUSPENDABLE_PROP, DELEGATE_INVARIANTS_TO_CALLER_PRAGMA,
         DISABLE_INVARIANTS_IN_BODY_PRAGMA, VERIFY_PRAGMA,
     },
-    ty::{TypeUnificationAdapter, Variance},
 };
 
 use itertools::Itertools;
+use serde::Serialize;
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Formatter},
@@ -37,6 +41,10 @@ pub struct VerificationInfo {
     /// Whether the function needs to have an inlined variant since it is called from a verified
     /// function and is not opaque.
     pub inlined: bool,
+    /// Whether the function is not verified but trusted to satisfy its contract, because
+    /// `ProverOptions::assume_unverified` is set. Calls to such functions are treated like calls
+    /// to opaque functions.
+    pub trusted: bool,
 }
 
 /// Get verification information for this function.
@@ -48,7 +56,22 @@ pub fn get_info(target: &FunctionTarget<'_>) -> VerificationInfo {
         .unwrap_or_else(VerificationInfo::default)
 }
 
+/// Returns true if the function is trusted to satisfy its contract without being verified.
+pub fn is_trusted(targets: &FunctionTargetsHolder, fun_env: &FunctionEnv<'_>) -> bool {
+    targets
+        .get_data(&fun_env.get_qualified_id(), &FunctionVariant::Baseline)
+        .and_then(|data| data.annotations.get::<VerificationInfo>())
+        .map_or(false, |info| info.trusted)
+}
+
+/// Returns true if calls to the function are replaced by its contract, which is the case for
+/// opaque and for trusted functions.
+pub fn is_opaque_call(targets: &FunctionTargetsHolder, fun_env: &FunctionEnv<'_>) -> bool {
+    fun_env.is_opaque() || is_trusted(targets, fun_env)
+}
+
 /// A named tuple for holding the information on how an invariant is relevant to a function.
+#[derive(Clone, Default)]
 pub struct InvariantRelevance {
     /// Global invariants covering memories that are accessed in a function
     pub accessed: BTreeSet<GlobalId>,
@@ -70,6 +93,138 @@ pub struct InvariantAnalysisData {
     pub fun_to_inv_map: BTreeMap<QualifiedId<FunId>, InvariantRelevance>,
 }
 
+/// The memory targeted by `ProverOptions::verify_memory`, resolved to struct ids, together with
+/// the global invariants which refer to it.
+pub struct MemoryVerificationTargets {
+    pub memory: BTreeSet<QualifiedId<StructId>>,
+    pub invariants: BTreeSet<GlobalId>,
+}
+
+/// The ledger of unverified contracts the verification depends on. A verified function depends
+/// on the contract of each callee whose calls are replaced by its contract (see
+/// `is_opaque_call`), whether called directly or via inlined callees. Only callees which are not
+/// verified themselves are recorded.
+#[derive(Clone, Default)]
+pub struct AssumptionsLedger {
+    /// For each verified function, the unverified functions whose contracts it assumes.
+    pub assumed_by: BTreeMap<QualifiedId<FunId>, BTreeSet<QualifiedId<FunId>>>,
+}
+
+/// The JSON representation of an assumed contract in the ledger.
+#[derive(Debug, Serialize)]
+pub struct AssumedContractJson {
+    pub function: String,
+    /// Why the contract is assumed: `opaque` or `trusted`.
+    pub reason: String,
+    pub conditions: Vec<AssumedConditionJson>,
+    /// The verified functions depending on the contract.
+    pub used_by: Vec<String>,
+}
+
+/// The JSON representation of a condition of an assumed contract.
+#[derive(Debug, Serialize)]
+pub struct AssumedConditionJson {
+    pub kind: String,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Get the assumptions ledger attached to the holder, if it has been computed.
+pub fn get_assumptions_ledger(targets: &FunctionTargetsHolder) -> Option<&AssumptionsLedger> {
+    targets.get_annotations().get::<AssumptionsLedger>()
+}
+
+impl AssumptionsLedger {
+    /// Compute the ledger from the verification info of the functions in the targets holder.
+    pub fn compute(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> Self {
+        let is_verified = |fun_env: &FunctionEnv<'_>| {
+            get_info(&targets.get_target(fun_env, &FunctionVariant::Baseline)).verified
+        };
+        let mut ledger = Self::default();
+        for (fun_id, _) in targets.get_funs_and_variants() {
+            if !is_verified(&env.get_function(fun_id)) {
+                continue;
+            }
+            let mut assumed = BTreeSet::new();
+            let mut visited = BTreeSet::new();
+            visited.insert(fun_id);
+            let mut todo = vec![fun_id];
+            while let Some(caller) = todo.pop() {
+                for callee in env.get_function(caller).get_called_functions() {
+                    let callee_env = env.get_function(callee);
+                    if is_opaque_call(targets, &callee_env) {
+                        if !is_verified(&callee_env) {
+                            assumed.insert(callee);
+                        }
+                    } else if !callee_env.is_native_or_intrinsic() && visited.insert(callee) {
+                        // The callee is inlined, so its callees are called by this function.
+                        todo.push(callee);
+                    }
+                }
+            }
+            if !assumed.is_empty() {
+                ledger.assumed_by.insert(fun_id, assumed);
+            }
+        }
+        ledger
+    }
+
+    /// Returns the ledger as JSON, listing each assumed contract with its conditions and the
+    /// verified functions depending on it.
+    pub fn to_json(&self, env: &GlobalEnv) -> serde_json::Value {
+        let mut used_by: BTreeMap<QualifiedId<FunId>, BTreeSet<String>> = BTreeMap::new();
+        for (fun_id, assumed) in &self.assumed_by {
+            for callee in assumed {
+                used_by
+                    .entry(*callee)
+                    .or_default()
+                    .insert(env.get_function(*fun_id).get_full_name_str());
+            }
+        }
+        let entries = used_by
+            .into_iter()
+            .map(|(callee, users)| {
+                let fun_env = env.get_function(callee);
+                let conditions = fun_env
+                    .get_spec()
+                    .conditions
+                    .iter()
+                    .filter(|cond| {
+                        use ConditionKind::*;
+                        matches!(
+                            cond.kind,
+                            AbortsIf | AbortsWith | Ensures | Modifies | Emits
+                        )
+                    })
+                    .map(|cond| {
+                        let (file, line) = env
+                            .get_file_and_location(&cond.loc)
+                            .map(|(file, l)| (file, l.line.0 as usize + 1))
+                            .unwrap_or_default();
+                        AssumedConditionJson {
+                            kind: cond.kind.to_string(),
+                            file,
+                            line,
+                        }
+                    })
+                    .collect();
+                AssumedContractJson {
+                    function: fun_env.get_full_name_str(),
+                    reason: if fun_env.is_opaque() {
+                        "opaque"
+                    } else {
+                        "trusted"
+                    }
+                    .to_string(),
+                    conditions,
+                    used_by: users.into_iter().collect(),
+                }
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_value(entries).expect("assumptions ledger is serializable")
+    }
+}
+
 // The function target processor
 pub struct VerificationAnalysisProcessor();
 
@@ -93,8 +248,20 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessor {
             return data;
         }
 
-        // Rule 2: verify the function if it is within the target modules
         let env = fun_env.module_env.env;
+
+        // Rule 2: if verification is targeted at memory, verify exactly the functions in the
+        // target modules which modify that memory, or which need to check an invariant over it.
+        if let Some(memory_targets) = env.get_extension::<MemoryVerificationTargets>() {
+            if fun_env.module_env.is_target()
+                && Self::is_relevant_for_memory_targets(fun_env, &data, &memory_targets)
+            {
+                Self::mark_verified(fun_env, &mut data, targets);
+            }
+            return data;
+        }
+
+        // Rule 3: verify the function if it is within the target modules
         let target_modules = env.get_target_modules();
 
         let is_in_target_module = target_modules
@@ -107,7 +274,7 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessor {
             return data;
         }
 
-        // Rule 3: verify the function if a global invariant (including update invariant) that is
+        // Rule 4: verify the function if a global invariant (including update invariant) that is
         // defined in the target modules (a.k.a. a target invariant) need to be checked in the
         // function, i.e., the function directly modifies some memory that are covered by at least
         // one of the target invariants.
@@ -218,11 +385,37 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessor {
                 } else {
                     writeln!(f, "verified")?;
                 }
+            } else if result.trusted {
+                writeln!(f, "trusted")?;
             } else {
                 writeln!(f, "inlined")?;
             }
         }
-        writeln!(f, "]")
+        writeln!(f, "]")?;
+
+        if let Some(ledger) = get_assumptions_ledger(targets) {
+            if !ledger.assumed_by.is_empty() {
+                writeln!(f, "\nassumed contracts: [")?;
+                for (fun_id, assumed) in &ledger.assumed_by {
+                    writeln!(
+                        f,
+                        "  {}: [{}]",
+                        env.get_function(*fun_id).get_full_name_str(),
+                        assumed
+                            .iter()
+                            .map(|callee| env.get_function(*callee).get_full_name_str())
+                            .join(", ")
+                    )?;
+                }
+                writeln!(f, "]")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
+        let ledger = AssumptionsLedger::compute(env, targets);
+        targets.get_annotations_mut().set(ledger);
     }
 
     fn initialize(&self, env: &GlobalEnv, targets: &mut FunctionTargetsHolder) {
@@ -246,7 +439,7 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessor {
                     }
                 }
                 if !target_exists {
-                    env.error(
+                    targets.diagnostics().error(
                         &env.unknown_loc(),
                         &format!(
                             "{} target {} does not exist in target modules",
@@ -259,6 +452,13 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessor {
             _ => {}
         }
 
+        // If we are verifying only functions modifying certain memory, resolve the memory.
+        if !options.verify_memory.is_empty() {
+            if let Some(memory_targets) = Self::resolve_memory_targets(env, targets) {
+                env.set_extension(memory_targets);
+            }
+        }
+
         // Collect information for global invariant instrumentation
 
         // probe how global invariants will be evaluated in the functions
@@ -286,12 +486,14 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessor {
                 if num_suspendable_inv_modified != 0 {
                     if is_invariant_checking_delegated(&fun_env) {
                         let message = "Public or script functions cannot delegate invariants";
-                        env.error(&fun_env.get_loc(), message);
+                        targets.diagnostics().error(&fun_env.get_loc(), message);
                     } else {
                         let message = "Public or script functions cannot be transitively \
                         called by functions disabling or delegating invariants";
                         let trace = Self::compute_non_inv_cause_chain(&fun_env);
-                        env.error_with_notes(&fun_env.get_loc(), message, trace);
+                        targets
+                            .diagnostics()
+                            .error_with_notes(&fun_env.get_loc(), message, trace);
                     };
                 }
             }
@@ -306,7 +508,9 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessor {
                     DISABLE_INVARIANTS_IN_BODY_PRAGMA,
                 );
                 let trace = Self::compute_non_inv_cause_chain(&fun_env);
-                env.error_with_notes(&fun_env.get_loc(), &message, trace);
+                targets
+                    .diagnostics()
+                    .error_with_notes(&fun_env.get_loc(), &message, trace);
             }
         }
 
@@ -339,6 +543,93 @@ impl VerificationAnalysisProcessor {
         }
     }
 
+    /// Check whether the function is relevant for verifying the targeted memory, i.e., whether it
+    /// modifies this memory, or directly modifies memory covered by an invariant over it.
+    fn is_relevant_for_memory_targets(
+        fun_env: &FunctionEnv,
+        data: &FunctionData,
+        memory_targets: &MemoryVerificationTargets,
+    ) -> bool {
+        let modifies_target = data.annotations.get::<UsageState>().map_or(false, |usage| {
+            !usage
+                .modified
+                .get_all_uninst()
+                .is_disjoint(&memory_targets.memory)
+        });
+        if modifies_target {
+            return true;
+        }
+        let env = fun_env.module_env.env;
+        let inv_analysis = env.get_extension::<InvariantAnalysisData>().unwrap();
+        inv_analysis
+            .fun_to_inv_map
+            .get(&fun_env.get_qualified_id())
+            .map_or(false, |relevance| {
+                !relevance
+                    .direct_modified
+                    .is_disjoint(&memory_targets.invariants)
+            })
+    }
+
+    /// Resolve the memory names given in `ProverOptions::verify_memory`, reporting an error for
+    /// each name which does not denote a struct.
+    fn resolve_memory_targets(
+        env: &GlobalEnv,
+        targets: &FunctionTargetsHolder,
+    ) -> Option<MemoryVerificationTargets> {
+        let options = ProverOptions::get(env);
+        let mut memory = BTreeSet::new();
+        for name in &options.verify_memory {
+            let found = env
+                .get_modules()
+                .flat_map(|module_env| module_env.into_structs())
+                .filter(|struct_env| matches_struct_name(struct_env, name))
+                .map(|struct_env| struct_env.get_qualified_id())
+                .collect_vec();
+            if found.is_empty() {
+                targets.diagnostics().error(
+                    &env.unknown_loc(),
+                    &format!("memory target {} does not exist", name),
+                );
+                return None;
+            }
+            memory.extend(found);
+        }
+        let invariants = env
+            .get_modules()
+            .flat_map(|module_env| env.get_global_invariants_by_module(module_env.get_id()))
+            .filter(|inv_id| {
+                env.get_global_invariant(*inv_id).map_or(false, |inv| {
+                    inv.mem_usage
+                        .iter()
+                        .any(|mem| memory.contains(&mem.module_id.qualified(mem.id)))
+                })
+            })
+            .collect();
+        Some(MemoryVerificationTargets { memory, invariants })
+    }
+
+    /// Returns true if the function can be trusted to satisfy its contract instead of being
+    /// inlined into verified callers. This requires the function to not be verified itself, to
+    /// have a contract to assume, and to declare all memory it modifies, as memory not declared
+    /// would not be havoced at the call site.
+    fn is_trustable(fun_env: &FunctionEnv, data: &FunctionData) -> bool {
+        let verified = data
+            .annotations
+            .get::<VerificationInfo>()
+            .map_or(false, |info| info.verified);
+        let has_contract = fun_env
+            .get_spec()
+            .conditions
+            .iter()
+            .any(|cond| matches!(cond.kind, ConditionKind::Ensures | ConditionKind::AbortsIf));
+        let modifies_complete = data
+            .annotations
+            .get::<UsageState>()
+            .map_or(false, |usage| usage.get_undeclared_modified().is_empty());
+        !verified && has_contract && modifies_complete
+    }
+
     /// Mark that this function should be verified, and as a result, mark that all its callees
     /// should be inlined
     fn mark_verified(
@@ -356,17 +647,26 @@ impl VerificationAnalysisProcessor {
     /// Mark that this function should be inlined because it is called by a function that is marked
     /// as verified, and as a result, mark that all its callees should be inlined as well.
     ///
-    /// NOTE: This does not apply to opaque, native, or intrinsic functions.
+    /// NOTE: This does not apply to opaque, native, or intrinsic functions. With
+    /// `ProverOptions::assume_unverified`, functions which are trustable are marked as trusted
+    /// instead.
     fn mark_inlined(fun_env: &FunctionEnv, targets: &mut FunctionTargetsHolder) {
         if fun_env.is_opaque() || fun_env.is_native() || fun_env.is_intrinsic() {
             return;
         }
+        let assume_unverified = ProverOptions::get(fun_env.module_env.env).assume_unverified;
 
         // at this time, we only have the `baseline` variant in the targets
         let variant = FunctionVariant::Baseline;
         let data = targets
             .get_data_mut(&fun_env.get_qualified_id(), &variant)
             .expect("function data defined");
+        if assume_unverified && Self::is_trustable(fun_env, data) {
+            data.annotations
+                .get_or_default_mut::<VerificationInfo>()
+                .trusted = true;
+            return;
+        }
         let info = data.annotations.get_or_default_mut::<VerificationInfo>();
         if !info.inlined {
             info.inlined = true;
@@ -487,82 +787,15 @@ impl VerificationAnalysisProcessor {
 
     /// Produce a `Map[fun_id -> InvariantRelevance]` ignoring the relevant pragmas on both
     /// function-side (i.e., `disable_invariants_in_body` and `delegate_invariants_to_caller`) and
-    /// invariant-side (i.e., `suspendable`)
+    /// invariant-side (i.e., `suspendable`). Reuses the map precomputed by the
+    /// `InvariantRelevanceProcessor` if it has run.
     fn build_function_to_invariants_map(
         env: &GlobalEnv,
         targets: &FunctionTargetsHolder,
     ) -> BTreeMap<QualifiedId<FunId>, InvariantRelevance> {
-        // collect all global invariants
-        let mut global_invariants = vec![];
-        for menv in env.get_modules() {
-            for inv_id in env.get_global_invariants_by_module(menv.get_id()) {
-                global_invariants.push(env.get_global_invariant(inv_id).unwrap());
-            }
-        }
-
-        // go over each function target and check global invariant applicability
-        let mut invariant_relevance = BTreeMap::new();
-        for (fun_id, fun_variant) in targets.get_funs_and_variants() {
-            debug_assert!(matches!(fun_variant, FunctionVariant::Baseline));
-            let fenv = env.get_function(fun_id);
-            let target = targets.get_target(&fenv, &fun_variant);
-            let related =
-                Self::find_relevant_invariants(&target, global_invariants.clone().into_iter());
-            invariant_relevance.insert(fun_id, related);
-        }
-
-        // return the collected relevance map
-        invariant_relevance
-    }
-
-    /// From the iterator of global invariants, find the ones that are relevant to the function as
-    /// well as how/why the invariant is relevant.
-    fn find_relevant_invariants<'a>(
-        target: &FunctionTarget,
-        invariants: impl Iterator<Item = &'a GlobalInvariant>,
-    ) -> InvariantRelevance {
-        let mem_usage = usage_analysis::get_memory_usage(target);
-        let mem_accessed = &mem_usage.accessed.all;
-        let mem_modified = &mem_usage.modified.all;
-        let mem_direct_accessed = &mem_usage.accessed.direct;
-        let mem_direct_modified = &mem_usage.modified.direct;
-
-        let mut inv_accessed = BTreeSet::new();
-        let mut inv_modified = BTreeSet::new();
-        let mut inv_direct_accessed = BTreeSet::new();
-        let mut inv_direct_modified = BTreeSet::new();
-        for inv in invariants {
-            for fun_mem in mem_accessed.iter() {
-                for inv_mem in &inv.mem_usage {
-                    if inv_mem.module_id != fun_mem.module_id || inv_mem.id != fun_mem.id {
-                        continue;
-                    }
-                    let adapter =
-                        TypeUnificationAdapter::new_vec(&fun_mem.inst, &inv_mem.inst, true, true);
-                    let rel = adapter.unify(Variance::Allow, /* shallow_subst */ false);
-                    if rel.is_some() {
-                        inv_accessed.insert(inv.id);
-
-                        // the rest exploits the fact that the `used_memory` set (a read-write set)
-                        // is always a superset of the others.
-                        if mem_modified.contains(fun_mem) {
-                            inv_modified.insert(inv.id);
-                        }
-                        if mem_direct_accessed.contains(fun_mem) {
-                            inv_direct_accessed.insert(inv.id);
-                        }
-                        if mem_direct_modified.contains(fun_mem) {
-                            inv_direct_modified.insert(inv.id);
-                        }
-                    }
-                }
-            }
-        }
-        InvariantRelevance {
-            accessed: inv_accessed,
-            modified: inv_modified,
-            direct_accessed: inv_direct_accessed,
-            direct_modified: inv_direct_modified,
+        match invariant_relevance::get_invariant_relevance(targets) {
+            Some(relevance) => relevance.fun_to_invs.clone(),
+            None => InvariantRelevanceMap::compute(env, targets).fun_to_invs,
         }
     }
 
@@ -688,3 +921,16 @@ pub fn is_invariant_suspendable(env: &GlobalEnv, inv_id: GlobalId) -> bool {
     env.is_property_true(&inv.properties, CONDITION_SUSPENDABLE_PROP)
         .unwrap_or(false)
 }
+
+/// Check whether the struct matches the given name, which is either `Module::Struct` or
+/// `0xADDR::Module::Struct`.
+fn matches_struct_name(struct_env: &StructEnv<'_>, name: &str) -> bool {
+    let env = struct_env.module_env.env;
+    name == struct_env.get_full_name_str()
+        || name
+            == format!(
+                "{}::{}",
+                struct_env.module_env.get_full_name_str(),
+                struct_env.get_name().display(env.symbol_pool())
+            )
+}
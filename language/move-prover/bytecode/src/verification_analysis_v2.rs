@@ -8,6 +8,7 @@ use crate::{
     function_target::{FunctionData, FunctionTarget},
     function_target_pipeline::{FunctionTargetProcessor, FunctionTargetsHolder, FunctionVariant},
     options::ProverOptions,
+    prover_diagnostics::ProverDiagnostics,
     usage_analysis,
 };
 use itertools::Itertools;
@@ -174,12 +175,12 @@ fn check_legal_disabled_invariants(
     disabled_inv_fun_set: &BTreeSet<QualifiedId<FunId>>,
     non_inv_fun_set: &BTreeSet<QualifiedId<FunId>>,
     funs_that_modify_some_inv: &BTreeSet<QualifiedId<FunId>>,
+    diagnostics: &ProverDiagnostics,
 ) {
-    let global_env = fun_env.module_env.env;
     let fun_id = fun_env.get_qualified_id();
     if non_inv_fun_set.contains(&fun_id) && funs_that_modify_some_inv.contains(&fun_id) {
         if disabled_inv_fun_set.contains(&fun_id) {
-            global_env.error(
+            diagnostics.error(
                 &fun_env.get_loc(),
                 "Functions must not have a disable invariant pragma when invariants are \
                  disabled in a transitive caller or there is a \
@@ -187,12 +188,12 @@ fn check_legal_disabled_invariants(
             );
         } else if fun_env.has_unknown_callers() {
             if is_fun_delegating(fun_env) {
-                global_env.error(
+                diagnostics.error(
                     &fun_env.get_loc(),
                     "Public or script functions cannot delegate invariants",
                 )
             } else {
-                global_env.error_with_notes(
+                diagnostics.error_with_notes(
                     &fun_env.get_loc(),
                     "Public or script functions cannot be transitively called by \
                       functions disabling or delegating invariants",
@@ -641,7 +642,7 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessorV2 {
                     }
                 }
                 if !target_exists {
-                    global_env.error(
+                    targets.diagnostics().error(
                         &global_env.unknown_loc(),
                         &format!(
                             "{} target {} does not exist in target modules",
@@ -692,6 +693,7 @@ impl FunctionTargetProcessor for VerificationAnalysisProcessorV2 {
                     &disabled_inv_fun_set,
                     &non_inv_fun_set,
                     &funs_that_modify_some_inv,
+                    targets.diagnostics(),
                 );
             }
         }
@@ -0,0 +1,29 @@
+module 0x2::M {
+    // Verified, assuming the contracts of its unverified callees which are not inlined.
+    public fun a_caller() {
+        b_trusted();
+        c_untrusted();
+        d_opaque();
+    }
+
+    // Trusted, as it has a contract and modifies no undeclared memory.
+    fun b_trusted() {}
+    spec b_trusted {
+        pragma verify = false;
+        aborts_if false;
+    }
+
+    // Inlined, as it has no contract to trust.
+    fun c_untrusted() {}
+    spec c_untrusted {
+        pragma verify = false;
+    }
+
+    // Neither trusted nor inlined, as calls to opaque functions use their contract anyway.
+    fun d_opaque() {}
+    spec d_opaque {
+        pragma verify = false;
+        pragma opaque;
+        aborts_if false;
+    }
+}
@@ -0,0 +1,24 @@
+module 0x42::TestConstantPropagation {
+
+    fun get_one(): u64 {
+        1
+    }
+
+    fun identity(x: u64): u64 {
+        x
+    }
+
+    // Called with the same constant at every call site.
+    fun add_one(x: u64): u64 {
+        x + 1
+    }
+
+    fun either(b: bool): u64 {
+        if (b) return 1;
+        2
+    }
+
+    public fun caller(): u64 {
+        identity(get_one()) + add_one(2) + add_one(2) + either(true)
+    }
+}
@@ -0,0 +1,44 @@
+address 0x123 {
+module TestDeadSpec {
+
+    struct R has key {
+        v: u64,
+    }
+
+    struct S has key {
+        v: u64,
+    }
+
+    fun always_true(): bool {
+        true
+    }
+
+    // The branch of the assert is on a constant, and replaced by a jump.
+    public fun constant_branch() {
+        assert(always_true(), 22)
+    }
+
+    // `flag` is `false` at the only call of this function, so in its baseline the assertion
+    // simplifies to `true` and is removed, and the branch on `flag` is replaced by a jump.
+    fun check(flag: bool): u64 {
+        spec {
+            assert !flag;
+        };
+        if (flag) return 1;
+        2
+    }
+
+    public fun call_check(): u64 {
+        check(false)
+    }
+
+    // The assume about `S` is removed, as `S` is not touched by the code.
+    public fun irrelevant_assume(): u64 acquires R {
+        spec {
+            assume exists<R>(@0x1);
+            assume exists<S>(@0x1);
+        };
+        borrow_global<R>(@0x1).v
+    }
+}
+}
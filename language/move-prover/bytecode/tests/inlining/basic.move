@@ -0,0 +1,30 @@
+module 0x42::TestInlining {
+
+    fun add(x: u64, y: u64): u64 {
+        x + y
+    }
+    spec add {
+        pragma inline = true;
+    }
+
+    // The call is inlined.
+    public fun caller(x: u64): u64 {
+        add(x, 1)
+    }
+
+    // The call is not inlined into an opaque caller.
+    public fun opaque_caller(x: u64): u64 {
+        add(x, 1)
+    }
+    spec opaque_caller {
+        pragma opaque;
+    }
+
+    // The call is not inlined into a caller with invariant checking disabled.
+    public fun disabled_caller(x: u64): u64 {
+        add(x, 1)
+    }
+    spec disabled_caller {
+        pragma disable_invariants_in_body;
+    }
+}
@@ -0,0 +1,30 @@
+module 0x2::M {
+    struct R<T: store> has key { t: T }
+    struct S has key { v: u64 }
+
+    public fun a_publish_bool(s: &signer) {
+        move_to(s, R { t: true });
+    }
+
+    // Relevant to the invariants over any instantiation of `R`.
+    public fun b_publish_generic<T: store>(s: &signer, t: T) {
+        move_to(s, R { t });
+    }
+
+    public fun c_read_s(): u64 acquires S {
+        borrow_global<S>(@0x1).v
+    }
+
+    // Relevant to the invariants of its callee.
+    public fun d_call_publish_bool(s: &signer) {
+        a_publish_bool(s);
+    }
+
+    spec module {
+        invariant forall a: address where exists<R<bool>>(a): global<R<bool>>(a).t;
+
+        invariant forall a: address where exists<S>(a): global<S>(a).v > 0;
+
+        invariant forall a: address where exists<R<u64>>(a): exists<S>(a);
+    }
+}
@@ -0,0 +1,9 @@
+module 0x42::TestLiveVarsSpec {
+    fun increment(x: u64): u64 {
+        x = x + 1;
+        x
+    }
+    spec increment {
+        ensures result == x + 1;
+    }
+}
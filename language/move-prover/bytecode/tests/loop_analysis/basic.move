@@ -0,0 +1,30 @@
+module 0x42::TestLoopAnalysis {
+    struct R has key { v: u64 }
+
+    // The loop writes `R`, so the invariant is checked again after an iteration.
+    fun loop_with_write(a: address, n: u64) acquires R {
+        let i = 0;
+        while ({
+            spec {
+                invariant exists<R>(a);
+            };
+            (i < n)
+        }) {
+            borrow_global_mut<R>(a).v = i;
+            i = i + 1;
+        }
+    }
+
+    // The loop does not touch `R`, so only the base case of the invariant is checked.
+    fun loop_without_write(a: address, n: u64) {
+        let i = 0;
+        while ({
+            spec {
+                invariant exists<R>(a);
+            };
+            (i < n)
+        }) {
+            i = i + 1;
+        }
+    }
+}
@@ -0,0 +1,44 @@
+address 0x123 {
+module TestDiagnostics {
+
+    struct R has key {
+        v: u64,
+    }
+
+    struct S has key {
+        v: u64,
+    }
+
+    fun always_true(): bool {
+        true
+    }
+
+    // Reports a note on the replaced branch of the assert.
+    public fun constant_branch() {
+        assert(always_true(), 22)
+    }
+
+    // Reports notes on the removed assertion, which simplifies to `true` as `flag` is `false` at
+    // the only call, and on the replaced branch on `flag`, in source order.
+    fun check(flag: bool): u64 {
+        spec {
+            assert !flag;
+        };
+        if (flag) return 1;
+        2
+    }
+
+    public fun call_check(): u64 {
+        check(false)
+    }
+
+    // Reports a note on the removed assume about `S`, which is not touched by the code.
+    public fun irrelevant_assume(): u64 acquires R {
+        spec {
+            assume exists<R>(@0x1);
+            assume exists<S>(@0x1);
+        };
+        borrow_global<R>(@0x1).v
+    }
+}
+}
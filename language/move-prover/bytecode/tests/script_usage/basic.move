@@ -0,0 +1,13 @@
+module 0x2::M {
+    struct R has key { v: u64 }
+
+    // A script function, which modifies `R` via its callee.
+    public(script) fun a_publish(s: signer) {
+        b_publish(&s);
+    }
+
+    // Not a script function, so not reported.
+    fun b_publish(s: &signer) {
+        move_to(s, R { v: 1 });
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use bytecode::{
+    borrow_analysis::BorrowAnalysisProcessor,
+    clean_and_optimize::CleanAndOptimizeProcessor,
+    eliminate_imm_refs::EliminateImmRefsProcessor,
+    function_target_pipeline::{FunctionTargetPipeline, FunctionTargetsHolder},
+    livevar_analysis::LiveVarAnalysisProcessor,
+    memory_instrumentation::MemoryInstrumentationProcessor,
+    mut_ref_instrumentation::MutRefInstrumenter,
+    options::ProverOptions,
+    reaching_def_analysis::ReachingDefProcessor,
+    spec_instrumentation::SpecInstrumentationProcessor,
+    usage_analysis::UsageProcessor,
+    verification_analysis::VerificationAnalysisProcessor,
+};
+use move_model::{model::GlobalEnv, run_model_builder};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const SOURCE: &str = r#"module 0x1::M {
+    public fun f(x: u64): u64 {
+        x + 1
+    }
+    spec f {
+        ensures result == x + 1;
+    }
+}
+"#;
+
+/// Returns an empty directory for the given test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("source_listing_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Builds the model of `SOURCE` in `dir`, returning the path of the source along with the model.
+fn build_env(dir: &Path) -> (String, GlobalEnv) {
+    let path = dir.join("M.move").to_string_lossy().to_string();
+    fs::write(&path, SOURCE).unwrap();
+    let env = run_model_builder(&[path.clone()], &[]).unwrap();
+    assert!(!env.has_errors());
+    env.set_extension(ProverOptions::default());
+    (path, env)
+}
+
+/// Runs the pipeline up to the spec instrumentation, dumping the listing after `stage` into
+/// `dir`. Returns the contents of the files dumped.
+fn run_with_source_dump(dir: &Path, env: &GlobalEnv, stage: &str) -> Vec<String> {
+    let mut targets = FunctionTargetsHolder::default();
+    for module_env in env.get_modules() {
+        for func_env in module_env.get_functions() {
+            targets.add_target(&func_env);
+        }
+    }
+    let mut pipeline = FunctionTargetPipeline::default();
+    pipeline.add_processor(EliminateImmRefsProcessor::new());
+    pipeline.add_processor(MutRefInstrumenter::new());
+    pipeline.add_processor(ReachingDefProcessor::new());
+    pipeline.add_processor(LiveVarAnalysisProcessor::new());
+    pipeline.add_processor(BorrowAnalysisProcessor::new());
+    pipeline.add_processor(MemoryInstrumentationProcessor::new());
+    pipeline.add_processor(CleanAndOptimizeProcessor::new());
+    pipeline.add_processor(UsageProcessor::new());
+    pipeline.add_processor(VerificationAnalysisProcessor::new());
+    pipeline.add_processor(SpecInstrumentationProcessor::new());
+    let base = dir.join("dump").to_string_lossy().to_string();
+    pipeline.run_with_source_dump(env, &mut targets, &base, stage);
+
+    let mut listings = vec![];
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map_or(false, |ext| ext == "listing") {
+            assert!(path
+                .to_string_lossy()
+                .ends_with(&format!("_{}.listing", stage)));
+            listings.push(fs::read_to_string(path).unwrap());
+        }
+    }
+    listings
+}
+
+#[test]
+fn test_stackless_listing() {
+    let dir = test_dir("stackless");
+    let (path, env) = build_env(&dir);
+    let listings = run_with_source_dump(&dir, &env, "stackless");
+    assert_eq!(listings.len(), 1);
+    let listing = &listings[0];
+    assert!(listing.starts_with("============ after processor `stackless` ================\n"));
+    // The source line is printed once, before the first instruction stemming from it.
+    let source_line = format!("     // at {}:3: x + 1\n", path);
+    assert_eq!(listing.matches(&source_line).count(), 1);
+    assert!(!listing.contains("ensures"));
+}
+
+#[test]
+fn test_instrumented_listing() {
+    let dir = test_dir("instrumented");
+    let (path, env) = build_env(&dir);
+    let listings = run_with_source_dump(&dir, &env, "spec_instrumenter");
+    assert_eq!(listings.len(), 1);
+    // The instructions checking the spec conditions stem from them.
+    let condition_line = format!("     // at {}:6: ensures result == x + 1;\n", path);
+    assert!(listings[0].contains(&condition_line));
+}
+
+#[test]
+fn test_unknown_stage() {
+    let dir = test_dir("unknown_stage");
+    let (_, env) = build_env(&dir);
+    assert!(run_with_source_dump(&dir, &env, "unknown").is_empty());
+    assert!(env.has_errors());
+}
@@ -0,0 +1,196 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use bytecode::{
+    function_target_pipeline::{FunctionTargetPipeline, FunctionTargetsHolder, FunctionVariant},
+    options::ProverOptions,
+    summary_store::{FunctionDigest, SummaryStore},
+    usage_analysis::{get_memory_usage, MemoryUsage, UsageProcessor, UsageState},
+};
+use itertools::Itertools;
+use move_model::{model::GlobalEnv, run_model_builder};
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const SOURCE: &str = r#"
+address 0x1 {
+module M {
+    struct R has key { v: u64 }
+    struct S has key { v: u64 }
+
+    public fun read(a: address): u64 acquires R {
+        borrow_global<R>(a).v
+    }
+
+    public fun call_read(a: address): u64 acquires R {
+        read(a)
+    }
+
+    public fun write(a: address) acquires S {
+        borrow_global_mut<S>(a).v = 0;
+    }
+}
+}
+"#;
+
+/// Returns an empty directory for the given test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("summary_store_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn store_dir(dir: &Path) -> PathBuf {
+    dir.join("store")
+}
+
+/// Builds the model of the given source, configured to use the summary store in `dir`.
+fn build_env(dir: &Path, source: &str) -> GlobalEnv {
+    let path = dir.join("M.move");
+    fs::write(&path, source).unwrap();
+    let env = run_model_builder(&[path.to_string_lossy().to_string()], &[]).unwrap();
+    assert!(!env.has_errors());
+    env.set_extension(ProverOptions {
+        summary_cache_dir: Some(store_dir(dir).to_string_lossy().to_string()),
+        ..Default::default()
+    });
+    env
+}
+
+/// Runs the usage analysis, which stores its summaries, on all functions of the env.
+fn analyze(env: &GlobalEnv) -> FunctionTargetsHolder {
+    let mut targets = FunctionTargetsHolder::default();
+    for module_env in env.get_modules() {
+        for func_env in module_env.get_functions() {
+            targets.add_target(&func_env);
+        }
+    }
+    let mut pipeline = FunctionTargetPipeline::default();
+    pipeline.add_processor(UsageProcessor::new());
+    pipeline.run(env, &mut targets);
+    targets
+}
+
+/// Returns the digests of all functions, by function name.
+fn digests(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> BTreeMap<String, FunctionDigest> {
+    let mut digests = BTreeMap::new();
+    for module_env in env.get_modules() {
+        for func_env in module_env.get_functions() {
+            let digest = targets
+                .get_data(&func_env.get_qualified_id(), &FunctionVariant::Baseline)
+                .unwrap()
+                .annotations
+                .get::<FunctionDigest>()
+                .expect("digest");
+            digests.insert(func_env.get_full_name_str(), digest.clone());
+        }
+    }
+    digests
+}
+
+fn display_usage(env: &GlobalEnv, usage: &UsageState) -> String {
+    let display = |usage: &MemoryUsage| {
+        format!(
+            "{{{}}} / {{{}}}",
+            usage
+                .direct
+                .iter()
+                .map(|mem| env.display(mem).to_string())
+                .join(", "),
+            usage
+                .transitive
+                .iter()
+                .map(|mem| env.display(mem).to_string())
+                .join(", ")
+        )
+    };
+    format!(
+        "accessed {}, modified {}, assumed {}, asserted {}, declared modified {}",
+        display(&usage.accessed),
+        display(&usage.modified),
+        display(&usage.assumed),
+        display(&usage.asserted),
+        display(&usage.declared_modified)
+    )
+}
+
+/// Finds the file of the summary with the given digest in the store.
+fn find_summary_file(dir: &Path, digest: &FunctionDigest) -> Option<PathBuf> {
+    let file_name = format!("{}.json", digest.0);
+    for entry in fs::read_dir(dir).ok()? {
+        let path = entry.ok()?.path();
+        if path.is_dir() {
+            if let Some(found) = find_summary_file(&path, digest) {
+                return Some(found);
+            }
+        } else if path.file_name() == Some(OsStr::new(&file_name)) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+#[test]
+fn test_round_trip() {
+    let dir = test_dir("round_trip");
+    let env = build_env(&dir, SOURCE);
+    let targets = analyze(&env);
+    let store = SummaryStore::new(store_dir(&dir));
+
+    // The summaries are restored in a new session, which refers to structs by other ids.
+    let new_env = build_env(&dir, SOURCE);
+    let digests = digests(&env, &targets);
+    for (name, digest) in &digests {
+        let func_env = env
+            .get_modules()
+            .flat_map(|module_env| module_env.into_functions())
+            .find(|func_env| &func_env.get_full_name_str() == name)
+            .unwrap();
+        let usage = get_memory_usage(&targets.get_target(&func_env, &FunctionVariant::Baseline));
+        let restored = store
+            .load::<UsageState>(&new_env, digest)
+            .unwrap_or_else(|| panic!("no summary stored for {}", name));
+        assert_eq!(
+            display_usage(&new_env, &restored),
+            display_usage(&env, usage),
+            "{}",
+            name
+        );
+    }
+
+    // A corrupted summary is ignored.
+    let digest = &digests["M::read"];
+    fs::write(find_summary_file(&dir, digest).unwrap(), "{").unwrap();
+    assert!(store.load::<UsageState>(&new_env, digest).is_none());
+}
+
+#[test]
+fn test_digest_invalidation() {
+    let dir = test_dir("invalidation");
+    let digests_of = |source: &str| {
+        let env = build_env(&dir, source);
+        let targets = analyze(&env);
+        digests(&env, &targets)
+    };
+    let before = digests_of(SOURCE);
+    assert_eq!(digests_of(SOURCE), before);
+
+    // Changing a function changes its digest and the ones of its callers.
+    let after = digests_of(&SOURCE.replace("borrow_global<R>(a).v", "borrow_global<R>(a).v + 1"));
+    assert_ne!(after["M::read"], before["M::read"]);
+    assert_ne!(after["M::call_read"], before["M::call_read"]);
+    assert_eq!(after["M::write"], before["M::write"]);
+
+    // So does changing its spec.
+    let after = digests_of(&SOURCE.replace(
+        "    public fun write(",
+        "    spec write { aborts_if !exists<S>(a); }\n    public fun write(",
+    ));
+    assert_ne!(after["M::write"], before["M::write"]);
+    assert_eq!(after["M::read"], before["M::read"]);
+}
@@ -5,14 +5,19 @@ use anyhow::anyhow;
 use bytecode::{
     borrow_analysis::BorrowAnalysisProcessor,
     clean_and_optimize::CleanAndOptimizeProcessor,
+    constant_propagation::ConstantPropagationProcessor,
     data_invariant_instrumentation::DataInvariantInstrumentationProcessor,
+    dead_spec_elimination::DeadSpecEliminationProcessor,
     eliminate_imm_refs::EliminateImmRefsProcessor,
     function_target_pipeline::{
         FunctionTargetPipeline, FunctionTargetsHolder, ProcessorResultDisplay,
     },
     global_invariant_analysis::GlobalInvariantAnalysisProcessor,
     global_invariant_instrumentation::GlobalInvariantInstrumentationProcessor,
+    inlining::InliningProcessor,
+    invariant_relevance::InvariantRelevanceProcessor,
     livevar_analysis::LiveVarAnalysisProcessor,
+    loop_analysis::LoopAnalysisProcessor,
     memory_instrumentation::MemoryInstrumentationProcessor,
     mono_analysis::MonoAnalysisProcessor,
     mut_ref_instrumentation::MutRefInstrumenter,
@@ -21,7 +26,7 @@ use bytecode::{
     reaching_def_analysis::ReachingDefProcessor,
     read_write_set_analysis::ReadWriteSetProcessor,
     spec_instrumentation::SpecInstrumentationProcessor,
-    usage_analysis::UsageProcessor,
+    usage_analysis::{self, UsageProcessor},
     verification_analysis::VerificationAnalysisProcessor,
 };
 use codespan_reporting::{diagnostic::Severity, term::termcolor::Buffer};
@@ -53,7 +58,7 @@ fn get_tested_transformation_pipeline(
             pipeline.add_processor(ReachingDefProcessor::new());
             Ok(Some(pipeline))
         }
-        "livevar" => {
+        "livevar" | "livevar_aggressive" => {
             let mut pipeline = FunctionTargetPipeline::default();
             pipeline.add_processor(EliminateImmRefsProcessor::new());
             pipeline.add_processor(MutRefInstrumenter::new());
@@ -188,11 +193,46 @@ fn get_tested_transformation_pipeline(
             pipeline.add_processor(MonoAnalysisProcessor::new());
             Ok(Some(pipeline))
         }
-        "usage_analysis" => {
+        "usage_analysis" | "script_usage" => {
             let mut pipeline = FunctionTargetPipeline::default();
             pipeline.add_processor(UsageProcessor::new());
             Ok(Some(pipeline))
         }
+        "loop_analysis" => {
+            let mut pipeline = FunctionTargetPipeline::default();
+            pipeline.add_processor(UsageProcessor::new());
+            pipeline.add_processor(LoopAnalysisProcessor::new());
+            Ok(Some(pipeline))
+        }
+        "inlining" => {
+            let mut pipeline = FunctionTargetPipeline::default();
+            pipeline.add_processor(InliningProcessor::new());
+            Ok(Some(pipeline))
+        }
+        "constant_propagation" => {
+            let mut pipeline = FunctionTargetPipeline::default();
+            pipeline.add_processor(ConstantPropagationProcessor::new());
+            Ok(Some(pipeline))
+        }
+        "invariant_relevance" => {
+            let mut pipeline = FunctionTargetPipeline::default();
+            pipeline.add_processor(UsageProcessor::new());
+            pipeline.add_processor(InvariantRelevanceProcessor::new());
+            Ok(Some(pipeline))
+        }
+        "verify_memory" | "assume_unverified" => {
+            let mut pipeline = FunctionTargetPipeline::default();
+            pipeline.add_processor(UsageProcessor::new());
+            pipeline.add_processor(VerificationAnalysisProcessor::new());
+            Ok(Some(pipeline))
+        }
+        "dead_spec_elimination" | "prover_diagnostics" => {
+            let mut pipeline = FunctionTargetPipeline::default();
+            pipeline.add_processor(ConstantPropagationProcessor::new());
+            pipeline.add_processor(UsageProcessor::new());
+            pipeline.add_processor(DeadSpecEliminationProcessor::new());
+            Ok(Some(pipeline))
+        }
 
         _ => Err(anyhow!(
             "the sub-directory `{}` has no associated pipeline to test",
@@ -201,6 +241,36 @@ fn get_tested_transformation_pipeline(
     }
 }
 
+/// Print the diagnostics reported by the pipeline stages, with the line they are reported at.
+fn print_diagnostics_for_test(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> String {
+    let mut text = String::from("============ diagnostics ================\n\n");
+    for diag in targets.diagnostics().get_sorted(env) {
+        let line = env
+            .get_location(&diag.loc)
+            .map_or(0, |location| location.line.0 + 1);
+        text += &format!(
+            "[{}] {:?} at line {}: {}\n",
+            diag.stage, diag.severity, line, diag.message
+        );
+    }
+    text
+}
+
+/// Print the resources which the scripts may access and modify.
+fn print_script_usage_for_test(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> String {
+    let mut text = String::from("============ script usage ================\n\n");
+    for usage in usage_analysis::get_script_usage(env, targets) {
+        text += &format!(
+            "script {} [{}] {{\n  accessed = {{{}}}\n  modified = {{{}}}\n}}\n",
+            usage.script,
+            usage.kind,
+            usage.accessed.join(", "),
+            usage.modified.join(", ")
+        );
+    }
+    text
+}
+
 fn test_runner(path: &Path) -> datatest_stable::Result<()> {
     let mut sources = extract_test_directives(path, "// dep:")?;
     sources.push(path.to_string_lossy().to_string());
@@ -215,16 +285,23 @@ fn test_runner(path: &Path) -> datatest_stable::Result<()> {
         env.report_diag(&mut error_writer, Severity::Error);
         String::from_utf8_lossy(&error_writer.into_inner()).to_string()
     } else {
-        let options = ProverOptions {
-            stable_test_output: true,
-            ..Default::default()
-        };
-        env.set_extension(options);
         let dir_name = path
             .parent()
             .and_then(|p| p.file_name())
             .and_then(|p| p.to_str())
             .ok_or_else(|| anyhow!("bad file name"))?;
+        let options = ProverOptions {
+            stable_test_output: true,
+            aggressive_dead_store_elimination: dir_name == "livevar_aggressive",
+            assume_unverified: dir_name == "assume_unverified",
+            verify_memory: if dir_name == "verify_memory" {
+                vec!["M::R".to_string()]
+            } else {
+                vec![]
+            },
+            ..Default::default()
+        };
+        env.set_extension(options);
         let pipeline_opt = get_tested_transformation_pipeline(dir_name)?;
 
         // Initialize and print function targets
@@ -254,6 +331,12 @@ fn test_runner(path: &Path) -> datatest_stable::Result<()> {
                 processor,
             }
             .to_string();
+            if dir_name == "prover_diagnostics" {
+                text += &print_diagnostics_for_test(&env, &targets);
+            }
+            if dir_name == "script_usage" {
+                text += &print_script_usage_for_test(&env, &targets);
+            }
         }
 
         text
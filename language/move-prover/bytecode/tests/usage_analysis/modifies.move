@@ -0,0 +1,19 @@
+address 0x123 {
+module TestModifies {
+
+    struct Admin has key {
+        addr: address,
+    }
+
+    struct Balance has key {
+        value: u64,
+    }
+
+    public fun reset() {
+    }
+    spec reset {
+        pragma opaque;
+        modifies global<Balance>(global<Admin>(@0x123).addr);
+    }
+}
+}
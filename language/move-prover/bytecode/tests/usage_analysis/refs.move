@@ -0,0 +1,17 @@
+address 0x123 {
+module TestRefs {
+
+    struct R has key {
+        v: u64,
+    }
+
+    fun assign_zero(r: &mut u64) {
+        *r = 0;
+    }
+
+    // The write through the reference handed over to `assign_zero` is attributed to the call.
+    public fun reset(a: address) acquires R {
+        assign_zero(&mut borrow_global_mut<R>(a).v);
+    }
+}
+}
@@ -0,0 +1,34 @@
+module 0x2::M {
+    struct R has key { v: u64 }
+    struct S has key { v: u64 }
+    struct T has key { v: u64 }
+
+    // Verified, as it modifies `R`.
+    public fun a_publish_r(s: &signer) {
+        move_to(s, R { v: 1 });
+    }
+
+    // Verified, as it modifies `S`, which is covered by an invariant over `R`.
+    public fun b_publish_s(s: &signer) {
+        move_to(s, S { v: 1 });
+    }
+
+    // Not verified, as neither `T` nor an invariant over `R` is affected.
+    public fun c_publish_t(s: &signer) {
+        move_to(s, T { v: 1 });
+    }
+
+    // Verified, as it modifies `R` via its callee.
+    public fun d_call_publish_r(s: &signer) {
+        a_publish_r(s);
+    }
+
+    // Not verified, as it only reads `R`.
+    public fun e_read_r(): u64 acquires R {
+        borrow_global<R>(@0x1).v
+    }
+
+    spec module {
+        invariant forall a: address where exists<S>(a): exists<R>(a);
+    }
+}
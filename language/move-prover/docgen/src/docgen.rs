@@ -7,15 +7,15 @@ use log::{debug, info, warn};
 use codespan::{ByteIndex, Span};
 use itertools::Itertools;
 use move_model::{
-    ast::{ModuleName, SpecBlockInfo, SpecBlockTarget},
+    ast::{ConditionKind, ModuleName, SpecBlockInfo, SpecBlockTarget},
     code_writer::{CodeWriter, CodeWriterLabel},
     emit, emitln,
     model::{
         AbilitySet, FunId, FunctionEnv, GlobalEnv, Loc, ModuleEnv, ModuleId, NamedConstantEnv,
-        Parameter, QualifiedId, StructEnv, TypeParameter,
+        Parameter, QualifiedId, QualifiedInstId, StructEnv, StructId, TypeParameter,
     },
     symbol::Symbol,
-    ty::TypeDisplayContext,
+    ty::{Type, TypeDisplayContext},
 };
 use num::BigUint;
 use once_cell::sync::Lazy;
@@ -147,6 +147,9 @@ pub struct DocgenOptions {
     pub include_dep_diagrams: bool,
     /// Whether to include call diagrams in the generated docs.
     pub include_call_diagrams: bool,
+    /// Whether to link the spec conditions of functions to the source of the code they guard,
+    /// and to index the global invariants of modules with the memory they constrain.
+    pub include_condition_links: bool,
 }
 
 impl Default for DocgenOptions {
@@ -165,6 +168,7 @@ impl Default for DocgenOptions {
             references_file: None,
             include_dep_diagrams: false,
             include_call_diagrams: false,
+            include_condition_links: false,
         }
     }
 }
@@ -686,6 +690,8 @@ impl<'env> Docgen<'env> {
             }
         }
 
+        self.gen_global_invariant_index(module_env);
+
         self.decrement_section_nest();
 
         // Generate table of contents if this is standalone.
@@ -1065,7 +1071,8 @@ impl<'env> Docgen<'env> {
                 "Specification",
                 &SpecBlockTarget::Function(func_env.module_env.get_id(), func_env.get_id()),
                 spec_block_map,
-            )
+            );
+            self.gen_condition_links(func_env);
         }
         if self.options.include_call_diagrams {
             let func_name = func_env.get_simple_name_string();
@@ -1300,11 +1307,114 @@ impl<'env> Docgen<'env> {
                 );
                 self.code_block(&self.function_header_display(&func_env));
                 self.gen_spec_blocks(module_env, "", &target, spec_block_map);
+                self.gen_condition_links(&func_env);
             }
         }
         self.decrement_section_nest();
     }
 
+    /// Generates the list of the spec conditions of a function, each linked to the source of the
+    /// code it guards: the implementation for the conditions of the function spec, and the code at
+    /// the offset of the spec block for the conditions of a spec block in the implementation.
+    fn gen_condition_links(&self, func_env: &FunctionEnv<'_>) {
+        if !self.options.include_condition_links || !self.options.include_specs {
+            return;
+        }
+        let is_linked = |kind: &ConditionKind| {
+            !matches!(kind, ConditionKind::LetPost(..) | ConditionKind::LetPre(..))
+        };
+        let spec = func_env.get_spec();
+        let conditions = spec
+            .conditions
+            .iter()
+            .filter(|cond| is_linked(&cond.kind))
+            .collect_vec();
+        let impl_conditions = spec
+            .on_impl
+            .iter()
+            .flat_map(|(offset, block_spec)| {
+                block_spec
+                    .conditions
+                    .iter()
+                    .map(move |cond| (*offset, cond))
+            })
+            .filter(|(_, cond)| is_linked(&cond.kind))
+            .collect_vec();
+        if conditions.is_empty() && impl_conditions.is_empty() {
+            return;
+        }
+        self.begin_collapsed("Conditions");
+        self.begin_items();
+        let impl_link = self.source_link(&func_env.get_loc());
+        for cond in conditions {
+            self.item_text(&format!(
+                "`{}` at {} guards the implementation at {}",
+                cond.kind,
+                self.source_link(&cond.loc),
+                impl_link
+            ));
+        }
+        for (offset, cond) in impl_conditions {
+            self.item_text(&format!(
+                "`{}` at {} guards code offset {} at {}",
+                cond.kind,
+                self.source_link(&cond.loc),
+                offset,
+                self.source_link(&func_env.get_bytecode_loc(offset))
+            ));
+        }
+        self.end_items();
+        self.end_collapsed();
+    }
+
+    /// Generates an index of the global invariants declared in a module, with the memory they
+    /// constrain.
+    fn gen_global_invariant_index(&self, module_env: &ModuleEnv<'_>) {
+        let invariants = self
+            .env
+            .get_global_invariants_by_module(module_env.get_id());
+        if !self.options.include_condition_links
+            || !self.options.include_specs
+            || invariants.is_empty()
+        {
+            return;
+        }
+        self.section_header(
+            "Global Invariants",
+            &self.label_for_section("Global Invariants"),
+        );
+        self.begin_items();
+        for id in invariants {
+            let inv = self.env.get_global_invariant(id).expect("global invariant");
+            let type_param_names = match &inv.kind {
+                ConditionKind::GlobalInvariant(names)
+                | ConditionKind::GlobalInvariantUpdate(names) => Some(names.clone()),
+                _ => None,
+            };
+            let tctx = TypeDisplayContext::WithEnv {
+                env: self.env,
+                type_param_names,
+            };
+            let memory = inv
+                .cond
+                .used_memory(self.env)
+                .into_iter()
+                .map(|(mem, _)| mem)
+                .collect::<BTreeSet<_>>()
+                .iter()
+                .map(|mem| self.memory_display(mem, &tctx))
+                .join(", ");
+            self.item_text(&format!(
+                "`{}` at {} constrains {}",
+                inv.kind,
+                self.source_link(&inv.loc),
+                memory
+            ));
+        }
+        self.end_items();
+        emitln!(self.writer);
+    }
+
     // ============================================================================================
     // Helpers
 
@@ -1712,6 +1822,53 @@ impl<'env> Docgen<'env> {
         )
     }
 
+    /// Returns a markdown link to the source lines of a location, e.g. `[lines 3-5](M.move#L3-L5)`.
+    fn source_link(&self, loc: &Loc) -> String {
+        let (file, start) = match self.env.get_file_and_location(loc) {
+            Some(file_and_start) => file_and_start,
+            None => return "an unknown location".to_string(),
+        };
+        let end_loc = Loc::new(loc.file_id(), Span::new(loc.span().end(), loc.span().end()));
+        let path = self
+            .path_relative_to(Path::new(&file), Path::new(&self.options.output_directory))
+            .to_string_lossy()
+            .to_string();
+        let start_line = start.line.0 + 1;
+        let end_line = self
+            .env
+            .get_location(&end_loc)
+            .map_or(start_line, |end| end.line.0 + 1);
+        if start_line == end_line {
+            format!("[line {}]({}#L{})", start_line, path, start_line)
+        } else {
+            format!(
+                "[lines {}-{}]({}#L{}-L{})",
+                start_line, end_line, path, start_line, end_line
+            )
+        }
+    }
+
+    /// Returns the display of a memory, linked to the documentation of its struct if any.
+    fn memory_display(
+        &self,
+        mem: &QualifiedInstId<StructId>,
+        tctx: &TypeDisplayContext<'_>,
+    ) -> String {
+        let ty = Type::Struct(mem.module_id, mem.id, mem.inst.clone());
+        let display = format!("`{}`", ty.display(tctx));
+        if self.infos.contains_key(&mem.module_id) {
+            let module_env = self.env.get_module(mem.module_id);
+            let name = module_env.get_struct(mem.id).get_name();
+            format!(
+                "[{}]({})",
+                display,
+                self.ref_for_module_item(&module_env, name)
+            )
+        } else {
+            display
+        }
+    }
+
     /// Create a unique label for a section header.
     fn label_for_section(&self, title: &str) -> String {
         let counter = *self.label_counter.borrow();
@@ -1827,3 +1984,79 @@ impl<'env> Docgen<'env> {
         (0..n).map(|_| s).collect::<String>()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_model::run_model_builder;
+    use std::fs;
+
+    const SOURCE: &str = r#"module 0x1::M {
+    fun f(x: u64): u64 {
+        x + 1
+    }
+    spec f {
+        ensures result == x + 1;
+    }
+}
+"#;
+
+    /// Builds the model of `SOURCE`, returning the path of the source along with the model.
+    fn build_env(dir: &Path) -> (String, GlobalEnv) {
+        let path = dir.join("M.move").to_string_lossy().to_string();
+        fs::write(&path, SOURCE).unwrap();
+        let env = run_model_builder(&[path.clone()], &[]).unwrap();
+        assert!(!env.has_errors());
+        (path, env)
+    }
+
+    fn docgen_options(dir: &Path, include_condition_links: bool) -> DocgenOptions {
+        DocgenOptions {
+            output_directory: dir.to_string_lossy().to_string(),
+            include_condition_links,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_source_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, env) = build_env(dir.path());
+        let options = docgen_options(dir.path(), true);
+        let docgen = Docgen::new(&env, &options);
+
+        let func_env = env
+            .get_modules()
+            .flat_map(|module_env| module_env.into_functions())
+            .next()
+            .unwrap();
+        assert_eq!(
+            docgen.source_link(&func_env.get_loc()),
+            format!("[lines 2-4]({}#L2-L4)", path)
+        );
+        let cond = &func_env.get_spec().conditions[0];
+        assert_eq!(
+            docgen.source_link(&cond.loc),
+            format!("[line 6]({}#L6)", path)
+        );
+    }
+
+    #[test]
+    fn test_condition_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, env) = build_env(dir.path());
+        let link = format!(
+            "`ensures` at [line 6]({}#L6) guards the implementation at [lines 2-4]({}#L2-L4)",
+            path, path
+        );
+
+        let options = docgen_options(dir.path(), true);
+        let docs = Docgen::new(&env, &options).gen();
+        assert!(docs.iter().any(|(_, content)| content.contains(&link)));
+
+        // Links are only generated when enabled.
+        let options = docgen_options(dir.path(), false);
+        let docs = Docgen::new(&env, &options).gen();
+        assert!(docs.iter().all(|(_, content)| !content.contains("guards")));
+    }
+}
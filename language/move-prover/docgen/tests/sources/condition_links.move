@@ -0,0 +1,18 @@
+address 0x2 {
+module ConditionLinks {
+    struct Counter has key { value: u64 }
+
+    spec module {
+        invariant forall a: address where exists<Counter>(a): global<Counter>(a).value > 0;
+    }
+
+    fun incr(x: u64): u64 {
+        spec { assume x < 100; };
+        x + 1
+    }
+    spec incr {
+        aborts_if false;
+        ensures result == x + 1;
+    }
+}
+}
@@ -19,6 +19,7 @@ fn test_runner(path: &Path) -> datatest_stable::Result<()> {
         source_files,
         dep_files: move_stdlib_files(),
         check_stackless_vm: true,
+        coverage_dir: None,
         report_storage_on_error: false,
         report_statistics: false,
         list: false,
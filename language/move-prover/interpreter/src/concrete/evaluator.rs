@@ -42,6 +42,13 @@ pub type EvalResult<T> = ::std::result::Result<T, BigInt>;
 const DIEM_CORE_ADDR: AccountAddress =
     AccountAddress::new([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
 
+/// Message of the error reported when an assert expression does not hold
+pub const ASSERTION_FAILURE_MESSAGE: &str = "property does not hold";
+
+/// Message of the error reported when an assume expression does not hold, e.g., when the
+/// arguments of the entrypoint do not meet its `requires` conditions
+pub const ASSUMPTION_FAILURE_MESSAGE: &str = "assumption does not hold";
+
 //**************************************************************************************************
 // Evaluation context
 //**************************************************************************************************
@@ -121,20 +128,7 @@ impl<'env> Evaluator<'env> {
 
     /// Check whether an assert expression holds
     pub fn check_assert(&self, exp: &Exp) {
-        match self.evaluate(exp) {
-            Ok(val) => {
-                if !val.into_bool() {
-                    self.record_checking_failure(exp);
-                }
-            }
-            Err(err) => {
-                // TODO (mengxu) this is just to keep tests happy, to be removed once completed
-                if err == BigInt::zero() {
-                    return;
-                }
-                self.record_evaluation_failure(exp, err);
-            }
-        }
+        self.check_property(exp, ASSERTION_FAILURE_MESSAGE)
     }
 
     /// Check whether an assume expression holds, unless the assume expression represents a `let`
@@ -165,12 +159,29 @@ impl<'env> Evaluator<'env> {
             let local_val = self.evaluate(&args[1]).unwrap();
             Some((local_idx, TypedValue::fuse_base(local_ty, local_val)))
         } else {
-            // for all other cases, treat with as an assertion
-            self.check_assert(exp);
+            // for all other cases, check the expression as an assertion which is assumed
+            self.check_property(exp, ASSUMPTION_FAILURE_MESSAGE);
             None
         }
     }
 
+    fn check_property(&self, exp: &Exp, failure_message: &str) {
+        match self.evaluate(exp) {
+            Ok(val) => {
+                if !val.into_bool() {
+                    self.record_checking_failure(exp, failure_message);
+                }
+            }
+            Err(err) => {
+                // TODO (mengxu) this is just to keep tests happy, to be removed once completed
+                if err == BigInt::zero() {
+                    return;
+                }
+                self.record_evaluation_failure(exp, err);
+            }
+        }
+    }
+
     //
     // dispatcher
     //
@@ -1272,10 +1283,10 @@ impl<'env> Evaluator<'env> {
         env.error(&loc, "failed to evaluate expression");
     }
 
-    fn record_checking_failure(&self, exp: &Exp) {
+    fn record_checking_failure(&self, exp: &Exp, failure_message: &str) {
         let env = self.target.global_env();
         let loc = env.get_node_loc(exp.node_id());
-        env.error(&loc, "property does not hold");
+        env.error(&loc, failure_message);
     }
 
     fn eval_failure_code() -> BigInt {
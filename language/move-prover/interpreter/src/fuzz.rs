@@ -0,0 +1,696 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This file implements spec-driven fuzzing. A harness is generated for each function whose
+//! specification has `aborts_if` or `ensures` conditions. The harness executes the function in
+//! the stackless bytecode interpreter with random arguments, where the conditions of the
+//! specification are checked as runtime oracles by the expression evaluator. Executions on which
+//! a condition does not hold are reported as divergences between the specification and the
+//! implementation, and can be turned into Move unit tests reproducing them.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+use bytecode::options::ProverOptions;
+use move_binary_format::errors::VMResult;
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::ModuleId,
+    value::{MoveTypeLayout, MoveValue},
+    vm_status::StatusCode,
+};
+use move_model::{
+    ast::ConditionKind,
+    model::{FunctionEnv, FunctionVisibility, GlobalEnv},
+    ty::{PrimitiveType as ModelPrimitiveType, Type as ModelType},
+};
+
+use crate::{
+    concrete::{
+        evaluator::ASSUMPTION_FAILURE_MESSAGE, runtime::convert_move_type_tag,
+        settings::InterpreterSettings, value::GlobalState,
+    },
+    StacklessBytecodeInterpreter,
+};
+
+/// Options for spec-driven fuzzing.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FuzzOptions {
+    /// Number of executions of each function, each with new random arguments.
+    pub iterations: usize,
+    /// Seed for the generation of the arguments. Runs with the same seed use the same arguments.
+    pub seed: u64,
+    /// Maximal length of the vectors generated as arguments.
+    pub max_vector_len: usize,
+    /// Directory where to write the Move unit tests reproducing the divergences found.
+    pub harness_dir: Option<String>,
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            iterations: 100,
+            seed: 0,
+            max_vector_len: 8,
+            harness_dir: None,
+        }
+    }
+}
+
+//**************************************************************************************************
+// Harnesses
+//**************************************************************************************************
+
+/// The type of an argument which the fuzzer can generate.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ArgType {
+    Bool,
+    U8,
+    U64,
+    U128,
+    Address,
+    Signer,
+    /// A `&signer`, which is passed to the interpreter as the address of the signer
+    SignerRef,
+    Vector(Box<ArgType>),
+}
+
+impl ArgType {
+    fn from_model_type(ty: &ModelType) -> Option<Self> {
+        let arg_ty = match ty {
+            ModelType::Primitive(ModelPrimitiveType::Bool) => ArgType::Bool,
+            ModelType::Primitive(ModelPrimitiveType::U8) => ArgType::U8,
+            ModelType::Primitive(ModelPrimitiveType::U64) => ArgType::U64,
+            ModelType::Primitive(ModelPrimitiveType::U128) => ArgType::U128,
+            ModelType::Primitive(ModelPrimitiveType::Address) => ArgType::Address,
+            ModelType::Primitive(ModelPrimitiveType::Signer) => ArgType::Signer,
+            ModelType::Reference(false, base_ty)
+                if matches!(**base_ty, ModelType::Primitive(ModelPrimitiveType::Signer)) =>
+            {
+                ArgType::SignerRef
+            }
+            ModelType::Vector(elem_ty) => match Self::from_model_type(elem_ty)? {
+                ArgType::Signer | ArgType::SignerRef => return None,
+                elem_arg_ty => ArgType::Vector(Box::new(elem_arg_ty)),
+            },
+            _ => return None,
+        };
+        Some(arg_ty)
+    }
+}
+
+/// A function to fuzz, along with the types of its parameters.
+#[derive(Clone, Debug)]
+pub struct FuzzHarness {
+    pub module_id: ModuleId,
+    pub function: Identifier,
+    /// The name of the function, in the format of `module_name::function_name`
+    pub name: String,
+    pub is_public: bool,
+    pub params: Vec<(String, ArgType)>,
+    /// The layouts of the return values, if they can be decoded for reporting
+    pub return_layouts: Option<Vec<MoveTypeLayout>>,
+}
+
+impl FuzzHarness {
+    /// Creates the harness of a function. Returns the reason why the function cannot be fuzzed if
+    /// that is the case.
+    pub fn new(func_env: &FunctionEnv) -> Result<Self, String> {
+        if func_env.is_native() {
+            return Err("native functions cannot be fuzzed".to_string());
+        }
+        if !func_env.get_type_parameters().is_empty() {
+            return Err("generic functions cannot be fuzzed".to_string());
+        }
+        let env = func_env.module_env.env;
+        let mut params = vec![];
+        for param in func_env.get_parameters() {
+            let name = env.symbol_pool().string(param.0).to_string();
+            match ArgType::from_model_type(&param.1) {
+                Some(arg_ty) => params.push((name, arg_ty)),
+                None => {
+                    return Err(format!(
+                        "values of type `{}` of parameter `{}` cannot be generated",
+                        param.1.display(&func_env.get_type_display_ctxt()),
+                        name
+                    ))
+                }
+            }
+        }
+        let return_layouts = func_env
+            .get_return_types()
+            .into_iter()
+            .map(|ty| {
+                let tag = ty.into_type_tag(env)?;
+                convert_move_type_tag(env, &tag)
+                    .ok()
+                    .map(|base_ty| base_ty.to_move_type_layout())
+            })
+            .collect();
+        Ok(Self {
+            module_id: func_env.module_env.get_verified_module().self_id(),
+            function: func_env.get_identifier(),
+            name: func_env.get_full_name_str(),
+            is_public: func_env.visibility() == FunctionVisibility::Public,
+            params,
+            return_layouts,
+        })
+    }
+
+    /// Generates random arguments for the function.
+    fn generate_args(&self, rng: &mut Rng, options: &FuzzOptions) -> Vec<MoveValue> {
+        self.params
+            .iter()
+            .map(|(_, arg_ty)| rng.gen_value(arg_ty, options.max_vector_len))
+            .collect()
+    }
+
+    /// Formats the outcome of an execution, decoding the return values if possible.
+    fn format_outcome(&self, result: &VMResult<Vec<Vec<u8>>>) -> String {
+        match result {
+            Ok(rets) => {
+                let decoded = self.return_layouts.as_ref().and_then(|layouts| {
+                    rets.iter()
+                        .zip(layouts)
+                        .map(|(bytes, layout)| MoveValue::simple_deserialize(bytes, layout).ok())
+                        .collect::<Option<Vec<_>>>()
+                });
+                match decoded {
+                    Some(vals) if vals.is_empty() => "returned".to_string(),
+                    Some(vals) => format!(
+                        "returned {}",
+                        vals.iter().map(format_value).collect::<Vec<_>>().join(", ")
+                    ),
+                    None => format!("returned {} values", rets.len()),
+                }
+            }
+            Err(err) if err.major_status() == StatusCode::ABORTED => {
+                format!("aborted with code {}", err.sub_status().unwrap_or_default())
+            }
+            Err(err) => format!("failed with status {:?}", err.major_status()),
+        }
+    }
+}
+
+/// Generates the harnesses of the functions to fuzz, i.e., the functions of the target modules
+/// which are verified and have `aborts_if` or `ensures` conditions. Also returns the functions
+/// which cannot be fuzzed, along with the reason.
+pub fn generate_harnesses(
+    env: &GlobalEnv,
+    prover_options: &ProverOptions,
+) -> (Vec<FuzzHarness>, Vec<(String, String)>) {
+    let mut harnesses = vec![];
+    let mut skipped = vec![];
+    for module_env in env.get_modules() {
+        if !module_env.is_target() || module_env.is_script_module() {
+            continue;
+        }
+        for func_env in module_env.get_functions() {
+            let has_oracles =
+                func_env.get_spec().conditions.iter().any(|cond| {
+                    matches!(cond.kind, ConditionKind::AbortsIf | ConditionKind::Ensures)
+                });
+            if !has_oracles || !func_env.should_verify(&prover_options.verify_scope) {
+                continue;
+            }
+            match FuzzHarness::new(&func_env) {
+                Ok(harness) => harnesses.push(harness),
+                Err(reason) => skipped.push((func_env.get_full_name_str(), reason)),
+            }
+        }
+    }
+    (harnesses, skipped)
+}
+
+//**************************************************************************************************
+// Fuzzing
+//**************************************************************************************************
+
+/// An execution on which the function does not satisfy its specification.
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    pub args: Vec<MoveValue>,
+    pub result: VMResult<Vec<Vec<u8>>>,
+    /// The outcome of the execution, in a human-readable format
+    pub outcome: String,
+    /// The diagnostics of the conditions which do not hold
+    pub diagnostics: String,
+}
+
+/// The result of fuzzing a function.
+#[derive(Clone, Debug)]
+pub struct FuzzResult {
+    pub harness: FuzzHarness,
+    /// Number of executions, including the discarded ones
+    pub executions: usize,
+    /// Number of executions discarded because the arguments do not meet the `requires` conditions
+    pub discarded: usize,
+    /// The first divergence found, if any. Fuzzing of the function stops at the first divergence.
+    pub divergence: Option<Divergence>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FuzzReport {
+    pub results: Vec<FuzzResult>,
+    /// The functions which cannot be fuzzed, along with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+impl FuzzReport {
+    pub fn divergences(&self) -> impl Iterator<Item = (&FuzzHarness, &Divergence)> {
+        self.results.iter().filter_map(|result| {
+            result
+                .divergence
+                .as_ref()
+                .map(|divergence| (&result.harness, divergence))
+        })
+    }
+
+    /// Generates a Move unit test module for each module with divergences, with a test replaying
+    /// each divergence which can be expressed in Move. The tests are meant to be run with the
+    /// stackless bytecode interpreter to check the specifications (i.e., `move-unit-test
+    /// --stackless`). Returns the source of the test modules, keyed by the name of the module.
+    pub fn generate_move_tests(&self) -> BTreeMap<String, String> {
+        let mut tests: BTreeMap<&ModuleId, Vec<String>> = BTreeMap::new();
+        for (harness, divergence) in self.divergences() {
+            tests
+                .entry(&harness.module_id)
+                .or_default()
+                .push(generate_move_test(harness, divergence));
+        }
+        tests
+            .into_iter()
+            .map(|(module_id, tests)| {
+                let test_module_name = format!("{}FuzzTests", module_id.name());
+                let source = format!(
+                    "#[test_only]\nmodule 0x{}::{} {{\n    use 0x{}::{};\n\n{}}}\n",
+                    module_id.address().short_str_lossless(),
+                    test_module_name,
+                    module_id.address().short_str_lossless(),
+                    module_id.name(),
+                    tests.join("\n"),
+                );
+                (test_module_name, source)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for FuzzReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, reason) in &self.skipped {
+            writeln!(f, "skipped `{}`: {}", name, reason)?;
+        }
+        for (harness, divergence) in self.divergences() {
+            writeln!(f, "`{}` diverges from its specification", harness.name)?;
+            let args = harness
+                .params
+                .iter()
+                .zip(&divergence.args)
+                .map(|((name, _), arg)| format!("{} = {}", name, format_value(arg)))
+                .collect::<Vec<_>>();
+            writeln!(f, "  arguments: {}", args.join(", "))?;
+            writeln!(f, "  outcome: {}", divergence.outcome)?;
+            write!(f, "{}", divergence.diagnostics)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fuzzes the functions of the target modules against their specification.
+pub fn fuzz_specs(
+    env: &GlobalEnv,
+    prover_options: &ProverOptions,
+    options: &FuzzOptions,
+) -> FuzzReport {
+    let (harnesses, skipped) = generate_harnesses(env, prover_options);
+    let interpreter = StacklessBytecodeInterpreter::new(
+        env,
+        Some(ProverOptions {
+            for_interpretation: true,
+            ..prover_options.clone()
+        }),
+        InterpreterSettings::default(),
+    );
+    let results = harnesses
+        .into_iter()
+        .map(|harness| fuzz_function(&interpreter, harness, options))
+        .collect();
+    FuzzReport { results, skipped }
+}
+
+fn fuzz_function(
+    interpreter: &StacklessBytecodeInterpreter,
+    harness: FuzzHarness,
+    options: &FuzzOptions,
+) -> FuzzResult {
+    let env = interpreter.env;
+    // each function has its own generator, such that its arguments do not depend on the other
+    // functions being fuzzed
+    let mut rng = Rng::new(options.seed);
+    let mut result = FuzzResult {
+        harness,
+        executions: 0,
+        discarded: 0,
+        divergence: None,
+    };
+    while result.executions < options.iterations {
+        let args = result.harness.generate_args(&mut rng, options);
+        let (vm_result, _, _) = interpreter.interpret(
+            &result.harness.module_id,
+            &result.harness.function,
+            &[],
+            &args,
+            &GlobalState::default(),
+        );
+        result.executions += 1;
+        if env.has_diag(ASSUMPTION_FAILURE_MESSAGE) {
+            // the arguments are outside of the domain of the function
+            env.clear_diag();
+            result.discarded += 1;
+            continue;
+        }
+        if let Some(diagnostics) = interpreter.report_property_checking_results() {
+            result.divergence = Some(Divergence {
+                outcome: result.harness.format_outcome(&vm_result),
+                args,
+                result: vm_result,
+                diagnostics,
+            });
+            break;
+        }
+    }
+    result
+}
+
+//**************************************************************************************************
+// Move unit tests
+//**************************************************************************************************
+
+fn generate_move_test(harness: &FuzzHarness, divergence: &Divergence) -> String {
+    let test_name = format!("{}_divergence", harness.function);
+    let args = harness
+        .params
+        .iter()
+        .zip(&divergence.args)
+        .map(|((name, arg_ty), arg)| format_move_arg(name, arg_ty, arg))
+        .collect::<Option<Vec<_>>>();
+    let args = match args {
+        Some(args) if harness.is_public => args,
+        // leave a note in the test module instead of a test which does not compile
+        _ => {
+            return format!(
+                "    // `{}` cannot be called with the arguments of its divergence from a test\n",
+                harness.name
+            )
+        }
+    };
+
+    // signers are passed as parameters of the test, with their address set by the attribute
+    let signers = harness
+        .params
+        .iter()
+        .zip(&divergence.args)
+        .filter_map(|((name, arg_ty), arg)| match (arg_ty, arg) {
+            (ArgType::Signer, MoveValue::Signer(addr))
+            | (ArgType::SignerRef, MoveValue::Address(addr)) => Some((name, addr)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut attributes = if signers.is_empty() {
+        "test".to_string()
+    } else {
+        let bindings = signers
+            .iter()
+            .map(|(name, addr)| format!("{} = @0x{}", name, addr.short_str_lossless()))
+            .collect::<Vec<_>>();
+        format!("test({})", bindings.join(", "))
+    };
+    match &divergence.result {
+        Err(err) if err.major_status() == StatusCode::ABORTED => {
+            attributes += &format!(
+                ", expected_failure(abort_code = {})",
+                err.sub_status().unwrap_or_default()
+            );
+        }
+        Err(_) => attributes += ", expected_failure",
+        Ok(_) => (),
+    }
+    let params = signers
+        .iter()
+        .map(|(name, _)| format!("{}: signer", name))
+        .collect::<Vec<_>>();
+    format!(
+        "    #[{}]\n    fun {}({}) {{\n        {}::{}({});\n    }}\n",
+        attributes,
+        test_name,
+        params.join(", "),
+        harness.module_id.name(),
+        harness.function,
+        args.join(", ")
+    )
+}
+
+/// Formats an argument as a Move expression, if it can be expressed in Move.
+fn format_move_arg(name: &str, arg_ty: &ArgType, arg: &MoveValue) -> Option<String> {
+    let formatted = match (arg_ty, arg) {
+        (ArgType::Signer, _) => name.to_string(),
+        (ArgType::SignerRef, _) => format!("&{}", name),
+        (ArgType::Vector(elem_ty), MoveValue::Vector(elems)) if **elem_ty == ArgType::U8 => {
+            let bytes = elems
+                .iter()
+                .map(|elem| match elem {
+                    MoveValue::U8(byte) => Some(format!("{:02x}", byte)),
+                    _ => None,
+                })
+                .collect::<Option<String>>()?;
+            format!("x\"{}\"", bytes)
+        }
+        // there are no literals for the other vectors
+        (ArgType::Vector(_), _) => return None,
+        _ => format_value(arg),
+    };
+    Some(formatted)
+}
+
+fn format_value(val: &MoveValue) -> String {
+    match val {
+        MoveValue::Bool(b) => b.to_string(),
+        MoveValue::U8(n) => format!("{}u8", n),
+        MoveValue::U64(n) => format!("{}u64", n),
+        MoveValue::U128(n) => format!("{}u128", n),
+        MoveValue::Address(addr) => format!("@0x{}", addr.short_str_lossless()),
+        MoveValue::Signer(addr) => format!("signer(@0x{})", addr.short_str_lossless()),
+        MoveValue::Vector(elems) => format!(
+            "[{}]",
+            elems
+                .iter()
+                .map(format_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        MoveValue::Struct(_) => format!("{:?}", val),
+    }
+}
+
+//**************************************************************************************************
+// Random generation
+//**************************************************************************************************
+
+/// A SplitMix64 generator. The arguments only need to be reproducible from the seed, not to be
+/// unpredictable.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Generates an integer up to `max`. The boundary values are generated more often than the
+    /// others, as they are the most likely to reveal overflows and off-by-one errors.
+    fn gen_int(&mut self, max: u128) -> u128 {
+        if self.below(4) == 0 {
+            [0, 1, max - 1, max][self.below(4) as usize]
+        } else {
+            let n = ((self.next_u64() as u128) << 64) | self.next_u64() as u128;
+            if max == u128::MAX {
+                n
+            } else {
+                n % (max + 1)
+            }
+        }
+    }
+
+    fn gen_address(&mut self) -> AccountAddress {
+        let mut bytes = [0u8; AccountAddress::LENGTH];
+        for chunk in bytes.chunks_mut(8) {
+            let n = self.next_u64().to_be_bytes();
+            chunk.copy_from_slice(&n[..chunk.len()]);
+        }
+        AccountAddress::new(bytes)
+    }
+
+    fn gen_value(&mut self, arg_ty: &ArgType, max_vector_len: usize) -> MoveValue {
+        match arg_ty {
+            ArgType::Bool => MoveValue::Bool(self.below(2) == 1),
+            ArgType::U8 => MoveValue::U8(self.gen_int(u8::MAX as u128) as u8),
+            ArgType::U64 => MoveValue::U64(self.gen_int(u64::MAX as u128) as u64),
+            ArgType::U128 => MoveValue::U128(self.gen_int(u128::MAX)),
+            ArgType::Address => MoveValue::Address(self.gen_address()),
+            ArgType::Signer => MoveValue::Signer(self.gen_address()),
+            ArgType::SignerRef => MoveValue::Address(self.gen_address()),
+            ArgType::Vector(elem_ty) => {
+                let len = self.below(max_vector_len as u64 + 1);
+                MoveValue::Vector(
+                    (0..len)
+                        .map(|_| self.gen_value(elem_ty, max_vector_len))
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::errors::{Location, PartialVMError};
+
+    fn address(literal: &str) -> AccountAddress {
+        AccountAddress::from_hex_literal(literal).unwrap()
+    }
+
+    fn harness(is_public: bool) -> FuzzHarness {
+        FuzzHarness {
+            module_id: ModuleId::new(address("0x2"), Identifier::new("M").unwrap()),
+            function: Identifier::new("f").unwrap(),
+            name: "M::f".to_string(),
+            is_public,
+            params: vec![
+                ("s".to_string(), ArgType::SignerRef),
+                ("x".to_string(), ArgType::U64),
+            ],
+            return_layouts: Some(vec![]),
+        }
+    }
+
+    fn aborted_divergence() -> Divergence {
+        let result = Err(PartialVMError::new(StatusCode::ABORTED)
+            .with_sub_status(3)
+            .finish(Location::Undefined));
+        Divergence {
+            args: vec![MoveValue::Address(address("0x5")), MoveValue::U64(7)],
+            outcome: harness(true).format_outcome(&result),
+            result,
+            diagnostics: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_generation_is_reproducible() {
+        let arg_ty = ArgType::Vector(Box::new(ArgType::U128));
+        let values = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..10)
+                .map(|_| rng.gen_value(&arg_ty, 4))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(1), values(1));
+        assert_ne!(values(1), values(2));
+        for value in values(1) {
+            match value {
+                MoveValue::Vector(elems) => assert!(elems.len() <= 4),
+                _ => panic!("expected a vector"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_gen_int_boundaries() {
+        let mut rng = Rng::new(0);
+        let ints = (0..1000).map(|_| rng.gen_int(10)).collect::<Vec<_>>();
+        assert!(ints.iter().all(|n| *n <= 10));
+        for boundary in &[0, 1, 9, 10] {
+            assert!(ints.contains(boundary));
+        }
+        let mut rng = Rng::new(0);
+        assert!((0..1000).any(|_| rng.gen_int(u128::MAX) == u128::MAX));
+    }
+
+    #[test]
+    fn test_format_move_arg() {
+        let bytes = MoveValue::Vector(vec![MoveValue::U8(10), MoveValue::U8(255)]);
+        let byte_vector = ArgType::Vector(Box::new(ArgType::U8));
+        assert_eq!(
+            format_move_arg("v", &byte_vector, &bytes),
+            Some("x\"0aff\"".to_string())
+        );
+        let numbers = MoveValue::Vector(vec![MoveValue::U64(1)]);
+        let number_vector = ArgType::Vector(Box::new(ArgType::U64));
+        assert_eq!(format_move_arg("v", &number_vector, &numbers), None);
+        let signer = MoveValue::Signer(address("0x5"));
+        assert_eq!(
+            format_move_arg("s", &ArgType::Signer, &signer),
+            Some("s".to_string())
+        );
+        assert_eq!(
+            format_move_arg("x", &ArgType::U64, &MoveValue::U64(7)),
+            Some("7u64".to_string())
+        );
+        assert_eq!(format_value(&signer), "signer(@0x5)");
+    }
+
+    #[test]
+    fn test_generate_move_tests() {
+        let divergence = aborted_divergence();
+        assert_eq!(divergence.outcome, "aborted with code 3");
+        assert_eq!(
+            generate_move_test(&harness(true), &divergence),
+            "    #[test(s = @0x5), expected_failure(abort_code = 3)]\n    \
+             fun f_divergence(s: signer) {\n        M::f(&s, 7u64);\n    }\n"
+        );
+        // Functions which can't be called from a test module are noted instead.
+        assert_eq!(
+            generate_move_test(&harness(false), &divergence),
+            "    // `M::f` cannot be called with the arguments of its divergence from a test\n"
+        );
+
+        let report = FuzzReport {
+            results: vec![FuzzResult {
+                harness: harness(true),
+                executions: 1,
+                discarded: 0,
+                divergence: Some(divergence),
+            }],
+            skipped: vec![],
+        };
+        let tests = report.generate_move_tests();
+        assert_eq!(
+            tests.keys().collect::<Vec<_>>(),
+            vec![&"MFuzzTests".to_string()]
+        );
+        assert!(tests["MFuzzTests"]
+            .starts_with("#[test_only]\nmodule 0x2::MFuzzTests {\n    use 0x2::M;\n\n    #[test("));
+        assert_eq!(
+            report.to_string(),
+            "`M::f` diverges from its specification\n  arguments: s = @0x5, x = 7u64\n  \
+             outcome: aborted with code 3\n"
+        );
+    }
+}
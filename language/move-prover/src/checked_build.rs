@@ -0,0 +1,769 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checked builds of Move modules.
+//!
+//! A checked build is a version of the compiled modules in which specification conditions are
+//! turned into runtime checks: `requires` conditions are checked at function entry, `ensures`
+//! conditions before each return, and `assert` conditions of spec blocks where the block is
+//! placed in the code. A failing check aborts the transaction with a designated abort code.
+//! Checked builds are meant to be deployed on test networks, to catch violations of the specs
+//! by code paths which are not (yet) verified.
+//!
+//! Only a subset of the specification language is translated, for which the checks never abort
+//! by themselves:
+//!
+//! - boolean connectives, comparisons, and `if`/`let` expressions;
+//! - additions, multiplications, and divisions by non-zero constants of integers. Integers are
+//!   computed as `u128`, and a condition is only translated if its arithmetic cannot overflow,
+//!   which excludes subtractions;
+//! - parameters and results of primitive types, and `exists<R>(addr)` for non-generic resources
+//!   `R` of the same module;
+//! - `old(..)` in `ensures` conditions, evaluated at function entry.
+//!
+//! In `assert` conditions, only the parameters which are never moved by the function can be
+//! referred to. Other conditions are skipped and reported.
+
+use move_binary_format::{
+    access::ModuleAccess,
+    constant::Constant,
+    file_format::{
+        Bytecode, CodeOffset, CompiledModule, ConstantPoolIndex, FunctionDefinitionIndex,
+        LocalIndex, Signature, SignatureIndex, SignatureToken, StructDefinitionIndex,
+    },
+    file_format_common::{LOCAL_INDEX_MAX, TABLE_INDEX_MAX},
+};
+use move_core_types::value::{MoveTypeLayout, MoveValue};
+use move_model::{
+    ast::{Condition, ConditionKind, Exp, ExpData, Operation, Value},
+    big_uint_to_addr,
+    model::{FunctionEnv, GlobalEnv, Loc, ModuleEnv, NodeId},
+    symbol::Symbol,
+    ty::Type,
+};
+use num::{BigInt, ToPrimitive};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The default abort code of failing runtime checks. It is not a valid Diem error code, as its
+/// category (the lowest byte) is undefined.
+pub const DEFAULT_ABORT_CODE: u64 = u64::MAX;
+
+/// Options for checked builds.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CheckedBuildOptions {
+    /// Directory the checked modules are written to.
+    pub output_directory: String,
+    /// Abort code of failing runtime checks.
+    pub abort_code: u64,
+}
+
+impl Default for CheckedBuildOptions {
+    fn default() -> Self {
+        Self {
+            output_directory: "checked_build".to_string(),
+            abort_code: DEFAULT_ABORT_CODE,
+        }
+    }
+}
+
+/// A condition which could not be turned into a runtime check.
+#[derive(Debug, Clone)]
+pub struct SkippedCondition {
+    pub loc: Loc,
+    pub reason: String,
+}
+
+/// The result of a checked build.
+#[derive(Debug, Default)]
+pub struct CheckedBuild {
+    /// The checked modules, by module name.
+    pub modules: BTreeMap<String, CompiledModule>,
+    /// The number of conditions turned into runtime checks.
+    pub checks: usize,
+    pub skipped: Vec<SkippedCondition>,
+}
+
+/// Builds the checked versions of the target modules of `env`.
+pub fn build_checked_modules(env: &GlobalEnv, options: &CheckedBuildOptions) -> CheckedBuild {
+    let mut build = CheckedBuild::default();
+    for module_env in env.get_modules() {
+        if !module_env.is_target() || module_env.is_script_module() {
+            continue;
+        }
+        let mut module = module_env.get_verified_module().clone();
+        for func_env in module_env.get_functions() {
+            if func_env.is_native() {
+                continue;
+            }
+            let mut instrumenter = FunctionInstrumenter::new(&module_env, &mut module, &func_env);
+            instrumenter.instrument(options.abort_code, &mut build);
+        }
+        build
+            .modules
+            .insert(module_env.get_identifier().to_string(), module);
+    }
+    build
+}
+
+// =================================================================================================
+/// # Translation of Conditions
+
+/// The point of a function where a condition is checked.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CheckPoint {
+    Entry,
+    Exit,
+    Inline,
+}
+
+/// The kind of the value of a translated expression. Integers are represented as `u128`, along
+/// with the number of bits their value is guaranteed to fit in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Int(u64),
+    Address,
+}
+
+impl Kind {
+    fn token(self) -> SignatureToken {
+        match self {
+            Kind::Bool => SignatureToken::Bool,
+            Kind::Int(_) => SignatureToken::U128,
+            Kind::Address => SignatureToken::Address,
+        }
+    }
+}
+
+type TranslationResult<T> = Result<T, String>;
+
+/// A sequence of instructions whose branch offsets are relative to its start.
+#[derive(Default)]
+struct Snippet {
+    code: Vec<Bytecode>,
+}
+
+impl Snippet {
+    fn emit(&mut self, instr: Bytecode) {
+        self.code.push(instr);
+    }
+
+    fn len(&self) -> usize {
+        self.code.len()
+    }
+
+    /// Appends the instructions to `code`, relocating the branch offsets.
+    fn relocate_into(&self, code: &mut Vec<Bytecode>) -> TranslationResult<()> {
+        check_code_size(code.len() + self.code.len())?;
+        let base = code.len() as CodeOffset;
+        code.extend(self.code.iter().map(|instr| match instr {
+            Bytecode::Branch(offset) => Bytecode::Branch(offset + base),
+            Bytecode::BrTrue(offset) => Bytecode::BrTrue(offset + base),
+            Bytecode::BrFalse(offset) => Bytecode::BrFalse(offset + base),
+            _ => instr.clone(),
+        }));
+        Ok(())
+    }
+}
+
+struct FunctionInstrumenter<'a, 'env> {
+    module_env: &'a ModuleEnv<'env>,
+    module: &'a mut CompiledModule,
+    func_env: &'a FunctionEnv<'env>,
+    def_idx: FunctionDefinitionIndex,
+    params: Vec<SignatureToken>,
+    returns: Vec<SignatureToken>,
+    /// The types of the locals, including the parameters and the locals added by the checks.
+    locals: Vec<SignatureToken>,
+    /// The locals the results are stored in while checking the `ensures` conditions.
+    result_locals: Vec<LocalIndex>,
+    /// The instructions executed at function entry: the checks of `requires` conditions, and the
+    /// evaluation of the values `ensures` conditions need from the pre-state.
+    prologue: Snippet,
+    /// The checks executed before returning.
+    epilogue: Snippet,
+    /// The checks executed before the instruction at the given offset.
+    inline: BTreeMap<CodeOffset, Snippet>,
+    /// The values of the variables introduced by `let` expressions.
+    vars: BTreeMap<Symbol, (LocalIndex, Kind)>,
+}
+
+impl<'a, 'env> FunctionInstrumenter<'a, 'env> {
+    fn new(
+        module_env: &'a ModuleEnv<'env>,
+        module: &'a mut CompiledModule,
+        func_env: &'a FunctionEnv<'env>,
+    ) -> Self {
+        let def_idx = func_env.get_def_idx();
+        let handle = module.function_handle_at(module.function_def_at(def_idx).function);
+        let params = module.signature_at(handle.parameters).0.clone();
+        let returns = module.signature_at(handle.return_).0.clone();
+        let code = module.function_def_at(def_idx).code.as_ref().unwrap();
+        let mut locals = params.clone();
+        locals.extend(module.signature_at(code.locals).0.iter().cloned());
+        FunctionInstrumenter {
+            module_env,
+            module,
+            func_env,
+            def_idx,
+            params,
+            returns,
+            locals,
+            result_locals: vec![],
+            prologue: Snippet::default(),
+            epilogue: Snippet::default(),
+            inline: BTreeMap::new(),
+            vars: BTreeMap::new(),
+        }
+    }
+
+    fn instrument(&mut self, abort_code: u64, build: &mut CheckedBuild) {
+        let func_env = self.func_env;
+        let spec = func_env.get_spec();
+        let mut conditions = vec![];
+        for cond in spec.filter_kind(ConditionKind::Requires) {
+            conditions.push((CheckPoint::Entry, 0, cond));
+        }
+        if spec.filter_kind(ConditionKind::Ensures).next().is_some() {
+            match self.new_result_locals() {
+                Ok(()) => {
+                    for cond in spec.filter_kind(ConditionKind::Ensures) {
+                        conditions.push((CheckPoint::Exit, 0, cond));
+                    }
+                }
+                Err(reason) => {
+                    for cond in spec.filter_kind(ConditionKind::Ensures) {
+                        build.skipped.push(SkippedCondition {
+                            loc: cond.loc.clone(),
+                            reason: reason.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for (offset, block_spec) in &spec.on_impl {
+            for cond in block_spec.filter_kind(ConditionKind::Assert) {
+                conditions.push((CheckPoint::Inline, *offset, cond));
+            }
+        }
+
+        let mut checks = 0;
+        for (point, offset, cond) in conditions {
+            match self.translate_condition(point, offset, cond, abort_code) {
+                Ok(()) => checks += 1,
+                Err(reason) => build.skipped.push(SkippedCondition {
+                    loc: cond.loc.clone(),
+                    reason,
+                }),
+            }
+        }
+        if checks == 0 {
+            return;
+        }
+        match self.rewrite_code() {
+            Ok(()) => build.checks += checks,
+            Err(reason) => build.skipped.push(SkippedCondition {
+                loc: self.func_env.get_loc(),
+                reason,
+            }),
+        }
+    }
+
+    /// Translates a condition into a check, leaving the instructions untouched if the condition
+    /// cannot be translated.
+    fn translate_condition(
+        &mut self,
+        point: CheckPoint,
+        offset: CodeOffset,
+        cond: &Condition,
+        abort_code: u64,
+    ) -> TranslationResult<()> {
+        let locals_count = self.locals.len();
+        let prologue_len = self.prologue.len();
+        let epilogue_len = self.epilogue.len();
+        let inline_len = self.inline.get(&offset).map_or(0, Snippet::len);
+
+        let mut snippet = match point {
+            CheckPoint::Entry => std::mem::take(&mut self.prologue),
+            CheckPoint::Exit => std::mem::take(&mut self.epilogue),
+            CheckPoint::Inline => self.inline.remove(&offset).unwrap_or_default(),
+        };
+        let result = self.translate_check(&mut snippet, point, &cond.exp, abort_code);
+        match point {
+            CheckPoint::Entry => self.prologue = snippet,
+            CheckPoint::Exit => self.epilogue = snippet,
+            CheckPoint::Inline => {
+                self.inline.insert(offset, snippet);
+            }
+        }
+
+        if result.is_err() {
+            self.locals.truncate(locals_count);
+            self.prologue.code.truncate(prologue_len);
+            self.epilogue.code.truncate(epilogue_len);
+            if let Some(snippet) = self.inline.get_mut(&offset) {
+                snippet.code.truncate(inline_len);
+            }
+        }
+        self.vars.clear();
+        result
+    }
+
+    fn translate_check(
+        &mut self,
+        snippet: &mut Snippet,
+        point: CheckPoint,
+        exp: &Exp,
+        abort_code: u64,
+    ) -> TranslationResult<()> {
+        let (local, kind) = self.translate_exp(snippet, point, exp)?;
+        if kind != Kind::Bool {
+            return Err("the condition is not a boolean".to_string());
+        }
+        snippet.emit(Bytecode::CopyLoc(local));
+        snippet.emit(Bytecode::BrTrue(snippet.len() as CodeOffset + 3));
+        snippet.emit(Bytecode::LdU64(abort_code));
+        snippet.emit(Bytecode::Abort);
+        Ok(())
+    }
+
+    /// Emits the instructions computing the value of `exp` into a new local, and returns the
+    /// local.
+    fn translate_exp(
+        &mut self,
+        snippet: &mut Snippet,
+        point: CheckPoint,
+        exp: &Exp,
+    ) -> TranslationResult<(LocalIndex, Kind)> {
+        match exp.as_ref() {
+            ExpData::Value(_, val) => self.translate_value(snippet, val),
+            ExpData::Temporary(_, idx) => self.translate_temporary(snippet, point, *idx),
+            ExpData::LocalVar(_, name) => match self.vars.get(name) {
+                Some(var) => Ok(*var),
+                None => Err("unbound variable".to_string()),
+            },
+            ExpData::Block(_, decls, body) => {
+                for decl in decls {
+                    let binding = match &decl.binding {
+                        Some(binding) => binding,
+                        None => return Err("unbound variable".to_string()),
+                    };
+                    let var = self.translate_exp(snippet, point, binding)?;
+                    self.vars.insert(decl.name, var);
+                }
+                self.translate_exp(snippet, point, body)
+            }
+            ExpData::IfElse(_, cond, if_true, if_false) => {
+                let (cond, cond_kind) = self.translate_exp(snippet, point, cond)?;
+                if cond_kind != Kind::Bool {
+                    return Err("the condition is not a boolean".to_string());
+                }
+                snippet.emit(Bytecode::CopyLoc(cond));
+                let branch_idx = snippet.len();
+                snippet.emit(Bytecode::BrFalse(0));
+                let (val, kind) = self.translate_exp(snippet, point, if_true)?;
+                let result = self.new_local(kind.token())?;
+                snippet.emit(Bytecode::CopyLoc(val));
+                snippet.emit(Bytecode::StLoc(result));
+                let jump_idx = snippet.len();
+                snippet.emit(Bytecode::Branch(0));
+                snippet.code[branch_idx] = Bytecode::BrFalse(snippet.len() as CodeOffset);
+                let (val, other_kind) = self.translate_exp(snippet, point, if_false)?;
+                let kind = match (kind, other_kind) {
+                    (Kind::Int(bits), Kind::Int(other_bits)) => Kind::Int(bits.max(other_bits)),
+                    (kind, other_kind) if kind == other_kind => kind,
+                    _ => return Err("the branches have different types".to_string()),
+                };
+                snippet.emit(Bytecode::CopyLoc(val));
+                snippet.emit(Bytecode::StLoc(result));
+                snippet.code[jump_idx] = Bytecode::Branch(snippet.len() as CodeOffset);
+                Ok((result, kind))
+            }
+            ExpData::Call(id, oper, args) => self.translate_call(snippet, point, *id, oper, args),
+            _ => Err("the expression is not supported".to_string()),
+        }
+    }
+
+    fn translate_value(
+        &mut self,
+        snippet: &mut Snippet,
+        val: &Value,
+    ) -> TranslationResult<(LocalIndex, Kind)> {
+        let kind = match val {
+            Value::Bool(b) => {
+                snippet.emit(if *b {
+                    Bytecode::LdTrue
+                } else {
+                    Bytecode::LdFalse
+                });
+                Kind::Bool
+            }
+            Value::Number(n) => return self.translate_int(snippet, n),
+            Value::Address(addr) => {
+                let constant = Constant::serialize_constant(
+                    &MoveTypeLayout::Address,
+                    &MoveValue::Address(big_uint_to_addr(addr)),
+                )
+                .ok_or_else(|| "invalid address".to_string())?;
+                snippet.emit(Bytecode::LdConst(self.add_constant(constant)?));
+                Kind::Address
+            }
+            Value::ByteArray(_) => return Err("byte arrays are not supported".to_string()),
+        };
+        let local = self.new_local(kind.token())?;
+        snippet.emit(Bytecode::StLoc(local));
+        Ok((local, kind))
+    }
+
+    fn translate_int(
+        &mut self,
+        snippet: &mut Snippet,
+        n: &BigInt,
+    ) -> TranslationResult<(LocalIndex, Kind)> {
+        let val = n
+            .to_u128()
+            .ok_or_else(|| "the number is not a u128".to_string())?;
+        snippet.emit(Bytecode::LdU128(val));
+        let local = self.new_local(SignatureToken::U128)?;
+        snippet.emit(Bytecode::StLoc(local));
+        Ok((local, Kind::Int(n.bits().max(1))))
+    }
+
+    fn translate_temporary(
+        &mut self,
+        snippet: &mut Snippet,
+        point: CheckPoint,
+        idx: usize,
+    ) -> TranslationResult<(LocalIndex, Kind)> {
+        let ty = match self.params.get(idx) {
+            Some(ty) => ty.clone(),
+            None => return Err("only parameters can be referred to".to_string()),
+        };
+        match point {
+            CheckPoint::Entry => (),
+            CheckPoint::Exit => {
+                // Parameters refer to their value at function entry, except for mutable
+                // references.
+                if matches!(ty, SignatureToken::MutableReference(_)) {
+                    return Err("mutable references are only supported in `old(..)`".to_string());
+                }
+                let mut prologue = std::mem::take(&mut self.prologue);
+                let result = self.translate_temporary(&mut prologue, CheckPoint::Entry, idx);
+                self.prologue = prologue;
+                return result;
+            }
+            CheckPoint::Inline => {
+                if matches!(ty, SignatureToken::MutableReference(_)) {
+                    return Err("mutable references are not supported in `assert`".to_string());
+                }
+                let moved = self.code().iter().any(|instr| match instr {
+                    Bytecode::MoveLoc(local) => *local as usize == idx,
+                    _ => false,
+                });
+                if moved {
+                    return Err("the parameter is moved by the function".to_string());
+                }
+            }
+        }
+        let (kind, is_reference) = match &ty {
+            SignatureToken::Reference(ty) | SignatureToken::MutableReference(ty) => {
+                (primitive_kind(ty)?, true)
+            }
+            ty => (primitive_kind(ty)?, false),
+        };
+        snippet.emit(Bytecode::CopyLoc(idx as LocalIndex));
+        if is_reference {
+            snippet.emit(Bytecode::ReadRef);
+        }
+        if matches!(kind, Kind::Int(_)) {
+            snippet.emit(Bytecode::CastU128);
+        }
+        let local = self.new_local(kind.token())?;
+        snippet.emit(Bytecode::StLoc(local));
+        Ok((local, kind))
+    }
+
+    fn translate_call(
+        &mut self,
+        snippet: &mut Snippet,
+        point: CheckPoint,
+        id: NodeId,
+        oper: &Operation,
+        args: &[Exp],
+    ) -> TranslationResult<(LocalIndex, Kind)> {
+        use Kind::*;
+        match oper {
+            Operation::Old => {
+                if point != CheckPoint::Exit {
+                    return Err("`old(..)` is only supported in `ensures`".to_string());
+                }
+                let mut prologue = std::mem::take(&mut self.prologue);
+                let result = self.translate_exp(&mut prologue, CheckPoint::Entry, &args[0]);
+                self.prologue = prologue;
+                return result;
+            }
+            Operation::Result(idx) => {
+                if point != CheckPoint::Exit {
+                    return Err("results are only supported in `ensures`".to_string());
+                }
+                let kind = primitive_kind(&self.returns[*idx])?;
+                snippet.emit(Bytecode::CopyLoc(self.result_locals[*idx]));
+                if matches!(kind, Int(_)) {
+                    snippet.emit(Bytecode::CastU128);
+                }
+                let local = self.new_local(kind.token())?;
+                snippet.emit(Bytecode::StLoc(local));
+                return Ok((local, kind));
+            }
+            Operation::MaxU8 => return self.translate_int(snippet, &BigInt::from(u8::MAX)),
+            Operation::MaxU64 => return self.translate_int(snippet, &BigInt::from(u64::MAX)),
+            Operation::MaxU128 => return self.translate_int(snippet, &BigInt::from(u128::MAX)),
+            Operation::Trace => return self.translate_exp(snippet, point, &args[0]),
+            Operation::Exists(None) => {
+                return self.translate_exists(snippet, point, id, &args[0]);
+            }
+            _ => (),
+        }
+
+        let mut vals = vec![];
+        for arg in args {
+            vals.push(self.translate_exp(snippet, point, arg)?);
+        }
+        // The instructions applying the operation to the values of the arguments
+        let apply = |instr: Bytecode| {
+            let mut instrs = vals
+                .iter()
+                .map(|(local, _)| Bytecode::CopyLoc(*local))
+                .collect::<Vec<_>>();
+            instrs.push(instr);
+            instrs
+        };
+        let (instrs, kind) = match (oper, vals.as_slice()) {
+            (Operation::Not, [(_, Bool)]) => (apply(Bytecode::Not), Bool),
+            (Operation::And, [(_, Bool), (_, Bool)]) => (apply(Bytecode::And), Bool),
+            (Operation::Or, [(_, Bool), (_, Bool)]) => (apply(Bytecode::Or), Bool),
+            (Operation::Implies, [(lhs, Bool), (rhs, Bool)]) => {
+                let instrs = vec![
+                    Bytecode::CopyLoc(*lhs),
+                    Bytecode::Not,
+                    Bytecode::CopyLoc(*rhs),
+                    Bytecode::Or,
+                ];
+                (instrs, Bool)
+            }
+            (Operation::Iff, [(_, Bool), (_, Bool)]) => (apply(Bytecode::Eq), Bool),
+            (Operation::Eq, [(_, lhs), (_, rhs)]) if same_kind(*lhs, *rhs) => {
+                (apply(Bytecode::Eq), Bool)
+            }
+            (Operation::Neq, [(_, lhs), (_, rhs)]) if same_kind(*lhs, *rhs) => {
+                (apply(Bytecode::Neq), Bool)
+            }
+            (Operation::Lt, [(_, Int(_)), (_, Int(_))]) => (apply(Bytecode::Lt), Bool),
+            (Operation::Le, [(_, Int(_)), (_, Int(_))]) => (apply(Bytecode::Le), Bool),
+            (Operation::Gt, [(_, Int(_)), (_, Int(_))]) => (apply(Bytecode::Gt), Bool),
+            (Operation::Ge, [(_, Int(_)), (_, Int(_))]) => (apply(Bytecode::Ge), Bool),
+            (Operation::Add, [(_, Int(lhs)), (_, Int(rhs))]) => {
+                (apply(Bytecode::Add), int_kind(*lhs.max(rhs) + 1)?)
+            }
+            (Operation::Mul, [(_, Int(lhs)), (_, Int(rhs))]) => {
+                (apply(Bytecode::Mul), int_kind(lhs + rhs)?)
+            }
+            (Operation::Div, [(_, Int(lhs)), (_, Int(_))]) if is_non_zero_number(&args[1]) => {
+                (apply(Bytecode::Div), Int(*lhs))
+            }
+            (Operation::Mod, [(_, Int(_)), (_, Int(rhs))]) if is_non_zero_number(&args[1]) => {
+                (apply(Bytecode::Mod), Int(*rhs))
+            }
+            _ => return Err(format!("the operation {:?} is not supported", oper)),
+        };
+        for instr in instrs {
+            snippet.emit(instr);
+        }
+        let local = self.new_local(kind.token())?;
+        snippet.emit(Bytecode::StLoc(local));
+        Ok((local, kind))
+    }
+
+    fn translate_exists(
+        &mut self,
+        snippet: &mut Snippet,
+        point: CheckPoint,
+        id: NodeId,
+        addr: &Exp,
+    ) -> TranslationResult<(LocalIndex, Kind)> {
+        let struct_id = match self.module_env.env.get_node_instantiation(id).first() {
+            Some(Type::Struct(module_id, struct_id, inst))
+                if *module_id == self.module_env.get_id() && inst.is_empty() =>
+            {
+                *struct_id
+            }
+            _ => {
+                return Err(
+                    "`exists` is only supported for non-generic resources of the same module"
+                        .to_string(),
+                )
+            }
+        };
+        // Ghost resources, which back spec variables, are not declared in the bytecode
+        let struct_env = self.module_env.get_struct(struct_id);
+        let name = struct_env.get_identifier();
+        let def_idx = self
+            .module
+            .struct_defs()
+            .iter()
+            .position(|def| {
+                let handle = self.module.struct_handle_at(def.struct_handle);
+                Some(self.module.identifier_at(handle.name)) == name.as_deref()
+            })
+            .ok_or_else(|| "`exists` is not supported for ghost resources".to_string())?;
+
+        let (addr, kind) = self.translate_exp(snippet, point, addr)?;
+        if kind != Kind::Address {
+            return Err("expected an address".to_string());
+        }
+        snippet.emit(Bytecode::CopyLoc(addr));
+        snippet.emit(Bytecode::Exists(StructDefinitionIndex(def_idx as u16)));
+        let local = self.new_local(SignatureToken::Bool)?;
+        snippet.emit(Bytecode::StLoc(local));
+        Ok((local, Kind::Bool))
+    }
+
+    // Helpers
+    // -------
+
+    fn code(&self) -> &[Bytecode] {
+        &self
+            .module
+            .function_def_at(self.def_idx)
+            .code
+            .as_ref()
+            .unwrap()
+            .code
+    }
+
+    fn new_local(&mut self, token: SignatureToken) -> TranslationResult<LocalIndex> {
+        if self.locals.len() as u64 > LOCAL_INDEX_MAX {
+            return Err("too many locals".to_string());
+        }
+        self.locals.push(token);
+        Ok((self.locals.len() - 1) as LocalIndex)
+    }
+
+    fn new_result_locals(&mut self) -> TranslationResult<()> {
+        if self.returns.iter().any(SignatureToken::is_reference) {
+            return Err("the function returns references".to_string());
+        }
+        for ty in self.returns.clone() {
+            let local = self.new_local(ty)?;
+            self.result_locals.push(local);
+        }
+        Ok(())
+    }
+
+    fn add_constant(&mut self, constant: Constant) -> TranslationResult<ConstantPoolIndex> {
+        let pool = &mut self.module.constant_pool;
+        let idx = match pool.iter().position(|c| *c == constant) {
+            Some(idx) => idx,
+            None if (pool.len() as u64) < TABLE_INDEX_MAX => {
+                pool.push(constant);
+                pool.len() - 1
+            }
+            None => return Err("too many constants".to_string()),
+        };
+        Ok(ConstantPoolIndex(idx as u16))
+    }
+
+    /// Rewrites the code of the function to run the checks, and adds the locals of the checks.
+    fn rewrite_code(&mut self) -> TranslationResult<()> {
+        let old_code = self.code().to_vec();
+        let mut code = vec![];
+        self.prologue.relocate_into(&mut code)?;
+        // The new offsets of the original instructions, and of the checks preceding them
+        let mut labels = vec![];
+        let mut positions = vec![];
+        for (offset, instr) in old_code.iter().enumerate() {
+            labels.push(code.len() as CodeOffset);
+            if let Some(snippet) = self.inline.get(&(offset as CodeOffset)) {
+                snippet.relocate_into(&mut code)?;
+            }
+            if matches!(instr, Bytecode::Ret) && !self.epilogue.code.is_empty() {
+                for local in self.result_locals.iter().rev() {
+                    code.push(Bytecode::StLoc(*local));
+                }
+                self.epilogue.relocate_into(&mut code)?;
+                for local in &self.result_locals {
+                    code.push(Bytecode::MoveLoc(*local));
+                }
+            }
+            positions.push(code.len());
+            code.push(instr.clone());
+            check_code_size(code.len())?;
+        }
+        for pos in positions {
+            match &mut code[pos] {
+                Bytecode::Branch(offset) | Bytecode::BrTrue(offset) | Bytecode::BrFalse(offset) => {
+                    *offset = labels[*offset as usize]
+                }
+                _ => (),
+            }
+        }
+
+        let locals = Signature(self.locals[self.params.len()..].to_vec());
+        let signatures = &mut self.module.signatures;
+        let locals_idx = match signatures.iter().position(|sig| *sig == locals) {
+            Some(idx) => idx,
+            None if (signatures.len() as u64) < TABLE_INDEX_MAX => {
+                signatures.push(locals);
+                signatures.len() - 1
+            }
+            None => return Err("too many signatures".to_string()),
+        };
+        let code_unit = self.module.function_defs[self.def_idx.0 as usize]
+            .code
+            .as_mut()
+            .unwrap();
+        code_unit.locals = SignatureIndex(locals_idx as u16);
+        code_unit.code = code;
+        Ok(())
+    }
+}
+
+fn check_code_size(len: usize) -> TranslationResult<()> {
+    if len <= CodeOffset::MAX as usize {
+        Ok(())
+    } else {
+        Err("the code of the function is too large".to_string())
+    }
+}
+
+fn primitive_kind(ty: &SignatureToken) -> TranslationResult<Kind> {
+    match ty {
+        SignatureToken::Bool => Ok(Kind::Bool),
+        SignatureToken::U8 => Ok(Kind::Int(8)),
+        SignatureToken::U64 => Ok(Kind::Int(64)),
+        SignatureToken::U128 => Ok(Kind::Int(128)),
+        SignatureToken::Address => Ok(Kind::Address),
+        _ => Err("only values of primitive types are supported".to_string()),
+    }
+}
+
+fn int_kind(bits: u64) -> TranslationResult<Kind> {
+    if bits <= 128 {
+        Ok(Kind::Int(bits))
+    } else {
+        Err("the arithmetic may overflow".to_string())
+    }
+}
+
+fn same_kind(lhs: Kind, rhs: Kind) -> bool {
+    matches!(
+        (lhs, rhs),
+        (Kind::Bool, Kind::Bool) | (Kind::Int(_), Kind::Int(_)) | (Kind::Address, Kind::Address)
+    )
+}
+
+fn is_non_zero_number(exp: &Exp) -> bool {
+    matches!(exp.as_ref(), ExpData::Value(_, Value::Number(n)) if n.bits() > 0)
+}
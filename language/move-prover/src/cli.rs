@@ -7,6 +7,7 @@
 
 use std::{
     collections::BTreeMap,
+    path::Path,
     sync::atomic::{AtomicBool, Ordering},
 };
 
@@ -14,6 +15,7 @@ use anyhow::anyhow;
 use clap::{App, Arg};
 use log::LevelFilter;
 use move_lang::shared::AddressBytes;
+use move_package::source_package::manifest_parser::parse_move_manifest_from_file;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use simplelog::{
@@ -22,12 +24,18 @@ use simplelog::{
 
 use abigen::AbigenOptions;
 use boogie_backend::options::{BoogieOptions, VectorTheory};
-use bytecode::options::{AutoTraceLevel, ProverOptions};
+use bytecode::{
+    invariant_explanation::InvariantQuery,
+    options::{AutoTraceLevel, ProverOptions},
+};
+use bytecode_interpreter::fuzz::FuzzOptions;
 use codespan_reporting::diagnostic::Severity;
 use docgen::DocgenOptions;
 use errmapgen::ErrmapOptions;
 use move_model::{model::VerificationScope, options::ModelBuilderOptions};
 
+use crate::{checked_build::CheckedBuildOptions, verification_cache::DEFAULT_CACHE_DIR};
+
 /// Atomic used to prevent re-initialization of logging.
 static LOGGER_CONFIGURED: AtomicBool = AtomicBool::new(false);
 
@@ -55,6 +63,11 @@ pub struct Options {
     pub run_errmapgen: bool,
     /// Whether to run the read write set analysis instead of the prover
     pub run_read_write_set: bool,
+    /// Whether to fuzz the functions against their specification instead of running the prover.
+    pub run_fuzz: bool,
+    /// Whether to build the modules with runtime checks of their specification instead of
+    /// running the prover.
+    pub run_checked_build: bool,
     /// The paths to the Move sources.
     pub move_sources: Vec<String>,
     /// The paths to any dependencies for the Move sources. Those will not be verified but
@@ -62,6 +75,14 @@ pub struct Options {
     pub move_deps: Vec<String>,
     /// The values assigned to named addresses in the Move code being verified.
     pub move_named_address_values: Vec<String>,
+    /// The path to a Move package to verify, instead of `move_sources`. The sources of the
+    /// package's dependencies are used as dependencies, and the named addresses are the ones
+    /// assigned by the packages.
+    pub move_package: Option<String>,
+    /// The paths to the Move sources of the currently deployed version of `move_sources`, if
+    /// verifying an upgrade. The global invariants of this version are verified in addition to
+    /// those of the new version.
+    pub upgrade_from: Vec<String>,
     /// Whether to run experimental pipeline
     pub experimental_pipeline: bool,
     /// BEGIN OF STRUCTURED OPTIONS
@@ -75,6 +96,13 @@ pub struct Options {
     pub backend: BoogieOptions,
     /// Options for the ABI generator.
     pub abigen: AbigenOptions,
+    /// Options for spec-driven fuzzing.
+    pub fuzz: FuzzOptions,
+    /// Options for checked builds.
+    pub checked_build: CheckedBuildOptions,
+    /// The global invariant and function for which to explain, instead of running the prover,
+    /// why the invariant is asserted in the function.
+    pub why_invariant: Option<InvariantQuery>,
     /// Options for the error map generator.
     /// TODO: this currently create errors during deserialization, so skip them for this.
     #[serde(skip_serializing)]
@@ -89,6 +117,8 @@ impl Default for Options {
             run_abigen: false,
             run_errmapgen: false,
             run_read_write_set: false,
+            run_fuzz: false,
+            run_checked_build: false,
             verbosity_level: LevelFilter::Info,
             move_sources: vec![],
             move_deps: vec![],
@@ -101,11 +131,16 @@ impl Default for Options {
                 "TreasuryCompliance=0xB1E55ED".into(),
                 "VMReserved=0x0".into(),
             ],
+            move_package: None,
+            upgrade_from: vec![],
             model_builder: ModelBuilderOptions::default(),
             prover: ProverOptions::default(),
             backend: BoogieOptions::default(),
             docgen: DocgenOptions::default(),
             abigen: AbigenOptions::default(),
+            fuzz: FuzzOptions::default(),
+            checked_build: CheckedBuildOptions::default(),
+            why_invariant: None,
             errmapgen: ErrmapOptions::default(),
             experimental_pipeline: false,
         }
@@ -125,6 +160,23 @@ impl Options {
         Self::create_from_toml(&std::fs::read_to_string(toml_file)?)
     }
 
+    /// Applies the timeouts and backend flags of the `[prover]` section of the manifest of the
+    /// Move package at `package_path`, if any. The pragma defaults and excluded functions of the
+    /// section are applied by the model of the package.
+    pub fn apply_package_manifest(&mut self, package_path: &Path) -> anyhow::Result<()> {
+        let manifest = parse_move_manifest_from_file(package_path)?;
+        if let Some(prover) = manifest.prover {
+            if let Some(vc_timeout) = prover.vc_timeout {
+                self.backend.vc_timeout = vc_timeout as usize;
+            }
+            if let Some(hard_timeout) = prover.hard_timeout {
+                self.backend.hard_timeout_secs = hard_timeout;
+            }
+            self.backend.boogie_flags.extend(prover.backend_flags);
+        }
+        Ok(())
+    }
+
     // Creates options from command line arguments. This parses the arguments and terminates
     // the program on errors, printing usage information. The first argument is expected to be
     // the program name.
@@ -204,6 +256,54 @@ impl Options {
                     .possible_values(&["bug", "error", "warn", "note"])
                     .help("The minimall level on which diagnostics are reported")
             )
+            .arg(
+                Arg::with_name("diagnostics-json")
+                    .long("diagnostics-json")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("writes the diagnostics of the bytecode pipeline stages to the given \
+                     file in JSON. Per-stage severity filters can be configured with \
+                     `-C=prover.stage_severity.<stage>=\"<Severity>\"`")
+            )
+            .arg(
+                Arg::with_name("assume-unverified")
+                    .long("assume-unverified")
+                    .help("trusts functions which are not verified to satisfy their `ensures` and \
+                     `aborts_if` conditions, and uses those instead of inlining the functions \
+                     into verified callers")
+            )
+            .arg(
+                Arg::with_name("assumptions-ledger")
+                    .long("assumptions-ledger")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("writes the unverified contracts the verified functions depend on to \
+                     the given file in JSON")
+            )
+            .arg(
+                Arg::with_name("script-usage")
+                    .long("script-usage")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("writes the resources which transaction scripts and script functions \
+                     may access and modify to the given file in JSON")
+            )
+            .arg(
+                Arg::with_name("inline-threshold")
+                    .long("inline-threshold")
+                    .takes_value(true)
+                    .value_name("NUMBER")
+                    .validator(is_number)
+                    .help("inlines non-opaque callees with at most the given number of bytecode \
+                     instructions into their callers instead of using their specs (default 0: \
+                     only callees with `pragma inline = true`)")
+            )
+            .arg(
+                Arg::with_name("aggressive-dse")
+                    .long("aggressive-dse")
+                    .help("also eliminates dead loads of constants and dead results of pure \
+                     operations, which are not referenced by specs")
+            )
             .arg(
                 Arg::with_name("trace")
                     .long("trace")
@@ -249,6 +349,13 @@ impl Options {
                      NOTE: multiple cores may currently lead to scrambled model \
                      output from boogie (default 4)")
             )
+            .arg(
+                Arg::with_name("modular")
+                    .long("modular")
+                    .help("verifies each function in a Boogie run of its own, running as many \
+                    runs in parallel as there are cores, and reports the result of each function \
+                    as soon as it is available")
+            )
             .arg(
                 Arg::with_name("timeout")
                     .long("timeout")
@@ -306,6 +413,69 @@ impl Options {
                     .long("read-write-set")
                     .help("runs the read/write set analysis instead of the prover.")
             )
+            .arg(
+                Arg::with_name("fuzz")
+                    .long("fuzz")
+                    .help("executes the functions with `aborts_if` or `ensures` conditions on \
+                    random arguments instead of running the prover, and reports the arguments \
+                    on which a function does not satisfy its specification")
+            )
+            .arg(
+                Arg::with_name("fuzz-iterations")
+                    .long("fuzz-iterations")
+                    .takes_value(true)
+                    .value_name("NUMBER")
+                    .validator(is_number)
+                    .help("number of executions of each function when fuzzing")
+            )
+            .arg(
+                Arg::with_name("fuzz-seed")
+                    .long("fuzz-seed")
+                    .takes_value(true)
+                    .value_name("NUMBER")
+                    .validator(is_number)
+                    .help("seed for the generation of the random arguments when fuzzing")
+            )
+            .arg(
+                Arg::with_name("fuzz-harness-dir")
+                    .long("fuzz-harness-dir")
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .help("writes Move unit tests reproducing the divergences found by fuzzing \
+                    into this directory")
+            )
+            .arg(
+                Arg::with_name("checked-build")
+                    .long("checked-build")
+                    .help("builds the modules with runtime checks of their `requires`, `ensures`, \
+                    and `assert` conditions instead of running the prover. The checked modules \
+                    will be written into the directory `./checked_build` unless configured \
+                    otherwise")
+            )
+            .arg(
+                Arg::with_name("checked-build-dir")
+                    .long("checked-build-dir")
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .help("directory the checked modules are written to")
+            )
+            .arg(
+                Arg::with_name("checked-build-abort-code")
+                    .long("checked-build-abort-code")
+                    .takes_value(true)
+                    .value_name("NUMBER")
+                    .validator(is_number)
+                    .help("abort code of failing runtime checks in checked modules")
+            )
+            .arg(
+                Arg::with_name("why-invariant")
+                    .long("why-invariant")
+                    .number_of_values(2)
+                    .value_names(&["ID", "FUNCTION"])
+                    .help("explains which memory accesses in the function cause the global \
+                    invariant with the given id to be asserted, instead of running the prover. \
+                    The function is given as `Module::function` or `0x1::Module::function`")
+            )
             .arg(
                 Arg::with_name("verify")
                     .long("verify")
@@ -315,6 +485,16 @@ impl Options {
                     .help("default scope of verification \
                     (can be overridden by `pragma verify=true|false`)"),
             )
+            .arg(
+                Arg::with_name("verify-memory")
+                    .long("verify-memory")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .value_name("STRUCT")
+                    .help("only verifies the functions which modify the given memory, e.g. \
+                    `0x1::DiemAccount::Balance`, and the global invariants over it. Can be repeated.")
+            )
             .arg(
                 Arg::with_name("bench-repeat")
                     .long("bench-repeat")
@@ -387,6 +567,18 @@ impl Options {
                     .help("path to a Move file, or a directory which will be searched for \
                     Move files, containing dependencies which will not be verified")
             )
+            .arg(
+                Arg::with_name("upgrade-from")
+                    .long("upgrade-from")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .takes_value(true)
+                    .value_name("PATH_TO_OLD_SOURCE")
+                    .help("path to a Move file, or a directory which will be searched for \
+                    Move files, containing the currently deployed version of the sources; \
+                    verifies that the sources also preserve the global invariants of this \
+                    version")
+            )
             .arg(
                 Arg::with_name("named-addresses")
                 .long("named-addresses")
@@ -395,6 +587,16 @@ impl Options {
                 .takes_value(true)
                 .help("specifies the value(s) of named addresses used in Move files")
             )
+            .arg(
+                Arg::with_name("package")
+                    .long("package")
+                    .takes_value(true)
+                    .value_name("PATH_TO_PACKAGE")
+                    .conflicts_with_all(&["sources", "dependencies", "named-addresses"])
+                    .help("path to the root directory of a Move package to verify; the \
+                    dependencies of the package are not verified, and the settings of the \
+                    `[prover]` section of its manifest apply")
+            )
             .arg(
                 Arg::with_name("sources")
                     .multiple(true)
@@ -423,12 +625,57 @@ impl Options {
                     .long("dump-bytecode")
                     .help("whether to dump the transformed bytecode to a file")
             )
+            .arg(
+                Arg::with_name("dump-bytecode-diff")
+                    .long("dump-bytecode-diff")
+                    .conflicts_with("dump-bytecode")
+                    .help("whether to dump unified diffs of each function's bytecode between \
+                     consecutive pipeline stages to files, instead of the full bytecode")
+            )
+            .arg(
+                Arg::with_name("dump-bytecode-with-source")
+                    .long("dump-bytecode-with-source")
+                    .takes_value(true)
+                    .value_name("STAGE")
+                    .conflicts_with_all(&["dump-bytecode", "dump-bytecode-diff"])
+                    .help("dumps the bytecode after the given pipeline stage to a file, \
+                     interleaved with the source lines and the injected spec conditions it stems \
+                     from (`stackless` for the bytecode before the pipeline)")
+            )
             .arg(
                 Arg::with_name("dump-cfg")
                     .long("dump-cfg")
                     .requires("dump-bytecode")
                     .help("whether to dump the per-function control-flow graphs (in dot format) to files")
             )
+            .arg(
+                Arg::with_name("dump-pipeline-graph")
+                    .long("dump-pipeline-graph")
+                    .help("whether to dump a graph (in dot format) of the bytecode pipeline stages, \
+                     the function variants they produce, and their dependencies via annotations")
+            )
+            .arg(
+                Arg::with_name("summary-cache")
+                    .long("summary-cache")
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .help("persists analysis summaries in the given directory and reuses them \
+                     in later runs for functions which did not change")
+            )
+            .arg(
+                Arg::with_name("cache")
+                    .long("cache")
+                    .help("caches verification results in `.move-prover-cache` and skips \
+                     verification of functions whose code, specs, and options did not change \
+                     since they were last verified")
+            )
+            .arg(
+                Arg::with_name("cache-dir")
+                    .long("cache-dir")
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .help("like --cache, but caches verification results in the given directory")
+            )
             .arg(
                 Arg::with_name("num-instances")
                     .long("num-instances")
@@ -454,6 +701,33 @@ impl Options {
                     .long("use-cvc4")
                     .help("uses cvc4 solver instead of z3")
             )
+            .arg(
+                Arg::with_name("smtlib")
+                    .long("smtlib")
+                    .help("verifies functions in the subset supported by the SMT-LIB backend by \
+                     racing z3 and cvc5 directly on SMT-LIB output, falling back to Boogie for \
+                     the remaining functions")
+            )
+            .arg(
+                Arg::with_name("solver-pool")
+                    .long("solver-pool")
+                    .takes_value(true)
+                    .value_name("NUMBER")
+                    .validator(is_number)
+                    .requires("smtlib")
+                    .help("with --smtlib, streams verification units to the given number of \
+                     warm processes per solver instead of starting a process per unit")
+            )
+            .arg(
+                Arg::with_name("solver-memory-limit")
+                    .long("solver-memory-limit")
+                    .takes_value(true)
+                    .value_name("MEGABYTES")
+                    .validator(is_number)
+                    .requires("solver-pool")
+                    .help("restarts pooled solver processes which use more than the given \
+                     amount of memory")
+            )
             .arg(
                 Arg::with_name("use-exp-boogie")
                     .long("use-exp-boogie")
@@ -464,6 +738,13 @@ impl Options {
                     .long("generate-smt")
                     .help("instructs boogie to log smtlib files for verified functions")
             )
+            .arg(
+                Arg::with_name("profile-vcs")
+                    .long("profile-vcs")
+                    .help("measures the solver time of each verification condition and writes \
+                     a report of the hot spots per function, condition, and pipeline pass to \
+                     `<output>.profile`. Each condition is checked in a separate solver query")
+            )
             .arg(
                 Arg::with_name("experimental-pipeline")
                     .long("experimental-pipeline")
@@ -538,6 +819,10 @@ impl Options {
         } else {
             Options::default()
         };
+        // The settings of the package are overridden by the arguments.
+        if matches.is_present("package") {
+            options.apply_package_manifest(Path::new(matches.value_of("package").unwrap()))?;
+        }
 
         // Analyze arguments.
         if matches.is_present("output") {
@@ -573,6 +858,31 @@ impl Options {
             }
         }
 
+        if matches.is_present("diagnostics-json") {
+            options.prover.diagnostics_json =
+                Some(matches.value_of("diagnostics-json").unwrap().to_string());
+        }
+        if matches.is_present("assume-unverified") {
+            options.prover.assume_unverified = true;
+        }
+        if matches.is_present("assumptions-ledger") {
+            options.prover.assumptions_ledger =
+                Some(matches.value_of("assumptions-ledger").unwrap().to_string());
+        }
+        if matches.is_present("script-usage") {
+            options.prover.script_usage =
+                Some(matches.value_of("script-usage").unwrap().to_string());
+        }
+        if matches.is_present("inline-threshold") {
+            options.prover.inline_threshold = matches
+                .value_of("inline-threshold")
+                .unwrap()
+                .parse::<usize>()?;
+        }
+        if matches.is_present("aggressive-dse") {
+            options.prover.aggressive_dead_store_elimination = true;
+        }
+
         if matches.is_present("generate-only") {
             options.prover.generate_only = true;
         }
@@ -582,9 +892,15 @@ impl Options {
         if matches.occurrences_of("dependencies") > 0 {
             options.move_deps = get_vec("dependencies");
         }
+        if matches.occurrences_of("upgrade-from") > 0 {
+            options.upgrade_from = get_vec("upgrade-from");
+        }
         if matches.occurrences_of("named-addresses") > 0 {
             options.move_named_address_values = get_vec("named-addresses");
         }
+        if matches.is_present("package") {
+            options.move_package = matches.value_of("package").map(|s| s.to_string());
+        }
         if matches.is_present("mutation") {
             options.prover.mutation = true;
         }
@@ -620,6 +936,9 @@ impl Options {
                 _ => unreachable!("should not happen"),
             }
         }
+        if matches.is_present("verify-memory") {
+            options.prover.verify_memory = get_vec("verify-memory");
+        }
         if matches.is_present("bench-repeat") {
             options.backend.bench_repeat =
                 matches.value_of("bench-repeat").unwrap().parse::<usize>()?;
@@ -649,15 +968,76 @@ impl Options {
         if matches.is_present("read-write-set") {
             options.run_read_write_set = true;
         }
+        if matches.is_present("fuzz") {
+            options.run_fuzz = true;
+        }
+        if matches.is_present("fuzz-iterations") {
+            options.fuzz.iterations = matches
+                .value_of("fuzz-iterations")
+                .unwrap()
+                .parse::<usize>()?;
+        }
+        if matches.is_present("fuzz-seed") {
+            options.fuzz.seed = matches.value_of("fuzz-seed").unwrap().parse::<u64>()?;
+        }
+        if matches.is_present("fuzz-harness-dir") {
+            options.fuzz.harness_dir =
+                Some(matches.value_of("fuzz-harness-dir").unwrap().to_string());
+        }
+        if matches.is_present("checked-build") {
+            options.run_checked_build = true;
+        }
+        if matches.is_present("checked-build-dir") {
+            options.checked_build.output_directory =
+                matches.value_of("checked-build-dir").unwrap().to_string();
+        }
+        if matches.is_present("checked-build-abort-code") {
+            options.checked_build.abort_code = matches
+                .value_of("checked-build-abort-code")
+                .unwrap()
+                .parse::<u64>()?;
+        }
+        if matches.is_present("why-invariant") {
+            let mut values = matches.values_of("why-invariant").unwrap();
+            options.why_invariant = Some(InvariantQuery {
+                invariant: values.next().unwrap().parse::<usize>()?,
+                function: values.next().unwrap().to_string(),
+            });
+        }
         if matches.is_present("trace") {
             options.prover.auto_trace_level = AutoTraceLevel::VerifiedFunction;
         }
         if matches.is_present("dump-bytecode") {
             options.prover.dump_bytecode = true;
         }
+        if matches.is_present("dump-bytecode-diff") {
+            options.prover.dump_bytecode_diff = true;
+        }
+        if matches.is_present("dump-bytecode-with-source") {
+            options.prover.dump_bytecode_with_source = Some(
+                matches
+                    .value_of("dump-bytecode-with-source")
+                    .unwrap()
+                    .to_string(),
+            );
+        }
         if matches.is_present("dump-cfg") {
             options.prover.dump_cfg = true;
         }
+        if matches.is_present("dump-pipeline-graph") {
+            options.prover.dump_pipeline_graph = true;
+        }
+        if matches.is_present("summary-cache") {
+            options.prover.summary_cache_dir =
+                Some(matches.value_of("summary-cache").unwrap().to_string());
+        }
+        if matches.is_present("cache") {
+            options.prover.verification_cache_dir = Some(DEFAULT_CACHE_DIR.to_string());
+        }
+        if matches.is_present("cache-dir") {
+            options.prover.verification_cache_dir =
+                Some(matches.value_of("cache-dir").unwrap().to_string());
+        }
         if matches.is_present("num-instances") {
             let num_instances = matches
                 .value_of("num-instances")
@@ -691,6 +1071,9 @@ impl Options {
         if matches.is_present("cores") {
             options.backend.proc_cores = matches.value_of("cores").unwrap().parse::<usize>()?;
         }
+        if matches.is_present("modular") {
+            options.backend.modular_verification = true;
+        }
         if matches.is_present("eager-threshold") {
             options.backend.eager_threshold = matches
                 .value_of("eager-threshold")
@@ -706,12 +1089,28 @@ impl Options {
         if matches.is_present("use-cvc4") {
             options.backend.use_cvc4 = true;
         }
+        if matches.is_present("smtlib") {
+            options.backend.smtlib_direct = true;
+        }
+        if matches.is_present("solver-pool") {
+            options.backend.solver_pool_size =
+                matches.value_of("solver-pool").unwrap().parse::<usize>()?;
+        }
+        if matches.is_present("solver-memory-limit") {
+            options.backend.solver_memory_limit_mb = matches
+                .value_of("solver-memory-limit")
+                .unwrap()
+                .parse::<usize>()?;
+        }
         if matches.is_present("use-exp-boogie") {
             options.backend.use_exp_boogie = true;
         }
         if matches.is_present("generate-smt") {
             options.backend.generate_smt = true;
         }
+        if matches.is_present("profile-vcs") {
+            options.backend.profile_vcs = true;
+        }
 
         if matches.is_present("check-inconsistency") {
             options.prover.check_inconsistency = true;
@@ -790,3 +1189,45 @@ pub fn named_addresses_for_options(
         .map(|(name, addr)| format!("{}=0x{:#X}", name, addr))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[package]
+name = "Test"
+version = "0.1.0"
+
+[prover]
+vc_timeout = 40
+hard_timeout = 600
+backend_flags = ["-proverOpt:O:smt.QI.EAGER_THRESHOLD=100"]
+"#;
+
+    #[test]
+    fn test_apply_package_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Move.toml"), MANIFEST).unwrap();
+
+        let mut options = Options::default();
+        options.apply_package_manifest(dir.path()).unwrap();
+        assert_eq!(options.backend.vc_timeout, 40);
+        assert_eq!(options.backend.hard_timeout_secs, 600);
+        assert_eq!(
+            options.backend.boogie_flags,
+            vec!["-proverOpt:O:smt.QI.EAGER_THRESHOLD=100".to_string()]
+        );
+
+        // The arguments take precedence over the manifest.
+        let package_path = dir.path().to_string_lossy().to_string();
+        let args = ["mvp", "--package", package_path.as_str(), "--timeout", "10"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>();
+        let options = Options::create_from_args(&args).unwrap();
+        assert_eq!(options.backend.vc_timeout, 10);
+        assert_eq!(options.backend.hard_timeout_secs, 600);
+        assert_eq!(options.move_package, Some(package_path));
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Invariant staging for framework upgrades.
+//!
+//! Given the model of the currently deployed version of a module set and the model of a pending
+//! upgrade, this produces spec modules which add the global invariants of the old version to the
+//! corresponding modules of the new version. Verifying the new version together with these spec
+//! modules checks that its functions preserve the invariants of both the old and the new specs,
+//! so that state which satisfied the old invariants is not broken by the upgrade. The invariant
+//! relevance analysis then determines, as for any other invariant, which functions of the new
+//! version need to preserve a staged invariant.
+//!
+//! Invariants of modules which no longer exist, and invariants which are unchanged in the new
+//! version, are not staged. A staged invariant must be expressible in the new version; if it
+//! refers to declarations which were removed, model building reports errors at the staged
+//! source.
+
+use itertools::Itertools;
+use log::info;
+use move_model::{
+    ast::{ConditionKind, GlobalInvariant},
+    model::{GlobalEnv, ModuleEnv},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The name of the file containing the staged invariants, relative to the output directory.
+pub const STAGED_INVARIANTS_FILE: &str = "staged_invariants.move";
+
+/// Returns the source of spec modules adding the invariants of the old version of target
+/// modules to the new version, or None if there are no invariants to stage.
+pub fn staged_invariants_source(old_env: &GlobalEnv, new_env: &GlobalEnv) -> Option<String> {
+    let new_modules = new_env
+        .get_modules()
+        .map(|module_env| (module_env.get_full_name_str(), module_env))
+        .collect::<BTreeMap<_, _>>();
+    let mut source = String::new();
+    let mut count = 0;
+    for old_module in old_env.get_modules() {
+        if !old_module.is_target() {
+            continue;
+        }
+        let new_module = match new_modules.get(&old_module.get_full_name_str()) {
+            Some(new_module) => new_module,
+            None => continue,
+        };
+        let existing = module_invariants(new_module)
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect::<BTreeSet<_>>();
+        let staged = module_invariants(&old_module)
+            .into_iter()
+            .filter(|(text, _)| !existing.contains(text))
+            .collect::<Vec<_>>();
+        if staged.is_empty() {
+            continue;
+        }
+        source.push_str(&format!("spec {} {{\n", old_module.get_full_name_str()));
+        for (text, origin) in staged {
+            source.push_str(&format!("    // staged from {}\n    {}\n", origin, text));
+            count += 1;
+        }
+        source.push_str("}\n\n");
+    }
+    if count == 0 {
+        return None;
+    }
+    info!(
+        "staging {} invariants of the old version for verification",
+        count
+    );
+    Some(source)
+}
+
+/// Returns the normalized source of the global invariants declared in the module, together with
+/// a description of their location.
+fn module_invariants(module_env: &ModuleEnv<'_>) -> Vec<(String, String)> {
+    let env = module_env.env;
+    env.get_global_invariants_by_module(module_env.get_id())
+        .into_iter()
+        .filter_map(|id| env.get_global_invariant(id))
+        .filter_map(|inv| {
+            let text = invariant_source(env, inv)?;
+            let origin = env
+                .get_file_and_location(&inv.loc)
+                .map(|(file, location)| format!("{}:{}", file, location.line.0 + 1))
+                .unwrap_or_else(|| module_env.get_full_name_str());
+            Some((text, origin))
+        })
+        .collect()
+}
+
+/// Returns the source of the invariant declaration, with whitespace normalized, or None if the
+/// source of its condition is not available.
+fn invariant_source(env: &GlobalEnv, inv: &GlobalInvariant) -> Option<String> {
+    let (ty_params, update) = match &inv.kind {
+        ConditionKind::GlobalInvariant(ty_params) => (ty_params, ""),
+        ConditionKind::GlobalInvariantUpdate(ty_params) => (ty_params, " update"),
+        _ => return None,
+    };
+    let ty_params = if ty_params.is_empty() {
+        "".to_string()
+    } else {
+        format!(
+            "<{}>",
+            ty_params
+                .iter()
+                .map(|sym| sym.display(env.symbol_pool()).to_string())
+                .join(", ")
+        )
+    };
+    let cond = env.get_source(&env.get_node_loc(inv.cond.node_id())).ok()?;
+    let text = format!("invariant{}{} {};", ty_params, update, cond);
+    Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_model::run_model_builder;
+    use std::{fs, path::Path};
+
+    const OLD_SOURCE: &str = r#"
+address 0x42 {
+module M {
+    struct R has key { v: u64 }
+    spec module {
+        invariant forall a: address where exists<R>(a): global<R>(a).v > 0;
+        invariant update forall a: address where old(exists<R>(a)): exists<R>(a);
+    }
+}
+module N {
+    struct S has key { v: u64 }
+    spec module {
+        invariant forall a: address where exists<S>(a): global<S>(a).v > 0;
+    }
+}
+}
+"#;
+
+    // Keeps the first invariant of `M`, formatted differently, and removes `N`.
+    const NEW_SOURCE: &str = r#"
+address 0x42 {
+module M {
+    struct R has key { v: u64 }
+    spec module {
+        invariant forall a: address
+            where exists<R>(a): global<R>(a).v > 0;
+    }
+}
+}
+"#;
+
+    fn build(path: &Path, source: &str) -> GlobalEnv {
+        fs::write(path, source).unwrap();
+        let env = run_model_builder(&[path.to_string_lossy().to_string()], &[]).unwrap();
+        assert!(!env.has_errors());
+        env
+    }
+
+    #[test]
+    fn test_staged_invariants_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let old_path = dir.path().join("old.move");
+        let old_env = build(&old_path, OLD_SOURCE);
+        let new_env = build(&dir.path().join("new.move"), NEW_SOURCE);
+
+        let line = OLD_SOURCE
+            .lines()
+            .position(|line| line.contains("invariant update"))
+            .unwrap()
+            + 1;
+        assert_eq!(
+            staged_invariants_source(&old_env, &new_env),
+            Some(format!(
+                "spec 0x42::M {{\n    // staged from {}:{}\n    invariant update forall a: \
+                 address where old(exists<R>(a)): exists<R>(a);\n}}\n\n",
+                old_path.display(),
+                line
+            ))
+        );
+
+        // Nothing is staged when the invariants are unchanged.
+        assert_eq!(staged_invariants_source(&new_env, &new_env), None);
+    }
+}
@@ -3,33 +3,46 @@
 
 #![forbid(unsafe_code)]
 
-use crate::cli::Options;
+use crate::{cli::Options, verification_cache::VerificationCache};
 use abigen::Abigen;
 use anyhow::anyhow;
 use boogie_backend::{
     add_prelude, boogie_wrapper::BoogieWrapper, bytecode_translator::BoogieTranslator,
+    smtlib_backend::SmtLibWrapper,
 };
 use bytecode::{
     function_target_pipeline::{FunctionTargetPipeline, FunctionTargetsHolder},
+    invariant_explanation::{self, InvariantExplanation, InvariantQuery},
     pipeline_factory,
     read_write_set_analysis::{self, ReadWriteSetProcessor},
+    usage_analysis, verification_analysis,
 };
+use bytecode_interpreter::fuzz;
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream, WriteColor};
 use docgen::Docgen;
 use errmapgen::ErrmapGen;
 #[allow(unused_imports)]
 use log::{debug, info, warn};
+use move_command_line_common::files::MOVE_COMPILED_EXTENSION;
 use move_model::{
-    code_writer::CodeWriter, model::GlobalEnv, parse_addresses_from_options,
-    run_model_builder_with_options,
+    code_writer::CodeWriter,
+    model::{FunId, GlobalEnv, GlobalId, QualifiedId},
+    parse_addresses_from_options, run_model_builder_with_options,
 };
+use move_package::BuildConfig;
 use std::{
+    cell::RefCell,
+    collections::BTreeSet,
     fs,
+    io::Write,
     path::{Path, PathBuf},
     time::Instant,
 };
 
+pub mod checked_build;
 pub mod cli;
+pub mod invariant_staging;
+pub mod verification_cache;
 
 // =================================================================================================
 // Prover API
@@ -45,13 +58,89 @@ pub fn run_move_prover<W: WriteColor>(
 ) -> anyhow::Result<()> {
     let now = Instant::now();
     // Run the model builder.
-    let env = run_model_builder_with_options(
-        &options.move_sources,
+    let mut env = match &options.move_package {
+        Some(package_path) => run_model_builder_for_package(Path::new(package_path), &options)?,
+        None => run_model_builder_with_options(
+            &options.move_sources,
+            &options.move_deps,
+            options.model_builder.clone(),
+            parse_addresses_from_options(options.move_named_address_values.clone())?,
+        )?,
+    };
+    if !options.upgrade_from.is_empty() {
+        check_errors(
+            &env,
+            &options,
+            error_writer,
+            "exiting with model building errors",
+        )?;
+        env = stage_invariants(env, error_writer, &options)?;
+    }
+    run_move_prover_with_model(&env, error_writer, options, Some(now))
+}
+
+/// Builds the model of the Move package at `package_path`. The sources of the package are the
+/// targets of the model, and the sources of its dependencies are dependencies. The named addresses
+/// are the ones of the packages, instead of `options.move_named_address_values`.
+fn run_model_builder_for_package(
+    package_path: &Path,
+    options: &Options,
+) -> anyhow::Result<GlobalEnv> {
+    if !options.upgrade_from.is_empty() {
+        return Err(anyhow!(
+            "verifying the upgrade of a package is not supported"
+        ));
+    }
+    let build_config = BuildConfig {
+        dev_mode: false,
+        test_mode: false,
+        generate_docs: false,
+        generate_abis: false,
+    };
+    build_config.move_model_for_package_with_options(package_path, options.model_builder.clone())
+}
+
+/// Rebuilds the model with the global invariants of the old version of the modules given by
+/// `options.upgrade_from` added, so that the functions of the new version are verified to
+/// preserve the invariants of both versions.
+fn stage_invariants<W: WriteColor>(
+    env: GlobalEnv,
+    error_writer: &mut W,
+    options: &Options,
+) -> anyhow::Result<GlobalEnv> {
+    let old_env = run_model_builder_with_options(
+        &options.upgrade_from,
         &options.move_deps,
         options.model_builder.clone(),
         parse_addresses_from_options(options.move_named_address_values.clone())?,
     )?;
-    run_move_prover_with_model(&env, error_writer, options, Some(now))
+    check_errors(
+        &old_env,
+        options,
+        error_writer,
+        "exiting with model building errors in old version",
+    )?;
+    let source = match invariant_staging::staged_invariants_source(&old_env, &env) {
+        Some(source) => source,
+        None => {
+            info!("no invariants of the old version to stage");
+            return Ok(env);
+        }
+    };
+    let staged_file = Path::new(&options.output_path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(invariant_staging::STAGED_INVARIANTS_FILE);
+    debug!("writing staged invariants to `{}`", staged_file.display());
+    fs::write(&staged_file, source)?;
+    let mut sources = options.move_sources.clone();
+    sources.push(staged_file.to_string_lossy().to_string());
+    run_model_builder_with_options(
+        &sources,
+        &options.move_deps,
+        options.model_builder.clone(),
+        parse_addresses_from_options(options.move_named_address_values.clone())?,
+    )
 }
 
 pub fn run_move_prover_with_model<W: WriteColor>(
@@ -97,14 +186,41 @@ pub fn run_move_prover_with_model<W: WriteColor>(
             Ok(())
         };
     }
+    // Same for spec-driven fuzzing
+    if options.run_fuzz {
+        return run_fuzz(env, &options, error_writer, now);
+    }
+    // Same for checked builds
+    if options.run_checked_build {
+        return run_checked_build(env, &options, now);
+    }
+    // Same for the explanation of global invariants
+    if let Some(query) = &options.why_invariant {
+        return run_why_invariant(env, &options, query, error_writer, now);
+    }
 
     // Check correct backend versions.
     options.backend.check_tool_versions()?;
 
     // Create and process bytecode
     let now = Instant::now();
-    let targets = create_and_process_bytecode(&options, env);
+    let mut targets = create_and_process_bytecode(&options, env);
     let trafo_duration = now.elapsed();
+    if let Some(path) = &options.prover.diagnostics_json {
+        fs::write(
+            path,
+            serde_json::to_string_pretty(&targets.diagnostics().to_json(env))?,
+        )?;
+    }
+    if let Some(path) = &options.prover.assumptions_ledger {
+        if let Some(ledger) = verification_analysis::get_assumptions_ledger(&targets) {
+            fs::write(path, serde_json::to_string_pretty(&ledger.to_json(env))?)?;
+        }
+    }
+    if let Some(path) = &options.prover.script_usage {
+        let usage = usage_analysis::script_usage_to_json(env, &targets);
+        fs::write(path, serde_json::to_string_pretty(&usage)?)?;
+    }
     check_errors(
         env,
         &options,
@@ -112,6 +228,28 @@ pub fn run_move_prover_with_model<W: WriteColor>(
         "exiting with bytecode transformation errors",
     )?;
 
+    // Skip the functions verified in an earlier run with the same code, specs, and options.
+    let cache = options
+        .prover
+        .verification_cache_dir
+        .as_ref()
+        .map(|dir| VerificationCache::prune_targets(env, &options, dir, &mut targets));
+    if cache.is_some()
+        && !targets
+            .get_funs_and_variants()
+            .any(|(_, variant)| variant.is_verified())
+    {
+        info!("all verification results are cached, nothing to verify");
+        return Ok(());
+    }
+
+    // Verify the functions supported by the SMT-LIB backend directly, leaving the remaining
+    // ones to Boogie.
+    let mut failed = BTreeSet::new();
+    if options.backend.smtlib_direct {
+        failed = verify_smtlib(env, &options, &mut targets)?;
+    }
+
     // Generate boogie code
     let now = Instant::now();
     let code_writer = generate_boogie(env, &options, &targets)?;
@@ -125,8 +263,15 @@ pub fn run_move_prover_with_model<W: WriteColor>(
 
     // Verify boogie code.
     let now = Instant::now();
-    verify_boogie(env, &options, &targets, code_writer)?;
+    let boogie_failed = verify_boogie(env, &options, &targets, code_writer)?;
     let verify_duration = now.elapsed();
+    if let Some(cache) = &cache {
+        let failed = boogie_failed.map(|boogie_failed| {
+            failed.extend(boogie_failed);
+            failed
+        });
+        cache.record(env, failed.as_ref());
+    }
 
     // Report durations.
     info!(
@@ -170,12 +315,14 @@ pub fn generate_boogie(
     Ok(writer)
 }
 
+/// Verify boogie code. Returns the functions for which errors were reported, or None if some
+/// error could not be attributed to a function.
 pub fn verify_boogie(
     env: &GlobalEnv,
     options: &Options,
     targets: &FunctionTargetsHolder,
     writer: CodeWriter,
-) -> anyhow::Result<()> {
+) -> anyhow::Result<Option<BTreeSet<QualifiedId<FunId>>>> {
     let output_existed = std::path::Path::new(&options.output_path).exists();
     debug!("writing boogie to `{}`", &options.output_path);
     writer.process_result(|result| fs::write(&options.output_path, result))?;
@@ -185,11 +332,38 @@ pub fn verify_boogie(
         writer: &writer,
         options: &options.backend,
     };
-    boogie.call_boogie_and_verify_output(&options.output_path)?;
+    let failed = boogie.call_boogie_and_verify_output(&options.output_path)?;
     if !output_existed && !options.backend.keep_artifacts {
         std::fs::remove_file(&options.output_path).unwrap_or_default();
     }
-    Ok(())
+    Ok(failed)
+}
+
+/// Verify the verification variants in the subset supported by the SMT-LIB backend, and remove
+/// those for which a conclusive result was obtained from the targets. Returns the functions for
+/// which errors were reported.
+pub fn verify_smtlib(
+    env: &GlobalEnv,
+    options: &Options,
+    targets: &mut FunctionTargetsHolder,
+) -> anyhow::Result<BTreeSet<QualifiedId<FunId>>> {
+    let output_dir = Path::new(&options.output_path)
+        .parent()
+        .expect("expect the parent directory of the output path to exist");
+    let verified = SmtLibWrapper {
+        env,
+        targets,
+        options: &options.backend,
+    }
+    .verify(output_dir)?;
+    let mut failed = BTreeSet::new();
+    for (fun_id, variant, holds) in verified {
+        targets.remove_target_data(&fun_id, &variant);
+        if !holds {
+            failed.insert(fun_id);
+        }
+    }
+    Ok(failed)
 }
 
 /// Create bytecode and process it.
@@ -220,17 +394,34 @@ pub fn create_and_process_bytecode(options: &Options, env: &GlobalEnv) -> Functi
         pipeline_factory::default_pipeline_with_options(&options.prover)
     };
 
-    if options.prover.dump_bytecode {
-        let dump_file_base = output_dir
+    let dump_file_base = || {
+        output_dir
             .join(output_prefix)
             .into_os_string()
             .into_string()
-            .unwrap();
-        pipeline.run_with_dump(env, &mut targets, &dump_file_base, options.prover.dump_cfg)
+            .unwrap()
+    };
+    if options.prover.dump_bytecode {
+        pipeline.run_with_dump(
+            env,
+            &mut targets,
+            &dump_file_base(),
+            options.prover.dump_cfg,
+        )
+    } else if options.prover.dump_bytecode_diff {
+        pipeline.run_with_diff_dump(env, &mut targets, &dump_file_base());
+    } else if let Some(stage) = &options.prover.dump_bytecode_with_source {
+        pipeline.run_with_source_dump(env, &mut targets, &dump_file_base(), stage);
     } else {
         pipeline.run(env, &mut targets);
     }
 
+    if options.prover.dump_pipeline_graph {
+        let dump_file = output_dir.join(format!("{}_pipeline.dot", output_prefix));
+        debug!("dumping pipeline graph to `{}`", dump_file.display());
+        fs::write(&dump_file, targets.pipeline_graph().to_dot()).expect("dumping pipeline graph");
+    }
+
     targets
 }
 
@@ -301,6 +492,159 @@ fn run_errmapgen(env: &GlobalEnv, options: &Options, now: Instant) {
     );
 }
 
+fn run_fuzz<W: WriteColor>(
+    env: &GlobalEnv,
+    options: &Options,
+    error_writer: &mut W,
+    now: Instant,
+) -> anyhow::Result<()> {
+    let checking_elapsed = now.elapsed();
+    info!("fuzzing functions against their specification");
+    let report = fuzz::fuzz_specs(env, &options.prover, &options.fuzz);
+    for result in &report.results {
+        debug!(
+            "fuzzed `{}` with {} executions ({} discarded)",
+            result.harness.name, result.executions, result.discarded
+        );
+    }
+    write!(error_writer, "{}", report)?;
+    if let Some(dir) = &options.fuzz.harness_dir {
+        fs::create_dir_all(dir)?;
+        for (module_name, source) in report.generate_move_tests() {
+            fs::write(Path::new(dir).join(format!("{}.move", module_name)), source)?;
+        }
+    }
+    let fuzzing_elapsed = now.elapsed();
+    info!(
+        "{:.3}s checking, {:.3}s fuzzing",
+        checking_elapsed.as_secs_f64(),
+        (fuzzing_elapsed - checking_elapsed).as_secs_f64()
+    );
+
+    let divergences = report.divergences().count();
+    if divergences > 0 {
+        Err(anyhow!(
+            "{} function(s) diverge from their specification",
+            divergences
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn run_checked_build(env: &GlobalEnv, options: &Options, now: Instant) -> anyhow::Result<()> {
+    let checking_elapsed = now.elapsed();
+    info!("building modules with runtime checks");
+    let build = checked_build::build_checked_modules(env, &options.checked_build);
+    for skipped in &build.skipped {
+        debug!(
+            "condition at {} not checked at runtime: {}",
+            skipped.loc.display(env),
+            skipped.reason
+        );
+    }
+    info!(
+        "{} condition(s) checked at runtime, {} skipped",
+        build.checks,
+        build.skipped.len()
+    );
+    let output_dir = Path::new(&options.checked_build.output_directory);
+    fs::create_dir_all(output_dir)?;
+    for (name, module) in &build.modules {
+        let mut bytes = vec![];
+        module.serialize(&mut bytes)?;
+        let mut path = output_dir.join(name);
+        path.set_extension(MOVE_COMPILED_EXTENSION);
+        fs::write(path, bytes)?;
+    }
+    let building_elapsed = now.elapsed();
+    info!(
+        "{:.3}s checking, {:.3}s building",
+        checking_elapsed.as_secs_f64(),
+        (building_elapsed - checking_elapsed).as_secs_f64()
+    );
+    Ok(())
+}
+
+fn run_why_invariant<W: WriteColor>(
+    env: &GlobalEnv,
+    options: &Options,
+    query: &InvariantQuery,
+    error_writer: &mut W,
+    now: Instant,
+) -> anyhow::Result<()> {
+    let checking_elapsed = now.elapsed();
+    info!("explaining global invariant {}", query.invariant);
+    let explanation = explain_invariant(env, options, query)?;
+    check_errors(
+        env,
+        options,
+        error_writer,
+        "exiting with bytecode transformation errors",
+    )?;
+    write!(error_writer, "{}", explanation)?;
+    let explaining_elapsed = now.elapsed();
+    info!(
+        "{:.3}s checking, {:.3}s explaining",
+        checking_elapsed.as_secs_f64(),
+        (explaining_elapsed - checking_elapsed).as_secs_f64()
+    );
+    Ok(())
+}
+
+/// Explains which memory accesses in the function of the query cause the global invariant of the
+/// query to be asserted.
+pub fn explain_invariant(
+    env: &GlobalEnv,
+    options: &Options,
+    query: &InvariantQuery,
+) -> anyhow::Result<InvariantExplanation> {
+    let inv_id = GlobalId::new(query.invariant);
+    if env.get_global_invariant(inv_id).is_none() {
+        return Err(anyhow!("no global invariant with id {}", query.invariant));
+    }
+    let fun_id = env
+        .get_modules()
+        .flat_map(|module_env| module_env.into_functions())
+        .find(|fun_env| {
+            fun_env.get_full_name_str() == query.function
+                || format!(
+                    "{}::{}",
+                    fun_env.module_env.get_full_name_str(),
+                    fun_env.get_name().display(env.symbol_pool())
+                ) == query.function
+        })
+        .map(|fun_env| fun_env.get_qualified_id())
+        .ok_or_else(|| anyhow!("no function named `{}`", query.function))?;
+
+    let mut targets = FunctionTargetsHolder::default();
+    for module_env in env.get_modules() {
+        for func_env in module_env.get_functions() {
+            targets.add_target(&func_env)
+        }
+    }
+
+    // The result of the global invariant analysis refers to the code offsets at the point the
+    // analysis is run, so the explanation is computed right after it.
+    let explanation = RefCell::new(None);
+    let pipeline = pipeline_factory::default_pipeline_with_options(&options.prover);
+    pipeline.run_with_hook(
+        env,
+        &mut targets,
+        |_| {},
+        |_, processor, targets| {
+            if processor.name() == "global_invariant_analysis" {
+                *explanation.borrow_mut() = Some(invariant_explanation::explain_invariant(
+                    env, targets, inv_id, fun_id,
+                ));
+            }
+        },
+    );
+    explanation
+        .into_inner()
+        .ok_or_else(|| anyhow!("global invariant analysis not performed"))
+}
+
 fn run_read_write_set(env: &GlobalEnv, options: &Options, now: Instant) {
     let mut targets = FunctionTargetsHolder::default();
 
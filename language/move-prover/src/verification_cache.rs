@@ -0,0 +1,558 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A cache of verification results.
+//!
+//! Each verification variant is keyed by a digest of its instrumented bytecode, the code and
+//! specs of the functions it calls, the spec functions used by its conditions, the global
+//! invariants relevant to it, the declarations shared by all variants (axioms, global spec
+//! variables, and struct layouts), and the prover options. A variant whose digest has been
+//! recorded as verified in an earlier run is removed from the targets before translation, so it
+//! is not passed to any solver. Since the seeds given to the solvers are derived from the options,
+//! which are part of the digest, a cached result stands for a reproducible run.
+
+use crate::cli::Options;
+use bytecode::{
+    function_target::FunctionTarget,
+    function_target_pipeline::{FunctionTargetsHolder, FunctionVariant},
+    invariant_relevance, mono_analysis,
+    stackless_bytecode::{Bytecode, Operation},
+};
+use log::{debug, info, warn};
+use move_model::{
+    ast::{self, Exp, ExpData, Spec},
+    model::{FunId, GlobalEnv, QualifiedId, SpecFunId},
+    ty::TypeDisplayContext,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+
+/// Version of the on-disk format. Bump this whenever the digest computation changes, so that
+/// stale entries are never picked up.
+const CACHE_FORMAT_VERSION: u32 = 2;
+
+/// The default directory of the cache, relative to the working directory.
+pub const DEFAULT_CACHE_DIR: &str = ".move-prover-cache";
+
+/// The content of a cache entry. The digest is the key; the content is only informative.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    function: String,
+    variant: String,
+}
+
+/// The verification variants of a run, as far as they are not yet known to verify.
+pub struct VerificationCache {
+    dir: PathBuf,
+    /// The digests of the variants which were not found in the cache.
+    pending: BTreeMap<(QualifiedId<FunId>, FunctionVariant), String>,
+}
+
+impl VerificationCache {
+    /// Computes the digests of all verification variants in the targets, and removes the
+    /// variants which were verified in an earlier run from the targets.
+    pub fn prune_targets(
+        env: &GlobalEnv,
+        options: &Options,
+        dir: &str,
+        targets: &mut FunctionTargetsHolder,
+    ) -> Self {
+        let mut cache = VerificationCache {
+            dir: PathBuf::from(dir).join(format!("v{}", CACHE_FORMAT_VERSION)),
+            pending: BTreeMap::new(),
+        };
+        let cached = {
+            let mut digester = Digester::new(env, options, targets);
+            let mut cached = vec![];
+            for (fun_id, variant) in targets.get_funs_and_variants() {
+                if !variant.is_verified() {
+                    continue;
+                }
+                let fun_env = env.get_function(fun_id);
+                let target = targets.get_target(&fun_env, &variant);
+                let digest = digester.variant_digest(&target);
+                if cache.path_for(&digest).exists() {
+                    debug!(
+                        "skipping `{}` [{}], verified in an earlier run",
+                        fun_env.get_full_name_str(),
+                        variant
+                    );
+                    cached.push((fun_id, variant));
+                } else {
+                    cache.pending.insert((fun_id, variant), digest);
+                }
+            }
+            cached
+        };
+        if !cached.is_empty() {
+            info!(
+                "reusing {} cached verification results from `{}`",
+                cached.len(),
+                dir
+            );
+        }
+        for (fun_id, variant) in cached {
+            targets.remove_target_data(&fun_id, &variant);
+        }
+        cache
+    }
+
+    /// Records the pending variants as verified, except those of the given failed functions.
+    /// If `failed` is None, errors could not be attributed to functions, and nothing is
+    /// recorded. Failures are reported as warnings only, since the cache is merely an
+    /// optimization.
+    pub fn record(&self, env: &GlobalEnv, failed: Option<&BTreeSet<QualifiedId<FunId>>>) {
+        let failed = match failed {
+            Some(failed) => failed,
+            None => return,
+        };
+        for ((fun_id, variant), digest) in &self.pending {
+            if failed.contains(fun_id) {
+                continue;
+            }
+            let entry = CacheEntry {
+                function: env.get_function(*fun_id).get_full_name_str(),
+                variant: variant.to_string(),
+            };
+            let path = self.path_for(digest);
+            let result = serde_json::to_string(&entry)
+                .map_err(|err| err.to_string())
+                .and_then(|content| {
+                    fs::create_dir_all(&self.dir).map_err(|err| err.to_string())?;
+                    fs::write(&path, content).map_err(|err| err.to_string())
+                });
+            if let Err(err) = result {
+                warn!(
+                    "failed to store verification result `{}`: {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    fn path_for(&self, digest: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", digest))
+    }
+}
+
+/// Memoized digests of functions or spec functions. While the digest of one is being computed,
+/// recursive calls to it are represented by its name. A digest computed while such a cycle is
+/// still open misses the code of the functions of the cycle, so it is not memoized.
+struct DigestMemo<K> {
+    digests: BTreeMap<K, String>,
+    /// The ids whose digest is being computed.
+    in_progress: BTreeSet<K>,
+    /// The ids in progress which have been represented by their name.
+    open_cycles: BTreeSet<K>,
+}
+
+impl<K: Ord + Copy> DigestMemo<K> {
+    fn new() -> Self {
+        Self {
+            digests: BTreeMap::new(),
+            in_progress: BTreeSet::new(),
+            open_cycles: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the memoized digest, or the placeholder for a recursive call. Otherwise, marks
+    /// the digest as being computed, to be completed with `finish`.
+    fn lookup(&mut self, id: K, name: &str) -> Option<String> {
+        if let Some(digest) = self.digests.get(&id) {
+            return Some(digest.clone());
+        }
+        if !self.in_progress.insert(id) {
+            self.open_cycles.insert(id);
+            return Some(format!("recursive {}", name));
+        }
+        None
+    }
+
+    fn finish(&mut self, id: K, digest: &str) {
+        self.in_progress.remove(&id);
+        self.open_cycles.remove(&id);
+        if self.open_cycles.is_empty() {
+            self.digests.insert(id, digest.to_string());
+        }
+    }
+}
+
+/// Computes digests, memoizing those of the called functions and spec functions.
+struct Digester<'a> {
+    env: &'a GlobalEnv,
+    targets: &'a FunctionTargetsHolder,
+    /// The digest of the options and the global declarations, shared by all variants.
+    globals_digest: String,
+    /// The digests of the baseline variants of called functions.
+    fun_digests: DigestMemo<QualifiedId<FunId>>,
+    /// The digests of spec functions.
+    spec_fun_digests: DigestMemo<QualifiedId<SpecFunId>>,
+}
+
+impl<'a> Digester<'a> {
+    fn new(env: &'a GlobalEnv, options: &Options, targets: &'a FunctionTargetsHolder) -> Self {
+        let mut digester = Self {
+            env,
+            targets,
+            globals_digest: String::new(),
+            fun_digests: DigestMemo::new(),
+            spec_fun_digests: DigestMemo::new(),
+        };
+        digester.globals_digest = digester.globals_digest(options);
+        digester
+    }
+
+    /// Computes the digest of the inputs shared by all variants: the options, the axioms, the
+    /// global spec variables, and the layouts of structs.
+    fn globals_digest(&mut self, options: &Options) -> String {
+        let env = self.env;
+        let mut parts = vec![env!("CARGO_PKG_VERSION").to_string()];
+        for part in &[
+            serde_json::to_string(&options.prover),
+            serde_json::to_string(&options.backend),
+        ] {
+            parts.push(part.as_deref().unwrap_or_default().to_string());
+        }
+        for axiom in &mono_analysis::get_info(env).axioms {
+            parts.push(format!("axiom {}", axiom.exp.display(env)));
+            parts.extend(self.spec_funs_digests(&axiom.exp));
+        }
+        let tctx = TypeDisplayContext::WithEnv {
+            env,
+            type_param_names: None,
+        };
+        for module_env in env.get_modules() {
+            let module_name = module_env.get_full_name_str();
+            for (_, decl) in module_env.get_spec_vars() {
+                parts.push(format!(
+                    "spec var {}::{}: {}",
+                    module_name,
+                    decl.name.display(env.symbol_pool()),
+                    decl.type_.display(&tctx)
+                ));
+            }
+            for struct_env in module_env.get_structs() {
+                parts.push(format!("struct {}", struct_env.get_full_name_str()));
+                for field_env in struct_env.get_fields() {
+                    parts.push(format!(
+                        "field {}: {}",
+                        field_env.get_name().display(env.symbol_pool()),
+                        field_env.get_type().display(&tctx)
+                    ));
+                }
+            }
+        }
+        digest(parts)
+    }
+
+    /// Computes the digest of a verification variant, which includes the global declarations
+    /// and the relevant global invariants.
+    fn variant_digest(&mut self, target: &FunctionTarget<'_>) -> String {
+        let mut parts = vec![self.globals_digest.clone(), self.code_digest(target)];
+        let fun_id = target.func_env.get_qualified_id();
+        let relevant = invariant_relevance::get_invariant_relevance(self.targets)
+            .and_then(|relevance| relevance.fun_to_invs.get(&fun_id))
+            .map(|relevance| relevance.accessed.clone());
+        for menv in self.env.get_modules() {
+            for inv_id in self.env.get_global_invariants_by_module(menv.get_id()) {
+                if relevant.as_ref().map_or(true, |ids| ids.contains(&inv_id)) {
+                    let inv = self.env.get_global_invariant(inv_id).unwrap();
+                    parts.push(format!("invariant {}", inv.cond.display(self.env)));
+                    parts.extend(self.spec_funs_digests(&inv.cond));
+                }
+            }
+        }
+        digest(parts)
+    }
+
+    /// Computes the digest of the code and spec of the given function variant, including the
+    /// digests of the functions and spec functions it calls.
+    fn code_digest(&mut self, target: &FunctionTarget<'_>) -> String {
+        let mut parts = vec![format!(
+            "{} [{}]",
+            target.func_env.get_full_name_str(),
+            target.data.variant
+        )];
+        let tctx = target.func_env.get_type_display_ctxt();
+        for idx in 0..target.get_local_count() {
+            parts.push(format!(
+                "local {}: {}",
+                idx,
+                target.get_local_type(idx).display(&tctx)
+            ));
+        }
+        let label_offsets = Bytecode::label_offsets(target.get_bytecode());
+        for code in target.get_bytecode() {
+            parts.push(code.display(target, &label_offsets).to_string());
+            match code {
+                Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _)
+                | Bytecode::Call(_, _, Operation::OpaqueCallBegin(mid, fid, _), _, _) => {
+                    parts.push(self.fun_digest(mid.qualified(*fid)));
+                }
+                Bytecode::Prop(_, _, exp) => parts.extend(self.spec_funs_digests(exp)),
+                _ => {}
+            }
+        }
+        self.add_spec(target.func_env.get_spec(), &mut parts);
+        digest(parts)
+    }
+
+    fn fun_digest(&mut self, fun_id: QualifiedId<FunId>) -> String {
+        let fun_env = self.env.get_function(fun_id);
+        let name = fun_env.get_full_name_str();
+        if fun_env.is_native_or_intrinsic() {
+            return format!("native {}", name);
+        }
+        if let Some(digest) = self.fun_digests.lookup(fun_id, &name) {
+            return digest;
+        }
+        let targets = self.targets;
+        let target = targets.get_target(&fun_env, &FunctionVariant::Baseline);
+        let digest = self.code_digest(&target);
+        self.fun_digests.finish(fun_id, &digest);
+        digest
+    }
+
+    fn add_spec(&mut self, spec: &Spec, parts: &mut Vec<String>) {
+        for cond in &spec.conditions {
+            parts.push(cond.kind.to_string());
+            for exp in cond.all_exps() {
+                parts.push(exp.display(self.env).to_string());
+                parts.extend(self.spec_funs_digests(exp));
+            }
+        }
+        for (offset, spec) in &spec.on_impl {
+            parts.push(format!("on_impl {}", offset));
+            self.add_spec(spec, parts);
+        }
+    }
+
+    /// Returns the digests of the spec functions called by the expression, whose definitions
+    /// are not part of the displayed expression.
+    fn spec_funs_digests(&mut self, exp: &Exp) -> Vec<String> {
+        let mut called = BTreeSet::new();
+        exp.visit(&mut |e| {
+            if let ExpData::Call(_, ast::Operation::Function(mid, fid, _), _) = e {
+                called.insert(mid.qualified(*fid));
+            }
+        });
+        called
+            .into_iter()
+            .map(|id| self.spec_fun_digest(id))
+            .collect()
+    }
+
+    fn spec_fun_digest(&mut self, id: QualifiedId<SpecFunId>) -> String {
+        let module_env = self.env.get_module(id.module_id);
+        let decl = module_env.get_spec_fun(id.id);
+        let name = format!(
+            "{}::{}",
+            module_env.get_full_name_str(),
+            decl.name.display(self.env.symbol_pool())
+        );
+        if let Some(digest) = self.spec_fun_digests.lookup(id, &name) {
+            return digest;
+        }
+        let mut parts = vec![name];
+        if let Some(body) = &decl.body {
+            parts.push(body.display(self.env).to_string());
+            parts.extend(self.spec_funs_digests(body));
+        }
+        let digest = digest(parts);
+        self.spec_fun_digests.finish(id, &digest);
+        digest
+    }
+}
+
+fn digest(parts: Vec<String>) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update(b"\n");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytecode::{
+        function_target_pipeline::{FunctionTargetPipeline, VerificationFlavor},
+        mono_analysis::MonoAnalysisProcessor,
+    };
+    use move_model::run_model_builder;
+
+    const SOURCE: &str = r#"
+address 0x42 {
+module M {
+    struct S has key { x: u64 }
+
+    spec module {
+        global counter: num;
+        fun double(x: u64): u64 { x * 2 }
+        axiom double(1) == 2;
+    }
+
+    fun f(x: u64): u64 { if (x == 0) 0 else g(x - 1) }
+    fun g(x: u64): u64 { if (x == 0) 1 else f(x - 1) }
+    fun h(x: u64): u64 { g(x) }
+    fun k(x: u64): u64 { f(x) }
+
+    fun get(a: address): u64 acquires S { borrow_global<S>(a).x }
+    spec get { ensures result == double(global<S>(a).x) / 2; }
+}
+}
+"#;
+
+    /// The functions of SOURCE, in the order their digests are computed.
+    const FUNS: &[&str] = &["M::h", "M::k", "M::f", "M::g", "M::get"];
+
+    /// Builds the model of the source, with a baseline and a verification variant of each
+    /// function.
+    fn build(source: &str) -> (GlobalEnv, FunctionTargetsHolder) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("M.move");
+        fs::write(&path, source).unwrap();
+        let env = run_model_builder(&[path.to_string_lossy().to_string()], &[]).unwrap();
+        assert!(!env.has_errors());
+        let mut targets = FunctionTargetsHolder::default();
+        for module_env in env.get_modules() {
+            for fun_env in module_env.get_functions() {
+                targets.add_target(&fun_env);
+                let fun_id = fun_env.get_qualified_id();
+                let variant = FunctionVariant::Verification(VerificationFlavor::Regular);
+                let data = targets
+                    .get_data(&fun_id, &FunctionVariant::Baseline)
+                    .unwrap()
+                    .fork(variant.clone());
+                targets.insert_target_data(&fun_id, variant, data);
+            }
+        }
+        let mut pipeline = FunctionTargetPipeline::default();
+        pipeline.add_processor(MonoAnalysisProcessor::new());
+        pipeline.run(&env, &mut targets);
+        (env, targets)
+    }
+
+    fn find_fun(env: &GlobalEnv, name: &str) -> QualifiedId<FunId> {
+        env.get_modules()
+            .flat_map(|module_env| module_env.into_functions())
+            .find(|fun_env| fun_env.get_full_name_str() == name)
+            .unwrap()
+            .get_qualified_id()
+    }
+
+    /// Returns the digests of the verification variants of FUNS, computed in that order.
+    fn digests_with(source: &str, options: &Options) -> BTreeMap<&'static str, String> {
+        let (env, targets) = build(source);
+        let mut digester = Digester::new(&env, options, &targets);
+        let variant = FunctionVariant::Verification(VerificationFlavor::Regular);
+        FUNS.iter()
+            .map(|name| {
+                let fun_env = env.get_function(find_fun(&env, name));
+                let target = targets.get_target(&fun_env, &variant);
+                (*name, digester.variant_digest(&target))
+            })
+            .collect()
+    }
+
+    fn digests(source: &str) -> BTreeMap<&'static str, String> {
+        digests_with(source, &Options::default())
+    }
+
+    /// Returns the names of the functions whose verification variants are left in the targets.
+    fn verified_funs(env: &GlobalEnv, targets: &FunctionTargetsHolder) -> BTreeSet<String> {
+        targets
+            .get_funs_and_variants()
+            .filter(|(_, variant)| variant.is_verified())
+            .map(|(fun_id, _)| env.get_function(fun_id).get_full_name_str())
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache_dir = cache_dir.path().to_string_lossy().to_string();
+        let options = Options::default();
+        let prune = |source: &str| {
+            let (env, mut targets) = build(source);
+            let cache = VerificationCache::prune_targets(&env, &options, &cache_dir, &mut targets);
+            (env, targets, cache)
+        };
+
+        let (env, targets, cache) = prune(SOURCE);
+        assert_eq!(verified_funs(&env, &targets), names(FUNS));
+
+        // Nothing is recorded if errors cannot be attributed to functions.
+        cache.record(&env, None);
+        let (env, targets, cache) = prune(SOURCE);
+        assert_eq!(verified_funs(&env, &targets), names(FUNS));
+
+        // The variants of failed functions are verified again.
+        let failed = vec![find_fun(&env, "M::f")].into_iter().collect();
+        cache.record(&env, Some(&failed));
+        let (env, targets, _) = prune(SOURCE);
+        assert_eq!(verified_funs(&env, &targets), names(&["M::f"]));
+
+        // So are the variants which transitively call a changed function.
+        let (env, targets, _) = prune(&SOURCE.replace("1 else f(x - 1)", "2 else f(x - 1)"));
+        assert_eq!(
+            verified_funs(&env, &targets),
+            names(&["M::f", "M::g", "M::h", "M::k"])
+        );
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let before = digests(SOURCE);
+        assert_eq!(digests(SOURCE), before);
+
+        // Changing a function changes the digests of its transitive callers only.
+        let after = digests(&SOURCE.replace("{ g(x) }", "{ g(x + 1) }"));
+        assert_ne!(after["M::h"], before["M::h"]);
+        for name in &["M::f", "M::g", "M::k", "M::get"] {
+            assert_eq!(after[name], before[name], "{}", name);
+        }
+
+        // Changing a spec function changes the digests of the specs using it.
+        let after = digests(&SOURCE.replace("{ x * 2 }", "{ x + x }"));
+        assert_ne!(after["M::get"], before["M::get"]);
+        assert_eq!(after["M::f"], before["M::f"]);
+
+        // Changing the global declarations or the options changes all digests.
+        let mut options = Options::default();
+        options.backend.vc_timeout += 1;
+        for after in vec![
+            digests(&SOURCE.replace("double(1) == 2", "double(2) == 4")),
+            digests(&SOURCE.replace("counter: num", "counter: u64")),
+            digests(&SOURCE.replace("{ x: u64 }", "{ x: u64, y: bool }")),
+            digests_with(SOURCE, &options),
+        ] {
+            for name in FUNS {
+                assert_ne!(after[name], before[name], "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_recursion() {
+        // `h` is digested first, while the digest of `f` is computed within the open cycle of
+        // `g`. The digest of `k` must still include the code of `g`, which `f` calls.
+        let before = digests(SOURCE);
+        let after = digests(&SOURCE.replace("1 else f(x - 1)", "2 else f(x - 1)"));
+        for name in FUNS.iter().filter(|name| **name != "M::get") {
+            assert_ne!(after[name], before[name], "{}", name);
+        }
+        assert_eq!(after["M::get"], before["M::get"]);
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_binary_format::{access::ModuleAccess, file_format::Bytecode};
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    value::{MoveTypeLayout, MoveValue},
+    vm_status::StatusCode,
+};
+use move_model::run_model_builder;
+use move_prover::checked_build::{build_checked_modules, CheckedBuild, CheckedBuildOptions};
+use move_vm_runtime::move_vm::MoveVM;
+use move_vm_test_utils::InMemoryStorage;
+use move_vm_types::gas_schedule::GasStatus;
+use std::fs;
+
+const ABORT_CODE: u64 = 42;
+
+const SOURCE: &str = r#"
+module 0x42::Checked {
+    struct R has key { v: u64 }
+
+    fun add(x: u64, y: u64): u64 {
+        x + y
+    }
+    spec add {
+        requires x < 100;
+        ensures result == x + y;
+    }
+
+    fun sub(x: u64, y: u64): u64 {
+        x - y
+    }
+    spec sub {
+        ensures result == x - y;
+    }
+
+    fun has_r(addr: address): bool {
+        exists<R>(addr)
+    }
+    spec has_r {
+        ensures result == old(exists<R>(addr));
+    }
+
+    fun inc(x: u64): u64 {
+        x + 1
+    }
+    spec inc {
+        ensures result == old(x) + 1;
+    }
+
+    fun bad_inc(x: u64): u64 {
+        x + 2
+    }
+    spec bad_inc {
+        ensures result == old(x) + 1;
+    }
+
+    fun choose(x: u64, flag: bool): u64 {
+        spec {
+            assert x > 0;
+        };
+        let y = if (flag) 1 else 2;
+        y + copy x
+    }
+
+    fun count(n: u64): u64 {
+        let i = 0;
+        while (i < copy n) {
+            i = i + 1;
+            spec {
+                assert n < 10;
+            };
+        };
+        i
+    }
+}
+"#;
+
+fn build() -> CheckedBuild {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Checked.move");
+    fs::write(&path, SOURCE).unwrap();
+    let env = run_model_builder(&[path.to_string_lossy().to_string()], &[]).unwrap();
+    assert!(!env.has_errors());
+
+    let options = CheckedBuildOptions {
+        abort_code: ABORT_CODE,
+        ..Default::default()
+    };
+    build_checked_modules(&env, &options)
+}
+
+/// Runs a function of the checked module, and returns its result, of the given layout, or the
+/// abort code it aborted with.
+fn run(
+    build: &CheckedBuild,
+    fun: &str,
+    args: Vec<MoveValue>,
+    layout: MoveTypeLayout,
+) -> Result<MoveValue, u64> {
+    let module = &build.modules["Checked"];
+    let mut blob = vec![];
+    module.serialize(&mut blob).unwrap();
+    let mut storage = InMemoryStorage::new();
+    storage.publish_or_overwrite_module(module.self_id(), blob);
+
+    let vm = MoveVM::new(vec![]).unwrap();
+    let mut session = vm.new_session(&storage);
+    let args = args
+        .into_iter()
+        .map(|arg| arg.simple_serialize().unwrap())
+        .collect();
+    let result = session.execute_function(
+        &module.self_id(),
+        &Identifier::new(fun).unwrap(),
+        vec![],
+        args,
+        &mut GasStatus::new_unmetered(),
+    );
+    match result {
+        Ok(return_vals) => {
+            assert_eq!(return_vals.len(), 1);
+            Ok(MoveValue::simple_deserialize(&return_vals[0], &layout).unwrap())
+        }
+        Err(err) => {
+            assert_eq!(err.major_status(), StatusCode::ABORTED);
+            Err(err.sub_status().unwrap())
+        }
+    }
+}
+
+fn run_u64(build: &CheckedBuild, fun: &str, args: Vec<MoveValue>) -> Result<u64, u64> {
+    run(build, fun, args, MoveTypeLayout::U64).map(|val| match val {
+        MoveValue::U64(val) => val,
+        _ => unreachable!(),
+    })
+}
+
+#[test]
+fn checked_build() {
+    let build = build();
+    assert_eq!(build.checks, 7);
+    // Subtractions may underflow, so are not checked
+    assert_eq!(build.skipped.len(), 1);
+    assert!(build.skipped[0].reason.contains("Sub"));
+
+    let module = &build.modules["Checked"];
+    for def in module.function_defs() {
+        let name = module.identifier_at(module.function_handle_at(def.function).name);
+        let code = &def.code.as_ref().unwrap().code;
+        for instr in code {
+            if let Some(offset) = instr.offset() {
+                assert!((*offset as usize) < code.len());
+            }
+        }
+        let checks = code
+            .iter()
+            .filter(|instr| matches!(instr, Bytecode::LdU64(ABORT_CODE)))
+            .count();
+        match name.as_str() {
+            "add" => assert_eq!(checks, 2),
+            "sub" => assert_eq!(checks, 0),
+            "has_r" | "inc" | "bad_inc" | "choose" | "count" => assert_eq!(checks, 1),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[test]
+fn checked_build_verifies() {
+    let build = build();
+    assert!(!build.modules.is_empty());
+    for module in build.modules.values() {
+        bytecode_verifier::verify_module(module).unwrap();
+    }
+}
+
+#[test]
+fn checked_build_runs_requires_and_ensures() {
+    use MoveValue::*;
+    let build = build();
+
+    assert_eq!(run_u64(&build, "add", vec![U64(1), U64(2)]), Ok(3));
+    assert_eq!(
+        run_u64(&build, "add", vec![U64(100), U64(2)]),
+        Err(ABORT_CODE)
+    );
+
+    let addr = AccountAddress::from_hex_literal("0x1").unwrap();
+    assert_eq!(
+        run(&build, "has_r", vec![Address(addr)], MoveTypeLayout::Bool),
+        Ok(Bool(false))
+    );
+
+    // `old(..)` of a parameter is its value at function entry
+    assert_eq!(run_u64(&build, "inc", vec![U64(1)]), Ok(2));
+    assert_eq!(run_u64(&build, "bad_inc", vec![U64(1)]), Err(ABORT_CODE));
+}
+
+#[test]
+fn checked_build_runs_asserts() {
+    use MoveValue::*;
+    let build = build();
+
+    // The branches of the function, which follow the check, still reach their targets
+    assert_eq!(run_u64(&build, "choose", vec![U64(1), Bool(true)]), Ok(2));
+    assert_eq!(run_u64(&build, "choose", vec![U64(1), Bool(false)]), Ok(3));
+    assert_eq!(
+        run_u64(&build, "choose", vec![U64(0), Bool(true)]),
+        Err(ABORT_CODE)
+    );
+    assert_eq!(
+        run_u64(&build, "choose", vec![U64(0), Bool(false)]),
+        Err(ABORT_CODE)
+    );
+
+    // The check in the loop body runs at each iteration, and the loop branches around it
+    assert_eq!(run_u64(&build, "count", vec![U64(0)]), Ok(0));
+    assert_eq!(run_u64(&build, "count", vec![U64(3)]), Ok(3));
+    assert_eq!(run_u64(&build, "count", vec![U64(12)]), Err(ABORT_CODE));
+}
@@ -0,0 +1,29 @@
+// flag: --fuzz
+// no-boogie-test
+module 0x42::TestFuzz {
+    // The implementation satisfies the specification for all arguments.
+    public fun add(x: u64, y: u64): u64 {
+        x + y
+    }
+    spec add {
+        aborts_if x + y > MAX_U64;
+        ensures result == x + y;
+    }
+
+    // The arguments which do not meet the precondition are discarded.
+    public fun div(x: u64, y: u64): u64 {
+        x / y
+    }
+    spec div {
+        requires y > 0;
+        ensures result == x / y;
+    }
+
+    // The postcondition does not hold when `y` is greater than `x`.
+    public fun max_incorrect(x: u64, y: u64): u64 {
+        if (x > y) x else y
+    }
+    spec max_incorrect {
+        ensures result == x;
+    }
+}
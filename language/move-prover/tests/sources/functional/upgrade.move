@@ -0,0 +1,36 @@
+// flag: --upgrade-from=tests/sources/functional/upgrade_old.move
+module 0x42::TestUpgrade {
+
+    struct R has key { value: u64 }
+
+    fun store(s: &signer, value: u64) {
+       move_to<R>(s, R{value})
+    }
+
+    fun store_incorrect(s: &signer, value: u64) {
+        move_to<R>(s, R{value})
+    }
+}
+
+spec 0x42::TestUpgrade {
+    use Std::Signer;
+
+    // The invariant of the deployed version is no longer declared, but still verified.
+
+    spec store(s: &signer, value: u64) {
+        requires value > 0;
+        include Store;
+    }
+
+    spec store_incorrect(s: &signer, value: u64) {
+        include Store;
+    }
+
+    spec schema Store {
+        s: signer;
+        value: u64;
+        let addr = Signer::spec_address_of(s);
+        ensures exists<R>(addr);
+        ensures global<R>(addr).value == value;
+    }
+}
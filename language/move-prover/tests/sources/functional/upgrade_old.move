@@ -0,0 +1,17 @@
+// The deployed version of the module upgraded in `upgrade.move`.
+module 0x42::TestUpgrade {
+
+    struct R has key { value: u64 }
+
+    fun store(s: &signer, value: u64) {
+       move_to<R>(s, R{value})
+    }
+}
+
+spec 0x42::TestUpgrade {
+    invariant forall addr: address where exists<R>(addr): global<R>(addr).value > 0;
+
+    spec store(s: &signer, value: u64) {
+        requires value > 0;
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use bytecode::invariant_explanation::InvariantQuery;
+use move_model::{model::GlobalEnv, run_model_builder};
+use move_prover::{cli::Options, explain_invariant};
+use std::fs;
+
+const SOURCE: &str = r#"
+module 0x42::Counter {
+    struct Counter has key { value: u64 }
+    struct Flag has key { set: bool }
+
+    public fun publish(account: &signer) {
+        move_to(account, Counter { value: 0 });
+    }
+
+    public fun increment(addr: address) acquires Counter {
+        let counter = borrow_global_mut<Counter>(addr);
+        counter.value = counter.value + 1;
+    }
+
+    public fun read(addr: address): u64 acquires Counter {
+        borrow_global<Counter>(addr).value
+    }
+
+    public fun publish_flag(account: &signer) {
+        move_to(account, Flag { set: true });
+    }
+
+    spec module {
+        invariant forall addr: address where exists<Counter>(addr):
+            global<Counter>(addr).value < 100;
+    }
+}
+"#;
+
+fn query(env: &GlobalEnv, function: &str) -> InvariantQuery {
+    let module_env = env
+        .get_modules()
+        .find(|module_env| module_env.matches_name("Counter"))
+        .unwrap();
+    let inv_id = env
+        .get_global_invariants_by_module(module_env.get_id())
+        .into_iter()
+        .next()
+        .unwrap();
+    InvariantQuery {
+        invariant: inv_id.as_usize(),
+        function: function.to_string(),
+    }
+}
+
+#[test]
+fn why_invariant() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Counter.move");
+    fs::write(&path, SOURCE).unwrap();
+    let env = run_model_builder(&[path.to_string_lossy().to_string()], &[]).unwrap();
+    assert!(!env.has_errors());
+    let options = Options::default();
+
+    let explanation = explain_invariant(&env, &options, &query(&env, "Counter::publish")).unwrap();
+    assert!(explanation.directly_modified);
+    assert_eq!(explanation.assertions.len(), 1);
+    let assertion = &explanation.assertions[0];
+    assert!(assertion.bytecode.contains("move_to"));
+    assert!(assertion.callee.is_none());
+    assert!(!assertion.deferred);
+    assert_eq!(assertion.memory, vec!["Counter::Counter".to_string()]);
+
+    let explanation =
+        explain_invariant(&env, &options, &query(&env, "0x42::Counter::increment")).unwrap();
+    assert!(explanation.assumed_at_entry);
+    assert!(!explanation.assertions.is_empty());
+    assert!(explanation
+        .assertions
+        .iter()
+        .all(|assertion| assertion.memory == vec!["Counter::Counter".to_string()]));
+
+    let explanation = explain_invariant(&env, &options, &query(&env, "Counter::read")).unwrap();
+    assert!(explanation.accessed && !explanation.modified);
+    assert!(explanation.assumed_at_entry);
+    assert!(explanation.assertions.is_empty());
+
+    let explanation =
+        explain_invariant(&env, &options, &query(&env, "Counter::publish_flag")).unwrap();
+    assert!(!explanation.accessed);
+    assert!(!explanation.assumed_at_entry);
+    assert!(explanation.assertions.is_empty());
+
+    assert!(explain_invariant(&env, &options, &query(&env, "Counter::missing")).is_err());
+}
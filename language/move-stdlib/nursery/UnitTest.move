@@ -9,4 +9,12 @@ module Std::UnitTest {
     /// This will cause a linking failure if an attempt is made to publish a
     /// test module in a VM that isn't in unit test mode.
     native public fun create_signers_for_testing(num_signers: u64): vector<signer>;
+
+    /// Expect the test to emit an event with the payload `msg`. The test fails
+    /// if no such event is emitted by the time it returns.
+    native public fun expect_event<T: drop + store>(msg: T);
+
+    /// Expect the test to emit exactly `count` events of type `T`. The test
+    /// fails if another number of such events is emitted by the time it returns.
+    native public fun expect_event_count<T: drop + store>(count: u64);
 }
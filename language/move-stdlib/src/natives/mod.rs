@@ -49,6 +49,14 @@ pub fn all_natives(move_std_addr: AccountAddress) -> NativeFunctionTable {
             "create_signers_for_testing",
             unit_test::native_create_signers_for_testing,
         ),
+        #[cfg(feature = "testing")]
+        ("UnitTest", "expect_event", unit_test::native_expect_event),
+        #[cfg(feature = "testing")]
+        (
+            "UnitTest",
+            "expect_event_count",
+            unit_test::native_expect_event_count,
+        ),
     ];
     NATIVES
         .iter()
@@ -1,8 +1,10 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use move_binary_format::errors::PartialVMResult;
-use move_core_types::gas_schedule::ONE_GAS_UNIT;
+use move_binary_format::errors::{PartialVMError, PartialVMResult};
+use move_core_types::{
+    effects::Event, gas_schedule::ONE_GAS_UNIT, language_storage::TypeTag, vm_status::StatusCode,
+};
 use move_vm_runtime::native_functions::NativeContext;
 use move_vm_types::{
     loaded_data::runtime_types::Type, natives::function::NativeResult, pop_arg, values::Value,
@@ -12,6 +14,65 @@ use std::collections::VecDeque;
 
 use move_core_types::account_address::AccountAddress;
 
+/// GUID of the events recording the payloads expected by `UnitTest::expect_event`.
+const EXPECTED_EVENT_GUID: &[u8] = b"UnitTest::expect_event";
+
+/// Prefix of the GUID of the events recording the counts expected by
+/// `UnitTest::expect_event_count`, followed by the BCS-serialized type of the counted events.
+const EXPECTED_EVENT_COUNT_GUID_PREFIX: &[u8] = b"UnitTest::expect_event_count::";
+
+/// An expectation of a test on the events it emits.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EventExpectation {
+    /// An event of the type with the BCS-serialized payload is emitted.
+    Payload(TypeTag, Vec<u8>),
+    /// Exactly this number of events of the type are emitted.
+    Count(TypeTag, u64),
+}
+
+impl EventExpectation {
+    /// Splits the events emitted by a test into the expectations recorded by the test and the
+    /// events actually emitted.
+    pub fn split_events(events: Vec<Event>) -> (Vec<EventExpectation>, Vec<Event>) {
+        let mut expectations = vec![];
+        let mut emitted_events = vec![];
+        for event in events {
+            match Self::from_event(&event) {
+                Some(expectation) => expectations.push(expectation),
+                None => emitted_events.push(event),
+            }
+        }
+        (expectations, emitted_events)
+    }
+
+    fn from_event((guid, _, type_tag, payload): &Event) -> Option<Self> {
+        if guid.as_slice() == EXPECTED_EVENT_GUID {
+            return Some(EventExpectation::Payload(type_tag.clone(), payload.clone()));
+        }
+        let counted_type = guid.strip_prefix(EXPECTED_EVENT_COUNT_GUID_PREFIX)?;
+        Some(EventExpectation::Count(
+            bcs::from_bytes(counted_type).ok()?,
+            bcs::from_bytes(payload).ok()?,
+        ))
+    }
+
+    /// Returns true if `events` meet the expectation.
+    pub fn is_met_by(&self, events: &[Event]) -> bool {
+        match self {
+            EventExpectation::Payload(type_tag, payload) => events
+                .iter()
+                .any(|(_, _, tag, blob)| tag == type_tag && blob == payload),
+            EventExpectation::Count(type_tag, count) => {
+                events
+                    .iter()
+                    .filter(|(_, _, tag, _)| tag == type_tag)
+                    .count() as u64
+                    == *count
+            }
+        }
+    }
+}
+
 pub fn native_create_signers_for_testing(
     _context: &mut NativeContext,
     ty_args: Vec<Type>,
@@ -27,3 +88,46 @@ pub fn native_create_signers_for_testing(
 
     Ok(NativeResult::ok(ONE_GAS_UNIT, smallvec![signers]))
 }
+
+/// Records the expected payload as an event, which is checked by the test runner against the
+/// events emitted by the test.
+pub fn native_expect_event(
+    context: &mut NativeContext,
+    mut ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(args.len() == 1);
+
+    let ty = ty_args.pop().unwrap();
+    let msg = args.pop_back().unwrap();
+    if !context.save_event(EXPECTED_EVENT_GUID.to_vec(), 0, ty, msg)? {
+        return Ok(NativeResult::err(ONE_GAS_UNIT, 0));
+    }
+
+    Ok(NativeResult::ok(ONE_GAS_UNIT, smallvec![]))
+}
+
+/// Records the expected count as an event, which is checked by the test runner against the
+/// events emitted by the test.
+pub fn native_expect_event_count(
+    context: &mut NativeContext,
+    mut ty_args: Vec<Type>,
+    mut args: VecDeque<Value>,
+) -> PartialVMResult<NativeResult> {
+    debug_assert!(ty_args.len() == 1);
+    debug_assert!(args.len() == 1);
+
+    let ty = ty_args.pop().unwrap();
+    let count = pop_arg!(args, u64);
+    let mut guid = EXPECTED_EVENT_COUNT_GUID_PREFIX.to_vec();
+    guid.extend(
+        bcs::to_bytes(&context.type_to_type_tag(&ty)?)
+            .map_err(|_| PartialVMError::new(StatusCode::VALUE_SERIALIZATION_ERROR))?,
+    );
+    if !context.save_event(guid, 0, Type::U64, Value::u64(count))? {
+        return Ok(NativeResult::err(ONE_GAS_UNIT, 0));
+    }
+
+    Ok(NativeResult::ok(ONE_GAS_UNIT, smallvec![]))
+}
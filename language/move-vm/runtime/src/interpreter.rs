@@ -268,7 +268,7 @@ impl Interpreter {
         let mut native_context = NativeContext::new(self, data_store, gas_status, resolver);
         let native_function = function.get_native()?;
         let result = native_function(&mut native_context, ty_args, arguments)?;
-        gas_status.deduct_gas(result.cost)?;
+        gas_status.charge_native(result.cost, || function.pretty_string())?;
         let return_values = result
             .result
             .map_err(|code| PartialVMError::new(StatusCode::ABORTED).with_sub_status(code))?;
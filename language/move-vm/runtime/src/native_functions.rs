@@ -7,6 +7,7 @@ use move_core_types::{
     account_address::AccountAddress,
     gas_schedule::CostTable,
     identifier::Identifier,
+    language_storage::TypeTag,
     value::MoveTypeLayout,
     vm_status::{StatusCode, StatusType},
 };
@@ -104,6 +105,10 @@ impl<'a> NativeContext<'a> {
         }
     }
 
+    pub fn type_to_type_tag(&self, ty: &Type) -> PartialVMResult<TypeTag> {
+        self.resolver.loader().type_to_type_tag(ty)
+    }
+
     pub fn type_to_type_layout(&self, ty: &Type) -> PartialVMResult<Option<MoveTypeLayout>> {
         match self.resolver.type_to_type_layout(ty) {
             Ok(ty_layout) => Ok(Some(ty_layout)),
@@ -109,11 +109,21 @@ impl VMRuntime {
                 let old_module = old_module_ref.module();
                 let old_m = normalized::Module::new(old_module);
                 let new_m = normalized::Module::new(module);
-                let compat = Compatibility::check(&old_m, &new_m);
-                if !compat.is_fully_compatible() {
+                let changes = Compatibility::changes(&old_m, &new_m);
+                if !Compatibility::from_changes(&changes).is_fully_compatible() {
+                    let breaking_changes = changes
+                        .iter()
+                        .filter(|change| change.is_breaking())
+                        .map(|change| change.to_string())
+                        .collect::<Vec<_>>();
                     return Err(PartialVMError::new(
                         StatusCode::BACKWARD_INCOMPATIBLE_MODULE_UPDATE,
                     )
+                    .with_message(format!(
+                        "breaking changes to module {}: {}",
+                        module_id,
+                        breaking_changes.join(", ")
+                    ))
                     .finish(Location::Undefined));
                 }
             }
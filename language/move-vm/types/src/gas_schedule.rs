@@ -18,8 +18,8 @@ use move_binary_format::{
 };
 use move_core_types::{
     gas_schedule::{
-        AbstractMemorySize, CostTable, GasAlgebra, GasCarrier, GasConstants, GasCost, GasUnits,
-        InternalGasUnits, MAX_TRANSACTION_SIZE_IN_BYTES,
+        AbstractMemorySize, CostTable, GasAlgebra, GasCarrier, GasConstants, GasCost, GasProfile,
+        GasUnits, InternalGasUnits, MAX_TRANSACTION_SIZE_IN_BYTES,
     },
     vm_status::StatusCode,
 };
@@ -37,6 +37,7 @@ pub struct GasStatus<'a> {
     cost_table: &'a CostTable,
     gas_left: InternalGasUnits<GasCarrier>,
     charge: bool,
+    profile: Option<GasProfile>,
 }
 
 impl<'a> GasStatus<'a> {
@@ -49,6 +50,7 @@ impl<'a> GasStatus<'a> {
             gas_left: cost_table.gas_constants.to_internal_units(gas_left),
             cost_table,
             charge: true,
+            profile: None,
         }
     }
 
@@ -61,9 +63,21 @@ impl<'a> GasStatus<'a> {
             gas_left: InternalGasUnits::new(0),
             cost_table: &ZERO_COST_SCHEDULE,
             charge: false,
+            profile: None,
         }
     }
 
+    /// Start recording the gas charged in a `GasProfile`. This is meant for debugging, and only
+    /// covers gas charged with the `charge_*` functions.
+    pub fn enable_profiling(&mut self) {
+        self.profile.get_or_insert_with(GasProfile::default);
+    }
+
+    /// Return the gas charged so far, if profiling is enabled.
+    pub fn profile(&self) -> Option<&GasProfile> {
+        self.profile.as_ref()
+    }
+
     /// Return the `CostTable` behind this `GasStatus`.
     pub fn cost_table(&self) -> &CostTable {
         self.cost_table
@@ -78,9 +92,23 @@ impl<'a> GasStatus<'a> {
 
     /// Charge a given amount of gas and fail if not enough gas units are left.
     pub fn deduct_gas(&mut self, amount: InternalGasUnits<GasCarrier>) -> PartialVMResult<()> {
+        self.deduct_gas_and_profile(amount, |_, _| ())
+    }
+
+    /// Charge a given amount of gas, and record the gas actually charged with `record` when
+    /// profiling.
+    fn deduct_gas_and_profile(
+        &mut self,
+        amount: InternalGasUnits<GasCarrier>,
+        record: impl FnOnce(&mut GasProfile, GasCarrier),
+    ) -> PartialVMResult<()> {
         if !self.charge {
             return Ok(());
         }
+        let charged = std::cmp::min(amount.get(), self.gas_left.get());
+        if let Some(profile) = &mut self.profile {
+            record(profile, charged);
+        }
         if self
             .gas_left
             .app(&amount, |curr_gas, gas_amt| curr_gas >= gas_amt)
@@ -94,6 +122,23 @@ impl<'a> GasStatus<'a> {
         }
     }
 
+    /// Charge an instruction, recording it under its class or storage operation when profiling.
+    fn charge_opcode(
+        &mut self,
+        opcode: Opcodes,
+        amount: InternalGasUnits<GasCarrier>,
+    ) -> PartialVMResult<()> {
+        self.deduct_gas_and_profile(amount, |profile, charged| {
+            let costs = match storage_operation(opcode) {
+                Some(operation) => profile.storage.entry(operation.to_string()),
+                None => profile
+                    .instructions
+                    .entry(instruction_class(opcode).to_string()),
+            };
+            *costs.or_default() += charged;
+        })
+    }
+
     /// Charge an instruction over data with a given size and fail if not enough gas units are left.
     pub fn charge_instr_with_size(
         &mut self,
@@ -103,7 +148,8 @@ impl<'a> GasStatus<'a> {
         // Make sure that the size is always non-zero
         let size = size.map(|x| std::cmp::max(1, x));
         debug_assert!(size.get() > 0);
-        self.deduct_gas(
+        self.charge_opcode(
+            opcode,
             self.cost_table
                 .instruction_cost(opcode as u8)
                 .total()
@@ -113,7 +159,34 @@ impl<'a> GasStatus<'a> {
 
     /// Charge an instruction and fail if not enough gas units are left.
     pub fn charge_instr(&mut self, opcode: Opcodes) -> PartialVMResult<()> {
-        self.deduct_gas(self.cost_table.instruction_cost(opcode as u8).total())
+        self.charge_opcode(
+            opcode,
+            self.cost_table.instruction_cost(opcode as u8).total(),
+        )
+    }
+
+    /// Charge the cost of a call to a native function and fail if not enough gas units are left.
+    /// `function_name` is only evaluated when profiling.
+    pub fn charge_native(
+        &mut self,
+        amount: InternalGasUnits<GasCarrier>,
+        function_name: impl FnOnce() -> String,
+    ) -> PartialVMResult<()> {
+        self.deduct_gas_and_profile(amount, |profile, charged| {
+            *profile.natives.entry(function_name()).or_default() += charged;
+        })
+    }
+
+    /// Charge a global storage operation not covered by bytecode instructions, such as writing
+    /// the effects of a transaction, and fail if not enough gas units are left.
+    pub fn charge_storage(
+        &mut self,
+        operation: &str,
+        amount: InternalGasUnits<GasCarrier>,
+    ) -> PartialVMResult<()> {
+        self.deduct_gas_and_profile(amount, |profile, charged| {
+            *profile.storage.entry(operation.to_string()).or_default() += charged;
+        })
     }
 
     /// Charge gas related to the overall size of a transaction and fail if not enough
@@ -123,7 +196,7 @@ impl<'a> GasStatus<'a> {
         intrinsic_cost: AbstractMemorySize<GasCarrier>,
     ) -> VMResult<()> {
         let cost = calculate_intrinsic_gas(intrinsic_cost, &self.cost_table.gas_constants);
-        self.deduct_gas(cost)
+        self.deduct_gas_and_profile(cost, |profile, charged| profile.intrinsic += charged)
             .map_err(|e| e.finish(Location::Undefined))
     }
 
@@ -132,6 +205,59 @@ impl<'a> GasStatus<'a> {
     }
 }
 
+/// The global storage operation performed by `opcode`, if any.
+fn storage_operation(opcode: Opcodes) -> Option<&'static str> {
+    use Opcodes::*;
+
+    Some(match opcode {
+        EXISTS | EXISTS_GENERIC => "exists",
+        MUT_BORROW_GLOBAL
+        | MUT_BORROW_GLOBAL_GENERIC
+        | IMM_BORROW_GLOBAL
+        | IMM_BORROW_GLOBAL_GENERIC => "borrow_global",
+        MOVE_FROM | MOVE_FROM_GENERIC => "move_from",
+        MOVE_TO | MOVE_TO_GENERIC => "move_to",
+        _ => return None,
+    })
+}
+
+/// The class of `opcode`, under which the gas it's charged is profiled.
+fn instruction_class(opcode: Opcodes) -> &'static str {
+    use Opcodes::*;
+
+    match opcode {
+        POP | COPY_LOC | MOVE_LOC | ST_LOC => "local",
+        LD_U8 | LD_U64 | LD_U128 | LD_CONST | LD_TRUE | LD_FALSE => "constant",
+        RET | BR_TRUE | BR_FALSE | BRANCH | ABORT | NOP => "control_flow",
+        CALL | CALL_GENERIC => "call",
+        PACK | PACK_GENERIC | UNPACK | UNPACK_GENERIC => "struct",
+        MUT_BORROW_LOC
+        | IMM_BORROW_LOC
+        | MUT_BORROW_FIELD
+        | MUT_BORROW_FIELD_GENERIC
+        | IMM_BORROW_FIELD
+        | IMM_BORROW_FIELD_GENERIC
+        | READ_REF
+        | WRITE_REF
+        | FREEZE_REF => "reference",
+        ADD | SUB | MUL | MOD | DIV | BIT_OR | BIT_AND | XOR | SHL | SHR | CAST_U8 | CAST_U64
+        | CAST_U128 => "arithmetic",
+        OR | AND | NOT | EQ | NEQ | LT | GT | LE | GE => "logic",
+        VEC_PACK | VEC_LEN | VEC_IMM_BORROW | VEC_MUT_BORROW | VEC_PUSH_BACK | VEC_POP_BACK
+        | VEC_UNPACK | VEC_SWAP => "vector",
+        EXISTS
+        | EXISTS_GENERIC
+        | MUT_BORROW_GLOBAL
+        | MUT_BORROW_GLOBAL_GENERIC
+        | IMM_BORROW_GLOBAL
+        | IMM_BORROW_GLOBAL_GENERIC
+        | MOVE_FROM
+        | MOVE_FROM_GENERIC
+        | MOVE_TO
+        | MOVE_TO_GENERIC => "global",
+    }
+}
+
 pub fn new_from_instructions(
     mut instrs: Vec<(Bytecode, GasCost)>,
     native_table: Vec<GasCost>,
@@ -11,7 +11,7 @@ use move_binary_format::{
     binary_views::BinaryIndexedView,
     control_flow_graph::{ControlFlowGraph, VMControlFlowGraph},
     file_format::{
-        Ability, AbilitySet, Bytecode, FieldHandleIndex, FunctionDefinition,
+        Ability, AbilitySet, Bytecode, CodeOffset, FieldHandleIndex, FunctionDefinition,
         FunctionDefinitionIndex, Signature, SignatureIndex, SignatureToken, StructDefinition,
         StructDefinitionIndex, StructFieldInformation, StructTypeParameter, TableIndex,
         TypeSignature, Visibility,
@@ -35,6 +35,9 @@ pub struct DisassemblerOptions {
 
     /// Print the locals inside each function body.
     pub print_locals: bool,
+
+    /// Print the source lines the instructions stem from, if the source code is available.
+    pub print_source: bool,
 }
 
 impl DisassemblerOptions {
@@ -44,6 +47,7 @@ impl DisassemblerOptions {
             print_code: false,
             print_basic_blocks: false,
             print_locals: false,
+            print_source: false,
         }
     }
 }
@@ -814,7 +818,7 @@ impl<'a> Disassembler<'a> {
             })
             .collect::<Result<Vec<String>>>()?;
 
-        let mut instrs: Vec<String> = instrs
+        let instrs: Vec<String> = instrs
             .into_iter()
             .enumerate()
             .map(|(instr_index, dis_instr)| {
@@ -825,6 +829,7 @@ impl<'a> Disassembler<'a> {
                 )
             })
             .collect();
+        let mut instrs = self.interleave_source(function_source_map, instrs);
 
         if self.options.print_basic_blocks {
             let cfg = VMControlFlowGraph::new(&code.code);
@@ -839,6 +844,27 @@ impl<'a> Disassembler<'a> {
         Ok(instrs)
     }
 
+    /// Prefixes each instruction with the source line it stems from, unless the previous
+    /// instruction stems from the same line.
+    fn interleave_source(
+        &self,
+        function_source_map: &FunctionSourceMap,
+        instrs: Vec<String>,
+    ) -> Vec<String> {
+        let source = match &self.source_mapper.source_code {
+            Some((_, source)) if self.options.print_source => source,
+            _ => return instrs,
+        };
+        let code_starts = (0..instrs.len())
+            .map(|pc| {
+                function_source_map
+                    .get_code_location(pc as CodeOffset)
+                    .map(|loc| loc.start() as usize)
+            })
+            .collect::<Vec<_>>();
+        interleave_source_lines(source, &code_starts, instrs)
+    }
+
     fn disassemble_struct_type_formals(
         source_map_ty_params: &[SourceName],
         type_parameters: &[StructTypeParameter],
@@ -1129,3 +1155,81 @@ impl<'a> Disassembler<'a> {
         ))
     }
 }
+
+/// Prefixes each instruction with the line of `source` containing the byte offset in
+/// `code_starts` at which the instruction's code starts, unless the previous instruction stems
+/// from the same line.
+fn interleave_source_lines(
+    source: &str,
+    code_starts: &[Option<usize>],
+    instrs: Vec<String>,
+) -> Vec<String> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect();
+    let mut last_line = None;
+    instrs
+        .into_iter()
+        .enumerate()
+        .map(|(pc, instr)| {
+            let line = match code_starts
+                .get(pc)
+                .copied()
+                .flatten()
+                .map(|start| line_starts.partition_point(|line_start| *line_start <= start))
+            {
+                Some(line) if Some(line) != last_line => line,
+                _ => return instr,
+            };
+            last_line = Some(line);
+            let end = line_starts
+                .get(line)
+                .copied()
+                .unwrap_or_else(|| source.len());
+            match source.get(line_starts[line - 1]..end) {
+                Some(text) => format!("\t// {}: {}\n{}", line, text.trim(), instr),
+                None => instr,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interleave_source_lines() {
+        let source = "fun f(x: u64): u64 {\n    let y = x + 1;\n    y\n}\n";
+        let offset = |text: &str| Some(source.find(text).unwrap());
+        let instrs = vec![
+            "0: CopyLoc[0]",
+            "1: LdU64(1)",
+            "2: Add",
+            "3: StLoc[1]",
+            "4: MoveLoc[1]",
+        ]
+        .into_iter()
+        .map(|instr| instr.to_string())
+        .collect();
+        // Instructions stemming from the line of the previous one, or without location, are not
+        // prefixed.
+        let code_starts = [
+            offset("x + 1"),
+            offset("1;"),
+            offset("x + 1"),
+            None,
+            offset("y\n"),
+        ];
+        assert_eq!(
+            interleave_source_lines(source, &code_starts, instrs),
+            vec![
+                "\t// 2: let y = x + 1;\n0: CopyLoc[0]",
+                "1: LdU64(1)",
+                "2: Add",
+                "3: StLoc[1]",
+                "\t// 3: y\n4: MoveLoc[1]",
+            ]
+        );
+    }
+}
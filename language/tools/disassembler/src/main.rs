@@ -39,6 +39,11 @@ struct Args {
     #[structopt(long = "skip-basic-blocks")]
     pub skip_basic_blocks: bool,
 
+    /// Print the source lines the instructions stem from, read from the Move source file next to
+    /// the bytecode file.
+    #[structopt(long = "print-source")]
+    pub print_source: bool,
+
     /// Treat input file as a script (default is to treat file as a module)
     #[structopt(short = "s", long = "script")]
     pub is_script: bool,
@@ -87,6 +92,7 @@ fn main() {
     disassembler_options.only_externally_visible = args.skip_private;
     disassembler_options.print_basic_blocks = !args.skip_basic_blocks;
     disassembler_options.print_locals = !args.skip_locals;
+    disassembler_options.print_source = args.print_source;
 
     // TODO: make source mapping work with the Move source language
     let no_loc = Spanned::unsafe_no_loc(()).loc;
@@ -283,10 +283,15 @@ pub(crate) fn explain_publish_error(
             let old_module = state.get_module_by_id(&module_id)?.unwrap();
             let old_api = normalized::Module::new(&old_module);
             let new_api = normalized::Module::new(module);
-            let compat = Compatibility::check(&old_api, &new_api);
+            let changes = Compatibility::changes(&old_api, &new_api);
+            let compat = Compatibility::from_changes(&changes);
             // the only way we get this error code is compatibility checking failed, so assert here
             assert!(!compat.is_fully_compatible());
 
+            for change in changes.iter().filter(|change| change.is_breaking()) {
+                println!("  {}", change);
+            }
+
             if !compat.struct_layout {
                 // TODO: we could choose to make this more precise by walking the global state and looking for published
                 // structs of this type. but probably a bad idea
@@ -2,5 +2,6 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod coverage_map;
+pub mod line_coverage;
 pub mod source_coverage;
 pub mod summary;
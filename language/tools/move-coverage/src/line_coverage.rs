@@ -0,0 +1,243 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Line coverage of Move source files, computed from the execution counts of the bytecode
+//! instructions of their modules, and its output in the lcov and HTML formats.
+
+#![forbid(unsafe_code)]
+
+use crate::coverage_map::ExecCoverageMap;
+use bytecode_source_map::source_map::SourceMap;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{CodeOffset, FunctionDefinitionIndex},
+    CompiledModule,
+};
+use move_ir_types::location::Loc;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Name of the index page of the HTML report.
+const HTML_INDEX_FILE_NAME: &str = "index.html";
+
+const HTML_STYLE: &str = "\
+body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+td, th { padding: 2px 8px; text-align: left; }
+pre { margin: 0; }
+.covered { background-color: #c8f0c8; }
+.uncovered { background-color: #f0c8c8; }
+.hits { color: #808080; text-align: right; }";
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionLineCoverage {
+    /// Line of the declaration of the function, starting at 1.
+    pub line: usize,
+    /// Number of times the function was entered.
+    pub hits: u64,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FileLineCoverage {
+    /// Contents of the file.
+    pub source: String,
+    /// Coverage of the functions declared in the file, by `<module name>::<function name>`.
+    pub functions: BTreeMap<String, FunctionLineCoverage>,
+    /// Number of executions of the lines holding instructions, by line number starting at 1. A
+    /// line is executed as many times as its most executed instruction.
+    pub lines: BTreeMap<usize, u64>,
+}
+
+/// Line coverage of a set of source files, by file path.
+#[derive(Clone, Debug, Default)]
+pub struct LineCoverage {
+    pub files: BTreeMap<String, FileLineCoverage>,
+}
+
+impl FileLineCoverage {
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|hits| **hits > 0).count()
+    }
+
+    pub fn functions_hit(&self) -> usize {
+        self.functions.values().filter(|f| f.hits > 0).count()
+    }
+}
+
+impl LineCoverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the coverage of the functions of `module`, whose source is `file_contents`.
+    pub fn add_module(
+        &mut self,
+        module: &CompiledModule,
+        source_map: &SourceMap,
+        file_contents: &str,
+        exec_map: &ExecCoverageMap,
+    ) {
+        let module_id = module.self_id();
+        let module_map = exec_map
+            .module_maps
+            .get(&(*module_id.address(), module_id.name().to_owned()));
+        let line_starts = line_starts(file_contents);
+        let line_of =
+            |loc: Loc| line_starts.partition_point(|start| *start <= loc.start() as usize);
+
+        let file = self
+            .files
+            .entry(source_map.definition_location.file().to_string())
+            .or_insert_with(|| FileLineCoverage {
+                source: file_contents.to_string(),
+                ..FileLineCoverage::default()
+            });
+        for (idx, function_def) in module.function_defs().iter().enumerate() {
+            let code_unit = match &function_def.code {
+                Some(code_unit) => code_unit,
+                // Native functions have no instructions to cover
+                None => continue,
+            };
+            let function_def_idx = FunctionDefinitionIndex(idx as u16);
+            let function_map = match source_map.get_function_source_map(function_def_idx) {
+                Ok(function_map) => function_map,
+                Err(_) => continue,
+            };
+            let fn_name =
+                module.identifier_at(module.function_handle_at(function_def.function).name);
+            let function_coverage = module_map.and_then(|m| m.get_function_coverage(fn_name));
+            let hits_at = |offset: usize| {
+                function_coverage
+                    .and_then(|coverage| coverage.get(&(offset as u64)))
+                    .copied()
+                    .unwrap_or(0)
+            };
+
+            file.functions.insert(
+                format!("{}::{}", module_id.name(), fn_name),
+                FunctionLineCoverage {
+                    line: line_of(function_map.decl_location),
+                    hits: hits_at(0),
+                },
+            );
+            for offset in 0..code_unit.code.len() {
+                if let Some(loc) = function_map.get_code_location(offset as CodeOffset) {
+                    let line_hits = file.lines.entry(line_of(loc)).or_insert(0);
+                    *line_hits = (*line_hits).max(hits_at(offset));
+                }
+            }
+        }
+    }
+
+    /// Outputs the coverage in the lcov tracefile format.
+    pub fn output_lcov<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (path, file) in &self.files {
+            writeln!(writer, "TN:")?;
+            writeln!(writer, "SF:{}", path)?;
+            for (name, function) in &file.functions {
+                writeln!(writer, "FN:{},{}", function.line, name)?;
+            }
+            for (name, function) in &file.functions {
+                writeln!(writer, "FNDA:{},{}", function.hits, name)?;
+            }
+            writeln!(writer, "FNF:{}", file.functions.len())?;
+            writeln!(writer, "FNH:{}", file.functions_hit())?;
+            for (line, hits) in &file.lines {
+                writeln!(writer, "DA:{},{}", line, hits)?;
+            }
+            writeln!(writer, "LF:{}", file.lines.len())?;
+            writeln!(writer, "LH:{}", file.lines_hit())?;
+            writeln!(writer, "end_of_record")?;
+        }
+        Ok(())
+    }
+
+    /// Outputs the coverage as HTML pages in `output_dir`: an index summarizing the coverage of
+    /// each file, and a page per file with its lines highlighted according to their coverage.
+    pub fn output_html(&self, output_dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(output_dir)?;
+        let mut index = html_header("Move coverage");
+        index.push_str(
+            "<table>\n<tr><th>File</th><th>Lines</th><th>Line coverage</th>\
+             <th>Functions</th></tr>\n",
+        );
+        for (file_idx, (path, file)) in self.files.iter().enumerate() {
+            let page_name = format!("file_{}.html", file_idx);
+            index.push_str(&format!(
+                "<tr><td><a href=\"{}\">{}</a></td><td>{} / {}</td><td>{}</td>\
+                 <td>{} / {}</td></tr>\n",
+                page_name,
+                escape_html(path),
+                file.lines_hit(),
+                file.lines.len(),
+                percentage(file.lines_hit(), file.lines.len()),
+                file.functions_hit(),
+                file.functions.len(),
+            ));
+            fs::write(output_dir.join(page_name), file_page(path, file))?;
+        }
+        index.push_str("</table>\n</body>\n</html>\n");
+        fs::write(output_dir.join(HTML_INDEX_FILE_NAME), index)
+    }
+}
+
+fn file_page(path: &str, file: &FileLineCoverage) -> String {
+    let mut page = html_header(path);
+    page.push_str(&format!(
+        "<p><a href=\"{}\">Index</a></p>\n<table>\n",
+        HTML_INDEX_FILE_NAME
+    ));
+    for (line_idx, line) in file.source.lines().enumerate() {
+        let (class, hits) = match file.lines.get(&(line_idx + 1)) {
+            Some(0) => (" class=\"uncovered\"", "0".to_string()),
+            Some(hits) => (" class=\"covered\"", hits.to_string()),
+            None => ("", String::new()),
+        };
+        page.push_str(&format!(
+            "<tr{}><td class=\"hits\">{}</td><td class=\"hits\">{}</td>\
+             <td><pre>{}</pre></td></tr>\n",
+            class,
+            line_idx + 1,
+            hits,
+            escape_html(line),
+        ));
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+fn html_header(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape_html(title),
+        HTML_STYLE,
+        escape_html(title),
+    )
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn percentage(hit: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.2}%", hit as f64 * 100.0 / total as f64)
+    }
+}
+
+/// Byte offsets of the start of each line of `contents`.
+fn line_starts(contents: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(contents.match_indices('\n').map(|(idx, _)| idx + 1))
+        .collect()
+}
@@ -1,10 +1,16 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::resolution::resolution_graph::ResolvedGraph;
+use crate::{
+    resolution::resolution_graph::{ResolvedGraph, ResolvedPackage},
+    source_package::parsed_manifest::PragmaValue,
+};
 use anyhow::Result;
-use move_lang::shared::AddressBytes;
-use move_model::{model::GlobalEnv, options::ModelBuilderOptions, run_model_builder_with_options};
+use move_model::{
+    model::{GlobalEnv, PackageInfo, PackagePragmaValue},
+    options::ModelBuilderOptions,
+    run_model_builder_for_packages,
+};
 
 #[derive(Debug, Clone)]
 pub struct ModelBuilder {
@@ -16,12 +22,16 @@ impl ModelBuilder {
         Self { resolution_graph }
     }
 
-    // NOTE: If there are now renamings, then the root package has the global resolution of all named
-    // addresses in the package graph in scope. So we can simply grab all of the source files
-    // across all packages and build the Move model from that.
+    pub fn build_model(&self) -> Result<GlobalEnv> {
+        self.build_model_with_options(ModelBuilderOptions::default())
+    }
+
+    // NOTE: If there are no renamings, then each named address has the same value in all the
+    // packages of the graph in which it is in scope. So we can simply grab all of the source files
+    // across all packages, along with their named addresses, and build the Move model from that.
     // TODO: In the future we will need a better way to do this to support renaming in packages
     // where we want to support building a Move model.
-    pub fn build_model(&self) -> Result<GlobalEnv> {
+    pub fn build_model_with_options(&self, options: ModelBuilderOptions) -> Result<GlobalEnv> {
         // Make sure no renamings have been performed
         for (pkg_name, pkg) in self.resolution_graph.package_table.iter() {
             if !pkg.renaming.is_empty() {
@@ -33,39 +43,65 @@ impl ModelBuilder {
             }
         }
 
-        // Targets are all files in the root package
+        // Targets are all files in the root package, dependencies are all files in non-root
+        // packages
         let root_name = &self.resolution_graph.root_package.package.name;
-        let root_package = self.resolution_graph.get_package(root_name).clone();
-        let targets: Vec<_> = root_package
-            .get_sources(&self.resolution_graph.build_options)?
-            .into_iter()
-            .map(|symbol| symbol.to_string())
-            .collect();
-        // Dependencies are all files in non-root package
-        let deps = self
-            .resolution_graph
-            .package_table
-            .iter()
-            .flat_map(|(nm, pkg)| {
-                if nm == root_name {
-                    vec![]
-                } else {
-                    pkg.get_sources(&self.resolution_graph.build_options)
-                        .unwrap()
-                }
-            })
-            .map(|symbol| symbol.to_string())
-            .collect::<Vec<String>>();
+        let mut targets = vec![];
+        let mut deps = vec![];
+        for (pkg_name, pkg) in self.resolution_graph.package_table.iter() {
+            let package_info = self.package_info(pkg)?;
+            if pkg_name == root_name {
+                targets.push(package_info);
+            } else {
+                deps.push(package_info);
+            }
+        }
+
+        run_model_builder_for_packages(targets, deps, options)
+    }
 
-        run_model_builder_with_options(
-            &targets,
-            &deps,
-            ModelBuilderOptions::default(),
-            root_package
+    fn package_info(&self, package: &ResolvedPackage) -> Result<PackageInfo> {
+        let build_options = &self.resolution_graph.build_options;
+        let manifest = &package.source_package;
+        let mut dependencies = manifest.dependencies.keys().collect::<Vec<_>>();
+        if build_options.dev_mode {
+            dependencies.extend(manifest.dev_dependencies.keys());
+        }
+        let prover = manifest.prover.clone().unwrap_or_default();
+        Ok(PackageInfo {
+            name: manifest.package.name.to_string(),
+            version: manifest.package.version,
+            root_path: package.package_path.clone(),
+            source_files: package
+                .get_sources(build_options)?
+                .into_iter()
+                .map(|symbol| symbol.to_string())
+                .collect(),
+            named_addresses: package
                 .resolution_table
+                .iter()
+                .map(|(name, addr)| (name.to_string(), *addr))
+                .collect(),
+            dependencies: dependencies
+                .into_iter()
+                .map(|name| name.to_string())
+                .collect(),
+            pragmas: prover
+                .pragmas
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        PragmaValue::Bool(b) => PackagePragmaValue::Bool(b),
+                        PragmaValue::Number(n) => PackagePragmaValue::Number(n as usize),
+                    };
+                    (name.to_string(), value)
+                })
+                .collect(),
+            excluded_functions: prover
+                .excluded_functions
                 .into_iter()
-                .map(|(ident, addr)| (ident.to_string(), AddressBytes::new(addr.to_u8())))
+                .map(|name| name.to_string())
                 .collect(),
-        )
+        })
     }
 }
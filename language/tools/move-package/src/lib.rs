@@ -6,7 +6,7 @@ pub mod resolution;
 pub mod source_package;
 
 use anyhow::Result;
-use move_model::model::GlobalEnv;
+use move_model::{model::GlobalEnv, options::ModelBuilderOptions};
 use serde::{Deserialize, Serialize};
 use std::{io::Write, path::Path};
 use structopt::*;
@@ -51,24 +51,26 @@ impl BuildConfig {
         BuildPlan::create(resolved_graph)?.compile(writer)
     }
 
-    // NOTE: If there are now renamings, then the root package has the global resolution of all named
-    // addresses in the package graph in scope. So we can simply grab all of the source files
-    // across all packages and build the Move model from that.
-    // TODO: In the future we will need a better way to do this to support renaming in packages
-    // where we want to support building a Move model.
     pub fn move_model_for_package(self, path: &Path) -> Result<GlobalEnv> {
+        self.move_model_for_package_with_options(path, ModelBuilderOptions::default())
+    }
+
+    /// Builds the Move model of the package at `path`, whose sources are the targets of the model.
+    /// The packages of the graph, with their named addresses, are recorded in the model.
+    pub fn move_model_for_package_with_options(
+        self,
+        path: &Path,
+        options: ModelBuilderOptions,
+    ) -> Result<GlobalEnv> {
         let resolved_graph = self.resolution_graph_for_package(path)?;
-        ModelBuilder::create(resolved_graph).build_model()
+        ModelBuilder::create(resolved_graph).build_model_with_options(options)
     }
 
     pub fn resolution_graph_for_package(mut self, path: &Path) -> Result<ResolvedGraph> {
         if self.test_mode {
             self.dev_mode = true;
         }
-        let manifest_string =
-            std::fs::read_to_string(path.join(layout::SourcePackageLayout::Manifest.path()))?;
-        let toml_manifest = manifest_parser::parse_move_manifest_string(manifest_string)?;
-        let manifest = manifest_parser::parse_source_manifest(toml_manifest)?;
+        let manifest = manifest_parser::parse_move_manifest_from_file(path)?;
         let resolution_graph = ResolutionGraph::new(manifest, path.to_path_buf(), self)?;
         resolution_graph.resolve()
     }
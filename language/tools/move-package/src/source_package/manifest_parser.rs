@@ -1,13 +1,13 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::source_package::parsed_manifest as PM;
+use crate::source_package::{layout::SourcePackageLayout, parsed_manifest as PM};
 use anyhow::{bail, format_err, Context, Result};
 use move_core_types::account_address::AccountAddress;
 use move_symbol_pool::symbol::Symbol;
 use std::{
     collections::{BTreeMap, BTreeSet},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 use toml::Value as TV;
 
@@ -15,6 +15,7 @@ const EMPTY_ADDR_STR: &str = "_";
 
 const PACKAGE_NAME: &str = "package";
 const BUILD_NAME: &str = "build";
+const PROVER_NAME: &str = "prover";
 const ADDRESSES_NAME: &str = "addresses";
 const DEV_ADDRESSES_NAME: &str = "dev-addresses";
 const DEPENDENCY_NAME: &str = "dependencies";
@@ -23,6 +24,7 @@ const DEV_DEPENDENCY_NAME: &str = "dev-dependencies";
 const KNOWN_NAMES: &[&str] = &[
     PACKAGE_NAME,
     BUILD_NAME,
+    PROVER_NAME,
     ADDRESSES_NAME,
     DEV_ADDRESSES_NAME,
     DEPENDENCY_NAME,
@@ -35,6 +37,13 @@ pub fn parse_move_manifest_string(manifest_string: String) -> Result<TV> {
     toml::from_str::<TV>(&manifest_string).context("Unable to parse Move package manifest")
 }
 
+pub fn parse_move_manifest_from_file(package_path: &Path) -> Result<PM::SourceManifest> {
+    let manifest_string =
+        std::fs::read_to_string(package_path.join(SourcePackageLayout::Manifest.path()))?;
+    let toml_manifest = parse_move_manifest_string(manifest_string)?;
+    parse_source_manifest(toml_manifest)
+}
+
 pub fn parse_source_manifest(tval: TV) -> Result<PM::SourceManifest> {
     match tval {
         TV::Table(mut table) => {
@@ -62,6 +71,11 @@ pub fn parse_source_manifest(tval: TV) -> Result<PM::SourceManifest> {
                 .map(parse_build_info)
                 .transpose()
                 .context("Error parsing '[build]' section of manifest")?;
+            let prover = table
+                .remove(PROVER_NAME)
+                .map(parse_prover_info)
+                .transpose()
+                .context("Error parsing '[prover]' section of manifest")?;
             let dependencies = table
                 .remove(DEPENDENCY_NAME)
                 .map(parse_dependencies)
@@ -79,6 +93,7 @@ pub fn parse_source_manifest(tval: TV) -> Result<PM::SourceManifest> {
                 addresses,
                 dev_address_assignments,
                 build,
+                prover,
                 dependencies,
                 dev_dependencies,
             })
@@ -186,6 +201,99 @@ pub fn parse_build_info(tval: TV) -> Result<PM::BuildInfo> {
     }
 }
 
+pub fn parse_prover_info(tval: TV) -> Result<PM::ProverInfo> {
+    match tval {
+        TV::Table(mut table) => {
+            warn_if_unknown_field_names(
+                &table,
+                &[
+                    "vc_timeout",
+                    "hard_timeout",
+                    "backend_flags",
+                    "pragmas",
+                    "excluded_functions",
+                ],
+            );
+            let parse_timeout = |tval: TV| match tval {
+                TV::Integer(secs) if secs > 0 => Ok(secs as u64),
+                x => bail!(
+                    "Invalid timeout {}. Expected a positive number of seconds",
+                    x
+                ),
+            };
+            let vc_timeout = table.remove("vc_timeout").map(parse_timeout).transpose()?;
+            let hard_timeout = table
+                .remove("hard_timeout")
+                .map(parse_timeout)
+                .transpose()?;
+            let backend_flags = table
+                .remove("backend_flags")
+                .map(parse_string_array)
+                .transpose()
+                .context("Invalid backend flags")?
+                .unwrap_or_default();
+            let pragmas = match table.remove("pragmas") {
+                None => BTreeMap::new(),
+                Some(TV::Table(pragmas)) => pragmas
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = match value {
+                            TV::Boolean(b) => PM::PragmaValue::Bool(b),
+                            TV::Integer(n) if n >= 0 => PM::PragmaValue::Number(n as u64),
+                            x => bail!(
+                                "Invalid value {} of pragma '{}'. Expected a boolean or a \
+                                 non-negative number",
+                                x,
+                                name
+                            ),
+                        };
+                        Ok((Symbol::from(name), value))
+                    })
+                    .collect::<Result<_>>()?,
+                Some(x) => bail!(
+                    "Malformed pragmas {}. Expected a table, but encountered a {}",
+                    x,
+                    x.type_str()
+                ),
+            };
+            let excluded_functions = table
+                .remove("excluded_functions")
+                .map(parse_string_array)
+                .transpose()
+                .context("Invalid excluded functions")?
+                .unwrap_or_default()
+                .into_iter()
+                .map(Symbol::from)
+                .collect();
+            Ok(PM::ProverInfo {
+                vc_timeout,
+                hard_timeout,
+                backend_flags,
+                pragmas,
+                excluded_functions,
+            })
+        }
+        x => bail!(
+            "Malformed section in manifest {}. Expected a table, but encountered a {}",
+            x,
+            x.type_str()
+        ),
+    }
+}
+
+fn parse_string_array(tval: TV) -> Result<Vec<String>> {
+    match tval {
+        TV::Array(values) => values
+            .into_iter()
+            .map(|tval| match tval {
+                TV::String(s) => Ok(s),
+                x => bail!("Expected a string, but encountered a {}", x.type_str()),
+            })
+            .collect(),
+        x => bail!("Expected an array, but encountered a {}", x.type_str()),
+    }
+}
+
 pub fn parse_addresses(tval: TV) -> Result<PM::AddressDeclarations> {
     match tval {
         TV::Table(table) => {
@@ -21,6 +21,7 @@ pub struct SourceManifest {
     pub addresses: Option<AddressDeclarations>,
     pub dev_address_assignments: Option<DevAddressDeclarations>,
     pub build: Option<BuildInfo>,
+    pub prover: Option<ProverInfo>,
     pub dependencies: Dependencies,
     pub dev_dependencies: Dependencies,
 }
@@ -46,6 +47,27 @@ pub struct BuildInfo {
     pub language_version: Option<Version>,
 }
 
+/// Settings of the Move Prover for the package, from the `[prover]` section of the manifest.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct ProverInfo {
+    /// Timeout in seconds of the verification of each function by the backend.
+    pub vc_timeout: Option<u64>,
+    /// Timeout in seconds after which the backend is killed.
+    pub hard_timeout: Option<u64>,
+    /// Extra flags passed to the backend.
+    pub backend_flags: Vec<String>,
+    /// Default values of pragmas for the modules of the package.
+    pub pragmas: BTreeMap<Symbol, PragmaValue>,
+    /// Functions excluded from verification, as `<module>::<function>`.
+    pub excluded_functions: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PragmaValue {
+    Bool(bool),
+    Number(u64),
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum SubstOrRename {
     RenameFrom(NamedAddress),
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use move_command_line_common::testing::{format_diff, read_env_update_baseline, EXP_EXT};
+use move_model::model::GlobalEnv;
 use move_package::{
     compilation::{build_plan::BuildPlan, model_builder::ModelBuilder},
     resolution::resolution_graph as RG,
@@ -17,6 +18,45 @@ use std::{
 const COMPILE_EXT: &str = "compile";
 const MODEL_EXT: &str = "model";
 
+/// Describes the packages recorded in the model, along with the modules declared in each of them.
+fn describe_packages(env: &GlobalEnv) -> String {
+    let mut output = String::new();
+    for package in env.get_packages() {
+        let (major, minor, patch) = package.version;
+        output.push_str(&format!(
+            "Package {} {}.{}.{}\n",
+            package.name, major, minor, patch
+        ));
+        if !package.named_addresses.is_empty() {
+            let named_addresses = package
+                .named_addresses
+                .iter()
+                .map(|(name, addr)| format!("{} = 0x{}", name, addr.short_str_lossless()))
+                .collect::<Vec<_>>();
+            output.push_str(&format!(
+                "  Named addresses: {}\n",
+                named_addresses.join(", ")
+            ));
+        }
+        if !package.dependencies.is_empty() {
+            output.push_str(&format!(
+                "  Dependencies: {}\n",
+                package.dependencies.join(", ")
+            ));
+        }
+        let modules = env
+            .get_modules()
+            .filter(|module_env| {
+                !module_env.is_script_module()
+                    && module_env.get_package().map(|p| &p.name) == Some(&package.name)
+            })
+            .map(|module_env| module_env.get_full_name_str())
+            .collect::<Vec<_>>();
+        output.push_str(&format!("  Modules: {}\n", modules.join(", ")));
+    }
+    output
+}
+
 pub fn run_test(path: &Path) -> datatest_stable::Result<()> {
     let update_baseline = read_env_update_baseline();
     if path
@@ -65,7 +105,7 @@ pub fn run_test(path: &Path) -> datatest_stable::Result<()> {
                 Err(error) => format!("{:#}\n", error),
             },
             (_, true) => match ModelBuilder::create(resolved_package).build_model() {
-                Ok(_) => "Built model".to_string(),
+                Ok(env) => format!("Built model\n{}", describe_packages(&env)),
                 Err(error) => format!("{:#}\n", error),
             },
             (_, _) => {
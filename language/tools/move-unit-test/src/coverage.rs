@@ -0,0 +1,181 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Coverage of the Move code under test, and of its specifications, by the unit tests.
+//!
+//! The coverage is computed from the trace of the instructions executed by the Move VM, which is
+//! only produced by debug builds.
+
+use crate::{format_module_id, UnitTestingConfig};
+use anyhow::{bail, Result};
+use move_binary_format::access::ModuleAccess;
+use move_core_types::language_storage::ModuleId;
+use move_coverage::{
+    coverage_map::{CoverageMap, ExecCoverageMap},
+    line_coverage::LineCoverage,
+};
+use move_lang::{
+    shared::{self, Flags},
+    unit_test::TestPlan,
+};
+use move_model::{
+    options::ModelBuilderOptions, run_model_builder_with_options_and_compilation_flags,
+};
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+const MOVE_VM_TRACING_ENV_VAR_NAME: &str = "MOVE_VM_TRACE";
+const TRACE_FILE_NAME: &str = "move_vm.trace";
+const LCOV_FILE_NAME: &str = "lcov.info";
+const HTML_DIR_NAME: &str = "html";
+
+/// Number of spec conditions of a module, and how many of them are exercised by the tests.
+#[derive(Debug, Default)]
+pub struct SpecCoverage {
+    pub covered: usize,
+    pub total: usize,
+}
+
+/// Makes the Move VM trace the instructions it executes into `coverage_dir`, and returns the
+/// path of the trace. The VM checks whether to trace when it first executes an instruction, so
+/// this has no effect once Move code has run in the process.
+pub fn enable_tracing(coverage_dir: &Path) -> io::Result<PathBuf> {
+    if !cfg!(debug_assertions) {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            "test coverage is only available in debug builds",
+        ));
+    }
+    fs::create_dir_all(coverage_dir)?;
+    let trace_path = coverage_dir.join(TRACE_FILE_NAME);
+    if trace_path.exists() {
+        fs::remove_file(&trace_path)?;
+    }
+    env::set_var(MOVE_VM_TRACING_ENV_VAR_NAME, &trace_path);
+    Ok(trace_path)
+}
+
+/// Writes the lcov and HTML reports of the coverage of the modules in the source files of
+/// `config` into `coverage_dir`, and summarizes the coverage of their lines and spec conditions.
+pub fn report_coverage<W: Write>(
+    config: &UnitTestingConfig,
+    test_plan: &TestPlan,
+    coverage_dir: &Path,
+    trace_path: &Path,
+    writer: &Mutex<W>,
+) -> io::Result<()> {
+    // No trace is produced if no instruction was executed
+    let exec_map = if trace_path.exists() {
+        CoverageMap::from_trace_file(trace_path).to_unified_exec_map()
+    } else {
+        ExecCoverageMap::new(String::new())
+    };
+
+    let mut line_coverage = LineCoverage::new();
+    for info in test_plan.module_info.values() {
+        let file = info.source_map.definition_location.file();
+        if !config.source_files.iter().any(|path| path == file.as_str()) {
+            continue;
+        }
+        if let Some(contents) = test_plan.files.get(&file) {
+            line_coverage.add_module(&info.module, &info.source_map, contents, &exec_map);
+        }
+    }
+    line_coverage.output_lcov(&mut File::create(coverage_dir.join(LCOV_FILE_NAME))?)?;
+    line_coverage.output_html(&coverage_dir.join(HTML_DIR_NAME))?;
+
+    let writer = &mut *writer.lock().unwrap();
+    writeln!(
+        writer,
+        "Coverage report written to {}",
+        coverage_dir.display()
+    )?;
+    for (path, file) in &line_coverage.files {
+        writeln!(
+            writer,
+            "{}: {} / {} lines covered, {} / {} functions called",
+            path,
+            file.lines_hit(),
+            file.lines.len(),
+            file.functions_hit(),
+            file.functions.len(),
+        )?;
+    }
+    match spec_coverage(config, &exec_map) {
+        Ok(spec_coverage) => {
+            for (module_id, coverage) in spec_coverage {
+                writeln!(
+                    writer,
+                    "{}: {} / {} spec conditions covered",
+                    format_module_id(&module_id),
+                    coverage.covered,
+                    coverage.total,
+                )?;
+            }
+        }
+        Err(err) => writeln!(
+            writer,
+            "Unable to compute the coverage of the spec conditions: {}",
+            err
+        )?,
+    }
+    Ok(())
+}
+
+/// Counts the spec conditions of the functions of each module with specs in the source files of
+/// `config`. The conditions of the spec of a function are covered when the function is called,
+/// and those of a spec block within a function when the code following the block is executed.
+fn spec_coverage(
+    config: &UnitTestingConfig,
+    exec_map: &ExecCoverageMap,
+) -> Result<BTreeMap<ModuleId, SpecCoverage>> {
+    let mut deps = config.dep_files.clone();
+    deps.extend(move_stdlib::unit_testing_files());
+    let env = run_model_builder_with_options_and_compilation_flags(
+        &config.source_files,
+        &deps,
+        ModelBuilderOptions::default(),
+        Flags::testing(),
+        shared::verify_and_create_named_address_mapping(config.named_address_values.clone())?,
+    )?;
+    if env.has_errors() {
+        bail!("the specifications do not compile");
+    }
+
+    let mut spec_coverage = BTreeMap::new();
+    for module_env in env
+        .get_modules()
+        .filter(|module_env| module_env.is_target())
+    {
+        let module_id = module_env.get_verified_module().self_id();
+        let module_map = exec_map
+            .module_maps
+            .get(&(*module_id.address(), module_id.name().to_owned()));
+        let mut coverage = SpecCoverage::default();
+        for func_env in module_env.get_functions() {
+            let fn_name = func_env.get_identifier();
+            let function_coverage = module_map.and_then(|m| m.get_function_coverage(&fn_name));
+            let spec = func_env.get_spec();
+            coverage.total += spec.conditions.len();
+            if function_coverage.is_some() {
+                coverage.covered += spec.conditions.len();
+            }
+            for (offset, block_spec) in &spec.on_impl {
+                coverage.total += block_spec.conditions.len();
+                if function_coverage.map_or(false, |c| c.contains_key(&(*offset as u64))) {
+                    coverage.covered += block_spec.conditions.len();
+                }
+            }
+        }
+        if coverage.total > 0 {
+            spec_coverage.insert(module_id, coverage);
+        }
+    }
+    Ok(spec_coverage)
+}
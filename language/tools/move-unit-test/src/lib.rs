@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod cargo_runner;
+pub mod coverage;
 pub mod test_reporter;
 pub mod test_runner;
 use crate::test_runner::TestRunner;
@@ -18,6 +19,7 @@ use std::{
     collections::BTreeMap,
     io::{Result, Write},
     marker::Send,
+    path::Path,
     sync::Mutex,
 };
 use structopt::*;
@@ -81,6 +83,11 @@ pub struct UnitTestingConfig {
     #[structopt(long = "stackless")]
     pub check_stackless_vm: bool,
 
+    /// Write a report of the coverage of the source files and of their specs by the tests, in the
+    /// lcov and HTML formats, to this directory. Only available in debug builds.
+    #[structopt(name = "coverage", long = "coverage")]
+    pub coverage_dir: Option<String>,
+
     /// Verbose mode
     #[structopt(short = "v", long = "verbose")]
     pub verbose: bool,
@@ -106,6 +113,7 @@ impl UnitTestingConfig {
             source_files: vec![],
             dep_files: vec![],
             check_stackless_vm: false,
+            coverage_dir: None,
             verbose: false,
             list: false,
             named_address_values: vec![],
@@ -189,6 +197,14 @@ impl UnitTestingConfig {
             return Ok((shared_writer.into_inner().unwrap(), true));
         }
 
+        let coverage = match &self.coverage_dir {
+            Some(coverage_dir) => Some((
+                coverage::enable_tracing(Path::new(coverage_dir))?,
+                test_plan.clone(),
+            )),
+            None => None,
+        };
+
         writeln!(shared_writer.lock().unwrap(), "Running Move unit tests")?;
         let mut test_runner = TestRunner::new(
             self.instruction_execution_bound,
@@ -212,6 +228,16 @@ impl UnitTestingConfig {
             test_results.report_statistics(&shared_writer)?;
         }
         let all_tests_passed = test_results.summarize(&shared_writer)?;
+        if let (Some(coverage_dir), Some((trace_path, test_plan))) = (&self.coverage_dir, coverage)
+        {
+            coverage::report_coverage(
+                self,
+                &test_plan,
+                Path::new(coverage_dir),
+                &trace_path,
+                &shared_writer,
+            )?;
+        }
 
         let writer = shared_writer.into_inner().unwrap();
         Ok((writer, all_tests_passed))
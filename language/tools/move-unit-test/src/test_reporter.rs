@@ -35,6 +35,12 @@ pub enum FailureReason {
     },
     // Property checking failed
     Property(String),
+    // The test executed, but the events it emitted do not meet its expectations
+    UnmetEventExpectations {
+        message: String,
+        unmet_expectations: Vec<String>,
+        emitted_events: Vec<String>,
+    },
     // The test failed for some unknown reason. This shouldn't be encountered
     Unknown(String),
 }
@@ -115,6 +121,17 @@ impl FailureReason {
         FailureReason::Property(details)
     }
 
+    pub fn unmet_event_expectations(
+        unmet_expectations: Vec<String>,
+        emitted_events: Vec<String>,
+    ) -> Self {
+        FailureReason::UnmetEventExpectations {
+            message: "Test did not emit the expected events".to_string(),
+            unmet_expectations,
+            emitted_events,
+        }
+    }
+
     pub fn unknown() -> Self {
         FailureReason::Unknown("ITE: An unknown error was reported.".to_string())
     }
@@ -170,6 +187,25 @@ impl TestFailure {
                 )
             }
             FailureReason::Property(message) => message.clone(),
+            FailureReason::UnmetEventExpectations {
+                message,
+                unmet_expectations,
+                emitted_events,
+            } => {
+                let mut error_string = format!("{}:", message);
+                for unmet_expectation in unmet_expectations {
+                    error_string
+                        .push_str(&format!("\n\t{}", unmet_expectation.replace("\n", "\n\t")));
+                }
+                error_string.push_str("\nEmitted events:");
+                if emitted_events.is_empty() {
+                    error_string.push_str(" <none>");
+                }
+                for event in emitted_events {
+                    error_string.push_str(&format!("\n\t{}", event.replace("\n", "\n\t")));
+                }
+                error_string
+            }
             FailureReason::Unknown(message) => {
                 format!(
                     "{} Location: {}\nVMError (if there is one): {}",
@@ -16,9 +16,10 @@ use move_binary_format::{errors::VMResult, file_format::CompiledModule};
 use move_bytecode_utils::Modules;
 use move_core_types::{
     account_address::AccountAddress,
-    effects::ChangeSet,
+    effects::{ChangeSet, Event},
     gas_schedule::{CostTable, GasAlgebra, GasCost, GasUnits},
     identifier::IdentStr,
+    language_storage::TypeTag,
     value::serialize_values,
     vm_status::StatusCode,
 };
@@ -30,6 +31,7 @@ use move_model::{
     model::GlobalEnv, options::ModelBuilderOptions,
     run_model_builder_with_options_and_compilation_flags,
 };
+use move_stdlib::natives::unit_test::EventExpectation;
 use move_vm_runtime::{move_vm::MoveVM, native_functions::NativeFunctionTable};
 use move_vm_test_utils::InMemoryStorage;
 use move_vm_types::gas_schedule::{zero_cost_schedule, GasStatus};
@@ -111,6 +113,43 @@ fn print_resources(cs: &ChangeSet, storage: &InMemoryStorage) -> Result<String>
     Ok(buf)
 }
 
+/// Describes the event of type `type_tag` with the BCS-serialized `payload`.
+fn view_event(type_tag: &TypeTag, payload: &[u8], storage: &InMemoryStorage) -> String {
+    match MoveValueAnnotator::new(storage).view_value(type_tag, payload) {
+        Ok(value) => value.to_string(),
+        Err(_) => format!("{} {:?}", type_tag, payload),
+    }
+}
+
+/// Describes the expectations of a test on its events which are not met by the emitted `events`.
+fn unmet_event_expectations(
+    expectations: &[EventExpectation],
+    events: &[Event],
+    storage: &InMemoryStorage,
+) -> Vec<String> {
+    expectations
+        .iter()
+        .filter(|expectation| !expectation.is_met_by(events))
+        .map(|expectation| match expectation {
+            EventExpectation::Payload(type_tag, payload) => {
+                format!(
+                    "Expected event not emitted: {}",
+                    view_event(type_tag, payload, storage)
+                )
+            }
+            EventExpectation::Count(type_tag, count) => format!(
+                "Expected {} events of type {} but {} were emitted",
+                count,
+                type_tag,
+                events
+                    .iter()
+                    .filter(|(_, _, tag, _)| tag == type_tag)
+                    .count()
+            ),
+        })
+        .collect()
+}
+
 impl TestRunner {
     pub fn new(
         execution_bound: u64,
@@ -187,7 +226,11 @@ impl SharedTestingConfig {
         test_plan: &ModuleTestPlan,
         function_name: &str,
         test_info: &TestCase,
-    ) -> (VMResult<ChangeSet>, VMResult<Vec<Vec<u8>>>, TestRunInfo) {
+    ) -> (
+        VMResult<(ChangeSet, Vec<Event>)>,
+        VMResult<Vec<Vec<u8>>>,
+        TestRunInfo,
+    ) {
         let move_vm = MoveVM::new(self.native_function_table.clone()).unwrap();
         let mut session = move_vm.new_session(&self.starting_storage_state);
         let mut gas_meter = GasStatus::new(&self.cost_table, GasUnits::new(self.execution_bound));
@@ -206,11 +249,7 @@ impl SharedTestingConfig {
             now.elapsed(),
             self.execution_bound - gas_meter.remaining_gas().get(),
         );
-        (
-            session.finish().map(|(cs, _)| cs),
-            return_result,
-            test_run_info,
-        )
+        (session.finish(), return_result, test_run_info)
     }
 
     fn execute_via_stackless_vm(
@@ -314,8 +353,13 @@ impl SharedTestingConfig {
         };
 
         for (function_name, test_info) in &test_plan.tests {
-            let (cs_result, exec_result, test_run_info) =
+            let (session_result, exec_result, test_run_info) =
                 self.execute_via_move_vm(test_plan, function_name, test_info);
+            let (cs_result, events) = match session_result {
+                Ok((change_set, events)) => (Ok(change_set), events),
+                Err(err) => (Err(err), vec![]),
+            };
+            let (event_expectations, events) = EventExpectation::split_events(events);
             if self.check_stackless_vm {
                 let (stackless_vm_change_set, stackless_vm_result, _, prop_check_result) = self
                     .execute_via_stackless_vm(
@@ -461,9 +505,37 @@ impl SharedTestingConfig {
                             test_plan,
                         )
                     } else {
-                        // Expected the test to execute fully and it did
-                        pass(function_name);
-                        stats.test_success(test_run_info, test_plan);
+                        let unmet_expectations = unmet_event_expectations(
+                            &event_expectations,
+                            &events,
+                            &self.starting_storage_state,
+                        );
+                        if unmet_expectations.is_empty() {
+                            // Expected the test to execute fully and it did
+                            pass(function_name);
+                            stats.test_success(test_run_info, test_plan);
+                        } else {
+                            // The test executed fully, but did not emit the expected events
+                            let emitted_events = events
+                                .iter()
+                                .map(|(_, _, type_tag, payload)| {
+                                    view_event(type_tag, payload, &self.starting_storage_state)
+                                })
+                                .collect();
+                            fail(function_name);
+                            stats.test_failure(
+                                TestFailure::new(
+                                    FailureReason::unmet_event_expectations(
+                                        unmet_expectations,
+                                        emitted_events,
+                                    ),
+                                    test_run_info,
+                                    None,
+                                    save_session_state(),
+                                ),
+                                test_plan,
+                            )
+                        }
                     }
                 }
             }
@@ -77,6 +77,7 @@ fn run_test_impl(path: &Path) -> anyhow::Result<()> {
         source_files,
         dep_files: move_stdlib::move_stdlib_files(),
         check_stackless_vm: false,
+        coverage_dir: None,
         verbose: false,
         report_statistics: false,
         report_storage_on_error: false,
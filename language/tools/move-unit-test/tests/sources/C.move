@@ -0,0 +1,25 @@
+module 0x1::C {
+    fun max(a: u64, b: u64): u64 {
+        if (a >= b) {
+            a
+        } else {
+            b
+        }
+    }
+    spec max {
+        aborts_if false;
+        ensures result >= a && result >= b;
+    }
+
+    fun zero(): u64 {
+        0
+    }
+    spec zero {
+        ensures result == 0;
+    }
+
+    #[test]
+    fun test_max() {
+        assert(max(2, 1) == 2, 0);
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_unit_test::{self, UnitTestingConfig};
+use std::{fs, path::PathBuf};
+
+// The Move VM only traces the executed instructions in debug builds.
+#[test]
+#[cfg_attr(not(debug_assertions), ignore)]
+fn test_coverage_report() {
+    let coverage_dir = tempfile::tempdir().unwrap();
+    let mut testing_config = UnitTestingConfig::default_with_bound(None)
+        .with_named_addresses(move_stdlib::move_stdlib_named_addresses());
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let c_path = path.join("tests/sources/C.move");
+
+    testing_config.source_files = vec![c_path.to_str().unwrap().to_owned()];
+    testing_config.coverage_dir = Some(coverage_dir.path().to_str().unwrap().to_owned());
+
+    let test_plan = testing_config.build_test_plan().unwrap();
+    let (output, all_tests_passed) = testing_config
+        .run_and_report_unit_tests(test_plan, None, Vec::new())
+        .unwrap();
+    assert!(all_tests_passed);
+    // Only the specs of `max` are exercised by the tests
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("0x1::C: 2 / 3 spec conditions covered"));
+
+    let lcov = fs::read_to_string(coverage_dir.path().join("lcov.info")).unwrap();
+    assert!(lcov.contains("FNDA:1,C::max"));
+    assert!(lcov.contains("FNDA:0,C::zero"));
+    // Only the first branch of `max` is executed
+    assert!(lcov.contains("DA:4,1"));
+    assert!(lcov.contains("DA:6,0"));
+    assert!(coverage_dir.path().join("html/index.html").exists());
+}
@@ -0,0 +1,38 @@
+module 0x1::M {
+    use Std::Event;
+    use Std::UnitTest;
+
+    struct Deposit has drop, store { amount: u64 }
+
+    fun deposit_twice(account: &signer) {
+        Event::publish_generator(account);
+        let handle = Event::new_event_handle<Deposit>(account);
+        Event::emit_event(&mut handle, Deposit { amount: 10 });
+        Event::emit_event(&mut handle, Deposit { amount: 20 });
+        Event::destroy_handle(handle);
+    }
+
+    #[test(a=@0x2)]
+    fun expected_events(a: signer) {
+        deposit_twice(&a);
+        UnitTest::expect_event(Deposit { amount: 20 });
+        UnitTest::expect_event_count<Deposit>(2);
+    }
+
+    #[test(a=@0x2)]
+    fun unexpected_payload(a: signer) {
+        deposit_twice(&a);
+        UnitTest::expect_event(Deposit { amount: 30 });
+    }
+
+    #[test]
+    fun unexpected_count() {
+        UnitTest::expect_event_count<Deposit>(1);
+    }
+
+    #[test, expected_failure(abort_code = 0)]
+    fun expectations_ignored_on_abort() {
+        UnitTest::expect_event_count<Deposit>(1);
+        abort 0
+    }
+}
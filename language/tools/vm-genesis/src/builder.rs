@@ -0,0 +1,236 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{encode_genesis_change_set_with_accounts, is_test_chain, Validator};
+use anyhow::{ensure, Result};
+use diem_crypto::ed25519::Ed25519PublicKey;
+use diem_framework_releases::{
+    current_module_blobs, legacy::transaction_scripts::LegacyStdlibScript,
+};
+use diem_types::{
+    chain_id::ChainId,
+    on_chain_config::{ConsensusConfigV1, OnChainConsensusConfig, VMPublishingOption},
+    transaction::{authenticator::AuthenticationKey, Transaction, WriteSetPayload},
+};
+use std::collections::HashSet;
+
+/// An account created by the genesis transaction, as a parent VASP.
+#[derive(Debug, Clone)]
+pub struct InitialAccount {
+    /// Authentication key for the account, from which its address is derived
+    pub auth_key: AuthenticationKey,
+    /// UTF8-encoded name for the account
+    pub name: Vec<u8>,
+    /// Amount of XUS the account is funded with by the testnet designated dealer, which must be 0
+    /// on chains other than test chains
+    pub balance: u64,
+}
+
+/// Assembles the genesis transaction of a chain from its root keys, validators, Move modules and
+/// initial accounts, without going through the management tooling and its secure storage. Useful
+/// to set up test networks and forks programmatically.
+pub struct GenesisBuilder {
+    chain_id: ChainId,
+    diem_root_key: Ed25519PublicKey,
+    treasury_compliance_key: Ed25519PublicKey,
+    validators: Vec<Validator>,
+    initial_accounts: Vec<InitialAccount>,
+    move_modules: Vec<Vec<u8>>,
+    publishing_option: VMPublishingOption,
+    consensus_config: OnChainConsensusConfig,
+}
+
+impl GenesisBuilder {
+    /// Starts from a genesis with no validators nor initial accounts, publishing the current
+    /// release of the framework.
+    pub fn new(
+        chain_id: ChainId,
+        diem_root_key: Ed25519PublicKey,
+        treasury_compliance_key: Ed25519PublicKey,
+    ) -> Self {
+        Self {
+            chain_id,
+            diem_root_key,
+            treasury_compliance_key,
+            validators: Vec::new(),
+            initial_accounts: Vec::new(),
+            move_modules: current_module_blobs().to_vec(),
+            publishing_option: VMPublishingOption::locked(LegacyStdlibScript::allowlist()),
+            consensus_config: OnChainConsensusConfig::V1(ConsensusConfigV1 { two_chain: true }),
+        }
+    }
+
+    pub fn validators(mut self, validators: Vec<Validator>) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    pub fn initial_accounts(mut self, initial_accounts: Vec<InitialAccount>) -> Self {
+        self.initial_accounts = initial_accounts;
+        self
+    }
+
+    /// Bytecodes of the Move modules to publish, all under the same address
+    pub fn move_modules(mut self, move_modules: Vec<Vec<u8>>) -> Self {
+        self.move_modules = move_modules;
+        self
+    }
+
+    pub fn publishing_option(mut self, publishing_option: VMPublishingOption) -> Self {
+        self.publishing_option = publishing_option;
+        self
+    }
+
+    pub fn consensus_config(mut self, consensus_config: OnChainConsensusConfig) -> Self {
+        self.consensus_config = consensus_config;
+        self
+    }
+
+    /// Executes the genesis and returns its write set. Note that a failure while executing the
+    /// Move modules, e.g. because they are not a valid framework, panics.
+    pub fn build(&self) -> Result<WriteSetPayload> {
+        self.validate()?;
+
+        Ok(WriteSetPayload::Direct(
+            encode_genesis_change_set_with_accounts(
+                &self.diem_root_key,
+                &self.treasury_compliance_key,
+                &self.validators,
+                &self.initial_accounts,
+                &self.move_modules,
+                self.publishing_option.clone(),
+                self.consensus_config.clone(),
+                self.chain_id,
+            ),
+        ))
+    }
+
+    pub fn build_transaction(&self) -> Result<Transaction> {
+        Ok(Transaction::GenesisTransaction(self.build()?))
+    }
+
+    fn validate(&self) -> Result<()> {
+        ensure!(!self.move_modules.is_empty(), "No Move modules to publish.");
+
+        let mut addresses = HashSet::new();
+        for validator in &self.validators {
+            ensure!(
+                addresses.insert(validator.address),
+                "Validator {} defined more than once.",
+                validator.address,
+            );
+        }
+        // Operators may operate several validators, or be validators themselves.
+        let operator_addresses: HashSet<_> = self
+            .validators
+            .iter()
+            .map(|validator| validator.operator_address)
+            .collect();
+        addresses.extend(operator_addresses);
+
+        let is_test_chain = is_test_chain(self.chain_id);
+        for account in &self.initial_accounts {
+            let address = account.auth_key.derived_address();
+            ensure!(
+                addresses.insert(address),
+                "Initial account {} conflicts with another account.",
+                address,
+            );
+            ensure!(
+                is_test_chain || account.balance == 0,
+                "Initial account {} can't be funded on non-test chain {}.",
+                address,
+                self.chain_id,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TestValidator, GENESIS_KEYPAIR};
+    use diem_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+    use diem_types::chain_id::NamedChain;
+    use rand::rngs::OsRng;
+
+    fn initial_account(balance: u64) -> InitialAccount {
+        let key = Ed25519PrivateKey::generate(&mut OsRng);
+        InitialAccount {
+            auth_key: AuthenticationKey::ed25519(&key.public_key()),
+            name: b"initial".to_vec(),
+            balance,
+        }
+    }
+
+    fn builder(chain_id: ChainId) -> GenesisBuilder {
+        GenesisBuilder::new(
+            chain_id,
+            GENESIS_KEYPAIR.1.clone(),
+            GENESIS_KEYPAIR.1.clone(),
+        )
+        .validators(
+            TestValidator::new_test_set(Some(1))
+                .into_iter()
+                .map(|validator| validator.data)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_build_with_initial_accounts() {
+        let account = initial_account(1_000_000);
+        let address = account.auth_key.derived_address();
+        let genesis = builder(ChainId::test())
+            .initial_accounts(vec![account])
+            .build()
+            .unwrap();
+
+        let change_set = match genesis {
+            WriteSetPayload::Direct(change_set) => change_set,
+            WriteSetPayload::Script { .. } => panic!("Unexpected genesis write set payload"),
+        };
+        assert!(change_set
+            .write_set()
+            .iter()
+            .any(|(access_path, _)| access_path.address == address));
+    }
+
+    #[test]
+    fn test_validate() {
+        // Initial accounts are only funded on test chains
+        let mainnet = ChainId::new(NamedChain::MAINNET.id());
+        assert!(builder(mainnet)
+            .initial_accounts(vec![initial_account(1)])
+            .validate()
+            .is_err());
+        assert!(builder(mainnet)
+            .initial_accounts(vec![initial_account(0)])
+            .validate()
+            .is_ok());
+
+        // Accounts are created only once
+        let account = initial_account(0);
+        assert!(builder(ChainId::test())
+            .initial_accounts(vec![account.clone(), account])
+            .validate()
+            .is_err());
+        let validator = TestValidator::new_test_set(Some(1)).remove(0).data;
+        assert!(builder(ChainId::test())
+            .initial_accounts(vec![InitialAccount {
+                auth_key: validator.auth_key,
+                name: b"validator".to_vec(),
+                balance: 0,
+            }])
+            .validate()
+            .is_err());
+
+        // Some modules must be published
+        assert!(builder(ChainId::test())
+            .move_modules(vec![])
+            .validate()
+            .is_err());
+    }
+}
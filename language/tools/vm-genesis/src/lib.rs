@@ -3,8 +3,11 @@
 
 #![forbid(unsafe_code)]
 
+mod builder;
 mod genesis_context;
 
+pub use crate::builder::{GenesisBuilder, InitialAccount};
+
 use crate::genesis_context::GenesisStateView;
 use diem_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
@@ -84,6 +87,29 @@ pub fn encode_genesis_change_set(
     vm_publishing_option: VMPublishingOption,
     consensus_config: OnChainConsensusConfig,
     chain_id: ChainId,
+) -> ChangeSet {
+    encode_genesis_change_set_with_accounts(
+        diem_root_key,
+        treasury_compliance_key,
+        validators,
+        &[],
+        stdlib_module_bytes,
+        vm_publishing_option,
+        consensus_config,
+        chain_id,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn encode_genesis_change_set_with_accounts(
+    diem_root_key: &Ed25519PublicKey,
+    treasury_compliance_key: &Ed25519PublicKey,
+    validators: &[Validator],
+    initial_accounts: &[InitialAccount],
+    stdlib_module_bytes: &[Vec<u8>],
+    vm_publishing_option: VMPublishingOption,
+    consensus_config: OnChainConsensusConfig,
+    chain_id: ChainId,
 ) -> ChangeSet {
     let mut stdlib_modules = Vec::new();
     // create a data view for move_vm
@@ -118,12 +144,10 @@ pub fn encode_genesis_change_set(
     create_and_initialize_owners_operators(&mut session, validators);
     reconfigure(&mut session);
 
-    if [NamedChain::TESTNET, NamedChain::DEVNET, NamedChain::TESTING]
-        .iter()
-        .any(|test_chain_id| test_chain_id.id() == chain_id.id())
-    {
+    if is_test_chain(chain_id) {
         create_and_initialize_testnet_minting(&mut session, treasury_compliance_key);
     }
+    create_initial_accounts(&mut session, initial_accounts);
 
     let (mut changeset1, mut events1) = session.finish().unwrap();
 
@@ -309,6 +333,47 @@ fn create_and_initialize_testnet_minting(
     );
 }
 
+/// Creates each initial account as a parent VASP, and funds it from the testnet designated dealer,
+/// which only exists on test chains.
+fn create_initial_accounts(
+    session: &mut Session<StateViewCache>,
+    initial_accounts: &[InitialAccount],
+) {
+    let treasury_compliance_account_address = account_config::treasury_compliance_account_address();
+    let testnet_dd_account_address = account_config::testnet_dd_account_address();
+    for account in initial_accounts {
+        let address = account.auth_key.derived_address();
+        exec_script_function(
+            session,
+            treasury_compliance_account_address,
+            &transaction_builder::encode_create_parent_vasp_account_script_function(
+                account_config::xus_tag(),
+                0,
+                address,
+                account.auth_key.prefix().to_vec(),
+                account.name.clone(),
+                true, // add_all_currencies
+            )
+            .into_script_function(),
+        );
+
+        if account.balance > 0 {
+            exec_script_function(
+                session,
+                testnet_dd_account_address,
+                &transaction_builder::encode_peer_to_peer_with_metadata_script_function(
+                    account_config::xus_tag(),
+                    address,
+                    account.balance,
+                    vec![], // metadata
+                    vec![], // metadata_signature
+                )
+                .into_script_function(),
+            );
+        }
+    }
+}
+
 /// Creates and initializes each validator owner and validator operator. This method creates all
 /// the required accounts, sets the validator operators for each validator owner, and sets the
 /// validator config on-chain.
@@ -400,6 +465,14 @@ fn reconfigure(session: &mut Session<StateViewCache>) {
     );
 }
 
+/// Whether the chain is one for testing, whose genesis sets up a designated dealer with lots of
+/// XUS at `account_config::testnet_dd_account_address()`.
+fn is_test_chain(chain_id: ChainId) -> bool {
+    [NamedChain::TESTNET, NamedChain::DEVNET, NamedChain::TESTING]
+        .iter()
+        .any(|test_chain_id| test_chain_id.id() == chain_id.id())
+}
+
 /// Verify the consistency of the genesis `WriteSet`
 fn verify_genesis_write_set(events: &[ContractEvent]) {
     // (1) first event is account creation event for DiemRoot
@@ -6,6 +6,7 @@
 use crate::{
     core_mempool::{
         index::TxnPointer,
+        summary::MempoolSummary,
         transaction::{MempoolTransaction, TimelineState},
         transaction_store::TransactionStore,
         ttl_cache::TtlCache,
@@ -19,6 +20,7 @@ use diem_types::{
     account_address::AccountAddress,
     account_config::AccountSequenceInfo,
     mempool_status::{MempoolStatus, MempoolStatusCode},
+    on_chain_config::{BlockLaneCaps, VMPublishingOption},
     transaction::{GovernanceRole, SignedTransaction},
 };
 use std::{
@@ -174,14 +176,23 @@ impl Mempool {
         self.transactions.insert(txn_info)
     }
 
+    /// Returns why `txn` can't replace the transaction with the same sender and sequence number in
+    /// mempool, if any, so it can be rejected before being validated.
+    pub(crate) fn check_replacement(&self, txn: &SignedTransaction) -> Option<MempoolStatus> {
+        self.transactions.check_replacement(txn)
+    }
+
     /// Fetches next block of transactions for consensus.
     /// `batch_size` - size of requested block.
+    /// `lane_caps` - max number of governance and user transactions in the block. Governance
+    ///  transactions are pulled first, so they are never crowded out by user transactions.
     /// `seen_txns` - transactions that were sent to Consensus but were not committed yet,
     ///  mempool should filter out such transactions.
     #[allow(clippy::explicit_counter_loop)]
     pub(crate) fn get_block(
         &mut self,
         batch_size: u64,
+        lane_caps: BlockLaneCaps,
         mut seen: HashSet<TxnPointer>,
     ) -> Vec<SignedTransaction> {
         let mut result = vec![];
@@ -194,12 +205,29 @@ impl Mempool {
         let mut skipped = HashSet::new();
         let seen_size = seen.len();
         let mut txn_walked = 0usize;
-        // iterate over the queue of transactions based on gas price
+        let mut governance_txns = 0u64;
+        let mut user_txns = 0u64;
+        // iterate over the queue of transactions based on governance role, then gas price
         'main: for txn in self.transactions.iter_queue() {
+            if governance_txns >= lane_caps.max_governance_txns
+                && user_txns >= lane_caps.max_user_txns
+            {
+                break;
+            }
             txn_walked += 1;
             if seen.contains(&TxnPointer::from(txn)) {
                 continue;
             }
+            // transactions of a full lane are left for the next blocks
+            let (lane_txns, max_lane_txns) =
+                if txn.governance_role == GovernanceRole::NonGovernanceRole {
+                    (&mut user_txns, lane_caps.max_user_txns)
+                } else {
+                    (&mut governance_txns, lane_caps.max_governance_txns)
+                };
+            if *lane_txns >= max_lane_txns {
+                continue;
+            }
             let account_seqtype = txn.sequence_number.account_sequence_number_type;
             let tx_seq = txn.sequence_number.transaction_sequence_number;
             let account_sequence_number = self.sequence_number_cache.get(&txn.address);
@@ -214,6 +242,7 @@ impl Mempool {
                 let ptr = TxnPointer::from(txn);
                 seen.insert(ptr);
                 result.push(ptr);
+                *lane_txns += 1;
                 if (result.len() as u64) == batch_size {
                     break;
                 }
@@ -221,9 +250,10 @@ impl Mempool {
                 // check if we can now include some transactions
                 // that were skipped before for given account
                 let mut skipped_txn = (txn.address, tx_seq + 1);
-                while skipped.contains(&skipped_txn) {
+                while *lane_txns < max_lane_txns && skipped.contains(&skipped_txn) {
                     seen.insert(skipped_txn);
                     result.push(skipped_txn);
+                    *lane_txns += 1;
                     if (result.len() as u64) == batch_size {
                         break 'main;
                     }
@@ -278,6 +308,15 @@ impl Mempool {
             .gc_by_expiration_time(block_time, &self.metrics_cache);
     }
 
+    /// Removes the transactions that an updated publishing option does not allow anymore.
+    pub(crate) fn remove_disallowed_transactions(
+        &mut self,
+        publishing_option: &VMPublishingOption,
+    ) {
+        self.transactions
+            .remove_disallowed_transactions(publishing_option);
+    }
+
     /// Read `count` transactions from timeline since `timeline_id`.
     /// Returns block of transactions and new last_timeline_id.
     pub(crate) fn read_timeline(
@@ -297,6 +336,12 @@ impl Mempool {
         self.transactions.gen_snapshot(&self.metrics_cache)
     }
 
+    /// Aggregates the transactions in mempool by sender, along with the latest evictions.
+    pub fn gen_summary(&self) -> MempoolSummary {
+        self.transactions
+            .gen_summary(self.system_transaction_timeout)
+    }
+
     #[cfg(test)]
     pub fn get_parking_lot_size(&self) -> usize {
         self.transactions.get_parking_lot_size()
@@ -3,10 +3,16 @@
 
 mod index;
 mod mempool;
+mod summary;
 mod transaction;
 mod transaction_store;
 mod ttl_cache;
 
 #[cfg(test)]
 pub use self::ttl_cache::TtlCache;
-pub use self::{index::TxnPointer, mempool::Mempool as CoreMempool, transaction::TimelineState};
+pub use self::{
+    index::TxnPointer,
+    mempool::Mempool as CoreMempool,
+    summary::{Eviction, EvictionReason, MempoolSummary, SenderSummary},
+    transaction::TimelineState,
+};
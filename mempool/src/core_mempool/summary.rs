@@ -0,0 +1,99 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Aggregated view of the transactions in mempool and of the ones recently evicted, for operators
+//! to diagnose surges.
+
+use crate::{core_mempool::transaction::MempoolTransaction, counters};
+use diem_types::account_address::AccountAddress;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// Number of evictions kept in the summary.
+const MAX_RECENT_EVICTIONS: usize = 1_000;
+
+/// Why a transaction was removed from mempool before being committed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionReason {
+    /// Stayed in mempool longer than the system TTL.
+    SystemTtl,
+    /// Reached its client-specified expiration time.
+    ClientExpiration,
+    /// Parked, and evicted to make room for a ready transaction while mempool was full.
+    MempoolFull,
+    /// Replaced by a transaction with the same sequence number and a higher gas price.
+    Replaced,
+    /// Rejected by consensus, along with the other transactions of its sender.
+    Rejected,
+    /// No longer allowed by the on-chain publishing option.
+    Disallowed,
+}
+
+impl EvictionReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EvictionReason::SystemTtl => "system_ttl",
+            EvictionReason::ClientExpiration => "client_expiration",
+            EvictionReason::MempoolFull => "mempool_full",
+            EvictionReason::Replaced => "replaced",
+            EvictionReason::Rejected => "rejected",
+            EvictionReason::Disallowed => "disallowed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Eviction {
+    pub sender: AccountAddress,
+    pub sequence_number: u64,
+    pub reason: EvictionReason,
+    pub timestamp_usecs: u64,
+}
+
+/// The transactions of a sender in mempool.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SenderSummary {
+    pub sender: AccountAddress,
+    pub num_transactions: usize,
+    /// When the oldest transaction of the sender entered mempool.
+    pub oldest_timestamp_usecs: u64,
+    /// Total size of the raw transactions of the sender.
+    pub bytes: usize,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct MempoolSummary {
+    pub num_transactions: usize,
+    /// Senders with the most transactions first.
+    pub senders: Vec<SenderSummary>,
+    /// The latest evictions, oldest first.
+    pub recent_evictions: Vec<Eviction>,
+}
+
+/// Counts evictions by reason, and keeps the latest ones.
+#[derive(Default)]
+pub(crate) struct EvictionLog {
+    evictions: VecDeque<Eviction>,
+}
+
+impl EvictionLog {
+    pub(crate) fn record(&mut self, txn: &MempoolTransaction, reason: EvictionReason) {
+        counters::CORE_MEMPOOL_EVICTED_TXNS
+            .with_label_values(&[reason.as_str()])
+            .inc();
+        if self.evictions.len() == MAX_RECENT_EVICTIONS {
+            self.evictions.pop_front();
+        }
+        self.evictions.push_back(Eviction {
+            sender: txn.get_sender(),
+            sequence_number: txn.sequence_info.transaction_sequence_number,
+            reason,
+            timestamp_usecs: diem_infallible::duration_since_epoch().as_micros() as u64,
+        });
+    }
+
+    pub(crate) fn recent_evictions(&self) -> Vec<Eviction> {
+        self.evictions.iter().cloned().collect()
+    }
+}
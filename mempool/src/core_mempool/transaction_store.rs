@@ -7,6 +7,7 @@ use crate::{
             AccountTransactions, ParkingLotIndex, PriorityIndex, PriorityQueueIter, TTLIndex,
             TimelineIndex,
         },
+        summary::{EvictionLog, EvictionReason, MempoolSummary, SenderSummary},
         transaction::{MempoolTransaction, TimelineState},
         ttl_cache::TtlCache,
     },
@@ -19,6 +20,7 @@ use diem_types::{
     account_address::AccountAddress,
     account_config::AccountSequenceInfo,
     mempool_status::{MempoolStatus, MempoolStatusCode},
+    on_chain_config::VMPublishingOption,
     transaction::SignedTransaction,
 };
 use std::{
@@ -44,9 +46,13 @@ pub struct TransactionStore {
     // keeps track of "non-ready" txns (transactions that can't be included in next block)
     parking_lot_index: ParkingLotIndex,
 
+    // transactions removed before being committed
+    eviction_log: EvictionLog,
+
     // configuration
     capacity: usize,
     capacity_per_user: usize,
+    replacement_gas_price_bump_pct: u64,
 }
 
 impl TransactionStore {
@@ -63,10 +69,12 @@ impl TransactionStore {
             priority_index: PriorityIndex::new(),
             timeline_index: TimelineIndex::new(),
             parking_lot_index: ParkingLotIndex::new(),
+            eviction_log: EvictionLog::default(),
 
             // configuration
             capacity: config.capacity,
             capacity_per_user: config.capacity_per_user,
+            replacement_gas_price_bump_pct: config.replacement_gas_price_bump_pct,
         }
     }
 
@@ -86,6 +94,42 @@ impl TransactionStore {
         None
     }
 
+    /// Returns why `txn` can't be inserted over the transaction with the same sender and sequence
+    /// number already in mempool, if any. A different transaction replaces it only if its gas
+    /// price is at least `replacement_gas_price_bump_pct` percent higher.
+    pub(crate) fn check_replacement(&self, txn: &SignedTransaction) -> Option<MempoolStatus> {
+        let current_version = self
+            .transactions
+            .get(&txn.sender())?
+            .get(&txn.sequence_number())?;
+        if current_version.txn == *txn {
+            return None;
+        }
+        let current_gas_price = current_version.get_gas_price();
+        let min_gas_price = self.min_replacement_gas_price(current_gas_price);
+        if txn.gas_unit_price() >= min_gas_price {
+            return None;
+        }
+        Some(
+            MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(format!(
+                "Failed to replace transaction with gas price {}: gas price must be at least {} \
+                 ({}% above {})",
+                txn.gas_unit_price(),
+                min_gas_price,
+                self.replacement_gas_price_bump_pct,
+                current_gas_price,
+            )),
+        )
+    }
+
+    /// The minimum gas price of a transaction replacing one with `gas_price`. It is higher than
+    /// `gas_price` unless that is already the maximum.
+    fn min_replacement_gas_price(&self, gas_price: u64) -> u64 {
+        let bump = (gas_price as u128 * self.replacement_gas_price_bump_pct as u128 + 99) / 100;
+        let min_gas_price = (gas_price as u128 + bump.max(1)).min(u64::max_value() as u128);
+        min_gas_price as u64
+    }
+
     /// Fetch mempool transaction by account address + sequence_number.
     pub(crate) fn get_mempool_txn(
         &self,
@@ -105,28 +149,20 @@ impl TransactionStore {
 
         // check if transaction is already present in Mempool
         // e.g. given request is update
-        // we allow increase in gas price to speed up process.
+        // we allow replacing it with a sufficient increase in gas price to speed up process.
         // ignores the case transaction hash is same for retrying submit transaction.
+        if let Some(status) = self.check_replacement(&txn.txn) {
+            return status;
+        }
         if let Some(txns) = self.transactions.get_mut(&address) {
-            if let Some(current_version) =
-                txns.get_mut(&sequence_number.transaction_sequence_number)
-            {
+            if let Some(current_version) = txns.get(&sequence_number.transaction_sequence_number) {
                 if current_version.txn == txn.txn {
                     return MempoolStatus::new(MempoolStatusCode::Accepted);
                 }
-                if current_version.txn.max_gas_amount() == txn.txn.max_gas_amount()
-                    && current_version.txn.payload() == txn.txn.payload()
-                    && current_version.txn.expiration_timestamp_secs()
-                        == txn.txn.expiration_timestamp_secs()
-                    && current_version.get_gas_price() < txn.get_gas_price()
-                {
-                    if let Some(txn) = txns.remove(&txn.sequence_info.transaction_sequence_number) {
-                        self.index_remove(&txn);
-                    }
-                } else {
-                    return MempoolStatus::new(MempoolStatusCode::InvalidUpdate).with_message(
-                        format!("Failed to update gas price to {}", txn.get_gas_price()),
-                    );
+                // evict the replaced transaction
+                if let Some(txn) = txns.remove(&txn.sequence_info.transaction_sequence_number) {
+                    self.eviction_log.record(&txn, EvictionReason::Replaced);
+                    self.index_remove(&txn);
                 }
             }
         }
@@ -220,6 +256,7 @@ impl TransactionStore {
                             txn.sequence_info.transaction_sequence_number
                         ))
                     );
+                    self.eviction_log.record(&txn, EvictionReason::MempoolFull);
                     self.index_remove(&txn);
                 }
             }
@@ -369,12 +406,57 @@ impl TransactionStore {
                     transaction.get_sender(),
                     transaction.sequence_info.transaction_sequence_number,
                 );
+                self.eviction_log
+                    .record(transaction, EvictionReason::Rejected);
                 self.index_remove(transaction);
             }
             debug!(LogSchema::new(LogEntry::CleanRejectedTxn).txns(txns_log));
         }
     }
 
+    /// Removes the transactions that `publishing_option` does not allow anymore. The following
+    /// transactions of their senders are parked, as they can't be executed until the sequence
+    /// number gaps are filled.
+    pub(crate) fn remove_disallowed_transactions(
+        &mut self,
+        publishing_option: &VMPublishingOption,
+    ) {
+        let mut disallowed_txns: Vec<_> = self
+            .transactions
+            .iter()
+            .flat_map(|(address, txns)| {
+                txns.iter()
+                    .filter(|(_, txn)| !publishing_option.allows(&txn.txn))
+                    .map(move |(sequence_number, _)| (*address, *sequence_number))
+            })
+            .collect();
+        disallowed_txns.sort_unstable();
+        let mut disallowed_iter = disallowed_txns.iter().peekable();
+
+        let mut txns_log = TxnsLog::new();
+        while let Some(&(address, sequence_number)) = disallowed_iter.next() {
+            if let Some(txns) = self.transactions.get_mut(&address) {
+                let park_range_end = disallowed_iter
+                    .peek()
+                    .filter(|(next_address, _)| address == *next_address)
+                    .map_or(Bound::Unbounded, |(_, next_sequence_number)| {
+                        Bound::Excluded(*next_sequence_number)
+                    });
+                for (_, t) in txns.range((Bound::Excluded(sequence_number), park_range_end)) {
+                    self.parking_lot_index.insert(t);
+                    self.priority_index.remove(t);
+                    self.timeline_index.remove(t);
+                }
+                if let Some(txn) = txns.remove(&sequence_number) {
+                    txns_log.add(address, sequence_number);
+                    self.eviction_log.record(&txn, EvictionReason::Disallowed);
+                    self.index_remove(&txn);
+                }
+            }
+        }
+        debug!(LogSchema::new(LogEntry::CleanDisallowedTxn).txns(txns_log));
+    }
+
     /// Removes transaction from all indexes.
     fn index_remove(&mut self, txn: &MempoolTransaction) {
         counters::CORE_MEMPOOL_REMOVED_TXNS.inc();
@@ -448,17 +530,19 @@ impl TransactionStore {
         by_system_ttl: bool,
         metrics_cache: &TtlCache<(AccountAddress, u64), SystemTime>,
     ) {
-        let (metric_label, index, log_event) = if by_system_ttl {
+        let (metric_label, index, log_event, eviction_reason) = if by_system_ttl {
             (
                 counters::GC_SYSTEM_TTL_LABEL,
                 &mut self.system_ttl_index,
                 LogEvent::SystemTTLExpiration,
+                EvictionReason::SystemTtl,
             )
         } else {
             (
                 counters::GC_CLIENT_EXP_LABEL,
                 &mut self.expiration_time_index,
                 LogEvent::ClientExpiration,
+                EvictionReason::ClientExpiration,
             )
         };
         counters::CORE_MEMPOOL_GC_EVENT_COUNT
@@ -506,6 +590,7 @@ impl TransactionStore {
                     }
 
                     // remove txn
+                    self.eviction_log.record(&txn, eviction_reason);
                     self.index_remove(&txn);
                 }
             }
@@ -519,6 +604,42 @@ impl TransactionStore {
         self.priority_index.iter()
     }
 
+    /// Aggregates the transactions in mempool by sender, given the system TTL they were inserted
+    /// with.
+    pub(crate) fn gen_summary(&self, system_transaction_timeout: Duration) -> MempoolSummary {
+        let mut senders: Vec<_> = self
+            .transactions
+            .iter()
+            .filter(|(_, txns)| !txns.is_empty())
+            .map(|(sender, txns)| {
+                let oldest_expiration_time = txns
+                    .values()
+                    .map(|txn| txn.expiration_time)
+                    .min()
+                    .unwrap_or_default();
+                SenderSummary {
+                    sender: *sender,
+                    num_transactions: txns.len(),
+                    oldest_timestamp_usecs: oldest_expiration_time
+                        .checked_sub(system_transaction_timeout)
+                        .unwrap_or_default()
+                        .as_micros() as u64,
+                    bytes: txns.values().map(|txn| txn.txn.raw_txn_bytes_len()).sum(),
+                }
+            })
+            .collect();
+        senders.sort_by(|a, b| {
+            b.num_transactions
+                .cmp(&a.num_transactions)
+                .then(a.oldest_timestamp_usecs.cmp(&b.oldest_timestamp_usecs))
+        });
+        MempoolSummary {
+            num_transactions: senders.iter().map(|sender| sender.num_transactions).sum(),
+            senders,
+            recent_evictions: self.eviction_log.recent_evictions(),
+        }
+    }
+
     pub(crate) fn gen_snapshot(
         &self,
         metrics_cache: &TtlCache<(AccountAddress, u64), SystemTime>,
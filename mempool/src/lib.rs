@@ -57,12 +57,14 @@
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod tests;
+pub use core_mempool::{Eviction, EvictionReason, MempoolSummary, SenderSummary};
 pub use shared_mempool::{
     bootstrap, network,
     types::{
         gen_mempool_reconfig_subscription, ConsensusRequest, ConsensusResponse,
         MempoolClientSender, SubmissionStatus, TransactionSummary,
     },
+    MempoolSummaryReader,
 };
 #[cfg(any(test, feature = "fuzzing"))]
 pub use tests::{fuzzing, mocks};
@@ -142,6 +142,7 @@ pub enum LogEntry {
     GCRemoveTxns,
     CleanCommittedTxn,
     CleanRejectedTxn,
+    CleanDisallowedTxn,
     ProcessReadyTxns,
     DBError,
     UpstreamNetwork,
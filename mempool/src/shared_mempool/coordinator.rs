@@ -23,8 +23,9 @@ use diem_config::{config::PeerNetworkId, network_id::NodeNetworkId};
 use diem_infallible::Mutex;
 use diem_logger::prelude::*;
 use diem_types::{
-    mempool_status::MempoolStatus, on_chain_config::OnChainConfigPayload,
-    transaction::SignedTransaction, vm_status::DiscardedVMStatus,
+    account_address::AccountAddress, mempool_status::MempoolStatus,
+    on_chain_config::OnChainConfigPayload, transaction::SignedTransaction,
+    vm_status::DiscardedVMStatus,
 };
 use futures::{
     channel::{mpsc, oneshot},
@@ -33,6 +34,7 @@ use futures::{
 };
 use mempool_notifications::{MempoolCommitNotification, MempoolNotificationListener};
 use std::{
+    collections::HashMap,
     sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
@@ -70,12 +72,19 @@ pub(crate) async fn coordinator<V>(
     // worker tasks that can process incoming transactions.
     let workers_available = smp.config.shared_mempool_max_concurrent_inbound_syncs;
     let bounded_executor = BoundedExecutor::new(workers_available, executor.clone());
+    // Client submissions are validated on their own workers, so they don't wait behind the
+    // broadcasts of other nodes.
+    let client_executor = BoundedExecutor::new(
+        smp.config.shared_mempool_max_concurrent_client_submissions,
+        executor.clone(),
+    );
+    let mut pending_client_submissions = HashMap::new();
 
     loop {
         let _timer = counters::MAIN_LOOP.start_timer();
         ::futures::select! {
             (msg, callback) = client_events.select_next_some() => {
-                handle_client_event(&mut smp, &bounded_executor, msg, callback).await;
+                handle_client_event(&mut smp, &client_executor, &mut pending_client_submissions, msg, callback).await;
             },
             msg = consensus_requests.select_next_some() => {
                 tasks::process_consensus_request(&smp.mempool, msg).await;
@@ -101,14 +110,23 @@ pub(crate) async fn coordinator<V>(
     ));
 }
 
+/// Validates a client submission concurrently with the submissions of other senders. Submissions
+/// of the same sender are processed in the order they were received: `pending_submissions` holds,
+/// for each sender, a receiver completed once its latest submission has been processed.
 async fn handle_client_event<V>(
     smp: &mut SharedMempool<V>,
     bounded_executor: &BoundedExecutor,
+    pending_submissions: &mut HashMap<AccountAddress, oneshot::Receiver<()>>,
     msg: SignedTransaction,
     callback: oneshot::Sender<anyhow::Result<(MempoolStatus, Option<DiscardedVMStatus>)>>,
 ) where
     V: TransactionValidation,
 {
+    // Forget the senders whose submissions have all been processed.
+    pending_submissions.retain(|_, processed| matches!(processed.try_recv(), Ok(None)));
+    let (processed_sender, processed_receiver) = oneshot::channel::<()>();
+    let previous_submission = pending_submissions.insert(msg.sender(), processed_receiver);
+
     // This timer measures how long it took for the bounded executor to *schedule* the
     // task.
     let _timer =
@@ -116,13 +134,17 @@ async fn handle_client_event<V>(
     // This timer measures how long it took for the task to go from scheduled to started.
     let task_start_timer =
         counters::task_spawn_latency_timer(counters::CLIENT_EVENT_LABEL, counters::START_LABEL);
+    let smp = smp.clone();
     bounded_executor
-        .spawn(tasks::process_client_transaction_submission(
-            smp.clone(),
-            msg,
-            callback,
-            task_start_timer,
-        ))
+        .spawn(async move {
+            if let Some(previous_submission) = previous_submission {
+                // The previous submission is processed once its sender is dropped.
+                let _ = previous_submission.await;
+            }
+            tasks::process_client_transaction_submission(smp, msg, callback, task_start_timer)
+                .await;
+            drop(processed_sender);
+        })
         .await;
 }
 
@@ -187,6 +209,7 @@ async fn handle_mempool_reconfig_event<V>(
         .spawn(tasks::process_config_update(
             config_update,
             smp.validator.clone(),
+            smp.mempool.clone(),
         ))
         .await;
 }
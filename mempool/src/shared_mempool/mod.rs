@@ -4,9 +4,9 @@
 pub mod network;
 mod runtime;
 pub(crate) mod types;
-pub use runtime::bootstrap;
 #[cfg(any(test, feature = "fuzzing"))]
 pub(crate) use runtime::start_shared_mempool;
+pub use runtime::{bootstrap, MempoolSummaryReader};
 mod coordinator;
 pub(crate) mod peer_manager;
 pub(crate) mod tasks;
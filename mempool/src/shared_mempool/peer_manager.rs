@@ -10,7 +10,10 @@ use crate::{
         types::{notify_subscribers, SharedMempool, SharedMempoolNotification},
     },
 };
-use diem_config::config::{MempoolConfig, PeerNetworkId, PeerRole, RoleType};
+use diem_config::{
+    config::{MempoolConfig, PeerNetworkId, PeerRole, RoleType},
+    network_id::NetworkId,
+};
 use diem_infallible::Mutex;
 use diem_logger::prelude::*;
 use diem_types::transaction::SignedTransaction;
@@ -44,11 +47,11 @@ pub(crate) struct PeerSyncState {
 }
 
 impl PeerSyncState {
-    pub fn new(metadata: ConnectionMetadata) -> Self {
+    pub fn new(metadata: ConnectionMetadata, batch_size: usize) -> Self {
         PeerSyncState {
             timeline_id: 0,
             is_alive: true,
-            broadcast_info: BroadcastInfo::new(),
+            broadcast_info: BroadcastInfo::new(batch_size),
             metadata,
         }
     }
@@ -88,14 +91,17 @@ pub struct BroadcastInfo {
     pub retry_batches: BTreeSet<BatchId>,
     // Whether broadcasting to this peer is in backoff mode, e.g. broadcasting at longer intervals.
     pub backoff_mode: bool,
+    // Max number of txns in the next fresh broadcast, adapted to how fast this peer ACKs them.
+    pub batch_size: usize,
 }
 
 impl BroadcastInfo {
-    fn new() -> Self {
+    fn new(batch_size: usize) -> Self {
         Self {
             sent_batches: BTreeMap::new(),
             retry_batches: BTreeSet::new(),
             backoff_mode: false,
+            batch_size,
         }
     }
 }
@@ -121,7 +127,8 @@ impl PeerManager {
             // If we have a new peer, let's insert new data, otherwise, let's just update the current state
             if is_new_peer {
                 counters::active_upstream_peers(&peer.raw_network_id()).inc();
-                peer_states.insert(peer, PeerSyncState::new(metadata));
+                let batch_size = self.max_batch_size(&peer);
+                peer_states.insert(peer, PeerSyncState::new(metadata, batch_size));
             } else if let Some(peer_state) = peer_states.get_mut(&peer) {
                 if !peer_state.is_alive {
                     counters::active_upstream_peers(&peer.raw_network_id()).inc();
@@ -271,10 +278,8 @@ impl PeerManager {
                 }
                 None => {
                     // Fresh broadcast
-                    let (txns, new_timeline_id) = mempool.read_timeline(
-                        state.timeline_id,
-                        self.mempool_config.shared_mempool_batch_size,
-                    );
+                    let (txns, new_timeline_id) =
+                        mempool.read_timeline(state.timeline_id, state.broadcast_info.batch_size);
                     (BatchId(state.timeline_id, new_timeline_id), txns)
                 }
             };
@@ -313,6 +318,11 @@ impl PeerManager {
         state.timeline_id = std::cmp::max(state.timeline_id, batch_id.1);
         // Turn off backoff mode after every broadcast.
         state.broadcast_info.backoff_mode = false;
+        if metric_label == Some(counters::EXPIRED_BROADCAST_LABEL) {
+            // The peer didn't ACK in time: send it smaller batches.
+            state.broadcast_info.batch_size =
+                self.decreased_batch_size(&peer, state.broadcast_info.batch_size);
+        }
         state
             .broadcast_info
             .sent_batches
@@ -405,7 +415,9 @@ impl PeerManager {
             return;
         };
 
-        if let Some(sent_timestamp) = sync_state.broadcast_info.sent_batches.remove(&batch_id) {
+        let rtt = if let Some(sent_timestamp) =
+            sync_state.broadcast_info.sent_batches.remove(&batch_id)
+        {
             let rtt = timestamp
                 .duration_since(sent_timestamp)
                 .expect("failed to calculate mempool broadcast RTT");
@@ -417,6 +429,7 @@ impl PeerManager {
                 .observe(rtt.as_secs_f64());
 
             counters::shared_mempool_pending_broadcasts(&peer).dec();
+            rtt
         } else {
             trace!(
                 LogSchema::new(LogEntry::ReceiveACK)
@@ -425,7 +438,7 @@ impl PeerManager {
                 "batch ID does not exist or expired"
             );
             return;
-        }
+        };
 
         trace!(
             LogSchema::new(LogEntry::ReceiveACK)
@@ -446,6 +459,45 @@ impl PeerManager {
         if backoff {
             sync_state.broadcast_info.backoff_mode = true;
         }
+
+        // Shrink the batches of peers that are slow to ACK or full (multiplicative decrease), and
+        // grow them back while the peer keeps up (additive increase).
+        let batch_size = sync_state.broadcast_info.batch_size;
+        sync_state.broadcast_info.batch_size = if backoff
+            || rtt > Duration::from_millis(self.mempool_config.shared_mempool_ack_latency_target_ms)
+        {
+            self.decreased_batch_size(&peer, batch_size)
+        } else {
+            self.increased_batch_size(&peer, batch_size)
+        };
+    }
+
+    /// The max number of txns broadcast in a batch to `peer`, given the bandwidth class of its
+    /// network.
+    fn max_batch_size(&self, peer: &PeerNetworkId) -> usize {
+        if peer.raw_network_id() == NetworkId::Public {
+            self.mempool_config.shared_mempool_public_batch_size
+        } else {
+            self.mempool_config.shared_mempool_batch_size
+        }
+    }
+
+    fn min_batch_size(&self, peer: &PeerNetworkId) -> usize {
+        std::cmp::min(
+            self.mempool_config.shared_mempool_min_batch_size,
+            self.max_batch_size(peer),
+        )
+    }
+
+    fn decreased_batch_size(&self, peer: &PeerNetworkId, batch_size: usize) -> usize {
+        std::cmp::max(batch_size / 2, self.min_batch_size(peer))
+    }
+
+    fn increased_batch_size(&self, peer: &PeerNetworkId, batch_size: usize) -> usize {
+        std::cmp::min(
+            batch_size + self.min_batch_size(peer),
+            self.max_batch_size(peer),
+        )
     }
 
     // If the origin is provided, checks whether this peer is an upstream peer based on configured preferences and
@@ -552,4 +604,71 @@ mod test {
         // Same the only equal case
         assert_eq!(Ordering::Equal, compare_prioritized_peers(&val_1, &val_1));
     }
+
+    #[test]
+    fn check_adaptive_batch_size() {
+        // Max batch sizes are 100 on the validator network and 50 on public ones, min is 10.
+        let peer_manager = PeerManager::new(RoleType::FullNode, MempoolConfig::default());
+        let validator = peer_network_id(PeerId::random(), NetworkId::Validator);
+        let public = peer_network_id(PeerId::random(), NetworkId::Public);
+        for peer in [&validator, &public].iter() {
+            peer_manager.add_peer(
+                (*peer).clone(),
+                ConnectionMetadata::mock_with_role_and_origin(
+                    peer.peer_id(),
+                    PeerRole::Unknown,
+                    ConnectionOrigin::Outbound,
+                ),
+            );
+        }
+        let batch_size = |peer: &PeerNetworkId| {
+            peer_manager.peer_states.lock()[peer]
+                .broadcast_info
+                .batch_size
+        };
+        let ack = |peer: &PeerNetworkId, rtt_ms: u64, backoff: bool| {
+            let batch_id = BatchId(0, 1);
+            let sent_time = SystemTime::now();
+            peer_manager
+                .peer_states
+                .lock()
+                .get_mut(peer)
+                .unwrap()
+                .broadcast_info
+                .sent_batches
+                .insert(batch_id, sent_time);
+            peer_manager.process_broadcast_ack(
+                peer.clone(),
+                bcs::to_bytes(&batch_id).unwrap(),
+                false,
+                backoff,
+                sent_time + Duration::from_millis(rtt_ms),
+            );
+        };
+
+        assert_eq!(batch_size(&validator), 100);
+        assert_eq!(batch_size(&public), 50);
+
+        // Slow and full peers get smaller batches...
+        ack(&validator, 1_000, false);
+        assert_eq!(batch_size(&validator), 50);
+        ack(&validator, 10, true);
+        assert_eq!(batch_size(&validator), 25);
+        // ...and bigger ones again once they keep up.
+        ack(&validator, 10, false);
+        assert_eq!(batch_size(&validator), 35);
+        for _ in 0..10 {
+            ack(&validator, 10, false);
+        }
+        assert_eq!(batch_size(&validator), 100);
+
+        for _ in 0..3 {
+            ack(&public, 1_000, false);
+        }
+        assert_eq!(batch_size(&public), 10);
+        for _ in 0..10 {
+            ack(&public, 10, false);
+        }
+        assert_eq!(batch_size(&public), 50);
+    }
 }
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::CoreMempool,
+    core_mempool::{CoreMempool, MempoolSummary},
     network::{MempoolNetworkEvents, MempoolNetworkSender},
     shared_mempool::{
         coordinator::{coordinator, gc_coordinator, snapshot_job},
@@ -15,6 +15,7 @@ use anyhow::Result;
 use channel::diem_channel;
 use diem_config::{config::NodeConfig, network_id::NodeNetworkId};
 use diem_infallible::{Mutex, RwLock};
+use diem_load_shedder::LoadShedder;
 use diem_types::{on_chain_config::OnChainConfigPayload, transaction::SignedTransaction};
 use futures::channel::{
     mpsc::{self, Receiver, UnboundedSender},
@@ -65,6 +66,7 @@ pub(crate) fn start_shared_mempool<V>(
         validator,
         peer_manager,
         subscribers,
+        load_shedder: LoadShedder::new(config.load_shedding.clone()),
     };
 
     executor.spawn(coordinator(
@@ -98,7 +100,7 @@ pub fn bootstrap(
     consensus_requests: Receiver<ConsensusRequest>,
     mempool_listener: MempoolNotificationListener,
     mempool_reconfig_events: diem_channel::Receiver<(), OnChainConfigPayload>,
-) -> Runtime {
+) -> (Runtime, MempoolSummaryReader) {
     let runtime = Builder::new_multi_thread()
         .thread_name("shared-mem")
         .enable_all()
@@ -109,7 +111,7 @@ pub fn bootstrap(
     start_shared_mempool(
         runtime.handle(),
         config,
-        mempool,
+        mempool.clone(),
         mempool_network_handles,
         client_events,
         consensus_requests,
@@ -119,5 +121,15 @@ pub fn bootstrap(
         vm_validator,
         vec![],
     );
-    runtime
+    (runtime, MempoolSummaryReader(mempool))
+}
+
+/// Reads the summary of the contents of a running mempool, e.g. to serve it to operators.
+#[derive(Clone)]
+pub struct MempoolSummaryReader(Arc<Mutex<CoreMempool>>);
+
+impl MempoolSummaryReader {
+    pub fn read(&self) -> MempoolSummary {
+        self.0.lock().gen_summary()
+    }
 }
@@ -17,11 +17,12 @@ use crate::{
 use anyhow::Result;
 use diem_config::config::PeerNetworkId;
 use diem_infallible::{Mutex, RwLock};
+use diem_load_shedder::{Admission, InboundPath};
 use diem_logger::prelude::*;
 use diem_metrics::HistogramTimer;
 use diem_types::{
     mempool_status::{MempoolStatus, MempoolStatusCode},
-    on_chain_config::OnChainConfigPayload,
+    on_chain_config::{OnChainConfigPayload, VMPublishingOption},
     transaction::SignedTransaction,
     vm_status::DiscardedVMStatus,
 };
@@ -112,6 +113,27 @@ pub(crate) async fn process_transaction_broadcast<V>(
     V: TransactionValidation,
 {
     timer.stop_and_record();
+    if !smp.peer_manager.is_upstream_peer(&peer, None) {
+        match smp
+            .load_shedder
+            .admit(InboundPath::NonPreferredMempoolBroadcast)
+        {
+            Admission::Accept => {}
+            Admission::Delay(delay) => tokio::time::sleep(delay).await,
+            Admission::Shed => {
+                // Drop the batch, and ask the peer to back off and retry it later.
+                update_ack_counter(&peer, counters::SENT_LABEL, true, true);
+                let ack_response = MempoolSyncMsg::BroadcastTransactionsResponse {
+                    request_id,
+                    retry: true,
+                    backoff: true,
+                };
+                send_ack_response(&mut smp, &peer, ack_response);
+                return;
+            }
+        }
+    }
+
     let _timer = counters::process_txn_submit_latency_timer(
         peer.raw_network_id().as_str(),
         peer.peer_id().short_str().as_str(),
@@ -120,6 +142,16 @@ pub(crate) async fn process_transaction_broadcast<V>(
     log_txn_process_results(&results, Some(peer.clone()));
 
     let ack_response = gen_ack_response(request_id, results, &peer);
+    send_ack_response(&mut smp, &peer, ack_response);
+}
+
+fn send_ack_response<V>(
+    smp: &mut SharedMempool<V>,
+    peer: &PeerNetworkId,
+    ack_response: MempoolSyncMsg,
+) where
+    V: TransactionValidation,
+{
     let network_sender = smp
         .network_senders
         .get_mut(&peer.network_id())
@@ -128,7 +160,7 @@ pub(crate) async fn process_transaction_broadcast<V>(
         counters::network_send_fail_inc(counters::ACK_TXNS);
         error!(
             LogSchema::event_log(LogEntry::BroadcastACK, LogEvent::NetworkSendFail)
-                .peer(&peer)
+                .peer(peer)
                 .error(&e.into())
         );
         return;
@@ -247,6 +279,22 @@ where
         })
         .collect();
 
+    // Reject the replacements of transactions in mempool whose gas price isn't bumped enough
+    // before spending time validating them.
+    let transactions: Vec<_> = {
+        let mempool = smp.mempool.lock();
+        transactions
+            .into_iter()
+            .filter_map(|(t, crsn_or_seqno)| match mempool.check_replacement(&t) {
+                None => Some((t, crsn_or_seqno)),
+                Some(mempool_status) => {
+                    statuses.push((t, (mempool_status, None)));
+                    None
+                }
+            })
+            .collect()
+    };
+
     // Track latency: VM validation
     let vm_validation_timer = counters::PROCESS_TXN_BREAKDOWN_LATENCY
         .with_label_values(&[counters::VM_VALIDATION_LABEL])
@@ -344,7 +392,7 @@ pub(crate) async fn process_consensus_request(mempool: &Mutex<CoreMempool>, req:
     debug!(LogSchema::event_log(LogEntry::Consensus, LogEvent::Received).consensus_msg(&req));
 
     let (resp, callback, counter_label) = match req {
-        ConsensusRequest::GetBlockRequest(max_block_size, transactions, callback) => {
+        ConsensusRequest::GetBlockRequest(max_block_size, lane_caps, transactions, callback) => {
             let exclude_transactions: HashSet<TxnPointer> = transactions
                 .iter()
                 .map(|txn| (txn.sender, txn.sequence_number))
@@ -357,7 +405,7 @@ pub(crate) async fn process_consensus_request(mempool: &Mutex<CoreMempool>, req:
                 let curr_time = diem_infallible::duration_since_epoch();
                 mempool.gc_by_expiration_time(curr_time);
                 let block_size = cmp::max(max_block_size, 1);
-                txns = mempool.get_block(block_size, exclude_transactions);
+                txns = mempool.get_block(block_size, lane_caps, exclude_transactions);
             }
             counters::mempool_service_transactions(counters::GET_BLOCK_LABEL, txns.len());
             txns.len();
@@ -421,6 +469,7 @@ pub async fn commit_txns(
 pub(crate) async fn process_config_update<V>(
     config_update: OnChainConfigPayload,
     validator: Arc<RwLock<V>>,
+    mempool: Arc<Mutex<CoreMempool>>,
 ) where
     V: TransactionValidation,
 {
@@ -429,6 +478,14 @@ pub(crate) async fn process_config_update<V>(
             .reconfig_update(config_update.clone())
     );
 
+    // Transactions accepted under a more permissive publishing option would now fail in the
+    // prologue, so they are dropped rather than proposed
+    if let Ok(publishing_option) = config_update.get::<VMPublishingOption>() {
+        mempool
+            .lock()
+            .remove_disallowed_transactions(&publishing_option);
+    }
+
     if let Err(e) = validator.write().restart(config_update) {
         counters::VM_RECONFIG_UPDATE_FAIL_COUNT.inc();
         error!(LogSchema::event_log(LogEntry::ReconfigUpdate, LogEvent::VMUpdateFail).error(&e));
@@ -14,10 +14,14 @@ use diem_config::{
     network_id::NodeNetworkId,
 };
 use diem_infallible::{Mutex, RwLock};
+use diem_load_shedder::LoadShedder;
 use diem_types::{
     account_address::AccountAddress,
     mempool_status::MempoolStatus,
-    on_chain_config::{ConfigID, DiemVersion, OnChainConfig, OnChainConfigPayload, VMConfig},
+    on_chain_config::{
+        BlockLaneCaps, ConfigID, DiemVersion, OnChainConfig, OnChainConfigPayload, VMConfig,
+        VMPublishingOption,
+    },
     transaction::SignedTransaction,
     vm_status::DiscardedVMStatus,
 };
@@ -45,6 +49,7 @@ where
     pub validator: Arc<RwLock<V>>,
     pub peer_manager: Arc<PeerManager>,
     pub subscribers: Vec<UnboundedSender<SharedMempoolNotification>>,
+    pub load_shedder: LoadShedder,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -120,6 +125,8 @@ pub enum ConsensusRequest {
     GetBlockRequest(
         // max block size
         u64,
+        // max number of transactions of each lane in the block
+        BlockLaneCaps,
         // transactions to exclude from the requested block
         Vec<TransactionSummary>,
         // callback to respond to
@@ -137,14 +144,14 @@ pub enum ConsensusRequest {
 impl fmt::Display for ConsensusRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let payload = match self {
-            ConsensusRequest::GetBlockRequest(block_size, excluded_txns, _) => {
+            ConsensusRequest::GetBlockRequest(block_size, lane_caps, excluded_txns, _) => {
                 let mut txns_str = "".to_string();
                 for tx in excluded_txns.iter() {
                     txns_str += &format!("{} ", tx);
                 }
                 format!(
-                    "GetBlockRequest [block_size: {}, excluded_txns: {}]",
-                    block_size, txns_str
+                    "GetBlockRequest [block_size: {}, lane_caps: {:?}, excluded_txns: {}]",
+                    block_size, lane_caps, txns_str
                 )
             }
             ConsensusRequest::RejectNotification(rejected_txns, _) => {
@@ -185,7 +192,11 @@ pub type SubmissionStatusBundle = (SignedTransaction, SubmissionStatus);
 pub type MempoolClientSender =
     mpsc::Sender<(SignedTransaction, oneshot::Sender<Result<SubmissionStatus>>)>;
 
-const MEMPOOL_SUBSCRIBED_CONFIGS: &[ConfigID] = &[DiemVersion::CONFIG_ID, VMConfig::CONFIG_ID];
+const MEMPOOL_SUBSCRIBED_CONFIGS: &[ConfigID] = &[
+    DiemVersion::CONFIG_ID,
+    VMConfig::CONFIG_ID,
+    VMPublishingOption::CONFIG_ID,
+];
 
 pub fn gen_mempool_reconfig_subscription(
 ) -> (ReconfigSubscription, Receiver<(), OnChainConfigPayload>) {
@@ -10,6 +10,7 @@ use diem_types::{
     account_config::{AccountSequenceInfo, XUS_NAME},
     chain_id::ChainId,
     mempool_status::MempoolStatusCode,
+    on_chain_config::BlockLaneCaps,
     transaction::{GovernanceRole, RawTransaction, Script, SignedTransaction},
 };
 use once_cell::sync::Lazy;
@@ -39,6 +40,7 @@ pub struct TestTransaction {
     pub(crate) gas_price: u64,
     pub(crate) governance_role: GovernanceRole,
     pub(crate) account_seqno_type: AccountSequenceInfo,
+    pub(crate) script_code: Vec<u8>,
 }
 
 impl TestTransaction {
@@ -49,9 +51,15 @@ impl TestTransaction {
             gas_price,
             governance_role: GovernanceRole::NonGovernanceRole,
             account_seqno_type: AccountSequenceInfo::Sequential(0),
+            script_code: vec![],
         }
     }
 
+    pub(crate) fn script_code(mut self, script_code: Vec<u8>) -> Self {
+        self.script_code = script_code;
+        self
+    }
+
     pub(crate) fn crsn(mut self, min_nonce: u64) -> Self {
         // Default CRSN size to 128
         self.account_seqno_type = AccountSequenceInfo::CRSN {
@@ -87,7 +95,7 @@ impl TestTransaction {
         let raw_txn = RawTransaction::new_script(
             TestTransaction::get_address(self.address),
             self.sequence_number,
-            Script::new(vec![], vec![], vec![]),
+            Script::new(self.script_code.clone(), vec![], vec![]),
             max_gas_amount,
             self.gas_price,
             XUS_NAME.to_owned(),
@@ -175,7 +183,7 @@ impl ConsensusMock {
         mempool: &mut CoreMempool,
         block_size: u64,
     ) -> Vec<SignedTransaction> {
-        let block = mempool.get_block(block_size, self.0.clone());
+        let block = mempool.get_block(block_size, BlockLaneCaps::default(), self.0.clone());
         self.0 = self
             .0
             .union(
@@ -2,15 +2,18 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    core_mempool::{CoreMempool, TimelineState, TtlCache},
+    core_mempool::{CoreMempool, EvictionReason, TimelineState, TtlCache},
     tests::common::{
         add_signed_txn, add_txn, add_txns_to_mempool, exist_in_metrics_cache, setup_mempool,
         TestTransaction,
     },
 };
 use diem_config::config::NodeConfig;
+use diem_crypto::HashValue;
 use diem_types::{
     account_config::AccountSequenceInfo,
+    mempool_status::MempoolStatusCode,
+    on_chain_config::{BlockLaneCaps, VMPublishingOption},
     transaction::{GovernanceRole, SignedTransaction},
 };
 use std::{
@@ -322,28 +325,31 @@ fn test_fail_for_same_gas_amount_and_not_same_expiration_time_crsn() {
 }
 
 #[test]
-fn test_update_invalid_transaction_in_mempool() {
+fn test_replace_transaction_in_mempool() {
     let (mut mempool, mut consensus) = setup_mempool();
     let txns = add_txns_to_mempool(
         &mut mempool,
         vec![TestTransaction::new(0, 0, 1), TestTransaction::new(1, 0, 2)],
     );
-    let updated_txn = TestTransaction::make_signed_transaction_with_max_gas_amount(
+    let replacement_txn = TestTransaction::make_signed_transaction_with_max_gas_amount(
         &TestTransaction::new(0, 0, 5),
         200,
     );
-    let _added_tnx = add_signed_txn(&mut mempool, updated_txn);
+    assert!(add_signed_txn(&mut mempool, replacement_txn.clone()).is_ok());
 
-    // Since both gas price and mas gas amount were updated, the ordering should not have changed.
-    // The second transaction with gas price 2 should come first.
+    // Any transaction with a high enough gas price replaces the one with the same sequence
+    // number, even if other fields changed.
+    assert_eq!(
+        consensus.get_block(&mut mempool, 1),
+        vec![replacement_txn.clone()]
+    );
     assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[1].clone()]);
-    let next_tnx = consensus.get_block(&mut mempool, 1);
-    assert_eq!(next_tnx, vec![txns[0].clone()]);
-    assert_eq!(next_tnx[0].gas_unit_price(), 1);
+    // The replaced transaction was evicted.
+    assert!(consensus.get_block(&mut mempool, 1).is_empty());
 }
 
 #[test]
-fn test_update_invalid_transaction_in_mempool_crsn() {
+fn test_replace_transaction_in_mempool_crsn() {
     let (mut mempool, mut consensus) = setup_mempool();
     let txns = add_txns_to_mempool(
         &mut mempool,
@@ -352,18 +358,39 @@ fn test_update_invalid_transaction_in_mempool_crsn() {
             TestTransaction::new(1, 0, 2).crsn(0),
         ],
     );
-    let updated_txn = TestTransaction::make_signed_transaction_with_max_gas_amount(
+    let replacement_txn = TestTransaction::make_signed_transaction_with_max_gas_amount(
         &TestTransaction::new(0, 0, 5).crsn(0),
         200,
     );
-    let _added_tnx = add_signed_txn(&mut mempool, updated_txn);
+    assert!(add_signed_txn(&mut mempool, replacement_txn.clone()).is_ok());
 
-    // Since both gas price and mas gas amount were updated, the ordering should not have changed.
-    // The second transaction with gas price 2 should come first.
+    assert_eq!(
+        consensus.get_block(&mut mempool, 1),
+        vec![replacement_txn.clone()]
+    );
     assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[1].clone()]);
-    let next_tnx = consensus.get_block(&mut mempool, 1);
-    assert_eq!(next_tnx, vec![txns[0].clone()]);
-    assert_eq!(next_tnx[0].gas_unit_price(), 1);
+}
+
+#[test]
+fn test_replace_transaction_without_gas_price_bump() {
+    let (mut mempool, mut consensus) = setup_mempool();
+    let txns = add_txns_to_mempool(&mut mempool, vec![TestTransaction::new(0, 0, 100)]);
+
+    // The default bump is 10%.
+    let underpriced_txn = TestTransaction::new(0, 0, 109).make_signed_transaction();
+    assert_eq!(
+        mempool
+            .check_replacement(&underpriced_txn)
+            .map(|status| status.code),
+        Some(MempoolStatusCode::InvalidUpdate)
+    );
+    assert!(add_signed_txn(&mut mempool, underpriced_txn).is_err());
+    assert_eq!(consensus.get_block(&mut mempool, 1), vec![txns[0].clone()]);
+
+    let replacement_txn = TestTransaction::new(0, 0, 110).make_signed_transaction();
+    assert_eq!(mempool.check_replacement(&replacement_txn), None);
+    // Resubmitting the transaction in mempool isn't a replacement.
+    assert_eq!(mempool.check_replacement(&txns[0]), None);
 }
 
 #[test]
@@ -432,7 +459,7 @@ fn test_system_ttl() {
 
     // GC routine should clear transaction from first insert but keep last one.
     mempool.gc();
-    let batch = mempool.get_block(1, HashSet::new());
+    let batch = mempool.get_block(1, BlockLaneCaps::default(), HashSet::new());
     assert_eq!(vec![transaction.make_signed_transaction()], batch);
 }
 
@@ -444,11 +471,16 @@ fn test_commit_callback() {
     let txns = add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 6, 1)]);
 
     // Check that pool is empty.
-    assert!(pool.get_block(1, HashSet::new()).is_empty());
+    assert!(pool
+        .get_block(1, BlockLaneCaps::default(), HashSet::new())
+        .is_empty());
     // Transaction 5 got back from consensus.
     pool.remove_transaction(&TestTransaction::get_address(1), 5, false);
     // Verify that we can execute transaction 6.
-    assert_eq!(pool.get_block(1, HashSet::new())[0], txns[0]);
+    assert_eq!(
+        pool.get_block(1, BlockLaneCaps::default(), HashSet::new())[0],
+        txns[0]
+    );
 }
 
 #[test]
@@ -462,7 +494,11 @@ fn test_sequence_number_cache() {
     // for AC is 0).
     add_txns_to_mempool(&mut pool, vec![TestTransaction::new(1, 6, 1)]);
     // Verify that we can execute transaction 6.
-    assert_eq!(pool.get_block(1, HashSet::new()).len(), 1);
+    assert_eq!(
+        pool.get_block(1, BlockLaneCaps::default(), HashSet::new())
+            .len(),
+        1
+    );
 }
 
 #[test]
@@ -559,7 +595,7 @@ fn test_parking_lot_eviction() {
     }
     // Make sure that we have correct txns in Mempool.
     let mut txns: Vec<_> = pool
-        .get_block(5, HashSet::new())
+        .get_block(5, BlockLaneCaps::default(), HashSet::new())
         .iter()
         .map(SignedTransaction::sequence_number)
         .collect();
@@ -588,7 +624,7 @@ fn test_parking_lot_evict_only_for_ready_txn_insertion() {
 
     // Make sure that we have correct txns in Mempool.
     let mut txns: Vec<_> = pool
-        .get_block(5, HashSet::new())
+        .get_block(5, BlockLaneCaps::default(), HashSet::new())
         .iter()
         .map(SignedTransaction::sequence_number)
         .collect();
@@ -631,7 +667,7 @@ fn test_gc_ready_transaction() {
     pool.gc_by_expiration_time(Duration::from_secs(1));
 
     // Make sure txns 2 and 3 became not ready and we can't read them from any API.
-    let block = pool.get_block(10, HashSet::new());
+    let block = pool.get_block(10, BlockLaneCaps::default(), HashSet::new());
     assert_eq!(block.len(), 1);
     assert_eq!(block[0].sequence_number(), 0);
 
@@ -640,6 +676,162 @@ fn test_gc_ready_transaction() {
     assert_eq!(timeline[0].sequence_number(), 0);
 }
 
+#[test]
+fn test_block_lane_caps() {
+    let mut pool = setup_mempool().0;
+    let mut governance_txn = TestTransaction::new(0, 0, 1);
+    governance_txn.governance_role = GovernanceRole::DiemRoot;
+    let mut governance_txns = vec![governance_txn.clone()];
+    governance_txn.sequence_number = 1;
+    governance_txns.push(governance_txn);
+    let governance_txns = add_txns_to_mempool(&mut pool, governance_txns);
+    let user_txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(1, 0, 100),
+            TestTransaction::new(2, 0, 10),
+            TestTransaction::new(2, 1, 10),
+        ],
+    );
+    let block_with_caps = |pool: &mut CoreMempool, max_governance_txns, max_user_txns| {
+        let lane_caps = BlockLaneCaps {
+            max_governance_txns,
+            max_user_txns,
+        };
+        pool.get_block(10, lane_caps, HashSet::new())
+    };
+
+    // Governance transactions come first regardless of gas price, then user ones by gas price.
+    assert_eq!(
+        block_with_caps(&mut pool, 1, 2),
+        vec![
+            governance_txns[0].clone(),
+            user_txns[0].clone(),
+            user_txns[1].clone()
+        ]
+    );
+    // A full user lane doesn't take the place of governance transactions.
+    assert_eq!(
+        block_with_caps(&mut pool, 2, 0),
+        vec![governance_txns[0].clone(), governance_txns[1].clone()]
+    );
+    assert_eq!(
+        pool.get_block(3, BlockLaneCaps::default(), HashSet::new()),
+        vec![
+            governance_txns[0].clone(),
+            governance_txns[1].clone(),
+            user_txns[0].clone()
+        ]
+    );
+}
+
+#[test]
+fn test_summary() {
+    let mut pool = setup_mempool().0;
+    let txns = add_txns_to_mempool(
+        &mut pool,
+        vec![
+            TestTransaction::new(0, 0, 1),
+            TestTransaction::new(1, 0, 1),
+            TestTransaction::new(1, 1, 1),
+        ],
+    );
+    let expiring_txn =
+        TestTransaction::new(1, 2, 1).make_signed_transaction_with_expiration_time(0);
+    add_signed_txn(&mut pool, expiring_txn).unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 0, 10)).unwrap();
+    pool.gc_by_expiration_time(Duration::from_secs(1));
+
+    let summary = pool.gen_summary();
+    assert_eq!(summary.num_transactions, 3);
+    let senders: Vec<_> = summary
+        .senders
+        .iter()
+        .map(|sender| (sender.sender, sender.num_transactions))
+        .collect();
+    assert_eq!(
+        senders,
+        vec![
+            (TestTransaction::get_address(1), 2),
+            (TestTransaction::get_address(0), 1),
+        ]
+    );
+    assert_eq!(
+        summary.senders[0].bytes,
+        txns[1].raw_txn_bytes_len() + txns[2].raw_txn_bytes_len()
+    );
+    assert!(summary.senders[0].oldest_timestamp_usecs > 0);
+
+    let evictions: Vec<_> = summary
+        .recent_evictions
+        .iter()
+        .map(|eviction| (eviction.sender, eviction.sequence_number, eviction.reason))
+        .collect();
+    assert_eq!(
+        evictions,
+        vec![
+            (TestTransaction::get_address(0), 0, EvictionReason::Replaced),
+            (
+                TestTransaction::get_address(1),
+                2,
+                EvictionReason::ClientExpiration
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_remove_disallowed_transactions() {
+    let mut pool = setup_mempool().0;
+    add_txn(&mut pool, TestTransaction::new(0, 0, 1)).unwrap();
+    add_txn(
+        &mut pool,
+        TestTransaction::new(0, 1, 1).script_code(vec![1]),
+    )
+    .unwrap();
+    add_txn(&mut pool, TestTransaction::new(0, 2, 1)).unwrap();
+    add_txn(&mut pool, TestTransaction::new(1, 0, 1)).unwrap();
+
+    // Scripts stay allowed while the allowlist is empty
+    pool.remove_disallowed_transactions(&VMPublishingOption::custom_scripts());
+    assert_eq!(pool.gen_summary().num_transactions, 4);
+
+    // Only the empty script is allowed
+    let allowlist = vec![HashValue::sha3_256_of(&[])];
+    pool.remove_disallowed_transactions(&VMPublishingOption::locked(allowlist));
+    assert_eq!(pool.gen_summary().num_transactions, 3);
+
+    // The transaction following the removed one is parked
+    let block = pool.get_block(10, BlockLaneCaps::default(), HashSet::new());
+    let mut block: Vec<_> = block
+        .iter()
+        .map(|txn| (txn.sender(), txn.sequence_number()))
+        .collect();
+    block.sort();
+    let mut expected = vec![
+        (TestTransaction::get_address(0), 0),
+        (TestTransaction::get_address(1), 0),
+    ];
+    expected.sort();
+    assert_eq!(block, expected);
+    assert_eq!(pool.get_parking_lot_size(), 1);
+
+    let evictions: Vec<_> = pool
+        .gen_summary()
+        .recent_evictions
+        .iter()
+        .map(|eviction| (eviction.sender, eviction.sequence_number, eviction.reason))
+        .collect();
+    assert_eq!(
+        evictions,
+        vec![(
+            TestTransaction::get_address(0),
+            1,
+            EvictionReason::Disallowed
+        )]
+    );
+}
+
 #[test]
 fn test_clean_stuck_transactions() {
     let mut pool = setup_mempool().0;
@@ -656,7 +848,7 @@ fn test_clean_stuck_transactions() {
         TimelineState::NotReady,
         GovernanceRole::NonGovernanceRole,
     );
-    let block = pool.get_block(10, HashSet::new());
+    let block = pool.get_block(10, BlockLaneCaps::default(), HashSet::new());
     assert_eq!(block.len(), 1);
     assert_eq!(block[0].sequence_number(), 10);
 }
@@ -7,6 +7,7 @@ use crate::{
 };
 use diem_config::config::NodeConfig;
 use diem_infallible::{Mutex, RwLock};
+use diem_load_shedder::LoadShedder;
 use diem_types::transaction::SignedTransaction;
 use proptest::{
     arbitrary::any,
@@ -43,6 +44,7 @@ pub fn test_mempool_process_incoming_transactions_impl(
         validator: vm_validator,
         peer_manager: Arc::new(PeerManager::new(config.base.role, config.mempool)),
         subscribers: vec![],
+        load_shedder: LoadShedder::new(config.load_shedding),
     };
 
     let _ = tasks::process_incoming_transactions(&smp, txns, timeline_state);
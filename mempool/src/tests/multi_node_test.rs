@@ -13,7 +13,7 @@ use crate::{
     },
 };
 use diem_config::config::{NodeConfig, PeerRole};
-use diem_types::{transaction::SignedTransaction, PeerId};
+use diem_types::{on_chain_config::BlockLaneCaps, transaction::SignedTransaction, PeerId};
 use netcore::transport::ConnectionOrigin;
 use network::{
     peer_manager::{PeerManagerNotification, PeerManagerRequest},
@@ -165,6 +165,7 @@ impl TestHarness {
         if let Some(mempool_config) = mempool_config {
             if let Some(batch_size) = mempool_config.broadcast_batch_size {
                 config.mempool.shared_mempool_batch_size = batch_size;
+                config.mempool.shared_mempool_public_batch_size = batch_size;
             }
             if let Some(mempool_size) = mempool_config.mempool_size {
                 config.mempool.capacity = mempool_size;
@@ -353,10 +354,11 @@ impl TestHarness {
 
                         // Verify transaction was inserted into Mempool
                         if check_txns_in_mempool {
-                            let block = self
-                                .node(sender_id)
-                                .mempool()
-                                .get_block(100, HashSet::new());
+                            let block = self.node(sender_id).mempool().get_block(
+                                100,
+                                BlockLaneCaps::default(),
+                                HashSet::new(),
+                            );
                             for txn in transactions.iter() {
                                 assert!(block.contains(txn));
                             }
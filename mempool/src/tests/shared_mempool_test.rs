@@ -7,8 +7,8 @@ use crate::{
     tests::common::{batch_add_signed_txn, TestTransaction},
     ConsensusRequest,
 };
-use diem_types::transaction::Transaction;
-use futures::{channel::oneshot, executor::block_on, sink::SinkExt};
+use diem_types::{mempool_status::MempoolStatusCode, transaction::Transaction};
+use futures::{channel::oneshot, executor::block_on, future::join_all, sink::SinkExt};
 use mempool_notifications::MempoolNotificationSender;
 use tokio::runtime::Builder;
 
@@ -94,3 +94,45 @@ fn test_mempool_notify_committed_txns() {
     assert_eq!(timeline.len(), 1);
     assert_eq!(timeline.get(0).unwrap(), &kept_txn);
 }
+
+#[test]
+fn test_concurrent_client_submissions() {
+    let smp = MockSharedMempool::new(None);
+
+    // Submit the transactions of several senders before waiting for any of them to be processed.
+    let txns: Vec<_> = (0..5)
+        .flat_map(|sequence_number| {
+            (0..2).map(move |address| {
+                TestTransaction::new(address, sequence_number, 1).make_signed_transaction()
+            })
+        })
+        .collect();
+    let mut ac_client = smp.ac_client.clone();
+    let statuses = block_on(async {
+        let mut callbacks = vec![];
+        for txn in &txns {
+            let (callback, callback_rcv) = oneshot::channel();
+            assert!(ac_client.send((txn.clone(), callback)).await.is_ok());
+            callbacks.push(callback_rcv);
+        }
+        join_all(callbacks).await
+    });
+    for status in statuses {
+        let (mempool_status, vm_status) = status.unwrap().unwrap();
+        assert_eq!(mempool_status.code, MempoolStatusCode::Accepted);
+        assert_eq!(vm_status, None);
+    }
+
+    // All the submissions were added to mempool, in order for each sender.
+    let timeline = smp.read_timeline(0, 20);
+    assert_eq!(timeline.len(), txns.len());
+    for address in 0..2 {
+        let sender = TestTransaction::get_address(address);
+        let sequence_numbers: Vec<_> = timeline
+            .iter()
+            .filter(|txn| txn.sender() == sender)
+            .map(|txn| txn.sequence_number())
+            .collect();
+        assert_eq!(sequence_numbers, (0..5).collect::<Vec<_>>());
+    }
+}
@@ -12,10 +12,10 @@
 use channel::{self, message_queues::QueueStyle};
 use diem_config::{
     config::{
-        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, RateLimitConfig, RoleType,
-        CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS, MAX_CONCURRENT_NETWORK_REQS,
-        MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE, MAX_FULLNODE_OUTBOUND_CONNECTIONS,
-        MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
+        DiscoveryMethod, NetworkConfig, Peer, PeerRole, PeerSet, ProtocolRateLimitConfig,
+        RateLimitConfig, RoleType, CONNECTION_BACKOFF_BASE, CONNECTIVITY_CHECK_INTERVAL_MS,
+        MAX_CONCURRENT_NETWORK_REQS, MAX_CONNECTION_DELAY_MS, MAX_FRAME_SIZE,
+        MAX_FULLNODE_OUTBOUND_CONNECTIONS, MAX_INBOUND_CONNECTIONS, NETWORK_CHANNEL_SIZE,
     },
     network_id::NetworkContext,
 };
@@ -38,6 +38,9 @@ use network::{
     protocols::{
         health_checker::{self, builder::HealthCheckerBuilder},
         network::{NewNetworkEvents, NewNetworkSender},
+        peer_monitor::{
+            self, builder::PeerMonitorBuilder, PeerMonitoringStorage, SyncedVersionProvider,
+        },
     },
     ProtocolId,
 };
@@ -70,6 +73,7 @@ pub struct NetworkBuilder {
     discovery_listeners: Option<Vec<DiscoveryChangeListener>>,
     connectivity_manager_builder: Option<ConnectivityManagerBuilder>,
     health_checker_builder: Option<HealthCheckerBuilder>,
+    peer_monitor_builder: Option<PeerMonitorBuilder>,
     peer_manager_builder: PeerManagerBuilder,
     peer_metadata_storage: Arc<PeerMetadataStorage>,
 
@@ -80,6 +84,7 @@ pub struct NetworkBuilder {
 impl NetworkBuilder {
     /// Return a new NetworkBuilder initialized with default configuration values.
     // TODO:  Remove `pub`.  NetworkBuilder should only be created thorugh `::create()`
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         chain_id: ChainId,
         trusted_peers: Arc<RwLock<PeerSet>>,
@@ -94,6 +99,7 @@ impl NetworkBuilder {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        inbound_protocol_rate_limits: HashMap<String, ProtocolRateLimitConfig>,
     ) -> Self {
         let peer_metadata_storage = Arc::new(PeerMetadataStorage::new());
         // A network cannot exist without a PeerManager
@@ -113,6 +119,7 @@ impl NetworkBuilder {
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            inbound_protocol_rate_limits,
         );
 
         NetworkBuilder {
@@ -123,6 +130,7 @@ impl NetworkBuilder {
             discovery_listeners: None,
             connectivity_manager_builder: None,
             health_checker_builder: None,
+            peer_monitor_builder: None,
             peer_manager_builder,
             reconfig_subscriptions: vec![],
             peer_metadata_storage,
@@ -154,6 +162,7 @@ impl NetworkBuilder {
             MAX_INBOUND_CONNECTIONS,
             None,
             None,
+            HashMap::new(),
         );
 
         builder.add_connectivity_manager(
@@ -209,6 +218,7 @@ impl NetworkBuilder {
             config.max_inbound_connections,
             config.inbound_rate_limit_config,
             config.outbound_rate_limit_config,
+            config.inbound_protocol_rate_limits.clone(),
         );
 
         network_builder.add_connection_monitoring(
@@ -294,6 +304,14 @@ impl NetworkBuilder {
             );
         }
 
+        if let Some(peer_monitor_builder) = self.peer_monitor_builder.as_mut() {
+            peer_monitor_builder.start(executor);
+            debug!(
+                NetworkSchema::new(&self.network_context),
+                "{} Started peer monitor", self.network_context
+            );
+        }
+
         if let Some(discovery_listeners) = self.discovery_listeners.take() {
             discovery_listeners
                 .into_iter()
@@ -432,6 +450,35 @@ impl NetworkBuilder {
         self
     }
 
+    /// Add a PeerMonitor to the network, which records the round trip time, role and synced
+    /// version of the connected peers in `storage`, and reports the version returned by
+    /// `synced_version` to the peers monitoring this node.
+    pub fn add_peer_monitoring(
+        &mut self,
+        probe_interval_ms: u64,
+        probe_timeout_ms: u64,
+        storage: Arc<PeerMonitoringStorage>,
+        synced_version: SyncedVersionProvider,
+    ) -> &mut Self {
+        let (pm_network_tx, pm_network_rx) =
+            self.add_protocol_handler(peer_monitor::network_endpoint_config());
+        self.peer_monitor_builder = Some(PeerMonitorBuilder::new(
+            self.network_context(),
+            self.time_service.clone(),
+            probe_interval_ms,
+            probe_timeout_ms,
+            pm_network_tx,
+            pm_network_rx,
+            storage,
+            synced_version,
+        ));
+        debug!(
+            NetworkSchema::new(&self.network_context),
+            "{} Created peer monitor", self.network_context
+        );
+        self
+    }
+
     /// Adds a endpoints for the provided configuration.  Returns NetworkSender and NetworkEvent which
     /// can be attached to other components.
     pub fn add_protocol_handler<SenderT, EventT>(
@@ -265,7 +265,7 @@ mod tests {
             ValidatorSet::CONFIG_ID,
             bcs::to_bytes(&validator_set).unwrap(),
         );
-        let payload = OnChainConfigPayload::new(1, Arc::new(configs));
+        let payload = OnChainConfigPayload::new(1, 0, Arc::new(configs));
         reconfig_tx.publish(payload).unwrap();
     }
 
@@ -0,0 +1,703 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! Connections are authenticated by the Noise upgrade layered on top of this transport, like they
+//! are for TCP connections, so the TLS certificates required by QUIC are self-signed and not
+//! verified. Each connection carries a bidirectional stream, read and written through its
+//! [`QuicSocket`], and any number of unidirectional substreams, see [`QuicSubstreams`].
+use crate::transport::{
+    tcp::{resolve_with_filter, TcpListenerStream, TcpSocket, TcpTransport},
+    Transport,
+};
+use diem_infallible::Mutex;
+use diem_types::{
+    network_address::{parse_dns_udp_quic, parse_ip_udp_quic, NetworkAddress, Protocol},
+    PeerId,
+};
+use futures::{
+    future::{self, BoxFuture, Either, FutureExt, TryFutureExt},
+    io::{AsyncRead, AsyncWrite},
+    stream::{self, BoxStream, StreamExt, TryStreamExt},
+};
+use quinn::{
+    Certificate, CertificateChain, ClientConfig, ClientConfigBuilder, Connection, Endpoint,
+    IncomingUniStreams, NewConnection, PrivateKey, RecvStream, SendStream, ServerConfig,
+    ServerConfigBuilder, TransportConfig,
+};
+use std::{
+    error::Error,
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// The ALPN protocol negotiated by DiemNet QUIC connections.
+const ALPN_PROTOCOL: &[u8] = b"diemnet";
+/// The server name dialed, which isn't verified.
+const SERVER_NAME: &str = "diem";
+/// Interval at which idle connections are kept alive, so that NAT bindings don't expire.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
+/// How long dialing a QUIC connection may take before falling back to TCP, as remotes which don't
+/// listen for QUIC connections never answer.
+const QUIC_DIAL_TIMEOUT: Duration = Duration::from_secs(2);
+/// The maximum number of substreams whose tag is being read at the same time.
+const MAX_PENDING_SUBSTREAMS: usize = 16;
+
+/// Transport to build QUIC connections
+///
+/// Outbound connections are dialed from the listening endpoint when the address family of the
+/// remote matches, so that remotes see the same address for inbound and outbound connections.
+#[derive(Clone, Default)]
+pub struct QuicTransport {
+    endpoint: Arc<Mutex<Option<Endpoint>>>,
+}
+
+impl fmt::Debug for QuicTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicTransport").finish()
+    }
+}
+
+impl QuicTransport {
+    /// Returns an endpoint to dial `remote_addr` from: the listening endpoint if it has the same
+    /// address family, or a new client endpoint bound to an ephemeral port otherwise.
+    fn dialing_endpoint(&self, remote_addr: &SocketAddr) -> io::Result<Endpoint> {
+        let mut endpoint = self.endpoint.lock();
+        if let Some(endpoint) = endpoint.as_ref() {
+            if endpoint.local_addr()?.is_ipv4() == remote_addr.is_ipv4() {
+                return Ok(endpoint.clone());
+            }
+        }
+
+        let unspecified_ip = if remote_addr.is_ipv4() {
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+        } else {
+            IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+        };
+        let mut builder = Endpoint::builder();
+        builder.default_client_config(client_config());
+        let (client_endpoint, _incoming) = builder
+            .bind(&SocketAddr::new(unspecified_ip, 0))
+            .map_err(other_error)?;
+        if endpoint.is_none() {
+            *endpoint = Some(client_endpoint.clone());
+        }
+        Ok(client_endpoint)
+    }
+
+    async fn connect(self, addr: NetworkAddress) -> io::Result<QuicSocket> {
+        let protos = addr.as_slice();
+
+        let socketaddrs = if let Some(((ipaddr, port), _addr_suffix)) = parse_ip_udp_quic(protos) {
+            vec![SocketAddr::new(ipaddr, port)]
+        } else if let Some(((ip_filter, dns_name, port), _addr_suffix)) = parse_dns_udp_quic(protos)
+        {
+            resolve_with_filter(ip_filter, dns_name.as_ref(), port)
+                .await?
+                .collect()
+        } else {
+            return Err(invalid_addr_error(&addr));
+        };
+
+        // try to connect until the first succeeds
+        let mut last_err = None;
+        for socketaddr in socketaddrs {
+            match self.connect_socketaddr(socketaddr).await {
+                Ok(socket) => return Ok(socket),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("could not resolve dns name to any address: {}", addr),
+            )
+        }))
+    }
+
+    async fn connect_socketaddr(&self, remote_addr: SocketAddr) -> io::Result<QuicSocket> {
+        let endpoint = self.dialing_endpoint(&remote_addr)?;
+        let new_connection = endpoint
+            .connect(&remote_addr, SERVER_NAME)
+            .map_err(other_error)?
+            .await
+            .map_err(other_error)?;
+        let NewConnection {
+            connection,
+            uni_streams,
+            ..
+        } = new_connection;
+        let (send, recv) = connection.open_bi().await.map_err(other_error)?;
+        Ok(QuicSocket::new(send, recv, connection, uni_streams))
+    }
+}
+
+impl Transport for QuicTransport {
+    type Output = QuicSocket;
+    type Error = io::Error;
+    type Listener = BoxStream<'static, io::Result<(Self::Inbound, NetworkAddress)>>;
+    type Inbound = BoxFuture<'static, io::Result<QuicSocket>>;
+    type Outbound = BoxFuture<'static, io::Result<QuicSocket>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let ((ipaddr, port), addr_suffix) =
+            parse_ip_udp_quic(addr.as_slice()).ok_or_else(|| invalid_addr_error(&addr))?;
+        if !addr_suffix.is_empty() {
+            return Err(invalid_addr_error(&addr));
+        }
+
+        let mut builder = Endpoint::builder();
+        builder.listen(server_config()?);
+        builder.default_client_config(client_config());
+        let (endpoint, incoming) = builder
+            .bind(&SocketAddr::new(ipaddr, port))
+            .map_err(other_error)?;
+        let listen_addr = quic_network_address(endpoint.local_addr()?);
+        *self.endpoint.lock() = Some(endpoint);
+
+        let listener = incoming
+            .map(|connecting| {
+                let dialer_addr = quic_network_address(connecting.remote_address());
+                let inbound = async move {
+                    let NewConnection {
+                        connection,
+                        uni_streams,
+                        mut bi_streams,
+                        ..
+                    } = connecting.await.map_err(other_error)?;
+                    let (send, recv) = bi_streams
+                        .next()
+                        .await
+                        .ok_or_else(|| {
+                            io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "QUIC connection closed before opening a stream",
+                            )
+                        })?
+                        .map_err(other_error)?;
+                    Ok(QuicSocket::new(send, recv, connection, uni_streams))
+                };
+                Ok((inbound.boxed(), dialer_addr))
+            })
+            .boxed();
+
+        Ok((listener, listen_addr))
+    }
+
+    fn dial(&self, _peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        // ensure addr is well formed to save some work before potentially
+        // spawning a dial task that will fail anyway.
+        let protos = addr.as_slice();
+        parse_ip_udp_quic(protos)
+            .map(|_| ())
+            .or_else(|| parse_dns_udp_quic(protos).map(|_| ()))
+            .ok_or_else(|| invalid_addr_error(&addr))?;
+
+        Ok(self.clone().connect(addr).boxed())
+    }
+}
+
+/// Transport which listens for both TCP and QUIC connections on the same port, and dials QUIC
+/// connections when the remote accepts them, TCP ones otherwise.
+///
+/// This lets a node listening on `/ip4/<addr>/udp/<port>/quic` keep accepting connections from
+/// the peers which only dial TCP. A remote whose address names QUIC is first dialed over QUIC and
+/// then over TCP, on the same port, while a remote whose address names TCP is dialed over TCP
+/// only, so that dialing the peers which don't listen for QUIC isn't held up. Whether the
+/// connection carries substreams is then negotiated by the DiemNet handshake.
+#[derive(Clone, Debug, Default)]
+pub struct TcpQuicTransport {
+    tcp: TcpTransport,
+    quic: QuicTransport,
+}
+
+impl TcpQuicTransport {
+    pub fn new(tcp: TcpTransport) -> Self {
+        Self {
+            tcp,
+            quic: QuicTransport::default(),
+        }
+    }
+}
+
+impl Transport for TcpQuicTransport {
+    type Output = Either<TcpSocket, QuicSocket>;
+    type Error = io::Error;
+    type Listener = BoxStream<'static, io::Result<(Self::Inbound, NetworkAddress)>>;
+    type Inbound = BoxFuture<'static, io::Result<Self::Output>>;
+    type Outbound = BoxFuture<'static, io::Result<Self::Output>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let ipaddr = match parse_ip_udp_quic(addr.as_slice()) {
+            Some(((ipaddr, _port), _addr_suffix)) => ipaddr,
+            None => {
+                let (tcp_listener, listen_addr) = self.tcp.listen_on(addr)?;
+                return Ok((tcp_inbounds(tcp_listener).boxed(), listen_addr));
+            }
+        };
+
+        let (quic_listener, listen_addr) = self.quic.listen_on(addr)?;
+        let port = match listen_addr.as_slice() {
+            [_, Protocol::Udp(port), Protocol::Quic] => *port,
+            _ => unreachable!("QUIC listen address is always ip+udp+quic"),
+        };
+        let (tcp_listener, _) = self
+            .tcp
+            .listen_on(NetworkAddress::from(SocketAddr::new(ipaddr, port)))?;
+
+        let quic_inbounds = quic_listener
+            .map_ok(|(inbound, dialer_addr)| (inbound.map_ok(Either::Right).boxed(), dialer_addr));
+        let listener = stream::select(tcp_inbounds(tcp_listener), quic_inbounds).boxed();
+        Ok((listener, listen_addr))
+    }
+
+    fn dial(&self, peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        let (quic_addr, tcp_addr) =
+            quic_and_tcp_addrs(&addr).ok_or_else(|| invalid_addr_error(&addr))?;
+        // The TCP connection is only dialed once the future is polled.
+        let tcp_outbound = self.tcp.dial(peer_id, tcp_addr)?;
+        if let [_, Protocol::Tcp(_)] = addr.as_slice() {
+            return Ok(tcp_outbound.map_ok(Either::Left).boxed());
+        }
+        let quic_outbound = self.quic.dial(peer_id, quic_addr)?;
+        let outbound = async move {
+            match tokio::time::timeout(QUIC_DIAL_TIMEOUT, quic_outbound).await {
+                Ok(Ok(socket)) => Ok(Either::Right(socket)),
+                Ok(Err(_)) | Err(_) => tcp_outbound.await.map(Either::Left),
+            }
+        };
+        Ok(outbound.boxed())
+    }
+}
+
+/// Returns the QUIC and the TCP address of the host and port of `addr`, which may name either.
+fn quic_and_tcp_addrs(addr: &NetworkAddress) -> Option<(NetworkAddress, NetworkAddress)> {
+    let (host, port) = match addr.as_slice() {
+        [host, Protocol::Tcp(port)] | [host, Protocol::Udp(port), Protocol::Quic] => (host, *port),
+        _ => return None,
+    };
+    let host = NetworkAddress::from(host.clone());
+    let quic_addr = host.clone().push(Protocol::Udp(port)).push(Protocol::Quic);
+    let tcp_addr = host.push(Protocol::Tcp(port));
+    Some((quic_addr, tcp_addr))
+}
+
+fn tcp_inbounds(
+    tcp_listener: TcpListenerStream,
+) -> impl stream::Stream<
+    Item = io::Result<(
+        BoxFuture<'static, io::Result<Either<TcpSocket, QuicSocket>>>,
+        NetworkAddress,
+    )>,
+> {
+    tcp_listener
+        .map_ok(|(inbound, dialer_addr)| (inbound.map_ok(Either::Left).boxed(), dialer_addr))
+}
+
+fn server_config() -> io::Result<ServerConfig> {
+    let cert =
+        rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()]).map_err(other_error)?;
+    let cert_der = cert.serialize_der().map_err(other_error)?;
+    let key_der = cert.serialize_private_key_der();
+
+    let mut config = ServerConfig::default();
+    config.transport = transport_config();
+    let mut builder = ServerConfigBuilder::new(config);
+    builder.protocols(&[ALPN_PROTOCOL]);
+    builder
+        .certificate(
+            CertificateChain::from_certs(vec![
+                Certificate::from_der(&cert_der).map_err(other_error)?
+            ]),
+            PrivateKey::from_der(&key_der).map_err(other_error)?,
+        )
+        .map_err(other_error)?;
+    Ok(builder.build())
+}
+
+fn client_config() -> ClientConfig {
+    let mut builder = ClientConfigBuilder::default();
+    builder.protocols(&[ALPN_PROTOCOL]);
+    let mut config = builder.build();
+    config.transport = transport_config();
+    Arc::get_mut(&mut config.crypto)
+        .expect("crypto config was just built")
+        .dangerous()
+        .set_certificate_verifier(Arc::new(SkipServerVerification));
+    config
+}
+
+fn transport_config() -> Arc<TransportConfig> {
+    let mut config = TransportConfig::default();
+    config.keep_alive_interval(Some(KEEP_ALIVE_INTERVAL));
+    Arc::new(config)
+}
+
+/// Accepts any server certificate: peers are authenticated by the Noise handshake instead.
+struct SkipServerVerification;
+
+impl rustls::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        _presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+fn quic_network_address(addr: SocketAddr) -> NetworkAddress {
+    NetworkAddress::from(Protocol::from(addr.ip()))
+        .push(Protocol::Udp(addr.port()))
+        .push(Protocol::Quic)
+}
+
+fn other_error<E: Error + Send + Sync + 'static>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err)
+}
+
+fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid NetworkAddress: '{}'", addr),
+    )
+}
+
+/// A bidirectional stream of a QUIC connection
+///
+/// The connection is kept along with the stream, as dropping it closes the connection.
+pub struct QuicSocket {
+    send: SendStream,
+    recv: RecvStream,
+    connection: Connection,
+    substreams: Option<QuicSubstreams>,
+}
+
+impl QuicSocket {
+    fn new(
+        send: SendStream,
+        recv: RecvStream,
+        connection: Connection,
+        uni_streams: IncomingUniStreams,
+    ) -> Self {
+        let substreams = QuicSubstreams {
+            connection: connection.clone(),
+            incoming: uni_streams,
+        };
+        Self {
+            send,
+            recv,
+            connection,
+            substreams: Some(substreams),
+        }
+    }
+
+    /// Whether the substreams of the connection were not taken yet.
+    pub fn has_substreams(&self) -> bool {
+        self.substreams.is_some()
+    }
+
+    /// Takes the substreams of the connection. They can only be taken once.
+    pub fn take_substreams(&mut self) -> Option<QuicSubstreams> {
+        self.substreams.take()
+    }
+}
+
+/// The unidirectional substreams of a QUIC connection, which are independent of each other and
+/// of the stream of the [`QuicSocket`], so that data lost on one does not hold up the others.
+///
+/// The first byte of a substream is a tag chosen by its opener, which tells the remote what the
+/// substream carries.
+pub struct QuicSubstreams {
+    connection: Connection,
+    incoming: IncomingUniStreams,
+}
+
+impl QuicSubstreams {
+    /// Opens a substream tagged with `tag` to the remote.
+    pub fn open(&self, tag: u8) -> BoxFuture<'static, io::Result<SendStream>> {
+        let connection = self.connection.clone();
+        async move {
+            let mut send = connection.open_uni().await.map_err(other_error)?;
+            send.write_all(&[tag]).await.map_err(other_error)?;
+            Ok(send)
+        }
+        .boxed()
+    }
+
+    /// Returns the substreams opened by the remote, along with their tags. The stream ends once
+    /// the connection is closed.
+    pub fn accept(self) -> BoxStream<'static, io::Result<(u8, RecvStream)>> {
+        self.incoming
+            // The connection error is returned again and again once the connection is closed.
+            .take_while(|result| future::ready(result.is_ok()))
+            .map_err(other_error)
+            .map_ok(|mut recv| async move {
+                let mut tag = [0; 1];
+                recv.read_exact(&mut tag).await.map_err(other_error)?;
+                Ok((tag[0], recv))
+            })
+            .try_buffer_unordered(MAX_PENDING_SUBSTREAMS)
+            .boxed()
+    }
+}
+
+impl fmt::Debug for QuicSubstreams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicSubstreams")
+            .field("remote_address", &self.connection.remote_address())
+            .finish()
+    }
+}
+
+impl fmt::Debug for QuicSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("QuicSocket")
+            .field("remote_address", &self.connection.remote_address())
+            .finish()
+    }
+}
+
+impl AsyncRead for QuicSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncRead::poll_read(Pin::new(&mut self.recv), context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(Pin::new(&mut self.send), context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(Pin::new(&mut self.send), context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(Pin::new(&mut self.send), context)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::{ConnectionOrigin, TransportExt};
+    use futures::{
+        future::{join, join3, try_join_all},
+        io::{AsyncReadExt, AsyncWriteExt},
+    };
+
+    async fn exchange<T: AsyncRead + AsyncWrite + Unpin>(
+        mut out: T,
+        origin: ConnectionOrigin,
+    ) -> io::Result<()> {
+        match origin {
+            ConnectionOrigin::Inbound => {
+                let mut buf = [0; 5];
+                out.read_exact(&mut buf).await?;
+                assert_eq!(&buf, b"Earth");
+                out.write_all(b"Air").await?;
+                out.flush().await?;
+            }
+            ConnectionOrigin::Outbound => {
+                // the dialer writes first, as the listener only sees the stream once it has data
+                out.write_all(b"Earth").await?;
+                out.flush().await?;
+                let mut buf = [0; 3];
+                out.read_exact(&mut buf).await?;
+                assert_eq!(&buf, b"Air");
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> Result<(), ::std::io::Error> {
+        let t = QuicTransport::default()
+            .and_then(|out, _addr, origin| async move { exchange(out, origin).await });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+        let peer_id = PeerId::random();
+        let dial = t.dial(peer_id, addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+
+        let (outgoing, _incoming) = join(dial, listener).await;
+        assert!(outgoing.is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tcp_quic_listen_and_dial() -> Result<(), ::std::io::Error> {
+        // Each end tells whether the connection is a QUIC one.
+        let t = TcpQuicTransport::default().and_then(|out, _addr, origin| async move {
+            let is_quic = matches!(out, Either::Right(_));
+            exchange(out, origin).await?;
+            Ok::<_, io::Error>(is_quic)
+        });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+        let mut listener = Box::pin(listener);
+        let port = match addr.as_slice() {
+            [Protocol::Ip4(_), Protocol::Udp(port), Protocol::Quic] => *port,
+            _ => panic!("unexpected listen address: {}", addr),
+        };
+        let tcp_addr: NetworkAddress = format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap();
+
+        // QUIC is used when the address names it, TCP otherwise.
+        for (dial_addr, expect_quic) in vec![(addr, true), (tcp_addr.clone(), false)] {
+            let dial = t.dial(PeerId::random(), dial_addr)?;
+            let accept = async {
+                let (incoming, _addr) = listener.next().await.unwrap()?;
+                incoming.await
+            };
+            let (outgoing, incoming) = join(dial, accept).await;
+            assert_eq!(outgoing?, expect_quic);
+            assert_eq!(incoming?, expect_quic);
+        }
+
+        // The peers which only dial TCP are still accepted.
+        let tcp = TcpTransport::default()
+            .and_then(|out, _addr, origin| async move { exchange(out, origin).await });
+        let dial = tcp.dial(PeerId::random(), tcp_addr)?;
+        let accept = async {
+            let (incoming, _addr) = listener.next().await.unwrap()?;
+            incoming.await
+        };
+        let (outgoing, incoming) = join(dial, accept).await;
+        outgoing?;
+        assert!(!incoming?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tcp_quic_dial_tcp_only() -> Result<(), ::std::io::Error> {
+        // Remotes which don't accept QUIC connections are dialed over TCP.
+        let tcp = TcpTransport::default()
+            .and_then(|out, _addr, origin| async move { exchange(out, origin).await });
+        let (listener, addr) = tcp.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap())?;
+        let t = TcpQuicTransport::default().and_then(|out, _addr, origin| async move {
+            let is_quic = matches!(out, Either::Right(_));
+            exchange(out, origin).await?;
+            Ok::<_, io::Error>(is_quic)
+        });
+        let dial = t.dial(PeerId::random(), addr)?;
+        let accept = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming
+        });
+        let (outgoing, incoming) = join(dial, accept).await;
+        assert!(!outgoing?);
+        incoming?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concurrent_substreams() -> Result<(), ::std::io::Error> {
+        const NUM_SUBSTREAMS: u8 = 8;
+        const NUM_WRITES: usize = 64;
+        const WRITE_SIZE: usize = 4096;
+
+        let t = QuicTransport::default();
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())?;
+        // the listener only sees the stream of the socket once the dialer writes to it
+        let dial = t
+            .dial(PeerId::random(), addr)?
+            .and_then(|mut socket| async move {
+                socket.write_all(b"Earth").await?;
+                socket.flush().await?;
+                Ok::<_, io::Error>(socket)
+            });
+        let accept = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming
+        });
+        let (outgoing, incoming) = join(dial, accept).await;
+        let (mut outgoing, mut incoming) = (outgoing?, incoming?);
+
+        let dialer_substreams = outgoing.take_substreams().unwrap();
+        assert!(outgoing.take_substreams().is_none());
+        let listener_substreams = incoming.take_substreams().unwrap();
+
+        // every substream is written to concurrently, while the socket is in use too
+        let send_all = try_join_all((0..NUM_SUBSTREAMS).map(|tag| {
+            let open = dialer_substreams.open(tag);
+            async move {
+                let mut send = open.await?;
+                for _ in 0..NUM_WRITES {
+                    AsyncWriteExt::write_all(&mut send, &[tag; WRITE_SIZE]).await?;
+                }
+                AsyncWriteExt::close(&mut send).await
+            }
+        }));
+        let receive_all = listener_substreams
+            .accept()
+            .take(NUM_SUBSTREAMS as usize)
+            .map(|result| async move {
+                let (tag, mut recv) = result?;
+                let mut data = vec![];
+                AsyncReadExt::read_to_end(&mut recv, &mut data).await?;
+                Ok::<_, io::Error>((tag, data))
+            })
+            .buffer_unordered(NUM_SUBSTREAMS as usize)
+            .try_collect::<Vec<_>>();
+        let exchange_on_socket = async {
+            let mut buf = [0; 5];
+            incoming.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"Earth");
+            incoming.write_all(b"Air").await?;
+            incoming.flush().await?;
+            let mut buf = [0; 3];
+            outgoing.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"Air");
+            Ok::<_, io::Error>(())
+        };
+
+        let (sent, received, exchanged) = join3(send_all, receive_all, exchange_on_socket).await;
+        sent?;
+        exchanged?;
+        let mut received = received?;
+        received.sort_by_key(|(tag, _)| *tag);
+        assert_eq!(received.len(), NUM_SUBSTREAMS as usize);
+        for (expected_tag, (tag, data)) in received.into_iter().enumerate() {
+            assert_eq!(tag as usize, expected_tag);
+            assert_eq!(data, vec![tag; NUM_WRITES * WRITE_SIZE]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport::default();
+
+        let result = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let peer_id = PeerId::random();
+        let result = t.dial(peer_id, "/memory/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
@@ -261,6 +261,27 @@ pub fn inbound_rpc_handler_latency(
     ])
 }
 
+pub static DIEM_NETWORK_RATE_LIMITED_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_rate_limited_messages",
+        "Number of inbound messages dropped because their protocol's rate limit was exceeded",
+        &["role_type", "network_id", "peer_id", "protocol_id"]
+    )
+    .unwrap()
+});
+
+pub fn rate_limited_messages(
+    network_context: &NetworkContext,
+    protocol_id: ProtocolId,
+) -> IntCounter {
+    DIEM_NETWORK_RATE_LIMITED_MESSAGES.with_label_values(&[
+        network_context.role().as_str(),
+        network_context.network_id().as_str(),
+        network_context.peer_id().short_str().as_str(),
+        protocol_id.as_str(),
+    ])
+}
+
 pub static DIEM_NETWORK_DIRECT_SEND_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_network_direct_send_messages",
@@ -334,6 +355,16 @@ pub static PENDING_HEALTH_CHECKER_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::ne
     .unwrap()
 });
 
+/// Counter of pending network events to Peer Monitor.
+pub static PENDING_PEER_MONITOR_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_network_pending_peer_monitor_events",
+        "Number of pending peer monitor events by state",
+        &["state"]
+    )
+    .unwrap()
+});
+
 /// Counter of pending network events to Discovery.
 pub static PENDING_DISCOVERY_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
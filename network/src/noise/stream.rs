@@ -19,6 +19,7 @@ use std::{
     task::{Context, Poll},
 };
 
+use crate::transport::{Substreams, TSubstreams};
 use diem_crypto::{noise, x25519};
 use diem_logger::prelude::*;
 
@@ -422,6 +423,21 @@ where
     }
 }
 
+/// The substreams of the socket are encrypted with sessions derived from the one of the stream.
+/// They are missing if the session was closed after an error.
+impl<TSocket> TSubstreams for NoiseStream<TSocket>
+where
+    TSocket: TSubstreams,
+{
+    fn has_substreams(&self) -> bool {
+        self.socket.has_substreams()
+    }
+
+    fn take_substreams(&mut self) -> Option<Substreams> {
+        self.socket.take_substreams()?.encrypt(&self.session).ok()
+    }
+}
+
 //
 // NoiseBuffers
 // ------------
@@ -21,7 +21,7 @@ use futures::{executor::block_on, future, io::AsyncReadExt, sink::SinkExt, strea
 use memsocket::MemorySocket;
 use netcore::transport::ConnectionOrigin;
 use proptest::{arbitrary::any, collection::vec};
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 /// Generate a sequence of `NetworkMessage`, bcs serialize them, and write them
 /// out to a buffer using our length-prefixed message codec.
@@ -109,6 +109,7 @@ pub fn fuzz(data: &[u8]) {
         constants::MAX_FRAME_SIZE,
         None,
         None,
+        HashMap::new(),
     );
     executor.spawn(peer.start());
 
@@ -26,7 +26,10 @@ use crate::{
             Priority, ReadError, WriteError,
         },
     },
-    transport::{self, Connection, ConnectionMetadata},
+    transport::{
+        self, Connection, ConnectionMetadata, SubstreamReader, Substreams, TSubstreams,
+        SUBSTREAMS_MESSAGING_PROTOCOL,
+    },
     ProtocolId,
 };
 use bytes::Bytes;
@@ -40,12 +43,12 @@ use futures::{
     self,
     channel::oneshot,
     io::{AsyncRead, AsyncWrite},
-    stream::StreamExt,
+    stream::{self, BoxStream, SelectAll, StreamExt},
     FutureExt, SinkExt, TryFutureExt,
 };
 use serde::Serialize;
 use short_hex_str::AsShortHexStr;
-use std::{fmt, panic, sync::Arc, time::Duration};
+use std::{collections::HashMap, fmt, io, panic, sync::Arc, time::Duration};
 use tokio::runtime::Handle;
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
@@ -134,12 +137,24 @@ pub struct Peer<TSocket> {
     inbound_rate_limiter: Option<SharedBucket>,
     /// Optional outbound rate limiter
     outbound_rate_limiter: Option<SharedBucket>,
+    /// Rate limiters of the inbound messages of the rate limited protocols
+    inbound_protocol_rate_limiters: HashMap<ProtocolId, SharedBucket>,
+    /// Queues of the writer tasks of the substreams opened to the remote peer, by protocol.
+    /// Messages of the other protocols are written to the connection socket.
+    substream_writers: HashMap<
+        ProtocolId,
+        channel::Sender<(
+            NetworkMessage,
+            oneshot::Sender<Result<(), PeerManagerError>>,
+        )>,
+    >,
 }
 
 impl<TSocket> Peer<TSocket>
 where
-    TSocket: AsyncRead + AsyncWrite + Send + 'static,
+    TSocket: AsyncRead + AsyncWrite + TSubstreams + Send + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         network_context: Arc<NetworkContext>,
         executor: Handle,
@@ -154,6 +169,7 @@ where
         max_frame_size: usize,
         inbound_rate_limiter: Option<SharedBucket>,
         outbound_rate_limiter: Option<SharedBucket>,
+        inbound_protocol_rate_limiters: HashMap<ProtocolId, SharedBucket>,
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
@@ -186,6 +202,8 @@ where
             max_frame_size,
             inbound_rate_limiter,
             outbound_rate_limiter,
+            inbound_protocol_rate_limiters,
+            substream_writers: HashMap::new(),
         }
     }
 
@@ -203,9 +221,18 @@ where
             remote_peer_id.short_str()
         );
 
+        // Connections which negotiated substreams carry the messages of each application
+        // protocol on substreams of their own.
+        let mut connection = self.connection.take().unwrap();
+        let substreams =
+            if self.connection_metadata.messaging_protocol >= SUBSTREAMS_MESSAGING_PROTOCOL {
+                connection.take_substreams()
+            } else {
+                None
+            };
+
         // Split the connection into a ReadHalf and a WriteHalf.
-        let (read_socket, write_socket) =
-            tokio::io::split(self.connection.take().unwrap().compat());
+        let (read_socket, write_socket) = tokio::io::split(connection.compat());
 
         let mut reader = NetworkMessageStream::new(
             read_socket.compat(),
@@ -230,6 +257,20 @@ where
             self.network_context.clone(),
             writer,
         );
+        let mut writer_close_txs = vec![writer_close_tx];
+
+        // Open our substreams and accept the ones of the remote peer, whose messages are read
+        // along with the ones of the socket.
+        let mut accepted_substreams = match substreams {
+            Some(substreams) => {
+                let (close_txs, accepted_substreams) = self.start_substreams(substreams).await;
+                writer_close_txs.extend(close_txs);
+                accepted_substreams
+            }
+            None => stream::empty().boxed(),
+        }
+        .fuse();
+        let mut substream_readers = SelectAll::new();
 
         // Start main Peer event loop.
         let reason = loop {
@@ -268,6 +309,25 @@ where
                         None => self.shutdown(DisconnectReason::ConnectionLost),
                     }
                 },
+                // Handle a new substream opened by the remote peer.
+                accepted = accepted_substreams.select_next_some() => {
+                    self.handle_accepted_substream(accepted, &mut substream_readers);
+                },
+                // Handle a new inbound NetworkMessage read off a substream. Substreams are closed
+                // along with the connection, which is noticed by the socket reader.
+                message = substream_readers.select_next_some() => {
+                    if let Err(err) = self.handle_inbound_message(message, &mut write_reqs_tx).await {
+                        warn!(
+                            NetworkSchema::new(&self.network_context)
+                                .connection_metadata(&self.connection_metadata),
+                            error = %err,
+                            "{} Error in handling inbound message from peer: {}, error: {}",
+                            self.network_context,
+                            remote_peer_id.short_str(),
+                            err
+                        );
+                    }
+                },
                 // Drive the queue of pending inbound rpcs. When one is fulfilled
                 // by an upstream protocol, send the response to the remote peer.
                 maybe_response = self.inbound_rpcs.next_completed_response() => {
@@ -287,9 +347,96 @@ where
             }
         };
 
-        // Finish shutting down the connection. Close the writer task and notify
+        // Finish shutting down the connection. Close the writer tasks and notify
         // PeerManager that this connection has shutdown.
-        self.do_shutdown(writer_close_tx, reason).await;
+        self.do_shutdown(writer_close_txs, reason).await;
+    }
+
+    // Open a substream to the remote peer for each of the application protocols of the
+    // connection, each written by a writer task of its own. Outbound direct send messages and rpc
+    // requests of a protocol are then written to its substream, while rpc responses and errors
+    // are still written to the socket. Returns the handles to close the writer tasks, and the
+    // substreams opened by the remote peer.
+    async fn start_substreams(
+        &mut self,
+        substreams: Substreams,
+    ) -> (
+        Vec<oneshot::Sender<()>>,
+        BoxStream<'static, io::Result<(ProtocolId, SubstreamReader)>>,
+    ) {
+        let mut close_txs = vec![];
+        for protocol_id in ProtocolId::all().iter().copied() {
+            if !self
+                .connection_metadata
+                .application_protocols
+                .contains(protocol_id)
+            {
+                continue;
+            }
+            match substreams.open(protocol_id).await {
+                Ok(socket) => {
+                    let writer = NetworkMessageSink::new(
+                        socket,
+                        self.max_frame_size,
+                        self.outbound_rate_limiter.clone(),
+                    );
+                    let (write_reqs_tx, close_tx) = Self::start_writer_task(
+                        &self.executor,
+                        self.time_service.clone(),
+                        self.connection_metadata.clone(),
+                        self.network_context.clone(),
+                        writer,
+                    );
+                    self.substream_writers.insert(protocol_id, write_reqs_tx);
+                    close_txs.push(close_tx);
+                }
+                // The messages of the protocol are written to the socket instead.
+                Err(err) => warn!(
+                    NetworkSchema::new(&self.network_context)
+                        .connection_metadata(&self.connection_metadata),
+                    error = %err,
+                    "{} Failed to open substream for protocol {} to peer: {}, error: {}",
+                    self.network_context,
+                    protocol_id,
+                    self.remote_peer_id().short_str(),
+                    err
+                ),
+            }
+        }
+        (close_txs, substreams.accept())
+    }
+
+    fn handle_accepted_substream(
+        &self,
+        accepted: io::Result<(ProtocolId, SubstreamReader)>,
+        substream_readers: &mut SelectAll<NetworkMessageStream<SubstreamReader>>,
+    ) {
+        match accepted {
+            Ok((protocol_id, reader)) => {
+                trace!(
+                    NetworkSchema::new(&self.network_context)
+                        .connection_metadata(&self.connection_metadata),
+                    "{} Accepted substream for protocol {} from peer: {}",
+                    self.network_context,
+                    protocol_id,
+                    self.remote_peer_id().short_str()
+                );
+                substream_readers.push(NetworkMessageStream::new(
+                    reader,
+                    self.max_frame_size,
+                    self.inbound_rate_limiter.clone(),
+                ));
+            }
+            Err(err) => warn!(
+                NetworkSchema::new(&self.network_context)
+                    .connection_metadata(&self.connection_metadata),
+                error = %err,
+                "{} Failed to accept substream from peer: {}, error: {}",
+                self.network_context,
+                self.remote_peer_id().short_str(),
+                err
+            ),
+        }
     }
 
     // Start a new task on the given executor which is responsible for writing outbound messages on
@@ -439,7 +586,12 @@ where
         };
 
         match message {
-            NetworkMessage::DirectSendMsg(message) => self.handle_inbound_direct_send(message),
+            NetworkMessage::DirectSendMsg(message) => {
+                if self.is_rate_limited(message.protocol_id) {
+                    return Ok(());
+                }
+                self.handle_inbound_direct_send(message)
+            }
             NetworkMessage::Error(error_msg) => {
                 warn!(
                     NetworkSchema::new(&self.network_context)
@@ -452,6 +604,9 @@ where
                 );
             }
             NetworkMessage::RpcRequest(request) => {
+                if self.is_rate_limited(request.protocol_id) {
+                    return Ok(());
+                }
                 if let Err(err) = self
                     .inbound_rpcs
                     .handle_inbound_request(&mut self.peer_notifs_tx, request)
@@ -473,6 +628,28 @@ where
         Ok(())
     }
 
+    /// Whether an inbound message of the protocol exceeds its rate limit, in which case it's
+    /// dropped so that floods of a protocol from one peer can't starve the others.
+    fn is_rate_limited(&self, protocol_id: ProtocolId) -> bool {
+        let rate_limiter = match self.inbound_protocol_rate_limiters.get(&protocol_id) {
+            Some(rate_limiter) => rate_limiter,
+            None => return false,
+        };
+        if rate_limiter.lock().acquire_all_tokens(1).is_ok() {
+            return false;
+        }
+        trace!(
+            NetworkSchema::new(&self.network_context).remote_peer(&self.remote_peer_id()),
+            protocol_id = protocol_id,
+            "{} Dropped inbound message from peer {} for rate limited protocol {:?}",
+            self.network_context,
+            self.remote_peer_id().short_str(),
+            protocol_id
+        );
+        counters::rate_limited_messages(&self.network_context, protocol_id).inc();
+        true
+    }
+
     /// Handle an inbound DirectSendMsg from the remote peer. There's not much to
     /// do here other than bump some counters and forward the message up to the
     /// PeerManager.
@@ -535,6 +712,10 @@ where
                     raw_msg: Vec::from(message.mdata.as_ref()),
                 });
                 let (ack_tx, _ack_rx) = oneshot::channel();
+                let write_reqs_tx = self
+                    .substream_writers
+                    .get_mut(&protocol_id)
+                    .unwrap_or(write_reqs_tx);
 
                 match write_reqs_tx.send((message, ack_tx)).await {
                     Ok(_) => {
@@ -557,6 +738,10 @@ where
             }
             PeerRequest::SendRpc(request) => {
                 let protocol_id = request.protocol_id;
+                let write_reqs_tx = self
+                    .substream_writers
+                    .get_mut(&protocol_id)
+                    .unwrap_or(write_reqs_tx);
                 if let Err(e) = self
                     .outbound_rpcs
                     .handle_outbound_request(request, write_reqs_tx)
@@ -582,7 +767,11 @@ where
         self.state = State::ShuttingDown(reason);
     }
 
-    async fn do_shutdown(mut self, writer_close_tx: oneshot::Sender<()>, reason: DisconnectReason) {
+    async fn do_shutdown(
+        mut self,
+        writer_close_txs: Vec<oneshot::Sender<()>>,
+        reason: DisconnectReason,
+    ) {
         let remote_peer_id = self.remote_peer_id();
 
         // Send a PeerDisconnected event to PeerManager.
@@ -605,18 +794,20 @@ where
             );
         }
 
-        // Send a close instruction to the writer task. On receipt of this
-        // instruction, the writer task drops all pending outbound messages and
-        // closes the connection.
-        if let Err(e) = writer_close_tx.send(()) {
-            info!(
-                NetworkSchema::new(&self.network_context)
-                    .connection_metadata(&self.connection_metadata),
-                error = ?e,
-                "{} Failed to send close instruction to writer task. It must already be terminating/terminated. Error: {:?}",
-                self.network_context,
-                e
-            );
+        // Send a close instruction to the writer tasks. On receipt of this
+        // instruction, a writer task drops all pending outbound messages and
+        // closes its socket or substream.
+        for writer_close_tx in writer_close_txs {
+            if let Err(e) = writer_close_tx.send(()) {
+                info!(
+                    NetworkSchema::new(&self.network_context)
+                        .connection_metadata(&self.connection_metadata),
+                    error = ?e,
+                    "{} Failed to send close instruction to writer task. It must already be terminating/terminated. Error: {:?}",
+                    self.network_context,
+                    e
+                );
+            }
         }
 
         trace!(
@@ -25,6 +25,7 @@ use crate::{
 use bytes::Bytes;
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use diem_config::{config::PeerRole, network_id::NetworkContext};
+use diem_rate_limiter::rate_limit::{SharedBucket, TokenBucketRateLimiter};
 use diem_time_service::{MockTimeService, TimeService};
 use diem_types::{network_address::NetworkAddress, PeerId};
 use futures::{
@@ -36,7 +37,11 @@ use futures::{
 };
 use memsocket::MemorySocket;
 use netcore::transport::ConnectionOrigin;
-use std::{collections::HashSet, str::FromStr, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    time::Duration,
+};
 use tokio::runtime::{Handle, Runtime};
 use tokio_util::compat::{
     FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt,
@@ -54,6 +59,21 @@ fn build_test_peer(
     MemorySocket,
     channel::Receiver<TransportNotification<MemorySocket>>,
     diem_channel::Receiver<ProtocolId, PeerNotification>,
+) {
+    build_test_peer_with_rate_limiters(executor, time_service, origin, HashMap::new())
+}
+
+fn build_test_peer_with_rate_limiters(
+    executor: Handle,
+    time_service: TimeService,
+    origin: ConnectionOrigin,
+    inbound_protocol_rate_limiters: HashMap<ProtocolId, SharedBucket>,
+) -> (
+    Peer<MemorySocket>,
+    PeerHandle,
+    MemorySocket,
+    channel::Receiver<TransportNotification<MemorySocket>>,
+    diem_channel::Receiver<ProtocolId, PeerNotification>,
 ) {
     let (a, b) = MemorySocket::new_pair();
     let peer_id = PeerId::random();
@@ -90,6 +110,7 @@ fn build_test_peer(
         MAX_FRAME_SIZE,
         None,
         None,
+        inbound_protocol_rate_limiters,
     );
     let peer_handle = PeerHandle(peer_reqs_tx);
 
@@ -280,6 +301,60 @@ fn peer_recv_message() {
     rt.block_on(future::join3(peer.start(), server, client));
 }
 
+// Inbound messages of a protocol exceeding its rate limit should be dropped, without affecting
+// the other protocols.
+#[test]
+fn peer_recv_message_rate_limited() {
+    ::diem_logger::Logger::init_for_testing();
+    let rt = Runtime::new().unwrap();
+    let bucket_size = 10;
+    let rate_limiters = TokenBucketRateLimiter::test(bucket_size, 1);
+    let (peer, _peer_handle, connection, _connection_notifs_rx, peer_notifs_rx) =
+        build_test_peer_with_rate_limiters(
+            rt.handle().clone(),
+            TimeService::mock(),
+            ConnectionOrigin::Inbound,
+            [(PROTOCOL, rate_limiters.bucket(PeerId::random()))]
+                .iter()
+                .cloned()
+                .collect(),
+        );
+
+    let client = async move {
+        let mut connection = NetworkMessageSink::new(connection, MAX_FRAME_SIZE, None);
+        for protocol_id in std::iter::repeat(PROTOCOL)
+            .take(30)
+            .chain(std::iter::once(ProtocolId::ConsensusDirectSend))
+        {
+            let send_msg = NetworkMessage::DirectSendMsg(DirectSendMsg {
+                protocol_id,
+                priority: 0,
+                raw_msg: Vec::from("hello world"),
+            });
+            connection.send(&send_msg).await.unwrap();
+        }
+        // Client then closes connection.
+        connection.close().await.unwrap();
+    };
+
+    let server = async move {
+        // Only the messages within the rate limit are received, until the peer shuts down.
+        let received = peer_notifs_rx.collect::<Vec<_>>().await;
+        let num_received = |protocol_id| {
+            received
+                .iter()
+                .filter(|notif| match notif {
+                    PeerNotification::RecvMessage(message) => message.protocol_id == protocol_id,
+                    _ => false,
+                })
+                .count()
+        };
+        assert_eq!(num_received(PROTOCOL), bucket_size);
+        assert_eq!(num_received(ProtocolId::ConsensusDirectSend), 1);
+    };
+    rt.block_on(future::join3(peer.start(), server, client));
+}
+
 // Two connected Peer actors should be able to send/recv a DirectSend from each
 // other and then shutdown gracefully.
 #[test]
@@ -9,6 +9,7 @@ use crate::{
     peer_manager::{
         conn_notifs_channel, ConnectionRequest, ConnectionRequestSender, PeerManager,
         PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
+        ProtocolRateLimiters,
     },
     protocols::wire::handshake::v1::SupportedProtocols,
     transport::{self, Connection, DiemNetTransport, DIEM_TCP_TRANSPORT},
@@ -16,7 +17,7 @@ use crate::{
 };
 use channel::{self, diem_channel, message_queues::QueueStyle};
 use diem_config::{
-    config::{PeerSet, RateLimitConfig, HANDSHAKE_VERSION},
+    config::{PeerSet, ProtocolRateLimitConfig, RateLimitConfig, HANDSHAKE_VERSION},
     network_id::NetworkContext,
 };
 use diem_crypto::x25519;
@@ -26,9 +27,11 @@ use diem_metrics::IntCounterVec;
 use diem_rate_limiter::rate_limit::TokenBucketRateLimiter;
 use diem_time_service::TimeService;
 use diem_types::{chain_id::ChainId, network_address::NetworkAddress, PeerId};
+use futures::future::Either;
 #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
 use netcore::transport::memory::MemoryTransport;
 use netcore::transport::{
+    quic::{QuicSocket, TcpQuicTransport},
     tcp::{TcpSocket, TcpTransport},
     Transport,
 };
@@ -117,6 +120,7 @@ struct PeerManagerContext {
     inbound_connection_limit: usize,
     inbound_rate_limit_config: Option<RateLimitConfig>,
     outbound_rate_limit_config: Option<RateLimitConfig>,
+    inbound_protocol_rate_limits: HashMap<String, ProtocolRateLimitConfig>,
 }
 
 impl PeerManagerContext {
@@ -141,6 +145,7 @@ impl PeerManagerContext {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        inbound_protocol_rate_limits: HashMap<String, ProtocolRateLimitConfig>,
     ) -> Self {
         Self {
             pm_reqs_tx,
@@ -159,6 +164,7 @@ impl PeerManagerContext {
             inbound_connection_limit,
             inbound_rate_limit_config,
             outbound_rate_limit_config,
+            inbound_protocol_rate_limits,
         }
     }
 
@@ -182,11 +188,14 @@ impl PeerManagerContext {
 type MemoryPeerManager =
     PeerManager<DiemNetTransport<MemoryTransport>, NoiseStream<memsocket::MemorySocket>>;
 type TcpPeerManager = PeerManager<DiemNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
+type QuicPeerManager =
+    PeerManager<DiemNetTransport<TcpQuicTransport>, NoiseStream<Either<TcpSocket, QuicSocket>>>;
 
 enum TransportPeerManager {
     #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
     Memory(MemoryPeerManager),
     Tcp(TcpPeerManager),
+    Quic(QuicPeerManager),
 }
 
 pub struct PeerManagerBuilder {
@@ -218,6 +227,7 @@ impl PeerManagerBuilder {
         inbound_connection_limit: usize,
         inbound_rate_limit_config: Option<RateLimitConfig>,
         outbound_rate_limit_config: Option<RateLimitConfig>,
+        inbound_protocol_rate_limits: HashMap<String, ProtocolRateLimitConfig>,
     ) -> Self {
         // Setup channel to send requests to peer manager.
         let (pm_reqs_tx, pm_reqs_rx) = diem_channel::new(
@@ -255,6 +265,7 @@ impl PeerManagerBuilder {
                 inbound_connection_limit,
                 inbound_rate_limit_config,
                 outbound_rate_limit_config,
+                inbound_protocol_rate_limits,
             )),
             peer_manager: None,
             listen_address,
@@ -315,6 +326,24 @@ impl PeerManagerBuilder {
                     executor,
                 )))
             }
+            // QUIC listeners also accept TCP connections on the same port, for the peers which
+            // only dial TCP addresses.
+            [Ip4(_), Udp(_), Quic] | [Ip6(_), Udp(_), Quic] => {
+                Some(TransportPeerManager::Quic(self.build_with_transport(
+                    DiemNetTransport::new(
+                        TcpQuicTransport::new(DIEM_TCP_TRANSPORT.clone()),
+                        self.network_context.clone(),
+                        self.time_service.clone(),
+                        key,
+                        auth_mode,
+                        HANDSHAKE_VERSION,
+                        chain_id,
+                        protos,
+                        enable_proxy_protocol,
+                    ),
+                    executor,
+                )))
+            }
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             [Memory(_)] => Some(TransportPeerManager::Memory(self.build_with_transport(
                 DiemNetTransport::new(
@@ -332,7 +361,8 @@ impl PeerManagerBuilder {
             ))),
             _ => panic!(
                 "{} Unsupported listen_address: '{}', expected '/memory/<port>', \
-                 '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>'.",
+                 '/ip4/<addr>/tcp/<port>', '/ip6/<addr>/tcp/<port>', \
+                 '/ip4/<addr>/udp/<port>/quic', or '/ip6/<addr>/udp/<port>/quic'.",
                 self.network_context, self.listen_address
             ),
         };
@@ -365,6 +395,10 @@ impl PeerManagerBuilder {
             "outbound",
             pm_context.outbound_rate_limit_config,
         );
+        let inbound_protocol_rate_limiters = protocol_rate_limiters(
+            &self.network_context,
+            &pm_context.inbound_protocol_rate_limits,
+        );
         let peer_mgr = PeerManager::new(
             executor.clone(),
             self.time_service.clone(),
@@ -385,6 +419,7 @@ impl PeerManagerBuilder {
             pm_context.inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            inbound_protocol_rate_limiters,
         );
 
         // PeerManager constructor appends a public key to the listen_address.
@@ -415,6 +450,7 @@ impl PeerManagerBuilder {
             #[cfg(any(test, feature = "testing", feature = "fuzzing"))]
             TransportPeerManager::Memory(pm) => self.start_peer_manager(pm, executor),
             TransportPeerManager::Tcp(pm) => self.start_peer_manager(pm, executor),
+            TransportPeerManager::Quic(pm) => self.start_peer_manager(pm, executor),
         }
     }
 
@@ -491,3 +527,33 @@ fn token_bucket_rate_limiter(
     }
     TokenBucketRateLimiter::open(label)
 }
+
+/// Builds the message rate limiters of the protocols with a configured rate limit
+fn protocol_rate_limiters(
+    network_context: &Arc<NetworkContext>,
+    configs: &HashMap<String, ProtocolRateLimitConfig>,
+) -> ProtocolRateLimiters {
+    configs
+        .iter()
+        .map(|(protocol_name, config)| {
+            let protocol_id = *ProtocolId::all()
+                .iter()
+                .find(|protocol_id| protocol_id.as_str() == protocol_name)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "{} Unknown protocol in inbound_protocol_rate_limits: '{}'",
+                        network_context, protocol_name
+                    )
+                });
+            let rate_limiters = TokenBucketRateLimiter::new(
+                protocol_id.as_str(),
+                network_context.to_string(),
+                100,
+                config.message_bucket_size,
+                config.message_bucket_rate,
+                None,
+            );
+            (protocol_id, rate_limiters)
+        })
+        .collect()
+}
@@ -64,6 +64,8 @@ pub use senders::*;
 pub use types::*;
 
 pub type IpAddrTokenBucketLimiter = TokenBucketRateLimiter<IpAddr>;
+/// Rate limiters of the messages received from each peer, per protocol
+pub type ProtocolRateLimiters = HashMap<ProtocolId, TokenBucketRateLimiter<PeerId>>;
 
 /// Responsible for handling and maintaining connections to other Peers
 pub struct PeerManager<TTransport, TSocket>
@@ -125,6 +127,8 @@ where
     inbound_rate_limiters: IpAddrTokenBucketLimiter,
     /// Keyed storage of all outbound rate limiters
     outbound_rate_limiters: IpAddrTokenBucketLimiter,
+    /// Keyed storage of all inbound message rate limiters, per protocol
+    inbound_protocol_rate_limiters: ProtocolRateLimiters,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -155,6 +159,7 @@ where
         inbound_connection_limit: usize,
         inbound_rate_limiters: IpAddrTokenBucketLimiter,
         outbound_rate_limiters: IpAddrTokenBucketLimiter,
+        inbound_protocol_rate_limiters: ProtocolRateLimiters,
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = channel::new(
             channel_size,
@@ -199,6 +204,7 @@ where
             inbound_connection_limit,
             inbound_rate_limiters,
             outbound_rate_limiters,
+            inbound_protocol_rate_limiters,
         }
     }
 
@@ -422,6 +428,9 @@ where
                 self.inbound_rate_limiters.try_garbage_collect_key(&ip_addr);
                 self.outbound_rate_limiters
                     .try_garbage_collect_key(&ip_addr);
+                for rate_limiters in self.inbound_protocol_rate_limiters.values() {
+                    rate_limiters.try_garbage_collect_key(&peer_id);
+                }
             }
         }
     }
@@ -673,6 +682,11 @@ where
             .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
         let inbound_rate_limiter = self.inbound_rate_limiters.bucket(ip_addr);
         let outbound_rate_limiter = self.outbound_rate_limiters.bucket(ip_addr);
+        let inbound_protocol_rate_limiters = self
+            .inbound_protocol_rate_limiters
+            .iter()
+            .map(|(protocol_id, rate_limiters)| (*protocol_id, rate_limiters.bucket(peer_id)))
+            .collect();
 
         // TODO: Add label for peer.
         let (peer_reqs_tx, peer_reqs_rx) = diem_channel::new(
@@ -702,6 +716,7 @@ where
             self.max_frame_size,
             Some(inbound_rate_limiter),
             Some(outbound_rate_limiter),
+            inbound_protocol_rate_limiters,
         );
         self.executor.spawn(peer.start());
 
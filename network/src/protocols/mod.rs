@@ -10,4 +10,5 @@ pub mod rpc;
 
 pub mod health_checker;
 pub mod identity;
+pub mod peer_monitor;
 pub mod wire;
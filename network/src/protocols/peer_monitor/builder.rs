@@ -0,0 +1,48 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::protocols::peer_monitor::{
+    PeerMonitor, PeerMonitorNetworkEvents, PeerMonitorNetworkSender, PeerMonitoringStorage,
+    SyncedVersionProvider,
+};
+use diem_config::network_id::NetworkContext;
+use diem_time_service::TimeService;
+use std::{sync::Arc, time::Duration};
+use tokio::runtime::Handle;
+
+pub struct PeerMonitorBuilder {
+    service: Option<PeerMonitor>,
+}
+
+impl PeerMonitorBuilder {
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        time_service: TimeService,
+        probe_interval_ms: u64,
+        probe_timeout_ms: u64,
+        network_tx: PeerMonitorNetworkSender,
+        network_rx: PeerMonitorNetworkEvents,
+        storage: Arc<PeerMonitoringStorage>,
+        synced_version: SyncedVersionProvider,
+    ) -> Self {
+        let service = PeerMonitor::new(
+            network_context,
+            time_service,
+            network_tx,
+            network_rx,
+            storage,
+            synced_version,
+            Duration::from_millis(probe_interval_ms),
+            Duration::from_millis(probe_timeout_ms),
+        );
+        Self {
+            service: Some(service),
+        }
+    }
+
+    pub fn start(&mut self, executor: &Handle) {
+        if let Some(service) = self.service.take() {
+            executor.spawn(service.start());
+        }
+    }
+}
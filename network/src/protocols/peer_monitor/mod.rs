@@ -0,0 +1,385 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Protocol used to monitor connected peers
+//!
+//! The PeerMonitor periodically probes every connected peer supporting the protocol with a
+//! `GetNodeStatus` request. The peer replies with its role and the latest version it is synced
+//! to, and the PeerMonitor records them along with the round trip time of the probe in a
+//! [`PeerMonitoringStorage`] shared by all the networks of the node. Other components, e.g.,
+//! state sync, read the storage to pick peers, and operators inspect it on the debug interface.
+//!
+//! Unlike the HealthChecker, the PeerMonitor never disconnects from peers whose probes fail.
+use crate::{
+    application::storage::LockingHashMap,
+    constants::NETWORK_CHANNEL_SIZE,
+    counters,
+    logging::NetworkSchema,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::{
+        network::{Event, NetworkEvents, NetworkSender, NewNetworkSender},
+        rpc::error::RpcError,
+    },
+    ProtocolId,
+};
+use bytes::Bytes;
+use channel::message_queues::QueueStyle;
+use diem_config::{
+    config::RoleType,
+    network_id::{NetworkContext, NetworkId},
+};
+use diem_logger::prelude::*;
+use diem_metrics::IntCounterVec;
+use diem_time_service::{TimeService, TimeServiceTrait};
+use diem_types::{transaction::Version, PeerId};
+use futures::{
+    channel::oneshot,
+    stream::{FuturesUnordered, StreamExt},
+};
+use serde::{Deserialize, Serialize};
+use short_hex_str::AsShortHexStr;
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+pub mod builder;
+#[cfg(test)]
+mod test;
+
+/// The weight of a new round trip time in the moving average of a peer's round trip times.
+const RTT_OBSERVATION_WEIGHT: f64 = 0.2;
+
+/// The monitoring metadata of the connected peers of all the networks of a node.
+pub type PeerMonitoringStorage = LockingHashMap<(NetworkId, PeerId), PeerMonitoringMetadata>;
+
+/// Returns the latest version the node is synced to, if known.
+pub type SyncedVersionProvider = Arc<dyn Fn() -> Option<Version> + Send + Sync>;
+
+/// The interface from Network to PeerMonitor layer.
+pub type PeerMonitorNetworkEvents = NetworkEvents<PeerMonitoringMsg>;
+
+/// The interface from PeerMonitor to Networking layer.
+#[derive(Clone)]
+pub struct PeerMonitorNetworkSender {
+    inner: NetworkSender<PeerMonitoringMsg>,
+}
+
+/// Configuration for the network endpoints to support PeerMonitor.
+pub fn network_endpoint_config() -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![ProtocolId::PeerMonitoringRpc],
+        vec![],
+        QueueStyle::LIFO,
+        NETWORK_CHANNEL_SIZE,
+        Some(&counters::PENDING_PEER_MONITOR_NETWORK_EVENTS),
+    )
+}
+
+impl NewNetworkSender for PeerMonitorNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+impl PeerMonitorNetworkSender {
+    /// Send a PeerMonitor RPC request to remote peer `recipient`. Returns the remote peer's
+    /// future reply.
+    pub async fn send_rpc(
+        &mut self,
+        recipient: PeerId,
+        req_msg: PeerMonitoringMsg,
+        timeout: Duration,
+    ) -> Result<PeerMonitoringMsg, RpcError> {
+        let protocol = ProtocolId::PeerMonitoringRpc;
+        self.inner
+            .send_rpc(recipient, protocol, req_msg, timeout)
+            .await
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PeerMonitoringMsg {
+    GetNodeStatus,
+    NodeStatus(NodeStatus),
+}
+
+/// What a node reports about itself to the peers monitoring it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct NodeStatus {
+    pub role: RoleType,
+    /// The latest version the node is synced to, if known.
+    pub synced_version: Option<Version>,
+}
+
+/// What is known about a connected peer from monitoring it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PeerMonitoringMetadata {
+    /// The status reported by the latest successful probe.
+    pub status: Option<NodeStatus>,
+    /// The round trip time of the latest successful probe.
+    pub latest_rtt_ms: Option<u64>,
+    /// The moving average of the round trip times of successful probes.
+    pub average_rtt_ms: Option<f64>,
+    /// The number of probes that failed since the latest successful one.
+    pub consecutive_failures: u64,
+}
+
+impl PeerMonitoringMetadata {
+    /// The latest version the peer reported being synced to.
+    pub fn synced_version(&self) -> Option<Version> {
+        self.status
+            .as_ref()
+            .and_then(|status| status.synced_version)
+    }
+
+    fn record_success(&mut self, status: NodeStatus, rtt: Duration) {
+        let rtt_ms = rtt.as_secs_f64() * 1000.0;
+        self.status = Some(status);
+        self.latest_rtt_ms = Some(rtt.as_millis() as u64);
+        self.average_rtt_ms = Some(match self.average_rtt_ms {
+            Some(average) => average + RTT_OBSERVATION_WEIGHT * (rtt_ms - average),
+            None => rtt_ms,
+        });
+        self.consecutive_failures = 0;
+    }
+}
+
+/// The monitoring metadata of a peer, as served on the debug interface.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MonitoredPeer {
+    pub network_id: NetworkId,
+    pub peer_id: PeerId,
+    #[serde(flatten)]
+    pub metadata: PeerMonitoringMetadata,
+}
+
+/// Returns the monitoring metadata of all the peers in `storage`, ordered by network and peer.
+pub fn monitored_peers(storage: &PeerMonitoringStorage) -> Vec<MonitoredPeer> {
+    let mut peers = storage
+        .read_all()
+        .into_iter()
+        .map(|((network_id, peer_id), metadata)| MonitoredPeer {
+            network_id,
+            peer_id,
+            metadata,
+        })
+        .collect::<Vec<_>>();
+    peers.sort_by(|a, b| (&a.network_id, a.peer_id).cmp(&(&b.network_id, b.peer_id)));
+    peers
+}
+
+/// The actor probing the connected peers of a network
+pub struct PeerMonitor {
+    network_context: Arc<NetworkContext>,
+    /// A handle to a time service for easily mocking time-related operations.
+    time_service: TimeService,
+    network_tx: PeerMonitorNetworkSender,
+    network_rx: PeerMonitorNetworkEvents,
+    /// The connected peers supporting the protocol.
+    peers: HashSet<PeerId>,
+    storage: Arc<PeerMonitoringStorage>,
+    synced_version: SyncedVersionProvider,
+    /// Time we wait between each set of probes.
+    probe_interval: Duration,
+    probe_timeout: Duration,
+}
+
+impl PeerMonitor {
+    /// Create new instance of the [`PeerMonitor`] actor.
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        time_service: TimeService,
+        network_tx: PeerMonitorNetworkSender,
+        network_rx: PeerMonitorNetworkEvents,
+        storage: Arc<PeerMonitoringStorage>,
+        synced_version: SyncedVersionProvider,
+        probe_interval: Duration,
+        probe_timeout: Duration,
+    ) -> Self {
+        Self {
+            network_context,
+            time_service,
+            network_tx,
+            network_rx,
+            peers: HashSet::new(),
+            storage,
+            synced_version,
+            probe_interval,
+            probe_timeout,
+        }
+    }
+
+    pub async fn start(mut self) {
+        let mut probes = FuturesUnordered::new();
+        info!(
+            NetworkSchema::new(&self.network_context),
+            "{} Peer monitor actor started", self.network_context
+        );
+
+        let ticker = self.time_service.interval(self.probe_interval);
+        tokio::pin!(ticker);
+
+        loop {
+            futures::select! {
+                maybe_event = self.network_rx.next() => {
+                    // Shutdown the PeerMonitor when this network instance shuts
+                    // down. This happens when the `PeerManager` drops.
+                    let event = match maybe_event {
+                        Some(event) => event,
+                        None => break,
+                    };
+
+                    match event {
+                        Event::NewPeer(metadata) => {
+                            if metadata
+                                .application_protocols
+                                .contains(ProtocolId::PeerMonitoringRpc)
+                            {
+                                self.peers.insert(metadata.remote_peer_id);
+                                self.storage.insert(
+                                    self.storage_key(metadata.remote_peer_id),
+                                    PeerMonitoringMetadata::default(),
+                                );
+                            }
+                        }
+                        Event::LostPeer(metadata) => {
+                            if self.peers.remove(&metadata.remote_peer_id) {
+                                self.storage.remove(&self.storage_key(metadata.remote_peer_id));
+                            }
+                        }
+                        Event::RpcRequest(peer_id, msg, res_tx) => match msg {
+                            PeerMonitoringMsg::GetNodeStatus => {
+                                self.handle_node_status_request(peer_id, res_tx)
+                            }
+                            _ => {
+                                warn!(
+                                    NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                                    rpc_message = msg,
+                                    "{} Unexpected RPC message from {}",
+                                    self.network_context,
+                                    peer_id
+                                );
+                            }
+                        },
+                        Event::Message(peer_id, msg) => {
+                            error!(
+                                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                                "{} Unexpected direct send from {} msg {:?}",
+                                self.network_context,
+                                peer_id,
+                                msg,
+                            );
+                            debug_assert!(false, "Unexpected network event");
+                        }
+                    }
+                }
+                _ = ticker.select_next_some() => {
+                    for peer_id in &self.peers {
+                        probes.push(Self::probe_peer(
+                            self.time_service.clone(),
+                            self.network_tx.clone(),
+                            *peer_id,
+                            self.probe_timeout,
+                        ));
+                    }
+                }
+                (peer_id, result) = probes.select_next_some() => {
+                    self.handle_probe_result(peer_id, result);
+                }
+            }
+        }
+        warn!(
+            NetworkSchema::new(&self.network_context),
+            "{} Peer monitor actor terminated", self.network_context
+        );
+    }
+
+    fn storage_key(&self, peer_id: PeerId) -> (NetworkId, PeerId) {
+        (self.network_context.network_id().clone(), peer_id)
+    }
+
+    fn handle_node_status_request(
+        &self,
+        peer_id: PeerId,
+        res_tx: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) {
+        let status = NodeStatus {
+            role: self.network_context.role(),
+            synced_version: (self.synced_version)(),
+        };
+        let message = match bcs::to_bytes(&PeerMonitoringMsg::NodeStatus(status)) {
+            Ok(msg) => msg,
+            Err(e) => {
+                warn!(
+                    NetworkSchema::new(&self.network_context),
+                    error = ?e,
+                    "{} Unable to serialize node status response: {}", self.network_context, e
+                );
+                return;
+            }
+        };
+        trace!(
+            NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+            "{} Sending node status to peer: {}",
+            self.network_context,
+            peer_id.short_str(),
+        );
+        let _ = res_tx.send(Ok(message.into()));
+    }
+
+    fn handle_probe_result(
+        &mut self,
+        peer_id: PeerId,
+        result: Result<(NodeStatus, Duration), RpcError>,
+    ) {
+        if let Err(err) = &result {
+            debug!(
+                NetworkSchema::new(&self.network_context).remote_peer(&peer_id),
+                error = ?err,
+                "{} Probe failed for peer: {} with error: {:?}",
+                self.network_context,
+                peer_id.short_str(),
+                err
+            );
+        }
+        // The peer may have disconnected since it was probed.
+        if !self.peers.contains(&peer_id) {
+            return;
+        }
+        let key = self.storage_key(peer_id);
+        let mut metadata = self.storage.read(&key).unwrap_or_default();
+        match result {
+            Ok((status, rtt)) => metadata.record_success(status, rtt),
+            Err(_) => metadata.consecutive_failures += 1,
+        }
+        self.storage.insert(key, metadata);
+    }
+
+    async fn probe_peer(
+        time_service: TimeService,
+        mut network_tx: PeerMonitorNetworkSender,
+        peer_id: PeerId,
+        probe_timeout: Duration,
+    ) -> (PeerId, Result<(NodeStatus, Duration), RpcError>) {
+        let start = time_service.now();
+        let result = network_tx
+            .send_rpc(peer_id, PeerMonitoringMsg::GetNodeStatus, probe_timeout)
+            .await
+            .and_then(|msg| match msg {
+                PeerMonitoringMsg::NodeStatus(status) => {
+                    Ok((status, time_service.now().duration_since(start)))
+                }
+                _ => Err(RpcError::InvalidRpcResponse),
+            });
+        (peer_id, result)
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    peer::DisconnectReason,
+    peer_manager::{
+        self, conn_notifs_channel, ConnectionRequest, PeerManagerNotification, PeerManagerRequest,
+    },
+    protocols::{network::NewNetworkEvents, rpc::InboundRpcRequest},
+    transport::ConnectionMetadata,
+};
+use channel::diem_channel;
+use diem_time_service::MockTimeService;
+use futures::{executor::block_on, future};
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+const SYNCED_VERSION: Version = 42;
+
+struct TestHarness {
+    mock_time: MockTimeService,
+    storage: Arc<PeerMonitoringStorage>,
+    peer_mgr_reqs_rx: diem_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    peer_mgr_notifs_tx: diem_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>,
+    _connection_reqs_rx: diem_channel::Receiver<PeerId, ConnectionRequest>,
+    connection_notifs_tx: conn_notifs_channel::Sender,
+}
+
+impl TestHarness {
+    fn new() -> (Self, PeerMonitor) {
+        ::diem_logger::Logger::init_for_testing();
+        let mock_time = TimeService::mock();
+        let storage = Arc::new(PeerMonitoringStorage::new());
+
+        let (peer_mgr_reqs_tx, peer_mgr_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 1, None);
+        let (connection_reqs_tx, connection_reqs_rx) = diem_channel::new(QueueStyle::FIFO, 1, None);
+        let (peer_mgr_notifs_tx, peer_mgr_notifs_rx) = diem_channel::new(QueueStyle::FIFO, 1, None);
+        let (connection_notifs_tx, connection_notifs_rx) = conn_notifs_channel::new();
+
+        let network_tx = PeerMonitorNetworkSender::new(
+            PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+        let network_rx = PeerMonitorNetworkEvents::new(peer_mgr_notifs_rx, connection_notifs_rx);
+        let peer_monitor = PeerMonitor::new(
+            NetworkContext::mock(),
+            mock_time.clone(),
+            network_tx,
+            network_rx,
+            storage.clone(),
+            Arc::new(|| Some(SYNCED_VERSION)),
+            PROBE_INTERVAL,
+            PROBE_TIMEOUT,
+        );
+
+        (
+            Self {
+                mock_time: mock_time.into_mock(),
+                storage,
+                peer_mgr_reqs_rx,
+                peer_mgr_notifs_tx,
+                _connection_reqs_rx: connection_reqs_rx,
+                connection_notifs_tx,
+            },
+            peer_monitor,
+        )
+    }
+
+    async fn trigger_probe(&self) {
+        self.mock_time.advance_async(PROBE_INTERVAL).await;
+    }
+
+    async fn expect_probe(&mut self) -> oneshot::Sender<Result<Bytes, RpcError>> {
+        let req = self.peer_mgr_reqs_rx.next().await.unwrap();
+        let rpc_req = match req {
+            PeerManagerRequest::SendRpc(_peer_id, rpc_req) => rpc_req,
+            _ => panic!("Unexpected PeerManagerRequest: {:?}", req),
+        };
+        assert_eq!(rpc_req.protocol_id, ProtocolId::PeerMonitoringRpc);
+        match bcs::from_bytes(&rpc_req.data).unwrap() {
+            PeerMonitoringMsg::GetNodeStatus => rpc_req.res_tx,
+            msg => panic!("Unexpected PeerMonitoringMsg: {:?}", msg),
+        }
+    }
+
+    async fn send_inbound_probe(
+        &mut self,
+        peer_id: PeerId,
+    ) -> oneshot::Receiver<Result<Bytes, RpcError>> {
+        let protocol_id = ProtocolId::PeerMonitoringRpc;
+        let data = bcs::to_bytes(&PeerMonitoringMsg::GetNodeStatus)
+            .unwrap()
+            .into();
+        let (res_tx, res_rx) = oneshot::channel();
+        let inbound_rpc_req = InboundRpcRequest {
+            protocol_id,
+            data,
+            res_tx,
+        };
+        let (delivered_tx, delivered_rx) = oneshot::channel();
+        self.peer_mgr_notifs_tx
+            .push_with_feedback(
+                (peer_id, protocol_id),
+                PeerManagerNotification::RecvRpc(peer_id, inbound_rpc_req),
+                Some(delivered_tx),
+            )
+            .unwrap();
+        delivered_rx.await.unwrap();
+        res_rx
+    }
+
+    async fn send_connection_notification(&mut self, notif: peer_manager::ConnectionNotification) {
+        let peer_id = match &notif {
+            peer_manager::ConnectionNotification::NewPeer(metadata, _)
+            | peer_manager::ConnectionNotification::LostPeer(metadata, _, _) => {
+                metadata.remote_peer_id
+            }
+        };
+        let (delivered_tx, delivered_rx) = oneshot::channel();
+        self.connection_notifs_tx
+            .push_with_feedback(peer_id, notif, Some(delivered_tx))
+            .unwrap();
+        delivered_rx.await.unwrap();
+    }
+
+    async fn send_new_peer_notification(&mut self, peer_id: PeerId) {
+        let mut metadata = ConnectionMetadata::mock(peer_id);
+        metadata.application_protocols = [ProtocolId::PeerMonitoringRpc].iter().into();
+        self.send_connection_notification(peer_manager::ConnectionNotification::NewPeer(
+            metadata,
+            NetworkContext::mock(),
+        ))
+        .await;
+    }
+
+    /// Waits for the PeerMonitor to store metadata of `peer_id` matching `predicate`.
+    async fn wait_for_metadata<F: Fn(&PeerMonitoringMetadata) -> bool>(
+        &self,
+        peer_id: PeerId,
+        predicate: F,
+    ) -> PeerMonitoringMetadata {
+        let key = (NetworkId::Validator, peer_id);
+        loop {
+            if let Some(metadata) = self.storage.read(&key) {
+                if predicate(&metadata) {
+                    return metadata;
+                }
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+fn node_status() -> NodeStatus {
+    NodeStatus {
+        role: RoleType::Validator,
+        synced_version: Some(SYNCED_VERSION),
+    }
+}
+
+#[test]
+fn outbound() {
+    let (mut harness, peer_monitor) = TestHarness::new();
+
+    let test = async move {
+        // Notify PeerMonitor of new connected node.
+        let peer_id = PeerId::new([0x42; PeerId::LENGTH]);
+        harness.send_new_peer_notification(peer_id).await;
+
+        // Trigger a probe, and reply to it after 100ms.
+        harness.trigger_probe().await;
+        let res_tx = harness.expect_probe().await;
+        harness
+            .mock_time
+            .advance_async(Duration::from_millis(100))
+            .await;
+        let res_data = bcs::to_bytes(&PeerMonitoringMsg::NodeStatus(node_status())).unwrap();
+        res_tx.send(Ok(res_data.into())).unwrap();
+
+        // PeerMonitor should record the status and the round trip time of the peer.
+        let metadata = harness
+            .wait_for_metadata(peer_id, |metadata| metadata.status.is_some())
+            .await;
+        assert_eq!(metadata.status, Some(node_status()));
+        assert_eq!(metadata.synced_version(), Some(SYNCED_VERSION));
+        assert_eq!(metadata.latest_rtt_ms, Some(100));
+        assert_eq!(metadata.average_rtt_ms, Some(100.0));
+        assert_eq!(metadata.consecutive_failures, 0);
+
+        // A failed probe is counted, but keeps the latest known status.
+        harness.trigger_probe().await;
+        let res_tx = harness.expect_probe().await;
+        res_tx.send(Err(RpcError::TimedOut)).unwrap();
+        let metadata = harness
+            .wait_for_metadata(peer_id, |metadata| metadata.consecutive_failures == 1)
+            .await;
+        assert_eq!(metadata.status, Some(node_status()));
+    };
+    block_on(future::join(peer_monitor.start(), test));
+}
+
+#[test]
+fn inbound() {
+    let (mut harness, peer_monitor) = TestHarness::new();
+
+    let test = async move {
+        let peer_id = PeerId::new([0x42; PeerId::LENGTH]);
+        let res_rx = harness.send_inbound_probe(peer_id).await;
+
+        // PeerMonitor should reply with the role and synced version of the node.
+        let res_data = res_rx.await.unwrap().unwrap();
+        match bcs::from_bytes(&res_data).unwrap() {
+            PeerMonitoringMsg::NodeStatus(status) => assert_eq!(status, node_status()),
+            msg => panic!("Unexpected PeerMonitoringMsg: {:?}", msg),
+        }
+    };
+    block_on(future::join(peer_monitor.start(), test));
+}
+
+#[test]
+fn lost_peer() {
+    let (mut harness, peer_monitor) = TestHarness::new();
+
+    let test = async move {
+        let peer_id = PeerId::new([0x42; PeerId::LENGTH]);
+        harness.send_new_peer_notification(peer_id).await;
+        let key = (NetworkId::Validator, peer_id);
+        assert_eq!(
+            harness.storage.read(&key),
+            Some(PeerMonitoringMetadata::default())
+        );
+
+        // PeerMonitor should forget the peer once disconnected.
+        harness
+            .send_connection_notification(peer_manager::ConnectionNotification::LostPeer(
+                ConnectionMetadata::mock(peer_id),
+                NetworkContext::mock(),
+                DisconnectReason::ConnectionLost,
+            ))
+            .await;
+        assert_eq!(harness.storage.read(&key), None);
+    };
+    block_on(future::join(peer_monitor.start(), test));
+}
@@ -42,6 +42,7 @@ pub enum ProtocolId {
     HealthCheckerRpc = 5,
     // json provides flexibility for backwards compatible upgrade
     ConsensusDirectSendJSON = 6,
+    PeerMonitoringRpc = 7,
 }
 
 impl ProtocolId {
@@ -55,6 +56,7 @@ impl ProtocolId {
             DiscoveryDirectSend => "DiscoveryDirectSend",
             HealthCheckerRpc => "HealthCheckerRpc",
             ConsensusDirectSendJSON => "ConsensusDirectSendJson",
+            PeerMonitoringRpc => "PeerMonitoringRpc",
         }
     }
 
@@ -67,6 +69,7 @@ impl ProtocolId {
             ProtocolId::DiscoveryDirectSend,
             ProtocolId::HealthCheckerRpc,
             ProtocolId::ConsensusDirectSendJSON,
+            ProtocolId::PeerMonitoringRpc,
         ]
     }
 
@@ -158,12 +161,15 @@ impl SupportedProtocols {
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
 pub enum MessagingProtocolVersion {
     V1 = 0,
+    /// V1 with a substream per application protocol.
+    V2 = 1,
 }
 
 impl MessagingProtocolVersion {
     fn as_str(&self) -> &str {
         match self {
             Self::V1 => "V1",
+            Self::V2 => "V2",
         }
     }
 }
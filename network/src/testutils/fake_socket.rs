@@ -7,6 +7,7 @@
 //! - ReadWriteTestSocket: a similar wrapper but around MemorySocket to retrieve handshake messages being sent as well.
 //!
 
+use crate::transport::TSubstreams;
 use futures::{
     io::{AsyncRead, AsyncWrite},
     ready,
@@ -203,6 +204,9 @@ impl AsyncRead for ReadOnlyTestSocketVec {
     }
 }
 
+/// The connection has no substreams
+impl TSubstreams for ReadOnlyTestSocketVec {}
+
 //
 // ReadWriteTestSocket
 // ==================
@@ -18,7 +18,10 @@ use diem_logger::prelude::*;
 use diem_time_service::{timeout, TimeService, TimeServiceTrait};
 use diem_types::{
     chain_id::ChainId,
-    network_address::{parse_dns_tcp, parse_ip_tcp, parse_memory, NetworkAddress},
+    network_address::{
+        parse_dns_tcp, parse_dns_udp_quic, parse_ip_tcp, parse_ip_udp_quic, parse_memory,
+        NetworkAddress,
+    },
     PeerId,
 };
 use futures::{
@@ -41,9 +44,12 @@ use std::{
     time::Duration,
 };
 
+mod substreams;
 #[cfg(test)]
 mod test;
 
+pub use substreams::{SubstreamReader, SubstreamWriter, Substreams, TSubstreams};
+
 /// A timeout for the connection to open and complete all of the upgrade steps.
 pub const TRANSPORT_TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -51,6 +57,10 @@ pub const TRANSPORT_TIMEOUT: Duration = Duration::from_secs(30);
 /// TODO: Add ability to support more than one messaging protocol.
 pub const SUPPORTED_MESSAGING_PROTOCOL: MessagingProtocolVersion = MessagingProtocolVersion::V1;
 
+/// The messaging protocol version of connections carrying a substream per application protocol,
+/// offered along with [`SUPPORTED_MESSAGING_PROTOCOL`] on connections which have substreams.
+pub const SUBSTREAMS_MESSAGING_PROTOCOL: MessagingProtocolVersion = MessagingProtocolVersion::V2;
+
 /// Global connection-id generator.
 static CONNECTION_ID_GENERATOR: ConnectionIdGenerator = ConnectionIdGenerator::new();
 
@@ -63,9 +73,15 @@ pub const DIEM_TCP_TRANSPORT: tcp::TcpTransport = tcp::TcpTransport {
 };
 
 /// A trait alias for "socket-like" things.
-pub trait TSocket: AsyncRead + AsyncWrite + Send + fmt::Debug + Unpin + 'static {}
+pub trait TSocket:
+    AsyncRead + AsyncWrite + TSubstreams + Send + fmt::Debug + Unpin + 'static
+{
+}
 
-impl<T> TSocket for T where T: AsyncRead + AsyncWrite + Send + fmt::Debug + Unpin + 'static {}
+impl<T> TSocket for T where
+    T: AsyncRead + AsyncWrite + TSubstreams + Send + fmt::Debug + Unpin + 'static
+{
+}
 
 /// Unique local identifier for a connection.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize)]
@@ -221,6 +237,25 @@ impl UpgradeContext {
             network_id,
         }
     }
+
+    /// The HandshakeMsg to offer on a connection. Connections which have substreams additionally
+    /// offer [`SUBSTREAMS_MESSAGING_PROTOCOL`], with the same application protocols.
+    fn handshake_msg(&self, has_substreams: bool) -> HandshakeMsg {
+        let mut supported_protocols = self.supported_protocols.clone();
+        if has_substreams {
+            if let Some(application_protocols) = supported_protocols
+                .get(&SUPPORTED_MESSAGING_PROTOCOL)
+                .cloned()
+            {
+                supported_protocols.insert(SUBSTREAMS_MESSAGING_PROTOCOL, application_protocols);
+            }
+        }
+        HandshakeMsg {
+            supported_protocols,
+            chain_id: self.chain_id,
+            network_id: self.network_id.clone(),
+        }
+    }
 }
 
 /// If we have proxy protocol enabled, then prepend the un-proxied address to the error.
@@ -289,11 +324,7 @@ async fn upgrade_inbound<T: TSocket>(
     let addr = addr.append_prod_protos(remote_pubkey, HANDSHAKE_VERSION);
 
     // exchange HandshakeMsg
-    let handshake_msg = HandshakeMsg {
-        supported_protocols: ctxt.supported_protocols.clone(),
-        chain_id: ctxt.chain_id,
-        network_id: ctxt.network_id.clone(),
-    };
+    let handshake_msg = ctxt.handshake_msg(socket.has_substreams());
     let remote_handshake = exchange_handshake(&handshake_msg, &mut socket)
         .await
         .map_err(|err| add_pp_addr(proxy_protocol_enabled, err, &addr))?;
@@ -366,11 +397,7 @@ pub async fn upgrade_outbound<T: TSocket>(
     debug_assert_eq!(remote_pubkey, socket.get_remote_static());
 
     // exchange HandshakeMsg
-    let handshake_msg = HandshakeMsg {
-        supported_protocols: ctxt.supported_protocols.clone(),
-        chain_id: ctxt.chain_id,
-        network_id: ctxt.network_id.clone(),
-    };
+    let handshake_msg = ctxt.handshake_msg(socket.has_substreams());
     let remote_handshake = exchange_handshake(&handshake_msg, &mut socket).await?;
 
     // try to negotiate common diemnet version and supported application protocols
@@ -476,13 +503,15 @@ where
         let (base_transport_protos, base_transport_suffix) = parse_ip_tcp(protos)
             .map(|x| (&protos[..2], x.1))
             .or_else(|| parse_dns_tcp(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_ip_udp_quic(protos).map(|x| (&protos[..3], x.1)))
+            .or_else(|| parse_dns_udp_quic(protos).map(|x| (&protos[..3], x.1)))
             .or_else(|| parse_memory(protos).map(|x| (&protos[..1], x.1)))
             .ok_or_else(|| {
                 io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
                         "Unexpected dialing network address: '{}', expected: \
-                         memory, ip+tcp, or dns+tcp",
+                         memory, ip+tcp, dns+tcp, ip+udp+quic, or dns+udp+quic",
                         addr
                     ),
                 )
@@ -528,6 +557,15 @@ where
     /// `/dns/<ipaddr>/tcp/<port>` or
     /// `/dns4/<ipaddr>/tcp/<port>` or
     /// `/dns6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `TcpQuicTransport`, then `/<base_transport>` is
+    /// also, for QUIC connections:
+    ///
+    /// `/ip4/<ipaddr>/udp/<port>/quic` or
+    /// `/ip6/<ipaddr>/udp/<port>/quic` or
+    /// `/dns/<ipaddr>/udp/<port>/quic` or
+    /// `/dns4/<ipaddr>/udp/<port>/quic` or
+    /// `/dns6/<ipaddr>/udp/<port>/quic`
     pub fn dial(
         &self,
         peer_id: PeerId,
@@ -0,0 +1,168 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Substreams of a connection, beside its socket.
+//!
+//! Connections over QUIC carry a substream per application protocol in each direction, so that
+//! messages of a busy protocol, or lost packets of one, do not hold up the messages of the other
+//! protocols. Substreams are only used once both peers offered
+//! [`SUBSTREAMS_MESSAGING_PROTOCOL`](super::SUBSTREAMS_MESSAGING_PROTOCOL) in the handshake.
+
+use crate::{noise::stream::NoiseStream, ProtocolId};
+use diem_crypto::noise::NoiseSession;
+use futures::{
+    future::{self, BoxFuture, Either, FutureExt},
+    io::{AsyncRead, AsyncWrite},
+    stream::{BoxStream, StreamExt, TryStreamExt},
+};
+use netcore::transport::{
+    quic::{QuicSocket, QuicSubstreams},
+    tcp::TcpSocket,
+};
+use std::{collections::HashSet, fmt, io};
+
+/// The write half of a substream.
+pub type SubstreamWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// The read half of a substream.
+pub type SubstreamReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Sockets whose connection may carry substreams. Sockets without any rely on the default
+/// methods.
+pub trait TSubstreams {
+    /// Whether the connection carries substreams which were not taken yet.
+    fn has_substreams(&self) -> bool {
+        false
+    }
+
+    /// Takes the substreams of the connection, if it carries any. They can only be taken once.
+    fn take_substreams(&mut self) -> Option<Substreams> {
+        None
+    }
+}
+
+/// The substreams of a connection. Each one is tagged with the application protocol whose
+/// messages it carries, and at most one substream per protocol is accepted from the remote.
+///
+/// Once the connection is authenticated, substreams are encrypted with Noise sessions derived from
+/// the session of the connection, see [`NoiseStream`].
+pub struct Substreams {
+    quic: QuicSubstreams,
+    session: Option<NoiseSession>,
+}
+
+impl Substreams {
+    pub fn new(quic: QuicSubstreams) -> Self {
+        Self {
+            quic,
+            session: None,
+        }
+    }
+
+    /// Encrypts the substreams with sessions derived from `session`, one per protocol.
+    pub(crate) fn encrypt(self, session: &NoiseSession) -> io::Result<Self> {
+        let session = session
+            .derive_session(b"substreams")
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok(Self {
+            quic: self.quic,
+            session: Some(session),
+        })
+    }
+
+    /// The session of the substream of the protocol. As a derived session starts with fresh
+    /// nonces, it must only be used for one substream in each direction.
+    fn substream_session(
+        session: &Option<NoiseSession>,
+        tag: u8,
+    ) -> io::Result<Option<NoiseSession>> {
+        session
+            .as_ref()
+            .map(|session| session.derive_session(&[tag]))
+            .transpose()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
+    /// Opens the substream of the protocol to the remote.
+    pub fn open(&self, protocol_id: ProtocolId) -> BoxFuture<'static, io::Result<SubstreamWriter>> {
+        let tag = protocol_id as u8;
+        let session = Self::substream_session(&self.session, tag);
+        let send = self.quic.open(tag);
+        async move {
+            let session = session?;
+            let send = send.await?;
+            let writer: SubstreamWriter = match session {
+                Some(session) => Box::new(NoiseStream::new(send, session)),
+                None => Box::new(send),
+            };
+            Ok(writer)
+        }
+        .boxed()
+    }
+
+    /// Returns the substreams opened by the remote, along with their protocols. Substreams of
+    /// unknown protocols are returned as errors, and ones of protocols which already have a
+    /// substream are dropped: the remote must not open them, and accepting them would let an
+    /// attacker replay a substream under the same derived session.
+    pub fn accept(self) -> BoxStream<'static, io::Result<(ProtocolId, SubstreamReader)>> {
+        let session = self.session;
+        let mut seen_tags = HashSet::new();
+        self.quic
+            .accept()
+            .try_filter(move |(tag, _)| future::ready(seen_tags.insert(*tag)))
+            .and_then(move |(tag, recv)| {
+                let accepted = bcs::from_bytes(&[tag])
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+                    .and_then(|protocol_id| {
+                        let session = Self::substream_session(&session, tag)?;
+                        let reader: SubstreamReader = match session {
+                            Some(session) => Box::new(NoiseStream::new(recv, session)),
+                            None => Box::new(recv),
+                        };
+                        Ok((protocol_id, reader))
+                    });
+                future::ready(accepted)
+            })
+            .boxed()
+    }
+}
+
+impl fmt::Debug for Substreams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Substreams")
+            .field("quic", &self.quic)
+            .field("encrypted", &self.session.is_some())
+            .finish()
+    }
+}
+
+#[cfg(any(test, feature = "testing", feature = "fuzzing"))]
+impl TSubstreams for memsocket::MemorySocket {}
+
+impl TSubstreams for TcpSocket {}
+
+impl TSubstreams for QuicSocket {
+    fn has_substreams(&self) -> bool {
+        QuicSocket::has_substreams(self)
+    }
+
+    fn take_substreams(&mut self) -> Option<Substreams> {
+        QuicSocket::take_substreams(self).map(Substreams::new)
+    }
+}
+
+impl<A: TSubstreams, B: TSubstreams> TSubstreams for Either<A, B> {
+    fn has_substreams(&self) -> bool {
+        match self {
+            Either::Left(socket) => socket.has_substreams(),
+            Either::Right(socket) => socket.has_substreams(),
+        }
+    }
+
+    fn take_substreams(&mut self) -> Option<Substreams> {
+        match self {
+            Either::Left(socket) => socket.take_substreams(),
+            Either::Right(socket) => socket.take_substreams(),
+        }
+    }
+}
@@ -21,10 +21,15 @@ use diem_types::{
 use futures::{future, io::AsyncWriteExt, stream::StreamExt};
 use netcore::{
     framing::{read_u16frame, write_u16frame},
-    transport::{memory, ConnectionOrigin, Transport},
+    transport::{memory, quic::TcpQuicTransport, ConnectionOrigin, Transport},
 };
 use rand::{rngs::StdRng, SeedableRng};
-use std::{collections::HashMap, io, sync::Arc};
+use std::{
+    collections::HashMap,
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::runtime::Runtime;
 
 /// helper to build trusted peer map
@@ -210,11 +215,25 @@ fn expect_ip4_tcp_noise_addr(addr: &NetworkAddress) {
     );
 }
 
+/// Check that the network address matches the format
+/// `"/ip4/<ipaddr>/udp/<port>/quic/ln-noise-ik/<pubkey>/ln-handshake/<version>"`
+fn expect_ip4_udp_quic_noise_addr(addr: &NetworkAddress) {
+    assert!(
+        matches!(
+            addr.as_slice(),
+            [Ip4(_), Udp(_), Quic, NoiseIK(_), Handshake(_)]
+        ),
+        "addr: '{}'",
+        addr
+    );
+}
+
 fn test_transport_success<TTransport>(
     base_transport: TTransport,
     auth: Auth,
     listen_addr: &str,
     expect_formatted_addr: fn(&NetworkAddress),
+    messaging_protocol: MessagingProtocolVersion,
 ) where
     TTransport: Transport<Error = io::Error> + Clone,
     TTransport::Output: TSocket,
@@ -250,10 +269,7 @@ fn test_transport_success<TTransport>(
         assert_eq!(conn.metadata.remote_peer_id, dialer_peer_id);
         expect_formatted_addr(&conn.metadata.addr);
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Inbound);
-        assert_eq!(
-            conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
-        );
+        assert_eq!(conn.metadata.messaging_protocol, messaging_protocol);
         assert_eq!(
             conn.metadata.application_protocols,
             supported_protocols_clone,
@@ -279,10 +295,7 @@ fn test_transport_success<TTransport>(
         assert_eq!(conn.metadata.remote_peer_id, listener_peer_id);
         assert_eq!(conn.metadata.addr, listener_addr);
         assert_eq!(conn.metadata.origin, ConnectionOrigin::Outbound);
-        assert_eq!(
-            conn.metadata.messaging_protocol,
-            MessagingProtocolVersion::V1
-        );
+        assert_eq!(conn.metadata.messaging_protocol, messaging_protocol);
         assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
         // test the socket works
@@ -501,6 +514,7 @@ fn test_memory_transport_mutual_auth() {
         Auth::Mutual,
         "/memory/0",
         expect_memory_noise_addr,
+        MessagingProtocolVersion::V1,
     );
 }
 
@@ -511,6 +525,7 @@ fn test_memory_transport_server_only_auth() {
         Auth::ServerOnly,
         "/memory/0",
         expect_memory_noise_addr,
+        MessagingProtocolVersion::V1,
     );
 }
 
@@ -543,6 +558,7 @@ fn test_tcp_transport_mutual_auth() {
         Auth::Mutual,
         "/ip4/127.0.0.1/tcp/0",
         expect_ip4_tcp_noise_addr,
+        MessagingProtocolVersion::V1,
     );
 }
 
@@ -553,6 +569,7 @@ fn test_tcp_transport_server_only_auth() {
         Auth::ServerOnly,
         "/ip4/127.0.0.1/tcp/0",
         expect_ip4_tcp_noise_addr,
+        MessagingProtocolVersion::V1,
     );
 }
 
@@ -564,3 +581,105 @@ fn test_tcp_transport_rejects_unauthed_dialer() {
         expect_ip4_tcp_noise_addr,
     );
 }
+
+// DiemNetTransport<TcpQuicTransport> //
+/////////////////////////////////////////
+
+#[test]
+fn test_quic_transport_mutual_auth() {
+    test_transport_success(
+        TcpQuicTransport::new(DIEM_TCP_TRANSPORT.clone()),
+        Auth::Mutual,
+        "/ip4/127.0.0.1/udp/0/quic",
+        expect_ip4_udp_quic_noise_addr,
+        MessagingProtocolVersion::V2,
+    );
+}
+
+#[test]
+fn test_quic_transport_rejects_unauthed_dialer() {
+    test_transport_rejects_unauthed_dialer(
+        TcpQuicTransport::new(DIEM_TCP_TRANSPORT.clone()),
+        "/ip4/127.0.0.1/udp/0/quic",
+        expect_ip4_udp_quic_noise_addr,
+    );
+}
+
+#[test]
+fn test_quic_transport_dials_tcp_only_listener() {
+    // The address of a listener which only accepts TCP connections names TCP, so it is dialed
+    // over TCP right away, without first waiting for QUIC to time out.
+    let start = Instant::now();
+    test_transport_success(
+        TcpQuicTransport::new(DIEM_TCP_TRANSPORT.clone()),
+        Auth::Mutual,
+        "/ip4/127.0.0.1/tcp/0",
+        expect_ip4_tcp_noise_addr,
+        MessagingProtocolVersion::V1,
+    );
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn test_quic_transport_substreams() {
+    let (
+        rt,
+        _mock_time,
+        (listener_peer_id, listener_transport),
+        (_dialer_peer_id, dialer_transport),
+        _trusted_peers,
+        _supported_protocols,
+    ) = setup(
+        TcpQuicTransport::new(DIEM_TCP_TRANSPORT.clone()),
+        Auth::Mutual,
+    );
+
+    // Each peer opens a substream per protocol, and reads the substreams of the other one.
+    async fn exchange_substreams(mut conn: Connection<impl TSocket>) {
+        assert_eq!(
+            conn.metadata.messaging_protocol,
+            MessagingProtocolVersion::V2
+        );
+        let substreams = conn.socket.take_substreams().unwrap();
+        assert!(!conn.socket.has_substreams());
+
+        let protocol_ids = [ProtocolId::ConsensusRpc, ProtocolId::MempoolDirectSend];
+        for protocol_id in protocol_ids.iter() {
+            let mut writer = substreams.open(*protocol_id).await.unwrap();
+            write_u16frame(&mut writer, protocol_id.as_str().as_bytes())
+                .await
+                .unwrap();
+            writer.close().await.unwrap();
+        }
+
+        let mut accepted = substreams.accept();
+        for _ in 0..protocol_ids.len() {
+            let (protocol_id, mut reader) = accepted.next().await.unwrap().unwrap();
+            let mut buf = BytesMut::new();
+            read_u16frame(&mut reader, &mut buf).await.unwrap();
+            assert_eq!(&buf[..], protocol_id.as_str().as_bytes());
+        }
+
+        // the socket still works along with the substreams
+        let msg = write_read_msg(&mut conn.socket, b"foobar").await;
+        assert_eq!(&msg, b"foobar".as_ref());
+    }
+
+    let _guard = rt.enter();
+    let (mut inbounds, listener_addr) = listener_transport
+        .listen_on("/ip4/127.0.0.1/udp/0/quic".parse().unwrap())
+        .unwrap();
+    let listener_task = async move {
+        let (inbound, _dialer_addr) = inbounds.next().await.unwrap().unwrap();
+        exchange_substreams(inbound.await.unwrap()).await;
+    };
+    let dialer_task = async move {
+        let conn = dialer_transport
+            .dial(listener_peer_id, listener_addr)
+            .unwrap()
+            .await
+            .unwrap();
+        exchange_substreams(conn).await;
+    };
+    rt.block_on(future::join(listener_task, dialer_task));
+}
@@ -9,11 +9,12 @@ use super::{
 };
 use crate::{
     error::WaitForTransactionError,
-    move_deserialize::{self, Event},
+    move_deserialize::{self, Event, EventRegistry, TypedEvent},
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionView,
+        AccountResourcesWithProofsView, AccountStateWithProofView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView,
+        CurrencyInfoView, EventByVersionWithProofView, EventView, EventWithProofView, MetadataView,
+        StateCheckpointView, StateProofView, TransactionSimulationView, TransactionView,
         TransactionsWithProofsView,
     },
     Error, Result, Retry, State,
@@ -145,6 +146,16 @@ impl Client {
         self.send_without_retry(&request, true).await
     }
 
+    /// Executes `txn` on the latest state of the server without submitting it, and returns the
+    /// write set and events it would produce.
+    pub async fn simulate(
+        &self,
+        txn: &SignedTransaction,
+    ) -> Result<Response<TransactionSimulationView>> {
+        self.send(MethodRequest::simulate(txn).map_err(Error::request)?)
+            .await
+    }
+
     pub async fn get_metadata_by_version(&self, version: u64) -> Result<Response<MetadataView>> {
         self.send(MethodRequest::get_metadata_by_version(version))
             .await
@@ -198,6 +209,15 @@ impl Client {
         .await
     }
 
+    pub async fn get_transaction_by_hash(
+        &self,
+        hash: HashValue,
+        include_events: bool,
+    ) -> Result<Response<Option<TransactionView>>> {
+        self.send(MethodRequest::get_transaction_by_hash(hash, include_events))
+            .await
+    }
+
     pub async fn get_account_transactions(
         &self,
         address: AccountAddress,
@@ -318,6 +338,30 @@ impl Client {
             .await
     }
 
+    pub async fn get_state_checkpoint(
+        &self,
+        version: Option<u64>,
+    ) -> Result<Response<StateCheckpointView>> {
+        self.send(MethodRequest::get_state_checkpoint(version))
+            .await
+    }
+
+    pub async fn get_account_resources_with_proofs(
+        &self,
+        address: AccountAddress,
+        resources: Vec<String>,
+        client_known_version: u64,
+        version: Option<u64>,
+    ) -> Result<Response<AccountResourcesWithProofsView>> {
+        self.send(MethodRequest::get_account_resources_with_proofs(
+            address,
+            resources,
+            client_known_version,
+            version,
+        ))
+        .await
+    }
+
     /// Return the events of type `T` that have been emitted to `event_key` since `start_seq`, with a max of `limit`
     /// results
     /// Returns an empty vector if there are no such events
@@ -338,6 +382,26 @@ impl Client {
         ))
     }
 
+    /// Return the events that have been emitted to `event_key` since `start_seq`, with a max of
+    /// `limit` results, deserialized into the types registered in `registry`
+    /// Events of types missing from `registry` are returned without deserialized data
+    pub async fn get_typed_events(
+        &self,
+        registry: &EventRegistry,
+        event_key: &EventKey,
+        start_seq: u64,
+        limit: u64,
+    ) -> Result<Response<Vec<TypedEvent>>> {
+        let (events, state) = self
+            .get_events_with_proofs(*event_key, start_seq, limit)
+            .await?
+            .into_parts();
+        Ok(Response::new(
+            move_deserialize::get_typed_events(registry, events)?,
+            state,
+        ))
+    }
+
     /// Deserialize and return the resource value of type `T` stored under `address`
     /// Returns None if there is no such value
     pub async fn get_deserialized_resource<T: MoveResource>(
@@ -35,7 +35,7 @@ pub use response::{MethodResponse, Response};
 
 cfg_async_or_blocking! {
     mod move_deserialize;
-    pub use move_deserialize::Event;
+    pub use move_deserialize::{Event, EventRegistry, TypedEvent};
 }
 
 // This API is experimental and subject to change
@@ -88,6 +88,10 @@ pub enum Method {
     GetAccountTransactionsWithProofs,
     GetEventsWithProofs,
     GetEventByVersionWithProof,
+    GetStateCheckpoint,
+    Simulate,
+    GetAccountResourcesWithProofs,
+    GetTransactionByHash,
 }
 
 cfg_async_or_blocking! {
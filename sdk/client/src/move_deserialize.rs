@@ -4,13 +4,22 @@
 use crate::{Error, Result};
 use diem_json_rpc_types::views::{AccountStateWithProofView, EventWithProofView};
 use diem_types::{
+    account_config::{
+        AdminTransactionEvent, BaseUrlRotationEvent, BurnEvent, CancelBurnEvent,
+        ComplianceKeyRotationEvent, CreateAccountEvent, MintEvent, NewBlockEvent, NewEpochEvent,
+        PreburnEvent, ReceivedMintEvent, ReceivedPaymentEvent, SentPaymentEvent,
+        ToXDXExchangeRateUpdateEvent, VASPDomainEvent,
+    },
     account_state::AccountState,
     account_state_blob::AccountStateBlob,
     contract_event::{ContractEvent, EventWithProof},
 };
-use move_core_types::{language_storage::TypeTag, move_resource::{MoveResource, MoveStructType}};
+use move_core_types::{
+    language_storage::TypeTag,
+    move_resource::{MoveResource, MoveStructType},
+};
 use serde::de::DeserializeOwned;
-use std::convert::TryFrom;
+use std::{any::Any, collections::HashMap, convert::TryFrom};
 
 /// Wrapper for a deserialized Move event and its containing `ContractEvent`
 #[derive(Debug, Clone)]
@@ -62,6 +71,114 @@ pub fn get_events<T: MoveStructType + DeserializeOwned>(
         .collect::<Result<Vec<Event<T>>>>()
 }
 
+type EventDecoder = fn(&[u8]) -> Result<Box<dyn Any + Send + Sync>>;
+
+fn decode_event<T: DeserializeOwned + Send + Sync + 'static>(
+    data: &[u8],
+) -> Result<Box<dyn Any + Send + Sync>> {
+    Ok(Box::new(bcs::from_bytes::<T>(data).map_err(Error::decode)?))
+}
+
+/// Maps the type tags of Move events to the Rust types they are deserialized into
+#[derive(Clone, Default)]
+pub struct EventRegistry {
+    decoders: HashMap<TypeTag, EventDecoder>,
+}
+
+impl EventRegistry {
+    /// Returns an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a registry of the events emitted by the Diem Framework
+    pub fn framework() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register::<AdminTransactionEvent>()
+            .register::<BaseUrlRotationEvent>()
+            .register::<BurnEvent>()
+            .register::<CancelBurnEvent>()
+            .register::<ComplianceKeyRotationEvent>()
+            .register::<CreateAccountEvent>()
+            .register::<MintEvent>()
+            .register::<NewBlockEvent>()
+            .register::<NewEpochEvent>()
+            .register::<PreburnEvent>()
+            .register::<ReceivedMintEvent>()
+            .register::<ReceivedPaymentEvent>()
+            .register::<SentPaymentEvent>()
+            .register::<ToXDXExchangeRateUpdateEvent>()
+            .register::<VASPDomainEvent>();
+        registry
+    }
+
+    /// Deserializes the events of the Move type of `T` into `T`, replacing the type previously
+    /// registered for it, if any
+    pub fn register<T: MoveStructType + DeserializeOwned + Send + Sync + 'static>(
+        &mut self,
+    ) -> &mut Self {
+        self.decoders
+            .insert(TypeTag::Struct(T::struct_tag()), decode_event::<T>);
+        self
+    }
+
+    /// Returns true if events of type `type_tag` are deserialized by the registry
+    pub fn contains(&self, type_tag: &TypeTag) -> bool {
+        self.decoders.contains_key(type_tag)
+    }
+
+    /// Deserialize `event` into the type registered for its type tag, if any
+    pub fn decode(&self, event: ContractEvent) -> Result<TypedEvent> {
+        let data = self
+            .decoders
+            .get(event.type_tag())
+            .map(|decode| decode(event.event_data()))
+            .transpose()?;
+        Ok(TypedEvent { data, event })
+    }
+}
+
+/// Wrapper for a Move event deserialized by an [`EventRegistry`] and its containing
+/// `ContractEvent`
+#[derive(Debug)]
+pub struct TypedEvent {
+    /// The deserialized event, None if its type isn't registered
+    data: Option<Box<dyn Any + Send + Sync>>,
+    event: ContractEvent,
+}
+
+impl TypedEvent {
+    /// Returns the deserialized event if it's of type `T`
+    pub fn data<T: 'static>(&self) -> Option<&T> {
+        self.data.as_ref().and_then(|data| data.downcast_ref::<T>())
+    }
+
+    /// Returns true if the event was deserialized into type `T`
+    pub fn is<T: 'static>(&self) -> bool {
+        self.data::<T>().is_some()
+    }
+
+    pub fn event(&self) -> &ContractEvent {
+        &self.event
+    }
+}
+
+/// Deserialize and return the Move events in `events` into the types registered in `registry`
+pub fn get_typed_events(
+    registry: &EventRegistry,
+    events: Vec<EventWithProofView>,
+) -> Result<Vec<TypedEvent>> {
+    events
+        .into_iter()
+        .map(|e| {
+            let event_with_proof = bcs::from_bytes::<EventWithProof>(e.event_with_proof.inner())
+                .map_err(Error::decode)?;
+            registry.decode(event_with_proof.event)
+        })
+        .collect()
+}
+
 fn get_account_state(
     account_state_with_proof: AccountStateWithProofView,
 ) -> Result<Option<AccountState>> {
@@ -86,3 +203,68 @@ pub fn get_resource<T: MoveResource>(
     }
     Ok(None)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_core_types::{ident_str, identifier::IdentStr};
+    use serde::Deserialize;
+
+    /// Another Rust type for the `NewEpochEvent` of the framework
+    #[derive(Debug, Deserialize)]
+    struct Epoch {
+        epoch: u64,
+    }
+
+    impl MoveStructType for Epoch {
+        const MODULE_NAME: &'static IdentStr = ident_str!("DiemConfig");
+        const STRUCT_NAME: &'static IdentStr = ident_str!("NewEpochEvent");
+    }
+
+    fn new_epoch_event(data: Vec<u8>) -> ContractEvent {
+        ContractEvent::new(
+            NewEpochEvent::event_key(),
+            0,
+            TypeTag::Struct(NewEpochEvent::struct_tag()),
+            data,
+        )
+    }
+
+    #[test]
+    fn test_decode_registered_event() {
+        let registry = EventRegistry::framework();
+        assert!(registry.contains(&TypeTag::Struct(NewEpochEvent::struct_tag())));
+
+        let event = new_epoch_event(bcs::to_bytes(&5u64).unwrap());
+        let typed_event = registry.decode(event.clone()).unwrap();
+        assert!(typed_event.is::<NewEpochEvent>());
+        assert!(!typed_event.is::<MintEvent>());
+        assert_eq!(typed_event.data::<NewEpochEvent>().unwrap().epoch(), 5);
+        assert_eq!(typed_event.event(), &event);
+
+        // Events which can't be deserialized into the registered type are errors.
+        assert!(registry.decode(new_epoch_event(vec![])).is_err());
+    }
+
+    #[test]
+    fn test_decode_unregistered_event() {
+        let event = new_epoch_event(bcs::to_bytes(&5u64).unwrap());
+        let registry = EventRegistry::new();
+        assert!(!registry.contains(event.type_tag()));
+
+        let typed_event = registry.decode(event.clone()).unwrap();
+        assert!(typed_event.data::<NewEpochEvent>().is_none());
+        assert_eq!(typed_event.event(), &event);
+    }
+
+    #[test]
+    fn test_register_replaces_type() {
+        let mut registry = EventRegistry::framework();
+        registry.register::<Epoch>();
+
+        let event = new_epoch_event(bcs::to_bytes(&5u64).unwrap());
+        let typed_event = registry.decode(event).unwrap();
+        assert!(!typed_event.is::<NewEpochEvent>());
+        assert_eq!(typed_event.data::<Epoch>().unwrap().epoch, 5);
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use super::{JsonRpcVersion, Method};
+use diem_crypto::HashValue;
 use diem_types::{
     account_address::AccountAddress, event::EventKey, transaction::SignedTransaction,
 };
@@ -32,6 +33,10 @@ pub enum MethodRequest {
     GetAccountTransactionsWithProofs(AccountAddress, u64, u64, bool, Option<u64>),
     GetEventsWithProofs(EventKey, u64, u64),
     GetEventByVersionWithProof(EventKey, Option<u64>),
+    GetStateCheckpoint((Option<u64>,)),
+    Simulate((String,)),
+    GetAccountResourcesWithProofs(AccountAddress, Vec<String>, u64, Option<u64>),
+    GetTransactionByHash(HashValue, bool),
 }
 
 impl MethodRequest {
@@ -144,6 +149,28 @@ impl MethodRequest {
         Self::GetEventByVersionWithProof(key, version)
     }
 
+    pub fn get_state_checkpoint(version: Option<u64>) -> Self {
+        Self::GetStateCheckpoint((version,))
+    }
+
+    pub fn simulate(txn: &SignedTransaction) -> Result<Self, bcs::Error> {
+        let txn_payload = hex::encode(bcs::to_bytes(txn)?);
+        Ok(Self::Simulate((txn_payload,)))
+    }
+
+    pub fn get_account_resources_with_proofs(
+        address: AccountAddress,
+        resources: Vec<String>,
+        client_known_version: u64,
+        version: Option<u64>,
+    ) -> Self {
+        Self::GetAccountResourcesWithProofs(address, resources, client_known_version, version)
+    }
+
+    pub fn get_transaction_by_hash(hash: HashValue, include_events: bool) -> Self {
+        Self::GetTransactionByHash(hash, include_events)
+    }
+
     pub fn method(&self) -> Method {
         match self {
             MethodRequest::Submit(_) => Method::Submit,
@@ -166,6 +193,12 @@ impl MethodRequest {
             }
             MethodRequest::GetEventsWithProofs(_, _, _) => Method::GetEventsWithProofs,
             MethodRequest::GetEventByVersionWithProof(_, _) => Method::GetEventByVersionWithProof,
+            MethodRequest::GetStateCheckpoint(_) => Method::GetStateCheckpoint,
+            MethodRequest::Simulate(_) => Method::Simulate,
+            MethodRequest::GetAccountResourcesWithProofs(..) => {
+                Method::GetAccountResourcesWithProofs
+            }
+            MethodRequest::GetTransactionByHash(_, _) => Method::GetTransactionByHash,
         }
     }
 }
@@ -4,9 +4,10 @@
 use super::Method;
 use crate::{
     views::{
-        AccountStateWithProofView, AccountTransactionsWithProofView, AccountView,
-        AccumulatorConsistencyProofView, CurrencyInfoView, EventByVersionWithProofView, EventView,
-        EventWithProofView, MetadataView, StateProofView, TransactionView,
+        AccountResourcesWithProofsView, AccountStateWithProofView,
+        AccountTransactionsWithProofView, AccountView, AccumulatorConsistencyProofView,
+        CurrencyInfoView, EventByVersionWithProofView, EventView, EventWithProofView, MetadataView,
+        StateCheckpointView, StateProofView, TransactionSimulationView, TransactionView,
         TransactionsWithProofsView,
     },
     Error, State,
@@ -76,6 +77,10 @@ pub enum MethodResponse {
     GetAccountTransactionsWithProofs(AccountTransactionsWithProofView),
     GetEventsWithProofs(Vec<EventWithProofView>),
     GetEventByVersionWithProof(EventByVersionWithProofView),
+    GetStateCheckpoint(StateCheckpointView),
+    Simulate(TransactionSimulationView),
+    GetAccountResourcesWithProofs(AccountResourcesWithProofsView),
+    GetTransactionByHash(Option<TransactionView>),
 }
 
 impl MethodResponse {
@@ -117,6 +122,16 @@ impl MethodResponse {
             Method::GetEventByVersionWithProof => {
                 MethodResponse::GetEventByVersionWithProof(serde_json::from_value(json)?)
             }
+            Method::GetStateCheckpoint => {
+                MethodResponse::GetStateCheckpoint(serde_json::from_value(json)?)
+            }
+            Method::Simulate => MethodResponse::Simulate(serde_json::from_value(json)?),
+            Method::GetAccountResourcesWithProofs => {
+                MethodResponse::GetAccountResourcesWithProofs(serde_json::from_value(json)?)
+            }
+            Method::GetTransactionByHash => {
+                MethodResponse::GetTransactionByHash(serde_json::from_value(json)?)
+            }
         };
 
         Ok(response)
@@ -144,6 +159,12 @@ impl MethodResponse {
             }
             MethodResponse::GetEventsWithProofs(_) => Method::GetEventsWithProofs,
             MethodResponse::GetEventByVersionWithProof(_) => Method::GetEventByVersionWithProof,
+            MethodResponse::GetStateCheckpoint(_) => Method::GetStateCheckpoint,
+            MethodResponse::Simulate(_) => Method::Simulate,
+            MethodResponse::GetAccountResourcesWithProofs(_) => {
+                Method::GetAccountResourcesWithProofs
+            }
+            MethodResponse::GetTransactionByHash(_) => Method::GetTransactionByHash,
         }
     }
 
@@ -272,6 +293,48 @@ impl MethodResponse {
         }
     }
 
+    pub fn try_into_get_state_checkpoint(self) -> Result<StateCheckpointView, Error> {
+        match self {
+            MethodResponse::GetStateCheckpoint(view) => Ok(view),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetStateCheckpoint found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_simulate(self) -> Result<TransactionSimulationView, Error> {
+        match self {
+            MethodResponse::Simulate(view) => Ok(view),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::Simulate found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_get_account_resources_with_proofs(
+        self,
+    ) -> Result<AccountResourcesWithProofsView, Error> {
+        match self {
+            MethodResponse::GetAccountResourcesWithProofs(view) => Ok(view),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetAccountResourcesWithProofs found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
+    pub fn try_into_get_transaction_by_hash(self) -> Result<Option<TransactionView>, Error> {
+        match self {
+            MethodResponse::GetTransactionByHash(tx) => Ok(tx),
+            _ => Err(Error::rpc_response(format!(
+                "expected MethodResponse::GetTransactionByHash found MethodResponse::{:?}",
+                self.method()
+            ))),
+        }
+    }
+
     pub fn try_into_get_currencies(self) -> Result<Vec<CurrencyInfoView>, Error> {
         match self {
             MethodResponse::GetCurrencies(currencies) => Ok(currencies),
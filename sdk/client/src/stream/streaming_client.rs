@@ -4,7 +4,10 @@
 use crate::{stream::websocket_transport::WebsocketTransport, StreamError, StreamResult};
 use diem_json_rpc_types::{
     stream::{
-        request::{StreamMethodRequest, SubscribeToEventsParams, SubscribeToTransactionsParams},
+        request::{
+            EventFilter, StreamMethodRequest, SubscribeToEventsParams,
+            SubscribeToMatchingEventsParams, SubscribeToTransactionsParams,
+        },
         response::StreamJsonRpcResponse,
     },
     Id,
@@ -143,6 +146,22 @@ impl StreamingClient {
         let request = StreamMethodRequest::SubscribeToTransactions(SubscribeToTransactionsParams {
             starting_version,
             include_events,
+            filter: None,
+        });
+        self.send_subscription(request).await
+    }
+
+    /// Subscribes to the transactions emitting an event matching `filter`.
+    pub async fn subscribe_matching_transactions(
+        &mut self,
+        starting_version: u64,
+        include_events: Option<bool>,
+        filter: EventFilter,
+    ) -> StreamResult<SubscriptionStream> {
+        let request = StreamMethodRequest::SubscribeToTransactions(SubscribeToTransactionsParams {
+            starting_version,
+            include_events,
+            filter: Some(filter),
         });
         self.send_subscription(request).await
     }
@@ -159,6 +178,20 @@ impl StreamingClient {
         self.send_subscription(request).await
     }
 
+    /// Subscribes to the events matching `filter`, emitted from `starting_version` on.
+    pub async fn subscribe_matching_events(
+        &mut self,
+        starting_version: u64,
+        filter: EventFilter,
+    ) -> StreamResult<SubscriptionStream> {
+        let request =
+            StreamMethodRequest::SubscribeToMatchingEvents(SubscribeToMatchingEventsParams {
+                starting_version,
+                filter,
+            });
+        self.send_subscription(request).await
+    }
+
     pub(crate) async fn send_unsubscribe(&mut self, id: &Id) -> StreamResult<()> {
         debug!("StreamingClient sending unsubscribe for: {:?}", id);
         self.client
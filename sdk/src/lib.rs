@@ -10,6 +10,7 @@
 //! * `client` - Includes a [JSON-RPC client](https://github.com/diem/diem/blob/master/json-rpc/json-rpc-spec.md) implementation
 //! * `crypto` - Types used for signing and verifying
 //! * `transaction_builder` - Includes helpers for constructing transactions
+//! * `transaction_submitter` - Submits transactions while keeping account sequence numbers in sync
 //! * `types` - Includes types for Diem on-chain data structures
 //!
 //! ## Example
@@ -62,6 +63,10 @@ pub mod crypto {
 
 pub mod transaction_builder;
 
+#[cfg(feature = "client")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client")))]
+pub mod transaction_submitter;
+
 pub mod types;
 
 pub mod move_types {
@@ -6,6 +6,7 @@ use crate::{
     types::{
         account_config::{xdx_type_tag, xus_tag, XDX_NAME, XUS_NAME},
         chain_id::ChainId,
+        on_chain_config::OnChainConsensusConfig,
         transaction::{authenticator::AuthenticationKey, RawTransaction, TransactionPayload},
     },
 };
@@ -448,6 +449,17 @@ impl TransactionFactory {
         ))
     }
 
+    /// Updates the consensus config, e.g. to tune the round timeout curve of the validators
+    /// without a software release. Sent by the Diem root account.
+    pub fn update_consensus_config(
+        &self,
+        sliding_nonce: u64,
+        config: &OnChainConsensusConfig,
+    ) -> TransactionBuilder {
+        let config = bcs::to_bytes(config).expect("consensus config serialization can't fail");
+        self.update_diem_consensus_config(sliding_nonce, config)
+    }
+
     pub fn update_diem_version(&self, sliding_nonce: u64, major: u64) -> TransactionBuilder {
         if self.is_script_function_enabled() {
             self.payload(stdlib::encode_update_diem_version_script_function(
@@ -0,0 +1,419 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Submission of transactions that keeps the sequence numbers of local accounts in sync with the
+//! node, and retries the submissions rejected for transient reasons.
+
+use crate::{
+    client::{
+        errors::ServerCode, views::TransactionView, Client, Error, Response, Result,
+        WaitForTransactionError,
+    },
+    transaction_builder::TransactionFactory,
+    types::{
+        transaction::{SignedTransaction, TransactionPayload},
+        vm_status::StatusCode,
+        LocalAccount,
+    },
+};
+use std::{cmp::min, time::Duration};
+
+/// How a [`TransactionSubmitter`] retries the submissions rejected for transient reasons.
+#[derive(Clone, Debug)]
+pub struct SubmitRetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl SubmitRetryPolicy {
+    pub fn new(max_retries: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// Never retries a rejected submission.
+    pub fn none() -> Self {
+        Self::new(0, Duration::from_millis(0), Duration::from_millis(0))
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    pub fn initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+}
+
+impl Default for SubmitRetryPolicy {
+    fn default() -> Self {
+        Self::new(5, Duration::from_millis(100), Duration::from_secs(5))
+    }
+}
+
+/// Why a submission was rejected, when retrying it may succeed.
+enum RetriableFailure {
+    /// The sequence number of the transaction is not the next one expected by the node.
+    SequenceNumber,
+    /// Mempool has no room for the transaction for now.
+    MempoolFull,
+}
+
+impl RetriableFailure {
+    fn from_error(error: &Error) -> Option<Self> {
+        let json_rpc_error = error.json_rpc_error()?;
+        match json_rpc_error.as_status_code() {
+            Some(StatusCode::SEQUENCE_NUMBER_TOO_OLD)
+            | Some(StatusCode::SEQUENCE_NUMBER_TOO_NEW) => {
+                return Some(RetriableFailure::SequenceNumber)
+            }
+            _ => (),
+        }
+        if json_rpc_error.code == ServerCode::MempoolInvalidSeqNumber as i16 {
+            Some(RetriableFailure::SequenceNumber)
+        } else if json_rpc_error.code == ServerCode::MempoolIsFull as i16 {
+            Some(RetriableFailure::MempoolFull)
+        } else {
+            None
+        }
+    }
+}
+
+/// Signs and submits transactions for [`LocalAccount`]s.
+///
+/// The sequence number tracked by each account is advanced on every successful submission, and
+/// refreshed from the node when a submission is rejected for using a stale or future sequence
+/// number. Submissions rejected because mempool is full are retried with an exponential backoff.
+#[derive(Clone, Debug)]
+pub struct TransactionSubmitter {
+    client: Client,
+    transaction_factory: TransactionFactory,
+    retry_policy: SubmitRetryPolicy,
+}
+
+impl TransactionSubmitter {
+    pub fn new(client: Client, transaction_factory: TransactionFactory) -> Self {
+        Self {
+            client,
+            transaction_factory,
+            retry_policy: SubmitRetryPolicy::default(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: SubmitRetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn transaction_factory(&self) -> &TransactionFactory {
+        &self.transaction_factory
+    }
+
+    /// Sets the sequence number of `account` to its on-chain sequence number. The sequence
+    /// number is left untouched if the account does not exist on-chain.
+    pub async fn sync_sequence_number(&self, account: &mut LocalAccount) -> Result<()> {
+        if let Some(account_view) = self
+            .client
+            .get_account(account.address())
+            .await?
+            .into_inner()
+        {
+            *account.sequence_number_mut() = account_view.sequence_number;
+        }
+        Ok(())
+    }
+
+    /// Signs a transaction sending `payload` from `account` and submits it, retrying as allowed
+    /// by the retry policy. Returns the transaction accepted by the node.
+    pub async fn submit(
+        &self,
+        account: &mut LocalAccount,
+        payload: TransactionPayload,
+    ) -> Result<SignedTransaction> {
+        let mut remaining_retries = self.retry_policy.max_retries;
+        let mut backoff = self.retry_policy.initial_backoff;
+        loop {
+            let txn = account
+                .sign_with_transaction_builder(self.transaction_factory.payload(payload.clone()));
+            let error = match self.client.submit(&txn).await {
+                Ok(_) => return Ok(txn),
+                Err(error) => error,
+            };
+            // The transaction was rejected, so its sequence number is still the next one to use
+            *account.sequence_number_mut() = txn.sequence_number();
+
+            let failure = match RetriableFailure::from_error(&error) {
+                Some(failure) if remaining_retries > 0 => failure,
+                _ => return Err(error),
+            };
+            remaining_retries -= 1;
+            match failure {
+                RetriableFailure::SequenceNumber => self.sync_sequence_number(account).await?,
+                RetriableFailure::MempoolFull => {
+                    tokio::time::sleep(backoff).await;
+                    backoff = min(backoff * 2, self.retry_policy.max_backoff);
+                }
+            }
+        }
+    }
+
+    /// Waits for `txn` to be executed, for at most `timeout`. Fails if the transaction expires,
+    /// or is executed without success.
+    pub async fn wait_for_transaction(
+        &self,
+        txn: &SignedTransaction,
+        timeout: Duration,
+    ) -> Result<Response<TransactionView>, WaitForTransactionError> {
+        self.client
+            .wait_for_signed_transaction(txn, Some(timeout), None)
+            .await
+    }
+
+    /// Submits a transaction sending `payload` from `account`, and waits for it to be executed
+    /// for at most `timeout`.
+    pub async fn submit_and_wait(
+        &self,
+        account: &mut LocalAccount,
+        payload: TransactionPayload,
+        timeout: Duration,
+    ) -> Result<Response<TransactionView>, WaitForTransactionError> {
+        let txn = self
+            .submit(account, payload)
+            .await
+            .map_err(WaitForTransactionError::GetTransactionError)?;
+        self.wait_for_transaction(&txn, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        client::{
+            errors::JsonRpcError,
+            views::{AccountRoleView, AccountView, BytesView},
+        },
+        transaction_builder::{stdlib, Currency},
+        types::{account_address::AccountAddress, chain_id::ChainId, event::EventKey},
+    };
+    use rand_core::OsRng;
+    use serde_json::{json, Value};
+    use std::{
+        collections::VecDeque,
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    /// A node answering the submissions with scripted errors, and accepting them once the script
+    /// is exhausted. Accounts are reported with the sequence number `sequence_number`.
+    struct FakeNode {
+        submit_errors: Mutex<VecDeque<JsonRpcError>>,
+        sequence_number: u64,
+        submissions: Mutex<u64>,
+    }
+
+    impl FakeNode {
+        /// Starts serving on a local port, returning the node and its URL.
+        fn start(submit_errors: Vec<JsonRpcError>, sequence_number: u64) -> (Arc<Self>, String) {
+            let node = Arc::new(Self {
+                submit_errors: Mutex::new(submit_errors.into()),
+                sequence_number,
+                submissions: Mutex::new(0),
+            });
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let server = node.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    server.serve(stream.unwrap());
+                }
+            });
+            (node, url)
+        }
+
+        fn submissions(&self) -> u64 {
+            *self.submissions.lock().unwrap()
+        }
+
+        /// Answers the single JSON-RPC request sent over `stream`, then closes it.
+        fn serve(&self, mut stream: TcpStream) {
+            let mut request = vec![];
+            let mut buf = [0; 4096];
+            let body = loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let request = String::from_utf8_lossy(&request);
+                if let Some(header_end) = request.find("\r\n\r\n") {
+                    let content_length = request[..header_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            if name.eq_ignore_ascii_case("content-length") {
+                                value.trim().parse::<usize>().ok()
+                            } else {
+                                None
+                            }
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + content_length {
+                        break request[header_end + 4..].to_string();
+                    }
+                }
+            };
+            let request: Value = serde_json::from_str(&body).unwrap();
+
+            let mut response = json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "diem_chain_id": ChainId::test().id(),
+                "diem_ledger_version": 1,
+                "diem_ledger_timestampusec": 1,
+            });
+            match request["method"].as_str().unwrap() {
+                "submit" => {
+                    *self.submissions.lock().unwrap() += 1;
+                    match self.submit_errors.lock().unwrap().pop_front() {
+                        Some(error) => response["error"] = serde_json::to_value(error).unwrap(),
+                        None => response["result"] = Value::Null,
+                    }
+                }
+                "get_account" => {
+                    let address: AccountAddress =
+                        serde_json::from_value(request["params"][0].clone()).unwrap();
+                    response["result"] = serde_json::to_value(self.account_view(address)).unwrap();
+                }
+                method => panic!("unexpected method {}", method),
+            }
+
+            let body = response.to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+
+        fn account_view(&self, address: AccountAddress) -> AccountView {
+            AccountView {
+                address,
+                balances: vec![],
+                sequence_number: self.sequence_number,
+                authentication_key: BytesView::from(vec![]),
+                sent_events_key: EventKey::new([0; EventKey::LENGTH]),
+                received_events_key: EventKey::new([0; EventKey::LENGTH]),
+                delegated_key_rotation_capability: false,
+                delegated_withdrawal_capability: false,
+                is_frozen: false,
+                role: AccountRoleView::Unknown,
+                version: None,
+            }
+        }
+    }
+
+    fn mempool_error(code: ServerCode) -> JsonRpcError {
+        JsonRpcError {
+            code: code as i16,
+            message: "mempool error".to_string(),
+            data: None,
+        }
+    }
+
+    fn new_submitter(url: String, retry_policy: SubmitRetryPolicy) -> TransactionSubmitter {
+        TransactionSubmitter::new(Client::new(url), TransactionFactory::new(ChainId::test()))
+            .with_retry_policy(retry_policy)
+    }
+
+    fn retry_policy() -> SubmitRetryPolicy {
+        SubmitRetryPolicy::new(2, Duration::from_millis(1), Duration::from_millis(2))
+    }
+
+    fn payload() -> TransactionPayload {
+        stdlib::encode_peer_to_peer_with_metadata_script_function(
+            Currency::XUS.type_tag(),
+            AccountAddress::ZERO,
+            1,
+            vec![],
+            vec![],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_submit_advances_sequence_number() {
+        let (node, url) = FakeNode::start(vec![], 0);
+        let submitter = new_submitter(url, retry_policy());
+        let mut account = LocalAccount::generate(&mut OsRng);
+
+        for sequence_number in 0..2 {
+            let txn = submitter.submit(&mut account, payload()).await.unwrap();
+            assert_eq!(txn.sequence_number(), sequence_number);
+        }
+        assert_eq!(account.sequence_number(), 2);
+        assert_eq!(node.submissions(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_submit_syncs_stale_sequence_number() {
+        let (node, url) = FakeNode::start(
+            vec![
+                JsonRpcError::vm_status(StatusCode::SEQUENCE_NUMBER_TOO_OLD),
+                mempool_error(ServerCode::MempoolInvalidSeqNumber),
+            ],
+            5,
+        );
+        let submitter = new_submitter(url, retry_policy());
+        let mut account = LocalAccount::generate(&mut OsRng);
+
+        let txn = submitter.submit(&mut account, payload()).await.unwrap();
+        assert_eq!(txn.sequence_number(), 5);
+        assert_eq!(account.sequence_number(), 6);
+        assert_eq!(node.submissions(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_submit_retries_when_mempool_is_full() {
+        let (node, url) = FakeNode::start(vec![mempool_error(ServerCode::MempoolIsFull)], 5);
+        let submitter = new_submitter(url, retry_policy());
+        let mut account = LocalAccount::generate(&mut OsRng);
+
+        // The sequence number is not synced with the node, as it was not the issue.
+        let txn = submitter.submit(&mut account, payload()).await.unwrap();
+        assert_eq!(txn.sequence_number(), 0);
+        assert_eq!(account.sequence_number(), 1);
+        assert_eq!(node.submissions(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_submit_failure() {
+        // Out of retries.
+        let (node, url) = FakeNode::start(vec![mempool_error(ServerCode::MempoolIsFull)], 5);
+        let submitter = new_submitter(url, SubmitRetryPolicy::none());
+        let mut account = LocalAccount::generate(&mut OsRng);
+        submitter.submit(&mut account, payload()).await.unwrap_err();
+        assert_eq!(account.sequence_number(), 0);
+        assert_eq!(node.submissions(), 1);
+
+        // Not retriable.
+        let (node, url) = FakeNode::start(vec![JsonRpcError::invalid_request()], 5);
+        let submitter = new_submitter(url, retry_policy());
+        submitter.submit(&mut account, payload()).await.unwrap_err();
+        assert_eq!(account.sequence_number(), 0);
+        assert_eq!(node.submissions(), 1);
+    }
+}
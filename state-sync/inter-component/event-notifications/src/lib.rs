@@ -7,19 +7,17 @@ use async_trait::async_trait;
 use channel::{diem_channel, message_queues::QueueStyle};
 use diem_infallible::RwLock;
 use diem_types::{
-    account_state::AccountState,
     contract_event::ContractEvent,
     event::EventKey,
     move_resource::MoveStorage,
     on_chain_config,
-    on_chain_config::{config_address, ConfigID, OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY},
+    on_chain_config::{ConfigID, OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY},
     transaction::Version,
 };
 use futures::{channel::mpsc::SendError, stream::FusedStream, Stream};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
-    convert::TryFrom,
     iter::FromIterator,
     ops::Deref,
     pin::Pin,
@@ -266,84 +264,49 @@ impl EventSubscriptionService {
         Ok(())
     }
 
-    /// Fetches the given config IDs on-chain at the specified version.
-    /// Note: We cannot assume that all configs will exist on-chain. As such, we
-    /// must fetch each resource one at a time. Reconfig subscribers must be able
-    /// to handle on-chain configs not existing in a reconfiguration notification.
+    /// Fetches the given config IDs (and the epoch) on-chain at the specified version.
+    /// Note: We cannot assume that all configs will exist on-chain. Reconfig subscribers
+    /// must be able to handle on-chain configs not existing in a reconfiguration notification.
     fn read_on_chain_configs(
         &self,
         config_registry: &[ConfigID],
         version: Version,
     ) -> Result<OnChainConfigPayload, Error> {
-        // Build a map from config ID to the config value found on-chain
-        let mut config_id_to_config = HashMap::new();
-        for config_id in config_registry.iter() {
-            if let Ok(config_list) = self
-                .storage
-                .read()
-                .reader
-                .deref()
-                .batch_fetch_resources_by_version(vec![config_id.access_path()], version)
-            {
-                match &config_list[..] {
-                    [config] => {
-                        if let Some(old_entry) =
-                            config_id_to_config.insert(*config_id, config.clone())
-                        {
-                            panic!(
-                                "Unexpected config values for duplicate config id found! Key: {}, Value: {:?}!",
-                                config_id, old_entry
-                            );
-                        }
-                    }
-                    _ => {
-                        panic!(
-                            "Expected a single on-chain config, but found: {:?}",
-                            config_list
-                        );
-                    }
-                }
-            }
-        }
-
-        // Fetch the account state blob
-        let (account_state_blob, _) = self
+        // Fetch all configs and the epoch at the same version
+        let access_paths = config_registry
+            .iter()
+            .map(|config_id| config_id.access_path())
+            .collect();
+        let (epoch, configs) = self
             .storage
             .read()
             .reader
-            .get_account_state_with_proof_by_version(config_address(), version)
+            .deref()
+            .fetch_configs_by_version(access_paths, version)
             .map_err(|error| {
                 Error::UnexpectedErrorEncountered(format!(
-                    "Failed to fetch account state with proof {:?}",
-                    error
-                ))
-            })?;
-        let account_state_blob = account_state_blob.ok_or_else(|| {
-            Error::UnexpectedErrorEncountered("Missing account state blob!".into())
-        })?;
-
-        // Fetch the new epoch from storage
-        let epoch = AccountState::try_from(&account_state_blob)
-            .and_then(|state| {
-                Ok(state
-                    .get_configuration_resource()?
-                    .ok_or_else(|| {
-                        Error::UnexpectedErrorEncountered(
-                            "Configuration resource does not exist!".into(),
-                        )
-                    })?
-                    .epoch())
-            })
-            .map_err(|error| {
-                Error::UnexpectedErrorEncountered(format!(
-                    "Failed to fetch configuration resource! Error: {:?}",
+                    "Failed to fetch on-chain configs! Error: {:?}",
                     error
                 ))
             })?;
 
+        // Build a map from config ID to the config value found on-chain
+        let mut config_id_to_config = HashMap::new();
+        for (config_id, config) in config_registry.iter().zip(configs) {
+            if let Some(config) = config {
+                if let Some(old_entry) = config_id_to_config.insert(*config_id, config) {
+                    panic!(
+                        "Unexpected config values for duplicate config id found! Key: {}, Value: {:?}!",
+                        config_id, old_entry
+                    );
+                }
+            }
+        }
+
         // Return the new on-chain config payload (containing all found configs at this version).
         Ok(OnChainConfigPayload::new(
             epoch,
+            version,
             Arc::new(config_id_to_config),
         ))
     }
@@ -501,6 +501,10 @@ fn verify_reconfig_notifications_received(
     for listener in listeners {
         if let Some(reconfig_notification) = listener.select_next_some().now_or_never() {
             assert_eq!(reconfig_notification.version, expected_version);
+            assert_eq!(
+                reconfig_notification.on_chain_configs.version(),
+                expected_version
+            );
             assert_eq!(
                 reconfig_notification.on_chain_configs.epoch(),
                 expected_epoch
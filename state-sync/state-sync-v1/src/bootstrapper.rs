@@ -12,6 +12,7 @@ use diem_types::waypoint::Waypoint;
 use executor_types::ChunkExecutor;
 use futures::channel::mpsc;
 use mempool_notifications::MempoolNotificationSender;
+use network::protocols::peer_monitor::PeerMonitoringStorage;
 use std::{boxed::Box, collections::HashMap, sync::Arc};
 use storage_interface::DbReader;
 use subscription_service::ReconfigSubscription;
@@ -27,6 +28,7 @@ pub struct StateSyncBootstrapper {
 impl StateSyncBootstrapper {
     pub fn bootstrap<M: MempoolNotificationSender + 'static>(
         network: Vec<(NodeNetworkId, StateSyncSender, StateSyncEvents)>,
+        peer_monitoring_storage: Arc<PeerMonitoringStorage>,
         mempool_notifier: M,
         consensus_listener: ConsensusNotificationListener,
         storage: Arc<dyn DbReader>,
@@ -41,10 +43,16 @@ impl StateSyncBootstrapper {
             .build()
             .expect("[State Sync] Failed to create runtime!");
 
-        let executor_proxy = ExecutorProxy::new(storage, executor, reconfig_event_subscriptions);
+        let executor_proxy = ExecutorProxy::new(
+            storage,
+            executor,
+            &node_config.state_sync,
+            reconfig_event_subscriptions,
+        );
         Self::bootstrap_with_executor_proxy(
             runtime,
             network,
+            peer_monitoring_storage,
             mempool_notifier,
             consensus_listener,
             node_config,
@@ -59,6 +67,7 @@ impl StateSyncBootstrapper {
     >(
         runtime: Runtime,
         network: Vec<(NodeNetworkId, StateSyncSender, StateSyncEvents)>,
+        peer_monitoring_storage: Arc<PeerMonitoringStorage>,
         mempool_notifier: M,
         consensus_listener: ConsensusNotificationListener,
         node_config: &NodeConfig,
@@ -79,6 +88,7 @@ impl StateSyncBootstrapper {
             mempool_notifier,
             consensus_listener,
             network_senders,
+            peer_monitoring_storage,
             node_config,
             waypoint,
             executor_proxy,
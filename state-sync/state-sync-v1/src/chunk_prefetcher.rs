@@ -0,0 +1,262 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{chunk_response::GetChunkResponse, counters};
+use diem_config::config::PeerNetworkId;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime},
+};
+
+/// The weight of a new observation in the moving averages of the request and execution latencies.
+const LATENCY_OBSERVATION_WEIGHT: f64 = 0.2;
+
+/// Speculatively requests the chunks following the next chunk, so that they arrive while the
+/// preceding chunks are being executed. Chunks which arrive before they can be applied are
+/// buffered until the synced version reaches them.
+///
+/// The number of chunks requested ahead is adapted to the observed latencies: enough requests
+/// are kept in flight for the responses to arrive by the time the chunks before them have been
+/// executed. The coordinator only prefetches within the epoch of its target, so every buffered
+/// chunk can be verified against the same validator set as the chunks before it.
+pub struct ChunkPrefetcher {
+    // The maximum number of chunks to request ahead of the next chunk (0 disables prefetching)
+    max_prefetch_chunks: u64,
+    // The duration after which an unanswered prefetch request is sent again
+    request_timeout: Duration,
+    // Moving averages of the time to receive a chunk after requesting it, and of the time to
+    // apply a chunk
+    request_latency: Option<Duration>,
+    execution_latency: Option<Duration>,
+    // The times at which outstanding prefetch requests were sent, keyed by known version
+    requests: BTreeMap<u64, SystemTime>,
+    // Prefetched chunks which cannot be applied yet, keyed by known version
+    buffered_chunks: BTreeMap<u64, (PeerNetworkId, GetChunkResponse)>,
+}
+
+impl ChunkPrefetcher {
+    pub fn new(max_prefetch_chunks: u64, request_timeout: Duration) -> Self {
+        Self {
+            max_prefetch_chunks,
+            request_timeout,
+            request_latency: None,
+            execution_latency: None,
+            requests: BTreeMap::new(),
+            buffered_chunks: BTreeMap::new(),
+        }
+    }
+
+    pub fn observe_request_latency(&mut self, latency: Duration) {
+        self.request_latency = Some(moving_average(self.request_latency, latency));
+    }
+
+    pub fn observe_execution_latency(&mut self, latency: Duration) {
+        self.execution_latency = Some(moving_average(self.execution_latency, latency));
+    }
+
+    /// Returns the number of chunks to request ahead of the next chunk. Until both latencies
+    /// have been observed, a single chunk is prefetched.
+    pub fn prefetch_depth(&self) -> u64 {
+        if self.max_prefetch_chunks == 0 {
+            return 0;
+        }
+        let depth = match (self.request_latency, self.execution_latency) {
+            (Some(request_latency), Some(execution_latency)) if !execution_latency.is_zero() => {
+                (request_latency.as_secs_f64() / execution_latency.as_secs_f64()).ceil() as u64
+            }
+            _ => 1,
+        };
+        depth.clamp(1, self.max_prefetch_chunks)
+    }
+
+    /// Returns the known versions of the chunk requests to send ahead of the request for the
+    /// chunk following `known_version`, given the chunk limit and the target version. Versions
+    /// which were requested recently, or whose chunks are buffered, are skipped.
+    pub fn versions_to_prefetch(
+        &mut self,
+        known_version: u64,
+        chunk_limit: u64,
+        target_version: u64,
+    ) -> Vec<u64> {
+        let depth = self.prefetch_depth();
+        counters::PREFETCH_DEPTH.set(depth as i64);
+
+        let now = SystemTime::now();
+        let mut versions = vec![];
+        for index in 1..=depth {
+            let version = match chunk_limit
+                .checked_mul(index)
+                .and_then(|offset| known_version.checked_add(offset))
+            {
+                Some(version) if version < target_version => version,
+                _ => break,
+            };
+            if !self.is_pending(version, now) {
+                self.requests.insert(version, now);
+                versions.push(version);
+            }
+        }
+        versions
+    }
+
+    /// Returns true iff the chunk following `known_version` is buffered, or was requested and
+    /// the request has not yet timed out at `now`.
+    pub fn is_pending(&self, known_version: u64, now: SystemTime) -> bool {
+        self.buffered_chunks.contains_key(&known_version)
+            || self
+                .requests
+                .get(&known_version)
+                .map_or(false, |request_time| {
+                    now.duration_since(*request_time)
+                        .map_or(true, |elapsed| elapsed < self.request_timeout)
+                })
+    }
+
+    /// Returns true iff a chunk following `known_version` was prefetched and is not yet
+    /// buffered.
+    pub fn is_prefetch_response(&self, known_version: u64) -> bool {
+        self.requests.contains_key(&known_version)
+            && !self.buffered_chunks.contains_key(&known_version)
+    }
+
+    /// Buffers a prefetched chunk until the synced version reaches `known_version`.
+    pub fn buffer_chunk(
+        &mut self,
+        known_version: u64,
+        peer: PeerNetworkId,
+        response: GetChunkResponse,
+    ) {
+        self.requests.remove(&known_version);
+        self.buffered_chunks.insert(known_version, (peer, response));
+        counters::PREFETCHED_CHUNKS.set(self.buffered_chunks.len() as i64);
+    }
+
+    /// Removes and returns the buffered chunk following `synced_version`, if any.
+    pub fn take_chunk(&mut self, synced_version: u64) -> Option<(PeerNetworkId, GetChunkResponse)> {
+        let chunk = self.buffered_chunks.remove(&synced_version);
+        counters::PREFETCHED_CHUNKS.set(self.buffered_chunks.len() as i64);
+        chunk
+    }
+
+    /// Forgets the requests and buffered chunks which start before the version following
+    /// `synced_version`, as they can no longer be applied.
+    pub fn remove_stale(&mut self, synced_version: u64) {
+        self.requests = self.requests.split_off(&synced_version);
+        self.buffered_chunks = self.buffered_chunks.split_off(&synced_version);
+        counters::PREFETCHED_CHUNKS.set(self.buffered_chunks.len() as i64);
+    }
+}
+
+fn moving_average(average: Option<Duration>, observation: Duration) -> Duration {
+    match average {
+        Some(average) => {
+            average.mul_f64(1.0 - LATENCY_OBSERVATION_WEIGHT)
+                + observation.mul_f64(LATENCY_OBSERVATION_WEIGHT)
+        }
+        None => observation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_response::ResponseLedgerInfo;
+    use diem_crypto::HashValue;
+    use diem_types::{
+        ledger_info::LedgerInfoWithSignatures, on_chain_config::ValidatorSet,
+        transaction::TransactionListWithProof,
+    };
+
+    const CHUNK_LIMIT: u64 = 100;
+
+    #[test]
+    fn test_prefetch_disabled() {
+        let mut prefetcher = ChunkPrefetcher::new(0, Duration::from_secs(10));
+        prefetcher.observe_request_latency(Duration::from_millis(500));
+        prefetcher.observe_execution_latency(Duration::from_millis(100));
+
+        assert_eq!(prefetcher.prefetch_depth(), 0);
+        assert!(prefetcher
+            .versions_to_prefetch(0, CHUNK_LIMIT, 10_000)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_prefetch_depth() {
+        let mut prefetcher = ChunkPrefetcher::new(4, Duration::from_secs(10));
+
+        // Verify a single chunk is prefetched until latencies are known
+        assert_eq!(prefetcher.prefetch_depth(), 1);
+        prefetcher.observe_execution_latency(Duration::from_millis(100));
+        assert_eq!(prefetcher.prefetch_depth(), 1);
+
+        // Verify enough chunks are requested to cover the request latency
+        prefetcher.observe_request_latency(Duration::from_millis(250));
+        assert_eq!(prefetcher.prefetch_depth(), 3);
+
+        // Verify the depth is bounded by the maximum
+        prefetcher.observe_request_latency(Duration::from_secs(10));
+        assert_eq!(prefetcher.prefetch_depth(), 4);
+    }
+
+    #[test]
+    fn test_versions_to_prefetch() {
+        let mut prefetcher = ChunkPrefetcher::new(3, Duration::from_secs(10));
+        prefetcher.observe_request_latency(Duration::from_secs(1));
+        prefetcher.observe_execution_latency(Duration::from_millis(100));
+
+        // Verify the chunks following the next chunk are requested, up to the target
+        assert_eq!(
+            prefetcher.versions_to_prefetch(0, CHUNK_LIMIT, 250),
+            vec![100, 200]
+        );
+
+        // Verify outstanding requests are not sent again
+        assert_eq!(
+            prefetcher.versions_to_prefetch(100, CHUNK_LIMIT, 1_000),
+            vec![300, 400]
+        );
+        assert!(prefetcher.is_pending(200, SystemTime::now()));
+        assert!(!prefetcher.is_pending(500, SystemTime::now()));
+    }
+
+    #[test]
+    fn test_buffered_chunks() {
+        let mut prefetcher = ChunkPrefetcher::new(2, Duration::from_secs(10));
+        let peer = PeerNetworkId::random_validator();
+        assert_eq!(
+            prefetcher.versions_to_prefetch(0, CHUNK_LIMIT, 1_000),
+            vec![100]
+        );
+        assert!(prefetcher.is_prefetch_response(100));
+        assert!(!prefetcher.is_prefetch_response(200));
+
+        // Buffer the prefetched chunk and verify it is only returned once it follows the
+        // synced version
+        prefetcher.buffer_chunk(100, peer, create_test_response());
+        assert!(!prefetcher.is_prefetch_response(100));
+        assert!(prefetcher.is_pending(100, SystemTime::now()));
+        assert!(prefetcher.take_chunk(50).is_none());
+        assert!(prefetcher.take_chunk(100).is_some());
+        assert!(prefetcher.take_chunk(100).is_none());
+
+        // Verify stale chunks are removed
+        prefetcher.buffer_chunk(
+            200,
+            PeerNetworkId::random_validator(),
+            create_test_response(),
+        );
+        prefetcher.remove_stale(300);
+        assert!(!prefetcher.is_pending(200, SystemTime::now()));
+    }
+
+    fn create_test_response() -> GetChunkResponse {
+        GetChunkResponse::new(
+            ResponseLedgerInfo::VerifiableLedgerInfo(LedgerInfoWithSignatures::genesis(
+                HashValue::zero(),
+                ValidatorSet::empty(),
+            )),
+            TransactionListWithProof::new_empty(),
+        )
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::counters;
+use diem_config::{config::ChunkRateLimitConfig, network_id::NetworkId};
+use diem_rate_limiter::rate_limit::Bucket;
+use std::collections::HashMap;
+
+/// Rate limits chunk requests (either sent to upstream peers or served to downstream peers) with
+/// a token bucket per network. Each chunk request takes a single token, and the buckets of the
+/// validator network and the other networks are sized by separate budgets.
+pub struct ChunkRateLimiter {
+    // Whether the limited requests are served ("inbound") or sent ("outbound")
+    direction: &'static str,
+    config: Option<ChunkRateLimitConfig>,
+    buckets: HashMap<NetworkId, Bucket>,
+}
+
+impl ChunkRateLimiter {
+    pub fn new(direction: &'static str, config: Option<ChunkRateLimitConfig>) -> Self {
+        Self {
+            direction,
+            config: config.filter(|config| config.enabled),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Takes a token for a chunk request on the given network. Returns false if the budget of
+    /// the network is exhausted, in which case the request should be dropped.
+    pub fn try_acquire(&mut self, network_id: &NetworkId) -> bool {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return true,
+        };
+        let direction = self.direction;
+        let bucket = self.buckets.entry(network_id.clone()).or_insert_with(|| {
+            let (rate, burst) = if network_id.is_validator_network() {
+                (
+                    config.validator_network_rate,
+                    config.validator_network_burst,
+                )
+            } else {
+                (config.public_network_rate, config.public_network_burst)
+            };
+            // The bucket must hold at least a second worth of tokens
+            let size = burst.max(rate);
+            Bucket::new(
+                direction.to_string(),
+                String::new(),
+                network_id.to_string(),
+                size,
+                size,
+                rate,
+                None,
+            )
+        });
+
+        let acquired = bucket.acquire_all_tokens(1).is_ok();
+        if !acquired {
+            counters::RATE_LIMITED_CHUNK_REQUESTS
+                .with_label_values(&[direction, &network_id.to_string()])
+                .inc();
+        }
+        acquired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::{INBOUND_LABEL, OUTBOUND_LABEL};
+
+    #[test]
+    fn test_rate_limit_disabled() {
+        let mut rate_limiter = ChunkRateLimiter::new(OUTBOUND_LABEL, None);
+        for _ in 0..1_000 {
+            assert!(rate_limiter.try_acquire(&NetworkId::Public));
+        }
+
+        let config = ChunkRateLimitConfig {
+            enabled: false,
+            ..ChunkRateLimitConfig::default()
+        };
+        let mut rate_limiter = ChunkRateLimiter::new(OUTBOUND_LABEL, Some(config));
+        for _ in 0..1_000 {
+            assert!(rate_limiter.try_acquire(&NetworkId::Public));
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_per_network() {
+        let config = ChunkRateLimitConfig {
+            validator_network_rate: 1,
+            validator_network_burst: 3,
+            public_network_rate: 1,
+            public_network_burst: 1,
+            enabled: true,
+        };
+        let mut rate_limiter = ChunkRateLimiter::new(INBOUND_LABEL, Some(config));
+
+        // Verify the public network budget is exhausted after a single request
+        assert!(rate_limiter.try_acquire(&NetworkId::Public));
+        assert!(!rate_limiter.try_acquire(&NetworkId::Public));
+
+        // Verify the validator network has its own (larger) budget
+        for _ in 0..3 {
+            assert!(rate_limiter.try_acquire(&NetworkId::Validator));
+        }
+        assert!(!rate_limiter.try_acquire(&NetworkId::Validator));
+
+        // Verify other networks have a budget of their own
+        assert!(rate_limiter.try_acquire(&NetworkId::vfn_network()));
+    }
+}
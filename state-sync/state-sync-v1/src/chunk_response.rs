@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_types::{
+    epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
     transaction::{TransactionListWithProof, Version},
 };
@@ -35,6 +36,16 @@ pub enum ResponseLedgerInfo {
         // In case a chunk terminates an epoch, the LedgerInfo corresponding to the epoch boundary.
         end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     },
+    /// A response to `TargetType::HighestAvailable` chunk request type for a requester which is
+    /// behind the epoch of `highest_li`.
+    ProgressiveLedgerInfoWithEpochChangeProof {
+        // LedgerInfo that the corresponding GetChunkResponse is built relative to.
+        target_li: LedgerInfoWithSignatures,
+        // LedgerInfo for a version later than that of `target_li`, in a later epoch.
+        highest_li: LedgerInfoWithSignatures,
+        // The epoch changes from the epoch of `target_li` up to the epoch of `highest_li`.
+        epoch_change_proof: EpochChangeProof,
+    },
 }
 
 impl ResponseLedgerInfo {
@@ -48,6 +59,9 @@ impl ResponseLedgerInfo {
             ResponseLedgerInfo::LedgerInfoForWaypoint { waypoint_li, .. } => {
                 waypoint_li.ledger_info().version()
             }
+            ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof { target_li, .. } => {
+                target_li.ledger_info().version()
+            }
         }
     }
 }
@@ -116,6 +130,16 @@ impl fmt::Display for GetChunkResponse {
                     .as_ref()
                     .map_or("None".to_string(), |li| li.ledger_info().to_string())
             ),
+            ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof {
+                target_li,
+                highest_li,
+                epoch_change_proof,
+            } => format!(
+                "[progressive LI: target LI {}, highest LI {}, epoch change proof of {} LIs]",
+                target_li.ledger_info(),
+                highest_li.ledger_info(),
+                epoch_change_proof.ledger_info_with_sigs.len(),
+            ),
         };
         write!(
             f,
@@ -0,0 +1,203 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chunk_response::{GetChunkResponse, ResponseLedgerInfo},
+    counters,
+    error::Error,
+    shared_components::SyncState,
+};
+use diem_config::config::PeerNetworkId;
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+/// A chunk response whose proofs were verified on the verifier pool.
+pub struct VerifiedChunk {
+    pub peer: PeerNetworkId,
+    pub response: GetChunkResponse,
+    /// The trusted epoch the ledger infos of the response were verified against. The result only
+    /// holds for as long as the local trusted epoch doesn't change.
+    pub trusted_epoch: u64,
+    pub result: Result<(), Error>,
+}
+
+/// Verifies the ledger info signatures and the transaction accumulator proofs of chunk responses
+/// on a dedicated pool of threads, so that the coordinator keeps processing messages from peers
+/// while large chunks are verified.
+pub struct ChunkVerifier {
+    pool: ThreadPool,
+}
+
+impl ChunkVerifier {
+    pub fn new(num_threads: usize) -> Result<Self, Error> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("state-sync-verifier-{}", index))
+            .build()
+            .map_err(|error| {
+                Error::UnexpectedError(format!("Failed to build the verifier pool: {}", error))
+            })?;
+        Ok(Self { pool })
+    }
+
+    /// Verifies the proofs of the response against `sync_state` on the pool. The returned future
+    /// completes once the verification is done, and yields None if the verification was dropped
+    /// before completing (e.g., it panicked).
+    pub fn verify(
+        &self,
+        peer: PeerNetworkId,
+        sync_state: SyncState,
+        response: GetChunkResponse,
+    ) -> BoxFuture<'static, Option<VerifiedChunk>> {
+        let (sender, receiver) = oneshot::channel();
+        self.pool.spawn(move || {
+            let _timer = counters::VERIFY_CHUNK_DURATION.start_timer();
+            let result = verify_chunk_proofs(&sync_state, &response);
+            let _ = sender.send(VerifiedChunk {
+                peer,
+                response,
+                trusted_epoch: sync_state.trusted_epoch(),
+                result,
+            });
+        });
+        receiver.map(Result::ok).boxed()
+    }
+}
+
+/// Verifies the ledger infos of the response against the trusted epoch of `sync_state`, and the
+/// transactions of the chunk against the ledger info they are proven by. The waypoint ledger info
+/// of a waypoint response is verified against the waypoint by the coordinator.
+pub fn verify_chunk_proofs(
+    sync_state: &SyncState,
+    response: &GetChunkResponse,
+) -> Result<(), Error> {
+    let proof_li = match &response.response_li {
+        ResponseLedgerInfo::VerifiableLedgerInfo(response_li) => {
+            sync_state.verify_ledger_info(response_li)?;
+            response_li
+        }
+        ResponseLedgerInfo::ProgressiveLedgerInfo {
+            target_li,
+            highest_li,
+        } => {
+            sync_state.verify_ledger_info(target_li)?;
+            if let Some(highest_li) = highest_li {
+                if highest_li != target_li {
+                    sync_state.verify_ledger_info(highest_li)?;
+                }
+            }
+            target_li
+        }
+        ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof {
+            target_li,
+            highest_li,
+            epoch_change_proof,
+        } => {
+            sync_state.verify_ledger_info(target_li)?;
+            if highest_li != target_li {
+                sync_state
+                    .verify_ledger_info_with_epoch_change_proof(highest_li, epoch_change_proof)?;
+            }
+            target_li
+        }
+        ResponseLedgerInfo::LedgerInfoForWaypoint {
+            waypoint_li,
+            end_of_epoch_li,
+        } => {
+            if let Some(end_of_epoch_li) = end_of_epoch_li {
+                sync_state.verify_ledger_info(end_of_epoch_li)?;
+            }
+            waypoint_li
+        }
+    };
+
+    let txn_list_with_proof = &response.txn_list_with_proof;
+    txn_list_with_proof
+        .verify(
+            proof_li.ledger_info(),
+            txn_list_with_proof.first_transaction_version,
+        )
+        .map_err(|error| Error::ProofVerificationFailure(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::HashValue;
+    use diem_types::{
+        block_info::BlockInfo,
+        epoch_state::EpochState,
+        ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+        on_chain_config::ValidatorSet,
+        transaction::{TransactionListWithProof, Version},
+    };
+    use executor_types::ExecutedTrees;
+    use futures::executor::block_on;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_verify_chunk_proofs() {
+        // The genesis ledger info ends epoch 0, so epoch 1 is trusted
+        let sync_state = create_test_sync_state();
+        assert_eq!(sync_state.trusted_epoch(), 1);
+
+        // Verify a response proven by a ledger info of the trusted epoch is valid
+        let response = create_test_response(create_ledger_info(1, 10));
+        assert_eq!(verify_chunk_proofs(&sync_state, &response), Ok(()));
+
+        // Verify a response proven by a ledger info of another epoch is rejected
+        let response = create_test_response(create_ledger_info(2, 10));
+        assert!(matches!(
+            verify_chunk_proofs(&sync_state, &response),
+            Err(Error::ProofVerificationFailure(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_on_pool() {
+        let chunk_verifier = ChunkVerifier::new(2).unwrap();
+        let peer = PeerNetworkId::random_validator();
+        let response = create_test_response(create_ledger_info(1, 10));
+
+        // Verify the verified chunk is returned with the result and the trusted epoch
+        let verified_chunk = block_on(chunk_verifier.verify(
+            peer.clone(),
+            create_test_sync_state(),
+            response.clone(),
+        ))
+        .unwrap();
+        assert_eq!(verified_chunk.peer, peer);
+        assert_eq!(verified_chunk.response, response);
+        assert_eq!(verified_chunk.trusted_epoch, 1);
+        assert_eq!(verified_chunk.result, Ok(()));
+    }
+
+    fn create_test_sync_state() -> SyncState {
+        SyncState::new(
+            LedgerInfoWithSignatures::genesis(HashValue::zero(), ValidatorSet::empty()),
+            ExecutedTrees::new_empty(),
+            EpochState::empty(),
+        )
+    }
+
+    fn create_ledger_info(epoch: u64, version: Version) -> LedgerInfoWithSignatures {
+        let block_info = BlockInfo::new(
+            epoch,
+            0,
+            HashValue::zero(),
+            HashValue::zero(),
+            version,
+            0,
+            None,
+        );
+        let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+        LedgerInfoWithSignatures::new(ledger_info, BTreeMap::new())
+    }
+
+    fn create_test_response(response_li: LedgerInfoWithSignatures) -> GetChunkResponse {
+        GetChunkResponse::new(
+            ResponseLedgerInfo::VerifiableLedgerInfo(response_li),
+            TransactionListWithProof::new_empty(),
+        )
+    }
+}
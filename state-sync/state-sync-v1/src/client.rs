@@ -7,10 +7,12 @@ use futures::{
     future::Future,
     SinkExt,
 };
+use subscription_service::ReconfigSubscription;
 
 /// Messages used by the StateSyncClient for communication with the StateSyncCoordinator.
 pub enum CoordinatorMessage {
     GetSyncState(oneshot::Sender<SyncState>), // Return the local sync state.
+    RegisterReconfigSubscription(ReconfigSubscription, oneshot::Sender<Result<(), Error>>), // Register a reconfig subscription.
     WaitForInitialization(oneshot::Sender<Result<(), Error>>), // Wait until state sync is initialized to the waypoint.
 }
 
@@ -51,4 +53,25 @@ impl StateSyncClient {
             cb_receiver.await?
         }
     }
+
+    /// Registers a reconfig subscription with state sync. The subscription immediately receives
+    /// the current on-chain configs, followed by all subsequent reconfig notifications. This
+    /// allows components started after state sync (or restarted) to subscribe.
+    pub fn register_reconfig_subscription(
+        &self,
+        subscription: ReconfigSubscription,
+    ) -> impl Future<Output = Result<(), Error>> {
+        let mut sender = self.coordinator_sender.clone();
+        let (cb_sender, cb_receiver) = oneshot::channel();
+
+        async move {
+            sender
+                .send(CoordinatorMessage::RegisterReconfigSubscription(
+                    subscription,
+                    cb_sender,
+                ))
+                .await?;
+            cb_receiver.await?
+        }
+    }
 }
@@ -2,16 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    chunk_prefetcher::ChunkPrefetcher,
+    chunk_rate_limiter::ChunkRateLimiter,
     chunk_request::{GetChunkRequest, TargetType},
     chunk_response::{GetChunkResponse, ResponseLedgerInfo},
+    chunk_verifier::{ChunkVerifier, VerifiedChunk},
     client::CoordinatorMessage,
     counters,
     error::Error,
     executor_proxy::ExecutorProxyTrait,
+    fast_sync::{self, FastSync},
     logging::{LogEntry, LogEvent, LogSchema},
     network::{StateSyncEvents, StateSyncMessage, StateSyncSender},
-    request_manager::RequestManager,
-    shared_components::SyncState,
+    request_manager::{RequestManager, PEER_SCORES_COMPONENT},
+    shared_components::{truncate_chunk_at_version, SyncState},
+    state_snapshot::{StateSnapshotRequest, StateSnapshotResponse, StateSnapshotTarget},
+    sync_progress::{ProgressTracker, SyncStage, SYNC_PROGRESS_COMPONENT},
 };
 use consensus_notifications::{
     ConsensusCommitNotification, ConsensusNotification, ConsensusNotificationListener,
@@ -23,7 +29,9 @@ use diem_config::{
 };
 use diem_logger::prelude::*;
 use diem_types::{
+    account_state_blob::AccountStateChunkWithProof,
     contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
     transaction::{Transaction, TransactionListWithProof, Version},
     waypoint::Waypoint,
@@ -33,16 +41,22 @@ use fail::fail_point;
 use futures::{
     channel::{mpsc, oneshot},
     executor::block_on,
-    stream::select_all,
+    future::BoxFuture,
+    stream::{select_all, FuturesUnordered},
     StreamExt,
 };
 use mempool_notifications::MempoolNotificationSender;
-use network::{protocols::network::Event, transport::ConnectionMetadata};
+use network::{
+    protocols::{network::Event, peer_monitor::PeerMonitoringStorage},
+    transport::ConnectionMetadata,
+};
 use std::{
     cmp,
     collections::HashMap,
+    sync::Arc,
     time::{Duration, SystemTime},
 };
+use subscription_service::ReconfigSubscription;
 use tokio::time::interval;
 use tokio_stream::wrappers::IntervalStream;
 
@@ -90,6 +104,21 @@ pub(crate) struct StateSyncCoordinator<T, M> {
     // Actor for sending chunk requests
     // Manages to whom and how to send chunk requests
     request_manager: RequestManager,
+    // Requests chunks ahead of the next chunk and buffers them until they can be applied
+    prefetcher: ChunkPrefetcher,
+    // Verifies the proofs of chunk responses off the coordinator thread, if configured
+    chunk_verifier: Option<ChunkVerifier>,
+    // The chunk verifications in progress on the verifier pool, and the known version of the
+    // chunk being verified. Only the chunk following the synced version is verified at a time.
+    pending_verifications: FuturesUnordered<BoxFuture<'static, Option<VerifiedChunk>>>,
+    verifying_version: Option<u64>,
+    // Limits the rate of the chunk requests served to each network
+    inbound_rate_limiter: ChunkRateLimiter,
+    // Measures the sync rates, which are published with the sync progress
+    progress_tracker: ProgressTracker,
+    // Bootstraps the node from a state snapshot, if fast sync is enabled and the node is empty.
+    // Chunk-based sync starts once the snapshot is restored.
+    fast_sync: Option<FastSync>,
     // Optional sync request to be called when the target sync is reached
     sync_request: Option<SyncRequest>,
     // If we're a full node syncing to the latest state, this holds the highest ledger info
@@ -111,6 +140,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         mempool_notifier: M,
         consensus_listener: ConsensusNotificationListener,
         network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+        peer_monitoring_storage: Arc<PeerMonitoringStorage>,
         node_config: &NodeConfig,
         waypoint: Waypoint,
         executor_proxy: T,
@@ -135,7 +165,30 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             Duration::from_millis(retry_timeout_val),
             Duration::from_millis(node_config.state_sync.multicast_timeout_ms),
             network_senders,
+            peer_monitoring_storage,
+            node_config.state_sync.outbound_rate_limit_config,
+        );
+        let prefetcher = ChunkPrefetcher::new(
+            node_config.state_sync.max_prefetch_chunks,
+            Duration::from_millis(retry_timeout_val),
         );
+        let chunk_verifier = match node_config.state_sync.num_verification_threads {
+            0 => None,
+            num_threads => Some(ChunkVerifier::new(num_threads)?),
+        };
+        let fast_sync = if node_config.state_sync.enable_fast_sync
+            && node_config.state_sync.halt_at_version.is_none()
+            && initial_state.synced_version() == 0
+            && (role == RoleType::FullNode
+                || waypoint.version() > initial_state.committed_version())
+        {
+            Some(FastSync::new(
+                node_config.state_sync.chunk_limit,
+                Duration::from_millis(retry_timeout_val),
+            ))
+        } else {
+            None
+        };
 
         Ok(Self {
             client_events,
@@ -146,6 +199,16 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             role,
             waypoint,
             request_manager,
+            prefetcher,
+            chunk_verifier,
+            pending_verifications: FuturesUnordered::new(),
+            verifying_version: None,
+            inbound_rate_limiter: ChunkRateLimiter::new(
+                counters::INBOUND_LABEL,
+                node_config.state_sync.inbound_rate_limit_config,
+            ),
+            progress_tracker: ProgressTracker::new(SystemTime::now()),
+            fast_sync,
             subscriptions: HashMap::new(),
             sync_request: None,
             target_ledger_info: None,
@@ -201,6 +264,11 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                         CoordinatorMessage::GetSyncState(callback) => {
                             let _ = self.get_sync_state(callback);
                         }
+                        CoordinatorMessage::RegisterReconfigSubscription(subscription, callback) => {
+                            if let Err(e) = self.register_reconfig_subscription(subscription, callback) {
+                                error!(LogSchema::new(LogEntry::Reconfig).error(&e));
+                            }
+                        }
                         CoordinatorMessage::WaitForInitialization(cb_sender) => {
                             if let Err(e) = self.wait_for_initialization(cb_sender) {
                                 error!(LogSchema::new(LogEntry::Waypoint).error(&e));
@@ -233,6 +301,11 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
 
                     }
                 },
+                verified_chunk = self.pending_verifications.select_next_some() => {
+                    if let Err(e) = self.process_verified_chunk(verified_chunk).await {
+                        error!(LogSchema::new(LogEntry::ProcessChunkResponse).error(&e));
+                    }
+                },
                 _ = interval.select_next_some() => {
                     if let Err(e) = self.check_progress() {
                         error!(LogSchema::event_log(LogEntry::ProgressCheck, LogEvent::Fail).error(&e));
@@ -320,6 +393,32 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 // Process chunk response
                 self.process_chunk_response(&peer, *response).await
             }
+            StateSyncMessage::StateSnapshotRequest(request) => {
+                // Time request handling
+                let _timer = counters::PROCESS_MSG_LATENCY
+                    .with_label_values(&[
+                        &peer.raw_network_id().to_string(),
+                        &peer.peer_id().to_string(),
+                        counters::STATE_SNAPSHOT_REQUEST_MSG_LABEL,
+                    ])
+                    .start_timer();
+
+                // Process state snapshot request
+                self.process_state_snapshot_request(&peer, *request)
+            }
+            StateSyncMessage::StateSnapshotResponse(response) => {
+                // Time response handling
+                let _timer = counters::PROCESS_MSG_LATENCY
+                    .with_label_values(&[
+                        &peer.raw_network_id().to_string(),
+                        &peer.peer_id().to_string(),
+                        counters::STATE_SNAPSHOT_RESPONSE_MSG_LABEL,
+                    ])
+                    .start_timer();
+
+                // Process state snapshot response
+                self.process_state_snapshot_response(&peer, *response).await
+            }
         }
     }
 
@@ -404,6 +503,13 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         if target_version == local_li_version {
             return self.send_sync_req_callback(request, Ok(())).await;
         }
+        if let Some(halt_version) = self.config.halt_at_version {
+            if target_version > halt_version {
+                self.send_sync_req_callback(request, Err(Error::SyncHalted(halt_version)))
+                    .await?;
+                return Err(Error::SyncHalted(halt_version));
+            }
+        }
         if target_version < local_li_version {
             self.send_sync_req_callback(
                 request,
@@ -495,6 +601,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         self.check_subscriptions();
         let synced_version = self.local_state.synced_version();
         self.request_manager.remove_requests(synced_version);
+        self.prefetcher.remove_stale(synced_version);
         if let Some(peer) = chunk_sender {
             self.request_manager.process_success_response(peer);
         }
@@ -630,9 +737,68 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             .local_li_version(committed_version)
             .local_synced_version(synced_version)
             .local_epoch(local_epoch));
+        self.publish_sync_progress();
         Ok(())
     }
 
+    /// Publishes the current sync progress (e.g., the sync rates and the estimated completion
+    /// time) to the metric server.
+    fn publish_sync_progress(&mut self) {
+        let (stage, target_version) = if let Some(fast_sync) = &self.fast_sync {
+            (SyncStage::FastSync, fast_sync.target_version())
+        } else if !self.is_initialized() {
+            (SyncStage::Waypoint, Some(self.waypoint.version()))
+        } else if let Some(sync_request) = &self.sync_request {
+            let target_version = sync_request
+                .consensus_sync_notification
+                .target
+                .ledger_info()
+                .version();
+            (SyncStage::SyncRequest, Some(target_version))
+        } else if self.role == RoleType::Validator {
+            (SyncStage::ConsensusExecuting, None)
+        } else {
+            let highest_version = counters::get_version(counters::VersionType::Highest);
+            let target_version = self
+                .target_ledger_info
+                .as_ref()
+                .map(|target_li| target_li.ledger_info().version())
+                .into_iter()
+                .chain(Some(highest_version).filter(|version| *version > 0))
+                .max();
+            (SyncStage::HighestAvailable, target_version)
+        };
+        let progress = self.progress_tracker.progress(
+            SystemTime::now(),
+            stage,
+            self.local_state.synced_version(),
+            self.local_state.committed_version(),
+            target_version,
+        );
+        match serde_json::to_value(&progress) {
+            Ok(progress) => diem_metrics::set_component_status(SYNC_PROGRESS_COMPONENT, progress),
+            Err(error) => warn!(
+                LogSchema::new(LogEntry::ProgressCheck)
+                    .error(&Error::UnexpectedError(error.to_string())),
+                "Failed to serialize the sync progress"
+            ),
+        }
+    }
+
+    /// Publishes the scores and chunk statistics of the upstream peers to the metric server.
+    fn publish_peer_scores(&self) {
+        match serde_json::to_value(&self.request_manager.peer_qualities()) {
+            Ok(peer_qualities) => {
+                diem_metrics::set_component_status(PEER_SCORES_COMPONENT, peer_qualities)
+            }
+            Err(error) => warn!(
+                LogSchema::new(LogEntry::ProgressCheck)
+                    .error(&Error::UnexpectedError(error.to_string())),
+                "Failed to serialize the peer scores"
+            ),
+        }
+    }
+
     /// Returns the current SyncState of state sync.
     /// Note: this is only used for testing and should be removed once integration/e2e tests
     /// are updated to not rely on this.
@@ -647,6 +813,24 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
     }
 
+    /// Registers the subscription with the executor proxy, which publishes the current on-chain
+    /// configs to it, and notifies the callback of the result.
+    fn register_reconfig_subscription(
+        &mut self,
+        subscription: ReconfigSubscription,
+        callback: oneshot::Sender<Result<(), Error>>,
+    ) -> Result<(), Error> {
+        let result = self
+            .executor_proxy
+            .register_reconfig_subscription(subscription);
+        if callback.send(result.clone()).is_err() {
+            return Err(Error::CallbackSendFailed(
+                "Failed to send the reconfig subscription registration result!".into(),
+            ));
+        }
+        result
+    }
+
     /// There are two types of ChunkRequests:
     /// 1) Validator chunk requests are for a specific target LI and don't ask for long polling.
     /// 2) FullNode chunk requests don't specify a target LI and can allow long polling.
@@ -666,6 +850,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 "Injected error in process_chunk_request".into(),
             ))
         });
+        self.check_inbound_rate_limit(&peer)?;
         self.sync_state_with_local_storage()?;
 
         // Verify the chunk request is valid before trying to process it. If it's invalid,
@@ -694,6 +879,52 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
     }
 
+    /// Serves a state snapshot request from a peer which bootstraps from a state snapshot.
+    fn process_state_snapshot_request(
+        &mut self,
+        peer: &PeerNetworkId,
+        request: StateSnapshotRequest,
+    ) -> Result<(), Error> {
+        debug!(LogSchema::event_log(LogEntry::FastSync, LogEvent::Received).peer(peer));
+        self.check_inbound_rate_limit(peer)?;
+        let response = match request {
+            StateSnapshotRequest::Target { known_epoch } => StateSnapshotResponse::Target(
+                self.executor_proxy.get_state_snapshot_target(known_epoch)?,
+            ),
+            StateSnapshotRequest::AccountStates {
+                version,
+                start_key,
+                limit,
+            } => {
+                let limit = cmp::min(limit, self.config.max_chunk_limit);
+                StateSnapshotResponse::AccountStates(
+                    self.executor_proxy
+                        .get_account_state_chunk(version, start_key, limit)?,
+                )
+            }
+        };
+        self.request_manager.send_chunk_response(
+            peer,
+            StateSyncMessage::StateSnapshotResponse(Box::new(response)),
+        )
+    }
+
+    /// Returns an error if the serving budget of the peer's network is exhausted, in which case
+    /// the request is dropped (the peer will retry once its request times out).
+    fn check_inbound_rate_limit(&mut self, peer: &PeerNetworkId) -> Result<(), Error> {
+        if self
+            .inbound_rate_limiter
+            .try_acquire(&peer.raw_network_id())
+        {
+            Ok(())
+        } else {
+            Err(Error::RateLimited(format!(
+                "Dropped a request from peer {:?}",
+                peer
+            )))
+        }
+    }
+
     fn verify_chunk_request_is_valid(&mut self, request: &GetChunkRequest) -> Result<(), Error> {
         // Ensure request versions are correctly formed
         if let Some(target_version) = request.target.version() {
@@ -771,18 +1002,53 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             return Ok(());
         }
 
+        let response_li =
+            self.create_progressive_response_li(request.current_epoch, target_li, local_version)?;
+
+        self.deliver_chunk(peer, request.known_version, response_li, chunk_limit)
+    }
+
+    /// Creates the ledger info for a response to a highest available request. If the requester
+    /// is behind the local epoch and epoch change proofs are enabled, the response carries the
+    /// epoch changes from the requester's epoch, so that the requester can verify the local
+    /// highest ledger info (instead of learning about one epoch at a time).
+    fn create_progressive_response_li(
+        &mut self,
+        request_epoch: u64,
+        request_target_li: Option<LedgerInfoWithSignatures>,
+        local_version: u64,
+    ) -> Result<ResponseLedgerInfo, Error> {
         let (target_li, highest_li) =
-            self.calculate_target_and_highest_li(request.current_epoch, target_li, local_version)?;
+            self.calculate_target_and_highest_li(request_epoch, request_target_li, local_version)?;
 
-        self.deliver_chunk(
-            peer,
-            request.known_version,
-            ResponseLedgerInfo::ProgressiveLedgerInfo {
-                target_li,
-                highest_li,
-            },
-            chunk_limit,
-        )
+        let target_epoch = target_li.ledger_info().epoch();
+        let local_epoch = self.local_state.committed_epoch();
+        if self.config.enable_epoch_change_proofs && target_epoch < local_epoch {
+            let epoch_change_proof = self
+                .executor_proxy
+                .get_epoch_change_proof(target_epoch, local_epoch)?;
+            // If the proof stops short of the local epoch, the highest verifiable ledger info
+            // is the last one in the proof.
+            let highest_li = if epoch_change_proof.more {
+                epoch_change_proof.ledger_info_with_sigs.last().cloned()
+            } else {
+                Some(self.local_state.committed_ledger_info())
+            };
+            if let Some(highest_li) = highest_li {
+                return Ok(
+                    ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof {
+                        target_li,
+                        highest_li,
+                        epoch_change_proof,
+                    },
+                );
+            }
+        }
+
+        Ok(ResponseLedgerInfo::ProgressiveLedgerInfo {
+            target_li,
+            highest_li,
+        })
     }
 
     fn calculate_target_and_highest_li(
@@ -932,11 +1198,14 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         Ok(target_li)
     }
 
-    /// Applies (i.e., executes and stores) the chunk to storage iff `response` is valid.
+    /// Applies (i.e., executes and stores) the chunk to storage iff `response` is valid. If
+    /// `proofs_verified` is set, the ledger info signatures were already verified on the
+    /// verifier pool against the current trusted epoch.
     fn apply_chunk(
         &mut self,
         peer: &PeerNetworkId,
         response: GetChunkResponse,
+        proofs_verified: bool,
     ) -> Result<(), Error> {
         debug!(
             LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::Received)
@@ -954,9 +1223,14 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         let chunk_size = response.txn_list_with_proof.len() as u64;
         let known_version = self.local_state.synced_version();
         match response.response_li {
-            ResponseLedgerInfo::VerifiableLedgerInfo(li) => {
-                self.process_response_with_target_and_highest(txn_list_with_proof, li, None)
-            }
+            ResponseLedgerInfo::VerifiableLedgerInfo(li) => self
+                .process_response_with_target_and_highest(
+                    txn_list_with_proof,
+                    li,
+                    None,
+                    None,
+                    proofs_verified,
+                ),
             ResponseLedgerInfo::ProgressiveLedgerInfo {
                 target_li,
                 highest_li,
@@ -966,8 +1240,21 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                     txn_list_with_proof,
                     target_li,
                     Some(highest_li),
+                    None,
+                    proofs_verified,
                 )
             }
+            ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof {
+                target_li,
+                highest_li,
+                epoch_change_proof,
+            } => self.process_response_with_target_and_highest(
+                txn_list_with_proof,
+                target_li,
+                Some(highest_li),
+                Some(epoch_change_proof),
+                proofs_verified,
+            ),
             ResponseLedgerInfo::LedgerInfoForWaypoint {
                 waypoint_li,
                 end_of_epoch_li,
@@ -975,10 +1262,19 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 txn_list_with_proof,
                 waypoint_li,
                 end_of_epoch_li,
+                proofs_verified,
             ),
         }
         .map_err(|error| {
-            self.request_manager.process_invalid_chunk(peer);
+            // Transient local failures (e.g., storage hiccups) are not the peer's fault
+            if error.is_retryable() {
+                return error;
+            }
+            if let Error::ProofVerificationFailure(_) = error {
+                self.request_manager.process_invalid_proof(peer);
+            } else {
+                self.request_manager.process_invalid_chunk(peer);
+            }
             Error::ProcessInvalidChunk(error.to_string())
         })?;
 
@@ -1021,12 +1317,315 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         Ok(())
     }
 
+    /// * Buffers the chunk in the given response if it was prefetched and cannot be applied yet.
+    /// * Otherwise, if a verifier pool is configured, verifies the chunk on the pool. The chunk
+    ///   is applied once the verification completes (see `process_verified_chunk`).
+    /// * Otherwise, applies the chunk, followed by any buffered chunks which can then be applied.
+    async fn process_chunk_response(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: GetChunkResponse,
+    ) -> Result<(), Error> {
+        if let Some(halt_version) = self.halted_version() {
+            return Err(Error::SyncHalted(halt_version));
+        }
+
+        let known_version = response
+            .txn_list_with_proof
+            .first_transaction_version
+            .and_then(|version| version.checked_sub(1));
+        if let Some(known_version) = known_version {
+            if let Some(request_time) = self.request_manager.get_last_request_time(known_version) {
+                if let Ok(latency) = SystemTime::now().duration_since(request_time) {
+                    self.prefetcher.observe_request_latency(latency);
+                    self.request_manager.process_response_latency(peer, latency);
+                }
+            }
+            if self.is_early_prefetch_response(peer, known_version) {
+                debug!(LogSchema::event_log(
+                    LogEntry::ProcessChunkResponse,
+                    LogEvent::BufferPrefetchedChunk
+                )
+                .peer(peer)
+                .version(known_version));
+                self.prefetcher
+                    .buffer_chunk(known_version, peer.clone(), response);
+                return Ok(());
+            }
+        }
+
+        if self.chunk_verifier.is_some() {
+            return self.verify_chunk_in_background(peer, response);
+        }
+        self.apply_and_commit_chunk(peer, response, false).await?;
+
+        // Apply the buffered chunks which now follow the synced version. If a chunk fails to
+        // apply, the error is logged and the chunk is requested again.
+        while let Some((peer, response)) = self
+            .prefetcher
+            .take_chunk(self.local_state.synced_version())
+        {
+            if self
+                .apply_and_commit_chunk(&peer, response, false)
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts verifying the proofs of the chunk on the verifier pool, unless the chunk following
+    /// the synced version is already being verified (e.g., the response is a multicast response
+    /// from another peer), in which case the response is dropped.
+    fn verify_chunk_in_background(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: GetChunkResponse,
+    ) -> Result<(), Error> {
+        let synced_version = self.local_state.synced_version();
+        if self.verifying_version == Some(synced_version) {
+            debug!(LogSchema::event_log(
+                LogEntry::ProcessChunkResponse,
+                LogEvent::AlreadyVerifying
+            )
+            .peer(peer)
+            .version(synced_version));
+            return Ok(());
+        }
+
+        // Verify the chunk response is well formed before spending the pool on it
+        self.verify_chunk_response_is_valid(peer, &response)?;
+
+        let chunk_verifier = self
+            .chunk_verifier
+            .as_ref()
+            .ok_or_else(|| Error::UnexpectedError("No verifier pool is configured!".into()))?;
+        self.pending_verifications.push(chunk_verifier.verify(
+            peer.clone(),
+            self.local_state.clone(),
+            response,
+        ));
+        self.verifying_version = Some(synced_version);
+        Ok(())
+    }
+
+    /// Applies a chunk once its proofs are verified on the verifier pool, and starts verifying
+    /// the buffered chunk which then follows the synced version (if any).
+    async fn process_verified_chunk(
+        &mut self,
+        verified_chunk: Option<VerifiedChunk>,
+    ) -> Result<(), Error> {
+        self.verifying_version = None;
+        let VerifiedChunk {
+            peer,
+            response,
+            trusted_epoch,
+            result,
+        } = verified_chunk.ok_or_else(|| {
+            Error::UnexpectedError("The chunk verification stopped before completing!".into())
+        })?;
+
+        if let Err(error) = result {
+            error!(
+                LogSchema::event_log(LogEntry::ProcessChunkResponse, LogEvent::ApplyChunkFail)
+                    .peer(&peer)
+                    .error(&error)
+            );
+            counters::APPLY_CHUNK_COUNT
+                .with_label_values(&[
+                    &peer.raw_network_id().to_string(),
+                    &peer.peer_id().to_string(),
+                    counters::FAIL_LABEL,
+                ])
+                .inc();
+            self.request_manager.process_invalid_proof(&peer);
+            return Err(Error::ProcessInvalidChunk(error.to_string()));
+        }
+
+        // The ledger infos are verified again if the trusted epoch changed during verification
+        let proofs_verified = trusted_epoch == self.local_state.trusted_epoch();
+        self.apply_and_commit_chunk(&peer, response, proofs_verified)
+            .await?;
+
+        if let Some((peer, response)) = self
+            .prefetcher
+            .take_chunk(self.local_state.synced_version())
+        {
+            self.verify_chunk_in_background(&peer, response)?;
+        }
+        Ok(())
+    }
+
+    /// Processes a response to the outstanding fast sync request. Responses which were not
+    /// requested (e.g., late responses after a request timed out) are ignored.
+    async fn process_state_snapshot_response(
+        &mut self,
+        peer: &PeerNetworkId,
+        response: StateSnapshotResponse,
+    ) -> Result<(), Error> {
+        let is_requested = self
+            .fast_sync
+            .as_mut()
+            .map_or(false, |fast_sync| fast_sync.take_pending_request(peer));
+        if !is_requested {
+            debug!(
+                LogSchema::event_log(LogEntry::FastSync, LogEvent::ReceivedChunkWithoutRequest)
+                    .peer(peer)
+            );
+            return Ok(());
+        }
+
+        let result = match response {
+            StateSnapshotResponse::Target(target) => {
+                self.process_state_snapshot_target(peer, target)
+            }
+            StateSnapshotResponse::AccountStates(chunk) => {
+                self.process_account_state_chunk(peer, chunk).await
+            }
+        };
+        if let Err(error) = &result {
+            error!(LogSchema::event_log(LogEntry::FastSync, LogEvent::Fail)
+                .peer(peer)
+                .error(error));
+        }
+        result
+    }
+
+    /// Verifies the target to fast sync to and starts restoring the state snapshot at its
+    /// version.
+    fn process_state_snapshot_target(
+        &mut self,
+        peer: &PeerNetworkId,
+        target: StateSnapshotTarget,
+    ) -> Result<(), Error> {
+        if !self
+            .fast_sync
+            .as_ref()
+            .map_or(false, |fast_sync| fast_sync.is_fetching_target())
+        {
+            self.request_manager.process_invalid_chunk(peer);
+            return Err(Error::ReceivedWrongChunkType(
+                "Received a state snapshot target while fetching account states".into(),
+            ));
+        }
+
+        // If the peer has nothing newer than the local state, there is no snapshot to restore
+        let synced_version = self.local_state.synced_version();
+        if target.version() <= synced_version {
+            info!(
+                LogSchema::event_log(LogEntry::FastSync, LogEvent::Complete)
+                    .peer(peer)
+                    .local_synced_version(synced_version),
+                "No state snapshot ahead of the local state, continuing with chunk-based sync"
+            );
+            self.fast_sync = None;
+            return Ok(());
+        }
+
+        let state_root_hash = match target.verify(&self.local_state, &self.waypoint) {
+            Ok(state_root_hash) => state_root_hash,
+            Err(error) => {
+                self.request_manager.process_invalid_proof(peer);
+                return Err(error);
+            }
+        };
+        self.executor_proxy
+            .start_state_snapshot_restore(target.version(), state_root_hash)?;
+        self.request_manager.process_success_response(peer);
+
+        info!(
+            LogSchema::event_log(LogEntry::FastSync, LogEvent::Initialize)
+                .peer(peer)
+                .version(target.version())
+        );
+        if let Some(fast_sync) = self.fast_sync.as_mut() {
+            fast_sync.fetch_account_states(target);
+        }
+        Ok(())
+    }
+
+    /// Adds a chunk of account states to the state snapshot being restored, and completes fast
+    /// sync once all the accounts are restored.
+    async fn process_account_state_chunk(
+        &mut self,
+        peer: &PeerNetworkId,
+        chunk: AccountStateChunkWithProof,
+    ) -> Result<(), Error> {
+        let target_version = self
+            .fast_sync
+            .as_ref()
+            .and_then(|fast_sync| fast_sync.target_version());
+        if target_version != Some(chunk.version) {
+            self.request_manager.process_invalid_chunk(peer);
+            return Err(Error::ReceivedWrongChunkType(format!(
+                "Received account states at version {} while restoring the snapshot at {:?}",
+                chunk.version, target_version
+            )));
+        }
+
+        // An invalid chunk leaves the restore in an unknown state, so fast sync starts over
+        let next_start_key = fast_sync::next_start_key(&chunk);
+        if !chunk.account_blobs.is_empty() {
+            if let Err(error) = self.executor_proxy.add_state_snapshot_chunk(chunk) {
+                self.request_manager.process_invalid_proof(peer);
+                self.restart_fast_sync();
+                return Err(error);
+            }
+        }
+        self.request_manager.process_success_response(peer);
+
+        match self
+            .fast_sync
+            .as_mut()
+            .and_then(|fast_sync| fast_sync.account_states_restored(next_start_key))
+        {
+            Some(target) => self.finish_fast_sync(target).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Commits the restored state snapshot and hands over to chunk-based sync.
+    async fn finish_fast_sync(&mut self, target: StateSnapshotTarget) -> Result<(), Error> {
+        if let Err(error) = self.executor_proxy.finish_state_snapshot_restore(target) {
+            self.restart_fast_sync();
+            return Err(error);
+        }
+        self.fast_sync = None;
+        self.sync_state_with_local_storage()?;
+
+        let synced_version = self.local_state.synced_version();
+        info!(LogSchema::event_log(LogEntry::FastSync, LogEvent::Complete)
+            .local_li_version(self.local_state.committed_version())
+            .local_synced_version(synced_version)
+            .local_epoch(self.local_state.trusted_epoch()));
+        self.update_sync_state_metrics_and_logs()?;
+        self.check_initialized_or_sync_request_completed(synced_version)
+            .await
+    }
+
+    fn restart_fast_sync(&mut self) {
+        if let Some(fast_sync) = self.fast_sync.as_mut() {
+            fast_sync.restart();
+        }
+    }
+
+    /// Returns true iff the chunk following `known_version` was prefetched from a known peer,
+    /// and cannot be applied yet because it starts beyond the chunk following the synced version.
+    fn is_early_prefetch_response(&self, peer: &PeerNetworkId, known_version: u64) -> bool {
+        known_version > self.local_state.synced_version()
+            && self.request_manager.is_known_state_sync_peer(peer)
+            && self.prefetcher.is_prefetch_response(known_version)
+    }
+
     /// * Verifies, processes and stores the chunk in the given response.
     /// * Triggers post-commit actions based on new local state (after successfully processing a chunk).
-    async fn process_chunk_response(
+    async fn apply_and_commit_chunk(
         &mut self,
         peer: &PeerNetworkId,
         response: GetChunkResponse,
+        proofs_verified: bool,
     ) -> Result<(), Error> {
         // Ensure consensus isn't running, otherwise we might get a race with storage writes.
         if self.is_consensus_executing() {
@@ -1040,10 +1639,32 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         // Verify the chunk response is well formed before trying to process it.
         self.verify_chunk_response_is_valid(peer, &response)?;
 
+        // Drop the transactions beyond the halt version (if any)
+        let response = match self.config.halt_at_version {
+            Some(halt_version) => {
+                let txn_list_with_proof =
+                    truncate_chunk_at_version(response.txn_list_with_proof, halt_version).map_err(
+                        |error| {
+                            self.request_manager.process_invalid_proof(peer);
+                            Error::ProcessInvalidChunk(error.to_string())
+                        },
+                    )?;
+                GetChunkResponse::new(response.response_li, txn_list_with_proof)
+            }
+            None => response,
+        };
+
         // Validate the response and store the chunk if possible.
         // Any errors thrown here should be for detecting bad chunks.
-        match self.apply_chunk(peer, response.clone()) {
+        let execution_start = SystemTime::now();
+        match self.apply_chunk(peer, response.clone(), proofs_verified) {
             Ok(()) => {
+                let now = SystemTime::now();
+                if let Ok(latency) = now.duration_since(execution_start) {
+                    self.prefetcher.observe_execution_latency(latency);
+                }
+                self.progress_tracker
+                    .record_chunk(now, response.txn_list_with_proof.len() as u64);
                 counters::APPLY_CHUNK_COUNT
                     .with_label_values(&[
                         &peer.raw_network_id().to_string(),
@@ -1138,6 +1759,11 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
                 target_li,
                 highest_li,
             } => self.verify_response_with_target_and_highest(target_li, highest_li),
+            ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof {
+                target_li,
+                highest_li,
+                ..
+            } => self.verify_response_with_target_and_highest(target_li, &Some(highest_li.clone())),
         }
     }
 
@@ -1311,6 +1937,10 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         txn_list_with_proof: TransactionListWithProof,
         response_li: LedgerInfoWithSignatures,
         new_highest_li: Option<LedgerInfoWithSignatures>,
+        // Proves the epoch changes up to the epoch of `new_highest_li`, if it is in a later epoch
+        epoch_change_proof: Option<EpochChangeProof>,
+        // Whether the ledger infos were already verified on the verifier pool
+        proofs_verified: bool,
     ) -> Result<(), Error> {
         // Optimistically calculate the new known version and epoch (assume the current chunk
         // is applied successfully).
@@ -1354,10 +1984,20 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
 
         // Validate chunk ledger infos
-        self.local_state.verify_ledger_info(&response_li)?;
-        if let Some(new_highest_li) = new_highest_li.clone() {
-            if new_highest_li != response_li {
-                self.local_state.verify_ledger_info(&new_highest_li)?;
+        if !proofs_verified {
+            self.local_state.verify_ledger_info(&response_li)?;
+            if let Some(new_highest_li) = new_highest_li.clone() {
+                if new_highest_li != response_li {
+                    match &epoch_change_proof {
+                        Some(epoch_change_proof) => self
+                            .local_state
+                            .verify_ledger_info_with_epoch_change_proof(
+                                &new_highest_li,
+                                epoch_change_proof,
+                            )?,
+                        None => self.local_state.verify_ledger_info(&new_highest_li)?,
+                    }
+                }
             }
         }
 
@@ -1395,6 +2035,8 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         txn_list_with_proof: TransactionListWithProof,
         waypoint_li: LedgerInfoWithSignatures,
         end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+        // Whether the end_of_epoch_li was already verified on the verifier pool
+        proofs_verified: bool,
     ) -> Result<(), Error> {
         // Optimistically calculate the new known version and epoch (assume the current chunk
         // is applied successfully).
@@ -1419,7 +2061,9 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         // it is going to execute/commit transactions leading up to that li, so we
         // also verify that the end_of_epoch_li actually ends the epoch.
         let end_of_epoch_li_to_commit = if let Some(end_of_epoch_li) = end_of_epoch_li {
-            self.local_state.verify_ledger_info(&end_of_epoch_li)?;
+            if !proofs_verified {
+                self.local_state.verify_ledger_info(&end_of_epoch_li)?;
+            }
 
             let ledger_info = end_of_epoch_li.ledger_info();
             if !ledger_info.ends_epoch() {
@@ -1442,7 +2086,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         self.waypoint
             .verify(waypoint_li.ledger_info())
             .map_err(|error| {
-                Error::UnexpectedError(format!("Waypoint verification failed: {}", error))
+                Error::ProofVerificationFailure(format!("Waypoint verification failed: {}", error))
             })?;
 
         self.validate_and_store_chunk(txn_list_with_proof, waypoint_li, end_of_epoch_li_to_commit)?;
@@ -1488,9 +2132,17 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
     /// * Kick starts the initial sync process (e.g., syncing to a waypoint or target).
     /// * Issues a new request if too much time has passed since the last request was sent.
     fn check_progress(&mut self) -> Result<(), Error> {
+        self.publish_sync_progress();
+        self.publish_peer_scores();
+        if self.fast_sync.is_some() {
+            return self.check_fast_sync_progress(); // Chunk-based sync starts after fast sync.
+        }
         if self.is_consensus_executing() {
             return Ok(()); // No need to check progress or issue any requests (consensus is running).
         }
+        if self.halted_version().is_some() {
+            return Ok(()); // State sync doesn't sync beyond the halt version.
+        }
 
         // Check if the sync request has timed out (i.e., if we aren't committing fast enough)
         if let Some(sync_request) = self.sync_request.as_ref() {
@@ -1554,6 +2206,38 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
     }
 
+    /// Sends the next fast sync request, unless a request is outstanding and has not yet timed
+    /// out.
+    fn check_fast_sync_progress(&mut self) -> Result<(), Error> {
+        let now = SystemTime::now();
+        let known_epoch = self.local_state.trusted_epoch();
+        let request = match self
+            .fast_sync
+            .as_ref()
+            .and_then(|fast_sync| fast_sync.next_request(known_epoch, now))
+        {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+
+        match self.request_manager.send_state_snapshot_request(request) {
+            Ok(peer) => {
+                if let Some(fast_sync) = self.fast_sync.as_mut() {
+                    fast_sync.request_sent(peer, now);
+                }
+                Ok(())
+            }
+            Err(error) => {
+                error!(
+                    LogSchema::event_log(LogEntry::FastSync, LogEvent::SendChunkRequestFail)
+                        .local_epoch(known_epoch)
+                        .error(&error)
+                );
+                Err(error)
+            }
+        }
+    }
+
     /// Sends a chunk request with a given `known_version`, `known_epoch` and `chunk_target`.
     /// Immediately logs any errors returned by the operation using the given log entry.
     fn send_chunk_request_and_log_error(
@@ -1578,6 +2262,25 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         }
     }
 
+    /// Returns the number of transactions to request after `known_version`. Chunks end at the
+    /// halt version (if any), so nothing is requested once the halt version is synced.
+    fn chunk_limit(&self, known_version: u64) -> u64 {
+        match self.config.halt_at_version {
+            Some(halt_version) => cmp::min(
+                self.config.chunk_limit,
+                halt_version.saturating_sub(known_version),
+            ),
+            None => self.config.chunk_limit,
+        }
+    }
+
+    /// Returns the halt version iff state sync has synced up to it.
+    fn halted_version(&self) -> Option<u64> {
+        self.config
+            .halt_at_version
+            .filter(|halt_version| self.local_state.synced_version() >= *halt_version)
+    }
+
     /// Sends a chunk request with a given `known_version`, `known_epoch` and `target`.
     fn send_chunk_request_with_target(
         &mut self,
@@ -1585,6 +2288,12 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         known_epoch: u64,
         target: TargetType,
     ) -> Result<(), Error> {
+        // Don't request chunks beyond the halt version
+        let chunk_limit = self.chunk_limit(known_version);
+        if chunk_limit == 0 {
+            return Ok(());
+        }
+
         if self.request_manager.no_available_peers() {
             warn!(LogSchema::event_log(
                 LogEntry::SendChunkRequest,
@@ -1600,8 +2309,51 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
             .unwrap_or_else(|| known_version.wrapping_add(1));
         counters::set_version(counters::VersionType::Target, target_version);
 
-        let req = GetChunkRequest::new(known_version, known_epoch, self.config.chunk_limit, target);
-        self.request_manager.send_chunk_request(req)
+        // The next chunk might already have been prefetched
+        if !self.prefetcher.is_pending(known_version, SystemTime::now()) {
+            let req = GetChunkRequest::new(known_version, known_epoch, chunk_limit, target.clone());
+            self.request_manager.send_chunk_request(req)?;
+        }
+        self.prefetch_chunks(known_version, known_epoch, target);
+        Ok(())
+    }
+
+    /// Requests the chunks following the chunk after `known_version` ahead of time. Chunks are
+    /// only prefetched if the target is in the known epoch, so that they can be verified once
+    /// the chunks before them are applied. Errors are only logged, as the chunks are requested
+    /// again when they are next.
+    fn prefetch_chunks(&mut self, known_version: u64, known_epoch: u64, target: TargetType) {
+        let target_version = match (target.epoch(), target.version()) {
+            (Some(target_epoch), Some(target_version)) if target_epoch == known_epoch => {
+                target_version
+            }
+            _ => return,
+        };
+        let target_version = match self.config.halt_at_version {
+            Some(halt_version) => cmp::min(target_version, halt_version),
+            None => target_version,
+        };
+        let versions = self.prefetcher.versions_to_prefetch(
+            known_version,
+            self.config.chunk_limit,
+            target_version,
+        );
+        for version in versions {
+            let req = GetChunkRequest::new(
+                version,
+                known_epoch,
+                self.chunk_limit(version),
+                target.clone(),
+            );
+            if let Err(error) = self.request_manager.send_chunk_request(req) {
+                warn!(
+                    LogSchema::event_log(LogEntry::Prefetch, LogEvent::SendChunkRequestFail)
+                        .version(version)
+                        .local_epoch(known_epoch)
+                        .error(&error)
+                );
+            }
+        }
     }
 
     fn deliver_subscription(
@@ -1610,7 +2362,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         request_info: PendingRequestInfo,
         local_version: u64,
     ) -> Result<(), Error> {
-        let (target_li, highest_li) = self.calculate_target_and_highest_li(
+        let response_li = self.create_progressive_response_li(
             request_info.request_epoch,
             request_info.target_li,
             local_version,
@@ -1619,10 +2371,7 @@ impl<T: ExecutorProxyTrait, M: MempoolNotificationSender> StateSyncCoordinator<T
         self.deliver_chunk(
             peer,
             request_info.known_version,
-            ResponseLedgerInfo::ProgressiveLedgerInfo {
-                target_li,
-                highest_li,
-            },
+            response_li,
             request_info.chunk_limit,
         )
     }
@@ -1725,6 +2474,7 @@ mod tests {
         executor_proxy::ExecutorProxy,
         network::StateSyncMessage,
         shared_components::{test_utils, test_utils::create_coordinator_with_config_and_waypoint},
+        state_snapshot::StateSnapshotResponse,
     };
     use claim::{assert_err, assert_matches, assert_ok};
     use consensus_notifications::{
@@ -1740,11 +2490,13 @@ mod tests {
     };
     use diem_types::{
         account_address::AccountAddress,
+        account_state_blob::AccountStateChunkWithProof,
         block_info::BlockInfo,
         chain_id::ChainId,
         contract_event::ContractEvent,
+        epoch_change::EpochChangeProof,
         ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-        proof::TransactionListProof,
+        proof::{SparseMerkleRangeProof, TransactionListProof},
         transaction::{
             RawTransaction, Script, SignedTransaction, Transaction, TransactionListWithProof,
             TransactionPayload, Version,
@@ -1752,7 +2504,7 @@ mod tests {
         waypoint::Waypoint,
         PeerId,
     };
-    use futures::{channel::oneshot, executor::block_on};
+    use futures::{channel::oneshot, executor::block_on, StreamExt};
     use mempool_notifications::MempoolNotifier;
     use netcore::transport::ConnectionOrigin;
     use network::transport::ConnectionMetadata;
@@ -1799,6 +2551,38 @@ mod tests {
         // modifications in unit tests.
     }
 
+    #[test]
+    fn test_halt_at_version() {
+        // Create a coordinator for a validator which halts at genesis
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::Validator;
+        node_config.state_sync.halt_at_version = Some(0);
+        let mut validator_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config, Waypoint::default());
+
+        // Verify sync requests beyond the halt version are rejected
+        let (sync_request, mut callback_receiver) = create_sync_notification_at_version(10);
+        let process_result = block_on(validator_coordinator.process_sync_request(sync_request));
+        assert_matches!(process_result, Err(Error::SyncHalted(0)));
+        match callback_receiver.try_recv() {
+            Ok(Some(notification_result)) => assert_err!(notification_result.result),
+            result => panic!("Expected an error but got: {:?}", result),
+        };
+
+        // Verify chunk responses are dropped, and that no more chunks are requested
+        let peer_network_id = PeerNetworkId::random_validator();
+        process_new_peer_event(&mut validator_coordinator, &peer_network_id);
+        for chunk_response in create_non_empty_chunk_responses(1) {
+            let result = block_on(validator_coordinator.process_chunk_message(
+                peer_network_id.network_id(),
+                peer_network_id.peer_id(),
+                chunk_response,
+            ));
+            assert_matches!(result, Err(Error::SyncHalted(0)));
+        }
+        assert_eq!(validator_coordinator.chunk_limit(0), 0);
+    }
+
     #[test]
     fn test_get_sync_state() {
         // Create a coordinator for a validator node
@@ -1951,6 +2735,41 @@ mod tests {
         // TODO(joshlind): test that check progress passes when there are valid peers.
     }
 
+    #[test]
+    fn test_fast_sync() {
+        // Verify fast sync is disabled by default
+        let full_node_coordinator = test_utils::create_full_node_coordinator();
+        assert!(full_node_coordinator.fast_sync.is_none());
+
+        // Create a full node coordinator with fast sync enabled
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::FullNode;
+        node_config.state_sync.enable_fast_sync = true;
+        let mut full_node_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config, Waypoint::default());
+        assert!(full_node_coordinator.fast_sync.is_some());
+
+        // Verify the no available peers error is returned when requesting the snapshot target
+        let progress_result = full_node_coordinator.check_progress();
+        assert_matches!(progress_result, Err(Error::NoAvailablePeers(_)));
+
+        // Verify unrequested state snapshot responses are ignored
+        let peer_network_id = PeerNetworkId::random();
+        let chunk = AccountStateChunkWithProof::new(
+            0,
+            vec![],
+            SparseMerkleRangeProof::new(vec![]),
+            HashValue::zero(),
+        );
+        let response = StateSnapshotResponse::AccountStates(chunk);
+        assert_ok!(block_on(full_node_coordinator.process_chunk_message(
+            peer_network_id.network_id(),
+            peer_network_id.peer_id(),
+            StateSyncMessage::StateSnapshotResponse(Box::new(response)),
+        )));
+        assert!(full_node_coordinator.fast_sync.is_some());
+    }
+
     #[test]
     fn test_new_and_lost_peers() {
         // Create a coordinator for a validator node
@@ -2166,6 +2985,66 @@ mod tests {
             &peer_network_id,
             &[highest_response],
         );
+
+        // Verify highest ledger infos which can't be verified with the epoch change proof are
+        // rejected
+        let response_ledger_info = ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof {
+            target_li: create_ledger_info_at_version(100),
+            highest_li: create_ledger_info_at_version(200),
+            epoch_change_proof: EpochChangeProof::new(
+                vec![create_ledger_info_at_version(100)],
+                false,
+            ),
+        };
+        let highest_response = create_chunk_response_message(
+            response_ledger_info,
+            create_dummy_transaction_list_with_proof(1),
+        );
+        verify_all_chunk_responses_are_invalid(
+            &mut full_node_coordinator,
+            &peer_network_id,
+            &[highest_response],
+        );
+    }
+
+    #[test]
+    fn test_process_chunk_response_verifier_pool() {
+        // Create a coordinator for a full node which verifies chunks on a verifier pool
+        let mut node_config = NodeConfig::default();
+        node_config.base.role = RoleType::FullNode;
+        node_config.state_sync.num_verification_threads = 2;
+        let mut full_node_coordinator =
+            create_coordinator_with_config_and_waypoint(node_config, Waypoint::default());
+
+        // Create a peer for the node and add the peer as a known peer
+        let peer_network_id = PeerNetworkId::random_validator();
+        process_new_peer_event(&mut full_node_coordinator, &peer_network_id);
+
+        // Verify a chunk with an invalid ledger info is accepted for verification, and that
+        // another response for the same chunk is dropped while it's being verified
+        let response_ledger_info = ResponseLedgerInfo::ProgressiveLedgerInfo {
+            target_li: create_ledger_info_at_version(100),
+            highest_li: None,
+        };
+        let highest_response = create_chunk_response_message(
+            response_ledger_info,
+            create_dummy_transaction_list_with_proof(1),
+        );
+        for _ in 0..2 {
+            let result = block_on(full_node_coordinator.process_chunk_message(
+                peer_network_id.network_id(),
+                peer_network_id.peer_id(),
+                highest_response.clone(),
+            ));
+            assert_ok!(result);
+        }
+        assert_eq!(full_node_coordinator.pending_verifications.len(), 1);
+
+        // Verify the chunk is rejected once the verification completes
+        let verified_chunk = block_on(full_node_coordinator.pending_verifications.next()).unwrap();
+        let result = block_on(full_node_coordinator.process_verified_chunk(verified_chunk));
+        assert_matches!(result, Err(Error::ProcessInvalidChunk(_)));
+        assert_eq!(full_node_coordinator.verifying_version, None);
     }
 
     #[test]
@@ -17,6 +17,8 @@ pub const SYNC_MSG_LABEL: &str = "sync";
 pub const COMMIT_MSG_LABEL: &str = "commit";
 pub const CHUNK_REQUEST_MSG_LABEL: &str = "chunk_request";
 pub const CHUNK_RESPONSE_MSG_LABEL: &str = "chunk_response";
+pub const STATE_SNAPSHOT_REQUEST_MSG_LABEL: &str = "state_snapshot_request";
+pub const STATE_SNAPSHOT_RESPONSE_MSG_LABEL: &str = "state_snapshot_response";
 
 pub fn set_timestamp(timestamp_type: TimestampType, time_as_usecs: u64) {
     TIMESTAMP
@@ -91,6 +93,18 @@ pub const CONSENSUS_LABEL: &str = "consensus";
 pub const COMPLETE_LABEL: &str = "complete";
 pub const TIMEOUT_LABEL: &str = "timeout";
 
+// invalid chunk reason labels
+pub const INVALID_PROOF_LABEL: &str = "invalid_proof";
+pub const INVALID_PAYLOAD_LABEL: &str = "invalid_payload";
+
+// rate limit direction labels
+pub const INBOUND_LABEL: &str = "inbound";
+pub const OUTBOUND_LABEL: &str = "outbound";
+
+// on-chain config fetch labels
+pub const FULL_FETCH_LABEL: &str = "full";
+pub const PARTIAL_FETCH_LABEL: &str = "partial";
+
 /// Counter of pending network events to State Sync
 pub static PENDING_STATE_SYNC_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -124,6 +138,16 @@ pub static RESPONSES_SENT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of chunk requests dropped by the rate limits (either served or sent)
+pub static RATE_LIMITED_CHUNK_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_state_sync_rate_limited_chunk_requests_total",
+        "Number of chunk requests dropped by the rate limits",
+        &["direction", "network"]
+    )
+    .unwrap()
+});
+
 pub static RESPONSE_FROM_DOWNSTREAM_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "diem_state_sync_responses_from_downstream_total",
@@ -173,6 +197,36 @@ pub static ACTIVE_UPSTREAM_PEERS: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// The score of each upstream peer, which weighs the selection of peers for chunk requests
+pub static PEER_SCORE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "diem_state_sync_peer_score",
+        "Score of an upstream peer used to select the peers to send chunk requests to",
+        &["network", "peer"]
+    )
+    .unwrap()
+});
+
+/// Time from sending a chunk request to receiving the response, per peer
+pub static PEER_RESPONSE_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "diem_state_sync_peer_response_latency_s",
+        "Time from sending a chunk request to a peer to receiving its response",
+        &["network", "peer"]
+    )
+    .unwrap()
+});
+
+/// Number of invalid chunks received from a peer, by reason
+pub static PEER_INVALID_CHUNKS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_state_sync_peer_invalid_chunks_total",
+        "Number of chunks received from a peer which failed verification",
+        &["network", "peer", "reason"]
+    )
+    .unwrap()
+});
+
 /// Highest preference of the networks this node is sending chunk requests to.
 /// It is usually 0 if the node's primary network is healthy, but can be >0 if the node's primary
 /// network is unhealthy/all peers in that network are dead
@@ -185,6 +239,24 @@ pub static MULTICAST_LEVEL: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of chunks currently requested ahead of the next chunk
+pub static PREFETCH_DEPTH: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_prefetch_depth",
+        "Number of chunks state sync requests ahead of the next chunk"
+    )
+    .unwrap()
+});
+
+/// Number of prefetched chunks waiting for the preceding chunks to be applied
+pub static PREFETCHED_CHUNKS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_prefetched_chunks",
+        "Number of prefetched chunks buffered until the preceding chunks are applied"
+    )
+    .unwrap()
+});
+
 pub static TIMESTAMP: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         "diem_state_sync_timestamp",
@@ -269,6 +341,51 @@ pub static EXECUTE_CHUNK_DURATION: Lazy<Histogram> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Time it takes to verify the proofs of a chunk on the verifier pool
+pub static VERIFY_CHUNK_DURATION: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "diem_state_sync_verify_chunk_duration_s",
+        "Histogram of time it takes for state sync's verifier pool to verify the proofs of a chunk"
+    )
+    .unwrap()
+});
+
+/// Size of the executed chunks buffered for the next commit
+pub static COMMIT_BATCH_OUTSTANDING_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_state_sync_commit_batch_outstanding_bytes",
+        "Size (in bytes) of the executed chunks waiting to be committed in a single batch"
+    )
+    .unwrap()
+});
+
+/// Number of chunks committed per storage write batch
+pub static COMMIT_BATCH_CHUNKS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        "diem_state_sync_commit_batch_chunks",
+        "Histogram of the number of chunks state sync commits in a single batch"
+    )
+    .unwrap()
+});
+
+/// Number of commits forced by reaching the outstanding bytes limit
+pub static COMMIT_BATCH_BACKPRESSURE: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_state_sync_commit_batch_backpressure_total",
+        "Number of times state sync committed buffered chunks upon reaching the outstanding bytes limit"
+    )
+    .unwrap()
+});
+
+/// Number of account states restored from a state snapshot
+pub static FAST_SYNC_ACCOUNTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "diem_state_sync_fast_sync_accounts_total",
+        "Number of account states state sync restored from a state snapshot"
+    )
+    .unwrap()
+});
+
 /// Number of times a long-poll subscription is successfully delivered
 pub static SUBSCRIPTION_DELIVERY_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
@@ -316,6 +433,26 @@ pub static RECONFIG_PUBLISH_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static RECONFIG_NOTIFICATION_DROPPED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_state_sync_reconfig_notification_dropped_count",
+        "Number of undelivered reconfig notifications dropped from a full subscription retry queue",
+        &["subscription"]
+    )
+    .unwrap()
+});
+
+/// Number of on-chain config fetches for reconfig notifications, by whether all configs were
+/// fetched or only the configs written since the last fetch
+pub static RECONFIG_CONFIG_FETCH_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "diem_state_sync_reconfig_config_fetch_count",
+        "Number of on-chain config fetches for reconfig notifications",
+        &["fetch"]
+    )
+    .unwrap()
+});
+
 pub static STORAGE_READ_FAIL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
         "diem_state_sync_storage_read_fail_count",
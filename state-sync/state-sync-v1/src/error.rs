@@ -14,6 +14,8 @@ pub enum Error {
     CallbackSendFailed(String),
     #[error("Consensus is executing. There is no need for state sync to drive synchronization.")]
     ConsensusIsExecuting,
+    #[error("Failed to execute transactions: {0}")]
+    ExecutionMismatch(String),
     #[error("A sync request was sent to a full node, but this isn't supported.")]
     FullNodeSyncRequest,
     #[error("An integer overflow has occurred: {0}")]
@@ -34,8 +36,12 @@ pub enum Error {
     NoTransactionsCommitted,
     #[error("Received an old sync request for version {0}, but our known version is: {1}")]
     OldSyncRequestVersion(Version, Version),
+    #[error("The chunk request rate limit is exceeded: {0}")]
+    RateLimited(String),
     #[error("Processed an invalid chunk! Failed to apply the chunk: {0}")]
     ProcessInvalidChunk(String),
+    #[error("Failed to verify a proof: {0}")]
+    ProofVerificationFailure(String),
     #[error(
         "Received a chunk for an outdated request from peer {0}. Known version: {1}, received: {2}"
     )]
@@ -50,14 +56,34 @@ pub enum Error {
     ReceivedWrongChunkType(String),
     #[error("Received a oneshot::canceled event as the sender of a channel was dropped: {0}")]
     SenderDroppedError(String),
+    #[error("Storage is unavailable: {0}")]
+    StorageUnavailable(String),
     #[error("Synced beyond the target version. Synced version: {0}, target version: {1}")]
     SyncedBeyondTarget(Version, Version),
+    #[error("State sync is halted at version {0}, and doesn't sync beyond it")]
+    SyncHalted(Version),
     #[error("State sync is uninitialized! Error: {0}")]
     UninitializedError(String),
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 }
 
+impl Error {
+    /// Returns true iff the error is caused by a transient condition (e.g., a storage or network
+    /// hiccup), so that the failed operation may succeed if retried. Errors which indicate
+    /// invalid data or a divergence from the verified ledger are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Error::ConsensusIsExecuting
+                | Error::NetworkError(_)
+                | Error::NoAvailablePeers(_)
+                | Error::RateLimited(_)
+                | Error::StorageUnavailable(_)
+        )
+    }
+}
+
 impl From<NetworkError> for Error {
     fn from(error: NetworkError) -> Self {
         Error::NetworkError(error.to_string())
@@ -81,3 +107,18 @@ impl From<mempool_notifications::Error> for Error {
         Error::UnexpectedError(error.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(Error::StorageUnavailable("".into()).is_retryable());
+        assert!(Error::NetworkError("".into()).is_retryable());
+        assert!(Error::RateLimited("".into()).is_retryable());
+        assert!(!Error::ProofVerificationFailure("".into()).is_retryable());
+        assert!(!Error::ExecutionMismatch("".into()).is_retryable());
+        assert!(!Error::IntegerOverflow("".into()).is_retryable());
+    }
+}
@@ -5,33 +5,44 @@ use crate::{
     counters,
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
-    shared_components::SyncState,
+    shared_components::{truncate_chunk_at_version, SyncState},
+    state_snapshot::StateSnapshotTarget,
 };
+use diem_config::config::{CustomOnChainConfig, StateSyncConfig};
+use diem_crypto::HashValue;
 use diem_logger::prelude::*;
 use diem_types::{
-    account_state::AccountState,
+    account_state_blob::AccountStateChunkWithProof,
     contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
     ledger_info::LedgerInfoWithSignatures,
     move_resource::MoveStorage,
     on_chain_config,
-    on_chain_config::{config_address, ConfigID, OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY},
-    transaction::TransactionListWithProof,
+    on_chain_config::{ConfigID, OnChainConfigPayload, ON_CHAIN_CONFIG_REGISTRY},
+    proof::AccumulatorConsistencyProof,
+    transaction::{TransactionListWithProof, Version},
 };
-use executor_types::{ChunkExecutor, ExecutedTrees};
+use executor_types::{ChunkExecutor, ChunkOutput, ExecutedTrees};
 use std::{
-    collections::{HashMap, HashSet},
-    convert::TryFrom,
+    collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
 };
-use storage_interface::DbReader;
+use storage_interface::{DbReader, StateSnapshotReceiver};
 use subscription_service::ReconfigSubscription;
 
+/// The maximum number of undelivered reconfig notifications queued per subscription. When a
+/// queue is full, its oldest notification is dropped.
+const MAX_PENDING_RECONFIG_NOTIFICATIONS: usize = 10;
+
 /// Proxies interactions with execution and storage for state synchronization
 pub trait ExecutorProxyTrait: Send {
-    /// Sync the local state with the latest in storage.
+    /// Sync the local state with the latest in storage (including the chunks buffered for the
+    /// next commit).
     fn get_local_storage_state(&self) -> Result<SyncState, Error>;
 
-    /// Execute and commit a batch of transactions
+    /// Execute and commit a batch of transactions. If commit batching is enabled, the commit may
+    /// be deferred to write the batch together with subsequent batches. If a halt version is
+    /// configured, the transactions beyond it are dropped.
     fn execute_chunk(
         &mut self,
         txn_list_with_proof: TransactionListWithProof,
@@ -39,6 +50,19 @@ pub trait ExecutorProxyTrait: Send {
         intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error>;
 
+    /// Execute a batch of transactions without committing them, on top of the batches previously
+    /// executed in dry-run mode, and return the resulting state root. Local storage is left
+    /// untouched, so a batch stream can be validated against a trusted ledger info first.
+    fn dry_run_chunk(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<HashValue, Error>;
+
+    /// Discard the batches executed in dry-run mode.
+    fn reset_dry_run(&mut self);
+
     /// Gets chunk of transactions given the known version, target version and the max limit.
     fn get_chunk(
         &self,
@@ -47,9 +71,26 @@ pub trait ExecutorProxyTrait: Send {
         target_version: u64,
     ) -> Result<TransactionListWithProof, Error>;
 
+    /// Gets a chunk of at most `limit` account states at the given version, starting from
+    /// `start_key`, together with a range proof against the state root at that version.
+    fn get_account_state_chunk(
+        &self,
+        version: u64,
+        start_key: HashValue,
+        limit: u64,
+    ) -> Result<AccountStateChunkWithProof, Error>;
+
     /// Get the epoch changing ledger info for the given epoch so that we can move to next epoch.
     fn get_epoch_change_ledger_info(&self, epoch: u64) -> Result<LedgerInfoWithSignatures, Error>;
 
+    /// Returns the ledger infos ending the epochs in [`start_epoch`, `end_epoch`). The proof may
+    /// stop short of `end_epoch` (in which case `more` is set) to bound its size.
+    fn get_epoch_change_proof(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochChangeProof, Error>;
+
     /// Get ledger info at an epoch boundary version.
     fn get_epoch_ending_ledger_info(&self, version: u64)
         -> Result<LedgerInfoWithSignatures, Error>;
@@ -59,31 +100,109 @@ pub trait ExecutorProxyTrait: Send {
 
     /// publishes on-chain config updates to subscribed components
     fn publish_on_chain_config_updates(&mut self, events: Vec<ContractEvent>) -> Result<(), Error>;
+
+    /// Registers a subscription after startup (e.g., for a component that started late or was
+    /// restarted). The current on-chain configs are published to the subscription immediately,
+    /// and it receives all subsequent reconfig notifications.
+    fn register_reconfig_subscription(
+        &mut self,
+        subscription: ReconfigSubscription,
+    ) -> Result<(), Error>;
+
+    /// Returns the target for a node fast syncing from `known_epoch`: the latest ledger info (or
+    /// the last ledger info of the epoch change proof, if the proof is truncated), together with
+    /// the proofs to verify it and to restore the ledger at its version.
+    fn get_state_snapshot_target(&self, known_epoch: u64) -> Result<StateSnapshotTarget, Error>;
+
+    /// Starts restoring the state snapshot at `version`, which is verified against
+    /// `expected_root_hash` once complete. Any restore in progress is discarded.
+    fn start_state_snapshot_restore(
+        &mut self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<(), Error>;
+
+    /// Adds a chunk of account states to the state snapshot being restored. The chunks must be
+    /// added in order.
+    fn add_state_snapshot_chunk(&mut self, chunk: AccountStateChunkWithProof) -> Result<(), Error>;
+
+    /// Completes the state snapshot being restored and commits the verified target, so that
+    /// syncing continues from the target version. The on-chain configs of the restored state
+    /// are published to all subscriptions.
+    fn finish_state_snapshot_restore(&mut self, target: StateSnapshotTarget) -> Result<(), Error>;
 }
 
 pub(crate) struct ExecutorProxy {
     storage: Arc<dyn DbReader>,
     executor: Box<dyn ChunkExecutor>,
+    config_registry: Vec<ConfigID>,
     reconfig_subscriptions: Vec<ReconfigSubscription>,
+    // Notifications which failed to publish, per subscription, for redelivery on the next update
+    pending_reconfig_notifications: Vec<VecDeque<OnChainConfigPayload>>,
     on_chain_configs: OnChainConfigPayload,
+    // The configs written since the on-chain configs were fetched (None if writes may be missing)
+    config_writes: Option<ConfigWrites>,
+    // The size of executed chunks to buffer before committing them (0 disables batching)
+    max_outstanding_commit_bytes: u64,
+    // The executed chunks which have not yet been committed
+    commit_batch: CommitBatch,
+    // The state snapshot being restored (fast sync), if any
+    state_snapshot_receiver: Option<Box<dyn StateSnapshotReceiver>>,
+    // The version beyond which no transactions are committed, if any
+    halt_at_version: Option<Version>,
+}
+
+/// Tracks the registered configs written by the chunks executed since the on-chain configs were
+/// fetched, so that only the written configs are refetched on the next reconfiguration.
+struct ConfigWrites {
+    // The version following the fetched configs and the chunks executed since
+    next_version: Version,
+    configs: HashSet<ConfigID>,
+}
+
+impl ConfigWrites {
+    fn new(configs_version: Version) -> Self {
+        Self {
+            next_version: configs_version + 1,
+            configs: HashSet::new(),
+        }
+    }
+}
+
+/// Tracks the chunks buffered by the executor for the next commit.
+#[derive(Default)]
+struct CommitBatch {
+    num_chunks: u64,
+    num_bytes: u64,
+    // The events of the buffered chunks, which are published once the chunks are committed
+    events: Vec<ContractEvent>,
 }
 
 impl ExecutorProxy {
     pub(crate) fn new(
         storage: Arc<dyn DbReader>,
         executor: Box<dyn ChunkExecutor>,
+        config: &StateSyncConfig,
         mut reconfig_subscriptions: Vec<ReconfigSubscription>,
     ) -> Self {
+        let config_registry = Self::config_registry(&config.custom_on_chain_configs);
         let on_chain_configs = Self::publish_initial_on_chain_configs(
-            ON_CHAIN_CONFIG_REGISTRY,
+            &config_registry,
             &*storage,
             &mut reconfig_subscriptions,
         );
         Self {
             storage,
             executor,
+            config_registry,
+            pending_reconfig_notifications: vec![VecDeque::new(); reconfig_subscriptions.len()],
             reconfig_subscriptions,
+            config_writes: Some(ConfigWrites::new(on_chain_configs.version())),
             on_chain_configs,
+            max_outstanding_commit_bytes: config.max_outstanding_commit_bytes,
+            commit_batch: CommitBatch::default(),
+            state_snapshot_receiver: None,
+            halt_at_version: config.halt_at_version,
         }
     }
 
@@ -102,8 +221,168 @@ impl ExecutorProxy {
         Self {
             storage,
             executor,
+            config_registry: config_registry.to_vec(),
+            pending_reconfig_notifications: vec![VecDeque::new(); reconfig_subscriptions.len()],
             reconfig_subscriptions,
+            config_writes: Some(ConfigWrites::new(on_chain_configs.version())),
             on_chain_configs,
+            max_outstanding_commit_bytes: 0,
+            commit_batch: CommitBatch::default(),
+            state_snapshot_receiver: None,
+            halt_at_version: None,
+        }
+    }
+
+    /// Returns the built-in on-chain config registry, extended with the given custom configs.
+    /// Custom configs which are already part of the registry are ignored.
+    fn config_registry(custom_configs: &[CustomOnChainConfig]) -> Vec<ConfigID> {
+        let mut config_registry = ON_CHAIN_CONFIG_REGISTRY.to_vec();
+        for custom_config in custom_configs {
+            let config_id = ConfigID::new_custom(&custom_config.address, &custom_config.identifier)
+                .unwrap_or_else(|error| {
+                    panic!(
+                        "[state sync] Invalid custom on-chain config {:?}: {}",
+                        custom_config, error
+                    )
+                });
+            if !config_registry.contains(&config_id) {
+                config_registry.push(config_id);
+            }
+        }
+        config_registry
+    }
+
+    /// Executes the chunk on top of the buffered chunks. The buffered chunks are committed in a
+    /// single batch once a chunk ends at a ledger info, or once their size reaches the outstanding
+    /// bytes limit, which holds back the execution of further chunks until storage catches up.
+    /// Returns the events of the committed chunks (if the buffered chunks were committed).
+    fn execute_and_buffer_chunk(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<Vec<ContractEvent>, Error> {
+        let chunk_bytes = bcs::serialized_size(&txn_list_with_proof).map_err(|error| {
+            Error::UnexpectedError(format!("Failed to compute the chunk size: {}", error))
+        })? as u64;
+        let first_version = txn_list_with_proof.first_transaction_version;
+        let num_transactions = txn_list_with_proof.transactions.len();
+        let result = self.executor.execute_and_buffer_chunk(
+            txn_list_with_proof,
+            verified_target_li,
+            intermediate_end_of_epoch_li,
+        );
+        let chunk_output = result.map_err(|error| {
+            self.reset_commit_batch_if_dropped();
+            Error::ExecutionMismatch(format!("Execute and buffer chunk failed: {}", error))
+        })?;
+        self.commit_batch.num_chunks += 1;
+        self.commit_batch.num_bytes = self.commit_batch.num_bytes.saturating_add(chunk_bytes);
+        let events = self.record_config_writes(first_version, num_transactions, chunk_output);
+        self.commit_batch.events.extend(events);
+
+        if self.executor.buffered_trees().is_some() {
+            if self.commit_batch.num_bytes < self.max_outstanding_commit_bytes {
+                counters::COMMIT_BATCH_OUTSTANDING_BYTES.set(self.commit_batch.num_bytes as i64);
+                return Ok(vec![]);
+            }
+            counters::COMMIT_BATCH_BACKPRESSURE.inc();
+            self.executor.commit_buffered_chunks().map_err(|error| {
+                self.reset_commit_batch_if_dropped();
+                Error::StorageUnavailable(format!("Commit buffered chunks failed: {}", error))
+            })?;
+        }
+
+        counters::COMMIT_BATCH_CHUNKS.observe(self.commit_batch.num_chunks as f64);
+        counters::COMMIT_BATCH_OUTSTANDING_BYTES.set(0);
+        Ok(std::mem::take(&mut self.commit_batch).events)
+    }
+
+    /// Records the registered configs written by the chunk executed at `first_version`, and
+    /// returns the events of the chunk. If the chunk starts beyond the chunks executed since the
+    /// configs were fetched, writes may have been missed (e.g., the transactions in between were
+    /// committed by consensus), so all configs are refetched on the next reconfiguration.
+    fn record_config_writes(
+        &mut self,
+        first_version: Option<Version>,
+        num_transactions: usize,
+        chunk_output: ChunkOutput,
+    ) -> Vec<ContractEvent> {
+        let ChunkOutput {
+            events,
+            written_paths,
+        } = chunk_output;
+        let first_version = match first_version {
+            Some(first_version) => first_version,
+            None => return events,
+        };
+
+        let config_registry = &self.config_registry;
+        self.config_writes = self.config_writes.take().and_then(|mut config_writes| {
+            if first_version > config_writes.next_version {
+                return None;
+            }
+            config_writes.next_version = config_writes
+                .next_version
+                .max(first_version + num_transactions as u64);
+            config_writes.configs.extend(
+                config_registry
+                    .iter()
+                    .filter(|config_id| written_paths.contains(&config_id.access_path())),
+            );
+            Some(config_writes)
+        });
+        events
+    }
+
+    /// Returns the events which end an epoch, or which any subscription is subscribed to.
+    fn extract_subscribed_events(&self, events: Vec<ContractEvent>) -> Vec<ContractEvent> {
+        let new_epoch_event_key = on_chain_config::new_epoch_event_key();
+        let subscribed_event_keys = self
+            .reconfig_subscriptions
+            .iter()
+            .flat_map(|subscription| subscription.subscribed_items().events)
+            .collect::<HashSet<_>>();
+        events
+            .into_iter()
+            .filter(|event| {
+                *event.key() == new_epoch_event_key || subscribed_event_keys.contains(event.key())
+            })
+            .collect()
+    }
+
+    /// Publishes the on-chain configs of a restored state snapshot to all subscriptions, as the
+    /// reconfigurations leading to the snapshot were never executed locally. Notifications which
+    /// fail to publish are redelivered with the next reconfiguration.
+    fn publish_restored_on_chain_configs(&mut self) -> Result<(), Error> {
+        let new_configs = Self::fetch_all_configs(&self.config_registry, &*self.storage)?;
+        for (subscription, pending) in self
+            .reconfig_subscriptions
+            .iter_mut()
+            .zip(self.pending_reconfig_notifications.iter_mut())
+        {
+            if let Err(error) =
+                publish_with_retry_queue(subscription, pending, Some(new_configs.clone()))
+            {
+                error!(
+                    LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError)
+                        .subscription_name(subscription.name.clone())
+                        .error(&error),
+                    "Failed to publish the restored on-chain configs to subscription {}",
+                    subscription.name
+                );
+            }
+        }
+        self.config_writes = Some(ConfigWrites::new(new_configs.version()));
+        self.on_chain_configs = new_configs;
+        Ok(())
+    }
+
+    /// Forgets the buffered chunks if the executor dropped them (e.g., after a failed commit).
+    fn reset_commit_batch_if_dropped(&mut self) {
+        if self.executor.buffered_trees().is_none() {
+            self.commit_batch = CommitBatch::default();
+            counters::COMMIT_BATCH_OUTSTANDING_BYTES.set(0);
         }
     }
 
@@ -124,89 +403,128 @@ impl ExecutorProxy {
         on_chain_configs
     }
 
-    /// Fetches the configs on-chain at the currently synchronized storage version.
-    /// Note: We cannot assume that all configs will exist on-chain. As such, we must fetch
-    /// each resource one at a time. For resources that don't exist, we'll simply log
-    /// an error. Reconfig subscribers must be able to handle on-chain configs not
-    /// existing in a reconfiguration notification.
+    /// Fetches the configs (and the epoch) on-chain at the currently synchronized storage
+    /// version. All reads are made at that version, so a concurrent commit cannot produce a
+    /// payload mixing configs of different versions.
+    /// Note: We cannot assume that all configs will exist on-chain. For resources that don't
+    /// exist, we'll simply log an error. Reconfig subscribers must be able to handle on-chain
+    /// configs not existing in a reconfiguration notification.
     fn fetch_all_configs(
         config_registry: &[ConfigID],
         storage: &dyn DbReader,
     ) -> Result<OnChainConfigPayload, Error> {
-        let synced_version = storage.fetch_synced_version().map_err(|error| {
-            Error::UnexpectedError(format!("Failed to fetch storage synced version: {}", error))
-        })?;
+        let synced_version = Self::fetch_synced_version(storage)?;
+        counters::RECONFIG_CONFIG_FETCH_COUNT
+            .with_label_values(&[counters::FULL_FETCH_LABEL])
+            .inc();
+        let (epoch, config_id_to_config) =
+            Self::fetch_configs(config_registry, storage, synced_version)?;
 
-        // Build a map from config ID to the config value found on-chain
-        let mut config_id_to_config = HashMap::new();
-        for config_id in config_registry.iter() {
-            // TODO(joshlind): refactor the move storage so we aren't batch fetching individual configs...
-            if let Ok(config_list) = storage.batch_fetch_resources(vec![config_id.access_path()]) {
-                match &config_list[..] {
-                    [config] => {
-                        if let Some(old_entry) =
-                            config_id_to_config.insert(*config_id, config.clone())
-                        {
-                            panic!(
-                                "Unexpected config values for duplicate config id found! Key: {}, Value: {:?}!",
-                                config_id, old_entry
-                            );
-                        }
-                    }
-                    _ => {
-                        panic!(
-                            "Expected a single on-chain config, but found: {:?}",
-                            config_list
-                        );
+        // Return the new on-chain config payload (containing all found configs at this version).
+        Ok(OnChainConfigPayload::new(
+            epoch,
+            synced_version,
+            Arc::new(config_id_to_config),
+        ))
+    }
+
+    /// Fetches the on-chain configs for a reconfiguration. Only the configs written by the chunks
+    /// executed since the configs were last fetched are refetched, and the others are taken from
+    /// the last payload. All configs are fetched if the epoch changed, or if writes may have been
+    /// missed.
+    fn fetch_updated_configs(
+        &mut self,
+        events: &[ContractEvent],
+    ) -> Result<OnChainConfigPayload, Error> {
+        let new_epoch_event_key = on_chain_config::new_epoch_event_key();
+        let epoch_ended = events
+            .iter()
+            .any(|event| *event.key() == new_epoch_event_key);
+        let config_writes = self.config_writes.take();
+        let synced_version = Self::fetch_synced_version(&*self.storage)?;
+
+        let new_configs = match config_writes {
+            Some(config_writes)
+                if !epoch_ended && config_writes.next_version == synced_version + 1 =>
+            {
+                let written_configs = config_writes.configs.into_iter().collect::<Vec<_>>();
+                let (epoch, written_config_values) =
+                    Self::fetch_configs(&written_configs, &*self.storage, synced_version)?;
+                if epoch == self.on_chain_configs.epoch() {
+                    counters::RECONFIG_CONFIG_FETCH_COUNT
+                        .with_label_values(&[counters::PARTIAL_FETCH_LABEL])
+                        .inc();
+                    let mut config_id_to_config = self.on_chain_configs.configs().clone();
+                    for config_id in written_configs {
+                        config_id_to_config.remove(&config_id);
                     }
+                    config_id_to_config.extend(written_config_values);
+                    OnChainConfigPayload::new(epoch, synced_version, Arc::new(config_id_to_config))
+                } else {
+                    Self::fetch_all_configs(&self.config_registry, &*self.storage)?
                 }
-            } else {
-                info!(
-                    LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError),
-                    "Failed to fetch on-chain config resource id: {}, at version: {}. Continuing anyway.",
-                   config_id, synced_version
-                );
             }
-        }
+            _ => Self::fetch_all_configs(&self.config_registry, &*self.storage)?,
+        };
+        self.config_writes = Some(ConfigWrites::new(new_configs.version()));
+        Ok(new_configs)
+    }
 
-        // Fetch the current epoch from the configuration resource
-        let account_state_blob = storage
-            .get_account_state_with_proof_by_version(config_address(), synced_version)
+    fn fetch_synced_version(storage: &dyn DbReader) -> Result<Version, Error> {
+        storage.fetch_synced_version().map_err(|error| {
+            Error::StorageUnavailable(format!("Failed to fetch storage synced version: {}", error))
+        })
+    }
+
+    /// Fetches the given configs and the epoch at `version`, returning a map from config ID to
+    /// the config value found on-chain.
+    fn fetch_configs(
+        config_ids: &[ConfigID],
+        storage: &dyn DbReader,
+        version: Version,
+    ) -> Result<(u64, HashMap<ConfigID, Vec<u8>>), Error> {
+        let access_paths = config_ids
+            .iter()
+            .map(|config_id| config_id.access_path())
+            .collect();
+        let (epoch, configs) = storage
+            .fetch_configs_by_version(access_paths, version)
             .map_err(|error| {
-                Error::UnexpectedError(format!(
-                    "Failed to fetch account state with proof {}",
-                    error
+                Error::StorageUnavailable(format!(
+                    "Failed to fetch on-chain configs at version {}: {}",
+                    version, error
                 ))
-            })?
-            .0;
-        let epoch = account_state_blob
-            .map(|blob| {
-                AccountState::try_from(&blob).and_then(|state| {
-                    Ok(state
-                        .get_configuration_resource()?
-                        .ok_or_else(|| {
-                            Error::UnexpectedError("Configuration resource does not exist".into())
-                        })?
-                        .epoch())
-                })
-            })
-            .ok_or_else(|| Error::UnexpectedError("Missing account state blob".into()))?
-            .map_err(|error| {
-                Error::UnexpectedError(format!("Failed to fetch configuration resource: {}", error))
             })?;
 
-        // Return the new on-chain config payload (containing all found configs at this version).
-        Ok(OnChainConfigPayload::new(
-            epoch,
-            Arc::new(config_id_to_config),
-        ))
+        // Build a map from config ID to the config value found on-chain
+        let mut config_id_to_config = HashMap::new();
+        for (config_id, config) in config_ids.iter().zip(configs) {
+            match config {
+                Some(config) => {
+                    if let Some(old_entry) = config_id_to_config.insert(*config_id, config) {
+                        panic!(
+                            "Unexpected config values for duplicate config id found! Key: {}, Value: {:?}!",
+                            config_id, old_entry
+                        );
+                    }
+                }
+                None => {
+                    info!(
+                        LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError),
+                        "Failed to fetch on-chain config resource id: {}, at version: {}. Continuing anyway.",
+                        config_id, version
+                    );
+                }
+            }
+        }
+        Ok((epoch, config_id_to_config))
     }
 }
 
 impl ExecutorProxyTrait for ExecutorProxy {
     fn get_local_storage_state(&self) -> Result<SyncState, Error> {
         let storage_info = self.storage.get_startup_info().map_err(|error| {
-            Error::UnexpectedError(format!(
+            Error::StorageUnavailable(format!(
                 "Failed to get startup info from storage: {}",
                 error
             ))
@@ -215,7 +533,11 @@ impl ExecutorProxyTrait for ExecutorProxy {
             .ok_or_else(|| Error::UnexpectedError("Missing startup info from storage".into()))?;
         let current_epoch_state = storage_info.get_epoch_state().clone();
 
-        let synced_trees = if let Some(synced_tree_state) = storage_info.synced_tree_state {
+        // Chunks buffered for the next commit count as synced, so that they are not requested
+        // again.
+        let synced_trees = if let Some(buffered_trees) = self.executor.buffered_trees() {
+            buffered_trees
+        } else if let Some(synced_tree_state) = storage_info.synced_tree_state {
             ExecutedTrees::from(synced_tree_state)
         } else {
             ExecutedTrees::from(storage_info.committed_tree_state)
@@ -234,21 +556,43 @@ impl ExecutorProxyTrait for ExecutorProxy {
         verified_target_li: LedgerInfoWithSignatures,
         intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
     ) -> Result<(), Error> {
+        // Never commit beyond the halt version. The end of epoch ledger info is dropped if the
+        // chunk no longer reaches it.
+        let (txn_list_with_proof, intermediate_end_of_epoch_li) = match self.halt_at_version {
+            Some(halt_version) => (
+                truncate_chunk_at_version(txn_list_with_proof, halt_version)?,
+                intermediate_end_of_epoch_li
+                    .filter(|li| li.ledger_info().version() <= halt_version),
+            ),
+            None => (txn_list_with_proof, intermediate_end_of_epoch_li),
+        };
+
         // track chunk execution time
         let timer = counters::EXECUTE_CHUNK_DURATION.start_timer();
-        let events = self
-            .executor
-            .execute_and_commit_chunk(
+        let events = if self.max_outstanding_commit_bytes == 0 {
+            let first_version = txn_list_with_proof.first_transaction_version;
+            let num_transactions = txn_list_with_proof.transactions.len();
+            let chunk_output = self
+                .executor
+                .execute_and_commit_chunk(
+                    txn_list_with_proof,
+                    verified_target_li,
+                    intermediate_end_of_epoch_li,
+                )
+                .map_err(|error| {
+                    Error::ExecutionMismatch(format!("Execute and commit chunk failed: {}", error))
+                })?;
+            self.record_config_writes(first_version, num_transactions, chunk_output)
+        } else {
+            self.execute_and_buffer_chunk(
                 txn_list_with_proof,
                 verified_target_li,
                 intermediate_end_of_epoch_li,
-            )
-            .map_err(|error| {
-                Error::UnexpectedError(format!("Execute and commit chunk failed: {}", error))
-            })?;
+            )?
+        };
         timer.stop_and_record();
-        let reconfig_events = extract_reconfig_events(events);
-        if let Err(e) = self.publish_on_chain_config_updates(reconfig_events) {
+        let subscribed_events = self.extract_subscribed_events(events);
+        if let Err(e) = self.publish_on_chain_config_updates(subscribed_events) {
             error!(
                 LogSchema::event_log(LogEntry::Reconfig, LogEvent::Fail).error(&e),
                 "Failed to publish reconfig updates in execute_chunk"
@@ -260,6 +604,29 @@ impl ExecutorProxyTrait for ExecutorProxy {
         Ok(())
     }
 
+    fn dry_run_chunk(
+        &mut self,
+        txn_list_with_proof: TransactionListWithProof,
+        verified_target_li: LedgerInfoWithSignatures,
+        intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<HashValue, Error> {
+        let executed_trees = self
+            .executor
+            .dry_run_chunk(
+                txn_list_with_proof,
+                verified_target_li,
+                intermediate_end_of_epoch_li,
+            )
+            .map_err(|error| {
+                Error::ExecutionMismatch(format!("Dry run of chunk failed: {}", error))
+            })?;
+        Ok(executed_trees.state_root())
+    }
+
+    fn reset_dry_run(&mut self) {
+        self.executor.reset_dry_run();
+    }
+
     fn get_chunk(
         &self,
         known_version: u64,
@@ -272,7 +639,26 @@ impl ExecutorProxyTrait for ExecutorProxy {
         self.storage
             .get_transactions(starting_version, limit, target_version, false)
             .map_err(|error| {
-                Error::UnexpectedError(format!("Failed to get transactions from storage {}", error))
+                Error::StorageUnavailable(format!(
+                    "Failed to get transactions from storage {}",
+                    error
+                ))
+            })
+    }
+
+    fn get_account_state_chunk(
+        &self,
+        version: u64,
+        start_key: HashValue,
+        limit: u64,
+    ) -> Result<AccountStateChunkWithProof, Error> {
+        self.storage
+            .get_account_state_chunk_with_proof(version, start_key, limit)
+            .map_err(|error| {
+                Error::StorageUnavailable(format!(
+                    "Failed to get account state chunk from storage {}",
+                    error
+                ))
             })
     }
 
@@ -283,7 +669,7 @@ impl ExecutorProxyTrait for ExecutorProxy {
         let mut epoch_ending_ledger_infos = self
             .storage
             .get_epoch_ending_ledger_infos(epoch, next_epoch)
-            .map_err(|error| Error::UnexpectedError(error.to_string()))?;
+            .map_err(|error| Error::StorageUnavailable(error.to_string()))?;
 
         epoch_ending_ledger_infos
             .ledger_info_with_sigs
@@ -296,22 +682,53 @@ impl ExecutorProxyTrait for ExecutorProxy {
             })
     }
 
+    fn get_epoch_change_proof(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochChangeProof, Error> {
+        self.storage
+            .get_epoch_ending_ledger_infos(start_epoch, end_epoch)
+            .map_err(|error| Error::StorageUnavailable(error.to_string()))
+    }
+
     fn get_epoch_ending_ledger_info(
         &self,
         version: u64,
     ) -> Result<LedgerInfoWithSignatures, Error> {
         self.storage
             .get_epoch_ending_ledger_info(version)
-            .map_err(|error| Error::UnexpectedError(error.to_string()))
+            .map_err(|error| Error::StorageUnavailable(error.to_string()))
     }
 
     fn get_version_timestamp(&self, version: u64) -> Result<u64, Error> {
         self.storage
             .get_block_timestamp(version)
-            .map_err(|error| Error::UnexpectedError(error.to_string()))
+            .map_err(|error| Error::StorageUnavailable(error.to_string()))
     }
 
     fn publish_on_chain_config_updates(&mut self, events: Vec<ContractEvent>) -> Result<(), Error> {
+        // redeliver notifications which failed to publish earlier
+        for (subscription, pending) in self
+            .reconfig_subscriptions
+            .iter_mut()
+            .zip(self.pending_reconfig_notifications.iter_mut())
+        {
+            if pending.is_empty() {
+                continue;
+            }
+            if let Err(error) = publish_with_retry_queue(subscription, pending, None) {
+                warn!(
+                    LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError)
+                        .subscription_name(subscription.name.clone())
+                        .error(&error),
+                    "Failed to redeliver {} pending reconfig notifications to subscription {}",
+                    pending.len(),
+                    subscription.name
+                );
+            }
+        }
+
         if events.is_empty() {
             return Ok(());
         }
@@ -319,13 +736,8 @@ impl ExecutorProxyTrait for ExecutorProxy {
             .count(events.len())
             .reconfig_events(events.clone()));
 
-        let event_keys = events
-            .iter()
-            .map(|event| *event.key())
-            .collect::<HashSet<_>>();
-
         // calculate deltas
-        let new_configs = Self::fetch_all_configs(ON_CHAIN_CONFIG_REGISTRY, &*self.storage)?;
+        let new_configs = self.fetch_updated_configs(&events)?;
         let changed_configs = new_configs
             .configs()
             .iter()
@@ -345,21 +757,32 @@ impl ExecutorProxyTrait for ExecutorProxy {
 
         // notify subscribers
         let mut publish_success = true;
-        for subscription in self.reconfig_subscriptions.iter_mut() {
+        for (subscription, pending) in self
+            .reconfig_subscriptions
+            .iter_mut()
+            .zip(self.pending_reconfig_notifications.iter_mut())
+        {
             // publish updates if *any* of the subscribed configs changed
             // or any of the subscribed events were emitted
             let subscribed_items = subscription.subscribed_items();
+            let subscribed_events = events
+                .iter()
+                .filter(|event| subscribed_items.events.contains(event.key()))
+                .cloned()
+                .collect::<Vec<_>>();
             if !changed_configs.is_disjoint(&subscribed_items.configs)
-                || !event_keys.is_disjoint(&subscribed_items.events)
+                || !subscribed_events.is_empty()
             {
-                if let Err(e) = subscription.publish(new_configs.clone()) {
+                let payload = new_configs.clone().with_events(subscribed_events);
+                if let Err(error) = publish_with_retry_queue(subscription, pending, Some(payload)) {
                     publish_success = false;
                     error!(
                         LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError)
                             .subscription_name(subscription.name.clone())
-                            .error(&Error::UnexpectedError(e.to_string())),
-                        "Failed to publish reconfig notification to subscription {}",
-                        subscription.name
+                            .error(&error),
+                        "Failed to publish reconfig notification to subscription {}, {} notifications pending",
+                        subscription.name,
+                        pending.len()
                     );
                 } else {
                     info!(
@@ -384,14 +807,183 @@ impl ExecutorProxyTrait for ExecutorProxy {
             ))
         }
     }
+    fn register_reconfig_subscription(
+        &mut self,
+        mut subscription: ReconfigSubscription,
+    ) -> Result<(), Error> {
+        subscription
+            .publish(self.on_chain_configs.clone())
+            .map_err(|error| {
+                Error::UnexpectedError(format!(
+                    "Failed to publish the current on-chain configs to subscription {}: {}",
+                    subscription.name, error
+                ))
+            })?;
+        info!(
+            LogSchema::event_log(LogEntry::Reconfig, LogEvent::Success)
+                .subscription_name(subscription.name.clone()),
+            "Registered reconfig subscription {}", subscription.name
+        );
+
+        self.reconfig_subscriptions.push(subscription);
+        self.pending_reconfig_notifications.push(VecDeque::new());
+        Ok(())
+    }
+
+    fn get_state_snapshot_target(&self, known_epoch: u64) -> Result<StateSnapshotTarget, Error> {
+        let latest_li = self.storage.get_latest_ledger_info().map_err(|error| {
+            Error::StorageUnavailable(format!(
+                "Failed to get the latest ledger info from storage: {}",
+                error
+            ))
+        })?;
+        let latest_epoch = latest_li.ledger_info().epoch();
+        let epoch_change_proof = if known_epoch < latest_epoch {
+            self.get_epoch_change_proof(known_epoch, latest_epoch)?
+        } else {
+            EpochChangeProof::new(vec![], false)
+        };
+        let target_li = match epoch_change_proof.ledger_info_with_sigs.last() {
+            Some(epoch_change_li) if epoch_change_proof.more => epoch_change_li.clone(),
+            _ => latest_li,
+        };
+
+        let version = target_li.ledger_info().version();
+        let accumulator_proof = match version.checked_sub(1) {
+            Some(previous_version) => self
+                .storage
+                .get_accumulator_consistency_proof(None, previous_version)
+                .map_err(|error| {
+                    Error::StorageUnavailable(format!(
+                        "Failed to get the accumulator consistency proof from storage: {}",
+                        error
+                    ))
+                })?,
+            None => AccumulatorConsistencyProof::new(vec![]),
+        };
+        let txn_list_with_proof = self
+            .storage
+            .get_transactions(version, 1, version, true)
+            .map_err(|error| {
+                Error::StorageUnavailable(format!(
+                    "Failed to get transactions from storage {}",
+                    error
+                ))
+            })?;
+
+        Ok(StateSnapshotTarget {
+            epoch_change_proof,
+            target_li,
+            accumulator_proof,
+            txn_list_with_proof,
+        })
+    }
+
+    fn start_state_snapshot_restore(
+        &mut self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<(), Error> {
+        let receiver = self
+            .executor
+            .get_state_snapshot_receiver(version, expected_root_hash)
+            .map_err(|error| {
+                Error::StorageUnavailable(format!(
+                    "Failed to start restoring the state snapshot: {}",
+                    error
+                ))
+            })?;
+        self.state_snapshot_receiver = Some(receiver);
+        Ok(())
+    }
+
+    fn add_state_snapshot_chunk(&mut self, chunk: AccountStateChunkWithProof) -> Result<(), Error> {
+        let receiver = self
+            .state_snapshot_receiver
+            .as_mut()
+            .ok_or_else(|| Error::UnexpectedError("No state snapshot is being restored".into()))?;
+        let num_accounts = chunk.account_blobs.len() as u64;
+        receiver
+            .add_chunk(chunk.account_blobs, chunk.proof)
+            .map_err(|error| {
+                Error::ProofVerificationFailure(format!(
+                    "Failed to add the account state chunk: {}",
+                    error
+                ))
+            })?;
+        counters::FAST_SYNC_ACCOUNTS.inc_by(num_accounts);
+        Ok(())
+    }
+
+    fn finish_state_snapshot_restore(&mut self, target: StateSnapshotTarget) -> Result<(), Error> {
+        let receiver = self
+            .state_snapshot_receiver
+            .take()
+            .ok_or_else(|| Error::UnexpectedError("No state snapshot is being restored".into()))?;
+        receiver.finish().map_err(|error| {
+            Error::ProofVerificationFailure(format!(
+                "Failed to finish restoring the state snapshot: {}",
+                error
+            ))
+        })?;
+        self.executor
+            .finalize_state_snapshot(
+                target.txn_list_with_proof,
+                target.accumulator_proof.into_subtrees(),
+                target.target_li,
+                target.epoch_change_proof.ledger_info_with_sigs,
+            )
+            .map_err(|error| {
+                Error::StorageUnavailable(format!(
+                    "Failed to finalize the state snapshot: {}",
+                    error
+                ))
+            })?;
+
+        // The snapshot is committed at this point, so a failure to publish is only logged
+        if let Err(error) = self.publish_restored_on_chain_configs() {
+            error!(
+                LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError).error(&error),
+                "Failed to publish the restored on-chain configs"
+            );
+        }
+        Ok(())
+    }
 }
 
-fn extract_reconfig_events(events: Vec<ContractEvent>) -> Vec<ContractEvent> {
-    let new_epoch_event_key = on_chain_config::new_epoch_event_key();
-    events
-        .into_iter()
-        .filter(|event| *event.key() == new_epoch_event_key)
-        .collect()
+/// Publishes the pending notifications of the subscription in order, followed by the given
+/// payload. Notifications which cannot be published are kept for redelivery on the next call;
+/// if the queue is full, its oldest notification is dropped. Returns an error if any
+/// notification remains pending.
+fn publish_with_retry_queue(
+    subscription: &mut ReconfigSubscription,
+    pending: &mut VecDeque<OnChainConfigPayload>,
+    payload: Option<OnChainConfigPayload>,
+) -> Result<(), Error> {
+    if let Some(payload) = payload {
+        if pending.len() >= MAX_PENDING_RECONFIG_NOTIFICATIONS {
+            if let Some(dropped) = pending.pop_front() {
+                counters::RECONFIG_NOTIFICATION_DROPPED_COUNT
+                    .with_label_values(&[&subscription.name])
+                    .inc();
+                error!(
+                    LogSchema::event_log(LogEntry::Reconfig, LogEvent::PublishError)
+                        .subscription_name(subscription.name.clone()),
+                    "Dropped undelivered reconfig notification for epoch {} to subscription {}",
+                    dropped.epoch(),
+                    subscription.name
+                );
+            }
+        }
+        pending.push_back(payload);
+    }
+    while let Some(payload) = pending.front() {
+        subscription
+            .publish(payload.clone())
+            .map_err(|error| Error::UnexpectedError(error.to_string()))?;
+        pending.pop_front();
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -410,6 +1002,7 @@ mod tests {
         account_config::{diem_root_address, xus_tag},
         block_metadata::BlockMetadata,
         contract_event::ContractEvent,
+        event::EventKey,
         ledger_info::LedgerInfoWithSignatures,
         on_chain_config::{
             ConsensusConfigV1, DiemVersion, OnChainConfig, OnChainConfigPayload,
@@ -482,6 +1075,77 @@ mod tests {
         assert_err!(executor_proxy.publish_on_chain_config_updates(reconfig_events));
     }
 
+    #[test]
+    fn test_pub_sub_retry_queue() {
+        let subscription_name = "test_pub_sub_retry_queue";
+        let (subscription, mut reconfig_receiver) = ReconfigSubscription::subscribe_all(
+            subscription_name,
+            vec![],
+            vec![on_chain_config::new_epoch_event_key()],
+        );
+        let (validators, mut block_executor, mut executor_proxy) =
+            bootstrap_genesis_and_set_subscription(subscription, &mut reconfig_receiver);
+
+        // Create a dummy prologue transaction that will bump the timer, and update the Diem version
+        let validator_account = validators[0].data.address;
+        let dummy_txn = create_dummy_transaction(1, validator_account);
+        let reconfig_txn = create_new_update_diem_version_transaction(1);
+
+        // Execute and commit the reconfig block
+        let block = vec![dummy_txn, reconfig_txn];
+        let (reconfig_events, _) = execute_and_commit_block(&mut block_executor, block, 1);
+
+        // Drop the reconfig receiver
+        drop(reconfig_receiver);
+
+        // Verify undelivered notifications are queued for redelivery, up to the queue limit
+        let dropped_count = || {
+            counters::RECONFIG_NOTIFICATION_DROPPED_COUNT
+                .with_label_values(&[subscription_name])
+                .get()
+        };
+        for _ in 0..MAX_PENDING_RECONFIG_NOTIFICATIONS {
+            assert_err!(executor_proxy.publish_on_chain_config_updates(reconfig_events.clone()));
+        }
+        assert_eq!(
+            executor_proxy.pending_reconfig_notifications[0].len(),
+            MAX_PENDING_RECONFIG_NOTIFICATIONS
+        );
+        assert_eq!(dropped_count(), 0);
+
+        // Verify the oldest notification is dropped once the queue is full
+        assert_err!(executor_proxy.publish_on_chain_config_updates(reconfig_events));
+        assert_eq!(
+            executor_proxy.pending_reconfig_notifications[0].len(),
+            MAX_PENDING_RECONFIG_NOTIFICATIONS
+        );
+        assert_eq!(dropped_count(), 1);
+
+        // Verify redelivery is attempted without failing updates that have no events
+        assert_ok!(executor_proxy.publish_on_chain_config_updates(vec![]));
+    }
+
+    #[test]
+    fn test_pub_sub_event_subscription() {
+        let event_key = EventKey::random();
+        let (subscription, mut reconfig_receiver) =
+            ReconfigSubscription::subscribe_all("", vec![], vec![event_key]);
+        let (_, _, mut executor_proxy) =
+            bootstrap_genesis_and_set_subscription(subscription, &mut reconfig_receiver);
+
+        // Verify only the subscribed events are extracted from the executed events
+        let subscribed_event = ContractEvent::new(event_key, 0, xus_tag(), vec![1]);
+        let other_event = ContractEvent::new(EventKey::random(), 0, xus_tag(), vec![]);
+        let events =
+            executor_proxy.extract_subscribed_events(vec![other_event, subscribed_event.clone()]);
+        assert_eq!(events, vec![subscribed_event.clone()]);
+
+        // Verify a notification is sent with the subscribed events
+        assert_ok!(executor_proxy.publish_on_chain_config_updates(events));
+        let payload = reconfig_receiver.select_next_some().now_or_never().unwrap();
+        assert_eq!(payload.events(), &[subscribed_event]);
+    }
+
     #[test]
     fn test_pub_sub_multiple_subscriptions() {
         let (subscription, mut reconfig_receiver) = ReconfigSubscription::subscribe_all(
@@ -581,6 +1245,101 @@ mod tests {
         let payload = reconfig_receiver.select_next_some().now_or_never().unwrap();
         let received_config = payload.get::<DiemVersion>().unwrap();
         assert_eq!(received_config, DiemVersion { major: 7 });
+
+        // Verify the configs were read at the version of the reconfig transaction
+        assert_eq!(payload.version(), 2);
+    }
+
+    #[test]
+    fn test_register_subscription_after_startup() {
+        let (subscription, mut reconfig_receiver) =
+            ReconfigSubscription::subscribe_all("", vec![], vec![]);
+        let (validators, mut block_executor, mut executor_proxy) =
+            bootstrap_genesis_and_set_subscription(subscription, &mut reconfig_receiver);
+
+        // Register a new subscription and verify the current configs are published immediately
+        let (late_subscription, mut late_receiver) =
+            ReconfigSubscription::subscribe_all("late", vec![DiemVersion::CONFIG_ID], vec![]);
+        assert_ok!(executor_proxy.register_reconfig_subscription(late_subscription));
+        let payload = late_receiver.select_next_some().now_or_never().unwrap();
+        assert!(payload.get::<DiemVersion>().is_ok());
+        assert_eq!(payload.version(), 0);
+
+        // Execute and commit a block that updates the Diem version
+        let validator_account = validators[0].data.address;
+        let dummy_txn = create_dummy_transaction(1, validator_account);
+        let reconfig_txn = create_new_update_diem_version_transaction(1);
+        let block = vec![dummy_txn, reconfig_txn];
+        let (reconfig_events, _) = execute_and_commit_block(&mut block_executor, block, 1);
+        assert_ok!(executor_proxy.publish_on_chain_config_updates(reconfig_events));
+
+        // Verify the registered subscription receives the reconfig notification
+        let payload = late_receiver.select_next_some().now_or_never().unwrap();
+        let received_config = payload.get::<DiemVersion>().unwrap();
+        assert_eq!(received_config, DiemVersion { major: 7 });
+    }
+
+    #[test]
+    fn test_pub_sub_config_writes() {
+        let (subscription, mut reconfig_receiver) =
+            ReconfigSubscription::subscribe_all("", vec![DiemVersion::CONFIG_ID], vec![]);
+        let (validators, mut block_executor, mut executor_proxy) =
+            bootstrap_genesis_and_set_subscription(subscription, &mut reconfig_receiver);
+        assert_eq!(
+            executor_proxy.config_writes.as_ref().unwrap().next_version,
+            1
+        );
+
+        // Execute and commit a reconfig block that updates the Diem version
+        let validator_account = validators[0].data.address;
+        let dummy_txn = create_dummy_transaction(1, validator_account);
+        let reconfig_txn = create_new_update_diem_version_transaction(1);
+        let block = vec![dummy_txn, reconfig_txn];
+        let (reconfig_events, _) = execute_and_commit_block(&mut block_executor, block, 1);
+
+        // Record the writes of the block as if it was executed in a chunk
+        let chunk_output = ChunkOutput {
+            events: reconfig_events.clone(),
+            written_paths: vec![DiemVersion::CONFIG_ID.access_path()]
+                .into_iter()
+                .collect(),
+        };
+        let events = executor_proxy.record_config_writes(Some(1), 2, chunk_output);
+        assert_eq!(events, reconfig_events);
+        let config_writes = executor_proxy.config_writes.as_ref().unwrap();
+        assert_eq!(config_writes.next_version, 3);
+        assert!(config_writes.configs.contains(&DiemVersion::CONFIG_ID));
+
+        // Verify the new epoch is published, and the written configs are reset
+        assert_ok!(executor_proxy.publish_on_chain_config_updates(reconfig_events));
+        let payload = reconfig_receiver.select_next_some().now_or_never().unwrap();
+        assert_eq!(
+            payload.get::<DiemVersion>().unwrap(),
+            DiemVersion { major: 7 }
+        );
+        let config_writes = executor_proxy.config_writes.as_ref().unwrap();
+        assert_eq!(config_writes.next_version, 3);
+        assert!(config_writes.configs.is_empty());
+
+        // Verify the cached configs are reused if no configs were written
+        let payload = executor_proxy.fetch_updated_configs(&[]).unwrap();
+        assert_eq!(payload, executor_proxy.on_chain_configs);
+
+        // Verify writes are no longer tracked once a chunk skips versions
+        let events = executor_proxy.record_config_writes(Some(5), 1, ChunkOutput::default());
+        assert!(events.is_empty());
+        assert!(executor_proxy.config_writes.is_none());
+
+        // Verify all configs are fetched again, and writes are tracked from the synced version
+        let payload = executor_proxy.fetch_updated_configs(&[]).unwrap();
+        assert_eq!(
+            payload.get::<DiemVersion>().unwrap(),
+            DiemVersion { major: 7 }
+        );
+        assert_eq!(
+            executor_proxy.config_writes.as_ref().unwrap().next_version,
+            3
+        );
     }
 
     #[test]
@@ -777,6 +1536,60 @@ mod tests {
         assert_err!(payload.get::<TestOnChainConfig>());
     }
 
+    #[test]
+    fn test_custom_on_chain_config() {
+        // Create a new subscriber for the custom on-chain test config
+        let (subscription, mut reconfig_receiver) = ReconfigSubscription::subscribe_all(
+            "",
+            vec![DiemVersion::CONFIG_ID, TestOnChainConfig::CONFIG_ID],
+            vec![],
+        );
+
+        // Create a test diem database
+        let db_path = diem_temppath::TempPath::new();
+        db_path.create_as_dir().unwrap();
+        let (db, db_rw) = DbReaderWriter::wrap(DiemDB::new_for_test(db_path.path()));
+
+        // Bootstrap the database with regular genesis
+        let (genesis, _) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+        let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+        assert_ok!(bootstrap_genesis::<DiemVM>(&db_rw, &genesis_txn));
+
+        // Register the test config (and a duplicate of a built-in config) via the node config
+        let mut config = StateSyncConfig::default();
+        config.custom_on_chain_configs = vec![
+            CustomOnChainConfig {
+                address: TestOnChainConfig::ADDRESS.into(),
+                identifier: TestOnChainConfig::IDENTIFIER.into(),
+            },
+            CustomOnChainConfig {
+                address: DiemVersion::ADDRESS.into(),
+                identifier: DiemVersion::IDENTIFIER.into(),
+            },
+        ];
+        let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
+        let executor_proxy = ExecutorProxy::new(db, chunk_executor, &config, vec![subscription]);
+
+        // Verify the registry contains the custom config exactly once, after the built-in ones
+        let mut expected_registry = ON_CHAIN_CONFIG_REGISTRY.to_vec();
+        expected_registry.push(TestOnChainConfig::CONFIG_ID);
+        assert_eq!(executor_proxy.config_registry, expected_registry);
+
+        // Verify the initial payload is published, even though the custom config is missing on-chain
+        let payload = reconfig_receiver.select_next_some().now_or_never().unwrap();
+        assert_ok!(payload.get::<DiemVersion>());
+        assert_err!(payload.get::<TestOnChainConfig>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_custom_on_chain_config() {
+        ExecutorProxy::config_registry(&[CustomOnChainConfig {
+            address: "not an address".into(),
+            identifier: "TestOnChainConfig".into(),
+        }]);
+    }
+
     /// Executes a genesis transaction, creates the executor proxy and sets the given reconfig
     /// subscription.
     fn bootstrap_genesis_and_set_subscription(
@@ -798,7 +1611,12 @@ mod tests {
         // Create executor proxy with given subscription
         let block_executor = Box::new(Executor::<DiemVM>::new(db_rw.clone()));
         let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
-        let executor_proxy = ExecutorProxy::new(db, chunk_executor, vec![subscription]);
+        let executor_proxy = ExecutorProxy::new(
+            db,
+            chunk_executor,
+            &StateSyncConfig::default(),
+            vec![subscription],
+        );
 
         // Verify initial reconfiguration notification is sent
         assert!(
@@ -0,0 +1,273 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::state_snapshot::{StateSnapshotRequest, StateSnapshotTarget};
+use diem_config::config::PeerNetworkId;
+use diem_crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
+use diem_types::{account_state_blob::AccountStateChunkWithProof, transaction::Version};
+use std::time::{Duration, SystemTime};
+
+/// The stages of bootstrapping from a state snapshot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum FastSyncStage {
+    /// Waiting for the ledger info to sync to, with the proofs to verify it.
+    FetchingTarget,
+    /// Restoring the account states at the version of the verified target, in chunks starting
+    /// from `next_start_key`.
+    FetchingAccountStates {
+        target: Box<StateSnapshotTarget>,
+        next_start_key: HashValue,
+    },
+}
+
+/// Bootstraps an empty node from a state snapshot ("fast sync"), instead of executing all the
+/// transactions since genesis. The node first fetches a recent ledger info together with the
+/// proofs of the epoch changes leading to it, and then the account states at the version of the
+/// ledger info, which are restored into storage with proof verification. Once the snapshot is
+/// complete, the coordinator continues with chunk-based sync from the snapshot version.
+///
+/// Requests are sent to one peer at a time, and are sent again (possibly to another peer) if no
+/// response arrives within the request timeout.
+pub struct FastSync {
+    stage: FastSyncStage,
+    // The maximum number of account states to request at once
+    chunk_limit: u64,
+    // The duration after which an unanswered request is sent again
+    request_timeout: Duration,
+    // The peer to which the outstanding request was sent, and the time at which it was sent
+    pending_request: Option<(PeerNetworkId, SystemTime)>,
+}
+
+impl FastSync {
+    pub fn new(chunk_limit: u64, request_timeout: Duration) -> Self {
+        Self {
+            stage: FastSyncStage::FetchingTarget,
+            chunk_limit,
+            request_timeout,
+            pending_request: None,
+        }
+    }
+
+    /// Returns the version of the snapshot being restored, if the target is known.
+    pub fn target_version(&self) -> Option<Version> {
+        match &self.stage {
+            FastSyncStage::FetchingTarget => None,
+            FastSyncStage::FetchingAccountStates { target, .. } => Some(target.version()),
+        }
+    }
+
+    /// Returns the request to send at `now`, unless a request is outstanding and has not yet
+    /// timed out.
+    pub fn next_request(&self, known_epoch: u64, now: SystemTime) -> Option<StateSnapshotRequest> {
+        if let Some((_, request_time)) = &self.pending_request {
+            let timed_out = now
+                .duration_since(*request_time)
+                .map_or(false, |elapsed| elapsed >= self.request_timeout);
+            if !timed_out {
+                return None;
+            }
+        }
+        Some(match &self.stage {
+            FastSyncStage::FetchingTarget => StateSnapshotRequest::Target { known_epoch },
+            FastSyncStage::FetchingAccountStates {
+                target,
+                next_start_key,
+            } => StateSnapshotRequest::AccountStates {
+                version: target.version(),
+                start_key: *next_start_key,
+                limit: self.chunk_limit,
+            },
+        })
+    }
+
+    pub fn request_sent(&mut self, peer: PeerNetworkId, now: SystemTime) {
+        self.pending_request = Some((peer, now));
+    }
+
+    /// Returns true iff a response from the peer answers the outstanding request. The request is
+    /// no longer outstanding afterwards.
+    pub fn take_pending_request(&mut self, peer: &PeerNetworkId) -> bool {
+        match &self.pending_request {
+            Some((pending_peer, _)) if pending_peer == peer => {
+                self.pending_request = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true iff the ledger info to sync to is still to be fetched.
+    pub fn is_fetching_target(&self) -> bool {
+        self.stage == FastSyncStage::FetchingTarget
+    }
+
+    /// Starts restoring the account states at the version of the verified target.
+    pub fn fetch_account_states(&mut self, target: StateSnapshotTarget) {
+        self.stage = FastSyncStage::FetchingAccountStates {
+            target: Box::new(target),
+            next_start_key: HashValue::zero(),
+        };
+    }
+
+    /// Records that the accounts before `next_start_key` are restored. Returns the target once
+    /// all the accounts are restored (i.e., if `next_start_key` is None).
+    pub fn account_states_restored(
+        &mut self,
+        next_start_key: Option<HashValue>,
+    ) -> Option<StateSnapshotTarget> {
+        let start_key = match &mut self.stage {
+            FastSyncStage::FetchingTarget => return None,
+            FastSyncStage::FetchingAccountStates { next_start_key, .. } => next_start_key,
+        };
+        if let Some(next_start_key) = next_start_key {
+            *start_key = next_start_key;
+            return None;
+        }
+        match std::mem::replace(&mut self.stage, FastSyncStage::FetchingTarget) {
+            FastSyncStage::FetchingAccountStates { target, .. } => Some(*target),
+            FastSyncStage::FetchingTarget => None,
+        }
+    }
+
+    /// Starts over by fetching a new target, e.g., after the snapshot failed to restore.
+    pub fn restart(&mut self) {
+        self.stage = FastSyncStage::FetchingTarget;
+        self.pending_request = None;
+    }
+}
+
+/// Returns the key to request the account states following `chunk` from, or None if the chunk
+/// ends with the rightmost account (i.e., the chunk is empty or the proof of its last account
+/// has no siblings to its right).
+pub fn next_start_key(chunk: &AccountStateChunkWithProof) -> Option<HashValue> {
+    let is_last_chunk = chunk
+        .proof
+        .right_siblings()
+        .iter()
+        .all(|sibling| *sibling == *SPARSE_MERKLE_PLACEHOLDER_HASH);
+    if is_last_chunk {
+        None
+    } else {
+        chunk.next_start_key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_types::{
+        account_state_blob::AccountStateBlob,
+        epoch_change::EpochChangeProof,
+        ledger_info::LedgerInfoWithSignatures,
+        on_chain_config::ValidatorSet,
+        proof::{AccumulatorConsistencyProof, SparseMerkleRangeProof},
+        transaction::TransactionListWithProof,
+    };
+
+    const CHUNK_LIMIT: u64 = 100;
+
+    #[test]
+    fn test_request_timeout() {
+        let mut fast_sync = FastSync::new(CHUNK_LIMIT, Duration::from_secs(10));
+        let peer = PeerNetworkId::random_validator();
+        let now = SystemTime::now();
+
+        // Verify the target is requested, and not requested again until the request times out
+        assert_eq!(
+            fast_sync.next_request(1, now),
+            Some(StateSnapshotRequest::Target { known_epoch: 1 })
+        );
+        fast_sync.request_sent(peer.clone(), now);
+        assert_eq!(
+            fast_sync.next_request(1, now + Duration::from_secs(5)),
+            None
+        );
+        assert!(fast_sync
+            .next_request(1, now + Duration::from_secs(10))
+            .is_some());
+
+        // Verify only responses from the requested peer are expected
+        assert!(!fast_sync.take_pending_request(&PeerNetworkId::random_validator()));
+        assert!(fast_sync.take_pending_request(&peer));
+        assert!(!fast_sync.take_pending_request(&peer));
+        assert!(fast_sync.next_request(1, now).is_some());
+    }
+
+    #[test]
+    fn test_account_state_requests() {
+        let mut fast_sync = FastSync::new(CHUNK_LIMIT, Duration::from_secs(10));
+        let target = create_test_target();
+        fast_sync.fetch_account_states(target.clone());
+        assert_eq!(fast_sync.target_version(), Some(0));
+
+        // Verify the account states are requested from the last restored key
+        let now = SystemTime::now();
+        assert_eq!(
+            fast_sync.next_request(1, now),
+            Some(StateSnapshotRequest::AccountStates {
+                version: 0,
+                start_key: HashValue::zero(),
+                limit: CHUNK_LIMIT,
+            })
+        );
+        let next_start_key = HashValue::random();
+        assert_eq!(
+            fast_sync.account_states_restored(Some(next_start_key)),
+            None
+        );
+        assert_eq!(
+            fast_sync.next_request(1, now),
+            Some(StateSnapshotRequest::AccountStates {
+                version: 0,
+                start_key: next_start_key,
+                limit: CHUNK_LIMIT,
+            })
+        );
+
+        // Verify the target is returned once all the accounts are restored
+        assert_eq!(fast_sync.account_states_restored(None), Some(target));
+        assert!(fast_sync.is_fetching_target());
+    }
+
+    #[test]
+    fn test_next_start_key() {
+        let key = HashValue::zero();
+        let account_blobs = vec![(key, AccountStateBlob::from(vec![1]))];
+
+        // Verify the accounts following a chunk are requested from the key after its last key
+        let chunk = AccountStateChunkWithProof::new(
+            0,
+            account_blobs.clone(),
+            SparseMerkleRangeProof::new(vec![HashValue::random()]),
+            HashValue::random(),
+        );
+        let mut next_key = [0; HashValue::LENGTH];
+        next_key[HashValue::LENGTH - 1] = 1;
+        assert_eq!(next_start_key(&chunk), Some(HashValue::new(next_key)));
+
+        // Verify no accounts are requested after the rightmost account or an empty chunk
+        let chunk = AccountStateChunkWithProof::new(
+            0,
+            account_blobs,
+            SparseMerkleRangeProof::new(vec![*SPARSE_MERKLE_PLACEHOLDER_HASH]),
+            HashValue::random(),
+        );
+        assert_eq!(next_start_key(&chunk), None);
+        let chunk = AccountStateChunkWithProof::new(
+            0,
+            vec![],
+            SparseMerkleRangeProof::new(vec![HashValue::random()]),
+            HashValue::random(),
+        );
+        assert_eq!(next_start_key(&chunk), None);
+    }
+
+    fn create_test_target() -> StateSnapshotTarget {
+        StateSnapshotTarget {
+            epoch_change_proof: EpochChangeProof::new(vec![], false),
+            target_li: LedgerInfoWithSignatures::genesis(HashValue::zero(), ValidatorSet::empty()),
+            accumulator_proof: AccumulatorConsistencyProof::new(vec![]),
+            txn_list_with_proof: TransactionListWithProof::new_empty(),
+        }
+    }
+}
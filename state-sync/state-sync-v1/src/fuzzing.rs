@@ -12,7 +12,8 @@ use crate::{
 use diem_config::network_id::{NetworkId, NodeNetworkId};
 use diem_infallible::Mutex;
 use diem_types::{
-    ledger_info::LedgerInfoWithSignatures, transaction::TransactionListWithProof, PeerId,
+    epoch_change::EpochChangeProof, ledger_info::LedgerInfoWithSignatures,
+    transaction::TransactionListWithProof, PeerId,
 };
 use futures::executor::block_on;
 use mempool_notifications::MempoolNotifier;
@@ -134,7 +135,19 @@ impl Arbitrary for ResponseLedgerInfo {
                         waypoint_li,
                         end_of_epoch_li,
                     }
-                },)
+                },),
+            (
+                any::<LedgerInfoWithSignatures>(),
+                any::<LedgerInfoWithSignatures>(),
+                any::<EpochChangeProof>(),
+            )
+                .prop_map(|(target_li, highest_li, epoch_change_proof)| {
+                    ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof {
+                        target_li,
+                        highest_li,
+                        epoch_change_proof,
+                    }
+                })
         ]
         .boxed()
     }
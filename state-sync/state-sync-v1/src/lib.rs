@@ -8,17 +8,23 @@
 #![recursion_limit = "1024"]
 
 pub mod bootstrapper;
+mod chunk_prefetcher;
+mod chunk_rate_limiter;
 pub mod chunk_request;
 pub mod chunk_response;
+mod chunk_verifier;
 pub mod client;
 pub mod coordinator;
 mod counters;
 pub mod error;
 pub mod executor_proxy;
+mod fast_sync;
 mod logging;
 pub mod network;
 mod request_manager;
 pub mod shared_components;
+pub mod state_snapshot;
+mod sync_progress;
 
 #[cfg(any(feature = "fuzzing", test))]
 pub mod fuzzing;
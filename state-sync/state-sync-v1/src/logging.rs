@@ -108,6 +108,8 @@ pub enum LogEntry {
     Multicast,
     SubscriptionDeliveryFail,
     ProgressCheck,
+    Prefetch,
+    FastSync,
 }
 
 #[derive(Clone, Copy, Serialize)]
@@ -127,6 +129,7 @@ pub enum LogEvent {
     NetworkSendError,
     Success,
     ChunkRequestInfo,
+    RateLimited,
 
     // ProcessChunkResponse events
     ReceivedChunkWithoutRequest,
@@ -135,6 +138,8 @@ pub enum LogEvent {
     ApplyChunkFail,
     PostCommitFail,
     OldResponseLI,
+    BufferPrefetchedChunk,
+    AlreadyVerifying,
 
     // ProcessChunkRequest events
     PastEpochRequested,
@@ -2,30 +2,34 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    chunk_rate_limiter::ChunkRateLimiter,
     chunk_request::GetChunkRequest,
     counters,
     error::Error,
     logging::{LogEntry, LogEvent, LogSchema},
     network::{StateSyncMessage, StateSyncSender},
+    state_snapshot::StateSnapshotRequest,
 };
 use diem_config::{
-    config::{PeerNetworkId, PeerRole},
+    config::{ChunkRateLimitConfig, PeerNetworkId, PeerRole},
     network_id::{NetworkId, NodeNetworkId},
 };
 use diem_logger::prelude::*;
 use itertools::Itertools;
 use netcore::transport::ConnectionOrigin;
-use network::transport::ConnectionMetadata;
+use network::{protocols::peer_monitor::PeerMonitoringStorage, transport::ConnectionMetadata};
 use rand::{
     distributions::{Distribution, WeightedIndex},
     thread_rng,
 };
+use serde::Serialize;
 use std::{
     cmp::Ordering,
     collections::{
         hash_map::Entry::{Occupied, Vacant},
         BTreeMap, HashMap,
     },
+    sync::Arc,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
@@ -35,6 +39,18 @@ const MIN_SCORE: f64 = 1.0;
 const STARTING_SCORE: f64 = 50.0;
 const STARTING_SCORE_PREFERRED: f64 = 100.0;
 
+/// A chunk response received later than this fraction of the request timeout is considered slow.
+const SLOW_RESPONSE_TIMEOUT_FRACTION: f64 = 0.5;
+/// The weight of a new observation in the moving average of a peer's response latency.
+const LATENCY_OBSERVATION_WEIGHT: f64 = 0.2;
+/// The factor applied to the score of a peer when picking peers to request a chunk from, if the
+/// peer monitor saw the peer behind the requested version.
+const BEHIND_PEER_SCORE_FACTOR: f64 = 0.1;
+
+/// The name under which the peer scores are published to the metric server, i.e., the scores are
+/// served at `host:port/status/state_sync_peers`.
+pub const PEER_SCORES_COMPONENT: &str = "state_sync_peers";
+
 /// Basic metadata about the chunk request.
 #[derive(Clone, Debug)]
 pub struct ChunkRequestInfo {
@@ -69,12 +85,39 @@ enum PeerScoreUpdateType {
     ChunkVersionCannotBeApplied,
     InvalidChunk,
     InvalidChunkRequest,
+    // The ledger infos of a received chunk failed verification.
+    InvalidProof,
+    // A chunk response arrived, but only after a large part of the request timeout.
+    SlowResponse,
     TimeOut,
 }
 
+/// Statistics about the quality of the chunk responses received from a peer.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PeerChunkStats {
+    /// The moving average of the time from sending a chunk request to receiving the response
+    pub average_latency_ms: Option<f64>,
+    pub successful_chunks: u64,
+    /// Chunks whose ledger infos (or epoch change proofs) failed verification
+    pub invalid_proofs: u64,
+    /// Chunks which failed to verify or execute against their (verified) ledger infos
+    pub invalid_payloads: u64,
+}
+
+/// The score and chunk statistics of a peer, as published at the inspection endpoint.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PeerQuality {
+    pub peer: String,
+    pub score: f64,
+    #[serde(flatten)]
+    pub stats: PeerChunkStats,
+}
+
 pub struct RequestManager {
     // Maps each peer to their peer score
     peer_scores: HashMap<PeerNetworkId, f64>,
+    // Maps each peer to the quality statistics of their chunk responses
+    peer_stats: HashMap<PeerNetworkId, PeerChunkStats>,
     requests: BTreeMap<u64, ChunkRequestInfo>,
     // duration with the same version before the next attempt to get the next chunk
     request_timeout: Duration,
@@ -85,6 +128,10 @@ pub struct RequestManager {
     // NetworkId.
     multicast_network_level: NetworkId,
     network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+    // The synced versions of the peers, as reported to their peer monitors
+    peer_monitoring_storage: Arc<PeerMonitoringStorage>,
+    // Limits the rate of the chunk requests sent to each network
+    rate_limiter: ChunkRateLimiter,
 }
 
 impl RequestManager {
@@ -92,17 +139,22 @@ impl RequestManager {
         request_timeout: Duration,
         multicast_timeout: Duration,
         network_senders: HashMap<NodeNetworkId, StateSyncSender>,
+        peer_monitoring_storage: Arc<PeerMonitoringStorage>,
+        rate_limit_config: Option<ChunkRateLimitConfig>,
     ) -> Self {
         let multicast_network_level = NetworkId::Validator;
         update_multicast_network_counter(multicast_network_level.clone());
 
         Self {
             peer_scores: HashMap::new(),
+            peer_stats: HashMap::new(),
             requests: BTreeMap::new(),
             request_timeout,
             multicast_timeout,
             multicast_network_level,
             network_senders,
+            peer_monitoring_storage,
+            rate_limiter: ChunkRateLimiter::new(counters::OUTBOUND_LABEL, rate_limit_config),
         }
     }
 
@@ -135,6 +187,9 @@ impl RequestManager {
                 } else {
                     STARTING_SCORE
                 };
+                update_peer_score_counter(vacant_entry.key(), peer_score);
+                self.peer_stats
+                    .insert(vacant_entry.key().clone(), PeerChunkStats::default());
                 vacant_entry.insert(peer_score);
             }
         }
@@ -150,6 +205,11 @@ impl RequestManager {
                 .with_label_values(&[&peer.raw_network_id().to_string()])
                 .dec();
             self.peer_scores.remove(peer);
+            self.peer_stats.remove(peer);
+            let _ = counters::PEER_SCORE.remove_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+            ]);
         } else {
             warn!(LogSchema::new(LogEntry::LostPeerNotKnown).peer(peer));
         }
@@ -169,12 +229,17 @@ impl RequestManager {
                     let new_score = old_score + 1.0;
                     new_score.min(MAX_SCORE)
                 }
+                PeerScoreUpdateType::InvalidProof => {
+                    let new_score = old_score * 0.5;
+                    new_score.max(MIN_SCORE)
+                }
                 PeerScoreUpdateType::InvalidChunk
                 | PeerScoreUpdateType::ChunkVersionCannotBeApplied => {
                     let new_score = old_score * 0.8;
                     new_score.max(MIN_SCORE)
                 }
                 PeerScoreUpdateType::TimeOut
+                | PeerScoreUpdateType::SlowResponse
                 | PeerScoreUpdateType::EmptyChunk
                 | PeerScoreUpdateType::InvalidChunkRequest => {
                     let new_score = old_score * 0.95;
@@ -182,6 +247,38 @@ impl RequestManager {
                 }
             };
             *score = new_score;
+            update_peer_score_counter(peer, new_score);
+        }
+    }
+
+    /// Returns the score and chunk statistics of every known peer, from the highest score to
+    /// the lowest.
+    pub fn peer_qualities(&self) -> Vec<PeerQuality> {
+        let mut qualities = self
+            .peer_scores
+            .iter()
+            .map(|(peer, score)| PeerQuality {
+                peer: peer.to_string(),
+                score: *score,
+                stats: self.peer_stats.get(peer).cloned().unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+        qualities.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        qualities
+    }
+
+    // Returns the weight of a peer when picking peers to request the chunk following
+    // `known_version` from: its score, reduced if the peer monitor saw it without that chunk.
+    fn peer_weight(&self, peer: &PeerNetworkId, score: f64, known_version: Option<u64>) -> f64 {
+        let synced_version = self
+            .peer_monitoring_storage
+            .read(&(peer.raw_network_id(), peer.peer_id()))
+            .and_then(|metadata| metadata.synced_version());
+        match (synced_version, known_version) {
+            (Some(synced_version), Some(known_version)) if synced_version <= known_version => {
+                score * BEHIND_PEER_SCORE_FACTOR
+            }
+            _ => score,
         }
     }
 
@@ -189,6 +286,7 @@ impl RequestManager {
     // select a peer (per network) to send a chunk request to.
     fn calculate_weighted_peers_per_network(
         &mut self,
+        known_version: Option<u64>,
     ) -> BTreeMap<NetworkId, (Vec<PeerNetworkId>, Option<WeightedIndex<f64>>)> {
         // Group peers by network level
         let peers_by_network_level = self
@@ -206,7 +304,7 @@ impl RequestManager {
                     .iter()
                     .map(|(peer, peer_score)| {
                         eligible_peers.push((*peer).clone());
-                        *peer_score
+                        self.peer_weight(peer, **peer_score, known_version)
                     })
                     .collect();
                 let weighted_index = WeightedIndex::new(weights)
@@ -228,9 +326,10 @@ impl RequestManager {
     /// determined by the multicast network level. All networks with preference
     /// level <= multicast level are sampled. If there are no live peers in these networks,
     /// the multicast level is updated to the preference level of the first chosen network.
-    fn pick_peers(&mut self) -> Vec<PeerNetworkId> {
+    /// Peers known to be behind `known_version` are less likely to be picked.
+    fn pick_peers(&mut self, known_version: Option<u64>) -> Vec<PeerNetworkId> {
         // Calculate a weighted peer selection map per network level
-        let weighted_peers_per_network = self.calculate_weighted_peers_per_network();
+        let weighted_peers_per_network = self.calculate_weighted_peers_per_network(known_version);
 
         let mut chosen_peers = vec![];
         let mut new_multicast_network_level = None;
@@ -258,7 +357,7 @@ impl RequestManager {
     pub fn send_chunk_request(&mut self, req: GetChunkRequest) -> Result<(), Error> {
         let log = LogSchema::new(LogEntry::SendChunkRequest).chunk_request(req.clone());
 
-        let peers = self.pick_peers();
+        let mut peers = self.pick_peers(Some(req.known_version));
         if peers.is_empty() {
             warn!(log.event(LogEvent::MissingPeers));
             return Err(Error::NoAvailablePeers(
@@ -266,6 +365,16 @@ impl RequestManager {
             ));
         }
 
+        // Only send the request to the networks with a remaining request budget
+        let rate_limiter = &mut self.rate_limiter;
+        peers.retain(|peer| rate_limiter.try_acquire(&peer.raw_network_id()));
+        if peers.is_empty() {
+            debug!(log.event(LogEvent::RateLimited));
+            return Err(Error::RateLimited(
+                "No request budget left to send chunk request".into(),
+            ));
+        }
+
         let req_info = self.add_request(req.known_version, peers.clone());
         debug!(log
             .clone()
@@ -307,6 +416,42 @@ impl RequestManager {
         }
     }
 
+    /// Sends a state snapshot request to a single peer (picked as for chunk requests) and returns
+    /// the peer. State snapshot requests are not tracked by version, so timeouts are left to the
+    /// caller.
+    pub fn send_state_snapshot_request(
+        &mut self,
+        req: StateSnapshotRequest,
+    ) -> Result<PeerNetworkId, Error> {
+        let peer = self.pick_peers(None).into_iter().next().ok_or_else(|| {
+            Error::NoAvailablePeers("No peers to send state snapshot request to".into())
+        })?;
+        if !self.rate_limiter.try_acquire(&peer.raw_network_id()) {
+            return Err(Error::RateLimited(
+                "No request budget left to send state snapshot request".into(),
+            ));
+        }
+
+        let peer_id = peer.peer_id();
+        let send_result = self.get_network_sender(&peer).send_to(
+            peer_id,
+            StateSyncMessage::StateSnapshotRequest(Box::new(req)),
+        );
+        let result_label = if send_result.is_err() {
+            counters::SEND_FAIL_LABEL
+        } else {
+            counters::SEND_SUCCESS_LABEL
+        };
+        counters::REQUESTS_SENT
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer_id.to_string(),
+                result_label,
+            ])
+            .inc();
+        send_result.map(|_| peer)
+    }
+
     fn get_network_sender(&mut self, peer: &PeerNetworkId) -> StateSyncSender {
         self.network_senders
             .get_mut(&peer.network_id())
@@ -356,9 +501,62 @@ impl RequestManager {
     }
 
     pub fn process_invalid_chunk(&mut self, peer: &PeerNetworkId) {
+        if let Some(stats) = self.peer_stats.get_mut(peer) {
+            stats.invalid_payloads += 1;
+        }
+        counters::PEER_INVALID_CHUNKS
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+                counters::INVALID_PAYLOAD_LABEL,
+            ])
+            .inc();
         self.update_score(peer, PeerScoreUpdateType::InvalidChunk);
     }
 
+    pub fn process_invalid_proof(&mut self, peer: &PeerNetworkId) {
+        if let Some(stats) = self.peer_stats.get_mut(peer) {
+            stats.invalid_proofs += 1;
+        }
+        counters::PEER_INVALID_CHUNKS
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+                counters::INVALID_PROOF_LABEL,
+            ])
+            .inc();
+        self.update_score(peer, PeerScoreUpdateType::InvalidProof);
+    }
+
+    /// Records the time the peer took to respond to a chunk request. Responses which take more
+    /// than a fraction of the request timeout lower the peer's score.
+    pub fn process_response_latency(&mut self, peer: &PeerNetworkId, latency: Duration) {
+        let stats = match self.peer_stats.get_mut(peer) {
+            Some(stats) => stats,
+            None => return,
+        };
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        stats.average_latency_ms = Some(match stats.average_latency_ms {
+            Some(average) => {
+                average * (1.0 - LATENCY_OBSERVATION_WEIGHT)
+                    + latency_ms * LATENCY_OBSERVATION_WEIGHT
+            }
+            None => latency_ms,
+        });
+        counters::PEER_RESPONSE_LATENCY
+            .with_label_values(&[
+                &peer.raw_network_id().to_string(),
+                &peer.peer_id().to_string(),
+            ])
+            .observe(latency.as_secs_f64());
+
+        if latency.as_secs_f64()
+            > self.request_timeout.as_secs_f64() * SLOW_RESPONSE_TIMEOUT_FRACTION
+        {
+            self.update_score(peer, PeerScoreUpdateType::SlowResponse);
+        }
+    }
+
     pub fn process_invalid_chunk_request(&mut self, peer: &PeerNetworkId) {
         self.update_score(peer, PeerScoreUpdateType::InvalidChunkRequest);
     }
@@ -373,6 +571,9 @@ impl RequestManager {
         }
 
         // Update the peer's score
+        if let Some(stats) = self.peer_stats.get_mut(peer) {
+            stats.successful_chunks += 1;
+        }
         self.update_score(peer, PeerScoreUpdateType::Success);
     }
 
@@ -553,6 +754,15 @@ fn pick_peer(
 // by NetworkId and not here. Look into updating the NetworkId interface to expose this
 // conversion, as well as provide clearer interfaces around max, min network values as
 // well as moving between network levels.
+fn update_peer_score_counter(peer: &PeerNetworkId, score: f64) {
+    counters::PEER_SCORE
+        .with_label_values(&[
+            &peer.raw_network_id().to_string(),
+            &peer.peer_id().to_string(),
+        ])
+        .set(score.round() as i64);
+}
+
 fn update_multicast_network_counter(multicast_network_level: NetworkId) {
     let network_counter_value = if multicast_network_level.is_validator_network() {
         0
@@ -567,6 +777,8 @@ fn update_multicast_network_counter(multicast_network_level: NetworkId) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use diem_config::config::RoleType;
+    use network::protocols::peer_monitor::{NodeStatus, PeerMonitoringMetadata};
 
     const NUM_CHUNKS_TO_PROCESS: u64 = 50;
     const NUM_PICKS_TO_MAKE: u64 = 1000;
@@ -704,6 +916,69 @@ mod tests {
         verify_validator_picked_least_often(&mut request_manager, &validators, 0);
     }
 
+    #[test]
+    fn test_score_invalid_proof() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(10, 4);
+
+        // Process multiple chunk responses with invalid proofs from validator 0
+        for _ in 0..NUM_CHUNKS_TO_PROCESS {
+            request_manager.process_invalid_proof(&validators[0]);
+        }
+
+        // Verify validator 0 is chosen less often than the other validators
+        verify_validator_picked_least_often(&mut request_manager, &validators, 0);
+    }
+
+    #[test]
+    fn test_score_slow_response() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(10, 4);
+
+        // Process slow chunk responses from validator 0, and fast ones from the others
+        for _ in 0..NUM_CHUNKS_TO_PROCESS {
+            for (validator_index, validator) in validators.iter().enumerate() {
+                let latency = if validator_index == 0 {
+                    Duration::from_secs(8)
+                } else {
+                    Duration::from_secs(1)
+                };
+                request_manager.process_response_latency(validator, latency);
+                request_manager.process_success_response(validator);
+            }
+        }
+
+        // Verify validator 0 is chosen less often than the other validators
+        verify_validator_picked_least_often(&mut request_manager, &validators, 0);
+    }
+
+    #[test]
+    fn test_peer_qualities() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(10, 2);
+
+        // Process a successful response from validator 0, and invalid ones from validator 1
+        request_manager.process_response_latency(&validators[0], Duration::from_millis(100));
+        request_manager.process_response_latency(&validators[0], Duration::from_millis(200));
+        request_manager.process_success_response(&validators[0]);
+        request_manager.process_invalid_proof(&validators[1]);
+        request_manager.process_invalid_chunk(&validators[1]);
+
+        // Verify the peers are reported from the highest score to the lowest
+        let qualities = request_manager.peer_qualities();
+        assert_eq!(qualities.len(), 2);
+        assert_eq!(qualities[0].peer, validators[0].to_string());
+        assert_eq!(qualities[0].score, STARTING_SCORE + 1.0);
+        assert_eq!(qualities[0].stats.successful_chunks, 1);
+        let average_latency_ms = qualities[0].stats.average_latency_ms.unwrap();
+        assert!((average_latency_ms - 120.0).abs() < 1e-6);
+        assert_eq!(qualities[1].peer, validators[1].to_string());
+        assert_eq!(qualities[1].score, STARTING_SCORE * 0.5 * 0.8);
+        assert_eq!(qualities[1].stats.invalid_proofs, 1);
+        assert_eq!(qualities[1].stats.invalid_payloads, 1);
+
+        // Verify disabled peers are no longer reported
+        request_manager.disable_peer(&validators[1]).unwrap();
+        assert_eq!(request_manager.peer_qualities().len(), 1);
+    }
+
     #[test]
     fn test_score_invalid_chunk_request() {
         let (mut request_manager, validators) = generate_request_manager_and_validators(10, 4);
@@ -753,6 +1028,37 @@ mod tests {
         verify_validator_picked_most_often(&mut request_manager, &validators, 0);
     }
 
+    #[test]
+    fn test_pick_peers_behind() {
+        let (mut request_manager, validators) = generate_request_manager_and_validators(0, 4);
+
+        // Report validator 0 as synced to version 10, and the others as synced to version 20
+        for (index, validator) in validators.iter().enumerate() {
+            let synced_version = if index == 0 { 10 } else { 20 };
+            request_manager.peer_monitoring_storage.insert(
+                (validator.raw_network_id(), validator.peer_id()),
+                PeerMonitoringMetadata {
+                    status: Some(NodeStatus {
+                        role: RoleType::Validator,
+                        synced_version: Some(synced_version),
+                    }),
+                    ..PeerMonitoringMetadata::default()
+                },
+            );
+        }
+
+        // Verify validator 0 is chosen least often when requesting the chunk after version 10
+        let mut pick_counts = HashMap::new();
+        for _ in 0..NUM_PICKS_TO_MAKE {
+            let picked_peer = request_manager.pick_peers(Some(10))[0].clone();
+            *pick_counts.entry(picked_peer).or_insert(0) += 1;
+        }
+        let validator_0_count = pick_counts.get(&validators[0]).unwrap_or(&0);
+        for validator in &validators[1..] {
+            assert!(validator_0_count < pick_counts.get(validator).unwrap());
+        }
+    }
+
     #[test]
     fn test_remove_requests() {
         let (mut request_manager, validators) = generate_request_manager_and_validators(0, 2);
@@ -850,7 +1156,7 @@ mod tests {
         let mut pick_counts = HashMap::new();
 
         for _ in 0..number_of_picks_to_execute {
-            let picked_peers = request_manager.pick_peers();
+            let picked_peers = request_manager.pick_peers(None);
             assert_eq!(1, picked_peers.len()); // Ensure only one validator per multicast level
 
             let picked_peer = picked_peers[0].clone();
@@ -867,6 +1173,8 @@ mod tests {
             Duration::from_secs(request_timeout),
             Duration::from_secs(30),
             HashMap::new(),
+            Arc::new(PeerMonitoringStorage::new()),
+            None,
         )
     }
 
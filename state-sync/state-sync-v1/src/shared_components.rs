@@ -3,7 +3,10 @@
 
 use crate::error::Error;
 use diem_types::{
-    epoch_change::Verifier, epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures,
+    epoch_change::{EpochChangeProof, Verifier},
+    epoch_state::EpochState,
+    ledger_info::LedgerInfoWithSignatures,
+    transaction::{TransactionListWithProof, Version},
 };
 use executor_types::ExecutedTrees;
 
@@ -71,8 +74,55 @@ impl SyncState {
     pub fn verify_ledger_info(&self, ledger_info: &LedgerInfoWithSignatures) -> Result<(), Error> {
         self.trusted_epoch_state
             .verify(ledger_info)
-            .map_err(|error| Error::UnexpectedError(error.to_string()))
+            .map_err(|error| Error::ProofVerificationFailure(error.to_string()))
     }
+
+    /// Verifies a ledger info from a later epoch than the trusted epoch, using a proof of the
+    /// epoch changes from the trusted epoch up to the epoch of the ledger info.
+    pub fn verify_ledger_info_with_epoch_change_proof(
+        &self,
+        ledger_info: &LedgerInfoWithSignatures,
+        epoch_change_proof: &EpochChangeProof,
+    ) -> Result<(), Error> {
+        let epoch_change_li = epoch_change_proof
+            .verify(&self.trusted_epoch_state)
+            .map_err(|error| Error::ProofVerificationFailure(error.to_string()))?;
+        if epoch_change_li == ledger_info {
+            return Ok(());
+        }
+        epoch_change_li
+            .ledger_info()
+            .next_epoch_state()
+            .ok_or_else(|| {
+                Error::ProofVerificationFailure("Epoch change proof doesn't end an epoch!".into())
+            })?
+            .verify(ledger_info)
+            .map_err(|error| Error::ProofVerificationFailure(error.to_string()))
+    }
+}
+
+/// Truncates the chunk so that it ends at `halt_version`, i.e., drops the transactions beyond the
+/// version at which state sync halts. Returns an error if the chunk starts beyond the version.
+pub fn truncate_chunk_at_version(
+    txn_list_with_proof: TransactionListWithProof,
+    halt_version: Version,
+) -> Result<TransactionListWithProof, Error> {
+    let first_version = match txn_list_with_proof.first_transaction_version {
+        Some(first_version) => first_version,
+        None => return Ok(txn_list_with_proof),
+    };
+    if first_version > halt_version {
+        return Err(Error::SyncHalted(halt_version));
+    }
+    let num_transactions = (halt_version - first_version).saturating_add(1);
+    if num_transactions >= txn_list_with_proof.len() as u64 {
+        return Ok(txn_list_with_proof);
+    }
+    txn_list_with_proof
+        .truncate(num_transactions as usize)
+        .map_err(|error| {
+            Error::ProofVerificationFailure(format!("Failed to truncate the chunk: {}", error))
+        })
 }
 
 #[cfg(any(feature = "fuzzing", test))]
@@ -98,9 +148,9 @@ pub(crate) mod test_utils {
     use mempool_notifications::MempoolNotifier;
     use network::{
         peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
-        protocols::network::NewNetworkSender,
+        protocols::{network::NewNetworkSender, peer_monitor::PeerMonitoringStorage},
     };
-    use std::collections::HashMap;
+    use std::{collections::HashMap, sync::Arc};
     use storage_interface::DbReaderWriter;
 
     #[cfg(test)]
@@ -146,7 +196,8 @@ pub(crate) mod test_utils {
 
         // Create executor proxy
         let chunk_executor = Box::new(Executor::<DiemVM>::new(db_rw));
-        let executor_proxy = ExecutorProxy::new(db, chunk_executor, vec![]);
+        let executor_proxy =
+            ExecutorProxy::new(db, chunk_executor, &node_config.state_sync, vec![]);
 
         // Get initial state
         let initial_state = executor_proxy.get_local_storage_state().unwrap();
@@ -175,6 +226,7 @@ pub(crate) mod test_utils {
             mempool_notifier,
             consensus_listener,
             network_senders,
+            Arc::new(PeerMonitoringStorage::new()),
             &node_config,
             waypoint,
             executor_proxy,
@@ -183,3 +235,156 @@ pub(crate) mod test_utils {
         .unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diem_crypto::{ed25519::Ed25519PrivateKey, HashValue, Uniform};
+    use diem_types::{
+        account_address::AccountAddress, block_info::BlockInfo, ledger_info::LedgerInfo,
+        validator_signer::ValidatorSigner, validator_verifier::ValidatorVerifier,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_verify_ledger_info_with_epoch_change_proof() {
+        let sync_state = create_test_sync_state();
+        assert_eq!(sync_state.trusted_epoch(), 1);
+
+        // Verify a ledger info in the epoch following the proof is accepted
+        let epoch_change_proof = EpochChangeProof::new(
+            vec![
+                create_ledger_info(1, 100, true),
+                create_ledger_info(2, 200, true),
+            ],
+            false,
+        );
+        let highest_li = create_ledger_info(3, 250, false);
+        assert_eq!(
+            sync_state.verify_ledger_info_with_epoch_change_proof(&highest_li, &epoch_change_proof),
+            Ok(())
+        );
+
+        // Verify a ledger info signed by the signer of another epoch is rejected
+        let highest_li = create_ledger_info_with_signer(3, 250, false, 2);
+        assert!(matches!(
+            sync_state.verify_ledger_info_with_epoch_change_proof(&highest_li, &epoch_change_proof),
+            Err(Error::ProofVerificationFailure(_))
+        ));
+
+        // Verify a ledger info beyond the epoch following the proof is rejected
+        let highest_li = create_ledger_info(4, 350, false);
+        assert!(matches!(
+            sync_state.verify_ledger_info_with_epoch_change_proof(&highest_li, &epoch_change_proof),
+            Err(Error::ProofVerificationFailure(_))
+        ));
+
+        // Verify a proof which doesn't start at the trusted epoch is rejected
+        let epoch_change_proof =
+            EpochChangeProof::new(vec![create_ledger_info(2, 200, true)], false);
+        let highest_li = create_ledger_info(3, 250, false);
+        assert!(matches!(
+            sync_state.verify_ledger_info_with_epoch_change_proof(&highest_li, &epoch_change_proof),
+            Err(Error::ProofVerificationFailure(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_ledger_info_at_end_of_epoch_change_proof() {
+        let sync_state = create_test_sync_state();
+
+        // Verify a ledger info which ends the proof is accepted (even if more epoch changes follow)
+        for more in [false, true] {
+            let epoch_change_proof = EpochChangeProof::new(
+                vec![
+                    create_ledger_info(1, 100, true),
+                    create_ledger_info(2, 200, true),
+                ],
+                more,
+            );
+            let highest_li = create_ledger_info(2, 200, true);
+            assert_eq!(
+                sync_state
+                    .verify_ledger_info_with_epoch_change_proof(&highest_li, &epoch_change_proof),
+                Ok(())
+            );
+        }
+
+        // Verify a ledger info which differs from the end of the proof in the same epoch is
+        // rejected (the proof's ledger info ends the epoch)
+        let epoch_change_proof = EpochChangeProof::new(
+            vec![
+                create_ledger_info(1, 100, true),
+                create_ledger_info(2, 200, true),
+            ],
+            true,
+        );
+        let highest_li = create_ledger_info(2, 150, false);
+        assert!(matches!(
+            sync_state.verify_ledger_info_with_epoch_change_proof(&highest_li, &epoch_change_proof),
+            Err(Error::ProofVerificationFailure(_))
+        ));
+    }
+
+    // Returns a sync state which committed the first ledger info of epoch 1 (so trusts epoch 1)
+    fn create_test_sync_state() -> SyncState {
+        SyncState::new(
+            create_ledger_info(1, 10, false),
+            ExecutedTrees::new_empty(),
+            create_epoch_state(1),
+        )
+    }
+
+    // Each epoch has a single validator, with an address and a key derived from the epoch
+    fn create_signer(epoch: u64) -> ValidatorSigner {
+        let mut rng = StdRng::from_seed([epoch as u8; 32]);
+        ValidatorSigner::new(
+            AccountAddress::new([epoch as u8; AccountAddress::LENGTH]),
+            Ed25519PrivateKey::generate(&mut rng),
+        )
+    }
+
+    fn create_epoch_state(epoch: u64) -> EpochState {
+        let signer = create_signer(epoch);
+        EpochState {
+            epoch,
+            verifier: ValidatorVerifier::new_single(signer.author(), signer.public_key()),
+        }
+    }
+
+    fn create_ledger_info(
+        epoch: u64,
+        version: Version,
+        ends_epoch: bool,
+    ) -> LedgerInfoWithSignatures {
+        create_ledger_info_with_signer(epoch, version, ends_epoch, epoch)
+    }
+
+    fn create_ledger_info_with_signer(
+        epoch: u64,
+        version: Version,
+        ends_epoch: bool,
+        signer_epoch: u64,
+    ) -> LedgerInfoWithSignatures {
+        let next_epoch_state = if ends_epoch {
+            Some(create_epoch_state(epoch + 1))
+        } else {
+            None
+        };
+        let block_info = BlockInfo::new(
+            epoch,
+            0,
+            HashValue::zero(),
+            HashValue::zero(),
+            version,
+            0,
+            next_epoch_state,
+        );
+        let ledger_info = LedgerInfo::new(block_info, HashValue::zero());
+        let signer = create_signer(signer_epoch);
+        let mut signatures = BTreeMap::new();
+        signatures.insert(signer.author(), signer.sign(&ledger_info));
+        LedgerInfoWithSignatures::new(ledger_info, signatures)
+    }
+}
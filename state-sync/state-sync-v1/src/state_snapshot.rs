@@ -0,0 +1,128 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{error::Error, shared_components::SyncState};
+use diem_crypto::{
+    hash::{CryptoHash, TransactionAccumulatorHasher},
+    HashValue,
+};
+use diem_types::{
+    account_state_blob::AccountStateChunkWithProof,
+    epoch_change::EpochChangeProof,
+    ledger_info::LedgerInfoWithSignatures,
+    proof::{accumulator::InMemoryAccumulator, AccumulatorConsistencyProof},
+    transaction::{TransactionListWithProof, Version},
+    waypoint::Waypoint,
+};
+use serde::{Deserialize, Serialize};
+
+/// A request sent by a node which bootstraps from a state snapshot (fast sync).
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StateSnapshotRequest {
+    /// Requests the ledger info to fast sync to, together with the proofs of the epoch changes
+    /// since `known_epoch`.
+    Target { known_epoch: u64 },
+    /// Requests at most `limit` account states at `version`, starting from `start_key`.
+    AccountStates {
+        version: Version,
+        start_key: HashValue,
+        limit: u64,
+    },
+}
+
+/// The response to a `StateSnapshotRequest`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum StateSnapshotResponse {
+    Target(StateSnapshotTarget),
+    AccountStates(AccountStateChunkWithProof),
+}
+
+/// The ledger info a node fast syncs to, together with the proofs needed to verify it from the
+/// node's trusted epoch and to restore the ledger at its version.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct StateSnapshotTarget {
+    /// The ledger infos ending the epochs between the requester's known epoch and the epoch of
+    /// the target.
+    pub epoch_change_proof: EpochChangeProof,
+    /// The ledger info to fast sync to.
+    pub target_li: LedgerInfoWithSignatures,
+    /// A consistency proof from genesis to the version before the target, i.e., the frozen
+    /// subtrees of the transaction accumulator before the transaction at the target version.
+    pub accumulator_proof: AccumulatorConsistencyProof,
+    /// The transaction at the target version, with its events.
+    pub txn_list_with_proof: TransactionListWithProof,
+}
+
+impl StateSnapshotTarget {
+    pub fn version(&self) -> Version {
+        self.target_li.ledger_info().version()
+    }
+
+    /// Verifies the target ledger info against the trusted epoch of `sync_state` and the
+    /// waypoint, and the transaction at the target version and the accumulator proof against
+    /// the target ledger info. Returns the state root hash at the target version.
+    pub fn verify(&self, sync_state: &SyncState, waypoint: &Waypoint) -> Result<HashValue, Error> {
+        // Verify the target ledger info and the waypoint
+        if self.epoch_change_proof.ledger_info_with_sigs.is_empty() {
+            sync_state.verify_ledger_info(&self.target_li)?;
+        } else {
+            sync_state.verify_ledger_info_with_epoch_change_proof(
+                &self.target_li,
+                &self.epoch_change_proof,
+            )?;
+        }
+        let version = self.version();
+        if waypoint.version() > sync_state.committed_version() {
+            if waypoint.version() > version {
+                return Err(Error::ProofVerificationFailure(format!(
+                    "The snapshot target version {} is lower than the waypoint version {}",
+                    version,
+                    waypoint.version()
+                )));
+            }
+            let waypoint_li = self
+                .epoch_change_proof
+                .ledger_info_with_sigs
+                .iter()
+                .chain(Some(&self.target_li))
+                .find(|li| li.ledger_info().version() == waypoint.version())
+                .ok_or_else(|| {
+                    Error::ProofVerificationFailure(format!(
+                        "Missing the ledger info at the waypoint version {}",
+                        waypoint.version()
+                    ))
+                })?;
+            waypoint
+                .verify(waypoint_li.ledger_info())
+                .map_err(|error| Error::ProofVerificationFailure(error.to_string()))?;
+        }
+
+        // Verify the transaction at the target version
+        let txn_list_with_proof = &self.txn_list_with_proof;
+        if txn_list_with_proof.transactions.len() != 1 || txn_list_with_proof.events.is_none() {
+            return Err(Error::ProofVerificationFailure(
+                "Expected a single transaction with its events at the snapshot target version"
+                    .into(),
+            ));
+        }
+        txn_list_with_proof
+            .verify(self.target_li.ledger_info(), Some(version))
+            .map_err(|error| Error::ProofVerificationFailure(error.to_string()))?;
+        let txn_info = &txn_list_with_proof.proof.transaction_infos()[0];
+
+        // Verify the frozen subtrees of the accumulator before the target version
+        let accumulator = InMemoryAccumulator::<TransactionAccumulatorHasher>::new(
+            self.accumulator_proof.subtrees().to_vec(),
+            version,
+        )
+        .map_err(|error| Error::ProofVerificationFailure(error.to_string()))?
+        .append(&[txn_info.hash()]);
+        if accumulator.root_hash() != self.target_li.ledger_info().transaction_accumulator_hash() {
+            return Err(Error::ProofVerificationFailure(
+                "The accumulator proof doesn't match the snapshot target ledger info".into(),
+            ));
+        }
+
+        Ok(txn_info.state_root_hash())
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The name under which the sync progress is published to the metric server, i.e., the progress
+/// is served at `host:port/status/state_sync`.
+pub const SYNC_PROGRESS_COMPONENT: &str = "state_sync";
+
+/// The window over which the sync rates are measured.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// What state sync is currently syncing to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStage {
+    /// Restoring a state snapshot, before chunk-based sync starts.
+    FastSync,
+    /// Syncing to the waypoint, before the node is initialized.
+    Waypoint,
+    /// Syncing to the target of a sync request from consensus.
+    SyncRequest,
+    /// Syncing to the highest ledger info known from peers.
+    HighestAvailable,
+    /// Consensus is executing, so state sync only serves peers.
+    ConsensusExecuting,
+}
+
+/// A snapshot of the progress of state sync.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SyncProgress {
+    pub stage: SyncStage,
+    pub synced_version: u64,
+    pub committed_version: u64,
+    /// The version state sync is syncing to, if known.
+    pub target_version: Option<u64>,
+    pub chunks_per_second: f64,
+    pub transactions_per_second: f64,
+    /// The estimated number of seconds until the target version is synced.
+    pub estimated_remaining_secs: Option<u64>,
+    /// The estimated time at which the target version is synced, in microseconds since the
+    /// Unix epoch.
+    pub estimated_completion_usecs: Option<u64>,
+}
+
+/// Tracks the chunks applied by state sync to estimate the sync rates and completion time.
+pub struct ProgressTracker {
+    start_time: SystemTime,
+    // The times at which chunks were applied within the rate window, with the chunk sizes
+    applied_chunks: VecDeque<(SystemTime, u64)>,
+}
+
+impl ProgressTracker {
+    pub fn new(start_time: SystemTime) -> Self {
+        Self {
+            start_time,
+            applied_chunks: VecDeque::new(),
+        }
+    }
+
+    pub fn record_chunk(&mut self, now: SystemTime, chunk_size: u64) {
+        self.applied_chunks.push_back((now, chunk_size));
+        self.prune(now);
+    }
+
+    /// Returns the progress at `now`, estimating the completion time from the rate at which
+    /// transactions were applied within the rate window.
+    pub fn progress(
+        &mut self,
+        now: SystemTime,
+        stage: SyncStage,
+        synced_version: u64,
+        committed_version: u64,
+        target_version: Option<u64>,
+    ) -> SyncProgress {
+        self.prune(now);
+        let elapsed = now
+            .duration_since(self.start_time)
+            .unwrap_or_default()
+            .min(RATE_WINDOW)
+            .as_secs_f64();
+        let (chunks_per_second, transactions_per_second) = if elapsed > 0.0 {
+            let transactions: u64 = self.applied_chunks.iter().map(|(_, size)| size).sum();
+            (
+                self.applied_chunks.len() as f64 / elapsed,
+                transactions as f64 / elapsed,
+            )
+        } else {
+            (0.0, 0.0)
+        };
+
+        let estimated_remaining_secs = target_version.and_then(|target_version| {
+            let remaining = target_version.saturating_sub(synced_version);
+            if remaining == 0 {
+                Some(0)
+            } else if transactions_per_second > 0.0 {
+                Some((remaining as f64 / transactions_per_second).ceil() as u64)
+            } else {
+                None
+            }
+        });
+        let estimated_completion_usecs = estimated_remaining_secs.and_then(|remaining_secs| {
+            now.checked_add(Duration::from_secs(remaining_secs))
+                .and_then(|completion| completion.duration_since(UNIX_EPOCH).ok())
+                .map(|completion| completion.as_micros() as u64)
+        });
+
+        SyncProgress {
+            stage,
+            synced_version,
+            committed_version,
+            target_version,
+            chunks_per_second,
+            transactions_per_second,
+            estimated_remaining_secs,
+            estimated_completion_usecs,
+        }
+    }
+
+    /// Forgets the chunks applied before the rate window.
+    fn prune(&mut self, now: SystemTime) {
+        while let Some((applied_time, _)) = self.applied_chunks.front() {
+            match now.duration_since(*applied_time) {
+                Ok(age) if age > RATE_WINDOW => {
+                    self.applied_chunks.pop_front();
+                }
+                _ => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_rates_and_eta() {
+        let start_time = UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut tracker = ProgressTracker::new(start_time);
+
+        // Apply 10 chunks of 100 transactions over 10 seconds
+        for second in 1..=10 {
+            tracker.record_chunk(start_time + Duration::from_secs(second), 100);
+        }
+        let now = start_time + Duration::from_secs(10);
+        let progress = tracker.progress(now, SyncStage::SyncRequest, 1_000, 900, Some(3_000));
+        assert_eq!(progress.chunks_per_second, 1.0);
+        assert_eq!(progress.transactions_per_second, 100.0);
+        assert_eq!(progress.estimated_remaining_secs, Some(20));
+        assert_eq!(progress.estimated_completion_usecs, Some(1_030 * 1_000_000));
+    }
+
+    #[test]
+    fn test_progress_without_rate() {
+        let start_time = UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut tracker = ProgressTracker::new(start_time);
+
+        // Verify no estimate is made without applied chunks, unless the target is reached
+        let progress = tracker.progress(start_time, SyncStage::Waypoint, 0, 0, Some(10));
+        assert_eq!(progress.transactions_per_second, 0.0);
+        assert_eq!(progress.estimated_remaining_secs, None);
+        let progress = tracker.progress(start_time, SyncStage::Waypoint, 10, 10, Some(10));
+        assert_eq!(progress.estimated_remaining_secs, Some(0));
+        let progress = tracker.progress(start_time, SyncStage::HighestAvailable, 10, 10, None);
+        assert_eq!(progress.estimated_completion_usecs, None);
+    }
+
+    #[test]
+    fn test_progress_rate_window() {
+        let start_time = UNIX_EPOCH;
+        let mut tracker = ProgressTracker::new(start_time);
+        tracker.record_chunk(start_time + Duration::from_secs(1), 100);
+
+        // Verify chunks applied before the rate window no longer count
+        let now = start_time + RATE_WINDOW + Duration::from_secs(2);
+        let progress = tracker.progress(now, SyncStage::SyncRequest, 100, 100, Some(200));
+        assert_eq!(progress.chunks_per_second, 0.0);
+        assert_eq!(progress.estimated_remaining_secs, None);
+    }
+}
@@ -8,7 +8,7 @@ use diem_config::config::RoleType;
 use diem_types::{transaction::TransactionListWithProof, waypoint::Waypoint, PeerId};
 use netcore::transport::ConnectionOrigin::*;
 use network::protocols::direct_send::Message;
-use state_sync_v1::{error::Error, network::StateSyncMessage};
+use state_sync_v1::{chunk_response::ResponseLedgerInfo, error::Error, network::StateSyncMessage};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use test_harness::StateSyncEnvironment;
 
@@ -396,6 +396,81 @@ fn test_fullnode_catch_up_moving_target_epochs() {
     }
 }
 
+#[test]
+fn test_fullnode_catch_up_with_epoch_change_proofs() {
+    // Create a validator (serving epoch change proofs) and a fullnode
+    let mut env = StateSyncEnvironment::new(2);
+    env.enable_epoch_change_proofs();
+    env.start_validator_peer(0, true);
+
+    // Move the validator several epochs ahead of the fullnode (to epoch 5 and version 450)
+    let validator = env.get_state_sync_peer(0);
+    for epoch in 1..5 {
+        validator.commit(epoch * 100);
+        validator.move_to_next_epoch(vec![validator.get_validator_info()], 0);
+    }
+    validator.commit(450);
+    drop(validator);
+
+    env.start_fullnode_peer(1, true);
+
+    // Get peer ids of nodes
+    let validator_peer_id = env
+        .get_state_sync_peer(0)
+        .get_peer_id(VALIDATOR_NETWORK.clone());
+    let fullnode_peer_id = env.get_state_sync_peer(1).get_peer_id(VFN_NETWORK.clone());
+
+    // Validator and fullnode discover each other.
+    send_connection_notifications(&mut env, validator_peer_id, fullnode_peer_id, true);
+
+    // The mock storage serves at most 2 epoch changes per proof. While the proof stops short of
+    // the validator's epoch, the highest ledger info is the last one in the proof. Otherwise,
+    // it's the validator's highest ledger info (in a later epoch than the whole proof).
+    let expected_responses = vec![
+        (100, vec![100, 200], true, 200),
+        (200, vec![200, 300], true, 300),
+        (300, vec![300, 400], false, 450),
+        (400, vec![400], false, 450),
+    ];
+    for (target_version, proof_versions, more, highest_version) in expected_responses {
+        env.deliver_msg(fullnode_peer_id);
+        let (_, message) = env.deliver_msg(validator_peer_id);
+        check_epoch_change_proof_response(
+            message,
+            target_version,
+            &proof_versions,
+            more,
+            highest_version,
+        );
+
+        // The fullnode accepts the highest ledger info and syncs up to the target
+        let fullnode = env.get_state_sync_peer(1);
+        if !fullnode.wait_for_version(target_version, Some(target_version)) {
+            panic!(
+                "Failed to reach synced and committed version: {}",
+                target_version
+            );
+        }
+    }
+
+    // Once in the validator's epoch, the fullnode syncs to the highest ledger info
+    env.deliver_msg(fullnode_peer_id);
+    let (_, message) = env.deliver_msg(validator_peer_id);
+    let chunk_response: StateSyncMessage = bcs::from_bytes(&message.mdata).unwrap();
+    match chunk_response {
+        StateSyncMessage::GetChunkResponse(chunk_response) => match chunk_response.response_li {
+            ResponseLedgerInfo::ProgressiveLedgerInfo { target_li, .. } => {
+                assert_eq!(target_li.ledger_info().version(), 450)
+            }
+            response_li => panic!("Unexpected response ledger info: {:?}", response_li),
+        },
+        message => panic!("Received {:?} but expecting chunk response!", message),
+    }
+    let fullnode = env.get_state_sync_peer(1);
+    assert!(fullnode.wait_for_version(450, Some(450)));
+    assert_eq!(fullnode.latest_li().ledger_info().epoch(), 5);
+}
+
 #[test]
 fn test_fullnode_catch_up_moving_target() {
     // Create validator and fullnode
@@ -846,6 +921,7 @@ fn check_chunk_request(message: Message, known_version: u64, target_version: Opt
         StateSyncMessage::GetChunkResponse(_) => {
             panic!("Received chunk response but expecting chunk request!");
         }
+        message => panic!("Received {:?} but expecting chunk request!", message),
     }
 }
 
@@ -874,6 +950,45 @@ fn check_chunk_response(
                 chunk_length
             )
         }
+        message => panic!("Received {:?} but expecting chunk response!", message),
+    }
+}
+
+fn check_epoch_change_proof_response(
+    message: Message,
+    target_version: u64,
+    proof_versions: &[u64],
+    more: bool,
+    highest_version: u64,
+) {
+    let chunk_response: StateSyncMessage = bcs::from_bytes(&message.mdata).unwrap();
+    let response_li = match chunk_response {
+        StateSyncMessage::GetChunkResponse(chunk_response) => chunk_response.response_li,
+        message => panic!("Received {:?} but expecting chunk response!", message),
+    };
+    match response_li {
+        ResponseLedgerInfo::ProgressiveLedgerInfoWithEpochChangeProof {
+            target_li,
+            highest_li,
+            epoch_change_proof,
+        } => {
+            assert_eq!(target_li.ledger_info().version(), target_version);
+            assert_eq!(highest_li.ledger_info().version(), highest_version);
+            assert_eq!(epoch_change_proof.more, more);
+            let epoch_change_versions: Vec<u64> = epoch_change_proof
+                .ledger_info_with_sigs
+                .iter()
+                .map(|li| li.ledger_info().version())
+                .collect();
+            assert_eq!(epoch_change_versions, proof_versions);
+            if more {
+                assert_eq!(
+                    epoch_change_proof.ledger_info_with_sigs.last(),
+                    Some(&highest_li)
+                );
+            }
+        }
+        response_li => panic!("Unexpected response ledger info: {:?}", response_li),
     }
 }
 
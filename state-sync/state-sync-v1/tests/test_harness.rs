@@ -18,9 +18,11 @@ use diem_transaction_builder::stdlib::encode_peer_to_peer_with_metadata_script;
 use diem_types::{
     account_address::AccountAddress,
     account_config::xus_tag,
+    account_state_blob::AccountStateChunkWithProof,
     block_info::BlockInfo,
     chain_id::ChainId,
     contract_event::ContractEvent,
+    epoch_change::EpochChangeProof,
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     network_address::{
@@ -54,6 +56,7 @@ use network::{
     protocols::{
         direct_send::Message,
         network::{NewNetworkEvents, NewNetworkSender},
+        peer_monitor::PeerMonitoringStorage,
     },
     transport::ConnectionMetadata,
     DisconnectReason, ProtocolId,
@@ -68,6 +71,7 @@ use state_sync_v1::{
     executor_proxy::ExecutorProxyTrait,
     network::{StateSyncEvents, StateSyncSender},
     shared_components::SyncState,
+    state_snapshot::StateSnapshotTarget,
 };
 use std::{
     cell::{Ref, RefCell},
@@ -75,6 +79,7 @@ use std::{
     ops::DerefMut,
     sync::Arc,
 };
+use subscription_service::ReconfigSubscription;
 use tokio::runtime::Runtime;
 use vm_genesis::GENESIS_KEYPAIR;
 
@@ -84,6 +89,10 @@ pub static VFN_NETWORK: Lazy<NetworkId> = Lazy::new(NetworkId::vfn_network);
 pub static VFN_NETWORK_2: Lazy<NetworkId> = Lazy::new(|| NetworkId::Private("Second VFN".into()));
 pub static PFN_NETWORK: Lazy<NetworkId> = Lazy::new(|| NetworkId::Public);
 
+// The maximum number of epoch changes in an epoch change proof served by the mock storage (so
+// that tests can exercise proofs which stop short of the local epoch).
+const MAX_EPOCH_CHANGE_PROOF_LEN: u64 = 2;
+
 pub struct StateSyncPeer {
     bootstrapper: Option<StateSyncBootstrapper>,
     client: Option<StateSyncClient>,
@@ -191,6 +200,7 @@ impl StateSyncPeer {
 }
 
 pub struct StateSyncEnvironment {
+    enable_epoch_change_proofs: bool,
     network_conn_event_notifs_txs: HashMap<PeerId, conn_notifs_channel::Sender>,
     network_notifs_txs:
         HashMap<PeerId, diem_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>>,
@@ -229,6 +239,7 @@ impl StateSyncEnvironment {
         }
 
         Self {
+            enable_epoch_change_proofs: false,
             network_conn_event_notifs_txs: HashMap::new(),
             network_notifs_txs: HashMap::new(),
             network_reqs_rxs: HashMap::new(),
@@ -237,6 +248,11 @@ impl StateSyncEnvironment {
         }
     }
 
+    // Enables epoch change proofs in the chunk responses of the peers started from now on.
+    pub fn enable_epoch_change_proofs(&mut self) {
+        self.enable_epoch_change_proofs = true;
+    }
+
     // Starts a new state sync peer with the validator role.
     pub fn start_validator_peer(&mut self, peer_index: usize, mock_network: bool) {
         self.start_state_sync_peer(
@@ -289,7 +305,9 @@ impl StateSyncEnvironment {
         multicast_timeout_ms: u64,
         mock_network: bool,
     ) {
-        let (config, network_id) = setup_state_sync_config(role, timeout_ms, multicast_timeout_ms);
+        let (mut config, network_id) =
+            setup_state_sync_config(role, timeout_ms, multicast_timeout_ms);
+        config.state_sync.enable_epoch_change_proofs = self.enable_epoch_change_proofs;
         let network_handles = self.setup_network_handles(index, &role, mock_network, network_id);
         let validators: Vec<ValidatorInfo> = self
             .peers
@@ -318,6 +336,7 @@ impl StateSyncEnvironment {
         let bootstrapper = StateSyncBootstrapper::bootstrap_with_executor_proxy(
             Runtime::new().unwrap(),
             network_handles,
+            Arc::new(PeerMonitoringStorage::new()),
             mempool_notifier,
             consensus_listener,
             &config,
@@ -858,6 +877,20 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         Ok(())
     }
 
+    fn dry_run_chunk(
+        &mut self,
+        _txn_list_with_proof: TransactionListWithProof,
+        _verified_target_li: LedgerInfoWithSignatures,
+        _intermediate_end_of_epoch_li: Option<LedgerInfoWithSignatures>,
+    ) -> Result<HashValue, Error> {
+        // Mock storage does not execute transactions, so there is no state root to compute
+        Err(Error::UnexpectedError(
+            "Dry runs are not supported by mock storage".into(),
+        ))
+    }
+
+    fn reset_dry_run(&mut self) {}
+
     fn get_chunk(
         &self,
         known_version: u64,
@@ -881,10 +914,35 @@ impl ExecutorProxyTrait for MockExecutorProxy {
         (self.handler)(txns_with_proof)
     }
 
+    fn get_account_state_chunk(
+        &self,
+        _version: u64,
+        _start_key: HashValue,
+        _limit: u64,
+    ) -> Result<AccountStateChunkWithProof, Error> {
+        // Mock storage only keeps transactions, so there is no account state to serve
+        Err(Error::UnexpectedError(
+            "Account state chunks are not supported by mock storage".into(),
+        ))
+    }
+
     fn get_epoch_change_ledger_info(&self, epoch: u64) -> Result<LedgerInfoWithSignatures, Error> {
         self.storage.read().get_epoch_changes(epoch)
     }
 
+    fn get_epoch_change_proof(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochChangeProof, Error> {
+        let storage = self.storage.read();
+        let limit_epoch = std::cmp::min(end_epoch, start_epoch + MAX_EPOCH_CHANGE_PROOF_LEN);
+        let ledger_infos = (start_epoch..limit_epoch)
+            .map(|epoch| storage.get_epoch_changes(epoch))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(EpochChangeProof::new(ledger_infos, limit_epoch < end_epoch))
+    }
+
     fn get_epoch_ending_ledger_info(
         &self,
         version: u64,
@@ -903,4 +961,43 @@ impl ExecutorProxyTrait for MockExecutorProxy {
     ) -> Result<(), Error> {
         Ok(())
     }
+
+    fn register_reconfig_subscription(
+        &mut self,
+        _subscription: ReconfigSubscription,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get_state_snapshot_target(&self, _known_epoch: u64) -> Result<StateSnapshotTarget, Error> {
+        // Mock storage only keeps transactions, so there is no state snapshot to serve
+        Err(Error::UnexpectedError(
+            "State snapshots are not supported by mock storage".into(),
+        ))
+    }
+
+    fn start_state_snapshot_restore(
+        &mut self,
+        _version: u64,
+        _expected_root_hash: HashValue,
+    ) -> Result<(), Error> {
+        Err(Error::UnexpectedError(
+            "State snapshots are not supported by mock storage".into(),
+        ))
+    }
+
+    fn add_state_snapshot_chunk(
+        &mut self,
+        _chunk: AccountStateChunkWithProof,
+    ) -> Result<(), Error> {
+        Err(Error::UnexpectedError(
+            "State snapshots are not supported by mock storage".into(),
+        ))
+    }
+
+    fn finish_state_snapshot_restore(&mut self, _target: StateSnapshotTarget) -> Result<(), Error> {
+        Err(Error::UnexpectedError(
+            "State snapshots are not supported by mock storage".into(),
+        ))
+    }
 }
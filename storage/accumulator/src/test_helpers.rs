@@ -270,6 +270,51 @@ pub fn test_range_proof_impl(
     proof.verify(root_hash, first_leaf_index, &batch2).unwrap();
 }
 
+pub fn test_truncated_range_proof_impl(
+    (batch1, batch2, batch3): (Vec<HashValue>, Vec<HashValue>, Vec<HashValue>),
+    num_leaves_to_keep: usize,
+) {
+    let mut store = MockHashStore::new();
+
+    let mut all_hashes = vec![];
+    all_hashes.extend_from_slice(&batch1);
+    all_hashes.extend_from_slice(&batch2);
+    all_hashes.extend_from_slice(&batch3);
+
+    let (root_hash, writes) = TestAccumulator::append(&store, 0, &all_hashes).unwrap();
+    store.put_many(&writes);
+
+    let first_leaf_index = batch1.len() as u64;
+    let num_leaves_to_keep = num_leaves_to_keep.min(batch2.len());
+    let proof = TestAccumulator::get_range_proof(
+        &store,
+        all_hashes.len() as LeafCount,
+        Some(first_leaf_index),
+        batch2.len() as LeafCount,
+    )
+    .unwrap();
+
+    // The truncated proof must match the proof of the truncated range
+    let truncated_proof = proof
+        .truncate(first_leaf_index, &batch2, num_leaves_to_keep)
+        .unwrap();
+    let expected_proof = TestAccumulator::get_range_proof(
+        &store,
+        all_hashes.len() as LeafCount,
+        Some(first_leaf_index),
+        num_leaves_to_keep as LeafCount,
+    )
+    .unwrap();
+    assert_eq!(truncated_proof, expected_proof);
+    truncated_proof
+        .verify(
+            root_hash,
+            Some(first_leaf_index),
+            &batch2[..num_leaves_to_keep],
+        )
+        .unwrap();
+}
+
 prop_compose! {
     pub fn arb_hash_batch(length: usize)(
         batch in vec(any::<HashValue>(), 0..length),
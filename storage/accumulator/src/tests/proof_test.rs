@@ -4,7 +4,7 @@
 use super::*;
 use crate::test_helpers::{
     arb_three_hash_batches, arb_two_hash_batches, test_consistency_proof_impl, test_proof_impl,
-    test_range_proof_impl, verify, MockHashStore, TestAccumulator,
+    test_range_proof_impl, test_truncated_range_proof_impl, verify, MockHashStore, TestAccumulator,
 };
 
 #[test]
@@ -41,4 +41,12 @@ proptest! {
     fn test_range_proof((batch1, batch2, batch3) in arb_three_hash_batches(100)) {
         test_range_proof_impl((batch1, batch2, batch3));
     }
+
+    #[test]
+    fn test_truncated_range_proof(
+        (batch1, batch2, batch3) in arb_three_hash_batches(100),
+        num_leaves_to_keep in 1..100usize,
+    ) {
+        test_truncated_range_proof_impl((batch1, batch2, batch3), num_leaves_to_keep);
+    }
 }
@@ -15,7 +15,10 @@ use crate::{
     },
 };
 use backup_service::start_backup_service;
-use diem_config::{config::RocksdbConfig, utils::get_available_port};
+use diem_config::{
+    config::{LoadSheddingConfig, RocksdbConfig},
+    utils::get_available_port,
+};
 use diem_temppath::TempPath;
 use diem_types::{
     ledger_info::LedgerInfoWithSignatures,
@@ -48,6 +51,8 @@ fn end_to_end() {
     let rt = start_backup_service(
         SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
         src_db,
+        &LoadSheddingConfig::default(),
+        None, /* checkpoint_dir */
     );
     let client = Arc::new(BackupServiceClient::new(format!(
         "http://localhost:{}",
@@ -26,7 +26,7 @@ use diem_types::{
 };
 use diem_vm::DiemVM;
 use executor::Executor;
-use executor_types::TransactionReplayer;
+use executor_types::{ReplayDivergence, TransactionReplayer};
 use futures::StreamExt;
 use std::{
     cmp::{max, min},
@@ -224,6 +224,7 @@ impl TransactionRestoreController {
                 let num_to_replay = (last - first_to_replay + 1) as usize;
                 chunk.txns.truncate(num_to_replay);
                 chunk.txn_infos.truncate(num_to_replay);
+                chunk.event_vecs.truncate(num_to_replay);
 
                 // replay in batches
                 info!("Replaying transactions {} to {}.", first_to_replay, last);
@@ -235,14 +236,27 @@ impl TransactionRestoreController {
                 let mut current_version = first_to_replay;
                 while !chunk.txns.is_empty() {
                     let this_batch_size = min(BATCH_SIZE, chunk.txns.len());
-                    self.transaction_replayer(current_version)?.replay_chunk(
-                        current_version,
-                        chunk.txns.drain(0..this_batch_size).collect::<Vec<_>>(),
-                        chunk
-                            .txn_infos
-                            .drain(0..this_batch_size)
-                            .collect::<Vec<_>>(),
-                    )?;
+                    let event_vecs = chunk
+                        .event_vecs
+                        .drain(0..this_batch_size)
+                        .collect::<Vec<_>>();
+                    self.transaction_replayer(current_version)?
+                        .replay_chunk(
+                            current_version,
+                            chunk.txns.drain(0..this_batch_size).collect::<Vec<_>>(),
+                            chunk
+                                .txn_infos
+                                .drain(0..this_batch_size)
+                                .collect::<Vec<_>>(),
+                        )
+                        .map_err(|e| {
+                            if let Some(divergence) = e.downcast_ref::<ReplayDivergence>() {
+                                let recorded_events =
+                                    &event_vecs[(divergence.version - current_version) as usize];
+                                log_replay_divergence(divergence, recorded_events);
+                            }
+                            e
+                        })?;
                     current_version += this_batch_size as u64;
                     TRANSACTION_REPLAY_VERSION.set(current_version as i64 - 1);
                 }
@@ -294,6 +308,38 @@ impl TransactionRestoreController {
     }
 }
 
+/// Logs how the outputs of a replayed transaction differ from the ones recorded in the backup. The
+/// original write set isn't backed up, so only the state root hashes tell whether the replayed
+/// write set is the same.
+fn log_replay_divergence(divergence: &ReplayDivergence, recorded_events: &[ContractEvent]) {
+    let recorded = &divergence.recorded_txn_info;
+    let replayed = &divergence.replayed_txn_info;
+    let replayed_events = &divergence.replayed_events;
+    let first_diverged_event = recorded_events
+        .iter()
+        .zip(replayed_events)
+        .position(|(recorded, replayed)| recorded != replayed)
+        .or_else(|| {
+            if recorded_events.len() != replayed_events.len() {
+                Some(min(recorded_events.len(), replayed_events.len()))
+            } else {
+                None
+            }
+        });
+
+    error!(
+        version = divergence.version,
+        status_diverged = recorded.status() != replayed.status(),
+        gas_used_diverged = recorded.gas_used() != replayed.gas_used(),
+        state_diverged = recorded.state_root_hash() != replayed.state_root_hash(),
+        first_diverged_event = first_diverged_event,
+        recorded_events = ?recorded_events,
+        replayed_events = ?replayed_events,
+        replayed_write_set = ?divergence.replayed_write_set,
+        "Replayed transaction diverged from the backup."
+    );
+}
+
 #[derive(Default)]
 struct State {
     frozen_subtree_confirmed: bool,
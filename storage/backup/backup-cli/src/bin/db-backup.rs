@@ -38,6 +38,12 @@ enum OneShotCommand {
     Query(OneShotQueryType),
     #[structopt(about = "Do a one shot backup.")]
     Backup(OneShotBackupOpt),
+    #[structopt(
+        about = "Create a checkpoint of the DB of the local Diem node without stopping it, via the \
+        backup service within it. The checkpoint is created on the node, in the checkpoint \
+        directory configured for it."
+    )]
+    Checkpoint(OneShotCheckpointOpt),
 }
 
 #[derive(StructOpt)]
@@ -69,6 +75,18 @@ struct OneShotQueryBackupStorageStateOpt {
     storage: StorageOpt,
 }
 
+#[derive(StructOpt)]
+struct OneShotCheckpointOpt {
+    #[structopt(
+        long,
+        help = "Name of the checkpoint, unique in the checkpoint directory."
+    )]
+    name: String,
+
+    #[structopt(flatten)]
+    client: BackupServiceClientOpt,
+}
+
 #[derive(StructOpt)]
 struct OneShotBackupOpt {
     #[structopt(flatten)]
@@ -195,6 +213,11 @@ async fn main_impl() -> Result<()> {
                     }
                 }
             }
+            OneShotCommand::Checkpoint(opt) => {
+                let client = BackupServiceClient::new_with_opt(opt.client);
+                let path = client.create_checkpoint(&opt.name).await?;
+                println!("Checkpoint created at {} on the node.", path)
+            }
         },
         Command::Coordinator(coordinator_cmd) => match coordinator_cmd {
             CoordinatorCommand::Run(opt) => {
@@ -9,6 +9,7 @@ use backup_cli::{
     utils::{ConcurrentDownloadsOpt, RocksdbOpt, TrustedWaypointOpt},
 };
 use diem_logger::{prelude::*, Level, Logger};
+use diem_temppath::TempPath;
 use diem_types::transaction::Version;
 use diemdb::{DiemDB, GetRestoreHandler};
 use std::{path::PathBuf, sync::Arc};
@@ -24,8 +25,13 @@ struct Opt {
     storage: StorageOpt,
     #[structopt(flatten)]
     concurrent_downloads: ConcurrentDownloadsOpt,
-    #[structopt(long = "target-db-dir", parse(from_os_str))]
-    pub db_dir: PathBuf,
+    #[structopt(
+        long = "target-db-dir",
+        parse(from_os_str),
+        help = "[Defaults to a temporary directory, removed on exit] The directory to restore the \
+                backup to and replay transactions in."
+    )]
+    pub db_dir: Option<PathBuf>,
     #[structopt(flatten)]
     pub rocksdb_opt: RocksdbOpt,
     #[structopt(
@@ -53,8 +59,16 @@ async fn main_impl() -> Result<()> {
     Logger::new().level(Level::Info).read_env().init();
 
     let opt = Opt::from_args();
+    let tmp_db_dir = TempPath::new();
+    let db_dir = match opt.db_dir {
+        Some(db_dir) => db_dir,
+        None => {
+            tmp_db_dir.create_as_dir()?;
+            tmp_db_dir.path().to_path_buf()
+        }
+    };
     let restore_handler = Arc::new(DiemDB::open(
-        opt.db_dir,
+        db_dir,
         false, /* read_only */
         None,  /* pruner */
         opt.rocksdb_opt.into(),
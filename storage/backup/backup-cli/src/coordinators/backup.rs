@@ -27,7 +27,7 @@ use std::{fmt::Debug, sync::Arc};
 use structopt::StructOpt;
 use tokio::{
     sync::watch,
-    time::{interval, Duration},
+    time::{interval, Duration, Instant},
 };
 use tokio_stream::wrappers::IntervalStream;
 
@@ -46,6 +46,12 @@ pub struct BackupCoordinatorOpt {
     // slower than expected.
     #[structopt(long, default_value = "100000")]
     pub transaction_batch_size: usize,
+    // If set, transactions are backed up continuously: when no backup was taken for this long,
+    // the transactions committed so far are backed up even if they don't fill a batch yet, which
+    // bounds the lag of the latest restore point behind the chain. The following backups still
+    // end at batch boundaries, so a batch can be spread over multiple backups.
+    #[structopt(long)]
+    pub max_transaction_backup_lag_secs: Option<u64>,
     #[structopt(flatten)]
     pub concurernt_downloads: ConcurrentDownloadsOpt,
 }
@@ -74,6 +80,7 @@ pub struct BackupCoordinator {
     metadata_cache_opt: MetadataCacheOpt,
     state_snapshot_interval: usize,
     transaction_batch_size: usize,
+    max_transaction_backup_lag: Option<Duration>,
     concurrent_downloads: usize,
 }
 
@@ -92,6 +99,9 @@ impl BackupCoordinator {
             metadata_cache_opt: opt.metadata_cache_opt,
             state_snapshot_interval: opt.state_snapshot_interval,
             transaction_batch_size: opt.transaction_batch_size,
+            max_transaction_backup_lag: opt
+                .max_transaction_backup_lag_secs
+                .map(Duration::from_secs),
             concurrent_downloads: opt.concurernt_downloads.get(),
         }
     }
@@ -136,7 +146,7 @@ impl BackupCoordinator {
             .boxed_local();
         let backup_transactions = self
             .backup_work_stream(
-                backup_state.latest_transaction_version,
+                (backup_state.latest_transaction_version, Instant::now()),
                 &rx2,
                 Self::backup_transactions,
             )
@@ -253,22 +263,26 @@ impl BackupCoordinator {
 
     async fn backup_transactions(
         &self,
-        mut last_transaction_version_in_backup: Option<Version>,
+        (mut last_transaction_version_in_backup, mut last_backup_time): (Option<Version>, Instant),
         db_state: DbState,
-    ) -> Result<Option<u64>> {
+    ) -> Result<(Option<u64>, Instant)> {
         loop {
             if let Some(version) = last_transaction_version_in_backup {
                 TRANSACTION_VERSION.set(version as i64);
             }
-            let (first, last) = get_batch_range(
+            let lag_exceeded = self
+                .max_transaction_backup_lag
+                .map_or(false, |max_lag| last_backup_time.elapsed() >= max_lag);
+            let (first, last) = match get_next_transaction_batch(
                 last_transaction_version_in_backup,
                 self.transaction_batch_size,
-            );
-
-            if db_state.committed_version < last {
+                db_state.committed_version,
+                lag_exceeded,
+            ) {
+                Some(range) => range,
                 // wait for the next db_state update
-                return Ok(last_transaction_version_in_backup);
-            }
+                None => return Ok((last_transaction_version_in_backup, last_backup_time)),
+            };
 
             TransactionBackupController::new(
                 TransactionBackupOpt {
@@ -283,6 +297,7 @@ impl BackupCoordinator {
             .await?;
 
             last_transaction_version_in_backup = Some(last);
+            last_backup_time = Instant::now();
         }
     }
 
@@ -341,6 +356,24 @@ fn get_batch_range(last_in_backup: Option<u64>, batch_size: usize) -> (u64, u64)
     })
 }
 
+/// Returns the range of the next transaction backup, or None if it has to wait for more
+/// transactions to be committed. Unless `partial` is set, only full batches are backed up.
+fn get_next_transaction_batch(
+    last_in_backup: Option<u64>,
+    batch_size: usize,
+    committed_version: u64,
+    partial: bool,
+) -> Option<(u64, u64)> {
+    let (first, last) = get_batch_range(last_in_backup, batch_size);
+    if committed_version >= last {
+        Some((first, last))
+    } else if partial && committed_version >= first {
+        Some((first, committed_version))
+    } else {
+        None
+    }
+}
+
 fn get_next_snapshot(last_in_backup: Option<u64>, db_state: DbState, interval: usize) -> u64 {
     // We don't try to guarantee snapshots are taken at each applicable interval: when the backup
     // progress can't keep up with the ledger growth, we favor timeliness over completeness.
@@ -359,7 +392,9 @@ fn get_next_snapshot(last_in_backup: Option<u64>, db_state: DbState, interval: u
 
 #[cfg(test)]
 mod tests {
-    use crate::coordinators::backup::{get_batch_range, get_next_snapshot};
+    use crate::coordinators::backup::{
+        get_batch_range, get_next_snapshot, get_next_transaction_batch,
+    };
     use diemdb::backup::backup_handler::DbState;
 
     #[test]
@@ -371,6 +406,33 @@ mod tests {
         assert_eq!(get_batch_range(Some(200), 100), (201, 300));
     }
 
+    #[test]
+    fn test_get_next_transaction_batch() {
+        // Only full batches are backed up by default.
+        assert_eq!(get_next_transaction_batch(Some(100), 100, 150, false), None);
+        assert_eq!(
+            get_next_transaction_batch(Some(100), 100, 250, false),
+            Some((101, 200))
+        );
+
+        // Partial batches are backed up when the max lag is exceeded.
+        assert_eq!(
+            get_next_transaction_batch(Some(100), 100, 150, true),
+            Some((101, 150))
+        );
+        assert_eq!(
+            get_next_transaction_batch(Some(150), 100, 180, true),
+            Some((151, 180))
+        );
+        assert_eq!(
+            get_next_transaction_batch(Some(180), 100, 250, true),
+            Some((181, 200))
+        );
+        // Nothing new is committed.
+        assert_eq!(get_next_transaction_batch(Some(150), 100, 150, true), None);
+        assert_eq!(get_next_transaction_batch(None, 100, 0, true), Some((0, 0)));
+    }
+
     #[test]
     fn test_get_next_snapshot() {
         let _state = |v| DbState {
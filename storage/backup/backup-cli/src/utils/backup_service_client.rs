@@ -57,6 +57,24 @@ impl BackupServiceClient {
             .compat())
     }
 
+    /// Asks the node to create a checkpoint of its DB named `name`, and returns the path of the
+    /// checkpoint on the node.
+    pub async fn create_checkpoint(&self, name: &str) -> Result<String> {
+        let url = format!("{}/checkpoint/{}", self.address, name);
+        let bytes = self
+            .client
+            .post(&url)
+            .send()
+            .await
+            .err_notes(&url)?
+            .error_for_status()
+            .err_notes(&url)?
+            .bytes()
+            .await
+            .err_notes(&url)?;
+        Ok(bcs::from_bytes(&bytes)?)
+    }
+
     pub async fn get_db_state(&self) -> Result<Option<DbState>> {
         let mut buf = Vec::new();
         self.get("db_state").await?.read_to_end(&mut buf).await?;
@@ -7,9 +7,15 @@ use crate::handlers::utils::{
     handle_rejection, reply_with_async_channel_writer, reply_with_bcs_bytes,
     send_size_prefixed_bcs_bytes, unwrap_or_500, LATENCY_HISTOGRAM,
 };
+use anyhow::{ensure, format_err, Result};
 use diem_crypto::hash::HashValue;
+use diem_load_shedder::LoadShedder;
 use diem_types::transaction::Version;
-use diemdb::backup::backup_handler::BackupHandler;
+use diemdb::{backup::backup_handler::BackupHandler, DiemDB};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use warp::{filters::BoxedFilter, reply::Reply, Filter};
 
 static DB_STATE: &str = "db_state";
@@ -19,8 +25,14 @@ static STATE_ROOT_PROOF: &str = "state_root_proof";
 static EPOCH_ENDING_LEDGER_INFOS: &str = "epoch_ending_ledger_infos";
 static TRANSACTIONS: &str = "transactions";
 static TRANSACTION_RANGE_PROOF: &str = "transaction_range_proof";
+static CHECKPOINT: &str = "checkpoint";
 
-pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Reply,)> {
+pub(crate) fn get_routes(
+    backup_handler: BackupHandler,
+    load_shedder: LoadShedder,
+    db: Arc<DiemDB>,
+    checkpoint_dir: Option<PathBuf>,
+) -> BoxedFilter<(impl Reply,)> {
     // GET db_state
     let bh = backup_handler.clone();
     let db_state = warp::path::end()
@@ -42,9 +54,10 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
 
     // GET state_snapshot/<version>
     let bh = backup_handler.clone();
+    let ls = load_shedder.clone();
     let state_snapshot = warp::path!(Version)
         .map(move |version| {
-            reply_with_async_channel_writer(&bh, STATE_SNAPSHOT, |bh, sender| {
+            reply_with_async_channel_writer(&bh, &ls, STATE_SNAPSHOT, |bh, sender| {
                 send_size_prefixed_bcs_bytes(bh.get_account_iter(version), sender)
             })
         })
@@ -61,12 +74,14 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
 
     // GET epoch_ending_ledger_infos/<start_epoch>/<end_epoch>/
     let bh = backup_handler.clone();
+    let ls = load_shedder.clone();
     let epoch_ending_ledger_infos = warp::path!(u64 / u64)
         .map(move |start_epoch, end_epoch| {
             // use async move block to group `bh` and the iterator into the same lifetime, since the
             // latter references the former.
             reply_with_async_channel_writer(
                 &bh,
+                &ls,
                 EPOCH_ENDING_LEDGER_INFOS,
                 |bh, sender| async move {
                     send_size_prefixed_bcs_bytes(
@@ -81,11 +96,12 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
 
     // GET transactions/<start_version>/<num_transactions>
     let bh = backup_handler.clone();
+    let ls = load_shedder;
     let transactions = warp::path!(Version / usize)
         .map(move |start_version, num_transactions| {
             // use async move block to group `bh` and the iterator into the same lifetime, since the
             // latter references the former.
-            reply_with_async_channel_writer(&bh, TRANSACTIONS, |bh, sender| async move {
+            reply_with_async_channel_writer(&bh, &ls, TRANSACTIONS, |bh, sender| async move {
                 send_size_prefixed_bcs_bytes(
                     bh.get_transaction_iter(start_version, num_transactions),
                     sender,
@@ -107,6 +123,17 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         .map(unwrap_or_500)
         .recover(handle_rejection);
 
+    // POST checkpoint/<name>
+    let checkpoint = warp::path!(String)
+        .map(move |name: String| {
+            reply_with_bcs_bytes(
+                CHECKPOINT,
+                &create_checkpoint(&db, checkpoint_dir.as_deref(), &name)?,
+            )
+        })
+        .map(unwrap_or_500)
+        .recover(handle_rejection);
+
     // Route by endpoint name.
     let routes = warp::any()
         .and(warp::path(DB_STATE).and(db_state))
@@ -117,9 +144,10 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         .or(warp::path(TRANSACTIONS).and(transactions))
         .or(warp::path(TRANSACTION_RANGE_PROOF).and(transaction_range_proof));
 
-    // Serve all routes for GET only.
+    // Serve all routes for GET only, except for the checkpoint creation.
     warp::get()
         .and(routes)
+        .or(warp::post().and(warp::path(CHECKPOINT)).and(checkpoint))
         .with(warp::log::custom(|info| {
             let endpoint = info.path().split('/').nth(1).unwrap_or("-");
             LATENCY_HISTOGRAM
@@ -128,3 +156,22 @@ pub(crate) fn get_routes(backup_handler: BackupHandler) -> BoxedFilter<(impl Rep
         }))
         .boxed()
 }
+
+/// Creates a checkpoint of the DB under `checkpoint_dir`, named `name`, and returns its path.
+fn create_checkpoint(db: &DiemDB, checkpoint_dir: Option<&Path>, name: &str) -> Result<String> {
+    let checkpoint_dir =
+        checkpoint_dir.ok_or_else(|| format_err!("Checkpoints are not enabled."))?;
+    ensure!(
+        !name.is_empty()
+            && !name.starts_with('.')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'),
+        "Invalid checkpoint name: {:?}.",
+        name,
+    );
+
+    let path = checkpoint_dir.join(name);
+    db.create_checkpoint(&path)?;
+    Ok(path.to_string_lossy().into_owned())
+}
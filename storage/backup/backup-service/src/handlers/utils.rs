@@ -3,6 +3,7 @@
 
 use anyhow::Result;
 use bytes::Bytes;
+use diem_load_shedder::{Admission, InboundPath, LoadShedder};
 use diem_logger::prelude::*;
 use diem_metrics::{register_histogram_vec, register_int_counter_vec, HistogramVec, IntCounterVec};
 use diemdb::backup::backup_handler::BackupHandler;
@@ -65,8 +66,11 @@ impl BytesSender {
     }
 }
 
+/// Streams the output of `get_channel_writer` as the response body, unless the request is shed
+/// because the node is overloaded, in which case 503 is returned.
 pub(super) fn reply_with_async_channel_writer<G, F>(
     backup_handler: &BackupHandler,
+    load_shedder: &LoadShedder,
     endpoint: &'static str,
     get_channel_writer: G,
 ) -> Box<dyn Reply>
@@ -74,10 +78,28 @@ where
     G: FnOnce(BackupHandler, BytesSender) -> F,
     F: Future<Output = ()> + Send + 'static,
 {
+    let delay = match load_shedder.admit(InboundPath::BackupStreaming) {
+        Admission::Accept => None,
+        Admission::Delay(delay) => Some(delay),
+        Admission::Shed => {
+            warn!(
+                endpoint = endpoint,
+                "Backup request shed, node is overloaded."
+            );
+            return Box::new(warp::http::StatusCode::SERVICE_UNAVAILABLE);
+        }
+    };
+
     let (sender, body) = Body::channel();
     let sender = BytesSender::new(endpoint, sender);
     let bh = backup_handler.clone();
-    tokio::spawn(get_channel_writer(bh, sender));
+    let writer = get_channel_writer(bh, sender);
+    tokio::spawn(async move {
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        writer.await
+    });
 
     Box::new(Response::new(body))
 }
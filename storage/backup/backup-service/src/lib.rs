@@ -4,14 +4,22 @@
 mod handlers;
 
 use crate::handlers::get_routes;
+use diem_config::config::LoadSheddingConfig;
+use diem_load_shedder::LoadShedder;
 use diem_logger::prelude::*;
 use diemdb::DiemDB;
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
 
-pub fn start_backup_service(address: SocketAddr, db: Arc<DiemDB>) -> Runtime {
+pub fn start_backup_service(
+    address: SocketAddr,
+    db: Arc<DiemDB>,
+    load_shedding_config: &LoadSheddingConfig,
+    checkpoint_dir: Option<PathBuf>,
+) -> Runtime {
     let backup_handler = db.get_backup_handler();
-    let routes = get_routes(backup_handler);
+    let load_shedder = LoadShedder::new(load_shedding_config.clone());
+    let routes = get_routes(backup_handler, load_shedder, db, checkpoint_dir);
 
     let runtime = Builder::new_multi_thread()
         .thread_name("backup")
@@ -39,8 +47,11 @@ mod tests {
     use diem_config::utils::get_available_port;
     use diem_crypto::hash::HashValue;
     use diem_temppath::TempPath;
-    use reqwest::blocking::get;
-    use std::net::{IpAddr, Ipv4Addr};
+    use reqwest::blocking::{get, Client};
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        path::Path,
+    };
 
     /// 404 - endpoint not found
     /// 400 - params not provided or failed parsing
@@ -52,7 +63,12 @@ mod tests {
         let tmpdir = TempPath::new();
         let db = Arc::new(DiemDB::new_for_test(&tmpdir));
         let port = get_available_port();
-        let _rt = start_backup_service(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port), db);
+        let _rt = start_backup_service(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+            db,
+            &LoadSheddingConfig::default(),
+            None, /* checkpoint_dir */
+        );
 
         // Endpoint doesn't exist.
         let resp = get(&format!("http://127.0.0.1:{}/", port)).unwrap();
@@ -93,5 +109,48 @@ mod tests {
         assert_eq!(resp.status(), 200);
         assert_eq!(resp.content_length(), None);
         assert!(resp.bytes().is_err());
+
+        // Checkpoints are not enabled.
+        let resp = Client::new()
+            .post(&format!("http://127.0.0.1:{}/checkpoint/a", port))
+            .send()
+            .unwrap();
+        assert_eq!(resp.status(), 500);
+    }
+
+    #[test]
+    fn create_checkpoint() {
+        let tmpdir = TempPath::new();
+        let db = Arc::new(DiemDB::new_for_test(&tmpdir));
+        let checkpoint_dir = TempPath::new();
+        let port = get_available_port();
+        let _rt = start_backup_service(
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+            db,
+            &LoadSheddingConfig::default(),
+            Some(checkpoint_dir.path().to_path_buf()),
+        );
+        let post = |name: &str| {
+            Client::new()
+                .post(&format!("http://127.0.0.1:{}/checkpoint/{}", port, name))
+                .send()
+                .unwrap()
+        };
+
+        // Checkpoints are created only by POST.
+        let resp = get(&format!("http://127.0.0.1:{}/checkpoint/a", port)).unwrap();
+        assert_ne!(resp.status(), 200);
+
+        let resp = post("a");
+        assert_eq!(resp.status(), 200);
+        let path: String = bcs::from_bytes(&resp.bytes().unwrap()).unwrap();
+        assert_eq!(Path::new(&path), checkpoint_dir.path().join("a"));
+        assert!(Path::new(&path).join("diemdb").is_dir());
+
+        // Existing checkpoint.
+        assert_eq!(post("a").status(), 500);
+        // Names escaping the checkpoint directory are rejected.
+        assert_eq!(post("..").status(), 500);
+        assert_eq!(post("%2E%2E%2Fb").status(), 500);
     }
 }
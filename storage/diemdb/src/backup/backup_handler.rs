@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    cold_store::{ColdStore, LedgerIter},
     event_store::EventStore,
     ledger_store::LedgerStore,
     metrics::{
@@ -21,7 +22,6 @@ use diem_types::{
     proof::{SparseMerkleRangeProof, TransactionAccumulatorRangeProof, TransactionInfoWithProof},
     transaction::{Transaction, TransactionInfo, Version},
 };
-use itertools::zip_eq;
 use serde::{Deserialize, Serialize};
 use std::{fmt, sync::Arc};
 
@@ -32,6 +32,7 @@ pub struct BackupHandler {
     transaction_store: Arc<TransactionStore>,
     state_store: Arc<StateStore>,
     event_store: Arc<EventStore>,
+    cold_store: Option<Arc<ColdStore>>,
 }
 
 impl BackupHandler {
@@ -40,39 +41,40 @@ impl BackupHandler {
         transaction_store: Arc<TransactionStore>,
         state_store: Arc<StateStore>,
         event_store: Arc<EventStore>,
+        cold_store: Option<Arc<ColdStore>>,
     ) -> Self {
         Self {
             ledger_store,
             transaction_store,
             state_store,
             event_store,
+            cold_store,
         }
     }
 
-    /// Gets an iterator that yields a range of transactions.
+    /// Gets an iterator that yields a range of transactions, including offloaded ones.
     pub fn get_transaction_iter(
         &self,
         start_version: Version,
         num_transactions: usize,
     ) -> Result<impl Iterator<Item = Result<(Transaction, TransactionInfo, Vec<ContractEvent>)>> + '_>
     {
-        let txn_iter = self
-            .transaction_store
-            .get_transaction_iter(start_version, num_transactions)?;
-        let txn_info_iter = self
-            .ledger_store
-            .get_transaction_info_iter(start_version, num_transactions)?;
-        let events_iter = self
-            .event_store
-            .get_events_by_version_iter(start_version, num_transactions)?;
+        let ledger_iter = LedgerIter::new(
+            self.cold_store.as_deref(),
+            &self.ledger_store,
+            &self.transaction_store,
+            &self.event_store,
+            start_version,
+            num_transactions,
+            true, /* fetch_events */
+        )?;
 
-        let zipped = zip_eq(zip_eq(txn_iter, txn_info_iter), events_iter)
-            .enumerate()
-            .map(move |(idx, ((txn_res, txn_info_res), events_res))| {
-                BACKUP_TXN_VERSION.set((start_version.wrapping_add(idx as u64)) as i64);
-                Ok((txn_res?, txn_info_res?, events_res?))
-            });
-        Ok(zipped)
+        let iter = ledger_iter.enumerate().map(move |(idx, res)| {
+            BACKUP_TXN_VERSION.set((start_version.wrapping_add(idx as u64)) as i64);
+            let (txn, txn_info, events) = res?;
+            Ok((txn, txn_info, events.expect("Events were requested.")))
+        });
+        Ok(iter)
     }
 
     /// Gets the proof for a transaction chunk.
@@ -0,0 +1,588 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides `ColdStore`, which offloads the transactions and events of old versions to
+//! an object store in the background, and reads them back when they are requested.
+//!
+//! Versions are offloaded by segments of `segment_size` versions, each uploaded as a single object
+//! before its transactions and events are deleted from the DB with a single atomic batch. So if
+//! the process crashes, the segment is uploaded again upon restart and no data is lost. Only the
+//! transactions and events themselves are deleted: the transaction infos are kept, so that
+//! offloaded data is verified when read back, and so are the indices and event accumulators, so
+//! that offloaded transactions and events are still found by hash, account, event key or type tag,
+//! with proofs.
+
+use crate::{
+    errors::DiemDbError,
+    event_store::{EventStore, EventsByVersionIter},
+    ledger_store::{LedgerStore, TransactionInfoIter},
+    metrics::{DIEM_STORAGE_COLD_STORE_FIRST_HOT_VERSION, DIEM_STORAGE_COLD_STORE_READS},
+    schema::{event::EventSchema, transaction::TransactionSchema},
+    transaction_store::{TransactionIter, TransactionStore},
+};
+use anyhow::{ensure, format_err, Result};
+use diem_config::config::{ColdStorageBackend, ColdStorageConfig};
+use diem_crypto::hash::{CryptoHash, EventAccumulatorHasher};
+use diem_infallible::{Mutex, RwLock};
+use diem_logger::prelude::*;
+use diem_types::{
+    contract_event::ContractEvent,
+    proof::accumulator::InMemoryAccumulator,
+    transaction::{Transaction, TransactionInfo, Version},
+};
+use schemadb::{ReadOptions, SchemaBatch, DB};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::Write,
+    path::PathBuf,
+    process::{Command as ProcessCommand, Stdio},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+/// A store of named objects, e.g., a bucket of S3 or GCS.
+pub trait ObjectStore: fmt::Debug + Send + Sync {
+    /// Writes the object, overwriting the existing one of the same name.
+    fn put_object(&self, name: &str, data: &[u8]) -> Result<()>;
+
+    fn get_object(&self, name: &str) -> Result<Vec<u8>>;
+}
+
+/// Objects are files in a local directory.
+#[derive(Debug)]
+pub struct LocalDirObjectStore {
+    path: PathBuf,
+}
+
+impl LocalDirObjectStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl ObjectStore for LocalDirObjectStore {
+    fn put_object(&self, name: &str, data: &[u8]) -> Result<()> {
+        std::fs::create_dir_all(&self.path)?;
+        // Write to a temporary file first so that a partially written object is never read.
+        let tmp_path = self.path.join(format!("{}.tmp", name));
+        std::fs::write(&tmp_path, data)?;
+        std::fs::rename(&tmp_path, self.path.join(name))?;
+        Ok(())
+    }
+
+    fn get_object(&self, name: &str) -> Result<Vec<u8>> {
+        Ok(std::fs::read(self.path.join(name))?)
+    }
+}
+
+/// Objects are written and read by command lines, see `ColdStorageBackend::Command`.
+#[derive(Debug)]
+pub struct CommandObjectStore {
+    put_object_cmd: String,
+    get_object_cmd: String,
+}
+
+impl CommandObjectStore {
+    pub fn new(put_object_cmd: String, get_object_cmd: String) -> Self {
+        Self {
+            put_object_cmd,
+            get_object_cmd,
+        }
+    }
+
+    fn spawn(cmd: &str, name: &str, stdin: Stdio) -> Result<std::process::Child> {
+        Ok(ProcessCommand::new("bash")
+            .args(&["-c", cmd])
+            .env("OBJECT_NAME", name)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?)
+    }
+}
+
+impl ObjectStore for CommandObjectStore {
+    fn put_object(&self, name: &str, data: &[u8]) -> Result<()> {
+        let mut child = Self::spawn(&self.put_object_cmd, name, Stdio::piped())?;
+        child
+            .stdin
+            .take()
+            .expect("Stdin of the child is piped.")
+            .write_all(data)?;
+        let status = child.wait()?;
+        ensure!(
+            status.success(),
+            "Command putting object {} failed: {}",
+            name,
+            status
+        );
+        Ok(())
+    }
+
+    fn get_object(&self, name: &str) -> Result<Vec<u8>> {
+        let output = Self::spawn(&self.get_object_cmd, name, Stdio::null())?.wait_with_output()?;
+        ensure!(
+            output.status.success(),
+            "Command getting object {} failed: {}",
+            name,
+            output.status
+        );
+        Ok(output.stdout)
+    }
+}
+
+pub fn create_object_store(backend: &ColdStorageBackend) -> Arc<dyn ObjectStore> {
+    match backend {
+        ColdStorageBackend::LocalDir { path } => Arc::new(LocalDirObjectStore::new(path.clone())),
+        ColdStorageBackend::Command {
+            put_object,
+            get_object,
+        } => Arc::new(CommandObjectStore::new(
+            put_object.clone(),
+            get_object.clone(),
+        )),
+    }
+}
+
+/// The transactions and events of the versions of a segment, as uploaded to the object store.
+#[derive(Debug, Deserialize, Serialize)]
+struct Segment {
+    /// The first version of the segment. It's the first version of the segment range unless the DB
+    /// didn't have the earlier versions (e.g., it was restored from a backup).
+    first_version: Version,
+    transactions: Vec<Transaction>,
+    events: Vec<Vec<ContractEvent>>,
+}
+
+/// The `ColdStore` is meant to be part of a `DiemDB` instance. It offloads old versions in a worker
+/// thread, which it creates on construction and joins on destruction.
+#[derive(Debug)]
+pub(crate) struct ColdStore {
+    /// How many latest versions are kept in the DB.
+    hot_window: u64,
+    inner: Arc<ColdStoreInner>,
+    /// The worker thread handle, only `None` after joined in `drop()`.
+    worker_thread: Option<JoinHandle<()>>,
+    /// The sender side of the channel talking to the worker thread.
+    command_sender: Mutex<Sender<Command>>,
+}
+
+impl ColdStore {
+    pub fn new(
+        db: Arc<DB>,
+        transaction_store: Arc<TransactionStore>,
+        event_store: Arc<EventStore>,
+        object_store: Arc<dyn ObjectStore>,
+        config: &ColdStorageConfig,
+    ) -> Result<Self> {
+        ensure!(config.segment_size > 0, "Segment size must be positive.");
+        let inner = Arc::new(ColdStoreInner::new(
+            db,
+            transaction_store,
+            event_store,
+            object_store,
+            config.segment_size,
+            config.cache_size,
+        )?);
+
+        let (command_sender, command_receiver) = channel();
+        let worker_inner = Arc::clone(&inner);
+        let worker_thread = std::thread::Builder::new()
+            .name("diemdb_cold_store".into())
+            .spawn(move || work(worker_inner, command_receiver))
+            .expect("Creating cold store thread should succeed.");
+
+        Ok(Self {
+            hot_window: config.hot_window,
+            inner,
+            worker_thread: Some(worker_thread),
+            command_sender: Mutex::new(command_sender),
+        })
+    }
+
+    /// Sends an offloading command to the worker thread when necessary.
+    pub fn wake(&self, latest_version: Version) {
+        if latest_version > self.hot_window {
+            self.command_sender
+                .lock()
+                .send(Command::Offload {
+                    first_hot_version: latest_version - self.hot_window,
+                })
+                .expect("Receiver should not destruct prematurely.");
+        }
+    }
+
+    /// Gets an offloaded transaction and, if requested, its events, verifying them against the
+    /// transaction info.
+    pub fn get_transaction(
+        &self,
+        version: Version,
+        txn_info: &TransactionInfo,
+        fetch_events: bool,
+    ) -> Result<(Transaction, Option<Vec<ContractEvent>>)> {
+        self.inner.get_transaction(version, txn_info, fetch_events)
+    }
+}
+
+impl Drop for ColdStore {
+    fn drop(&mut self) {
+        self.command_sender
+            .lock()
+            .send(Command::Quit)
+            .expect("Receiver should not destruct.");
+        self.worker_thread
+            .take()
+            .expect("Worker thread must exist.")
+            .join()
+            .expect("Worker thread should join peacefully.");
+    }
+}
+
+enum Command {
+    Quit,
+    Offload { first_hot_version: Version },
+}
+
+/// Offloads the segments before the latest requested first hot version, one at a time, until
+/// `Command::Quit` is received.
+fn work(inner: Arc<ColdStoreInner>, command_receiver: Receiver<Command>) {
+    let mut target_first_hot_version = 0;
+    let mut blocking_recv = true;
+    loop {
+        // Block on the next command if there's nothing to do, otherwise drain the channel.
+        loop {
+            let command = if blocking_recv {
+                command_receiver
+                    .recv()
+                    .expect("Sender should not destruct prematurely.")
+            } else {
+                match command_receiver.try_recv() {
+                    Ok(command) => command,
+                    Err(_) => break,
+                }
+            };
+            match command {
+                Command::Quit => return,
+                Command::Offload { first_hot_version } => {
+                    target_first_hot_version = target_first_hot_version.max(first_hot_version);
+                    blocking_recv = false;
+                }
+            }
+        }
+
+        match inner.offload_next_segment(target_first_hot_version) {
+            Ok(offloaded) => blocking_recv = !offloaded,
+            Err(e) => {
+                error!(error = ?e, "Error offloading to cold storage.");
+                // Stop retrying vigorously, until the next command.
+                blocking_recv = true;
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ColdStoreInner {
+    db: Arc<DB>,
+    transaction_store: Arc<TransactionStore>,
+    event_store: Arc<EventStore>,
+    object_store: Arc<dyn ObjectStore>,
+    segment_size: u64,
+    cache_size: usize,
+    /// The recently read segments by index, the most recently read last.
+    cache: Mutex<VecDeque<(u64, Arc<Segment>)>>,
+    /// Versions before this one are offloaded.
+    first_hot_version: AtomicU64,
+    /// Held for writing while a segment is deleted from the DB and `first_hot_version` advanced,
+    /// so that readers holding it for reading see both or neither.
+    offload_lock: RwLock<()>,
+}
+
+impl ColdStoreInner {
+    fn new(
+        db: Arc<DB>,
+        transaction_store: Arc<TransactionStore>,
+        event_store: Arc<EventStore>,
+        object_store: Arc<dyn ObjectStore>,
+        segment_size: u64,
+        cache_size: usize,
+    ) -> Result<Self> {
+        let inner = Self {
+            db,
+            transaction_store,
+            event_store,
+            object_store,
+            segment_size,
+            cache_size,
+            cache: Mutex::new(VecDeque::new()),
+            first_hot_version: AtomicU64::new(0),
+            offload_lock: RwLock::new(()),
+        };
+        if let Some(first_local_version) = inner.get_first_local_version()? {
+            inner.record_progress(first_local_version);
+        }
+        Ok(inner)
+    }
+
+    fn get_first_local_version(&self) -> Result<Option<Version>> {
+        let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        Ok(iter.next().transpose()?.map(|(version, _)| version))
+    }
+
+    fn record_progress(&self, first_hot_version: Version) {
+        self.first_hot_version
+            .store(first_hot_version, Ordering::Relaxed);
+        DIEM_STORAGE_COLD_STORE_FIRST_HOT_VERSION.set(first_hot_version as i64);
+    }
+
+    fn segment_name(segment_index: u64) -> String {
+        format!("ledger_segment_{}", segment_index)
+    }
+
+    /// Offloads the segment of the first version in the DB, if the whole segment is before
+    /// `target_first_hot_version`. Returns whether a segment was offloaded.
+    fn offload_next_segment(&self, target_first_hot_version: Version) -> Result<bool> {
+        let first_version = match self.get_first_local_version()? {
+            Some(first_version) => first_version,
+            None => return Ok(false),
+        };
+        let segment_index = first_version / self.segment_size;
+        let end_version = (segment_index + 1)
+            .checked_mul(self.segment_size)
+            .ok_or_else(|| format_err!("Segment end version overflows."))?;
+        if end_version > target_first_hot_version {
+            return Ok(false);
+        }
+
+        let num_versions = (end_version - first_version) as usize;
+        let transactions = self
+            .transaction_store
+            .get_transaction_iter(first_version, num_versions)?
+            .collect::<Result<Vec<_>>>()?;
+        let events = self
+            .event_store
+            .get_events_by_version_iter(first_version, num_versions)?
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(
+            transactions.len() == num_versions && events.len() == num_versions,
+            "Versions [{}, {}) are not all in the DB.",
+            first_version,
+            end_version,
+        );
+        let segment = Segment {
+            first_version,
+            transactions,
+            events,
+        };
+        self.object_store.put_object(
+            &Self::segment_name(segment_index),
+            &bcs::to_bytes(&segment)?,
+        )?;
+
+        let mut batch = SchemaBatch::new();
+        for version in first_version..end_version {
+            batch.delete::<TransactionSchema>(&version)?;
+        }
+        let mut iter = self.db.iter::<EventSchema>(ReadOptions::default())?;
+        iter.seek(&first_version)?;
+        for res in iter {
+            let ((version, index), _) = res?;
+            if version >= end_version {
+                break;
+            }
+            batch.delete::<EventSchema>(&(version, index))?;
+        }
+        {
+            let _lock = self.offload_lock.write();
+            self.db.write_schemas(batch)?;
+            self.record_progress(end_version);
+        }
+        info!(
+            segment_index = segment_index,
+            first_version = first_version,
+            end_version = end_version,
+            "Offloaded segment to cold storage."
+        );
+
+        Ok(true)
+    }
+
+    fn get_segment(&self, segment_index: u64) -> Result<Arc<Segment>> {
+        {
+            let mut cache = self.cache.lock();
+            if let Some(position) = cache.iter().position(|(index, _)| *index == segment_index) {
+                let entry = cache.remove(position).expect("Position is in range.");
+                let segment = Arc::clone(&entry.1);
+                cache.push_back(entry);
+                DIEM_STORAGE_COLD_STORE_READS
+                    .with_label_values(&["cache_hit"])
+                    .inc();
+                return Ok(segment);
+            }
+        }
+
+        DIEM_STORAGE_COLD_STORE_READS
+            .with_label_values(&["cache_miss"])
+            .inc();
+        let segment: Arc<Segment> = Arc::new(bcs::from_bytes(
+            &self
+                .object_store
+                .get_object(&Self::segment_name(segment_index))?,
+        )?);
+        let mut cache = self.cache.lock();
+        cache.push_back((segment_index, Arc::clone(&segment)));
+        while cache.len() > self.cache_size {
+            cache.pop_front();
+        }
+        Ok(segment)
+    }
+
+    fn get_transaction(
+        &self,
+        version: Version,
+        txn_info: &TransactionInfo,
+        fetch_events: bool,
+    ) -> Result<(Transaction, Option<Vec<ContractEvent>>)> {
+        ensure!(
+            version < self.first_hot_version.load(Ordering::Relaxed),
+            DiemDbError::NotFound(format!("Txn {}", version)),
+        );
+        let segment = self.get_segment(version / self.segment_size)?;
+        let index = version
+            .checked_sub(segment.first_version)
+            .ok_or_else(|| DiemDbError::NotFound(format!("Txn {}", version)))?
+            as usize;
+        let transaction = segment
+            .transactions
+            .get(index)
+            .ok_or_else(|| format_err!("Segment is missing txn {}.", version))?
+            .clone();
+        let events = segment
+            .events
+            .get(index)
+            .ok_or_else(|| format_err!("Segment is missing the events of txn {}.", version))?;
+
+        // Verify the offloaded data against the transaction info.
+        ensure!(
+            transaction.hash() == txn_info.transaction_hash(),
+            "Offloaded txn {} doesn't match its transaction info.",
+            version,
+        );
+        let event_hashes: Vec<_> = events.iter().map(CryptoHash::hash).collect();
+        ensure!(
+            InMemoryAccumulator::<EventAccumulatorHasher>::from_leaves(&event_hashes).root_hash()
+                == txn_info.event_root_hash(),
+            "Offloaded events of txn {} don't match the transaction info.",
+            version,
+        );
+
+        Ok((transaction, fetch_events.then(|| events.clone())))
+    }
+}
+
+/// Yields the transactions of a range of versions together with their transaction infos and, if
+/// requested, their events, reading the offloaded versions from the cold store.
+pub(crate) struct LedgerIter<'a> {
+    cold_store: Option<&'a ColdStore>,
+    /// Versions before this one are read from the cold store, the others from the DB iterators.
+    first_hot_version: Version,
+    next_version: Version,
+    txn_info_iter: TransactionInfoIter<'a>,
+    txn_iter: TransactionIter<'a>,
+    events_iter: Option<EventsByVersionIter<'a>>,
+}
+
+impl<'a> LedgerIter<'a> {
+    pub fn new(
+        cold_store: Option<&'a ColdStore>,
+        ledger_store: &'a LedgerStore,
+        transaction_store: &'a TransactionStore,
+        event_store: &'a EventStore,
+        start_version: Version,
+        num_versions: usize,
+        fetch_events: bool,
+    ) -> Result<Self> {
+        let end_version = start_version
+            .checked_add(num_versions as u64)
+            .ok_or_else(|| format_err!("Too many versions requested."))?;
+        // Nothing is offloaded while the DB iterators are created, so that they see all versions
+        // from the first hot version on.
+        let _lock = cold_store.map(|cold_store| cold_store.inner.offload_lock.read());
+        let first_hot_version = cold_store.map_or(start_version, |cold_store| {
+            cold_store
+                .inner
+                .first_hot_version
+                .load(Ordering::Relaxed)
+                .max(start_version)
+                .min(end_version)
+        });
+        let num_hot_versions = (end_version - first_hot_version) as usize;
+        let events_iter = if fetch_events {
+            Some(event_store.get_events_by_version_iter(first_hot_version, num_hot_versions)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            cold_store,
+            first_hot_version,
+            next_version: start_version,
+            txn_info_iter: ledger_store.get_transaction_info_iter(start_version, num_versions)?,
+            txn_iter: transaction_store
+                .get_transaction_iter(first_hot_version, num_hot_versions)?,
+            events_iter,
+        })
+    }
+
+    fn next_impl(
+        &mut self,
+    ) -> Result<Option<(Transaction, TransactionInfo, Option<Vec<ContractEvent>>)>> {
+        let txn_info = match self.txn_info_iter.next().transpose()? {
+            Some(txn_info) => txn_info,
+            None => return Ok(None),
+        };
+        let version = self.next_version;
+        self.next_version += 1;
+
+        if version < self.first_hot_version {
+            let cold_store = self
+                .cold_store
+                .expect("Versions are offloaded only if there's a cold store.");
+            let (txn, events) =
+                cold_store.get_transaction(version, &txn_info, self.events_iter.is_some())?;
+            return Ok(Some((txn, txn_info, events)));
+        }
+        let txn = self
+            .txn_iter
+            .next()
+            .transpose()?
+            .ok_or_else(|| format_err!("Missing txn {}.", version))?;
+        let events = match &mut self.events_iter {
+            Some(events_iter) => Some(
+                events_iter
+                    .next()
+                    .transpose()?
+                    .ok_or_else(|| format_err!("Missing the events of txn {}.", version))?,
+            ),
+            None => None,
+        };
+        Ok(Some((txn, txn_info, events)))
+    }
+}
+
+impl<'a> Iterator for LedgerIter<'a> {
+    type Item = Result<(Transaction, TransactionInfo, Option<Vec<ContractEvent>>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_impl().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test;
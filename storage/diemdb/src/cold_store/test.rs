@@ -0,0 +1,242 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{change_set::ChangeSet, test_helper::arb_blocks_to_commit, DiemDB, MAX_LIMIT};
+use diem_crypto::HashValue;
+use diem_temppath::TempPath;
+use diem_types::{
+    account_address::AccountAddress, block_metadata::BlockMetadata, event::EventKey,
+    vm_status::KeptVMStatus,
+};
+use move_core_types::language_storage::TypeTag;
+use proptest::prelude::*;
+use std::collections::BTreeSet;
+use storage_interface::{DbReader, DbWriter, Order};
+
+const SEGMENT_SIZE: u64 = 4;
+
+// Saves `num_versions` transactions emitting an event each from `first_version`, and returns the
+// transaction infos of all versions.
+fn init_db(
+    db: &DiemDB,
+    first_version: Version,
+    num_versions: u64,
+) -> Vec<(Version, TransactionInfo)> {
+    let key = EventKey::random();
+    let mut cs = ChangeSet::new();
+    let txn_infos = (first_version..first_version + num_versions)
+        .map(|version| {
+            let txn = Transaction::BlockMetadata(BlockMetadata::new(
+                HashValue::random(),
+                version,
+                version,
+                vec![],
+                AccountAddress::random(),
+            ));
+            let event = ContractEvent::new(key, version, TypeTag::Bool, vec![]);
+            db.transaction_store
+                .put_transaction(version, &txn, &mut cs)
+                .unwrap();
+            let event_root_hash = db
+                .event_store
+                .put_events(version, &[event], &mut cs)
+                .unwrap();
+            let txn_info = TransactionInfo::new(
+                txn.hash(),
+                HashValue::zero(),
+                event_root_hash,
+                0,
+                KeptVMStatus::Executed,
+            );
+            (version, txn_info)
+        })
+        .collect();
+    db.db.write_schemas(cs.batch).unwrap();
+    txn_infos
+}
+
+fn create_cold_store(db: &DiemDB, object_dir: &TempPath) -> ColdStoreInner {
+    ColdStoreInner::new(
+        Arc::clone(&db.db),
+        Arc::clone(&db.transaction_store),
+        Arc::clone(&db.event_store),
+        Arc::new(LocalDirObjectStore::new(object_dir.path().to_path_buf())),
+        SEGMENT_SIZE,
+        1, /* cache_size */
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_offload_and_read() {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    let txn_infos = init_db(&db, 0, 10);
+    let object_dir = TempPath::new();
+    let cold_store = create_cold_store(&db, &object_dir);
+
+    // Only whole segments before the target are offloaded.
+    assert!(cold_store.offload_next_segment(9).unwrap());
+    assert!(cold_store.offload_next_segment(9).unwrap());
+    assert!(!cold_store.offload_next_segment(9).unwrap());
+    assert_eq!(cold_store.first_hot_version.load(Ordering::Relaxed), 8);
+    assert!(db.transaction_store.get_transaction(7).is_err());
+    assert!(db.event_store.get_events_by_version(7).unwrap().is_empty());
+    assert!(db.transaction_store.get_transaction(8).is_ok());
+
+    // Offloaded transactions and events are read back and verified.
+    for (version, txn_info) in &txn_infos[..8] {
+        let (txn, events) = cold_store
+            .get_transaction(*version, txn_info, true /* fetch_events */)
+            .unwrap();
+        assert_eq!(txn.hash(), txn_info.transaction_hash());
+        assert_eq!(events.unwrap().len(), 1);
+    }
+    assert!(cold_store
+        .get_transaction(8, &txn_infos[8].1, true /* fetch_events */)
+        .is_err());
+
+    // Offloaded data not matching the transaction infos is rejected.
+    assert!(cold_store
+        .get_transaction(1, &txn_infos[2].1, false /* fetch_events */)
+        .is_err());
+
+    // The offloading progress is found again upon restart.
+    let cold_store = create_cold_store(&db, &object_dir);
+    assert_eq!(cold_store.first_hot_version.load(Ordering::Relaxed), 8);
+    assert!(cold_store
+        .get_transaction(5, &txn_infos[5].1, false /* fetch_events */)
+        .is_ok());
+}
+
+#[test]
+fn test_offload_partial_segment() {
+    // As if the DB was restored from version 2.
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    let txn_infos = init_db(&db, 2, 4);
+    let object_dir = TempPath::new();
+    let cold_store = create_cold_store(&db, &object_dir);
+
+    assert!(cold_store.offload_next_segment(4).unwrap());
+    assert_eq!(cold_store.first_hot_version.load(Ordering::Relaxed), 4);
+    for (version, txn_info) in &txn_infos[..2] {
+        assert!(cold_store
+            .get_transaction(*version, txn_info, true /* fetch_events */)
+            .is_ok());
+    }
+    // Versions the DB never had are not found.
+    let err = cold_store
+        .get_transaction(1, &txn_infos[0].1, false /* fetch_events */)
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DiemDbError>(),
+        Some(DiemDbError::NotFound(_))
+    ));
+}
+
+/// Reads all transactions and events of the DB through every read API which serves them.
+fn read_all(db: &DiemDB, ledger_version: Version) -> Vec<String> {
+    let num_txns = ledger_version + 1;
+    let mut reads = vec![];
+
+    let backup_txns = db
+        .get_backup_handler()
+        .get_transaction_iter(0, num_txns as usize)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(backup_txns.len() as u64, num_txns);
+    reads.push(format!("{:?}", backup_txns));
+
+    let txn_lists = db
+        .get_transaction_iterator(0, num_txns, 3, ledger_version, true /* fetch_events */)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    reads.push(format!("{:?}", txn_lists));
+
+    let mut event_keys = BTreeSet::new();
+    let mut type_tags = BTreeSet::new();
+    for (txn, _, events) in &backup_txns {
+        let by_hash = db
+            .get_transaction_by_hash(txn.hash(), true /* include_events */, ledger_version)
+            .unwrap();
+        assert!(by_hash.is_some());
+        reads.push(format!("{:?}", by_hash));
+        if let Transaction::UserTransaction(signed_txn) = txn {
+            let by_account = db
+                .get_account_transactions(
+                    signed_txn.sender(),
+                    signed_txn.sequence_number(),
+                    1,    /* limit */
+                    true, /* include_events */
+                    ledger_version,
+                )
+                .unwrap();
+            assert_eq!(by_account.len(), 1);
+            reads.push(format!("{:?}", by_account));
+        }
+        for event in events {
+            event_keys.insert(*event.key());
+            type_tags.insert(event.type_tag().clone());
+        }
+    }
+    for event_key in &event_keys {
+        let events = db
+            .get_events_with_proofs(event_key, 0, Order::Ascending, MAX_LIMIT, None)
+            .unwrap();
+        assert!(!events.is_empty());
+        reads.push(format!("{:?}", events));
+    }
+    for type_tag in &type_tags {
+        let events = db.get_events_by_type_tag(type_tag, 0, MAX_LIMIT).unwrap();
+        assert!(!events.is_empty());
+        reads.push(format!("{:?}", events));
+    }
+    reads
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_read_offloaded(input in arb_blocks_to_commit()) {
+        let tmp_dir = TempPath::new();
+        let mut db = DiemDB::new_for_test(&tmp_dir);
+        let object_dir = TempPath::new();
+        db.enable_cold_storage(&ColdStorageConfig {
+            // Segments are only offloaded by the test.
+            hot_window: u64::MAX,
+            segment_size: SEGMENT_SIZE,
+            cache_size: 1,
+            backend: ColdStorageBackend::LocalDir {
+                path: object_dir.path().to_path_buf(),
+            },
+        })
+        .unwrap();
+
+        let mut cur_ver = 0;
+        for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+            db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+                .unwrap();
+            cur_ver += txns_to_commit.len() as u64;
+        }
+        let ledger_version = cur_ver - 1;
+        let expected = read_all(&db, ledger_version);
+
+        let cold_store = &db.cold_store.as_ref().unwrap().inner;
+        while cold_store.offload_next_segment(cur_ver).unwrap() {}
+        let first_hot_version = cur_ver / SEGMENT_SIZE * SEGMENT_SIZE;
+        prop_assert_eq!(
+            cold_store.first_hot_version.load(Ordering::Relaxed),
+            first_hot_version
+        );
+        if first_hot_version > 0 {
+            prop_assert!(db.transaction_store.get_transaction(0).is_err());
+        }
+
+        prop_assert_eq!(read_all(&db, ledger_version), expected);
+    }
+}
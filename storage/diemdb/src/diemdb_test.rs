@@ -11,7 +11,6 @@ use diem_crypto::hash::CryptoHash;
 #[allow(unused_imports)]
 use diem_jellyfish_merkle::node_type::{Node, NodeKey};
 use diem_temppath::TempPath;
-use diem_types::transaction::Transaction;
 #[allow(unused_imports)]
 use diem_types::{
     account_address::{AccountAddress, HashAccountAddress},
@@ -21,8 +20,9 @@ use diem_types::{
     proof::SparseMerkleLeafNode,
     vm_status::{KeptVMStatus, StatusCode},
 };
+use diem_types::{account_state_blob::AccountResourcesVerifier, transaction::Transaction};
 use proptest::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 fn verify_epochs(db: &DiemDB, ledger_infos_with_sigs: &[LedgerInfoWithSignatures]) {
     const LIMIT: usize = 2;
@@ -137,6 +137,171 @@ pub fn test_save_blocks_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWit
     );
     let (_, ledger_infos_with_sigs): (Vec<_>, Vec<_>) = input.iter().cloned().unzip();
     verify_epochs(&db, &ledger_infos_with_sigs);
+    verify_events_by_type_tag(&db, &all_committed_txns);
+    verify_write_set_updates(&db, &all_committed_txns);
+    verify_events_with_proofs_batched(&db, &all_committed_txns, latest_ledger_info.ledger_info());
+}
+
+fn test_state_snapshot_restore_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(txns_to_commit, cur_ver, Some(ledger_info_with_sigs))
+            .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+    }
+    let (all_txns, ledger_infos): (Vec<_>, Vec<_>) = input.into_iter().unzip();
+    let all_txns = all_txns.into_iter().flatten().collect::<Vec<_>>();
+    let latest_li = ledger_infos.last().unwrap().clone();
+    let version = latest_li.ledger_info().version();
+
+    // Restore the state at the latest version into an empty db, in chunks
+    let restore_tmp_dir = TempPath::new();
+    let restore_db = DiemDB::new_for_test(&restore_tmp_dir);
+    let state_root_hash = db.state_store.get_root_hash(version).unwrap();
+    let mut receiver = restore_db
+        .get_state_snapshot_receiver(version, state_root_hash)
+        .unwrap();
+    let limit = 2;
+    let mut start_key = HashValue::zero();
+    loop {
+        let chunk = db
+            .get_account_state_chunk_with_proof(version, start_key, limit)
+            .unwrap();
+        if chunk.account_blobs.is_empty() {
+            break;
+        }
+        let next_start_key = chunk.next_start_key();
+        let is_last_chunk = (chunk.account_blobs.len() as u64) < limit;
+        receiver
+            .add_chunk(chunk.account_blobs, chunk.proof)
+            .unwrap();
+        match next_start_key {
+            Some(next_start_key) if !is_last_chunk => start_key = next_start_key,
+            _ => break,
+        }
+    }
+    receiver.finish().unwrap();
+
+    let frozen_subtrees = db.ledger_store.get_frozen_subtree_hashes(version).unwrap();
+    let txn_list = db.get_transactions(version, 1, version, true).unwrap();
+
+    // Nothing is written if the accumulator doesn't match the ledger info
+    let mut bad_frozen_subtrees = frozen_subtrees.clone();
+    match bad_frozen_subtrees.first_mut() {
+        Some(first) => *first = HashValue::zero(),
+        None => bad_frozen_subtrees.push(HashValue::zero()),
+    }
+    assert!(restore_db
+        .finalize_state_snapshot(
+            version,
+            &bad_frozen_subtrees,
+            &txn_list.transactions[0],
+            &txn_list.proof.transaction_infos[0],
+            &txn_list.events.as_ref().unwrap()[0],
+            &[latest_li.clone()],
+        )
+        .is_err());
+    assert!(restore_db.get_latest_ledger_info().is_err());
+    assert!(restore_db
+        .ledger_store
+        .get_transaction_info(version)
+        .is_err());
+    assert!(restore_db
+        .ledger_store
+        .get_frozen_subtree_hashes(version + 1)
+        .is_err());
+
+    restore_db
+        .finalize_state_snapshot(
+            version,
+            &frozen_subtrees,
+            &txn_list.transactions[0],
+            &txn_list.proof.transaction_infos[0],
+            &txn_list.events.as_ref().unwrap()[0],
+            &[latest_li.clone()],
+        )
+        .unwrap();
+
+    // Verify the restored db serves the latest state and transaction
+    assert_eq!(restore_db.get_latest_ledger_info().unwrap(), latest_li);
+    assert_eq!(
+        restore_db.get_accumulator_root_hash(version).unwrap(),
+        latest_li.ledger_info().transaction_accumulator_hash()
+    );
+    assert_eq!(
+        restore_db
+            .get_transactions(version, 1, version, true)
+            .unwrap(),
+        txn_list
+    );
+    let latest_account_states = all_txns
+        .iter()
+        .flat_map(|txn_to_commit| txn_to_commit.account_states().clone())
+        .collect::<HashMap<_, _>>();
+    for (address, blob) in latest_account_states {
+        let (account_state_with_proof, _) = restore_db
+            .get_account_state_with_proof_by_version(address, version)
+            .unwrap();
+        assert_eq!(account_state_with_proof, Some(blob));
+    }
+}
+
+fn test_transaction_iterator_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+
+    let mut cur_ver = 0;
+    let mut all_committed_txns = vec![];
+    for (txns_to_commit, ledger_info_with_sigs) in input.iter() {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        all_committed_txns.extend_from_slice(txns_to_commit);
+        cur_ver += txns_to_commit.len() as u64;
+    }
+
+    // Iterate over all transactions (and beyond the ledger version) in chunks of 3
+    let ledger_info = input.last().unwrap().1.ledger_info();
+    let ledger_version = ledger_info.version();
+    let chunk_size = 3;
+    let mut next_version = 0;
+    for txn_list_with_proof in db
+        .get_transaction_iterator(0, cur_ver + 10, chunk_size, ledger_version, true)
+        .unwrap()
+    {
+        let txn_list_with_proof = txn_list_with_proof.unwrap();
+        txn_list_with_proof
+            .verify(ledger_info, Some(next_version))
+            .unwrap();
+        assert!(txn_list_with_proof.len() as u64 <= chunk_size);
+        for (txn, events) in txn_list_with_proof
+            .transactions
+            .iter()
+            .zip(txn_list_with_proof.events.unwrap())
+        {
+            let txn_to_commit = &all_committed_txns[next_version as usize];
+            assert_eq!(txn, txn_to_commit.transaction());
+            assert_eq!(events, txn_to_commit.events().to_vec());
+            next_version += 1;
+        }
+    }
+    assert_eq!(next_version, ledger_version + 1);
+
+    // Verify an empty range yields no chunks
+    assert!(db
+        .get_transaction_iterator(ledger_version + 1, 10, chunk_size, ledger_version, false)
+        .unwrap()
+        .next()
+        .is_none());
 }
 
 fn test_sync_transactions_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
@@ -203,6 +368,8 @@ fn get_events_by_event_key(
             order,
             LIMIT,
             ledger_info.version(),
+            &db.ledger_store
+                .get_batched_transaction_info_with_proof_reader(ledger_info.version()),
         )?;
 
         let num_events = events_with_proof.len() as u64;
@@ -319,6 +486,107 @@ fn verify_events_by_event_key(
         .unwrap();
 }
 
+fn verify_events_by_type_tag(db: &DiemDB, txns_to_commit: &[TransactionToCommit]) {
+    let mut events_by_type_tag: HashMap<TypeTag, Vec<(Version, ContractEvent)>> = HashMap::new();
+    for (version, txn) in txns_to_commit.iter().enumerate() {
+        for event in txn.events() {
+            events_by_type_tag
+                .entry(event.type_tag().clone())
+                .or_default()
+                .push((version as Version, event.clone()));
+        }
+    }
+
+    for (type_tag, expected_events) in events_by_type_tag {
+        let events = db.get_events_by_type_tag(&type_tag, 0, MAX_LIMIT).unwrap();
+        let expected_events = expected_events
+            .into_iter()
+            .take(MAX_LIMIT as usize)
+            .collect::<Vec<_>>();
+        assert_eq!(events, expected_events);
+
+        // Verify no events emitted before the start version are returned
+        let (last_version, _) = expected_events.last().unwrap();
+        let events = db
+            .get_events_by_type_tag(&type_tag, *last_version, MAX_LIMIT)
+            .unwrap();
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|(version, _)| version == last_version));
+    }
+}
+
+fn verify_write_set_updates(db: &DiemDB, txns_to_commit: &[TransactionToCommit]) {
+    let mut updates_by_access_path: HashMap<AccessPath, Vec<(Version, WriteOp)>> = HashMap::new();
+    for (version, txn) in txns_to_commit.iter().enumerate() {
+        for (access_path, write_op) in txn.write_set() {
+            updates_by_access_path
+                .entry(access_path.clone())
+                .or_default()
+                .push((version as Version, write_op.clone()));
+        }
+    }
+
+    for (access_path, expected_updates) in updates_by_access_path {
+        let updates = db
+            .get_write_set_updates(&access_path, 0, MAX_LIMIT)
+            .unwrap();
+        let expected_updates = expected_updates
+            .into_iter()
+            .take(MAX_LIMIT as usize)
+            .collect::<Vec<_>>();
+        assert_eq!(updates, expected_updates);
+
+        // Verify no updates written before the start version are returned
+        let (last_version, _) = expected_updates.last().unwrap();
+        let updates = db
+            .get_write_set_updates(&access_path, *last_version, MAX_LIMIT)
+            .unwrap();
+        assert_eq!(updates, vec![expected_updates.last().unwrap().clone()]);
+    }
+}
+
+fn verify_events_with_proofs_batched(
+    db: &DiemDB,
+    txns_to_commit: &[TransactionToCommit],
+    ledger_info: &LedgerInfo,
+) {
+    const LIMIT: u64 = 3;
+
+    let requests: Vec<_> = group_events_by_event_key(0, txns_to_commit)
+        .into_iter()
+        .map(|(event_key, events)| {
+            let (_, first_event) = events.first().expect("Shouldn't be empty");
+            (event_key, first_event.sequence_number(), LIMIT)
+        })
+        .take((MAX_LIMIT / LIMIT) as usize)
+        .collect();
+    let events_with_proofs = db
+        .get_events_with_proofs_batched(&requests, Some(ledger_info.version()))
+        .unwrap();
+    EventWithProof::verify_batch(ledger_info, &requests, &events_with_proofs).unwrap();
+
+    // Verify the batched results are the same as the unbatched ones
+    for ((event_key, start, limit), events) in requests.iter().zip(&events_with_proofs) {
+        let expected_events = db
+            .get_events_with_proofs(
+                event_key,
+                *start,
+                Order::Ascending,
+                *limit,
+                Some(ledger_info.version()),
+            )
+            .unwrap();
+        assert_eq!(events, &expected_events);
+    }
+
+    // Verify the limits of all requests together are bounded
+    let event_key = EventKey::random();
+    let requests = [(event_key, 0, MAX_LIMIT), (event_key, 0, 1)];
+    assert!(db
+        .get_events_with_proofs_batched(&requests, Some(ledger_info.version()))
+        .is_err());
+}
+
 fn group_events_by_event_key(
     first_version: Version,
     txns_to_commit: &[TransactionToCommit],
@@ -496,6 +764,14 @@ fn verify_committed_transactions(
             .verify_user_txn(ledger_info, cur_ver, txn.sender(), txn.sequence_number())
             .unwrap();
 
+        let txn_with_proof = db
+            .get_transaction_by_hash(txn_info.transaction_hash(), true, ledger_version)
+            .unwrap()
+            .expect("Should exist.");
+        txn_with_proof
+            .verify_user_txn(ledger_info, cur_ver, txn.sender(), txn.sequence_number())
+            .unwrap();
+
         let acct_txns_with_proof = db
             .get_account_transactions(txn.sender(), txn.sequence_number(), 1, true, ledger_version)
             .unwrap();
@@ -528,6 +804,7 @@ fn verify_committed_transactions(
             account_state_with_proof
                 .verify(ledger_info, cur_ver, *addr)
                 .unwrap();
+            verify_account_resources(db, *addr, expected_blob, cur_ver, ledger_info);
         }
 
         cur_ver += 1;
@@ -545,6 +822,221 @@ fn verify_committed_transactions(
     verify_account_txns(db, group_txns_by_account(txns_to_commit), ledger_info);
 }
 
+fn verify_account_resources(
+    db: &DiemDB,
+    address: AccountAddress,
+    expected_blob: &AccountStateBlob,
+    version: Version,
+    ledger_info: &LedgerInfo,
+) {
+    // Fetch the resources a page of a single resource at a time.
+    let mut verifier = AccountResourcesVerifier::new(version, address);
+    let mut start_path = vec![];
+    while !verifier.is_complete() {
+        let page = db
+            .get_account_resources_with_proof(
+                address,
+                &start_path,
+                1,
+                version,
+                ledger_info.version(),
+            )
+            .unwrap();
+        assert!(page.resources.len() <= 1);
+        if let Some(next_start_path) = page.next_start_path() {
+            start_path = next_start_path;
+        }
+        verifier.add_page(page).unwrap();
+    }
+    assert_eq!(
+        verifier.finish(ledger_info).unwrap(),
+        Some(AccountState::try_from(expected_blob).unwrap())
+    );
+}
+
+fn test_checkpoint_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    let checkpoint_dir = TempPath::new();
+
+    let mut cur_ver = 0;
+    for (batch_idx, (txns_to_commit, ledger_info_with_sigs)) in input.iter().enumerate() {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        cur_ver += txns_to_commit.len() as u64;
+        if batch_idx == 0 {
+            db.create_checkpoint(&checkpoint_dir).unwrap();
+        }
+    }
+    // The checkpoint path must not exist.
+    assert!(db.create_checkpoint(&checkpoint_dir).is_err());
+
+    // The checkpoint has the first batch only.
+    let checkpoint = DiemDB::open(
+        &checkpoint_dir,
+        true, /* readonly */
+        None, /* pruner */
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    let (first_batch, first_ledger_info_with_sigs) = input.first().unwrap();
+    assert_eq!(
+        checkpoint.get_latest_ledger_info().unwrap(),
+        *first_ledger_info_with_sigs
+    );
+    verify_committed_transactions(
+        &checkpoint,
+        first_batch,
+        0, /* first_version */
+        first_ledger_info_with_sigs,
+        true, /* is_latest */
+    );
+}
+
+fn test_read_replica_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    let secondary_dir = TempPath::new();
+
+    let (first_batch, first_ledger_info_with_sigs) = input.first().unwrap();
+    db.save_transactions(first_batch, 0, Some(first_ledger_info_with_sigs))
+        .unwrap();
+    // Only catches up when asked to within the test.
+    let replica = DiemDB::open_as_read_replica(
+        &tmp_dir,
+        &secondary_dir,
+        Duration::from_secs(3600), /* catch_up_interval */
+        RocksdbConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        replica.get_latest_ledger_info().unwrap(),
+        *first_ledger_info_with_sigs
+    );
+
+    let mut cur_ver = first_batch.len() as Version;
+    for (txns_to_commit, ledger_info_with_sigs) in &input[1..] {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        cur_ver += txns_to_commit.len() as Version;
+    }
+
+    // The replica serves the latest data once caught up.
+    replica.try_catch_up_with_primary().unwrap();
+    let (last_batch, last_ledger_info_with_sigs) = input.last().unwrap();
+    assert_eq!(
+        replica.get_latest_ledger_info().unwrap(),
+        *last_ledger_info_with_sigs
+    );
+    verify_committed_transactions(
+        &replica,
+        last_batch,
+        cur_ver - last_batch.len() as Version, /* first_version */
+        last_ledger_info_with_sigs,
+        true, /* is_latest */
+    );
+}
+
+fn test_state_checkpoints_impl(input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>) {
+    const INTERVAL: u64 = 3;
+
+    let tmp_dir = TempPath::new();
+    let mut db = DiemDB::new_for_test(&tmp_dir);
+    db.enable_state_checkpoints(INTERVAL).unwrap();
+
+    let mut expected_checkpoints = BTreeMap::new();
+    let mut epoch_states = HashMap::new();
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        cur_ver += txns_to_commit.len() as Version;
+
+        // The first ledger info at or after a checkpoint is kept.
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        expected_checkpoints
+            .entry(ledger_info.version() / INTERVAL * INTERVAL)
+            .or_insert_with(|| ledger_info_with_sigs.clone());
+        if let Some(epoch_state) = ledger_info.next_epoch_state() {
+            epoch_states.insert(epoch_state.epoch, epoch_state.clone());
+        }
+    }
+
+    for version in 0..cur_ver {
+        let checkpoint = db.get_state_checkpoint(Some(version)).unwrap();
+        let (checkpoint_version, ledger_info_with_sigs) =
+            expected_checkpoints.range(..=version).next_back().unwrap();
+        assert_eq!(checkpoint.version(), *checkpoint_version);
+        assert_eq!(checkpoint.ledger_info_with_sigs(), ledger_info_with_sigs);
+        assert_eq!(
+            checkpoint.state_root_hash(),
+            db.ledger_store
+                .get_transaction_info(*checkpoint_version)
+                .unwrap()
+                .state_root_hash()
+        );
+        // The validators of the first epoch are not known from the ledger.
+        if let Some(epoch_state) = epoch_states.get(&checkpoint.epoch()) {
+            checkpoint.verify(epoch_state).unwrap();
+        }
+    }
+    assert_eq!(
+        db.get_state_checkpoint(None).unwrap().version(),
+        *expected_checkpoints.keys().next_back().unwrap()
+    );
+}
+
+fn test_get_all_accounts_at_version_impl(
+    input: Vec<(Vec<TransactionToCommit>, LedgerInfoWithSignatures)>,
+) {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+
+    let mut expected_accounts = HashMap::new();
+    let mut cur_ver = 0;
+    for (txns_to_commit, ledger_info_with_sigs) in &input {
+        db.save_transactions(
+            txns_to_commit,
+            cur_ver, /* first_version */
+            Some(ledger_info_with_sigs),
+        )
+        .unwrap();
+        cur_ver += txns_to_commit.len() as Version;
+        for txn_to_commit in txns_to_commit {
+            expected_accounts.extend(txn_to_commit.account_states().clone());
+        }
+    }
+    let latest_version = cur_ver - 1;
+
+    let accounts = db
+        .get_all_accounts_at_version(latest_version)
+        .unwrap()
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+    // Accounts are in the order of the hashes of their addresses.
+    assert!(accounts
+        .windows(2)
+        .all(|pair| pair[0].0.hash() < pair[1].0.hash()));
+    assert_eq!(
+        accounts.into_iter().collect::<HashMap<_, _>>(),
+        expected_accounts
+    );
+
+    assert!(db.get_all_accounts_at_version(latest_version + 1).is_err());
+}
+
 proptest! {
     #![proptest_config(ProptestConfig::with_cases(10))]
 
@@ -557,6 +1049,36 @@ proptest! {
     fn test_sync_transactions(input in arb_blocks_to_commit()) {
         test_sync_transactions_impl(input);
     }
+
+    #[test]
+    fn test_transaction_iterator(input in arb_blocks_to_commit()) {
+        test_transaction_iterator_impl(input);
+    }
+
+    #[test]
+    fn test_state_snapshot_restore(input in arb_blocks_to_commit()) {
+        test_state_snapshot_restore_impl(input);
+    }
+
+    #[test]
+    fn test_checkpoint(input in arb_blocks_to_commit()) {
+        test_checkpoint_impl(input);
+    }
+
+    #[test]
+    fn test_read_replica(input in arb_blocks_to_commit()) {
+        test_read_replica_impl(input);
+    }
+
+    #[test]
+    fn test_state_checkpoints(input in arb_blocks_to_commit()) {
+        test_state_checkpoints_impl(input);
+    }
+
+    #[test]
+    fn test_get_all_accounts_at_version(input in arb_blocks_to_commit()) {
+        test_get_all_accounts_at_version_impl(input);
+    }
 }
 
 #[test]
@@ -600,6 +1122,36 @@ fn test_too_many_requested() {
     assert!(db.get_transactions(0, 1001 /* limit */, 0, true).is_err());
 }
 
+#[test]
+fn test_estimate_read_amplification() {
+    let tmp_dir = TempPath::new();
+    let db = DiemDB::new_for_test(&tmp_dir);
+    assert_eq!(
+        estimate_read_amplification(&db.db, JELLYFISH_MERKLE_NODE_CF_NAME).unwrap(),
+        0
+    );
+
+    // Each flush adds a file to L0, all of which a lookup may need to read.
+    for version in 0..2 {
+        db.db
+            .put::<JellyfishMerkleNodeSchema>(
+                &NodeKey::new_empty_path(version),
+                &Node::new_leaf(HashValue::random(), AccountStateBlob::from(vec![1])),
+            )
+            .unwrap();
+        db.db.flush_all().unwrap();
+    }
+    assert_eq!(
+        estimate_read_amplification(&db.db, JELLYFISH_MERKLE_NODE_CF_NAME).unwrap(),
+        2
+    );
+    assert_eq!(
+        estimate_read_amplification(&db.db, EVENT_CF_NAME).unwrap(),
+        0
+    );
+    db.update_rocksdb_properties().unwrap();
+}
+
 #[test]
 fn test_get_latest_tree_state() {
     let tmp_dir = TempPath::new();
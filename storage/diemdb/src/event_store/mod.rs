@@ -11,8 +11,11 @@ use crate::{
     errors::DiemDbError,
     ledger_counters::{LedgerCounter, LedgerCounterBumps},
     schema::{
-        event::EventSchema, event_accumulator::EventAccumulatorSchema,
-        event_by_key::EventByKeySchema, event_by_version::EventByVersionSchema,
+        event::EventSchema,
+        event_accumulator::EventAccumulatorSchema,
+        event_by_key::EventByKeySchema,
+        event_by_type_tag::{type_tag_hash, EventByTypeTagSchema},
+        event_by_version::EventByVersionSchema,
     },
 };
 use accumulator::{HashReader, MerkleAccumulator};
@@ -30,6 +33,7 @@ use diem_types::{
     proof::{position::Position, EventAccumulatorProof, EventProof},
     transaction::Version,
 };
+use move_core_types::language_storage::TypeTag;
 use schemadb::{schema::ValueCodec, ReadOptions, SchemaIterator, DB};
 use std::{
     convert::{TryFrom, TryInto},
@@ -83,7 +87,7 @@ impl EventStore {
         })
     }
 
-    fn get_event_by_version_and_index(
+    pub fn get_event_by_version_and_index(
         &self,
         version: Version,
         index: u64,
@@ -112,13 +116,22 @@ impl EventStore {
             _ => unreachable!(), // since we've already got at least one event above
         };
 
-        // Get proof.
-        let proof =
-            Accumulator::get_proof(&EventHashReader::new(self, version), num_events, index)?;
+        let proof = self.get_event_proof_by_version_and_index(version, num_events, index)?;
 
         Ok((event, proof))
     }
 
+    /// Get the proof of the event at `index` among the `num_events` events of the transaction at
+    /// `version`, which only needs the event accumulator, not the events.
+    pub fn get_event_proof_by_version_and_index(
+        &self,
+        version: Version,
+        num_events: u64,
+        index: u64,
+    ) -> Result<EventAccumulatorProof> {
+        Accumulator::get_proof(&EventHashReader::new(self, version), num_events, index)
+    }
+
     fn get_txn_ver_by_seq_num(&self, event_key: &EventKey, seq_num: u64) -> Result<u64> {
         let (ver, _) = self
             .db
@@ -207,6 +220,35 @@ impl EventStore {
         Ok(result)
     }
 
+    /// Given `type_tag` and `start_version`, returns up to `limit` events of the type identified by
+    /// transaction version and index among all events emitted by the same transaction. Result
+    /// won't contain records with a transaction version > `ledger_version` and is in ascending
+    /// order.
+    pub fn lookup_events_by_type_tag(
+        &self,
+        type_tag: &TypeTag,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<(Version, u64)>> {
+        let tag_hash = type_tag_hash(type_tag)?;
+        let mut iter = self
+            .db
+            .iter::<EventByTypeTagSchema>(ReadOptions::default())?;
+        iter.seek(&(tag_hash, start_version, 0))?;
+
+        let mut result = Vec::new();
+        for res in iter.take(limit as usize) {
+            let ((hash, ver, idx), ()) = res?;
+            if hash != tag_hash || ver > ledger_version {
+                break;
+            }
+            result.push((ver, idx));
+        }
+
+        Ok(result)
+    }
+
     fn lookup_event_by_key(
         &self,
         event_key: &EventKey,
@@ -251,6 +293,10 @@ impl EventStore {
                     &(*event.key(), version, event.sequence_number()),
                     &(idx as u64),
                 )?;
+                cs.batch.put::<EventByTypeTagSchema>(
+                    &(type_tag_hash(event.type_tag())?, version, idx as u64),
+                    &(),
+                )?;
                 Ok(())
             })?;
 
@@ -9,6 +9,7 @@ use crate::{
     errors::DiemDbError,
     schema::{
         epoch_by_version::EpochByVersionSchema, ledger_info::LedgerInfoSchema,
+        state_checkpoint::StateCheckpointSchema,
         transaction_accumulator::TransactionAccumulatorSchema,
         transaction_info::TransactionInfoSchema,
     },
@@ -24,14 +25,16 @@ use diem_types::{
     epoch_state::EpochState,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        definition::LeafCount, position::Position, AccumulatorConsistencyProof,
-        TransactionAccumulatorProof, TransactionAccumulatorRangeProof, TransactionInfoWithProof,
+        definition::LeafCount,
+        position::{FrozenSubTreeIterator, Position},
+        AccumulatorConsistencyProof, TransactionAccumulatorProof, TransactionAccumulatorRangeProof,
+        TransactionInfoWithProof,
     },
     transaction::{TransactionInfo, Version},
 };
 use itertools::Itertools;
 use schemadb::{ReadOptions, SchemaIterator, DB};
-use std::{ops::Deref, sync::Arc};
+use std::{cell::RefCell, collections::HashMap, ops::Deref, sync::Arc};
 use storage_interface::{StartupInfo, TreeState};
 
 #[derive(Debug)]
@@ -47,16 +50,8 @@ pub(crate) struct LedgerStore {
 impl LedgerStore {
     pub fn new(db: Arc<DB>) -> Self {
         // Upon restart, read the latest ledger info and signatures and cache them in memory.
-        let ledger_info = {
-            let mut iter = db
-                .iter::<LedgerInfoSchema>(ReadOptions::default())
-                .expect("Constructing iterator should work.");
-            iter.seek_to_last();
-            iter.next()
-                .transpose()
-                .expect("Reading latest ledger info from DB should work.")
-                .map(|kv| kv.1)
-        };
+        let ledger_info = Self::read_latest_ledger_info(&db)
+            .expect("Reading latest ledger info from DB should work.");
 
         Self {
             db,
@@ -64,6 +59,20 @@ impl LedgerStore {
         }
     }
 
+    fn read_latest_ledger_info(db: &DB) -> Result<Option<LedgerInfoWithSignatures>> {
+        let mut iter = db.iter::<LedgerInfoSchema>(ReadOptions::default())?;
+        iter.seek_to_last();
+        Ok(iter.next().transpose()?.map(|kv| kv.1))
+    }
+
+    /// Reloads the cached latest ledger info and signatures from the DB, which is needed when the
+    /// DB is written by another process.
+    pub fn reload_latest_ledger_info(&self) -> Result<()> {
+        let ledger_info = Self::read_latest_ledger_info(&self.db)?;
+        self.latest_ledger_info.store(Arc::new(ledger_info));
+        Ok(())
+    }
+
     pub fn get_epoch(&self, version: Version) -> Result<u64> {
         let mut iter = self
             .db
@@ -281,6 +290,20 @@ impl LedgerStore {
         ))
     }
 
+    /// Gets a reader of transaction infos with proofs towards root of ledger at `ledger_version`,
+    /// which reads the accumulator nodes shared by the proofs of many versions only once.
+    pub fn get_batched_transaction_info_with_proof_reader(
+        &self,
+        ledger_version: Version,
+    ) -> BatchedTransactionInfoWithProofReader {
+        BatchedTransactionInfoWithProofReader {
+            store: self,
+            ledger_version,
+            nodes: RefCell::new(HashMap::new()),
+            txn_infos_with_proofs: RefCell::new(HashMap::new()),
+        }
+    }
+
     /// Get proof for transaction at `version` towards root of ledger at `ledger_version`.
     pub fn get_transaction_proof(
         &self,
@@ -348,6 +371,44 @@ impl LedgerStore {
         Ok(root_hash)
     }
 
+    /// Write the roots of the frozen subtrees of an accumulator with `version` leaves, ordered
+    /// from left to right, and `txn_info` at `version` on top of them, to `cs`. Unlike
+    /// `put_transaction_infos`, this reads nothing from the DB, so the returned root hash can be
+    /// verified before anything is written.
+    pub fn put_transaction_info_on_frozen_subtrees(
+        &self,
+        version: Version,
+        frozen_subtrees: &[HashValue],
+        txn_info: &TransactionInfo,
+        cs: &mut ChangeSet,
+    ) -> Result<HashValue> {
+        let positions: Vec<_> = FrozenSubTreeIterator::new(version).collect();
+        ensure!(
+            positions.len() == frozen_subtrees.len(),
+            "Number of frozen subtree roots not expected. Expected: {}, actual: {}",
+            positions.len(),
+            frozen_subtrees.len(),
+        );
+        let frozen_nodes = FrozenNodes(
+            positions
+                .into_iter()
+                .zip(frozen_subtrees.iter().cloned())
+                .collect(),
+        );
+        let (root_hash, writes) = MerkleAccumulator::<_, TransactionAccumulatorHasher>::append(
+            &frozen_nodes,
+            version, /* num_existing_leaves */
+            &[txn_info.hash()],
+        )?;
+        cs.batch.put::<TransactionInfoSchema>(&version, txn_info)?;
+        frozen_nodes
+            .0
+            .iter()
+            .chain(writes.iter().map(|(pos, hash)| (pos, hash)))
+            .try_for_each(|(pos, hash)| cs.batch.put::<TransactionAccumulatorSchema>(pos, hash))?;
+        Ok(root_hash)
+    }
+
     /// Write `ledger_info` to `cs`.
     pub fn put_ledger_info(
         &self,
@@ -365,6 +426,44 @@ impl LedgerStore {
             .put::<LedgerInfoSchema>(&ledger_info.epoch(), ledger_info_with_sigs)
     }
 
+    /// Records `ledger_info_with_sigs` as the ledger info proving the state at the last state
+    /// checkpoint at or before its version, unless that checkpoint already has one, i.e. the
+    /// first ledger info committed at or after a checkpoint is kept.
+    pub fn put_state_checkpoint(
+        &self,
+        ledger_info_with_sigs: &LedgerInfoWithSignatures,
+        interval: u64,
+        cs: &mut ChangeSet,
+    ) -> Result<()> {
+        let version = ledger_info_with_sigs.ledger_info().version();
+        let checkpoint_version = version / interval * interval;
+        if self
+            .db
+            .get::<StateCheckpointSchema>(&checkpoint_version)?
+            .is_some()
+        {
+            return Ok(());
+        }
+        cs.batch
+            .put::<StateCheckpointSchema>(&checkpoint_version, ledger_info_with_sigs)
+    }
+
+    /// Gets the latest state checkpoint at or before `version` and the ledger info recorded for
+    /// it.
+    pub fn get_state_checkpoint_ledger_info(
+        &self,
+        version: Version,
+    ) -> Result<(Version, LedgerInfoWithSignatures)> {
+        let mut iter = self
+            .db
+            .iter::<StateCheckpointSchema>(ReadOptions::default())?;
+        iter.seek_for_prev(&version)?;
+        iter.next().transpose()?.ok_or_else(|| {
+            DiemDbError::NotFound(format!("State checkpoint at or before version {}", version))
+                .into()
+        })
+    }
+
     pub fn get_root_hash(&self, version: Version) -> Result<HashValue> {
         Accumulator::get_root_hash(self, version + 1)
     }
@@ -380,6 +479,64 @@ impl HashReader for LedgerStore {
     }
 }
 
+/// The frozen nodes of an accumulator which is not in the DB yet.
+struct FrozenNodes(HashMap<Position, HashValue>);
+
+impl HashReader for FrozenNodes {
+    fn get(&self, position: Position) -> Result<HashValue> {
+        self.0
+            .get(&position)
+            .cloned()
+            .ok_or_else(|| format_err!("{} is not a frozen subtree root.", position))
+    }
+}
+
+/// Serves transaction infos with proofs towards root of the same ledger, caching the transaction
+/// infos and accumulator nodes read, so that proofs of nearby versions share most of their reads.
+pub struct BatchedTransactionInfoWithProofReader<'a> {
+    store: &'a LedgerStore,
+    ledger_version: Version,
+    nodes: RefCell<HashMap<Position, HashValue>>,
+    txn_infos_with_proofs: RefCell<HashMap<Version, TransactionInfoWithProof>>,
+}
+
+impl<'a> BatchedTransactionInfoWithProofReader<'a> {
+    /// Get transaction info at `version` with proof towards root of the ledger of this reader.
+    pub fn get_transaction_info_with_proof(
+        &self,
+        version: Version,
+    ) -> Result<TransactionInfoWithProof> {
+        if let Some(txn_info_with_proof) = self.txn_infos_with_proofs.borrow().get(&version) {
+            return Ok(txn_info_with_proof.clone());
+        }
+
+        let txn_info_with_proof = TransactionInfoWithProof::new(
+            MerkleAccumulator::<Self, TransactionAccumulatorHasher>::get_proof(
+                self,
+                self.ledger_version + 1, /* num_leaves */
+                version,
+            )?,
+            self.store.get_transaction_info(version)?,
+        );
+        self.txn_infos_with_proofs
+            .borrow_mut()
+            .insert(version, txn_info_with_proof.clone());
+        Ok(txn_info_with_proof)
+    }
+}
+
+impl<'a> HashReader for BatchedTransactionInfoWithProofReader<'a> {
+    fn get(&self, position: Position) -> Result<HashValue> {
+        if let Some(hash) = self.nodes.borrow().get(&position) {
+            return Ok(*hash);
+        }
+
+        let hash = self.store.get(position)?;
+        self.nodes.borrow_mut().insert(position, hash);
+        Ok(hash)
+    }
+}
+
 pub struct TransactionInfoIter<'a> {
     inner: SchemaIterator<'a, TransactionInfoSchema>,
     expected_next_version: Version,
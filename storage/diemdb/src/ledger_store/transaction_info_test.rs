@@ -100,4 +100,30 @@ proptest! {
             iter.collect::<Result<Vec<_>>>().unwrap()
         );
     }
+
+    #[test]
+    fn test_batched_transaction_info_with_proof_reader(
+        (infos, versions) in vec(any::<TransactionInfo>(), 1..100)
+            .prop_flat_map(|infos| {
+                let num_infos = infos.len() as u64;
+                (Just(infos), vec(0..num_infos, 1..20))
+            })
+    ) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+        let store = &db.ledger_store;
+        save(store, 0, &infos);
+        let ledger_version = infos.len() as u64 - 1;
+
+        // Repeated versions are served from the cache, and all match the unbatched proofs.
+        let reader = store.get_batched_transaction_info_with_proof_reader(ledger_version);
+        for version in versions {
+            prop_assert_eq!(
+                reader.get_transaction_info_with_proof(version).unwrap(),
+                store
+                    .get_transaction_info_with_proof(version, ledger_version)
+                    .unwrap()
+            );
+        }
+    }
 }
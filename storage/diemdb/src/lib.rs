@@ -21,6 +21,7 @@ pub mod metrics;
 pub mod schema;
 
 mod change_set;
+mod cold_store;
 mod event_store;
 mod ledger_counters;
 mod ledger_store;
@@ -39,47 +40,55 @@ pub use diemdb_test::test_save_blocks_impl;
 use crate::{
     backup::{backup_handler::BackupHandler, restore_handler::RestoreHandler},
     change_set::{ChangeSet, SealedChangeSet},
+    cold_store::{create_object_store, ColdStore, LedgerIter},
     errors::DiemDbError,
     event_store::EventStore,
     ledger_counters::LedgerCounters,
-    ledger_store::LedgerStore,
+    ledger_store::{BatchedTransactionInfoWithProofReader, LedgerStore},
     metrics::{
         DIEM_STORAGE_API_LATENCY_SECONDS, DIEM_STORAGE_COMMITTED_TXNS,
         DIEM_STORAGE_LATEST_TXN_VERSION, DIEM_STORAGE_LEDGER_VERSION,
         DIEM_STORAGE_NEXT_BLOCK_EPOCH, DIEM_STORAGE_OTHER_TIMERS_SECONDS,
         DIEM_STORAGE_ROCKSDB_PROPERTIES,
     },
-    pruner::Pruner,
+    pruner::{PrunedData, Pruner},
     schema::*,
     state_store::StateStore,
     system_store::SystemStore,
     transaction_store::TransactionStore,
 };
 use anyhow::{ensure, format_err, Result};
-use diem_config::config::RocksdbConfig;
+use diem_config::config::{ColdStorageConfig, LedgerPruneWindows, RocksdbConfig};
 use diem_crypto::hash::{CryptoHash, HashValue, SPARSE_MERKLE_PLACEHOLDER_HASH};
+use diem_jellyfish_merkle::{iterator::JellyfishMerkleIterator, restore::JellyfishMerkleRestore};
 use diem_logger::prelude::*;
 use diem_types::{
+    access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{
+        AccountResourcesPageWithProof, AccountStateBlob, AccountStateChunkWithProof,
+        AccountStateWithProof,
+    },
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
-        AccountStateProof, AccumulatorConsistencyProof, EventProof, SparseMerkleProof,
-        TransactionListProof,
+        AccountStateProof, AccumulatorConsistencyProof, EventAccumulatorProof, EventProof,
+        SparseMerkleProof, SparseMerkleRangeProof, TransactionListProof,
     },
+    state_checkpoint::StateCheckpoint,
     state_proof::StateProof,
     transaction::{
-        AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof,
+        AccountTransactionsWithProof, Transaction, TransactionInfo, TransactionListWithProof,
         TransactionToCommit, TransactionWithProof, Version, PRE_GENESIS_VERSION,
     },
+    write_set::WriteOp,
 };
 use itertools::{izip, zip_eq};
 use move_core_types::{
-    language_storage::{ModuleId, StructTag},
+    language_storage::{ModuleId, StructTag, TypeTag},
     resolver::{ModuleResolver, ResourceResolver},
 };
 use once_cell::sync::Lazy;
@@ -93,7 +102,9 @@ use std::{
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
-use storage_interface::{DbReader, DbWriter, MoveDbReader, Order, StartupInfo, TreeState};
+use storage_interface::{
+    DbReader, DbWriter, MoveDbReader, Order, StartupInfo, StateSnapshotReceiver, TreeState,
+};
 
 const MAX_LIMIT: u64 = 1000;
 
@@ -127,12 +138,32 @@ static ROCKSDB_PROPERTY_MAP: Lazy<HashMap<&str, &str>> = Lazy::new(|| {
             "diem_rocksdb_cf_size_bytes",
             "rocksdb.estimate-live-data-size",
         ),
+        (
+            "diem_rocksdb_total_sst_files_size_bytes",
+            "rocksdb.total-sst-files-size",
+        ),
+        (
+            "diem_rocksdb_compaction_pending",
+            "rocksdb.compaction-pending",
+        ),
+        (
+            "diem_rocksdb_pending_compaction_bytes",
+            "rocksdb.estimate-pending-compaction-bytes",
+        ),
+        (
+            "diem_rocksdb_num_immutable_memtables",
+            "rocksdb.num-immutable-mem-table",
+        ),
+        ("diem_rocksdb_is_write_stopped", "rocksdb.is-write-stopped"),
     ]
     .iter()
     .cloned()
     .collect()
 });
 
+// The number of levels of the LSM trees, as configured by default.
+const ROCKSDB_NUM_LEVELS: usize = 7;
+
 fn error_if_too_many_requested(num_requested: u64, max_allowed: u64) -> Result<()> {
     if num_requested > max_allowed {
         Err(DiemDbError::TooManyRequested(num_requested, max_allowed).into())
@@ -158,10 +189,30 @@ fn update_rocksdb_properties(db: &DB) -> Result<()> {
                 .with_label_values(&[cf_name, property_name])
                 .set(db.get_property(cf_name, rocksdb_property_argument)? as i64);
         }
+        DIEM_STORAGE_ROCKSDB_PROPERTIES
+            .with_label_values(&[cf_name, "diem_rocksdb_read_amplification"])
+            .set(estimate_read_amplification(db, cf_name)? as i64);
     }
     Ok(())
 }
 
+/// Estimates the number of SST files a point lookup in the column family may have to read: all
+/// files in L0, since their key ranges overlap, plus one file in each deeper non-empty level.
+fn estimate_read_amplification(db: &DB, cf_name: &str) -> Result<u64> {
+    let mut read_amplification = 0;
+    for level in 0..ROCKSDB_NUM_LEVELS {
+        let num_files: u64 = db
+            .get_string_property(cf_name, &format!("rocksdb.num-files-at-level{}", level))?
+            .parse()?;
+        read_amplification += if level == 0 {
+            num_files
+        } else {
+            std::cmp::min(num_files, 1)
+        };
+    }
+    Ok(read_amplification)
+}
+
 #[derive(Debug)]
 struct RocksdbPropertyReporter {
     sender: Mutex<mpsc::Sender<()>>,
@@ -204,6 +255,56 @@ impl Drop for RocksdbPropertyReporter {
     }
 }
 
+fn catch_up_with_primary(db: &DB, ledger_store: &LedgerStore) -> Result<()> {
+    let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["catch_up_with_primary"])
+        .start_timer();
+    db.try_catch_up_with_primary()?;
+    ledger_store.reload_latest_ledger_info()
+}
+
+/// Periodically catches a read replica up with the primary DB.
+#[derive(Debug)]
+struct ReadReplicaCatchUp {
+    sender: Mutex<mpsc::Sender<()>>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl ReadReplicaCatchUp {
+    fn new(db: Arc<DB>, ledger_store: Arc<LedgerStore>, catch_up_interval: Duration) -> Self {
+        let (send, recv) = mpsc::channel();
+        let join_handle = Some(thread::spawn(move || loop {
+            match recv.recv_timeout(catch_up_interval) {
+                Ok(_) => break,
+                Err(mpsc::RecvTimeoutError::Timeout) => (),
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+            if let Err(e) = catch_up_with_primary(&db, &ledger_store) {
+                warn!(
+                    error = ?e,
+                    "Catching up with the primary DB failed."
+                );
+            }
+        }));
+        Self {
+            sender: Mutex::new(send),
+            join_handle,
+        }
+    }
+}
+
+impl Drop for ReadReplicaCatchUp {
+    fn drop(&mut self) {
+        // Notify the catch up thread to exit
+        self.sender.lock().unwrap().send(()).unwrap();
+        self.join_handle
+            .take()
+            .expect("Read replica catch up thread must exist.")
+            .join()
+            .expect("Read replica catch up thread should join peacefully.");
+    }
+}
+
 /// This holds a handle to the underlying DB responsible for physical storage and provides APIs for
 /// access to the core Diem data structures.
 #[derive(Debug)]
@@ -215,7 +316,11 @@ pub struct DiemDB {
     event_store: Arc<EventStore>,
     system_store: SystemStore,
     rocksdb_property_reporter: RocksdbPropertyReporter,
-    pruner: Option<Pruner>,
+    pruners: Vec<Pruner>,
+    ledger_pruning_enabled: bool,
+    cold_store: Option<Arc<ColdStore>>,
+    read_replica_catch_up: Option<ReadReplicaCatchUp>,
+    state_checkpoint_interval: Option<u64>,
 }
 
 impl DiemDB {
@@ -225,20 +330,39 @@ impl DiemDB {
             EPOCH_BY_VERSION_CF_NAME,
             EVENT_ACCUMULATOR_CF_NAME,
             EVENT_BY_KEY_CF_NAME,
+            EVENT_BY_TYPE_TAG_CF_NAME,
             EVENT_BY_VERSION_CF_NAME,
             EVENT_CF_NAME,
             JELLYFISH_MERKLE_NODE_CF_NAME,
             LEDGER_COUNTERS_CF_NAME,
             STALE_NODE_INDEX_CF_NAME,
+            STATE_CHECKPOINT_CF_NAME,
             TRANSACTION_CF_NAME,
             TRANSACTION_ACCUMULATOR_CF_NAME,
             TRANSACTION_BY_ACCOUNT_CF_NAME,
+            TRANSACTION_BY_HASH_CF_NAME,
             TRANSACTION_INFO_CF_NAME,
+            WRITE_SET_BY_ACCESS_PATH_CF_NAME,
         ]
     }
 
-    fn new_with_db(db: DB, prune_window: Option<u64>) -> Self {
+    fn new_with_db(
+        db: DB,
+        prune_window: Option<u64>,
+        ledger_prune_windows: LedgerPruneWindows,
+    ) -> Self {
         let db = Arc::new(db);
+        let pruners = [
+            (PrunedData::State, prune_window),
+            (
+                PrunedData::Transactions,
+                ledger_prune_windows.transaction_prune_window,
+            ),
+            (PrunedData::Events, ledger_prune_windows.event_prune_window),
+        ]
+        .iter()
+        .filter_map(|(data, window)| window.map(|n| Pruner::new(Arc::clone(&db), *data, n)))
+        .collect();
 
         DiemDB {
             db: Arc::clone(&db),
@@ -248,20 +372,78 @@ impl DiemDB {
             transaction_store: Arc::new(TransactionStore::new(Arc::clone(&db))),
             system_store: SystemStore::new(Arc::clone(&db)),
             rocksdb_property_reporter: RocksdbPropertyReporter::new(Arc::clone(&db)),
-            pruner: prune_window.map(|n| Pruner::new(Arc::clone(&db), n)),
+            pruners,
+            ledger_pruning_enabled: ledger_prune_windows != LedgerPruneWindows::default(),
+            cold_store: None,
+            read_replica_catch_up: None,
+            state_checkpoint_interval: None,
         }
     }
 
+    /// Offloads the transactions and events of old versions to the object store configured by
+    /// `config`. Offloaded transactions and events are still served by all read APIs and the
+    /// backup handler, since their indices are kept in the DB.
+    pub fn enable_cold_storage(&mut self, config: &ColdStorageConfig) -> Result<()> {
+        ensure!(
+            !self.ledger_pruning_enabled,
+            "Cold storage can't be enabled when the transactions or events are pruned.",
+        );
+        ensure!(
+            self.cold_store.is_none(),
+            "Cold storage is already enabled."
+        );
+        self.cold_store = Some(Arc::new(ColdStore::new(
+            Arc::clone(&self.db),
+            Arc::clone(&self.transaction_store),
+            Arc::clone(&self.event_store),
+            create_object_store(&config.backend),
+            config,
+        )?));
+        Ok(())
+    }
+
+    /// Records a state checkpoint every `interval` versions: the first ledger info committed at
+    /// or after each multiple of `interval` is kept to prove the state root hash at that version,
+    /// see `get_state_checkpoint()`. A checkpoint is skipped if no ledger info is committed
+    /// between it and the next one.
+    pub fn enable_state_checkpoints(&mut self, interval: u64) -> Result<()> {
+        ensure!(interval > 0, "State checkpoint interval must be positive.");
+        self.state_checkpoint_interval = Some(interval);
+        Ok(())
+    }
+
     pub fn open<P: AsRef<Path> + Clone>(
         db_root_path: P,
         readonly: bool,
         prune_window: Option<u64>,
         rocksdb_config: RocksdbConfig,
+    ) -> Result<Self> {
+        Self::open_with_ledger_prune_windows(
+            db_root_path,
+            readonly,
+            prune_window,
+            LedgerPruneWindows::default(),
+            rocksdb_config,
+        )
+    }
+
+    /// Opens the DB like `open()`, additionally pruning the transactions and events independently
+    /// of the state, each with its own window.
+    pub fn open_with_ledger_prune_windows<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        readonly: bool,
+        prune_window: Option<u64>,
+        ledger_prune_windows: LedgerPruneWindows,
+        rocksdb_config: RocksdbConfig,
     ) -> Result<Self> {
         ensure!(
             prune_window.is_none() || !readonly,
             "Do not set prune_window when opening readonly.",
         );
+        ensure!(
+            ledger_prune_windows == LedgerPruneWindows::default() || !readonly,
+            "Do not set ledger_prune_windows when opening readonly.",
+        );
 
         let path = db_root_path.as_ref().join("diemdb");
         let instant = Instant::now();
@@ -286,7 +468,7 @@ impl DiemDB {
             )?
         };
 
-        let ret = Self::new_with_db(db, prune_window);
+        let ret = Self::new_with_db(db, prune_window, ledger_prune_windows);
         info!(
             path = path,
             time_ms = %instant.elapsed().as_millis(),
@@ -315,9 +497,34 @@ impl DiemDB {
                 &rocksdb_opts,
             )?,
             None, // prune_window
+            LedgerPruneWindows::default(),
         ))
     }
 
+    /// Opens the DB of a node running in another process as a read replica, via a secondary
+    /// instance keeping its own logs under `secondary_path`. The replica serves reads only, and
+    /// tails the WAL of the primary every `catch_up_interval` to stay up to date, so that query
+    /// load can be served by a process isolated from the node.
+    pub fn open_as_read_replica<P: AsRef<Path> + Clone>(
+        db_root_path: P,
+        secondary_path: P,
+        catch_up_interval: Duration,
+        rocksdb_config: RocksdbConfig,
+    ) -> Result<Self> {
+        let mut db = Self::open_as_secondary(db_root_path, secondary_path, rocksdb_config)?;
+        db.read_replica_catch_up = Some(ReadReplicaCatchUp::new(
+            Arc::clone(&db.db),
+            Arc::clone(&db.ledger_store),
+            catch_up_interval,
+        ));
+        Ok(db)
+    }
+
+    /// Catches a DB opened as secondary up with the primary, including the latest ledger info.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        catch_up_with_primary(&self.db, &self.ledger_store)
+    }
+
     /// This opens db in non-readonly mode, without the pruner.
     #[cfg(any(test, feature = "fuzzing"))]
     pub fn new_for_test<P: AsRef<Path> + Clone>(db_root_path: P) -> Self {
@@ -330,6 +537,32 @@ impl DiemDB {
         .expect("Unable to open DiemDB")
     }
 
+    /// Creates a checkpoint of the DB under `checkpoint_root_path`, which must not exist yet. The
+    /// checkpoint is consistent as of the last committed batch, is taken without blocking writes
+    /// and can be opened via `DiemDB::open(checkpoint_root_path, ...)`, e.g., to clone the node.
+    /// Files are hard-linked if the checkpoint is on the same filesystem as the DB.
+    ///
+    /// Data offloaded to the cold storage is not part of the checkpoint.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, checkpoint_root_path: P) -> Result<()> {
+        let checkpoint_root_path = checkpoint_root_path.as_ref();
+        ensure!(
+            !checkpoint_root_path.exists(),
+            "Checkpoint path {:?} already exists.",
+            checkpoint_root_path,
+        );
+        let instant = Instant::now();
+
+        std::fs::create_dir_all(checkpoint_root_path)?;
+        self.db
+            .create_checkpoint(checkpoint_root_path.join("diemdb"))?;
+        info!(
+            path = checkpoint_root_path,
+            time_ms = %instant.elapsed().as_millis(),
+            "Created DiemDB checkpoint.",
+        );
+        Ok(())
+    }
+
     /// This force the db to update rocksdb properties immediately.
     pub fn update_rocksdb_properties(&self) -> Result<()> {
         update_rocksdb_properties(&self.db)
@@ -406,14 +639,8 @@ impl DiemDB {
         let proof = self
             .ledger_store
             .get_transaction_info_with_proof(version, ledger_version)?;
-        let transaction = self.transaction_store.get_transaction(version)?;
-
         // If events were requested, also fetch those.
-        let events = if fetch_events {
-            Some(self.event_store.get_events_by_version(version)?)
-        } else {
-            None
-        };
+        let (transaction, events) = self.get_transaction_and_events(version, fetch_events)?;
 
         Ok(TransactionWithProof {
             version,
@@ -432,6 +659,7 @@ impl DiemDB {
             Arc::clone(&self.transaction_store),
             Arc::clone(&self.state_store),
             Arc::clone(&self.event_store),
+            self.cold_store.clone(),
         )
     }
 
@@ -443,6 +671,7 @@ impl DiemDB {
         order: Order,
         limit: u64,
         ledger_version: Version,
+        txn_info_reader: &BatchedTransactionInfoWithProofReader,
     ) -> Result<Vec<EventWithProof>> {
         error_if_too_many_requested(limit, MAX_LIMIT)?;
         let get_latest = order == Order::Descending && start_seq_num == u64::max_value();
@@ -485,18 +714,15 @@ impl DiemDB {
         let mut events_with_proof = event_indices
             .into_iter()
             .map(|(seq, ver, idx)| {
-                let (event, event_proof) = self
-                    .event_store
-                    .get_event_with_proof_by_version_and_index(ver, idx)?;
+                let (event, event_proof) =
+                    self.get_event_with_proof_by_version_and_index(ver, idx)?;
                 ensure!(
                     seq == event.sequence_number(),
                     "Index broken, expected seq:{}, actual:{}",
                     seq,
                     event.sequence_number()
                 );
-                let txn_info_with_proof = self
-                    .ledger_store
-                    .get_transaction_info_with_proof(ver, ledger_version)?;
+                let txn_info_with_proof = txn_info_reader.get_transaction_info_with_proof(ver)?;
                 let proof = EventProof::new(txn_info_with_proof, event_proof);
                 Ok(EventWithProof::new(ver, idx, event, proof))
             })
@@ -568,8 +794,13 @@ impl DiemDB {
         // Transaction updates. Gather transaction hashes.
         zip_eq(first_version..=last_version, txns_to_commit).try_for_each(
             |(ver, txn_to_commit)| {
+                self.transaction_store.put_transaction(
+                    ver,
+                    txn_to_commit.transaction(),
+                    &mut cs,
+                )?;
                 self.transaction_store
-                    .put_transaction(ver, txn_to_commit.transaction(), &mut cs)
+                    .put_write_set(ver, txn_to_commit.write_set(), &mut cs)
             },
         )?;
 
@@ -604,9 +835,103 @@ impl DiemDB {
     }
 
     fn wake_pruner(&self, latest_version: Version) {
-        if let Some(pruner) = self.pruner.as_ref() {
+        for pruner in &self.pruners {
             pruner.wake(latest_version)
         }
+        if let Some(cold_store) = self.cold_store.as_ref() {
+            cold_store.wake(latest_version)
+        }
+    }
+
+    /// Gets the transaction at `version` and, if requested, its events, from the cold store if
+    /// they were offloaded.
+    fn get_transaction_and_events(
+        &self,
+        version: Version,
+        fetch_events: bool,
+    ) -> Result<(Transaction, Option<Vec<ContractEvent>>)> {
+        // The events are read first: they are offloaded in the same batch as the transaction, so
+        // they were still there if the transaction is read afterwards.
+        let events = if fetch_events {
+            Some(self.event_store.get_events_by_version(version)?)
+        } else {
+            None
+        };
+        match self.transaction_store.get_transaction(version) {
+            Ok(transaction) => Ok((transaction, events)),
+            Err(e) => self.get_offloaded_transaction(version, fetch_events, e),
+        }
+    }
+
+    /// Gets the transaction at `version` and, if requested, its events, from the cold store, given
+    /// the error of reading them from the DB. The error is returned if they were not offloaded.
+    fn get_offloaded_transaction(
+        &self,
+        version: Version,
+        fetch_events: bool,
+        error: anyhow::Error,
+    ) -> Result<(Transaction, Option<Vec<ContractEvent>>)> {
+        match (&self.cold_store, error.downcast_ref::<DiemDbError>()) {
+            (Some(cold_store), Some(DiemDbError::NotFound(_))) => {
+                let txn_info = self.ledger_store.get_transaction_info(version)?;
+                cold_store.get_transaction(version, &txn_info, fetch_events)
+            }
+            _ => Err(error),
+        }
+    }
+
+    /// Gets the events of the transaction at `version` from the cold store, given the error of
+    /// reading one of them from the DB.
+    fn get_offloaded_events(
+        &self,
+        version: Version,
+        error: anyhow::Error,
+    ) -> Result<Vec<ContractEvent>> {
+        let (_, events) =
+            self.get_offloaded_transaction(version, true /* fetch_events */, error)?;
+        Ok(events.expect("Events were requested."))
+    }
+
+    /// Gets the event at `index` of the transaction at `version`, from the cold store if it was
+    /// offloaded.
+    fn get_event_by_version_and_index(
+        &self,
+        version: Version,
+        index: u64,
+    ) -> Result<ContractEvent> {
+        self.event_store
+            .get_event_by_version_and_index(version, index)
+            .or_else(|e| {
+                self.get_offloaded_events(version, e)?
+                    .into_iter()
+                    .nth(index as usize)
+                    .ok_or_else(|| {
+                        DiemDbError::NotFound(format!("Event {} of Txn {}", index, version)).into()
+                    })
+            })
+    }
+
+    /// Same as `get_event_by_version_and_index()`, with the proof of the event towards the event
+    /// root hash of the transaction. The event accumulators are kept for offloaded events.
+    fn get_event_with_proof_by_version_and_index(
+        &self,
+        version: Version,
+        index: u64,
+    ) -> Result<(ContractEvent, EventAccumulatorProof)> {
+        self.event_store
+            .get_event_with_proof_by_version_and_index(version, index)
+            .or_else(|e| {
+                let events = self.get_offloaded_events(version, e)?;
+                let event = events.get(index as usize).cloned().ok_or_else(|| {
+                    DiemDbError::NotFound(format!("Event {} of Txn {}", index, version))
+                })?;
+                let proof = self.event_store.get_event_proof_by_version_and_index(
+                    version,
+                    events.len() as u64,
+                    index,
+                )?;
+                Ok((event, proof))
+            })
     }
 }
 
@@ -660,6 +985,25 @@ impl DbReader for DiemDB {
         })
     }
 
+    /// Returns the transaction with the given `hash`, found via an index by hash, or `None` if
+    /// no transaction with that hash was committed at or before `ledger_version`, or if it was
+    /// pruned.
+    fn get_transaction_by_hash(
+        &self,
+        hash: HashValue,
+        include_events: bool,
+        ledger_version: Version,
+    ) -> Result<Option<TransactionWithProof>> {
+        gauged_api("get_transaction_by_hash", || {
+            self.transaction_store
+                .get_transaction_version_by_hash(hash, ledger_version)?
+                .map(|txn_version| {
+                    self.get_transaction_with_proof(txn_version, ledger_version, include_events)
+                })
+                .transpose()
+        })
+    }
+
     fn get_account_transactions(
         &self,
         address: AccountAddress,
@@ -709,18 +1053,16 @@ impl DbReader for DiemDB {
 
             let limit = std::cmp::min(limit, ledger_version - start_version + 1);
 
-            let txns = (start_version..start_version + limit)
-                .map(|version| self.transaction_store.get_transaction(version))
-                .collect::<Result<Vec<_>>>()?;
+            let (txns, events): (Vec<_>, Vec<_>) = (start_version..start_version + limit)
+                .map(|version| self.get_transaction_and_events(version, fetch_events))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .unzip();
             let txn_infos = (start_version..start_version + limit)
                 .map(|version| self.ledger_store.get_transaction_info(version))
                 .collect::<Result<Vec<_>>>()?;
             let events = if fetch_events {
-                Some(
-                    (start_version..start_version + limit)
-                        .map(|version| self.event_store.get_events_by_version(version))
-                        .collect::<Result<Vec<_>>>()?,
-                )
+                Some(events.into_iter().flatten().collect())
             } else {
                 None
             };
@@ -742,6 +1084,49 @@ impl DbReader for DiemDB {
         })
     }
 
+    /// Gets an iterator that yields the transactions in `[start_version, start_version +
+    /// num_transactions)` (capped at `ledger_version`) in chunks of at most `chunk_size`
+    /// transactions, each with a range proof against `ledger_version`. The transactions are read
+    /// with database iterators, so that only a single chunk is held in memory at a time.
+    fn get_transaction_iterator(
+        &self,
+        start_version: Version,
+        num_transactions: u64,
+        chunk_size: u64,
+        ledger_version: Version,
+        fetch_events: bool,
+    ) -> Result<Box<dyn Iterator<Item = Result<TransactionListWithProof>> + '_>> {
+        gauged_api("get_transaction_iterator", || {
+            ensure!(chunk_size > 0, "Chunk size must be positive.");
+            error_if_too_many_requested(chunk_size, MAX_LIMIT)?;
+
+            let num_transactions = if start_version > ledger_version {
+                0
+            } else {
+                std::cmp::min(num_transactions, ledger_version - start_version + 1)
+            };
+            let num_versions = usize::try_from(num_transactions)?;
+            let iter: Box<dyn Iterator<Item = Result<TransactionListWithProof>> + '_> =
+                Box::new(TransactionListIter {
+                    ledger_store: &self.ledger_store,
+                    ledger_iter: LedgerIter::new(
+                        self.cold_store.as_deref(),
+                        &self.ledger_store,
+                        &self.transaction_store,
+                        &self.event_store,
+                        start_version,
+                        num_versions,
+                        fetch_events,
+                    )?,
+                    next_version: start_version,
+                    end_version: start_version + num_transactions,
+                    chunk_size,
+                    ledger_version,
+                });
+            Ok(iter)
+        })
+    }
+
     fn get_events(
         &self,
         event_key: &EventKey,
@@ -773,12 +1158,110 @@ impl DbReader for DiemDB {
                 Some(version) => version,
                 None => self.get_latest_version()?,
             };
-            let events =
-                self.get_events_with_proof_by_event_key(event_key, start, order, limit, version)?;
+            let txn_info_reader = self
+                .ledger_store
+                .get_batched_transaction_info_with_proof_reader(version);
+            let events = self.get_events_with_proof_by_event_key(
+                event_key,
+                start,
+                order,
+                limit,
+                version,
+                &txn_info_reader,
+            )?;
             Ok(events)
         })
     }
 
+    /// Gets the events of many event streams at once, as if by calling `get_events_with_proofs`
+    /// in ascending order for each `(event_key, start_seq_num, limit)` of `requests`, all towards
+    /// the same ledger version. Accumulator nodes and transaction infos shared by the proofs of
+    /// different events are read only once. The sum of all limits is bounded like a single limit.
+    fn get_events_with_proofs_batched(
+        &self,
+        requests: &[(EventKey, u64, u64)],
+        known_version: Option<u64>,
+    ) -> Result<Vec<Vec<EventWithProof>>> {
+        gauged_api("get_events_with_proofs_batched", || {
+            let total_limit = requests
+                .iter()
+                .try_fold(0u64, |total, (_, _, limit)| total.checked_add(*limit))
+                .ok_or_else(|| format_err!("Too many events requested."))?;
+            error_if_too_many_requested(total_limit, MAX_LIMIT)?;
+
+            let version = match known_version {
+                Some(version) => version,
+                None => self.get_latest_version()?,
+            };
+            let txn_info_reader = self
+                .ledger_store
+                .get_batched_transaction_info_with_proof_reader(version);
+            requests
+                .iter()
+                .map(|(event_key, start, limit)| {
+                    self.get_events_with_proof_by_event_key(
+                        event_key,
+                        *start,
+                        Order::Ascending,
+                        *limit,
+                        version,
+                        &txn_info_reader,
+                    )
+                })
+                .collect()
+        })
+    }
+
+    /// Gets up to `limit` events of the given type tag, emitted by transactions at or after
+    /// `start_version` and up to the latest ledger version, in ascending order of version. Events
+    /// are found via an index by type tag, so no scan of the events is needed.
+    fn get_events_by_type_tag(
+        &self,
+        type_tag: &TypeTag,
+        start_version: Version,
+        limit: u64,
+    ) -> Result<Vec<(Version, ContractEvent)>> {
+        gauged_api("get_events_by_type_tag", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            let ledger_version = self.get_latest_version()?;
+            self.event_store
+                .lookup_events_by_type_tag(type_tag, start_version, limit, ledger_version)?
+                .into_iter()
+                .map(|(version, index)| {
+                    let event = self.get_event_by_version_and_index(version, index)?;
+                    Ok((version, event))
+                })
+                .collect()
+        })
+    }
+
+    /// Gets up to `limit` values written to `access_path` by transactions at or after
+    /// `start_version` and up to the latest ledger version, in ascending order of version. A
+    /// deletion of the access path is returned as `WriteOp::Deletion`. Writes are found via an
+    /// index by access path, so no transaction needs to be replayed.
+    ///
+    /// The index is built upon commit, so writes of transactions restored from a backup are not
+    /// found.
+    fn get_write_set_updates(
+        &self,
+        access_path: &AccessPath,
+        start_version: Version,
+        limit: u64,
+    ) -> Result<Vec<(Version, WriteOp)>> {
+        gauged_api("get_write_set_updates", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            let ledger_version = self.get_latest_version()?;
+            self.transaction_store.get_write_set_updates(
+                access_path,
+                start_version,
+                limit,
+                ledger_version,
+            )
+        })
+    }
+
     /// Gets ledger info at specified version and ensures it's an epoch ending.
     fn get_epoch_ending_ledger_info(&self, version: u64) -> Result<LedgerInfoWithSignatures> {
         gauged_api("get_epoch_ending_ledger_info", || {
@@ -882,6 +1365,44 @@ impl DbReader for DiemDB {
         })
     }
 
+    /// Returns up to `limit` resources of an account at `version`, in the order of their paths and
+    /// starting at `start_path`, with the proof of the account state based on `ledger_version`.
+    /// This bounds the size of the responses for accounts with large states, although the whole
+    /// account state blob is still read from the DB.
+    fn get_account_resources_with_proof(
+        &self,
+        address: AccountAddress,
+        start_path: &[u8],
+        limit: u64,
+        version: Version,
+        ledger_version: Version,
+    ) -> Result<AccountResourcesPageWithProof> {
+        gauged_api("get_account_resources_with_proof", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+            let account_state_with_proof =
+                self.get_account_state_with_proof(address, version, ledger_version)?;
+            let account_state = account_state_with_proof
+                .blob
+                .as_ref()
+                .map(AccountState::try_from)
+                .transpose()?
+                .unwrap_or_default();
+            let mut resources = account_state
+                .iter()
+                .filter(|(path, _)| path.as_slice() >= start_path)
+                .map(|(path, resource)| (path.clone(), resource.clone()));
+            let page = resources.by_ref().take(limit as usize).collect();
+            let more = resources.next().is_some();
+            Ok(AccountResourcesPageWithProof::new(
+                version,
+                page,
+                more,
+                account_state_with_proof.proof,
+            ))
+        })
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         gauged_api("get_startup_info", || self.ledger_store.get_startup_info())
     }
@@ -900,6 +1421,69 @@ impl DbReader for DiemDB {
         })
     }
 
+    fn get_account_state_chunk_with_proof(
+        &self,
+        version: Version,
+        start_key: HashValue,
+        limit: u64,
+    ) -> Result<AccountStateChunkWithProof> {
+        gauged_api("get_account_state_chunk_with_proof", || {
+            error_if_too_many_requested(limit, MAX_LIMIT)?;
+            let root_hash = self.state_store.get_root_hash(version)?;
+            let account_blobs =
+                JellyfishMerkleIterator::new(Arc::clone(&self.state_store), version, start_key)?
+                    .take(limit as usize)
+                    .collect::<Result<Vec<_>>>()?;
+            let proof = match account_blobs.last() {
+                Some((rightmost_key, _)) => self
+                    .state_store
+                    .get_account_state_range_proof(*rightmost_key, version)?,
+                None => SparseMerkleRangeProof::new(vec![]),
+            };
+            Ok(AccountStateChunkWithProof::new(
+                version,
+                account_blobs,
+                proof,
+                root_hash,
+            ))
+        })
+    }
+
+    /// Gets an iterator over the addresses and states of all the accounts at `version`, in the
+    /// order of the hashes of the addresses. The Jellyfish Merkle tree is walked lazily, so only
+    /// the path to the current account is held in memory however many accounts there are. The
+    /// iteration fails if the state at `version` gets pruned meanwhile.
+    fn get_all_accounts_at_version(
+        &self,
+        version: Version,
+    ) -> Result<Box<dyn Iterator<Item = Result<(AccountAddress, AccountStateBlob)>> + '_>> {
+        gauged_api("get_all_accounts_at_version", || {
+            let (latest_version, _) = self.ledger_store.get_latest_transaction_info()?;
+            ensure!(
+                version <= latest_version,
+                "Version {} is greater than the latest version {}.",
+                version,
+                latest_version,
+            );
+            let iter: Box<dyn Iterator<Item = Result<(AccountAddress, AccountStateBlob)>>> =
+                Box::new(
+                    JellyfishMerkleIterator::new(
+                        Arc::clone(&self.state_store),
+                        version,
+                        HashValue::zero(),
+                    )?
+                    .map(|res| {
+                        let (key, blob) = res?;
+                        let address = AccountState::try_from(&blob)?
+                            .get_account_address()?
+                            .ok_or_else(|| format_err!("No AccountResource in account {}.", key))?;
+                        Ok((address, blob))
+                    }),
+                );
+            Ok(iter)
+        })
+    }
+
     fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
         gauged_api("get_latest_state_root", || {
             let (version, txn_info) = self.ledger_store.get_latest_transaction_info()?;
@@ -969,6 +1553,9 @@ impl DbReader for DiemDB {
             let maybe_seq_num = self
                 .event_store
                 .get_latest_sequence_number(event_version, event_key)?;
+            let txn_info_reader = self
+                .ledger_store
+                .get_batched_transaction_info_with_proof_reader(proof_version);
 
             let (lower_bound_incl, upper_bound_excl) = if let Some(seq_num) = maybe_seq_num {
                 // We need to request the surrounding events (surrounding
@@ -986,6 +1573,7 @@ impl DbReader for DiemDB {
                     Order::Ascending,
                     limit,
                     proof_version,
+                    &txn_info_reader,
                 )?;
 
                 let mut events_iter = events.into_iter();
@@ -1007,6 +1595,7 @@ impl DbReader for DiemDB {
                     Order::Ascending,
                     limit,
                     proof_version,
+                    &txn_info_reader,
                 )?;
 
                 let mut events_iter = events.into_iter();
@@ -1056,6 +1645,27 @@ impl DbReader for DiemDB {
                 .get_consistency_proof(client_known_version, ledger_version)
         })
     }
+
+    /// Gets the latest state checkpoint at or before `version`, or the latest one if `version`
+    /// is `None`, with the state root hash at the checkpoint version proven towards the ledger
+    /// info recorded for it. Checkpoints are only recorded if enabled by
+    /// `enable_state_checkpoints()`.
+    fn get_state_checkpoint(&self, version: Option<Version>) -> Result<StateCheckpoint> {
+        gauged_api("get_state_checkpoint", || {
+            let (checkpoint_version, ledger_info_with_sigs) = self
+                .ledger_store
+                .get_state_checkpoint_ledger_info(version.unwrap_or(Version::max_value()))?;
+            let txn_info_with_proof = self.ledger_store.get_transaction_info_with_proof(
+                checkpoint_version,
+                ledger_info_with_sigs.ledger_info().version(),
+            )?;
+            Ok(StateCheckpoint::new(
+                checkpoint_version,
+                ledger_info_with_sigs,
+                txn_info_with_proof,
+            ))
+        })
+    }
 }
 
 impl ModuleResolver for DiemDB {
@@ -1141,6 +1751,10 @@ impl DbWriter for DiemDB {
                 );
 
                 self.ledger_store.put_ledger_info(x, &mut cs)?;
+                if let Some(interval) = self.state_checkpoint_interval {
+                    self.ledger_store
+                        .put_state_checkpoint(x, interval, &mut cs)?;
+                }
             }
 
             // Persist.
@@ -1176,9 +1790,167 @@ impl DbWriter for DiemDB {
             Ok(())
         })
     }
+
+    fn get_state_snapshot_receiver(
+        &self,
+        version: Version,
+        expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        gauged_api("get_state_snapshot_receiver", || {
+            let restore = JellyfishMerkleRestore::new_overwrite(
+                Arc::clone(&self.state_store),
+                version,
+                expected_root_hash,
+            )?;
+            Ok(Box::new(StateSnapshotRestore(restore)) as Box<dyn StateSnapshotReceiver>)
+        })
+    }
+
+    /// The transaction accumulator is restored from `frozen_subtrees` and the info of the
+    /// transaction at `version`, and its root hash is verified against the last ledger info,
+    /// which must be at `version`.
+    fn finalize_state_snapshot(
+        &self,
+        version: Version,
+        frozen_subtrees: &[HashValue],
+        transaction: &Transaction,
+        transaction_info: &TransactionInfo,
+        events: &[ContractEvent],
+        ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        gauged_api("finalize_state_snapshot", || {
+            let latest_li = ledger_infos
+                .last()
+                .ok_or_else(|| format_err!("No LedgerInfos to save."))?;
+            ensure!(
+                latest_li.ledger_info().version() == version,
+                "The latest ledger info is not at the snapshot version. Ledger info version: {}, \
+                 snapshot version: {}",
+                latest_li.ledger_info().version(),
+                version,
+            );
+            let state_root_hash = self.state_store.get_root_hash(version)?;
+            ensure!(
+                state_root_hash == transaction_info.state_root_hash(),
+                "The restored state root hash doesn't match the transaction info. {:?} vs {:?}",
+                state_root_hash,
+                transaction_info.state_root_hash(),
+            );
+
+            // Nothing is written unless the accumulator, built from the frozen subtrees and the
+            // transaction info alone, matches the ledger info.
+            let mut cs = ChangeSet::new();
+            let new_root_hash = self.ledger_store.put_transaction_info_on_frozen_subtrees(
+                version,
+                frozen_subtrees,
+                transaction_info,
+                &mut cs,
+            )?;
+            ensure!(
+                new_root_hash == latest_li.ledger_info().transaction_accumulator_hash(),
+                "Root hash calculated doesn't match expected. {:?} vs {:?}",
+                new_root_hash,
+                latest_li.ledger_info().transaction_accumulator_hash(),
+            );
+            self.transaction_store
+                .put_transaction(version, transaction, &mut cs)?;
+            self.event_store.put_events(version, events, &mut cs)?;
+            ledger_infos
+                .iter()
+                .try_for_each(|li| self.ledger_store.put_ledger_info(li, &mut cs))?;
+            self.db.write_schemas(cs.batch)?;
+
+            self.ledger_store.set_latest_ledger_info(latest_li.clone());
+            DIEM_STORAGE_LEDGER_VERSION.set(version as i64);
+            DIEM_STORAGE_NEXT_BLOCK_EPOCH.set(latest_li.ledger_info().next_block_epoch() as i64);
+            DIEM_STORAGE_LATEST_TXN_VERSION.set(version as i64);
+
+            Ok(())
+        })
+    }
+}
+
+/// Restores the account state tree of a state snapshot into the state store.
+struct StateSnapshotRestore(JellyfishMerkleRestore<AccountStateBlob>);
+
+impl StateSnapshotReceiver for StateSnapshotRestore {
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()> {
+        self.0.add_chunk(chunk, proof)
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.0.finish()
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
+/// Yields a range of transactions in chunks, each with a range proof against the same ledger
+/// version.
+struct TransactionListIter<'a> {
+    ledger_store: &'a LedgerStore,
+    ledger_iter: LedgerIter<'a>,
+    next_version: Version,
+    end_version: Version,
+    chunk_size: u64,
+    ledger_version: Version,
+}
+
+impl<'a> TransactionListIter<'a> {
+    fn next_impl(&mut self) -> Result<Option<TransactionListWithProof>> {
+        if self.next_version >= self.end_version {
+            return Ok(None);
+        }
+
+        let first_version = self.next_version;
+        let limit = std::cmp::min(self.chunk_size, self.end_version - first_version);
+        let mut txns = Vec::new();
+        let mut txn_infos = Vec::new();
+        let mut events = Vec::new();
+        for res in (&mut self.ledger_iter).take(limit as usize) {
+            let (txn, txn_info, txn_events) = res?;
+            txns.push(txn);
+            txn_infos.push(txn_info);
+            events.push(txn_events);
+        }
+        ensure!(
+            txns.len() == limit as usize,
+            "Missing transactions in range [{}, {}).",
+            first_version,
+            first_version + limit,
+        );
+        // The events are either fetched for all transactions or for none.
+        let events = events.into_iter().collect::<Option<Vec<_>>>();
+        let proof = TransactionListProof::new(
+            self.ledger_store.get_transaction_range_proof(
+                Some(first_version),
+                limit,
+                self.ledger_version,
+            )?,
+            txn_infos,
+        );
+        self.next_version += limit;
+
+        Ok(Some(TransactionListWithProof::new(
+            txns,
+            events,
+            Some(first_version),
+            proof,
+        )))
+    }
+}
+
+impl<'a> Iterator for TransactionListIter<'a> {
+    type Item = Result<TransactionListWithProof>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_impl().transpose()
+    }
+}
+
 fn get_first_seq_num_and_limit(order: Order, cursor: u64, limit: u64) -> Result<(u64, u64)> {
     ensure!(limit > 0, "limit should > 0, got {}", limit);
 
@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use diem_metrics::{
-    register_histogram_vec, register_int_counter, register_int_gauge, register_int_gauge_vec,
-    HistogramVec, IntCounter, IntGauge, IntGaugeVec,
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -63,6 +63,50 @@ pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION: Lazy<IntGauge> = La
     .unwrap()
 });
 
+pub static DIEM_STORAGE_PRUNER_WINDOW: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        // metric name
+        "diem_storage_pruner_window",
+        // metric description
+        "Diem storage prune window of each class of pruned data",
+        // metric labels (dimensions)
+        &["data"]
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        // metric name
+        "diem_storage_pruner_least_readable_version",
+        // metric description
+        "Diem storage pruner least readable version of each class of pruned data",
+        // metric labels (dimensions)
+        &["data"]
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_COLD_STORE_FIRST_HOT_VERSION: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "diem_storage_cold_store_first_hot_version",
+        "Diem storage first version not offloaded to cold storage"
+    )
+    .unwrap()
+});
+
+pub static DIEM_STORAGE_COLD_STORE_READS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "diem_storage_cold_store_reads",
+        // metric description
+        "Diem storage reads of offloaded segments, by whether they hit the cache",
+        // metric labels (dimensions)
+        &["result"]
+    )
+    .unwrap()
+});
+
 pub static DIEM_STORAGE_API_LATENCY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         // metric name
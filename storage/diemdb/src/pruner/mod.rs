@@ -3,21 +3,40 @@
 
 //! This module provides `Pruner` which manages a thread pruning old data in the background and is
 //! meant to be triggered by other threads as they commit new data to the DB.
+//!
+//! Each class of data (see `PrunedData`) is pruned by a `Pruner` of its own, so that each can be
+//! kept for a different number of versions. The pruning progress is never persisted separately:
+//! data is deleted in increasing version order with atomic batches, so a worker finds out where to
+//! resume from the oldest data left in the DB.
 
 use crate::{
     metrics::{
         DIEM_STORAGE_OTHER_TIMERS_SECONDS, DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION,
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION, DIEM_STORAGE_PRUNER_WINDOW,
         DIEM_STORAGE_PRUNE_WINDOW,
     },
     schema::{
-        jellyfish_merkle_node::JellyfishMerkleNodeSchema, stale_node_index::StaleNodeIndexSchema,
+        event::EventSchema,
+        event_accumulator::EventAccumulatorSchema,
+        event_by_key::EventByKeySchema,
+        event_by_type_tag::{type_tag_hash, EventByTypeTagSchema},
+        event_by_version::EventByVersionSchema,
+        jellyfish_merkle_node::JellyfishMerkleNodeSchema,
+        stale_node_index::StaleNodeIndexSchema,
+        transaction::TransactionSchema,
+        transaction_by_account::TransactionByAccountSchema,
+        transaction_by_hash::TransactionByHashSchema,
     },
 };
 use anyhow::Result;
+use diem_crypto::hash::CryptoHash;
 use diem_infallible::Mutex;
 use diem_jellyfish_merkle::StaleNodeIndex;
 use diem_logger::prelude::*;
-use diem_types::transaction::Version;
+use diem_types::{
+    proof::position::Position,
+    transaction::{Transaction, Version},
+};
 use schemadb::{ReadOptions, SchemaBatch, SchemaIterator, DB};
 use std::{
     iter::Peekable,
@@ -30,6 +49,28 @@ use std::{
     time::{Duration, Instant},
 };
 
+/// The classes of data which are pruned independently of each other.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum PrunedData {
+    /// Stale nodes of the account state tree, i.e., the state history.
+    State,
+    /// Transactions, along with the index of user transactions by account. Transaction infos and
+    /// the transaction accumulator are kept, so that the ledger history can still be proven.
+    Transactions,
+    /// Events, along with their indices and the event accumulators.
+    Events,
+}
+
+impl PrunedData {
+    pub fn name(self) -> &'static str {
+        match self {
+            PrunedData::State => "state",
+            PrunedData::Transactions => "transaction",
+            PrunedData::Events => "event",
+        }
+    }
+}
+
 /// The `Pruner` is meant to be part of a `DiemDB` instance and runs in the background to prune old
 /// data.
 ///
@@ -53,16 +94,21 @@ pub(crate) struct Pruner {
 
 impl Pruner {
     /// Creates a worker thread that waits on a channel for pruning commands.
-    pub fn new(db: Arc<DB>, historical_versions_to_keep: u64) -> Self {
+    pub fn new(db: Arc<DB>, data: PrunedData, historical_versions_to_keep: u64) -> Self {
         let (command_sender, command_receiver) = channel();
 
         let worker_progress = Arc::new(AtomicU64::new(0));
         let worker_progress_clone = Arc::clone(&worker_progress);
 
-        DIEM_STORAGE_PRUNE_WINDOW.set(historical_versions_to_keep as i64);
+        if data == PrunedData::State {
+            DIEM_STORAGE_PRUNE_WINDOW.set(historical_versions_to_keep as i64);
+        }
+        DIEM_STORAGE_PRUNER_WINDOW
+            .with_label_values(&[data.name()])
+            .set(historical_versions_to_keep as i64);
         let worker_thread = std::thread::Builder::new()
-            .name("diemdb_pruner".into())
-            .spawn(move || Worker::new(db, command_receiver, worker_progress_clone).work())
+            .name(format!("diemdb_{}_pruner", data.name()))
+            .spawn(move || Worker::new(db, data, command_receiver, worker_progress_clone).work())
             .expect("Creating pruner thread should succeed.");
 
         Self {
@@ -131,6 +177,7 @@ enum Command {
 
 struct Worker {
     db: Arc<DB>,
+    data: PrunedData,
     command_receiver: Receiver<Command>,
     target_least_readable_version: Version,
     /// Keeps a record of the pruning progress. If this equals to version `V`, we know versions
@@ -149,11 +196,13 @@ impl Worker {
 
     fn new(
         db: Arc<DB>,
+        data: PrunedData,
         command_receiver: Receiver<Command>,
         least_readable_version: Arc<AtomicU64>,
     ) -> Self {
         Self {
             db,
+            data,
             command_receiver,
             least_readable_version,
             target_least_readable_version: 0,
@@ -170,12 +219,7 @@ impl Worker {
             // Process a reasonably small batch of work before trying to receive commands again,
             // in case `Command::Quit` is received (that's when we should quit.)
             let least_readable_version = self.least_readable_version.load(Ordering::Relaxed);
-            match prune_state(
-                Arc::clone(&self.db),
-                least_readable_version,
-                self.target_least_readable_version,
-                Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
-            ) {
+            match self.prune(least_readable_version) {
                 Ok(new_least_readable_version) => {
                     self.record_progress(new_least_readable_version);
 
@@ -184,17 +228,20 @@ impl Worker {
                         || new_least_readable_version == self.target_least_readable_version; // did all
 
                     // Try to purge the log.
-                    if let Err(e) = self.maybe_purge_index() {
-                        warn!(
-                            error = ?e,
-                            "Failed purging state node index, ignored.",
-                        );
+                    if self.data == PrunedData::State {
+                        if let Err(e) = self.maybe_purge_index() {
+                            warn!(
+                                error = ?e,
+                                "Failed purging state node index, ignored.",
+                            );
+                        }
                     }
                 }
                 Err(e) => {
                     error!(
+                        data = self.data.name(),
                         error = ?e,
-                        "Error pruning stale state nodes.",
+                        "Error pruning.",
                     );
                     // On error, stop retrying vigorously by making next recv() blocking.
                     self.blocking_recv = true;
@@ -203,7 +250,22 @@ impl Worker {
         }
     }
 
-    /// Find out the first undeleted item in the stale node index.
+    /// Prunes a reasonably small batch of data, returning the new least readable version.
+    fn prune(&self, least_readable_version: Version) -> Result<Version> {
+        let prune = match self.data {
+            PrunedData::State => prune_state,
+            PrunedData::Transactions => prune_transactions,
+            PrunedData::Events => prune_events,
+        };
+        prune(
+            Arc::clone(&self.db),
+            least_readable_version,
+            self.target_least_readable_version,
+            Self::MAX_VERSIONS_TO_PRUNE_PER_BATCH,
+        )
+    }
+
+    /// Find out the first undeleted item of the pruned data, e.g., in the stale node index.
     ///
     /// Seeking from the beginning (version 0) is potentially costly, we do it once upon worker
     /// thread start, record the progress and seek from that position afterwards.
@@ -212,8 +274,9 @@ impl Worker {
             match self.get_least_readable_version() {
                 Ok(least_readable_version) => {
                     info!(
+                        data = self.data.name(),
                         least_readable_version = least_readable_version,
-                        "[state pruner worker] initialized."
+                        "[pruner worker] initialized."
                     );
                     self.target_least_readable_version = least_readable_version;
                     self.record_progress(least_readable_version);
@@ -221,8 +284,9 @@ impl Worker {
                 }
                 Err(e) => {
                     error!(
+                        data = self.data.name(),
                         error = ?e,
-                        "[state pruner worker] Error on first seek. Retrying in 1 second.",
+                        "[pruner worker] Error on first seek. Retrying in 1 second.",
                     );
                     sleep(Duration::from_secs(1));
                 }
@@ -231,23 +295,45 @@ impl Worker {
     }
 
     fn get_least_readable_version(&self) -> Result<Version> {
-        let mut iter = self
-            .db
-            .iter::<StaleNodeIndexSchema>(ReadOptions::default())?;
-        iter.seek_to_first();
-        Ok(iter.next().transpose()?.map_or(0, |(index, _)| {
-            index
-                .stale_since_version
-                .checked_sub(1)
-                .expect("Nothing is stale since version 0.")
-        }))
+        match self.data {
+            PrunedData::State => {
+                let mut iter = self
+                    .db
+                    .iter::<StaleNodeIndexSchema>(ReadOptions::default())?;
+                iter.seek_to_first();
+                Ok(iter.next().transpose()?.map_or(0, |(index, _)| {
+                    index
+                        .stale_since_version
+                        .checked_sub(1)
+                        .expect("Nothing is stale since version 0.")
+                }))
+            }
+            PrunedData::Transactions => {
+                let mut iter = self.db.iter::<TransactionSchema>(ReadOptions::default())?;
+                iter.seek_to_first();
+                Ok(iter.next().transpose()?.map_or(0, |(version, _)| version))
+            }
+            PrunedData::Events => {
+                let mut iter = self.db.iter::<EventSchema>(ReadOptions::default())?;
+                iter.seek_to_first();
+                Ok(iter
+                    .next()
+                    .transpose()?
+                    .map_or(0, |((version, _index), _)| version))
+            }
+        }
     }
 
     /// Log the progress.
     fn record_progress(&mut self, least_readable_version: Version) {
         self.least_readable_version
             .store(least_readable_version, Ordering::Relaxed);
-        DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION.set(least_readable_version as i64);
+        if self.data == PrunedData::State {
+            DIEM_STORAGE_PRUNER_LEAST_READABLE_STATE_VERSION.set(least_readable_version as i64);
+        }
+        DIEM_STORAGE_PRUNER_LEAST_READABLE_VERSION
+            .with_label_values(&[self.data.name()])
+            .set(least_readable_version as i64);
     }
 
     /// Tries to receive all pending commands, blocking waits for the next command if no work needs
@@ -408,5 +494,117 @@ pub fn prune_state(
     }
 }
 
+/// Deletes the transactions of versions from `least_readable_version` up to (excluding)
+/// `target_least_readable_version`, no more than `max_versions` of them.
+pub fn prune_transactions(
+    db: Arc<DB>,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let mut iter = db.iter::<TransactionSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+
+    let mut batch = SchemaBatch::new();
+    let mut num_versions = 0;
+    let mut new_least_readable_version = target_least_readable_version;
+    for res in iter {
+        let (version, txn) = res?;
+        if version >= target_least_readable_version {
+            break;
+        }
+        if num_versions == max_versions {
+            new_least_readable_version = version;
+            break;
+        }
+        batch.delete::<TransactionByHashSchema>(&txn.hash())?;
+        if let Transaction::UserTransaction(txn) = txn {
+            batch.delete::<TransactionByAccountSchema>(&(txn.sender(), txn.sequence_number()))?;
+        }
+        batch.delete::<TransactionSchema>(&version)?;
+        num_versions += 1;
+    }
+
+    commit_pruned(
+        &db,
+        batch,
+        least_readable_version,
+        new_least_readable_version,
+    )
+}
+
+/// Deletes the events of versions from `least_readable_version` up to (excluding)
+/// `target_least_readable_version`, no more than `max_versions` of them, along with their indices
+/// and accumulators.
+pub fn prune_events(
+    db: Arc<DB>,
+    least_readable_version: Version,
+    target_least_readable_version: Version,
+    max_versions: usize,
+) -> Result<Version> {
+    let mut iter = db.iter::<EventSchema>(ReadOptions::default())?;
+    iter.seek(&least_readable_version)?;
+
+    let mut batch = SchemaBatch::new();
+    let mut pruned_versions = Vec::new();
+    let mut new_least_readable_version = target_least_readable_version;
+    for res in iter {
+        let ((version, index), event) = res?;
+        if version >= target_least_readable_version {
+            break;
+        }
+        if pruned_versions.last() != Some(&version) {
+            if pruned_versions.len() == max_versions {
+                new_least_readable_version = version;
+                break;
+            }
+            pruned_versions.push(version);
+        }
+        batch.delete::<EventSchema>(&(version, index))?;
+        batch.delete::<EventByKeySchema>(&(*event.key(), event.sequence_number()))?;
+        batch.delete::<EventByVersionSchema>(&(*event.key(), version, event.sequence_number()))?;
+        batch.delete::<EventByTypeTagSchema>(&(
+            type_tag_hash(event.type_tag())?,
+            version,
+            index,
+        ))?;
+    }
+
+    for version in pruned_versions {
+        let mut iter = db.iter::<EventAccumulatorSchema>(ReadOptions::default())?;
+        iter.seek(&(version, Position::from_inorder_index(0)))?;
+        for res in iter {
+            let ((node_version, position), _) = res?;
+            if node_version != version {
+                break;
+            }
+            batch.delete::<EventAccumulatorSchema>(&(version, position))?;
+        }
+    }
+
+    commit_pruned(
+        &db,
+        batch,
+        least_readable_version,
+        new_least_readable_version,
+    )
+}
+
+fn commit_pruned(
+    db: &DB,
+    batch: SchemaBatch,
+    least_readable_version: Version,
+    new_least_readable_version: Version,
+) -> Result<Version> {
+    if new_least_readable_version <= least_readable_version {
+        return Ok(least_readable_version);
+    }
+    let _timer = DIEM_STORAGE_OTHER_TIMERS_SECONDS
+        .with_label_values(&["pruner_commit"])
+        .start_timer();
+    db.write_schemas(batch)?;
+    Ok(new_least_readable_version)
+}
+
 #[cfg(test)]
 mod test;
@@ -4,8 +4,17 @@
 use super::*;
 use crate::{change_set::ChangeSet, state_store::StateStore, DiemDB};
 use diem_crypto::HashValue;
+use diem_proptest_helpers::Index;
 use diem_temppath::TempPath;
-use diem_types::{account_address::AccountAddress, account_state_blob::AccountStateBlob};
+use diem_types::{
+    account_address::AccountAddress,
+    account_state_blob::AccountStateBlob,
+    contract_event::ContractEvent,
+    event::EventKey,
+    proptest_types::{AccountInfoUniverse, SignatureCheckedTransactionGen},
+};
+use move_core_types::language_storage::TypeTag;
+use proptest::{collection::vec, prelude::*};
 use std::collections::HashMap;
 
 fn put_account_state_set(
@@ -50,7 +59,11 @@ fn test_pruner() {
     let tmp_dir = TempPath::new();
     let db = DiemDB::new_for_test(&tmp_dir).db;
     let state_store = &StateStore::new(Arc::clone(&db));
-    let pruner = Pruner::new(Arc::clone(&db), 0 /* historical_versions_to_keep */);
+    let pruner = Pruner::new(
+        Arc::clone(&db),
+        PrunedData::State,
+        0, /* historical_versions_to_keep */
+    );
 
     let _root0 = put_account_state_set(
         &db,
@@ -135,6 +148,7 @@ fn test_worker_quit_eagerly() {
         let (command_sender, command_receiver) = channel();
         let worker = Worker::new(
             Arc::clone(&db),
+            PrunedData::State,
             command_receiver,
             Arc::new(AtomicU64::new(0)), /* progress */
         );
@@ -156,3 +170,156 @@ fn test_worker_quit_eagerly() {
         verify_state_in_store(state_store, address, Some(&value2), 2);
     }
 }
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+
+    #[test]
+    fn test_transaction_pruner(
+        mut universe in any_with::<AccountInfoUniverse>(3),
+        gens in vec(
+            (any::<Index>(), any::<SignatureCheckedTransactionGen>()),
+            3..10
+        ),
+    ) {
+        let tmp_dir = TempPath::new();
+        let diem_db = DiemDB::new_for_test(&tmp_dir);
+        let store = &diem_db.transaction_store;
+        let txns = gens
+            .into_iter()
+            .map(|(index, gen)| gen.materialize(*index, &mut universe).into_inner())
+            .collect::<Vec<_>>();
+        let mut cs = ChangeSet::new();
+        for (ver, txn) in txns.iter().enumerate() {
+            let txn = Transaction::UserTransaction(txn.clone());
+            store.put_transaction(ver as Version, &txn, &mut cs).unwrap();
+        }
+        diem_db.db.write_schemas(cs.batch).unwrap();
+
+        // Keep the latest two versions.
+        let latest_version = txns.len() as Version - 1;
+        let pruner = Pruner::new(Arc::clone(&diem_db.db), PrunedData::Transactions, 1);
+        pruner.wake_and_wait(latest_version).unwrap();
+
+        for (ver, txn) in txns.iter().enumerate() {
+            let ver = ver as Version;
+            let account_version = store
+                .get_account_transaction_version(
+                    txn.sender(),
+                    txn.sequence_number(),
+                    latest_version,
+                )
+                .unwrap();
+            let hash_version = store
+                .get_transaction_version_by_hash(
+                    Transaction::UserTransaction(txn.clone()).hash(),
+                    latest_version,
+                )
+                .unwrap();
+            if ver + 1 < latest_version {
+                prop_assert!(store.get_transaction(ver).is_err());
+                prop_assert_eq!(account_version, None);
+                prop_assert_eq!(hash_version, None);
+            } else {
+                prop_assert_eq!(
+                    store.get_transaction(ver).unwrap(),
+                    Transaction::UserTransaction(txn.clone())
+                );
+                prop_assert_eq!(account_version, Some(ver));
+                prop_assert_eq!(hash_version, Some(ver));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_event_pruner() {
+    let tmp_dir = TempPath::new();
+    let diem_db = DiemDB::new_for_test(&tmp_dir);
+    let store = &diem_db.event_store;
+    let key = EventKey::random();
+    // Versions 1 and 3 emit no event.
+    let events_by_version = vec![vec![0, 1], vec![], vec![2], vec![], vec![3, 4, 5]];
+    let mut cs = ChangeSet::new();
+    for (ver, seq_nums) in events_by_version.iter().enumerate() {
+        let events = seq_nums
+            .iter()
+            .map(|seq_num| ContractEvent::new(key, *seq_num, TypeTag::Bool, vec![]))
+            .collect::<Vec<_>>();
+        store.put_events(ver as Version, &events, &mut cs).unwrap();
+    }
+    diem_db.db.write_schemas(cs.batch).unwrap();
+
+    let pruner = Pruner::new(Arc::clone(&diem_db.db), PrunedData::Events, 1);
+    // Prune till version=3, i.e., events of versions 0 and 2 are gone.
+    pruner.wake_and_wait(4 /* latest_version */).unwrap();
+    for ver in 0..3 {
+        assert!(store.get_events_by_version(ver).unwrap().is_empty());
+        assert!(store
+            .get_event_with_proof_by_version_and_index(ver, 0)
+            .is_err());
+    }
+    for seq_num in 0..3 {
+        assert!(diem_db
+            .db
+            .get::<EventByKeySchema>(&(key, seq_num))
+            .unwrap()
+            .is_none());
+    }
+    assert!(store
+        .lookup_events_by_type_tag(&TypeTag::Bool, 0, 10, 4)
+        .unwrap()
+        .iter()
+        .all(|(ver, _)| *ver >= 3));
+    let mut iter = diem_db
+        .db
+        .iter::<EventAccumulatorSchema>(ReadOptions::default())
+        .unwrap();
+    iter.seek_to_first();
+    assert!(iter.all(|res| {
+        let ((ver, _position), _hash) = res.unwrap();
+        ver >= 3
+    }));
+
+    // Events of version 4 are still readable.
+    assert_eq!(store.get_events_by_version(4).unwrap().len(), 3);
+    assert_eq!(
+        store.lookup_events_by_key(&key, 3, 10, 4).unwrap(),
+        vec![(3, 4, 0), (4, 4, 1), (5, 4, 2)]
+    );
+}
+
+#[test]
+fn test_ledger_pruner_resumes() {
+    let tmp_dir = TempPath::new();
+    let diem_db = DiemDB::new_for_test(&tmp_dir);
+    let key = EventKey::random();
+    let mut cs = ChangeSet::new();
+    for ver in 0..10 {
+        let event = ContractEvent::new(key, ver, TypeTag::Bool, vec![]);
+        diem_db
+            .event_store
+            .put_events(ver, &[event], &mut cs)
+            .unwrap();
+    }
+    diem_db.db.write_schemas(cs.batch).unwrap();
+
+    // Prune part of the events, as if the worker stopped before pruning all.
+    assert_eq!(
+        prune_events(Arc::clone(&diem_db.db), 0, 8, 5 /* max_versions */).unwrap(),
+        5
+    );
+
+    // A new worker resumes from the first events left.
+    let (command_sender, command_receiver) = channel();
+    let progress = Arc::new(AtomicU64::new(0));
+    let worker = Worker::new(
+        Arc::clone(&diem_db.db),
+        PrunedData::Events,
+        command_receiver,
+        Arc::clone(&progress),
+    );
+    command_sender.send(Command::Quit).unwrap();
+    worker.work();
+    assert_eq!(progress.load(Ordering::Relaxed), 5);
+}
@@ -0,0 +1,72 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for an event index via which a ContractEvent (
+//! represented by a <txn_version, event_idx> tuple so that it can be fetched from `EventSchema`)
+//! can be found by the type tag of the event. The type tag is variable in length, so it is
+//! represented by its hash in the key.
+//!
+//! ```text
+//! |<---------------key---------------->|
+//! | type_tag_hash | txn_ver | event_idx |
+//! ```
+
+use crate::schema::{ensure_slice_len_eq, EVENT_BY_TYPE_TAG_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use diem_crypto::HashValue;
+use diem_types::transaction::Version;
+use move_core_types::language_storage::TypeTag;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(EventByTypeTagSchema, Key, (), EVENT_BY_TYPE_TAG_CF_NAME);
+
+type Index = u64;
+type Key = (HashValue, Version, Index);
+
+/// Returns the hash by which events of the given type tag are indexed.
+pub(crate) fn type_tag_hash(type_tag: &TypeTag) -> Result<HashValue> {
+    Ok(HashValue::sha3_256_of(&bcs::to_bytes(type_tag)?))
+}
+
+impl KeyCodec<EventByTypeTagSchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (ref type_tag_hash, version, index) = *self;
+
+        let mut encoded = type_tag_hash.to_vec();
+        encoded.write_u64::<BigEndian>(version)?;
+        encoded.write_u64::<BigEndian>(index)?;
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+
+        const HASH_LEN: usize = HashValue::LENGTH;
+        const HASH_AND_VER_LEN: usize = HashValue::LENGTH + size_of::<Version>();
+        let type_tag_hash = HashValue::from_slice(&data[..HASH_LEN])?;
+        let version = (&data[HASH_LEN..]).read_u64::<BigEndian>()?;
+        let index = (&data[HASH_AND_VER_LEN..]).read_u64::<BigEndian>()?;
+
+        Ok((type_tag_hash, version, index))
+    }
+}
+
+impl ValueCodec<EventByTypeTagSchema> for () {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(Vec::new())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
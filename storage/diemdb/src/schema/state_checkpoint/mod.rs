@@ -0,0 +1,55 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for state checkpoints: for each checkpoint version
+//! it stores the first LedgerInfoWithSignatures committed at or after that version, which is what
+//! the state root hash at the checkpoint version is proven against.
+//!
+//! ```text
+//! |<---key--->|<---------------value------------->|
+//! |  version  | ledger_info_with_signatures bytes |
+//! ```
+//!
+//! `version` is serialized in big endian so that records in RocksDB will be in order of their
+//! numeric value.
+
+use crate::schema::{ensure_slice_len_eq, STATE_CHECKPOINT_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt};
+use diem_types::{ledger_info::LedgerInfoWithSignatures, transaction::Version};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    StateCheckpointSchema,
+    Version,
+    LedgerInfoWithSignatures,
+    STATE_CHECKPOINT_CF_NAME
+);
+
+impl KeyCodec<StateCheckpointSchema> for Version {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_be_bytes().to_vec())
+    }
+
+    fn decode_key(mut data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+        Ok(data.read_u64::<BigEndian>()?)
+    }
+}
+
+impl ValueCodec<StateCheckpointSchema> for LedgerInfoWithSignatures {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,16 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        version in any::<Version>(),
+        ledger_info_with_sigs in any_with::<LedgerInfoWithSignatures>((1..10).into())
+    ) {
+        assert_encode_decode::<StateCheckpointSchema>(&version, &ledger_info_with_sigs);
+    }
+}
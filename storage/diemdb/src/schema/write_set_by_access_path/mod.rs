@@ -0,0 +1,69 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for an index via which the values written to an
+//! access path can be found in the order of the versions of the transactions writing them. The
+//! access path is variable in length, so it is represented by its hash in the key.
+//!
+//! ```text
+//! |<------------key----------->|<-value-->|
+//! | access_path_hash | txn_ver | write_op |
+//! ```
+
+use crate::schema::{ensure_slice_len_eq, WRITE_SET_BY_ACCESS_PATH_CF_NAME};
+use anyhow::Result;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use diem_crypto::HashValue;
+use diem_types::{access_path::AccessPath, transaction::Version, write_set::WriteOp};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::mem::size_of;
+
+define_schema!(
+    WriteSetByAccessPathSchema,
+    Key,
+    WriteOp,
+    WRITE_SET_BY_ACCESS_PATH_CF_NAME
+);
+
+type Key = (HashValue, Version);
+
+/// Returns the hash by which the writes to the given access path are indexed.
+pub(crate) fn access_path_hash(access_path: &AccessPath) -> Result<HashValue> {
+    Ok(HashValue::sha3_256_of(&bcs::to_bytes(access_path)?))
+}
+
+impl KeyCodec<WriteSetByAccessPathSchema> for Key {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let (ref access_path_hash, version) = *self;
+
+        let mut encoded = access_path_hash.to_vec();
+        encoded.write_u64::<BigEndian>(version)?;
+
+        Ok(encoded)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, size_of::<Self>())?;
+
+        let access_path_hash = HashValue::from_slice(&data[..HashValue::LENGTH])?;
+        let version = (&data[HashValue::LENGTH..]).read_u64::<BigEndian>()?;
+
+        Ok((access_path_hash, version))
+    }
+}
+
+impl ValueCodec<WriteSetByAccessPathSchema> for WriteOp {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        bcs::to_bytes(self).map_err(Into::into)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        bcs::from_bytes(data).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod test;
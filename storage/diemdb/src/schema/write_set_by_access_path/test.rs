@@ -0,0 +1,20 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use proptest::prelude::*;
+use schemadb::schema::assert_encode_decode;
+
+proptest! {
+    #[test]
+    fn test_encode_decode(
+        access_path_hash in any::<[u8; HashValue::LENGTH]>(),
+        version in any::<Version>(),
+        write_op in any::<WriteOp>(),
+    ) {
+        assert_encode_decode::<WriteSetByAccessPathSchema>(
+            &(HashValue::new(access_path_hash), version),
+            &write_op,
+        );
+    }
+}
@@ -10,6 +10,7 @@ use diem_types::{
     account_state_blob::AccountStateBlob,
 };
 use proptest::{collection::hash_map, prelude::*};
+use storage_interface::DbReader;
 
 fn put_account_state_set(
     store: &StateStore,
@@ -259,6 +260,47 @@ proptest! {
         }
     }
 
+    #[test]
+    fn test_get_account_state_chunks(
+        (input, chunk_size) in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 1..200)
+            .prop_flat_map(|input| {
+                let len = input.len();
+                (Just(input), 1..=len)
+            })
+    ) {
+        let kvs: Vec<_> = input.into_iter().collect();
+
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+        let store = &db.state_store;
+        init_store(store, kvs.clone().into_iter());
+
+        let version = (kvs.len() - 1) as Version;
+        let expected_root_hash = store.get_root_hash(version).unwrap();
+        let mut actual_values = vec![];
+        let mut start_key = Some(HashValue::zero());
+        while let Some(key) = start_key {
+            let chunk = db
+                .get_account_state_chunk_with_proof(version, key, chunk_size as u64)
+                .unwrap();
+            prop_assert_eq!(chunk.version, version);
+            prop_assert_eq!(chunk.root_hash, expected_root_hash);
+            prop_assert!(chunk.account_blobs.len() <= chunk_size);
+            if chunk.account_blobs.is_empty() {
+                break;
+            }
+            start_key = chunk.next_start_key();
+            actual_values.extend(chunk.account_blobs);
+        }
+
+        let mut expected_values: Vec<_> = kvs
+            .iter()
+            .map(|(addr, account)| (addr.hash(), account.clone()))
+            .collect();
+        expected_values.sort_unstable_by_key(|item| item.0);
+        prop_assert_eq!(actual_values, expected_values);
+    }
+
     #[test]
     fn test_restore(
         (input, batch1_size) in hash_map(any::<AccountAddress>(), any::<AccountStateBlob>(), 2..1000)
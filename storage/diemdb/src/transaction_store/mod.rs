@@ -6,13 +6,21 @@
 use crate::{
     change_set::ChangeSet,
     errors::DiemDbError,
-    schema::{transaction::TransactionSchema, transaction_by_account::TransactionByAccountSchema},
+    schema::{
+        transaction::TransactionSchema,
+        transaction_by_account::TransactionByAccountSchema,
+        transaction_by_hash::TransactionByHashSchema,
+        write_set_by_access_path::{access_path_hash, WriteSetByAccessPathSchema},
+    },
 };
 use anyhow::{ensure, format_err, Result};
+use diem_crypto::{hash::CryptoHash, HashValue};
 use diem_types::{
+    access_path::AccessPath,
     account_address::AccountAddress,
     block_metadata::BlockMetadata,
     transaction::{Transaction, Version},
+    write_set::{WriteOp, WriteSet},
 };
 use schemadb::{ReadOptions, SchemaIterator, DB};
 use std::sync::Arc;
@@ -46,6 +54,18 @@ impl TransactionStore {
         Ok(None)
     }
 
+    /// Gets the version of a transaction by its `hash`.
+    pub fn get_transaction_version_by_hash(
+        &self,
+        hash: HashValue,
+        ledger_version: Version,
+    ) -> Result<Option<Version>> {
+        Ok(self
+            .db
+            .get::<TransactionByHashSchema>(&hash)?
+            .filter(|version| *version <= ledger_version))
+    }
+
     /// Gets an iterator that yields `(sequence_number, version)` for each
     /// transaction sent by an account, starting at `start_seq_num`, and returning
     /// at most `num_versions` results with `version <= ledger_version`.
@@ -124,6 +144,35 @@ impl TransactionStore {
         Err(DiemDbError::NotFound(format!("BlockMetadata preceding version {}", version)).into())
     }
 
+    /// Given `access_path` and `start_version`, returns up to `limit` writes to the access path by
+    /// transactions at or after `start_version`, together with the versions of the transactions.
+    /// Result won't contain records with a transaction version > `ledger_version` and is in
+    /// ascending order.
+    pub fn get_write_set_updates(
+        &self,
+        access_path: &AccessPath,
+        start_version: Version,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<(Version, WriteOp)>> {
+        let path_hash = access_path_hash(access_path)?;
+        let mut iter = self
+            .db
+            .iter::<WriteSetByAccessPathSchema>(ReadOptions::default())?;
+        iter.seek(&(path_hash, start_version))?;
+
+        let mut result = Vec::new();
+        for res in iter.take(limit as usize) {
+            let ((hash, version), write_op) = res?;
+            if hash != path_hash || version > ledger_version {
+                break;
+            }
+            result.push((version, write_op));
+        }
+
+        Ok(result)
+    }
+
     /// Save signed transaction at `version`
     pub fn put_transaction(
         &self,
@@ -137,10 +186,27 @@ impl TransactionStore {
                 &version,
             )?;
         }
+        cs.batch
+            .put::<TransactionByHashSchema>(&transaction.hash(), &version)?;
         cs.batch.put::<TransactionSchema>(&version, transaction)?;
 
         Ok(())
     }
+
+    /// Indexes the writes of the write set of the transaction at `version` by access path.
+    pub fn put_write_set(
+        &self,
+        version: Version,
+        write_set: &WriteSet,
+        cs: &mut ChangeSet,
+    ) -> Result<()> {
+        write_set.iter().try_for_each(|(access_path, write_op)| {
+            cs.batch.put::<WriteSetByAccessPathSchema>(
+                &(access_path_hash(access_path)?, version),
+                write_op,
+            )
+        })
+    }
 }
 
 pub struct TransactionIter<'a> {
@@ -9,6 +9,7 @@ use diem_types::{
     block_metadata::BlockMetadata,
     proptest_types::{AccountInfoUniverse, SignatureCheckedTransactionGen},
     transaction::{SignedTransaction, Transaction},
+    write_set::WriteSetMut,
 };
 use proptest::{collection::vec, prelude::*};
 use std::collections::BTreeMap;
@@ -45,9 +46,21 @@ proptest! {
                     .unwrap(),
                 Some(ver as Version)
             );
+            prop_assert_eq!(
+                store
+                    .get_transaction_version_by_hash(txn.hash(), ledger_version)
+                    .unwrap(),
+                Some(ver as Version)
+            );
         }
 
         prop_assert!(store.get_transaction(ledger_version + 1).is_err());
+        prop_assert_eq!(
+            store
+                .get_transaction_version_by_hash(HashValue::random(), ledger_version)
+                .unwrap(),
+            None
+        );
     }
 
     #[test]
@@ -234,6 +247,60 @@ proptest! {
 
         prop_assert_eq!(&actual_scan, &expected_scan);
     }
+
+    #[test]
+    fn test_get_write_set_updates(
+        access_paths in vec(any::<AccessPath>(), 1..5),
+        writes in vec(vec((any::<Index>(), any::<WriteOp>()), 0..3), 1..20),
+        ledger_version in 0_u64..20,
+    ) {
+        let tmp_dir = TempPath::new();
+        let db = DiemDB::new_for_test(&tmp_dir);
+        let store = &db.transaction_store;
+
+        let mut expected = BTreeMap::<AccessPath, Vec<(Version, WriteOp)>>::new();
+        let mut cs = ChangeSet::new();
+        for (ver, writes) in writes.into_iter().enumerate() {
+            // A transaction writes each access path at most once.
+            let writes = writes
+                .into_iter()
+                .map(|(index, write_op)| (index.get(&access_paths).clone(), write_op))
+                .collect::<BTreeMap<_, _>>();
+            if ver as Version <= ledger_version {
+                for (access_path, write_op) in &writes {
+                    expected
+                        .entry(access_path.clone())
+                        .or_default()
+                        .push((ver as Version, write_op.clone()));
+                }
+            }
+            let write_set = writes.into_iter().collect::<WriteSetMut>().freeze().unwrap();
+            store.put_write_set(ver as Version, &write_set, &mut cs).unwrap();
+        }
+        store.db.write_schemas(cs.batch).unwrap();
+
+        for access_path in &access_paths {
+            let expected_updates = expected.get(access_path).cloned().unwrap_or_default();
+            let updates = store
+                .get_write_set_updates(access_path, 0, 100, ledger_version)
+                .unwrap();
+            prop_assert_eq!(&updates, &expected_updates);
+
+            // Updates before `start_version` are skipped.
+            if let Some((first_version, _)) = expected_updates.first() {
+                let updates = store
+                    .get_write_set_updates(access_path, first_version + 1, 100, ledger_version)
+                    .unwrap();
+                prop_assert_eq!(&updates[..], &expected_updates[1..]);
+            }
+
+            // No more than `limit` updates are returned.
+            let updates = store
+                .get_write_set_updates(access_path, 0, 1, ledger_version)
+                .unwrap();
+            prop_assert_eq!(&updates[..], &expected_updates[..expected_updates.len().min(1)]);
+        }
+    }
 }
 
 fn init_store(
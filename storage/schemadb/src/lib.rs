@@ -442,6 +442,21 @@ impl DB {
         Ok(())
     }
 
+    /// Catches up with the primary DB by tailing its MANIFEST and WAL. Only applicable to a DB
+    /// opened via `open_as_secondary`.
+    pub fn try_catch_up_with_primary(&self) -> Result<()> {
+        self.inner.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
+    /// Creates a checkpoint of the DB at `path`, which must not exist yet. The checkpoint is a
+    /// consistent snapshot of all column families that can be opened as a DB of its own. SST files
+    /// are hard-linked if `path` is on the same filesystem as the DB, and copied otherwise.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        rocksdb::checkpoint::Checkpoint::new(&self.inner)?.create_checkpoint(path)?;
+        Ok(())
+    }
+
     pub fn get_property(&self, cf_name: &str, property_name: &str) -> Result<u64> {
         self.inner
             .property_int_value_cf(self.get_cf_handle(cf_name)?, property_name)?
@@ -453,6 +468,19 @@ impl DB {
                 )
             })
     }
+
+    /// Gets a property which has no integer form, e.g. "rocksdb.num-files-at-level<N>".
+    pub fn get_string_property(&self, cf_name: &str, property_name: &str) -> Result<String> {
+        self.inner
+            .property_value_cf(self.get_cf_handle(cf_name)?, property_name)?
+            .ok_or_else(|| {
+                format_err!(
+                    "Unable to get property \"{}\" of column family \"{}\".",
+                    property_name,
+                    cf_name,
+                )
+            })
+    }
 }
 
 /// For now we always use synchronous writes. This makes sure that once the operation returns
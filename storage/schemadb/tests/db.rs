@@ -349,6 +349,38 @@ fn test_open_as_secondary() {
         db_sec.get::<TestSchema1>(&TestField(0)).unwrap(),
         Some(TestField(0)),
     );
+
+    // Writes to the primary show on the secondary once it catches up.
+    db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+    assert_eq!(db_sec.get::<TestSchema1>(&TestField(1)).unwrap(), None);
+    db_sec.try_catch_up_with_primary().unwrap();
+    assert_eq!(
+        db_sec.get::<TestSchema1>(&TestField(1)).unwrap(),
+        Some(TestField(1)),
+    );
+}
+
+#[test]
+fn test_checkpoint() {
+    let tmpdir = diem_temppath::TempPath::new();
+    let checkpoint_dir = diem_temppath::TempPath::new();
+    {
+        let db = open_db(&tmpdir);
+        db.put::<TestSchema1>(&TestField(0), &TestField(0)).unwrap();
+        db.create_checkpoint(&checkpoint_dir).unwrap();
+        // Writes after the checkpoint don't show in it.
+        db.put::<TestSchema1>(&TestField(1), &TestField(1)).unwrap();
+        // The checkpoint directory must not exist.
+        assert!(db.create_checkpoint(&checkpoint_dir).is_err());
+    }
+    {
+        let db = open_db(&checkpoint_dir);
+        assert_eq!(
+            db.get::<TestSchema1>(&TestField(0)).unwrap(),
+            Some(TestField(0)),
+        );
+        assert_eq!(db.get::<TestSchema1>(&TestField(1)).unwrap(), None);
+    }
 }
 
 #[test]
@@ -383,4 +415,9 @@ fn test_report_size() {
             .unwrap(),
         0
     );
+    assert_eq!(
+        db.get_string_property("TestCF1", "rocksdb.num-files-at-level0")
+            .unwrap(),
+        "1"
+    );
 }
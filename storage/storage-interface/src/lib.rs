@@ -7,25 +7,34 @@ use diem_types::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_state::AccountState,
-    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    account_state_blob::{
+        AccountResourcesPageWithProof, AccountStateBlob, AccountStateChunkWithProof,
+        AccountStateWithProof,
+    },
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
     epoch_state::EpochState,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     move_resource::MoveStorage,
+    on_chain_config::config_address,
     proof::{
         definition::LeafCount, AccumulatorConsistencyProof, SparseMerkleProof,
-        TransactionAccumulatorSummary,
+        SparseMerkleRangeProof, TransactionAccumulatorSummary,
     },
+    state_checkpoint::StateCheckpoint,
     state_proof::StateProof,
     transaction::{
-        AccountTransactionsWithProof, TransactionInfo, TransactionListWithProof,
+        AccountTransactionsWithProof, Transaction, TransactionInfo, TransactionListWithProof,
         TransactionToCommit, TransactionWithProof, Version,
     },
+    write_set::WriteOp,
 };
 use itertools::Itertools;
-use move_core_types::resolver::{ModuleResolver, ResourceResolver};
+use move_core_types::{
+    language_storage::TypeTag,
+    resolver::{ModuleResolver, ResourceResolver},
+};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
@@ -190,6 +199,21 @@ pub trait DbReader: Send + Sync {
         fetch_events: bool,
     ) -> Result<TransactionListWithProof>;
 
+    /// See [`DiemDB::get_transaction_iterator`].
+    ///
+    /// [`DiemDB::get_transaction_iterator`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_transaction_iterator
+    fn get_transaction_iterator(
+        &self,
+        _start_version: Version,
+        _num_transactions: u64,
+        _chunk_size: u64,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<Box<dyn Iterator<Item = Result<TransactionListWithProof>> + '_>> {
+        unimplemented!()
+    }
+
     /// Returns events by given event key
     fn get_events(
         &self,
@@ -209,6 +233,44 @@ pub trait DbReader: Send + Sync {
         known_version: Option<u64>,
     ) -> Result<Vec<EventWithProof>>;
 
+    /// See [`DiemDB::get_events_with_proofs_batched`].
+    ///
+    /// [`DiemDB::get_events_with_proofs_batched`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_events_with_proofs_batched
+    fn get_events_with_proofs_batched(
+        &self,
+        _requests: &[(EventKey, u64, u64)],
+        _known_version: Option<u64>,
+    ) -> Result<Vec<Vec<EventWithProof>>> {
+        unimplemented!()
+    }
+
+    /// See [`DiemDB::get_events_by_type_tag`].
+    ///
+    /// [`DiemDB::get_events_by_type_tag`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_events_by_type_tag
+    fn get_events_by_type_tag(
+        &self,
+        _type_tag: &TypeTag,
+        _start_version: Version,
+        _limit: u64,
+    ) -> Result<Vec<(Version, ContractEvent)>> {
+        unimplemented!()
+    }
+
+    /// See [`DiemDB::get_write_set_updates`].
+    ///
+    /// [`DiemDB::get_write_set_updates`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_write_set_updates
+    fn get_write_set_updates(
+        &self,
+        _access_path: &AccessPath,
+        _start_version: Version,
+        _limit: u64,
+    ) -> Result<Vec<(Version, WriteOp)>> {
+        unimplemented!()
+    }
+
     /// See [`DiemDB::get_block_timestamp`].
     ///
     /// [`DiemDB::get_block_timestamp`]:
@@ -287,6 +349,19 @@ pub trait DbReader: Send + Sync {
         ledger_version: Version,
     ) -> Result<AccountTransactionsWithProof>;
 
+    /// See [`DiemDB::get_transaction_by_hash`].
+    ///
+    /// [`DiemDB::get_transaction_by_hash`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_transaction_by_hash
+    fn get_transaction_by_hash(
+        &self,
+        _hash: HashValue,
+        _include_events: bool,
+        _ledger_version: Version,
+    ) -> Result<Option<TransactionWithProof>> {
+        unimplemented!()
+    }
+
     /// Returns proof of new state for a given ledger info with signatures relative to version known
     /// to client
     fn get_state_proof_with_ledger_info(
@@ -307,6 +382,21 @@ pub trait DbReader: Send + Sync {
         ledger_version: Version,
     ) -> Result<AccountStateWithProof>;
 
+    /// See [`DiemDB::get_account_resources_with_proof`].
+    ///
+    /// [`DiemDB::get_account_resources_with_proof`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_account_resources_with_proof
+    fn get_account_resources_with_proof(
+        &self,
+        _address: AccountAddress,
+        _start_path: &[u8],
+        _limit: u64,
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountResourcesPageWithProof> {
+        unimplemented!()
+    }
+
     // Gets an account state by account address, out of the ledger state indicated by the state
     // Merkle tree root with a sparse merkle proof proving state tree root.
     // See [`DiemDB::get_account_state_with_proof_by_version`].
@@ -330,6 +420,29 @@ pub trait DbReader: Send + Sync {
     /// ../diemdb/struct.DiemDB.html#method.get_latest_state_root
     fn get_latest_state_root(&self) -> Result<(Version, HashValue)>;
 
+    /// Returns at most `limit` account states of the state tree at `version`, in the order of
+    /// the hashes of the account addresses, starting from `start_key`. Together with the proof,
+    /// this allows downloading a state snapshot in chunks.
+    fn get_account_state_chunk_with_proof(
+        &self,
+        _version: Version,
+        _start_key: HashValue,
+        _limit: u64,
+    ) -> Result<AccountStateChunkWithProof> {
+        unimplemented!()
+    }
+
+    /// See [`DiemDB::get_all_accounts_at_version`].
+    ///
+    /// [`DiemDB::get_all_accounts_at_version`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_all_accounts_at_version
+    fn get_all_accounts_at_version(
+        &self,
+        _version: Version,
+    ) -> Result<Box<dyn Iterator<Item = Result<(AccountAddress, AccountStateBlob)>> + '_>> {
+        unimplemented!()
+    }
+
     /// Gets the latest TreeState no matter if db has been bootstrapped.
     /// Used by the Db-bootstrapper.
     fn get_latest_tree_state(&self) -> Result<TreeState>;
@@ -387,6 +500,14 @@ pub trait DbReader: Send + Sync {
             ledger_version,
         )
     }
+
+    /// See [`DiemDB::get_state_checkpoint`].
+    ///
+    /// [`DiemDB::get_state_checkpoint`]:
+    /// ../diemdb/struct.DiemDB.html#method.get_state_checkpoint
+    fn get_state_checkpoint(&self, _version: Option<Version>) -> Result<StateCheckpoint> {
+        unimplemented!()
+    }
 }
 
 impl MoveStorage for &dyn DbReader {
@@ -436,6 +557,45 @@ impl MoveStorage for &dyn DbReader {
             .collect()
     }
 
+    fn fetch_configs_by_version(
+        &self,
+        access_paths: Vec<AccessPath>,
+        version: Version,
+    ) -> Result<(u64, Vec<Option<Vec<u8>>>)> {
+        // Read each account (including the one holding the configuration resource) once
+        let mut account_states = HashMap::new();
+        for address in access_paths
+            .iter()
+            .map(|path| path.address)
+            .chain(Some(config_address()))
+        {
+            if !account_states.contains_key(&address) {
+                let (blob, _proof) =
+                    self.get_account_state_with_proof_by_version(address, version)?;
+                let account_state = blob.map(|blob| AccountState::try_from(&blob)).transpose()?;
+                account_states.insert(address, account_state);
+            }
+        }
+
+        let epoch = account_states
+            .get(&config_address())
+            .and_then(Option::as_ref)
+            .ok_or_else(|| format_err!("missing account state of the config address"))?
+            .get_configuration_resource()?
+            .ok_or_else(|| format_err!("configuration resource does not exist"))?
+            .epoch();
+        let configs = access_paths
+            .iter()
+            .map(|path| {
+                account_states
+                    .get(&path.address)
+                    .and_then(Option::as_ref)
+                    .and_then(|account_state| account_state.get(&path.path).cloned())
+            })
+            .collect();
+        Ok((epoch, configs))
+    }
+
     fn fetch_synced_version(&self) -> Result<u64> {
         let (synced_version, _) = self
             .get_latest_transaction_info_option()
@@ -464,6 +624,48 @@ pub trait DbWriter: Send + Sync {
         first_version: Version,
         ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
     ) -> Result<()>;
+
+    /// Gets a receiver which restores the account state tree at `version` from the chunks of a
+    /// state snapshot, verifying that the tree has `expected_root_hash` once it is complete.
+    /// See [`DbWriter::finalize_state_snapshot`] for how the restore is completed.
+    fn get_state_snapshot_receiver(
+        &self,
+        _version: Version,
+        _expected_root_hash: HashValue,
+    ) -> Result<Box<dyn StateSnapshotReceiver>> {
+        unimplemented!()
+    }
+
+    /// Finalizes a state snapshot restored at `version` by saving the transaction at `version`,
+    /// its info and events, the frozen subtrees of the transaction accumulator before `version`
+    /// (ordered from left to right) and the given ledger infos, the last of which becomes the
+    /// latest ledger info. The state tree at `version` must have been restored already.
+    fn finalize_state_snapshot(
+        &self,
+        _version: Version,
+        _frozen_subtrees: &[HashValue],
+        _transaction: &Transaction,
+        _transaction_info: &TransactionInfo,
+        _events: &[ContractEvent],
+        _ledger_infos: &[LedgerInfoWithSignatures],
+    ) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+/// Restores the account state tree at a version from the chunks of a state snapshot, which are
+/// added in increasing order of the hashed account keys.
+pub trait StateSnapshotReceiver: Send {
+    /// Adds a chunk of accounts, which is verified against `proof` before it is written.
+    fn add_chunk(
+        &mut self,
+        chunk: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+    ) -> Result<()>;
+
+    /// Writes the remaining nodes once all the chunks are added, verifying the root hash of the
+    /// restored tree.
+    fn finish(self: Box<Self>) -> Result<()>;
 }
 
 pub trait MoveDbReader:
@@ -552,3 +754,6 @@ impl SaveTransactionsRequest {
         }
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,268 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{DbReader, Order, StartupInfo, TreeState};
+use anyhow::Result;
+use diem_crypto::HashValue;
+use diem_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_state::AccountState,
+    account_state_blob::{AccountStateBlob, AccountStateWithProof},
+    contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
+    epoch_change::EpochChangeProof,
+    event::{EventHandle, EventKey},
+    ledger_info::LedgerInfoWithSignatures,
+    move_resource::MoveStorage,
+    on_chain_config::{
+        config_address, ConfigurationResource, DiemVersion, OnChainConfig, ValidatorSet,
+    },
+    proof::SparseMerkleProof,
+    state_proof::StateProof,
+    transaction::{
+        AccountTransactionsWithProof, TransactionListWithProof, TransactionWithProof, Version,
+    },
+};
+use move_core_types::move_resource::MoveResource;
+use parking_lot::Mutex;
+use std::{collections::HashMap, convert::TryFrom};
+
+/// A reader of the account states at a single version, which records the accounts read.
+struct VersionedDbReader {
+    version: Version,
+    account_states: HashMap<AccountAddress, AccountStateBlob>,
+    reads: Mutex<Vec<(AccountAddress, Version)>>,
+}
+
+impl VersionedDbReader {
+    fn new(version: Version, account_states: Vec<(AccountAddress, AccountState)>) -> Self {
+        Self {
+            version,
+            account_states: account_states
+                .iter()
+                .map(|(address, state)| (*address, AccountStateBlob::try_from(state).unwrap()))
+                .collect(),
+            reads: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl DbReader for VersionedDbReader {
+    fn get_epoch_ending_ledger_infos(
+        &self,
+        _start_epoch: u64,
+        _end_epoch: u64,
+    ) -> Result<EpochChangeProof> {
+        unimplemented!()
+    }
+
+    fn get_transactions(
+        &self,
+        _start_version: Version,
+        _batch_size: u64,
+        _ledger_version: Version,
+        _fetch_events: bool,
+    ) -> Result<TransactionListWithProof> {
+        unimplemented!()
+    }
+
+    fn get_events(
+        &self,
+        _event_key: &EventKey,
+        _start: u64,
+        _order: Order,
+        _limit: u64,
+    ) -> Result<Vec<(u64, ContractEvent)>> {
+        unimplemented!()
+    }
+
+    fn get_events_with_proofs(
+        &self,
+        _event_key: &EventKey,
+        _start: u64,
+        _order: Order,
+        _limit: u64,
+        _known_version: Option<u64>,
+    ) -> Result<Vec<EventWithProof>> {
+        unimplemented!()
+    }
+
+    fn get_event_by_version_with_proof(
+        &self,
+        _event_key: &EventKey,
+        _version: u64,
+        _proof_version: u64,
+    ) -> Result<EventByVersionWithProof> {
+        unimplemented!()
+    }
+
+    fn get_block_timestamp(&self, _version: u64) -> Result<u64> {
+        unimplemented!()
+    }
+
+    fn get_latest_account_state(
+        &self,
+        _address: AccountAddress,
+    ) -> Result<Option<AccountStateBlob>> {
+        unimplemented!()
+    }
+
+    fn get_latest_ledger_info(&self) -> Result<LedgerInfoWithSignatures> {
+        unimplemented!()
+    }
+
+    fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
+        unimplemented!()
+    }
+
+    fn get_account_transaction(
+        &self,
+        _address: AccountAddress,
+        _seq_num: u64,
+        _include_events: bool,
+        _ledger_version: Version,
+    ) -> Result<Option<TransactionWithProof>> {
+        unimplemented!()
+    }
+
+    fn get_account_transactions(
+        &self,
+        _address: AccountAddress,
+        _start_seq_num: u64,
+        _limit: u64,
+        _include_events: bool,
+        _ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        unimplemented!()
+    }
+
+    fn get_state_proof_with_ledger_info(
+        &self,
+        _known_version: u64,
+        _ledger_info: LedgerInfoWithSignatures,
+    ) -> Result<StateProof> {
+        unimplemented!()
+    }
+
+    fn get_state_proof(&self, _known_version: u64) -> Result<StateProof> {
+        unimplemented!()
+    }
+
+    fn get_account_state_with_proof(
+        &self,
+        _address: AccountAddress,
+        _version: Version,
+        _ledger_version: Version,
+    ) -> Result<AccountStateWithProof> {
+        unimplemented!()
+    }
+
+    fn get_account_state_with_proof_by_version(
+        &self,
+        address: AccountAddress,
+        version: Version,
+    ) -> Result<(
+        Option<AccountStateBlob>,
+        SparseMerkleProof<AccountStateBlob>,
+    )> {
+        self.reads.lock().push((address, version));
+        let blob = if version == self.version {
+            self.account_states.get(&address).cloned()
+        } else {
+            None
+        };
+        Ok((blob, SparseMerkleProof::new(None, vec![])))
+    }
+
+    fn get_latest_state_root(&self) -> Result<(Version, HashValue)> {
+        unimplemented!()
+    }
+
+    fn get_latest_tree_state(&self) -> Result<TreeState> {
+        unimplemented!()
+    }
+
+    fn get_epoch_ending_ledger_info(
+        &self,
+        _known_version: u64,
+    ) -> Result<LedgerInfoWithSignatures> {
+        unimplemented!()
+    }
+}
+
+/// Returns the state of the config address at the given epoch, holding the given configs.
+fn config_account_state(epoch: u64, configs: Vec<(AccessPath, Vec<u8>)>) -> AccountState {
+    // The fields of `ConfigurationResource`: the epoch, the last reconfiguration time and the
+    // handle of the reconfiguration events.
+    let configuration = (
+        epoch,
+        0u64,
+        EventHandle::new(EventKey::new([0; EventKey::LENGTH]), 0),
+    );
+    let mut account_state = AccountState::default();
+    account_state.insert(
+        ConfigurationResource::resource_path(),
+        bcs::to_bytes(&configuration).unwrap(),
+    );
+    for (access_path, config) in configs {
+        account_state.insert(access_path.path, config);
+    }
+    account_state
+}
+
+#[test]
+fn test_fetch_configs_by_version() {
+    let version_path = DiemVersion::CONFIG_ID.access_path();
+    let validator_set_path = ValidatorSet::CONFIG_ID.access_path();
+    let other_address = AccountAddress::from_hex_literal("0xB").unwrap();
+    let other_path = AccessPath::new(other_address, version_path.path.clone());
+    let db = VersionedDbReader::new(
+        10,
+        vec![(
+            config_address(),
+            config_account_state(7, vec![(version_path.clone(), vec![1, 2, 3])]),
+        )],
+    );
+
+    let (epoch, configs) = (&db as &dyn DbReader)
+        .fetch_configs_by_version(
+            vec![
+                validator_set_path,
+                version_path.clone(),
+                other_path,
+                version_path,
+            ],
+            10,
+        )
+        .unwrap();
+    assert_eq!(epoch, 7);
+    // Configs come in the order of the access paths, with None for the missing ones.
+    assert_eq!(
+        configs,
+        vec![None, Some(vec![1, 2, 3]), None, Some(vec![1, 2, 3])]
+    );
+
+    // Each account is read once, at the given version.
+    let mut reads = db.reads.lock().clone();
+    reads.sort();
+    let mut expected = vec![(config_address(), 10), (other_address, 10)];
+    expected.sort();
+    assert_eq!(reads, expected);
+}
+
+#[test]
+fn test_fetch_configs_by_version_without_configuration() {
+    let version_path = DiemVersion::CONFIG_ID.access_path();
+    let db = VersionedDbReader::new(
+        10,
+        vec![(
+            config_address(),
+            config_account_state(7, vec![(version_path.clone(), vec![1, 2, 3])]),
+        )],
+    );
+
+    // The config address has no state at another version, so there is no epoch to return.
+    assert!((&db as &dyn DbReader)
+        .fetch_configs_by_version(vec![version_path], 9)
+        .is_err());
+}
@@ -374,6 +374,36 @@ fn test_set_operator_and_add_new_validator() {
     }
 }
 
+#[test]
+fn test_export_import_safety_rules_state() {
+    let (swarm, op_tool, backend, storage) = launch_swarm_with_op_tool_and_backend(1);
+
+    // Export the safety rules state to file
+    let node_config_path = swarm.validators().next().unwrap().config_path();
+    let state_file_path = node_config_path.with_file_name("safety_rules_state.yaml");
+    let state = op_tool
+        .export_safety_rules_state(&state_file_path, &backend)
+        .unwrap();
+
+    // Verify the state is the one of the validator in storage
+    let author: AccountAddress = storage.get(OWNER_ACCOUNT).unwrap().value;
+    assert_eq!(author, state.author);
+    let consensus_key = storage.get_public_key(CONSENSUS_KEY).unwrap().public_key;
+    assert_eq!(consensus_key, state.consensus_public_key);
+
+    // Verify the state of another validator is rejected
+    let mut other_state = state;
+    other_state.author = AccountAddress::random();
+    fs::write(
+        &state_file_path,
+        serde_yaml::to_string(&other_state).unwrap(),
+    )
+    .unwrap();
+    op_tool
+        .import_safety_rules_state(&state_file_path, &backend)
+        .unwrap_err();
+}
+
 #[test]
 fn test_extract_private_key() {
     let (swarm, op_tool, backend, storage) = launch_swarm_with_op_tool_and_backend(1);
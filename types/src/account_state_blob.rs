@@ -6,7 +6,7 @@ use crate::{
     account_config::{AccountResource, BalanceResource},
     account_state::AccountState,
     ledger_info::LedgerInfo,
-    proof::AccountStateProof,
+    proof::{AccountStateProof, SparseMerkleRangeProof},
     transaction::Version,
 };
 use anyhow::{anyhow, ensure, Error, Result};
@@ -184,9 +184,192 @@ impl AccountStateWithProof {
     }
 }
 
+/// A chunk of the account states in the state tree at a version, ordered by the hashes of the
+/// account addresses, together with the proof needed to add the chunk to a partially restored
+/// state tree.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AccountStateChunkWithProof {
+    /// The version of the state tree.
+    pub version: Version,
+    /// The account states in the chunk, keyed by the hashes of the account addresses.
+    pub account_blobs: Vec<(HashValue, AccountStateBlob)>,
+    /// The proof of the rightmost account state in the chunk against the root hash.
+    pub proof: SparseMerkleRangeProof,
+    /// The root hash of the state tree at `version`.
+    pub root_hash: HashValue,
+}
+
+impl AccountStateChunkWithProof {
+    /// Constructor.
+    pub fn new(
+        version: Version,
+        account_blobs: Vec<(HashValue, AccountStateBlob)>,
+        proof: SparseMerkleRangeProof,
+        root_hash: HashValue,
+    ) -> Self {
+        Self {
+            version,
+            account_blobs,
+            proof,
+            root_hash,
+        }
+    }
+
+    /// Returns the key following the last account in the chunk, from which the next chunk
+    /// starts, or None if there are no accounts after this chunk.
+    pub fn next_start_key(&self) -> Option<HashValue> {
+        let (last_key, _) = self.account_blobs.last()?;
+        let mut bytes = last_key.to_vec();
+        for byte in bytes.iter_mut().rev() {
+            if *byte == u8::MAX {
+                *byte = 0;
+            } else {
+                *byte += 1;
+                return Some(HashValue::from_slice(&bytes).expect("hash length is preserved"));
+            }
+        }
+        None
+    }
+}
+
+/// A page of the resources of an account at a version, ordered by their paths.
+///
+/// The state tree commits to account state blobs as a whole, so the resources of a page can't be
+/// authenticated on their own. Instead, every page carries the proof of the account state, and the
+/// pages of an account are verified together by an `AccountResourcesVerifier` once all of them are
+/// received.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AccountResourcesPageWithProof {
+    /// The transaction version at which the account state is seen.
+    pub version: Version,
+    /// The resources in the page, keyed by their paths.
+    pub resources: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Whether there are more resources after this page.
+    pub more: bool,
+    /// The proof of the whole account state.
+    pub proof: AccountStateProof,
+}
+
+impl AccountResourcesPageWithProof {
+    /// Constructor.
+    pub fn new(
+        version: Version,
+        resources: Vec<(Vec<u8>, Vec<u8>)>,
+        more: bool,
+        proof: AccountStateProof,
+    ) -> Self {
+        Self {
+            version,
+            resources,
+            more,
+            proof,
+        }
+    }
+
+    /// Returns the smallest path following the last resource in the page, from which the next
+    /// page starts, or None if this is the last page.
+    pub fn next_start_path(&self) -> Option<Vec<u8>> {
+        if !self.more {
+            return None;
+        }
+        let (last_path, _) = self.resources.last()?;
+        let mut path = last_path.clone();
+        path.push(0);
+        Some(path)
+    }
+}
+
+/// Collects the pages of the resources of an account, and verifies them against the account state
+/// proof once the last page is received.
+#[derive(Debug)]
+pub struct AccountResourcesVerifier {
+    version: Version,
+    address: AccountAddress,
+    account_state: AccountState,
+    proof: Option<AccountStateProof>,
+    complete: bool,
+}
+
+impl AccountResourcesVerifier {
+    pub fn new(version: Version, address: AccountAddress) -> Self {
+        Self {
+            version,
+            address,
+            account_state: AccountState::default(),
+            proof: None,
+            complete: false,
+        }
+    }
+
+    /// Adds the next page of resources. The resources of a page must follow the ones of the pages
+    /// added so far, and all pages must carry the same proof.
+    pub fn add_page(&mut self, page: AccountResourcesPageWithProof) -> Result<()> {
+        ensure!(!self.complete, "The last page was already added.");
+        ensure!(
+            page.version == self.version,
+            "Page version ({}) is not expected ({}).",
+            page.version,
+            self.version,
+        );
+        match &self.proof {
+            Some(proof) => ensure!(
+                *proof == page.proof,
+                "Page proof differs from the proof of the previous pages."
+            ),
+            None => self.proof = Some(page.proof),
+        }
+        let mut last_path = self
+            .account_state
+            .iter()
+            .last()
+            .map(|(path, _)| path.clone());
+        for (path, resource) in page.resources {
+            ensure!(
+                last_path
+                    .as_ref()
+                    .map_or(true, |last_path| *last_path < path),
+                "Resource paths are not in ascending order.",
+            );
+            last_path = Some(path.clone());
+            self.account_state.insert(path, resource);
+        }
+        self.complete = !page.more;
+        Ok(())
+    }
+
+    /// Returns whether the last page was added.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Verifies the resources of all the pages with the proof, returning the account state, or
+    /// None if the account doesn't exist. See `AccountStateWithProof::verify` for the guarantees.
+    pub fn finish(self, ledger_info: &LedgerInfo) -> Result<Option<AccountState>> {
+        ensure!(self.complete, "Not all pages were added.");
+        let proof = self.proof.expect("A page must have been added.");
+        if self.account_state.iter().next().is_none() {
+            proof.verify(ledger_info, self.version, self.address.hash(), None)?;
+            return Ok(None);
+        }
+
+        let blob = AccountStateBlob::try_from(&self.account_state)?;
+        proof.verify(ledger_info, self.version, self.address.hash(), Some(&blob))?;
+        Ok(Some(self.account_state))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        block_info::BlockInfo,
+        proof::{
+            SparseMerkleLeafNode, SparseMerkleProof, TransactionAccumulatorProof,
+            TransactionInfoWithProof,
+        },
+        transaction::TransactionInfo,
+        vm_status::KeptVMStatus,
+    };
     use bcs::test_helpers::assert_canonical_encode_decode;
     use proptest::collection::vec;
 
@@ -213,6 +396,116 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_chunk_next_start_key() {
+        let chunk_ending_at = |last_key: HashValue| {
+            AccountStateChunkWithProof::new(
+                0,
+                vec![(last_key, AccountStateBlob::from(vec![1u8]))],
+                SparseMerkleRangeProof::new(vec![]),
+                HashValue::zero(),
+            )
+        };
+        let mut bytes = [0u8; HashValue::LENGTH];
+        bytes[HashValue::LENGTH - 1] = u8::MAX;
+        let mut expected = [0u8; HashValue::LENGTH];
+        expected[HashValue::LENGTH - 2] = 1;
+        assert_eq!(
+            chunk_ending_at(HashValue::new(bytes)).next_start_key(),
+            Some(HashValue::new(expected))
+        );
+        assert_eq!(
+            chunk_ending_at(HashValue::new([u8::MAX; HashValue::LENGTH])).next_start_key(),
+            None
+        );
+    }
+
+    // Creates the pages of the resources of an account which is the only one in the state tree,
+    // and the ledger info the pages are proven by.
+    fn create_resource_pages(
+        address: AccountAddress,
+        account_state: &AccountState,
+        page_size: usize,
+    ) -> (Vec<AccountResourcesPageWithProof>, LedgerInfo) {
+        let blob = AccountStateBlob::try_from(account_state).unwrap();
+        let leaf = SparseMerkleLeafNode::new(address.hash(), blob.hash());
+        let txn_info = TransactionInfo::new(
+            HashValue::zero(),
+            leaf.hash(),
+            HashValue::zero(),
+            0,
+            KeptVMStatus::Executed,
+        );
+        let ledger_info = LedgerInfo::new(
+            BlockInfo::new(0, 0, HashValue::zero(), txn_info.hash(), 0, 0, None),
+            HashValue::zero(),
+        );
+        let proof = AccountStateProof::new(
+            TransactionInfoWithProof::new(TransactionAccumulatorProof::new(vec![]), txn_info),
+            SparseMerkleProof::new(Some(leaf), vec![]),
+        );
+
+        let resources: Vec<_> = account_state
+            .iter()
+            .map(|(path, resource)| (path.clone(), resource.clone()))
+            .collect();
+        let num_pages = (resources.len() + page_size - 1) / page_size;
+        let pages = resources
+            .chunks(page_size)
+            .enumerate()
+            .map(|(index, chunk)| {
+                AccountResourcesPageWithProof::new(
+                    0,
+                    chunk.to_vec(),
+                    index + 1 < num_pages,
+                    proof.clone(),
+                )
+            })
+            .collect();
+        (pages, ledger_info)
+    }
+
+    #[test]
+    fn test_verify_resource_pages() {
+        let address = AccountAddress::random();
+        let mut account_state = AccountState::default();
+        for byte in 0..5u8 {
+            account_state.insert(vec![byte], vec![byte; 10]);
+        }
+        let (pages, ledger_info) = create_resource_pages(address, &account_state, 2);
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0].next_start_path(), Some(vec![1, 0]));
+        assert_eq!(pages[2].next_start_path(), None);
+
+        // Verify the pages are verified once all of them are added
+        let mut verifier = AccountResourcesVerifier::new(0, address);
+        for page in pages.clone() {
+            assert!(!verifier.is_complete());
+            verifier.add_page(page).unwrap();
+        }
+        assert_eq!(verifier.finish(&ledger_info).unwrap(), Some(account_state));
+
+        // Verify a tampered resource is rejected
+        let mut verifier = AccountResourcesVerifier::new(0, address);
+        let mut tampered_pages = pages.clone();
+        tampered_pages[1].resources[0].1 = vec![];
+        for page in tampered_pages {
+            verifier.add_page(page).unwrap();
+        }
+        assert!(verifier.finish(&ledger_info).is_err());
+
+        // Verify a missing page is rejected
+        let mut verifier = AccountResourcesVerifier::new(0, address);
+        verifier.add_page(pages[0].clone()).unwrap();
+        assert!(verifier.add_page(pages[2].clone()).is_ok());
+        assert!(verifier.finish(&ledger_info).is_err());
+
+        // Verify pages out of order are rejected
+        let mut verifier = AccountResourcesVerifier::new(0, address);
+        verifier.add_page(pages[1].clone()).unwrap();
+        assert!(verifier.add_page(pages[0].clone()).is_err());
+    }
+
     #[test]
     fn test_debug_does_not_panic() {
         format!("{:#?}", AccountStateBlob::from(vec![1u8, 2u8, 3u8]));
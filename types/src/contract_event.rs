@@ -21,7 +21,7 @@ use move_core_types::{language_storage::TypeTag, move_resource::MoveStructType};
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, ops::Deref};
+use std::{collections::HashMap, convert::TryFrom, ops::Deref};
 
 /// Support versioning of the data structure.
 #[derive(Hash, Clone, Eq, PartialEq, Serialize, Deserialize, CryptoHasher, BCSCryptoHash)]
@@ -349,6 +349,75 @@ impl EventWithProof {
         sequence_number: u64,
         transaction_version: Version,
         event_index: u64,
+    ) -> Result<()> {
+        self.verify_fields(event_key, sequence_number, transaction_version, event_index)?;
+
+        self.proof.verify(
+            ledger_info,
+            self.event.hash(),
+            transaction_version,
+            event_index,
+        )?;
+
+        Ok(())
+    }
+
+    /// Verifies the events returned for a batch of event stream requests, such as by
+    /// `DbReader::get_events_with_proofs_batched`.
+    ///
+    /// For each `(event_key, start_seq_num, limit)` in `requests`, the corresponding entry of
+    /// `events_with_proofs` is ensured to hold at most `limit` events of `event_key`, with
+    /// consecutive sequence numbers starting from `start_seq_num`, that exist in the ledger
+    /// represented by `ledger_info`. The proof of a transaction info shared by several events is
+    /// only verified once.
+    pub fn verify_batch(
+        ledger_info: &LedgerInfo,
+        requests: &[(EventKey, u64, u64)],
+        events_with_proofs: &[Vec<EventWithProof>],
+    ) -> Result<()> {
+        ensure!(
+            requests.len() == events_with_proofs.len(),
+            "Number of event lists ({}) not expected ({}).",
+            events_with_proofs.len(),
+            requests.len(),
+        );
+
+        let mut verified_txn_info_hashes = HashMap::new();
+        for ((event_key, start_seq_num, limit), events) in requests.iter().zip(events_with_proofs) {
+            ensure!(
+                events.len() as u64 <= *limit,
+                "Number of events ({}) exceeds limit ({}).",
+                events.len(),
+                limit,
+            );
+            for (sequence_number, event_with_proof) in (*start_seq_num..).zip(events) {
+                event_with_proof.verify_fields(
+                    event_key,
+                    sequence_number,
+                    event_with_proof.transaction_version,
+                    event_with_proof.event_index,
+                )?;
+                event_with_proof
+                    .proof
+                    .verify_with_verified_transaction_infos(
+                        ledger_info,
+                        event_with_proof.event.hash(),
+                        event_with_proof.transaction_version,
+                        event_with_proof.event_index,
+                        &mut verified_txn_info_hashes,
+                    )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_fields(
+        &self,
+        event_key: &EventKey,
+        sequence_number: u64,
+        transaction_version: Version,
+        event_index: u64,
     ) -> Result<()> {
         ensure!(
             self.event.key() == event_key,
@@ -375,13 +444,6 @@ impl EventWithProof {
             event_index,
         );
 
-        self.proof.verify(
-            ledger_info,
-            self.event.hash(),
-            transaction_version,
-            event_index,
-        )?;
-
         Ok(())
     }
 }
@@ -19,6 +19,17 @@ pub trait MoveStorage {
         version: Version,
     ) -> Result<Vec<Vec<u8>>>;
 
+    /// Returns the epoch of the configuration resource and the on-chain configs at
+    /// `access_paths` (None for the configs which don't exist), all read from the state at
+    /// `version`. As a single version is read, the configs and the epoch are consistent with
+    /// each other even if transactions are committed concurrently.
+    /// Order of configs returned matches the order of `access_paths`
+    fn fetch_configs_by_version(
+        &self,
+        access_paths: Vec<AccessPath>,
+        version: Version,
+    ) -> Result<(u64, Vec<Option<Vec<u8>>>)>;
+
     /// Get the version on the latest transaction info.
     fn fetch_synced_version(&self) -> Result<Version>;
 }
@@ -9,12 +9,51 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum OnChainConsensusConfig {
     V1(ConsensusConfigV1),
+    V2(ConsensusConfigV2),
+    V3(ConsensusConfigV3),
+    V4(ConsensusConfigV4),
 }
 
 impl OnChainConsensusConfig {
     pub fn two_chain(&self) -> bool {
         match &self {
             OnChainConsensusConfig::V1(config) => config.two_chain,
+            OnChainConsensusConfig::V2(config) => config.two_chain,
+            OnChainConsensusConfig::V3(config) => config.two_chain,
+            OnChainConsensusConfig::V4(config) => config.two_chain,
+        }
+    }
+
+    /// Whether the transactions of a block are executed in parallel by the VM. The result of the
+    /// execution is the same either way.
+    pub fn parallel_execution(&self) -> bool {
+        match &self {
+            OnChainConsensusConfig::V1(_) => false,
+            OnChainConsensusConfig::V2(config) => config.parallel_execution,
+            OnChainConsensusConfig::V3(config) => config.parallel_execution,
+            OnChainConsensusConfig::V4(config) => config.parallel_execution,
+        }
+    }
+
+    /// How many transactions of each lane a proposed block may include. Lanes are unbounded, up to
+    /// the block size, unless set.
+    pub fn block_lane_caps(&self) -> BlockLaneCaps {
+        match &self {
+            OnChainConsensusConfig::V1(_) | OnChainConsensusConfig::V2(_) => {
+                BlockLaneCaps::default()
+            }
+            OnChainConsensusConfig::V3(config) => config.block_lane_caps,
+            OnChainConsensusConfig::V4(config) => config.block_lane_caps,
+        }
+    }
+
+    /// How long rounds last before timing out. Each node uses its local config unless set.
+    pub fn round_timeout_curve(&self) -> Option<RoundTimeoutCurve> {
+        match &self {
+            OnChainConsensusConfig::V1(_)
+            | OnChainConsensusConfig::V2(_)
+            | OnChainConsensusConfig::V3(_) => None,
+            OnChainConsensusConfig::V4(config) => Some(config.round_timeout_curve),
         }
     }
 }
@@ -37,6 +76,68 @@ impl Default for ConsensusConfigV1 {
     }
 }
 
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusConfigV2 {
+    pub two_chain: bool,
+    pub parallel_execution: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusConfigV3 {
+    pub two_chain: bool,
+    pub parallel_execution: bool,
+    pub block_lane_caps: BlockLaneCaps,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ConsensusConfigV4 {
+    pub two_chain: bool,
+    pub parallel_execution: bool,
+    pub block_lane_caps: BlockLaneCaps,
+    pub round_timeout_curve: RoundTimeoutCurve,
+}
+
+/// The duration of a round grows exponentially with the number of rounds since the last commit:
+/// `base_timeout_ms * (exponent_base_percent / 100) ^ min(rounds_since_commit, max_exponent)`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RoundTimeoutCurve {
+    pub base_timeout_ms: u64,
+    /// By how much the duration grows every round, in percent, e.g. 150 for 1.5 times longer.
+    /// Curves with less than 100, which would shrink the duration, are ignored.
+    pub exponent_base_percent: u64,
+    /// The number of rounds after which the duration stops growing.
+    pub max_exponent: u64,
+}
+
+impl Default for RoundTimeoutCurve {
+    fn default() -> Self {
+        Self {
+            base_timeout_ms: 1000,
+            exponent_base_percent: 120,
+            max_exponent: 6,
+        }
+    }
+}
+
+/// Caps on the number of transactions pulled from mempool into a proposed block, per lane.
+/// Transactions of the governance lane, sent by accounts with a governance role such as Diem root
+/// or validators, are pulled before the ones of the user lane, which are ordered by fee, so that
+/// reconfigurations are never crowded out by user transactions.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockLaneCaps {
+    pub max_governance_txns: u64,
+    pub max_user_txns: u64,
+}
+
+impl Default for BlockLaneCaps {
+    fn default() -> Self {
+        Self {
+            max_governance_txns: u64::MAX,
+            max_user_txns: u64::MAX,
+        }
+    }
+}
+
 impl OnChainConfig for OnChainConsensusConfig {
     const IDENTIFIER: &'static str = "DiemConsensusConfig";
 
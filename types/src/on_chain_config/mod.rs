@@ -5,7 +5,9 @@ use crate::{
     access_path::AccessPath,
     account_address::AccountAddress,
     account_config::CORE_CODE_ADDRESS,
+    contract_event::ContractEvent,
     event::{EventHandle, EventKey},
+    transaction::Version,
 };
 use anyhow::{format_err, Result};
 use move_core_types::{
@@ -25,7 +27,10 @@ mod vm_config;
 mod vm_publishing_option;
 
 pub use self::{
-    consensus_config::{ConsensusConfigV1, OnChainConsensusConfig},
+    consensus_config::{
+        BlockLaneCaps, ConsensusConfigV1, ConsensusConfigV2, ConsensusConfigV3, ConsensusConfigV4,
+        OnChainConsensusConfig, RoundTimeoutCurve,
+    },
     diem_version::{
         DiemVersion, DIEM_MAX_KNOWN_VERSION, DIEM_VERSION_2, DIEM_VERSION_3, DIEM_VERSION_4,
     },
@@ -49,6 +54,18 @@ pub fn config_address() -> AccountAddress {
 }
 
 impl ConfigID {
+    /// Creates the ID of a config which is not known at compile time, e.g. one added to the
+    /// registry by the node config. Config IDs are `'static`, so the given strings are leaked:
+    /// this should only be called for a bounded number of configs, when the node starts.
+    pub fn new_custom(address: &str, identifier: &str) -> Result<Self> {
+        AccountAddress::from_hex_literal(address)?;
+        Identifier::new(identifier)?;
+        Ok(ConfigID(
+            Box::leak(address.to_owned().into_boxed_str()),
+            Box::leak(identifier.to_owned().into_boxed_str()),
+        ))
+    }
+
     pub fn access_path(self) -> AccessPath {
         access_path_for_config(
             AccountAddress::from_hex_literal(self.0).expect("failed to get address"),
@@ -80,18 +97,36 @@ pub const ON_CHAIN_CONFIG_REGISTRY: &[ConfigID] = &[
 #[derive(Clone, Debug, PartialEq)]
 pub struct OnChainConfigPayload {
     epoch: u64,
+    // The version at which the configs (and the epoch) were read
+    version: Version,
     configs: Arc<HashMap<ConfigID, Vec<u8>>>,
+    // The subscribed events which triggered this notification
+    events: Arc<Vec<ContractEvent>>,
 }
 
 impl OnChainConfigPayload {
-    pub fn new(epoch: u64, configs: Arc<HashMap<ConfigID, Vec<u8>>>) -> Self {
-        Self { epoch, configs }
+    pub fn new(epoch: u64, version: Version, configs: Arc<HashMap<ConfigID, Vec<u8>>>) -> Self {
+        Self {
+            epoch,
+            version,
+            configs,
+            events: Arc::new(vec![]),
+        }
+    }
+
+    pub fn with_events(mut self, events: Vec<ContractEvent>) -> Self {
+        self.events = Arc::new(events);
+        self
     }
 
     pub fn epoch(&self) -> u64 {
         self.epoch
     }
 
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
     pub fn get<T: OnChainConfig>(&self) -> Result<T> {
         let bytes = self
             .configs
@@ -103,6 +138,11 @@ impl OnChainConfigPayload {
     pub fn configs(&self) -> &HashMap<ConfigID, Vec<u8>> {
         &self.configs
     }
+
+    /// Returns the emitted events the subscriber subscribed to, in the order of emission.
+    pub fn events(&self) -> &[ContractEvent] {
+        &self.events
+    }
 }
 
 impl fmt::Display for OnChainConfigPayload {
@@ -113,8 +153,8 @@ impl fmt::Display for OnChainConfigPayload {
         }
         write!(
             f,
-            "OnChainConfigPayload [epoch: {}, configs: {}]",
-            self.epoch, config_ids
+            "OnChainConfigPayload [epoch: {}, version: {}, configs: {}]",
+            self.epoch, self.version, config_ids
         )
     }
 }
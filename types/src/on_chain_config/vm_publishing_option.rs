@@ -1,7 +1,11 @@
 // Copyright (c) The Diem Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::on_chain_config::OnChainConfig;
+use crate::{
+    account_config::diem_root_address,
+    on_chain_config::OnChainConfig,
+    transaction::{SignedTransaction, TransactionPayload},
+};
 use diem_crypto::HashValue;
 use serde::{Deserialize, Serialize};
 
@@ -46,6 +50,24 @@ impl VMPublishingOption {
     pub fn is_open_script(&self) -> bool {
         self.script_allow_list.is_empty()
     }
+
+    /// Whether `txn` complies with the publishing policy, as checked in the prologue of
+    /// transactions by the `DiemTransactionPublishingOption` module. Diem root is exempt.
+    pub fn allows(&self, txn: &SignedTransaction) -> bool {
+        if txn.sender() == diem_root_address() {
+            return true;
+        }
+        match txn.payload() {
+            TransactionPayload::Script(script) => {
+                self.is_open_script()
+                    || self
+                        .script_allow_list
+                        .contains(&HashValue::sha3_256_of(script.code()))
+            }
+            TransactionPayload::Module(_) => self.is_open_module(),
+            TransactionPayload::WriteSet(_) | TransactionPayload::ScriptFunction(_) => true,
+        }
+    }
 }
 
 impl OnChainConfig for VMPublishingOption {
@@ -25,7 +25,7 @@ use diem_crypto::{
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData};
 
 /// A proof that can be used authenticate an element in an accumulator given trusted root hash. For
 /// example, both `LedgerInfoToTransactionInfoProof` and `TransactionInfoToEventProof` can be
@@ -546,6 +546,85 @@ where
 
         Ok(())
     }
+
+    /// Returns a proof of the first `num_leaves` leaves of the range proven by this proof, where
+    /// `leaf_hashes` are all the leaves of the range. The right siblings of the new proof are
+    /// computed from the dropped leaves and the right siblings of this proof, so the new proof
+    /// is only valid if this proof is valid for `leaf_hashes`.
+    pub fn truncate(
+        &self,
+        first_leaf_index: u64,
+        leaf_hashes: &[HashValue],
+        num_leaves: usize,
+    ) -> Result<Self> {
+        ensure!(
+            num_leaves > 0 && num_leaves <= leaf_hashes.len(),
+            "Cannot truncate a range of {} leaves to {} leaves.",
+            leaf_hashes.len(),
+            num_leaves,
+        );
+
+        let mut left_sibling_iter = self.left_siblings.iter().peekable();
+        let mut right_sibling_iter = self.right_siblings.iter().peekable();
+        let mut right_siblings = vec![];
+
+        // The indices (on the current level) of the first node and the last node to keep
+        let mut first_index = first_leaf_index;
+        let mut last_index = first_leaf_index + num_leaves as u64 - 1;
+        let mut current_hashes = leaf_hashes.to_vec();
+        let mut parent_hashes = vec![];
+
+        // Reduce the list of hashes like `verify`, and record the sibling on the right of the
+        // last node to keep whenever that node is a left child.
+        while current_hashes.len() > 1
+            || left_sibling_iter.peek().is_some()
+            || right_sibling_iter.peek().is_some()
+        {
+            let mut children_iter = current_hashes.iter();
+            if first_index % 2 == 1 {
+                let left_hash = *left_sibling_iter.next().ok_or_else(|| {
+                    format_err!("First child is a right child, but missing sibling on the left.")
+                })?;
+                let right_hash = *children_iter.next().expect("The first leaf must exist.");
+                parent_hashes.push(MerkleTreeInternalNode::<H>::new(left_hash, right_hash).hash());
+            }
+
+            let mut children_iter = children_iter.as_slice().chunks_exact(2);
+            while let Some(chunk) = children_iter.next() {
+                parent_hashes.push(MerkleTreeInternalNode::<H>::new(chunk[0], chunk[1]).hash());
+            }
+
+            let remainder = children_iter.remainder();
+            let mut last_right_sibling = None;
+            if !remainder.is_empty() {
+                let right_hash = *right_sibling_iter.next().ok_or_else(|| {
+                    format_err!("Last child is a left child, but missing sibling on the right.")
+                })?;
+                last_right_sibling = Some(right_hash);
+                parent_hashes
+                    .push(MerkleTreeInternalNode::<H>::new(remainder[0], right_hash).hash());
+            }
+
+            if last_index % 2 == 0 {
+                // The sibling is either a dropped node, or the right sibling of the full range
+                let sibling_offset = (last_index + 1 - first_index) as usize;
+                let right_hash = match current_hashes.get(sibling_offset) {
+                    Some(hash) => *hash,
+                    None => last_right_sibling.ok_or_else(|| {
+                        format_err!("Missing sibling on the right of the last node to keep.")
+                    })?,
+                };
+                right_siblings.push(right_hash);
+            }
+
+            first_index /= 2;
+            last_index /= 2;
+            current_hashes.clear();
+            std::mem::swap(&mut current_hashes, &mut parent_hashes);
+        }
+
+        Ok(Self::new(self.left_siblings.clone(), right_siblings))
+    }
 }
 
 impl<H> std::fmt::Debug for AccumulatorRangeProof<H> {
@@ -767,6 +846,42 @@ impl EventProof {
 
         Ok(())
     }
+
+    /// Same as [`verify`](EventProof::verify), except that the transaction info is not verified
+    /// against `ledger_info` again if one of the same version was verified before, as recorded in
+    /// `verified_txn_info_hashes`. This saves re-verifying the same transaction accumulator proof
+    /// when verifying many events emitted by the same transactions.
+    pub fn verify_with_verified_transaction_infos(
+        &self,
+        ledger_info: &LedgerInfo,
+        event_hash: HashValue,
+        transaction_version: Version,
+        event_version_within_transaction: Version,
+        verified_txn_info_hashes: &mut HashMap<Version, HashValue>,
+    ) -> Result<()> {
+        let txn_info = self.transaction_info_with_proof.transaction_info();
+        self.transaction_info_to_event_proof.verify(
+            txn_info.event_root_hash(),
+            event_hash,
+            event_version_within_transaction,
+        )?;
+
+        let txn_info_hash = txn_info.hash();
+        match verified_txn_info_hashes.get(&transaction_version) {
+            Some(verified_hash) => ensure!(
+                *verified_hash == txn_info_hash,
+                "Transaction info at version {} differs from the one verified before.",
+                transaction_version,
+            ),
+            None => {
+                self.transaction_info_with_proof
+                    .verify(ledger_info, transaction_version)?;
+                verified_txn_info_hashes.insert(transaction_version, txn_info_hash);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// The complete proof used to authenticate a list of consecutive transactions.
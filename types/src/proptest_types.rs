@@ -7,6 +7,7 @@ use crate::{
     account_config::{
         AccountResource, BalanceResource, KeyRotationCapabilityResource, WithdrawCapabilityResource,
     },
+    account_state::AccountState,
     account_state_blob::AccountStateBlob,
     block_info::{BlockInfo, Round},
     block_metadata::BlockMetadata,
@@ -43,7 +44,7 @@ use proptest::{
 use proptest_derive::Arbitrary;
 use serde_json::Value;
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     convert::TryFrom,
     iter::Iterator,
 };
@@ -811,7 +812,7 @@ impl TransactionToCommitGen {
             .collect();
         // Account states must be materialized last, to reflect the latest account and event
         // sequence numbers.
-        let account_states = self
+        let account_states: HashMap<_, _> = self
             .account_state_gens
             .into_iter()
             .map(|(index, blob_gen)| {
@@ -821,11 +822,30 @@ impl TransactionToCommitGen {
                 )
             })
             .collect();
+        // The write set writes every resource of the updated account states.
+        let write_set = account_states
+            .iter()
+            .flat_map(|(address, blob)| {
+                AccountState::try_from(blob)
+                    .expect("Generated account state blob must be valid.")
+                    .iter()
+                    .map(|(path, value)| {
+                        (
+                            AccessPath::new(*address, path.clone()),
+                            WriteOp::Value(value.clone()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<WriteSetMut>()
+            .freeze()
+            .expect("freeze cannot fail");
 
         TransactionToCommit::new(
             Transaction::UserTransaction(transaction),
             account_states,
             None,
+            write_set,
             events,
             self.gas_used,
             self.status,
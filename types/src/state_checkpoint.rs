@@ -0,0 +1,94 @@
+// Copyright (c) The Diem Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    epoch_state::EpochState,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::TransactionInfoWithProof,
+    transaction::Version,
+    waypoint::Waypoint,
+};
+use anyhow::{ensure, Result};
+use diem_crypto::HashValue;
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest_derive::Arbitrary;
+use serde::{Deserialize, Serialize};
+
+/// A compact commitment to the state at a checkpoint version, which nodes record at regular
+/// version intervals. It lets a light client learn a state root signed by the validators of an
+/// epoch it trusts, and a waypoint to bootstrap from, without going through every epoch change
+/// from genesis.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(Arbitrary))]
+pub struct StateCheckpoint {
+    version: Version,
+    /// The first ledger info committed at or after `version`, signed by a quorum of the
+    /// validators of its epoch.
+    ledger_info_with_sigs: LedgerInfoWithSignatures,
+    /// The transaction info at `version`, carrying the state root hash, with proof towards the
+    /// ledger info.
+    txn_info_with_proof: TransactionInfoWithProof,
+}
+
+impl StateCheckpoint {
+    pub fn new(
+        version: Version,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+        txn_info_with_proof: TransactionInfoWithProof,
+    ) -> Self {
+        Self {
+            version,
+            ledger_info_with_sigs,
+            txn_info_with_proof,
+        }
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.ledger_info().epoch()
+    }
+
+    pub fn state_root_hash(&self) -> HashValue {
+        self.txn_info_with_proof
+            .transaction_info()
+            .state_root_hash()
+    }
+
+    pub fn ledger_info(&self) -> &LedgerInfo {
+        self.ledger_info_with_sigs.ledger_info()
+    }
+
+    pub fn ledger_info_with_sigs(&self) -> &LedgerInfoWithSignatures {
+        &self.ledger_info_with_sigs
+    }
+
+    /// A waypoint on the ledger info of this checkpoint, which a client can bootstrap its trusted
+    /// state from once the checkpoint is verified.
+    pub fn waypoint(&self) -> Waypoint {
+        Waypoint::new_any(self.ledger_info())
+    }
+
+    /// Verifies the ledger info is signed by a quorum of the validators of `epoch_state`, and
+    /// that the state root hash at the checkpoint version is proven by it.
+    pub fn verify(&self, epoch_state: &EpochState) -> Result<()> {
+        ensure!(
+            self.epoch() == epoch_state.epoch,
+            "Checkpoint epoch ({}) not expected ({}).",
+            self.epoch(),
+            epoch_state.epoch,
+        );
+        ensure!(
+            self.version <= self.ledger_info().version(),
+            "Checkpoint version ({}) is beyond its ledger info version ({}).",
+            self.version,
+            self.ledger_info().version(),
+        );
+        self.ledger_info_with_sigs
+            .verify_signatures(&epoch_state.verifier)?;
+        self.txn_info_with_proof
+            .verify(self.ledger_info(), self.version)
+    }
+}
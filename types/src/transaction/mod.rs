@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    access_path::AccessPath,
     account_address::AccountAddress,
     account_config::XUS_NAME,
     account_state_blob::AccountStateBlob,
@@ -24,7 +25,7 @@ use diem_crypto::{
     HashValue,
 };
 use diem_crypto_derive::{BCSCryptoHash, CryptoHasher};
-use move_core_types::transaction_argument::convert_txn_args;
+use move_core_types::{gas_schedule::GasProfile, transaction_argument::convert_txn_args};
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
@@ -603,6 +604,27 @@ impl SignedTransaction {
         Ok(SignatureCheckedTransaction(self))
     }
 
+    /// Checks the signature of the sender only, ignoring the signatures of the secondary signers of
+    /// a multi-agent transaction. This is only meant for simulating transactions before all their
+    /// signers have signed: the result must never be committed.
+    pub fn check_sender_signature(self) -> Result<SignatureCheckedTransaction> {
+        match &self.authenticator {
+            TransactionAuthenticator::MultiAgent {
+                sender,
+                secondary_signer_addresses,
+                ..
+            } => sender.verify(&RawTransactionWithData::new_multi_agent(
+                self.raw_txn.clone(),
+                secondary_signer_addresses.clone(),
+            ))?,
+            TransactionAuthenticator::Ed25519 { .. }
+            | TransactionAuthenticator::MultiEd25519 { .. } => {
+                self.authenticator.verify(&self.raw_txn)?
+            }
+        }
+        Ok(SignatureCheckedTransaction(self))
+    }
+
     pub fn contains_duplicate_signers(&self) -> bool {
         let mut all_signer_addresses = self.authenticator.secondary_signer_addreses();
         all_signer_addresses.push(self.sender());
@@ -870,6 +892,16 @@ pub struct TransactionOutput {
 
     /// The execution status.
     status: TransactionStatus,
+
+    /// Debugging information about the gas charged during execution, only recorded when the VM
+    /// profiles gas. It's not part of the transaction info.
+    #[serde(skip)]
+    gas_profile: Option<GasProfile>,
+
+    /// Debugging information about the keys read from storage during execution, only recorded
+    /// when the VM records reads. It's not part of the transaction info.
+    #[serde(skip)]
+    read_set: Option<Vec<AccessPath>>,
 }
 
 impl TransactionOutput {
@@ -884,9 +916,21 @@ impl TransactionOutput {
             events,
             gas_used,
             status,
+            gas_profile: None,
+            read_set: None,
         }
     }
 
+    pub fn with_gas_profile(mut self, gas_profile: GasProfile) -> Self {
+        self.gas_profile = Some(gas_profile);
+        self
+    }
+
+    pub fn with_read_set(mut self, read_set: Vec<AccessPath>) -> Self {
+        self.read_set = Some(read_set);
+        self
+    }
+
     pub fn into(self) -> (WriteSet, Vec<ContractEvent>) {
         (self.write_set, self.events)
     }
@@ -906,6 +950,14 @@ impl TransactionOutput {
     pub fn status(&self) -> &TransactionStatus {
         &self.status
     }
+
+    pub fn gas_profile(&self) -> Option<&GasProfile> {
+        self.gas_profile.as_ref()
+    }
+
+    pub fn read_set(&self) -> Option<&[AccessPath]> {
+        self.read_set.as_deref()
+    }
 }
 
 /// `TransactionInfo` is the object we store in the transaction accumulator. It consists of the
@@ -993,6 +1045,7 @@ pub struct TransactionToCommit {
     transaction: Transaction,
     account_states: HashMap<AccountAddress, AccountStateBlob>,
     jf_node_hashes: Option<HashMap<NibblePath, HashValue>>,
+    write_set: WriteSet,
     events: Vec<ContractEvent>,
     gas_used: u64,
     status: KeptVMStatus,
@@ -1003,6 +1056,7 @@ impl TransactionToCommit {
         transaction: Transaction,
         account_states: HashMap<AccountAddress, AccountStateBlob>,
         jf_node_hashes: Option<HashMap<NibblePath, HashValue>>,
+        write_set: WriteSet,
         events: Vec<ContractEvent>,
         gas_used: u64,
         status: KeptVMStatus,
@@ -1011,6 +1065,7 @@ impl TransactionToCommit {
             transaction,
             account_states,
             jf_node_hashes,
+            write_set,
             events,
             gas_used,
             status,
@@ -1029,6 +1084,10 @@ impl TransactionToCommit {
         self.jf_node_hashes.as_ref()
     }
 
+    pub fn write_set(&self) -> &WriteSet {
+        &self.write_set
+    }
+
     pub fn events(&self) -> &[ContractEvent] {
         &self.events
     }
@@ -1084,6 +1143,44 @@ impl TransactionListWithProof {
         self.transactions.len()
     }
 
+    /// Keeps only the first `num_transactions` transactions (with their events and proofs). The
+    /// remaining transactions are still proven by the ledger info proving the full list, as
+    /// long as the full list is valid.
+    pub fn truncate(self, num_transactions: usize) -> Result<Self> {
+        if num_transactions >= self.transactions.len() {
+            return Ok(self);
+        }
+        ensure!(
+            num_transactions > 0,
+            "Cannot truncate a transaction list to an empty list."
+        );
+        let first_transaction_version = self
+            .first_transaction_version
+            .ok_or_else(|| format_err!("A non-empty transaction list has no first version."))?;
+
+        let (range_proof, mut transaction_infos) = self.proof.unpack();
+        let txn_info_hashes: Vec<_> = transaction_infos.iter().map(CryptoHash::hash).collect();
+        let range_proof = range_proof.truncate(
+            first_transaction_version,
+            &txn_info_hashes,
+            num_transactions,
+        )?;
+        transaction_infos.truncate(num_transactions);
+
+        let mut transactions = self.transactions;
+        transactions.truncate(num_transactions);
+        let events = self.events.map(|mut events| {
+            events.truncate(num_transactions);
+            events
+        });
+        Ok(Self::new(
+            transactions,
+            events,
+            Some(first_transaction_version),
+            TransactionListProof::new(range_proof, transaction_infos),
+        ))
+    }
+
     /// Verifies the transaction list with the proofs, both carried on `self`.
     ///
     /// Two things are ensured if no error is raised:
@@ -2,10 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    block_info::BlockInfo,
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     event::EventKey,
+    ledger_info::LedgerInfo,
+    proof::{
+        EventAccumulatorInternalNode, EventAccumulatorProof, EventProof,
+        TransactionAccumulatorProof, TransactionInfoWithProof,
+    },
+    transaction::TransactionInfo,
+    vm_status::KeptVMStatus,
 };
 use bcs::test_helpers::assert_canonical_encode_decode;
+use diem_crypto::{hash::CryptoHash, HashValue};
 use move_core_types::language_storage::TypeTag;
 use proptest::prelude::*;
 
@@ -37,3 +46,78 @@ fn test_event_json_serialize() {
     let contract_event2: ContractEvent = serde_json::from_str(contract_json.as_str()).unwrap();
     assert_eq!(contract_event, contract_event2)
 }
+
+#[test]
+fn test_verify_batch() {
+    let key = EventKey::random();
+    let events: Vec<_> = (0..2)
+        .map(|seq_num| ContractEvent::new(key, seq_num, TypeTag::Bool, vec![]))
+        .collect();
+    let event_hashes: Vec<_> = events.iter().map(CryptoHash::hash).collect();
+    let event_root_hash =
+        EventAccumulatorInternalNode::new(event_hashes[0], event_hashes[1]).hash();
+    let txn_info = TransactionInfo::new(
+        HashValue::random(),
+        HashValue::random(),
+        event_root_hash,
+        0,
+        KeptVMStatus::Executed,
+    );
+    // With a single transaction, the ledger root is the hash of its transaction info.
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(0, 0, HashValue::zero(), txn_info.hash(), 0, 0, None),
+        HashValue::zero(),
+    );
+    let event_with_proof = |index: usize, txn_info: TransactionInfo| {
+        EventWithProof::new(
+            0,
+            index as u64,
+            events[index].clone(),
+            EventProof::new(
+                TransactionInfoWithProof::new(TransactionAccumulatorProof::new(vec![]), txn_info),
+                EventAccumulatorProof::new(vec![event_hashes[1 - index]]),
+            ),
+        )
+    };
+
+    let events_with_proofs = vec![
+        vec![event_with_proof(0, txn_info.clone())],
+        vec![event_with_proof(1, txn_info.clone())],
+    ];
+    let requests = [(key, 0, 1), (key, 1, 1)];
+    EventWithProof::verify_batch(&ledger_info, &requests, &events_with_proofs).unwrap();
+    let requests = [(key, 0, 2)];
+    EventWithProof::verify_batch(
+        &ledger_info,
+        &requests,
+        &[vec![
+            event_with_proof(0, txn_info.clone()),
+            event_with_proof(1, txn_info.clone()),
+        ]],
+    )
+    .unwrap();
+
+    // Requests and responses not matching are rejected.
+    let requests = [(key, 1, 1), (key, 0, 1)];
+    assert!(EventWithProof::verify_batch(&ledger_info, &requests, &events_with_proofs).is_err());
+    let requests = [(key, 0, 1)];
+    assert!(EventWithProof::verify_batch(&ledger_info, &requests, &events_with_proofs).is_err());
+    let too_many_events = [events_with_proofs.concat()];
+    assert!(EventWithProof::verify_batch(&ledger_info, &requests, &too_many_events).is_err());
+
+    // A transaction info differing from the one verified before for the same version is rejected,
+    // even though its proof isn't verified again.
+    let bad_txn_info = TransactionInfo::new(
+        txn_info.transaction_hash(),
+        HashValue::random(),
+        event_root_hash,
+        0,
+        KeptVMStatus::Executed,
+    );
+    let requests = [(key, 0, 1), (key, 1, 1)];
+    let events_with_proofs = vec![
+        events_with_proofs[0].clone(),
+        vec![event_with_proof(1, bad_txn_info)],
+    ];
+    assert!(EventWithProof::verify_batch(&ledger_info, &requests, &events_with_proofs).is_err());
+}
@@ -305,6 +305,39 @@ fn test_validate_module_publishing_non_association() {
     assert_eq!(ret.status().unwrap(), StatusCode::INVALID_MODULE_PUBLISHER);
 }
 
+#[test]
+fn test_simulate_module_publish() {
+    let vm_validator = TestValidator::new();
+
+    // The modules on chain are compatible with themselves.
+    let ret = vm_validator
+        .simulate_module_publish(
+            diem_framework_releases::current_module_blobs().to_vec(),
+            account_config::diem_root_address(),
+        )
+        .unwrap();
+    assert_eq!(ret, None);
+
+    let ret = vm_validator
+        .simulate_module_publish(vec![vec![]], account_config::diem_root_address())
+        .unwrap();
+    assert_eq!(
+        ret.unwrap().status_code(),
+        StatusCode::CODE_DESERIALIZATION_ERROR
+    );
+
+    let ret = vm_validator
+        .simulate_module_publish(
+            diem_framework_releases::current_module_blobs().to_vec(),
+            account_config::treasury_compliance_account_address(),
+        )
+        .unwrap();
+    assert_eq!(
+        ret.unwrap().status_code(),
+        StatusCode::INVALID_MODULE_PUBLISHER
+    );
+}
+
 #[test]
 fn test_validate_invalid_auth_key() {
     let vm_validator = TestValidator::new();
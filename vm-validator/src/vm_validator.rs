@@ -9,6 +9,7 @@ use diem_types::{
     account_state::AccountState,
     on_chain_config::{DiemVersion, OnChainConfigPayload, VMConfig, VMPublishingOption},
     transaction::{SignedTransaction, VMValidatorResult},
+    vm_status::VMStatus,
 };
 use diem_vm::DiemVM;
 use fail::fail_point;
@@ -51,6 +52,30 @@ impl VMValidator {
         let vm = DiemVM::new_for_validation(&state_view);
         VMValidator { db_reader, vm }
     }
+
+    /// Checks whether `sender` could publish the `modules` bundle on the latest state, without
+    /// submitting a transaction. Returns `None` if it could, or the error publishing it would
+    /// fail with.
+    pub fn simulate_module_publish(
+        &self,
+        modules: Vec<Vec<u8>>,
+        sender: AccountAddress,
+    ) -> Result<Option<VMStatus>> {
+        let (version, state_root) = self.db_reader.get_latest_state_root()?;
+        let smt = SparseMerkleTree::new(state_root);
+        let state_view = VerifiedStateView::new(
+            StateViewId::Miscellaneous,
+            Arc::clone(&self.db_reader),
+            Some(version),
+            state_root,
+            &smt,
+        );
+
+        Ok(self
+            .vm
+            .simulate_module_publish(modules, sender, &state_view)
+            .err())
+    }
 }
 
 impl TransactionValidation for VMValidator {